@@ -0,0 +1,611 @@
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+
+use num_traits::FromPrimitive;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt};
+
+use crate::reader::resample_to_pixel_size;
+use crate::{
+    Compliance, Extent, GeoTiff, GeoTiffReader, GeoTiffResult, Layout, Overview, Resampling,
+    Sample, WindowSnap,
+};
+
+/// The number of bytes fetched at least when parsing the tags requires further data, as the
+/// tags of an image are usually located closely together.
+const READ_AHEAD: u64 = 16 * 1024;
+
+/// A source of data read asynchronously in byte ranges by an [`AsyncGeoTiffReader`].
+///
+/// It is implemented for all readers implementing [`AsyncRead`] and [`AsyncSeek`], e.g. files,
+/// and may be implemented for sources like remote files, which are read by range requests, or
+/// `fetch` requests in browsers.
+pub trait RangeSource: MaybeSend {
+    /// The length of the data in bytes.
+    fn length(&mut self) -> impl Future<Output = io::Result<u64>> + MaybeSend;
+
+    /// Reads the bytes in the given range, which lies within the data.
+    fn read_range(
+        &mut self,
+        range: Range<u64>,
+    ) -> impl Future<Output = io::Result<Vec<u8>>> + MaybeSend;
+
+    /// Reads the bytes in each of the given ranges, which lie within the data, e.g. by
+    /// concurrent requests. The ranges are read one after another by default.
+    fn read_ranges(
+        &mut self,
+        ranges: &[Range<u64>],
+    ) -> impl Future<Output = io::Result<Vec<Vec<u8>>>> + MaybeSend {
+        async move {
+            let mut bytes = Vec::with_capacity(ranges.len());
+            for range in ranges {
+                bytes.push(self.read_range(range.clone()).await?);
+            }
+            Ok(bytes)
+        }
+    }
+}
+
+/// `Send`, except on wasm32, where the futures of browser APIs like `fetch` are not `Send` and
+/// there are no threads to send them to. The futures of a [`RangeSource`] are required to be
+/// `MaybeSend`, such that the futures of an [`AsyncGeoTiffReader`] can be spawned on
+/// multi-threaded runtimes natively.
+#[cfg(not(target_arch = "wasm32"))]
+pub trait MaybeSend: Send {}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<T: Send> MaybeSend for T {}
+
+/// Implemented by all types on wasm32, see the native definition.
+#[cfg(target_arch = "wasm32")]
+pub trait MaybeSend {}
+
+#[cfg(target_arch = "wasm32")]
+impl<T> MaybeSend for T {}
+
+impl<R: AsyncRead + AsyncSeek + Send + Unpin> RangeSource for R {
+    async fn length(&mut self) -> io::Result<u64> {
+        self.seek(SeekFrom::End(0)).await
+    }
+
+    async fn read_range(&mut self, range: Range<u64>) -> io::Result<Vec<u8>> {
+        let mut bytes = vec![0; (range.end - range.start) as usize];
+        self.seek(SeekFrom::Start(range.start)).await?;
+        self.read_exact(&mut bytes).await?;
+        Ok(bytes)
+    }
+}
+
+/// An asynchronous variant of [`GeoTiffReader`] reading from a [`RangeSource`], e.g. a file
+/// implementing [`AsyncRead`] and [`AsyncSeek`] used within a Tokio runtime.
+///
+/// The bytes needed for parsing the tags and decoding the requested strips or tiles are fetched
+/// asynchronously, so that the runtime is never blocked on I/O. Decoding itself takes place in
+/// memory once the data is available.
+pub struct AsyncGeoTiffReader<R> {
+    source: R,
+    /// The length of the data of the source in bytes
+    length: u64,
+    cache: Arc<Mutex<Cache>>,
+    reader: GeoTiffReader<CachedReader>,
+}
+
+impl<R: RangeSource> AsyncGeoTiffReader<R> {
+    pub async fn new(source: R) -> GeoTiffResult<Self> {
+        Self::with_compliance(source, Compliance::Strict).await
+    }
+
+    /// See [`GeoTiffReader::with_compliance`].
+    pub async fn with_compliance(mut source: R, compliance: Compliance) -> GeoTiffResult<Self> {
+        let length = source.length().await?;
+        let cache = Arc::new(Mutex::new(Cache::default()));
+        fetch(&mut source, &cache, 0..READ_AHEAD.min(length)).await?;
+        let reader = loop {
+            match GeoTiffReader::with_compliance(
+                CachedReader::new(cache.clone(), length),
+                compliance,
+            ) {
+                Ok(reader) => break reader,
+                Err(err) => {
+                    let missing = cache.lock().unwrap().missing.take();
+                    match missing {
+                        Some(range) => read_ahead(&mut source, &cache, range, length).await?,
+                        None => return Err(err),
+                    }
+                }
+            }
+        };
+        Ok(Self {
+            source,
+            length,
+            cache,
+            reader,
+        })
+    }
+
+    pub fn raster_width(&self) -> usize {
+        self.reader.raster_width()
+    }
+
+    pub fn raster_height(&self) -> usize {
+        self.reader.raster_height()
+    }
+
+    pub fn num_samples(&self) -> usize {
+        self.reader.num_samples()
+    }
+
+    /// See [`GeoTiffReader::set_apply_scale_offset`].
+    pub fn set_apply_scale_offset(&mut self, apply_scale_offset: bool) {
+        self.reader.set_apply_scale_offset(apply_scale_offset);
+    }
+
+    /// See [`GeoTiffReader::has_mask`].
+    pub fn has_mask(&self) -> bool {
+        self.reader.has_mask()
+    }
+
+    /// See [`GeoTiffReader::tile_count`].
+    pub fn tile_count(&self) -> Option<[usize; 2]> {
+        self.reader.tile_count()
+    }
+
+    /// See [`GeoTiffReader::tile_dimensions`].
+    pub fn tile_dimensions(&self) -> Option<[usize; 2]> {
+        self.reader.tile_dimensions()
+    }
+
+    /// See [`GeoTiffReader::strip_count`].
+    pub fn strip_count(&self) -> Option<usize> {
+        self.reader.strip_count()
+    }
+
+    /// See [`GeoTiffReader::rows_per_strip`].
+    pub fn rows_per_strip(&self) -> Option<usize> {
+        self.reader.rows_per_strip()
+    }
+
+    /// See [`GeoTiffReader::image_count`].
+    pub fn image_count(&self) -> usize {
+        self.reader.image_count()
+    }
+
+    /// See [`GeoTiffReader::image_metadata`].
+    pub fn image_metadata(&self, index: usize) -> Option<&GeoTiff> {
+        self.reader.image_metadata(index)
+    }
+
+    /// See [`GeoTiffReader::selected_image`].
+    pub fn selected_image(&self) -> usize {
+        self.reader.selected_image()
+    }
+
+    /// Selects a top-level image for all subsequent reads, see [`GeoTiffReader::select_image`].
+    pub async fn select_image(&mut self, index: usize) -> GeoTiffResult<()> {
+        self.run(|reader| reader.select_image(index)).await
+    }
+
+    /// See [`GeoTiffReader::overviews`].
+    pub fn overviews(&self) -> &[Overview] {
+        self.reader.overviews()
+    }
+
+    /// See [`GeoTiffReader::selected_overview`].
+    pub fn selected_overview(&self) -> Option<usize> {
+        self.reader.selected_overview()
+    }
+
+    /// Selects an overview or the full-resolution raster for all subsequent reads, see
+    /// [`GeoTiffReader::select_overview`].
+    pub async fn select_overview(&mut self, overview: Option<usize>) -> GeoTiffResult<()> {
+        self.run(|reader| reader.select_overview(overview)).await
+    }
+
+    /// Reads the whole raster, see [`GeoTiffReader::read`].
+    pub async fn read(&mut self) -> GeoTiffResult<GeoTiff> {
+        let window = [0, 0, self.raster_width(), self.raster_height()];
+        self.prefetch(window, None).await?;
+        self.run(|reader| reader.read()).await
+    }
+
+    /// Reads a window of `width * height` pixels with its upper left corner at pixel `(x, y)`,
+    /// see [`GeoTiffReader::read_window`].
+    pub async fn read_window(
+        &mut self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    ) -> GeoTiffResult<GeoTiff> {
+        self.prefetch([x, y, width, height], None).await?;
+        self.run(|reader| reader.read_window(x, y, width, height))
+            .await
+    }
+
+    /// Reads the samples of a window of `width * height` pixels with its upper left corner at
+    /// pixel `(x, y)`, see [`GeoTiffReader::read_window_values`].
+    pub async fn read_window_values<T: Sample + 'static>(
+        &mut self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    ) -> GeoTiffResult<Vec<T>> {
+        self.prefetch([x, y, width, height], None).await?;
+        self.run(|reader| reader.read_window_values(x, y, width, height))
+            .await
+    }
+
+    /// Reads a single band of the whole raster, see [`GeoTiffReader::read_band`].
+    pub async fn read_band(&mut self, band: usize) -> GeoTiffResult<GeoTiff> {
+        let [width, height] = [self.raster_width(), self.raster_height()];
+        self.read_band_window(band, 0, 0, width, height).await
+    }
+
+    /// Reads a single band of a window of `width * height` pixels with its upper left corner at
+    /// pixel `(x, y)`, see [`GeoTiffReader::read_band_window`].
+    pub async fn read_band_window(
+        &mut self,
+        band: usize,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    ) -> GeoTiffResult<GeoTiff> {
+        self.prefetch([x, y, width, height], Some(&[band])).await?;
+        self.run(|reader| reader.read_band_window(band, x, y, width, height))
+            .await
+    }
+
+    /// Reads the given bands of the window `[x, y, width, height]` in the given layout, see
+    /// [`GeoTiffReader::read_bands`].
+    pub async fn read_bands<T: FromPrimitive + 'static>(
+        &mut self,
+        window: [usize; 4],
+        bands: &[usize],
+        layout: Layout,
+    ) -> GeoTiffResult<Vec<T>> {
+        self.prefetch(window, Some(bands)).await?;
+        self.run(|reader| reader.read_bands(window, bands, layout))
+            .await
+    }
+
+    /// Reads the pixels intersecting a bounding box in model space, see
+    /// [`GeoTiffReader::read_bbox`].
+    pub async fn read_bbox(
+        &mut self,
+        min_x: f64,
+        min_y: f64,
+        max_x: f64,
+        max_y: f64,
+    ) -> GeoTiffResult<GeoTiff> {
+        let [x, y, width, height] =
+            self.reader
+                .bbox_window(min_x, min_y, max_x, max_y, WindowSnap::Outward)?;
+        self.read_window(x, y, width, height).await
+    }
+
+    /// Reads the pixels intersecting a bounding box in model space at approximately the given
+    /// size of a pixel, see [`GeoTiffReader::read_window_at_resolution`].
+    pub async fn read_window_at_resolution(
+        &mut self,
+        bbox: Extent,
+        pixel_size: f64,
+    ) -> GeoTiffResult<GeoTiff> {
+        let selected_overview = self.selected_overview();
+        let overview = self.reader.overview_for_pixel_size(pixel_size)?;
+        self.select_overview(overview).await?;
+        let window = self
+            .read_bbox(bbox.min_x, bbox.min_y, bbox.max_x, bbox.max_y)
+            .await;
+        self.select_overview(selected_overview).await?;
+        resample_to_pixel_size(window?, pixel_size)
+    }
+
+    /// Reads a window resampled to the given size, see
+    /// [`GeoTiffReader::read_window_resampled`].
+    pub async fn read_window_resampled(
+        &mut self,
+        window: [usize; 4],
+        out_width: usize,
+        out_height: usize,
+        resampling: Resampling,
+    ) -> GeoTiffResult<GeoTiff> {
+        self.run(|reader| reader.read_window_resampled(window, out_width, out_height, resampling))
+            .await
+    }
+
+    /// Reads the tile in the given column and row of the tile grid, see
+    /// [`GeoTiffReader::read_tile`].
+    pub async fn read_tile(&mut self, column: usize, row: usize) -> GeoTiffResult<GeoTiff> {
+        let [x, y, width, height] = self.reader.tile_window(column, row)?;
+        self.read_window(x, y, width, height).await
+    }
+
+    /// Reads the strip with the given index, see [`GeoTiffReader::read_strip`].
+    pub async fn read_strip(&mut self, index: usize) -> GeoTiffResult<GeoTiff> {
+        let [x, y, width, height] = self.reader.strip_window(index)?;
+        self.read_window(x, y, width, height).await
+    }
+
+    /// Fetches the strips or tiles intersecting the window `[x, y, width, height]` of the given
+    /// bands, or of all bands for `None`, and those of its transparency mask, up front, so that
+    /// decoding the window doesn't need to be repeated for each of them. The ranges are read at
+    /// once, see [`RangeSource::read_ranges`].
+    async fn prefetch(&mut self, window: [usize; 4], bands: Option<&[usize]>) -> GeoTiffResult<()> {
+        let mut ranges = self
+            .run(|reader| reader.window_byte_ranges(window, bands))
+            .await?;
+        ranges.sort_by_key(|range| range.start);
+
+        // Merge adjacent chunks to fetch them at once, as is typical for cloud optimized GeoTIFFs
+        let mut merged: Vec<Range<u64>> = Vec::new();
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => merged.push(range),
+            }
+        }
+        let missing = merged
+            .into_iter()
+            .map(|range| range.start.min(self.length)..range.end.min(self.length))
+            .filter(|range| !self.cache.lock().unwrap().contains(range))
+            .collect::<Vec<_>>();
+        let fetched = self.source.read_ranges(&missing).await?;
+        for (range, bytes) in missing.into_iter().zip(fetched) {
+            insert(&self.cache, range, bytes)?;
+        }
+        Ok(())
+    }
+
+    /// Runs the operation on the cached data, fetching any missing data and retrying until the
+    /// operation either succeeds or fails for another reason.
+    async fn run<T>(
+        &mut self,
+        mut operation: impl FnMut(&mut GeoTiffReader<CachedReader>) -> GeoTiffResult<T>,
+    ) -> GeoTiffResult<T> {
+        loop {
+            self.cache.lock().unwrap().missing = None;
+            match operation(&mut self.reader) {
+                Ok(value) => return Ok(value),
+                Err(err) => {
+                    let missing = self.cache.lock().unwrap().missing.take();
+                    match missing {
+                        Some(range) => {
+                            read_ahead(&mut self.source, &self.cache, range, self.length).await?
+                        }
+                        None => return Err(err),
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Fetches the missing range, extended by [`READ_AHEAD`] bytes.
+async fn read_ahead<R: RangeSource>(
+    source: &mut R,
+    cache: &Mutex<Cache>,
+    range: Range<u64>,
+    length: u64,
+) -> io::Result<()> {
+    let end = range.end.max(range.start + READ_AHEAD).min(length);
+    fetch(source, cache, range.start..end).await
+}
+
+async fn fetch<R: RangeSource>(
+    source: &mut R,
+    cache: &Mutex<Cache>,
+    range: Range<u64>,
+) -> io::Result<()> {
+    let bytes = source.read_range(range.clone()).await?;
+    insert(cache, range, bytes)
+}
+
+/// Caches the bytes fetched for the range, failing if they do not cover it.
+fn insert(cache: &Mutex<Cache>, range: Range<u64>, bytes: Vec<u8>) -> io::Result<()> {
+    if bytes.len() as u64 != range.end - range.start {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!(
+                "Read {} bytes instead of the range {:?}",
+                bytes.len(),
+                range
+            ),
+        ));
+    }
+    cache.lock().unwrap().insert(range.start, bytes);
+    Ok(())
+}
+
+/// The data fetched from the source so far, as disjoint segments keyed by their offset.
+#[derive(Debug, Default)]
+struct Cache {
+    segments: BTreeMap<u64, Vec<u8>>,
+    /// The range the last read failed on as it had not been fetched yet
+    missing: Option<Range<u64>>,
+}
+
+impl Cache {
+    /// The segment containing the given position.
+    fn segment(&self, position: u64) -> Option<(u64, &[u8])> {
+        self.segments
+            .range(..=position)
+            .next_back()
+            .filter(|(&start, bytes)| position < start + bytes.len() as u64)
+            .map(|(&start, bytes)| (start, bytes.as_slice()))
+    }
+
+    fn contains(&self, range: &Range<u64>) -> bool {
+        range.is_empty()
+            || self
+                .segment(range.start)
+                .is_some_and(|(start, bytes)| range.end <= start + bytes.len() as u64)
+    }
+
+    /// Inserts the bytes at the given offset, merging them with overlapping and adjacent segments.
+    fn insert(&mut self, mut start: u64, mut bytes: Vec<u8>) {
+        let mut end = start + bytes.len() as u64;
+        let overlapping = self
+            .segments
+            .range(..=end)
+            .rev()
+            .take_while(|(&offset, segment)| offset + segment.len() as u64 >= start)
+            .map(|(&offset, _)| offset)
+            .collect::<Vec<_>>();
+        for offset in overlapping.into_iter().rev() {
+            let segment = self.segments.remove(&offset).unwrap();
+            let segment_end = offset + segment.len() as u64;
+            if offset < start {
+                let mut merged = segment[..(start - offset) as usize].to_vec();
+                merged.append(&mut bytes);
+                bytes = merged;
+                start = offset;
+            }
+            if segment_end > end {
+                bytes.extend_from_slice(&segment[(end - offset) as usize..]);
+                end = segment_end;
+            }
+        }
+        self.segments.insert(start, bytes);
+    }
+}
+
+/// A synchronous reader over the cached data, failing on and recording data not fetched yet.
+struct CachedReader {
+    cache: Arc<Mutex<Cache>>,
+    length: u64,
+    position: u64,
+}
+
+impl CachedReader {
+    fn new(cache: Arc<Mutex<Cache>>, length: u64) -> Self {
+        Self {
+            cache,
+            length,
+            position: 0,
+        }
+    }
+}
+
+impl Read for CachedReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() || self.position >= self.length {
+            return Ok(0);
+        }
+        let mut cache = self.cache.lock().unwrap();
+        match cache.segment(self.position) {
+            Some((start, bytes)) => {
+                let bytes = &bytes[(self.position - start) as usize..];
+                let len = bytes.len().min(buf.len());
+                buf[..len].copy_from_slice(&bytes[..len]);
+                self.position += len as u64;
+                Ok(len)
+            }
+            None => {
+                let end = (self.position + buf.len() as u64).min(self.length);
+                cache.missing = Some(self.position..end);
+                Err(io::Error::new(
+                    io::ErrorKind::WouldBlock,
+                    "The data has not been fetched yet",
+                ))
+            }
+        }
+    }
+}
+
+impl Seek for CachedReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.length.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+        };
+        self.position = position.ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "Seek to a negative position")
+        })?;
+        Ok(self.position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{Compression, CoordinateTransform, GeoTiffWriter, Resampling};
+
+    #[test]
+    fn test_cache() {
+        let mut cache = Cache::default();
+        cache.insert(10, vec![1, 2, 3]);
+        cache.insert(20, vec![4]);
+        assert!(cache.contains(&(11..13)));
+        assert!(!cache.contains(&(11..14)));
+        assert_eq!(cache.segment(15), None);
+
+        // Merge with both segments
+        cache.insert(12, vec![9, 9, 9, 9, 9, 9, 9, 9, 9]);
+        assert_eq!(cache.segments.len(), 1);
+        assert_eq!(
+            cache.segment(15),
+            Some((10, [1, 2, 9, 9, 9, 9, 9, 9, 9, 9, 9].as_slice()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read() {
+        let data = (0..600 * 300).map(|value| value as u16).collect::<Vec<_>>();
+        let transform =
+            CoordinateTransform::from_geotransform([1000.0, 10.0, 0.0, 2000.0, 0.0, -10.0])
+                .unwrap();
+        let mut bytes = Vec::new();
+        GeoTiffWriter::new()
+            .with_tiles(64, 64)
+            .with_compression(Compression::Deflate { level: 6 })
+            .with_coordinate_transform(transform)
+            .add_overviews(&[2], Resampling::Average)
+            .write(&mut bytes, 600, 300, 1, &data)
+            .unwrap();
+
+        let mut reader = AsyncGeoTiffReader::new(Cursor::new(bytes)).await.unwrap();
+        assert_eq!((reader.raster_width(), reader.raster_height()), (600, 300));
+        assert_eq!(reader.tile_count(), Some([10, 5]));
+
+        let tile = reader.read_tile(9, 4).await.unwrap();
+        assert_eq!((tile.raster_width, tile.raster_height), (24, 44));
+        assert_eq!(tile.get_value_at::<u16>(0, 0, 0), (256 * 600 + 576) as u16);
+        assert_eq!(tile.transform_to_model([0, 0]), Some([6760.0, -560.0]));
+
+        let window = reader
+            .read_bbox(1005.0, 1985.0, 1015.0, 1995.0)
+            .await
+            .unwrap();
+        assert_eq!((window.raster_width, window.raster_height), (2, 2));
+        assert_eq!(window.get_value_at::<u16>(1, 1, 0), 601);
+
+        reader.select_overview(Some(0)).await.unwrap();
+        assert_eq!(reader.read_tile(0, 0).await.unwrap().raster_width, 64);
+        reader.select_overview(None).await.unwrap();
+        let window = reader
+            .read_window_at_resolution(Extent::new(1000.0, 1000.0, 2000.0, 2000.0), 40.0)
+            .await
+            .unwrap();
+        assert_eq!((window.raster_width, window.raster_height), (25, 25));
+        let window = reader
+            .read_window_resampled([0, 0, 100, 100], 25, 25, Resampling::Nearest)
+            .await
+            .unwrap();
+        assert_eq!(window.resolution(), Some([40.0, 40.0]));
+        assert_eq!(reader.selected_overview(), None);
+
+        let geotiff = reader.read().await.unwrap();
+        assert_eq!(
+            geotiff.get_value_at::<u16>(599, 299, 0),
+            (299 * 600 + 599) as u16
+        );
+        assert!(reader.read_window(590, 290, 20, 20).await.is_err());
+    }
+}
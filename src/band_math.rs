@@ -0,0 +1,412 @@
+use std::any::type_name;
+use std::fmt::{self, Debug, Formatter};
+use std::io::{Read, Seek, Write};
+use std::ops::{Add, Div, Mul, Neg, Sub};
+use std::sync::Arc;
+
+use crate::raster_data::RasterData;
+use crate::{GeoTiff, GeoTiffError, GeoTiffReader, GeoTiffResult, GeoTiffWriter, Sample};
+
+/// An expression of band math, e.g. the NDVI
+/// `(Expr::band(3) - Expr::band(2)) / (Expr::band(3) + Expr::band(2))` of a raster whose bands
+/// 3 and 2 are near-infrared and red, evaluated pixel by pixel by [`GeoTiffReader::evaluate`].
+///
+/// Expressions are combined by the arithmetic operators, also with constants, and by closures,
+/// see [`Expr::map`] and [`Expr::function`]. Nodata propagates: the result is nodata if any band
+/// it depends on is nodata at the pixel, see [`GeoTiff::get_value_at_checked`], or if it is NaN,
+/// e.g. by dividing zero by zero.
+#[derive(Clone)]
+pub struct Expr {
+    node: Node,
+}
+
+/// A function of the values of several expressions at a pixel, see [`Expr::function`].
+type Function = Arc<dyn Fn(&[f64]) -> f64 + Send + Sync>;
+
+#[derive(Clone)]
+enum Node {
+    Band(usize),
+    Constant(f64),
+    Binary(BinaryOperator, Box<Expr>, Box<Expr>),
+    Map(Box<Expr>, Arc<dyn Fn(f64) -> f64 + Send + Sync>),
+    Function(Vec<Expr>, Function),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum BinaryOperator {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl Expr {
+    /// The values of the band with the given index, as physical values if the reader applies the
+    /// scale and offset, see [`GeoTiffReader::set_apply_scale_offset`].
+    pub fn band(band: usize) -> Self {
+        Self {
+            node: Node::Band(band),
+        }
+    }
+
+    pub fn constant(value: f64) -> Self {
+        Self {
+            node: Node::Constant(value),
+        }
+    }
+
+    /// Applies the function to the values of the expression, e.g. `|value| value.max(0.0)`.
+    pub fn map(self, function: impl Fn(f64) -> f64 + Send + Sync + 'static) -> Self {
+        Self {
+            node: Node::Map(Box::new(self), Arc::new(function)),
+        }
+    }
+
+    /// Applies the function to the values of the expressions at each pixel, passed in the same
+    /// order, e.g. to choose between bands by a condition.
+    pub fn function(
+        exprs: impl IntoIterator<Item = Expr>,
+        function: impl Fn(&[f64]) -> f64 + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            node: Node::Function(exprs.into_iter().collect(), Arc::new(function)),
+        }
+    }
+
+    /// Adds the bands the expression depends on.
+    fn add_bands(&self, bands: &mut Vec<usize>) {
+        match &self.node {
+            Node::Band(band) => bands.push(*band),
+            Node::Constant(_) => {}
+            Node::Binary(_, left, right) => {
+                left.add_bands(bands);
+                right.add_bands(bands);
+            }
+            Node::Map(expr, _) => expr.add_bands(bands),
+            Node::Function(exprs, _) => exprs.iter().for_each(|expr| expr.add_bands(bands)),
+        }
+    }
+
+    /// Evaluates the expression given the values of the bands at a pixel, `None` for nodata.
+    fn evaluate(&self, values: &[Option<f64>]) -> Option<f64> {
+        match &self.node {
+            Node::Band(band) => values[*band],
+            Node::Constant(value) => Some(*value),
+            Node::Binary(operator, left, right) => {
+                let (left, right) = (left.evaluate(values)?, right.evaluate(values)?);
+                Some(match operator {
+                    BinaryOperator::Add => left + right,
+                    BinaryOperator::Sub => left - right,
+                    BinaryOperator::Mul => left * right,
+                    BinaryOperator::Div => left / right,
+                })
+            }
+            Node::Map(expr, function) => expr.evaluate(values).map(|value| function(value)),
+            Node::Function(exprs, function) => {
+                let arguments = exprs
+                    .iter()
+                    .map(|expr| expr.evaluate(values))
+                    .collect::<Option<Vec<_>>>()?;
+                Some(function(&arguments))
+            }
+        }
+    }
+}
+
+impl Debug for Expr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.node {
+            Node::Band(band) => write!(f, "b{}", band),
+            Node::Constant(value) => write!(f, "{}", value),
+            Node::Binary(operator, left, right) => {
+                let symbol = match operator {
+                    BinaryOperator::Add => "+",
+                    BinaryOperator::Sub => "-",
+                    BinaryOperator::Mul => "*",
+                    BinaryOperator::Div => "/",
+                };
+                write!(f, "({:?} {} {:?})", left, symbol, right)
+            }
+            Node::Map(expr, _) => write!(f, "f({:?})", expr),
+            Node::Function(exprs, _) => f.debug_tuple("f").field(exprs).finish(),
+        }
+    }
+}
+
+impl From<f64> for Expr {
+    fn from(value: f64) -> Self {
+        Expr::constant(value)
+    }
+}
+
+macro_rules! impl_binary_operator {
+    ($($trait: ident, $method: ident, $operator: ident);*) => {
+        $(
+            impl<E: Into<Expr>> $trait<E> for Expr {
+                type Output = Expr;
+
+                fn $method(self, right: E) -> Expr {
+                    Expr {
+                        node: Node::Binary(
+                            BinaryOperator::$operator,
+                            Box::new(self),
+                            Box::new(right.into()),
+                        ),
+                    }
+                }
+            }
+
+            impl $trait<Expr> for f64 {
+                type Output = Expr;
+
+                fn $method(self, right: Expr) -> Expr {
+                    Expr::constant(self).$method(right)
+                }
+            }
+        )*
+    };
+}
+
+impl_binary_operator!(Add, add, Add; Sub, sub, Sub; Mul, mul, Mul; Div, div, Div);
+
+impl Neg for Expr {
+    type Output = Expr;
+
+    fn neg(self) -> Expr {
+        self.map(|value| -value)
+    }
+}
+
+/// Receives the values of a single band, `None` for nodata, a number of rows at a time.
+pub(crate) type Rows<'a> = &'a mut dyn FnMut(Vec<Option<f64>>) -> GeoTiffResult<()>;
+
+impl<R: Read + Seek> GeoTiffReader<R> {
+    /// Evaluates the expression for each pixel of the selected image or overview, returning a
+    /// single band of `f64` values with the georeferencing of the raster and NaN as nodata.
+    ///
+    /// The raster is read in rows of strips or tiles, decoding only the bands the expression
+    /// depends on. Fails if a band does not exist.
+    pub fn evaluate(&mut self, expr: &Expr) -> GeoTiffResult<GeoTiff> {
+        self.collect_band(|reader, rows| reader.evaluate_rows(expr, rows))
+    }
+
+    /// Evaluates the expression like [`GeoTiffReader::evaluate`], streaming the result as
+    /// samples of type `T` to the sink, see [`GeoTiffWriter::stream`], so that only a row of
+    /// strips or tiles is held in memory. The GeoTIFF has the georeferencing of the raster and the
+    /// options of the writer otherwise.
+    ///
+    /// Nodata is written as the nodata value of the writer, or as NaN if it has none. Fails if a
+    /// band does not exist, if the writer is configured for tiles, planar bands or overviews, or
+    /// if a value cannot be represented as `T`, e.g. nodata as an integer without a nodata value.
+    pub fn evaluate_to<W: Write, T: Sample>(
+        &mut self,
+        expr: &Expr,
+        writer: &GeoTiffWriter,
+        sink: W,
+    ) -> GeoTiffResult<W> {
+        self.stream_band::<W, T>(writer, sink, |reader, rows| {
+            reader.evaluate_rows(expr, rows)
+        })
+    }
+
+    /// Collects the values of a single band computed from the selected image or overview into a
+    /// GeoTiff of `f64` values with the georeferencing of the raster and NaN as nodata. `compute`
+    /// passes the values a number of rows at a time to the given function, `None` for nodata.
+    pub(crate) fn collect_band(
+        &mut self,
+        compute: impl FnOnce(&mut Self, Rows<'_>) -> GeoTiffResult<()>,
+    ) -> GeoTiffResult<GeoTiff> {
+        let mut values = Vec::with_capacity(self.raster_width() * self.raster_height());
+        compute(self, &mut |rows| {
+            values.extend(rows.into_iter().map(|value| value.unwrap_or(f64::NAN)));
+            Ok(())
+        })?;
+
+        let mut geotiff = self.metadata().clone();
+        geotiff.select_bands(&[0]);
+        geotiff.raster_data = RasterData::F64(values);
+        geotiff.nodata = Some(RasterData::F64(vec![f64::NAN]));
+        geotiff.band_scale_offset = vec![None];
+        geotiff.mask = None;
+        geotiff.gdal_metadata = None;
+        Ok(geotiff)
+    }
+
+    /// Streams the values of a single band computed like by [`GeoTiffReader::collect_band`] as
+    /// samples of type `T` to the sink, see [`GeoTiffReader::evaluate_to`].
+    pub(crate) fn stream_band<W: Write, T: Sample>(
+        &mut self,
+        writer: &GeoTiffWriter,
+        sink: W,
+        compute: impl FnOnce(&mut Self, Rows<'_>) -> GeoTiffResult<()>,
+    ) -> GeoTiffResult<W> {
+        let nodata = writer.nodata().unwrap_or(f64::NAN);
+        let mut writer = writer
+            .clone()
+            .with_geo_key_directory(self.metadata().geo_key_directory().clone())
+            .with_nodata(nodata);
+        if let Some(coordinate_transform) = self.metadata().coordinate_transform() {
+            writer = writer.with_coordinate_transform(coordinate_transform.clone());
+        }
+        let mut stream =
+            writer.stream::<W, T>(sink, self.raster_width(), self.raster_height(), 1)?;
+        compute(self, &mut |rows| {
+            let samples = rows
+                .into_iter()
+                .map(|value| {
+                    let value = value.unwrap_or(nodata);
+                    T::from_f64(value).ok_or_else(|| {
+                        GeoTiffError::InvalidArgument(format!(
+                            "The value {} cannot be represented as {}",
+                            value,
+                            type_name::<T>()
+                        ))
+                    })
+                })
+                .collect::<GeoTiffResult<Vec<_>>>()?;
+            stream.write_rows(&samples)
+        })?;
+        stream.finish()
+    }
+
+    /// Evaluates the expression row of strips or tiles by row, passing the values of the rows of
+    /// each to `rows`.
+    fn evaluate_rows(&mut self, expr: &Expr, rows: Rows<'_>) -> GeoTiffResult<()> {
+        let mut bands = Vec::new();
+        expr.add_bands(&mut bands);
+        bands.sort_unstable();
+        bands.dedup();
+        for &band in &bands {
+            self.check_band(band)?;
+        }
+        // The first band is read for the mask of constant expressions
+        let read_bands = if bands.is_empty() { vec![0] } else { bands };
+
+        let (width, height) = (self.raster_width(), self.raster_height());
+        let [_, block_height] = self.block_size();
+        let mut values = vec![None; self.num_samples()];
+        for y in (0..height).step_by(block_height) {
+            let block_height = block_height.min(height - y);
+            let window = self.read_window_bands([0, y, width, block_height], &read_bands)?;
+            let mut row_values = Vec::with_capacity(width * block_height);
+            for row in 0..block_height {
+                for column in 0..width {
+                    for (index, &band) in read_bands.iter().enumerate() {
+                        values[band] = window.get_value_at_checked(column, row, index);
+                    }
+                    let valid = window.is_valid_at(column, row);
+                    row_values.push(
+                        expr.evaluate(&values)
+                            .filter(|value| valid && !value.is_nan()),
+                    );
+                }
+            }
+            rows(row_values)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{CoordinateTransform, Layout};
+
+    fn reader() -> GeoTiffReader<Cursor<Vec<u8>>> {
+        // Band b of pixel i has the value 10 * b + i % 7, the value 5 of band 0 being nodata
+        let data = (0..40 * 20)
+            .flat_map(|pixel| (0..3).map(move |band| (10 * band + pixel % 7) as i16))
+            .collect::<Vec<_>>();
+        let transform =
+            CoordinateTransform::from_geotransform([1000.0, 10.0, 0.0, 2000.0, 0.0, -10.0])
+                .unwrap();
+        let mut bytes = Vec::new();
+        GeoTiffWriter::new()
+            .with_tiles(16, 16)
+            .with_nodata(5.0)
+            .with_epsg(32632)
+            .unwrap()
+            .with_coordinate_transform(transform)
+            .write(&mut bytes, 40, 20, 3, &data)
+            .unwrap();
+        GeoTiffReader::new(Cursor::new(bytes)).unwrap()
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let mut reader = reader();
+        let (b0, b1, b2) = (Expr::band(0), Expr::band(1), Expr::band(2));
+        let ndvi = (b1.clone() - b0.clone()) / (b1.clone() + b0.clone());
+        assert_eq!(format!("{:?}", ndvi), "((b1 - b0) / (b1 + b0))");
+        let geotiff = reader.evaluate(&ndvi).unwrap();
+        assert_eq!((geotiff.raster_width, geotiff.raster_height), (40, 20));
+        assert_eq!(geotiff.num_samples, 1);
+        assert_eq!(geotiff.epsg_code(), Some(32632));
+        assert_eq!(geotiff.geotransform(), reader.metadata().geotransform());
+        // Pixel 1 has the values 1 and 11
+        assert_eq!(
+            geotiff.get_value_at_checked::<f64>(1, 0, 0),
+            Some(10.0 / 12.0)
+        );
+        // The nodata value 5 of band 0 makes nodata
+        assert_eq!(geotiff.get_value_at_checked::<f64>(5, 0, 0), None);
+        // The last pixel, in the last row of tiles, has the values 1 and 11 as well
+        assert_eq!(
+            geotiff.get_value_at_checked::<f64>(39, 19, 0),
+            Some(10.0 / 12.0)
+        );
+        // NaN, e.g. of 0 / 0, is nodata
+        let geotiff = reader.evaluate(&(b0.clone() / b0.clone())).unwrap();
+        assert_eq!(geotiff.get_value_at_checked::<f64>(0, 0, 0), None);
+        assert_eq!(geotiff.get_value_at_checked::<f64>(1, 0, 0), Some(1.0));
+
+        let expr = Expr::function([b2, -b1.map(|value| value * 2.0)], |values| {
+            values[0].max(values[1])
+        }) + 1.0;
+        let geotiff = reader.evaluate(&expr).unwrap();
+        assert_eq!(geotiff.get_value_at_checked::<f64>(3, 0, 0), Some(24.0));
+        let geotiff = reader.evaluate(&(2.0 * Expr::constant(3.0))).unwrap();
+        assert_eq!(
+            geotiff.get_values::<f64>(Layout::PixelInterleaved),
+            vec![6.0; 40 * 20]
+        );
+
+        assert!(matches!(
+            reader.evaluate(&Expr::band(3)),
+            Err(GeoTiffError::OutOfBounds(_))
+        ));
+    }
+
+    #[test]
+    fn test_evaluate_to() {
+        let mut reader = reader();
+        let expr = Expr::band(2) - Expr::band(0);
+        let bytes = reader
+            .evaluate_to::<_, f32>(&expr, &GeoTiffWriter::new(), Vec::new())
+            .unwrap();
+        let geotiff = GeoTiff::read(Cursor::new(bytes)).unwrap();
+        assert_eq!(geotiff.epsg_code(), Some(32632));
+        assert_eq!(geotiff.get_value_at_checked::<f32>(4, 0, 0), Some(20.0));
+        assert_eq!(geotiff.get_value_at_checked::<f32>(5, 0, 0), None);
+        assert_eq!(
+            geotiff.get_window_values_checked::<f32>(0, 0, 40, 20, 0),
+            reader
+                .evaluate(&expr)
+                .unwrap()
+                .get_window_values_checked::<f32>(0, 0, 40, 20, 0)
+        );
+
+        // Integers require a nodata value
+        assert!(reader
+            .evaluate_to::<_, i16>(&expr, &GeoTiffWriter::new(), Vec::new())
+            .is_err());
+        let bytes = reader
+            .evaluate_to::<_, i16>(&expr, &GeoTiffWriter::new().with_nodata(-1.0), Vec::new())
+            .unwrap();
+        let geotiff = GeoTiff::read(Cursor::new(bytes)).unwrap();
+        assert_eq!(geotiff.get_value_at::<i16>(5, 0, 0), -1);
+        assert_eq!(geotiff.get_value_at_checked::<i16>(6, 0, 0), Some(20));
+    }
+}
@@ -0,0 +1,189 @@
+//! Prints a summary of a GeoTIFF like `gdalinfo` does, optionally followed by all TIFF tags and
+//! GeoKeys of its images like `tiffdump` and `listgeo` do.
+use std::env;
+use std::fs::File;
+use std::io::BufReader;
+use std::process::ExitCode;
+
+use geotiff::{GeoTiff, GeoTiffReader, GeoTiffResult, ImageMetadata, Metadata, TagValue};
+
+const USAGE: &str = "Usage: geotiffinfo [--tags] <file>";
+
+/// The Compression tag
+const COMPRESSION_TAG: u16 = 259;
+/// The BitsPerSample tag
+const BITS_PER_SAMPLE_TAG: u16 = 258;
+/// The PlanarConfiguration tag
+const PLANAR_CONFIGURATION_TAG: u16 = 284;
+/// The SampleFormat tag
+const SAMPLE_FORMAT_TAG: u16 = 339;
+
+fn main() -> ExitCode {
+    let mut tags = false;
+    let mut path = None;
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--tags" => tags = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return ExitCode::SUCCESS;
+            }
+            _ if path.is_none() && !arg.starts_with('-') => path = Some(arg),
+            _ => {
+                eprintln!("{}", USAGE);
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+    let Some(path) = path else {
+        eprintln!("{}", USAGE);
+        return ExitCode::FAILURE;
+    };
+    match print_info(&path, tags) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{}: {}", path, err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn print_info(path: &str, tags: bool) -> GeoTiffResult<()> {
+    let metadata = GeoTiff::metadata(BufReader::new(File::open(path)?))?;
+    let reader = GeoTiffReader::new(BufReader::new(File::open(path)?))?;
+    let image = &metadata.images[0];
+    let geotiff = reader
+        .image_metadata(reader.selected_image())
+        .expect("The selected image exists");
+
+    println!("File: {}", path);
+    println!(
+        "Size: {} x {}, {} band{}",
+        geotiff.raster_width,
+        geotiff.raster_height,
+        geotiff.num_samples,
+        if geotiff.num_samples == 1 { "" } else { "s" }
+    );
+    println!("Data type: {}", data_type(image));
+    println!("Compression: {}", compression(image));
+    let interleave = match image.get(PLANAR_CONFIGURATION_TAG) {
+        Some(TagValue::Unsigned(values)) if values.first() == Some(&2) => "band",
+        _ => "pixel",
+    };
+    match (reader.tile_dimensions(), reader.rows_per_strip()) {
+        (Some([width, height]), _) => {
+            println!(
+                "Layout: tiles of {} x {}, {} interleaved",
+                width, height, interleave
+            )
+        }
+        (None, Some(rows)) => println!(
+            "Layout: strips of {} rows, {} interleaved",
+            rows, interleave
+        ),
+        (None, None) => println!("Layout: {} interleaved", interleave),
+    }
+
+    match (geotiff.epsg_code(), geotiff.crs_description()) {
+        (Some(code), Some(description)) => println!("CRS: EPSG:{} ({})", code, description),
+        (Some(code), None) => println!("CRS: EPSG:{}", code),
+        (None, Some(description)) => println!("CRS: {}", description),
+        (None, None) => println!("CRS: none"),
+    }
+    if let Some(version) = geotiff.geotiff_version() {
+        println!("GeoTIFF version: {}", version);
+    }
+    if let Some([origin_x, pixel_width, _, origin_y, _, pixel_height]) = geotiff.geotransform() {
+        println!("Origin: ({}, {})", origin_x, origin_y);
+        println!("Pixel size: ({}, {})", pixel_width, pixel_height);
+    }
+    if let Some(extent) = geotiff.model_extent() {
+        println!(
+            "Extent: ({}, {}) - ({}, {})",
+            extent.min_x, extent.min_y, extent.max_x, extent.max_y
+        );
+    }
+    if let Some(nodata) = geotiff.nodata::<f64>() {
+        println!("NoData: {}", nodata);
+    }
+    if reader.overviews().is_empty() {
+        println!("Overviews: none");
+    } else {
+        let overviews = reader
+            .overviews()
+            .iter()
+            .map(|overview| format!("{} x {}", overview.raster_width, overview.raster_height))
+            .collect::<Vec<_>>();
+        println!("Overviews: {}", overviews.join(", "));
+    }
+    for warning in geotiff.warnings() {
+        println!("Warning: {}", warning);
+    }
+
+    if tags {
+        print_tags(&metadata);
+    }
+    Ok(())
+}
+
+/// Lists the tags and GeoKeys of all images.
+fn print_tags(metadata: &Metadata) {
+    for (index, image) in metadata.iter().enumerate() {
+        println!();
+        println!("IFD {} at byte {}:", index, image.ifd_offset);
+        for tag in &image.tags {
+            let name = tag.name().unwrap_or_else(|| "Unknown".to_string());
+            match &tag.value {
+                Some(value) => println!("  {} ({}): {}", name, tag.code, value),
+                None => println!("  {} ({}): <unreadable>", name, tag.code),
+            }
+        }
+        for (key, value) in image.geo_keys() {
+            println!("  {:?} ({}): {:?}", key, key.to_u16(), value);
+        }
+        for error in &image.errors {
+            println!("  Error: {}", error);
+        }
+    }
+}
+
+/// The data type of the samples in the terms of GDAL, e.g. `UInt16`.
+fn data_type(image: &ImageMetadata) -> String {
+    let bits = match image.get(BITS_PER_SAMPLE_TAG) {
+        Some(TagValue::Unsigned(values)) => values.first().copied().unwrap_or(1),
+        _ => 1,
+    };
+    let sample_format = match image.get(SAMPLE_FORMAT_TAG) {
+        Some(TagValue::Unsigned(values)) => values.first().copied().unwrap_or(1),
+        _ => 1,
+    };
+    match (sample_format, bits) {
+        (1, 8) => "Byte".to_string(),
+        (1, bits) => format!("UInt{}", bits),
+        (2, bits) => format!("Int{}", bits),
+        (3, bits) => format!("Float{}", bits),
+        (sample_format, bits) => {
+            format!("Unknown sample format {} of {} bits", sample_format, bits)
+        }
+    }
+}
+
+/// The name of the compression method.
+fn compression(image: &ImageMetadata) -> String {
+    let code = match image.get(COMPRESSION_TAG) {
+        Some(TagValue::Unsigned(values)) => values.first().copied().unwrap_or(1),
+        _ => 1,
+    };
+    match code {
+        1 => "None".to_string(),
+        5 => "LZW".to_string(),
+        6 | 7 => "JPEG".to_string(),
+        8 | 32946 => "Deflate".to_string(),
+        32773 => "PackBits".to_string(),
+        34887 => "LERC".to_string(),
+        34925 => "LZMA".to_string(),
+        50000 => "ZSTD".to_string(),
+        50001 => "WebP".to_string(),
+        code => format!("Unknown ({})", code),
+    }
+}
@@ -0,0 +1,183 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use crate::raster_data::RasterData;
+use crate::GeoTiffResult;
+
+/// The capacity of the cache of decoded strips or tiles of a reader, see
+/// [`GeoTiffReader::set_block_cache`](crate::GeoTiffReader::set_block_cache).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockCacheCapacity {
+    /// The maximum total size of the decoded samples in bytes
+    Bytes(usize),
+    /// The maximum number of strips or tiles
+    Blocks(usize),
+}
+
+/// Identifies a decoded strip or tile by its image and index and, if only some bands were
+/// decoded, by those bands.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct BlockKey {
+    ifd_index: usize,
+    chunk_index: usize,
+    bands: Vec<usize>,
+}
+
+/// A least recently used cache of decoded strips or tiles, evicting the blocks used longest ago
+/// once the capacity is exceeded.
+#[derive(Debug)]
+pub(crate) struct BlockCache {
+    capacity: BlockCacheCapacity,
+    /// The decoded blocks along with the time they were last used
+    blocks: HashMap<BlockKey, (Arc<RasterData>, u64)>,
+    /// The keys of the blocks by the time they were last used
+    recency: BTreeMap<u64, BlockKey>,
+    time: u64,
+    bytes: usize,
+}
+
+impl BlockCache {
+    pub(crate) fn new(capacity: BlockCacheCapacity) -> Self {
+        Self {
+            capacity,
+            blocks: HashMap::new(),
+            recency: BTreeMap::new(),
+            time: 0,
+            bytes: 0,
+        }
+    }
+
+    /// The cached blocks of the image with the given IFD index.
+    pub(crate) fn image(&mut self, ifd_index: usize) -> ImageBlocks<'_> {
+        ImageBlocks {
+            cache: self,
+            ifd_index,
+        }
+    }
+
+    /// The number of cached blocks.
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Whether the blocks exceed the capacity.
+    fn is_full(&self) -> bool {
+        match self.capacity {
+            BlockCacheCapacity::Bytes(bytes) => self.bytes > bytes,
+            BlockCacheCapacity::Blocks(blocks) => self.blocks.len() > blocks,
+        }
+    }
+
+    fn get(&mut self, key: &BlockKey) -> Option<Arc<RasterData>> {
+        let (block, last_used) = self.blocks.get_mut(key)?;
+        self.recency.remove(last_used);
+        self.time += 1;
+        *last_used = self.time;
+        self.recency.insert(self.time, key.clone());
+        Some(block.clone())
+    }
+
+    fn insert(&mut self, key: BlockKey, block: Arc<RasterData>) {
+        self.time += 1;
+        self.bytes += block.byte_len();
+        self.recency.insert(self.time, key.clone());
+        if let Some((replaced, last_used)) = self.blocks.insert(key, (block, self.time)) {
+            self.bytes -= replaced.byte_len();
+            self.recency.remove(&last_used);
+        }
+        while self.is_full() {
+            let Some((_, key)) = self.recency.pop_first() else {
+                break;
+            };
+            if let Some((evicted, _)) = self.blocks.remove(&key) {
+                self.bytes -= evicted.byte_len();
+            }
+        }
+    }
+}
+
+/// The blocks of a single image within a [`BlockCache`].
+pub(crate) struct ImageBlocks<'a> {
+    cache: &'a mut BlockCache,
+    ifd_index: usize,
+}
+
+impl ImageBlocks<'_> {
+    /// Returns the cached chunk with the given index, decoding and caching it if absent. `bands`
+    /// are the bands the chunk is decoded to, or empty if it is decoded as stored.
+    pub(crate) fn get_or_decode(
+        &mut self,
+        chunk_index: usize,
+        bands: &[usize],
+        decode: impl FnOnce() -> GeoTiffResult<RasterData>,
+    ) -> GeoTiffResult<Arc<RasterData>> {
+        let key = BlockKey {
+            ifd_index: self.ifd_index,
+            chunk_index,
+            bands: bands.to_vec(),
+        };
+        if let Some(block) = self.cache.get(&key) {
+            return Ok(block);
+        }
+        let block = Arc::new(decode()?);
+        self.cache.insert(key, block.clone());
+        Ok(block)
+    }
+}
+
+/// Returns the chunk with the given index from the cache, if any, or decodes it, see
+/// [`ImageBlocks::get_or_decode`].
+pub(crate) fn get_or_decode(
+    cache: Option<&mut ImageBlocks>,
+    chunk_index: usize,
+    bands: &[usize],
+    decode: impl FnOnce() -> GeoTiffResult<RasterData>,
+) -> GeoTiffResult<Arc<RasterData>> {
+    match cache {
+        Some(cache) => cache.get_or_decode(chunk_index, bands, decode),
+        None => decode().map(Arc::new),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+
+    #[test]
+    fn test_block_cache() {
+        let decoded = Cell::new(0);
+        let decode = |len| {
+            decoded.set(decoded.get() + 1);
+            Ok(RasterData::U16(vec![0; len]))
+        };
+
+        let mut cache = BlockCache::new(BlockCacheCapacity::Blocks(2));
+        cache.image(0).get_or_decode(0, &[], || decode(4)).unwrap();
+        cache.image(0).get_or_decode(1, &[], || decode(4)).unwrap();
+        cache.image(0).get_or_decode(0, &[], || decode(4)).unwrap();
+        // Blocks of other images or bands are distinct
+        cache.image(1).get_or_decode(0, &[], || decode(4)).unwrap();
+        assert_eq!(decoded.get(), 3);
+        assert_eq!((cache.blocks.len(), cache.bytes), (2, 16));
+        // The least recently used block 1 was evicted
+        cache.image(0).get_or_decode(0, &[], || decode(4)).unwrap();
+        cache.image(0).get_or_decode(1, &[], || decode(4)).unwrap();
+        assert_eq!(decoded.get(), 4);
+        cache.image(0).get_or_decode(1, &[2], || decode(4)).unwrap();
+        assert_eq!(decoded.get(), 5);
+
+        let mut cache = BlockCache::new(BlockCacheCapacity::Bytes(20));
+        cache.image(0).get_or_decode(0, &[], || decode(4)).unwrap();
+        cache.image(0).get_or_decode(1, &[], || decode(4)).unwrap();
+        assert_eq!((cache.blocks.len(), cache.bytes), (2, 16));
+        cache.image(0).get_or_decode(2, &[], || decode(4)).unwrap();
+        assert_eq!((cache.blocks.len(), cache.bytes), (2, 16));
+        // Blocks exceeding the capacity are not cached
+        cache.image(0).get_or_decode(3, &[], || decode(20)).unwrap();
+        assert_eq!((cache.blocks.len(), cache.bytes), (0, 0));
+        assert!(cache.recency.is_empty());
+    }
+}
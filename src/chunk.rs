@@ -0,0 +1,755 @@
+use std::io::{Read, Seek};
+
+use tiff::decoder::{ChunkType, Decoder};
+use tiff::tags::{CompressionMethod, PlanarConfiguration, Predictor, SampleFormat, Tag};
+use tiff::{TiffError, TiffFormatError, TiffUnsupportedError};
+
+use crate::raster_data::{RasterData, RowLayout};
+use crate::{predictor, GeoTiffError, GeoTiffResult};
+
+/// The compression code of the ZSTD extension, which is not part of [`CompressionMethod`].
+#[cfg(feature = "zstd")]
+const ZSTD: u16 = 50000;
+/// The compression code of the WebP extension, which is not part of [`CompressionMethod`].
+#[cfg(feature = "webp")]
+const WEBP: u16 = 50001;
+/// The compression code of LERC, which is not part of [`CompressionMethod`].
+#[cfg(feature = "lerc")]
+const LERC: u16 = 34887;
+/// The tag storing the LERC version and the additional compression of the LERC blobs.
+#[cfg(feature = "lerc")]
+const LERC_PARAMETERS: u16 = 50674;
+
+/// The raw strips or tiles of the current image of a decoder, for data the decoder does not
+/// decode itself, like transparency masks or single bands of rasters with many bands.
+pub(crate) struct RawChunks {
+    compression: u16,
+    offsets: Vec<u64>,
+    byte_counts: Vec<u64>,
+    /// The number of samples of each pixel, which WebP does not store
+    #[cfg(feature = "webp")]
+    num_samples: usize,
+    /// How to decode LERC blobs, if the chunks are LERC compressed
+    #[cfg(feature = "lerc")]
+    lerc: Option<LercDecoding>,
+}
+
+impl RawChunks {
+    pub(crate) fn new<R: Read + Seek>(decoder: &mut Decoder<R>) -> GeoTiffResult<Self> {
+        let compression = decoder
+            .find_tag_unsigned::<u16>(Tag::Compression)?
+            .unwrap_or(CompressionMethod::None.to_u16());
+        let (offsets_tag, byte_counts_tag) = match decoder.get_chunk_type() {
+            ChunkType::Strip => (Tag::StripOffsets, Tag::StripByteCounts),
+            ChunkType::Tile => (Tag::TileOffsets, Tag::TileByteCounts),
+        };
+        Ok(Self {
+            compression,
+            offsets: decoder.get_tag_u64_vec(offsets_tag)?,
+            byte_counts: decoder.get_tag_u64_vec(byte_counts_tag)?,
+            #[cfg(feature = "webp")]
+            num_samples: decoder
+                .find_tag_unsigned::<u16>(Tag::SamplesPerPixel)?
+                .unwrap_or(1) as usize,
+            #[cfg(feature = "lerc")]
+            lerc: if compression == LERC {
+                Some(LercDecoding::new(decoder)?)
+            } else {
+                None
+            },
+        })
+    }
+
+    /// Whether the chunks are compressed by a method supported by [`RawChunks::read`].
+    pub(crate) fn is_supported(&self) -> bool {
+        match CompressionMethod::from_u16(self.compression) {
+            Some(
+                CompressionMethod::None
+                | CompressionMethod::LZW
+                | CompressionMethod::Deflate
+                | CompressionMethod::OldDeflate
+                | CompressionMethod::PackBits,
+            ) => true,
+            #[cfg(feature = "zstd")]
+            _ if self.compression == ZSTD => true,
+            #[cfg(feature = "webp")]
+            _ if self.compression == WEBP => true,
+            #[cfg(feature = "lerc")]
+            _ if self.compression == LERC => true,
+            _ => false,
+        }
+    }
+
+    /// Whether the chunks are compressed by a method supported here but not by the decoder.
+    pub(crate) fn is_unsupported_by_decoder(&self) -> bool {
+        #[cfg(feature = "zstd")]
+        if self.compression == ZSTD {
+            return true;
+        }
+        #[cfg(feature = "webp")]
+        if self.compression == WEBP {
+            return true;
+        }
+        #[cfg(feature = "lerc")]
+        if self.compression == LERC {
+            return true;
+        }
+        false
+    }
+
+    /// Whether the chunk with the given index is sparse, i.e. not stored at all but marked by an
+    /// offset or byte count of 0, as GDAL writes chunks of only nodata with `SPARSE_OK=TRUE`.
+    pub(crate) fn is_sparse(&self, index: usize) -> bool {
+        self.offsets.get(index) == Some(&0) || self.byte_counts.get(index) == Some(&0)
+    }
+
+    /// Whether any chunk is sparse, see [`RawChunks::is_sparse`].
+    pub(crate) fn has_sparse(&self) -> bool {
+        (0..self.offsets.len()).any(|index| self.is_sparse(index))
+    }
+
+    /// Reads and decompresses the chunk with the given index.
+    pub(crate) fn read<R: Read + Seek>(
+        &self,
+        decoder: &mut Decoder<R>,
+        index: usize,
+    ) -> GeoTiffResult<Vec<u8>> {
+        match (self.offsets.get(index), self.byte_counts.get(index)) {
+            (Some(&offset), Some(&byte_count)) => {
+                let bytes = read_bytes(decoder, offset, byte_count)?;
+                #[cfg(feature = "webp")]
+                if self.compression == WEBP {
+                    return decompress_webp(&bytes, self.num_samples);
+                }
+                #[cfg(feature = "lerc")]
+                if let Some(lerc) = &self.lerc {
+                    return lerc.decompress(bytes);
+                }
+                decompress(self.compression, bytes)
+            }
+            _ => Err(format_error(format!("Chunk {} is missing", index))),
+        }
+    }
+}
+
+/// Decodes selected bands of the strips or tiles of the current image of a decoder, converting
+/// only the samples of those bands, e.g. to read one band of a hyperspectral raster.
+///
+/// Unlike the decoder, it supports any number of bands, ZSTD, WebP and LERC compression and
+/// unsigned samples of any size up to 64 bits, e.g. 4-bit classes, which are unpacked into the
+/// next larger integer type. It supports neither JPEG compression nor signed or floating point samples of sizes
+/// other than 8, 16, 32 and 64 bits.
+pub(crate) struct BandDecoder {
+    chunks: RawChunks,
+    sample_format: SampleFormat,
+    bits_per_sample: usize,
+    little_endian: bool,
+    predictor: Predictor,
+    /// The number of samples of each pixel within a chunk, which is 1 for planar rasters
+    chunk_samples: usize,
+    /// The number of chunks of each band of planar rasters, or `None` for chunky rasters
+    chunks_per_plane: Option<usize>,
+    /// The width of the chunks including any padding
+    chunk_width: usize,
+}
+
+impl BandDecoder {
+    /// Creates a decoder of the bands of the current image of the decoder, or returns `None` if
+    /// the compression, sample type or predictor of the image is not supported.
+    pub(crate) fn new<R: Read + Seek>(decoder: &mut Decoder<R>) -> GeoTiffResult<Option<Self>> {
+        let chunks = RawChunks::new(decoder)?;
+        if !chunks.is_supported() {
+            return Ok(None);
+        }
+        let num_samples = decoder
+            .find_tag_unsigned::<u16>(Tag::SamplesPerPixel)?
+            .unwrap_or(1) as usize;
+        let sample_format = decoder
+            .find_tag_unsigned_vec::<u16>(Tag::SampleFormat)?
+            .and_then(|formats| formats.first().copied())
+            .map_or(SampleFormat::Uint, SampleFormat::from_u16_exhaustive);
+        let bits_per_sample = decoder
+            .find_tag_unsigned_vec::<u16>(Tag::BitsPerSample)?
+            .and_then(|bits| bits.first().copied())
+            .unwrap_or(1);
+        let supported_sample_type = match sample_format {
+            SampleFormat::Uint => (1..=64).contains(&bits_per_sample),
+            SampleFormat::Int => matches!(bits_per_sample, 8 | 16 | 32 | 64),
+            SampleFormat::IEEEFP => matches!(bits_per_sample, 32 | 64),
+            _ => false,
+        };
+        let predictor = match decoder.find_tag_unsigned::<u16>(Tag::Predictor)? {
+            None => Some(Predictor::None),
+            Some(predictor) => Predictor::from_u16(predictor),
+        };
+        let predictor = match predictor {
+            Some(Predictor::None) if supported_sample_type => Predictor::None,
+            // Samples are only differenced if they fill whole bytes
+            Some(predictor @ (Predictor::Horizontal | Predictor::FloatingPoint))
+                if supported_sample_type && bits_per_sample.is_multiple_of(8) =>
+            {
+                predictor
+            }
+            _ => return Ok(None),
+        };
+
+        let (width, height) = decoder.dimensions()?;
+        let (chunk_width, chunk_height) = decoder.chunk_dimensions();
+        let chunks_per_plane = (decoder.find_tag_unsigned::<u16>(Tag::PlanarConfiguration)?
+            == Some(PlanarConfiguration::Planar.to_u16()))
+        .then(|| (width.div_ceil(chunk_width) * height.div_ceil(chunk_height)) as usize);
+
+        // The first byte of the header is "I" for little-endian and "M" for big-endian files
+        decoder.goto_offset_u64(0)?;
+        let little_endian = decoder.read_byte()? == b'I';
+
+        Ok(Some(Self {
+            chunks,
+            sample_format,
+            bits_per_sample: bits_per_sample as usize,
+            little_endian,
+            predictor,
+            chunk_samples: if chunks_per_plane.is_some() {
+                1
+            } else {
+                num_samples
+            },
+            chunks_per_plane,
+            chunk_width: chunk_width as usize,
+        }))
+    }
+
+    /// Whether the decoder does not support the compression of the chunks, like ZSTD, or does not
+    /// unpack their samples, like those of 4 bits.
+    pub(crate) fn is_unsupported_by_decoder(&self) -> bool {
+        self.chunks.is_unsupported_by_decoder() || !self.bits_per_sample.is_multiple_of(8)
+    }
+
+    /// Decodes the bands of the chunk with the given index, which covers
+    /// `data_width * data_height` pixels of the raster without padding, returning the samples of
+    /// the bands pixel by pixel, row by row.
+    ///
+    /// The bands of sparse chunks are filled with the single sample `fill`, which must be given if
+    /// any chunk is sparse, see [`sparse_fill`].
+    pub(crate) fn read_bands<R: Read + Seek>(
+        &self,
+        decoder: &mut Decoder<R>,
+        chunk_index: usize,
+        bands: &[usize],
+        [data_width, data_height]: [usize; 2],
+        fill: Option<&RasterData>,
+    ) -> GeoTiffResult<RasterData> {
+        let fill = || fill.expect("sparse chunks require a fill value");
+        let len = data_width * data_height;
+        let mut values = vec![0u64; len * bands.len()];
+        let mut sparse_bands = Vec::new();
+        match self.chunks_per_plane {
+            Some(chunks_per_plane) => {
+                for (target_band, &band) in bands.iter().enumerate() {
+                    let plane_chunk_index = band * chunks_per_plane + chunk_index;
+                    if self.chunks.is_sparse(plane_chunk_index) {
+                        sparse_bands.push(target_band);
+                        continue;
+                    }
+                    let bytes = self.chunks.read(decoder, plane_chunk_index)?;
+                    self.decode(
+                        bytes,
+                        plane_chunk_index,
+                        &[(target_band, 0)],
+                        bands.len(),
+                        [data_width, data_height],
+                        &mut values,
+                    )?;
+                }
+            }
+            None if self.chunks.is_sparse(chunk_index) => {
+                return Ok(fill().filled(len * bands.len()));
+            }
+            None => {
+                let bytes = self.chunks.read(decoder, chunk_index)?;
+                let samples = bands.iter().copied().enumerate().collect::<Vec<_>>();
+                self.decode(
+                    bytes,
+                    chunk_index,
+                    &samples,
+                    bands.len(),
+                    [data_width, data_height],
+                    &mut values,
+                )?;
+            }
+        }
+
+        let mut data = match (self.sample_format, self.bits_per_sample.div_ceil(8)) {
+            (SampleFormat::Uint, 1) => RasterData::U8(values.iter().map(|&v| v as u8).collect()),
+            (SampleFormat::Uint, 2) => RasterData::U16(values.iter().map(|&v| v as u16).collect()),
+            (SampleFormat::Uint, 3 | 4) => {
+                RasterData::U32(values.iter().map(|&v| v as u32).collect())
+            }
+            (SampleFormat::Uint, _) => RasterData::U64(values),
+            (SampleFormat::Int, 1) => RasterData::I8(values.iter().map(|&v| v as i8).collect()),
+            (SampleFormat::Int, 2) => RasterData::I16(values.iter().map(|&v| v as i16).collect()),
+            (SampleFormat::Int, 4) => RasterData::I32(values.iter().map(|&v| v as i32).collect()),
+            (SampleFormat::Int, 8) => RasterData::I64(values.iter().map(|&v| v as i64).collect()),
+            (SampleFormat::IEEEFP, 4) => {
+                RasterData::F32(values.iter().map(|&v| f32::from_bits(v as u32)).collect())
+            }
+            (SampleFormat::IEEEFP, 8) => {
+                RasterData::F64(values.into_iter().map(f64::from_bits).collect())
+            }
+            _ => unreachable!("The sample type is checked when creating the decoder"),
+        };
+        if !sparse_bands.is_empty() {
+            let filled = fill().filled(len);
+            for target_band in sparse_bands {
+                data.copy_band(
+                    RowLayout {
+                        start: target_band,
+                        stride: data_width * bands.len(),
+                    },
+                    bands.len(),
+                    &filled,
+                    RowLayout {
+                        start: 0,
+                        stride: data_width,
+                    },
+                    1,
+                    [data_width, data_height],
+                );
+            }
+        }
+        Ok(data)
+    }
+
+    /// Decodes the samples of the decompressed chunk given as `(target_sample, sample)` into
+    /// `values`, whose pixels have `target_samples` samples.
+    fn decode(
+        &self,
+        mut bytes: Vec<u8>,
+        chunk_index: usize,
+        samples: &[(usize, usize)],
+        target_samples: usize,
+        [data_width, data_height]: [usize; 2],
+        values: &mut [u64],
+    ) -> GeoTiffResult<()> {
+        // Rows of samples of less than 8 bits start at a byte
+        let row_size = (self.chunk_width * self.chunk_samples * self.bits_per_sample).div_ceil(8);
+        if bytes.len() < row_size * data_height {
+            return Err(format_error(format!("Chunk {} is truncated", chunk_index)));
+        }
+
+        let bytes_per_sample = self.bits_per_sample / 8;
+        for (row_index, row) in bytes
+            .chunks_exact_mut(row_size)
+            .take(data_height)
+            .enumerate()
+        {
+            let target_row = &mut values[row_index * data_width * target_samples..]
+                [..data_width * target_samples];
+            match self.predictor {
+                // The bytes of the row are differenced, after sorting them by significance
+                Predictor::FloatingPoint => {
+                    predictor::undo_horizontal(row, 1, self.chunk_samples);
+                }
+                predictor => {
+                    if !self.little_endian && bytes_per_sample > 1 {
+                        predictor::swap_bytes(row, bytes_per_sample);
+                    }
+                    // Each sample is stored as the difference to the sample of the previous pixel
+                    if predictor == Predictor::Horizontal {
+                        predictor::undo_horizontal(
+                            row,
+                            bytes_per_sample,
+                            bytes_per_sample * self.chunk_samples,
+                        );
+                    }
+                }
+            }
+            for &(target_sample, sample) in samples {
+                for pixel in 0..data_width {
+                    target_row[pixel * target_samples + target_sample] =
+                        if self.predictor == Predictor::FloatingPoint {
+                            let samples_per_row = row.len() / bytes_per_sample;
+                            let index = pixel * self.chunk_samples + sample;
+                            (0..bytes_per_sample).fold(0, |bits, byte| {
+                                bits << 8 | row[byte * samples_per_row + index] as u64
+                            })
+                        } else {
+                            self.sample_bits(row, pixel, sample)
+                        };
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The bits of the sample of the pixel in a row of decompressed bytes, which are converted to
+    /// little-endian if they fill whole bytes.
+    fn sample_bits(&self, row: &[u8], pixel: usize, sample: usize) -> u64 {
+        let index = pixel * self.chunk_samples + sample;
+        if !self.bits_per_sample.is_multiple_of(8) {
+            // Samples not filling whole bytes are packed with the most significant bit first, in
+            // any byte order
+            let start = index * self.bits_per_sample;
+            return (start..start + self.bits_per_sample).fold(0, |bits, bit| {
+                bits << 1 | (row[bit / 8] >> (7 - bit % 8) & 1) as u64
+            });
+        }
+        let bytes_per_sample = self.bits_per_sample / 8;
+        row[index * bytes_per_sample..(index + 1) * bytes_per_sample]
+            .iter()
+            .rev()
+            .fold(0, |bits, &byte| bits << 8 | byte as u64)
+    }
+}
+
+/// The single sample sparse chunks of the current image of a decoder are filled with, which is
+/// the nodata value of the raster or 0 like in GDAL.
+pub(crate) fn sparse_fill<R: Read + Seek>(
+    decoder: &mut Decoder<R>,
+    nodata: Option<&RasterData>,
+) -> GeoTiffResult<RasterData> {
+    if let Some(nodata) = nodata {
+        return Ok(nodata.clone());
+    }
+    let sample_format = decoder
+        .find_tag_unsigned_vec::<u16>(Tag::SampleFormat)?
+        .and_then(|formats| formats.first().copied())
+        .map_or(SampleFormat::Uint, SampleFormat::from_u16_exhaustive);
+    let bits_per_sample = decoder
+        .find_tag_unsigned_vec::<u16>(Tag::BitsPerSample)?
+        .and_then(|bits| bits.first().copied())
+        .unwrap_or(1);
+    RasterData::parse_sample("0", sample_format, bits_per_sample).ok_or_else(|| {
+        GeoTiffError::Unsupported(format!(
+            "Sparse chunks of samples of {} bits and format {:?} are not supported",
+            bits_per_sample, sample_format
+        ))
+    })
+}
+
+/// Reads the samples of the current image of a decoder as stored, without unpacking samples of
+/// less than 8 bits, each row starting at a byte and unused bits at the end of a row being 0. The
+/// rows of planar rasters are returned plane by plane, with sparse chunks being 0.
+pub(crate) fn read_packed_rows<R: Read + Seek>(decoder: &mut Decoder<R>) -> GeoTiffResult<Vec<u8>> {
+    let chunks = RawChunks::new(decoder)?;
+    if !chunks.is_supported() {
+        return Err(GeoTiffError::Unsupported(format!(
+            "Compression {} is not supported for reading packed samples",
+            chunks.compression
+        )));
+    }
+    if decoder
+        .find_tag_unsigned::<u16>(Tag::Predictor)?
+        .is_some_and(|predictor| predictor != Predictor::None.to_u16())
+    {
+        return Err(GeoTiffError::Unsupported(
+            "Packed samples transformed by a predictor are not supported".to_string(),
+        ));
+    }
+    let num_samples = decoder
+        .find_tag_unsigned::<u16>(Tag::SamplesPerPixel)?
+        .unwrap_or(1) as usize;
+    let bits_per_sample = decoder
+        .find_tag_unsigned_vec::<u16>(Tag::BitsPerSample)?
+        .and_then(|bits| bits.first().copied())
+        .unwrap_or(1) as usize;
+    let planar = decoder.find_tag_unsigned::<u16>(Tag::PlanarConfiguration)?
+        == Some(PlanarConfiguration::Planar.to_u16());
+    let (num_planes, chunk_samples) = if planar {
+        (num_samples, 1)
+    } else {
+        (1, num_samples)
+    };
+
+    let (width, height) = decoder.dimensions()?;
+    let (width, height) = (width as usize, height as usize);
+    let (chunk_width, chunk_height) = decoder.chunk_dimensions();
+    let (chunk_width, chunk_height) = (chunk_width as usize, chunk_height as usize);
+    let chunks_across = width.div_ceil(chunk_width);
+    let chunks_per_plane = chunks_across * height.div_ceil(chunk_height);
+    let row_bits = width * chunk_samples * bits_per_sample;
+    let row_size = row_bits.div_ceil(8);
+    // Tiles are a multiple of 16 pixels wide, so that their rows fill whole bytes
+    let chunk_row_size = (chunk_width * chunk_samples * bits_per_sample).div_ceil(8);
+
+    let mut rows = vec![0; num_planes * height * row_size];
+    for plane in 0..num_planes {
+        for chunk_index in 0..chunks_per_plane {
+            let plane_chunk_index = plane * chunks_per_plane + chunk_index;
+            if chunks.is_sparse(plane_chunk_index) {
+                continue;
+            }
+            let chunk = chunks.read(decoder, plane_chunk_index)?;
+            let (_, data_height) = decoder.chunk_data_dimensions(chunk_index as u32);
+            let (chunk_x, chunk_y) = (chunk_index % chunks_across, chunk_index / chunks_across);
+            let start = chunk_x * chunk_row_size;
+            let len = chunk_row_size.min(row_size - start);
+            if chunk.len() < (data_height as usize - 1) * chunk_row_size + len {
+                return Err(format_error(format!(
+                    "Chunk {} is truncated",
+                    plane_chunk_index
+                )));
+            }
+            for row in 0..data_height as usize {
+                let target = (plane * height + chunk_y * chunk_height + row) * row_size + start;
+                rows[target..target + len]
+                    .copy_from_slice(&chunk[row * chunk_row_size..row * chunk_row_size + len]);
+            }
+        }
+    }
+    if !row_bits.is_multiple_of(8) {
+        let unused_bits = 0xff >> (row_bits % 8);
+        for row in rows.chunks_exact_mut(row_size) {
+            row[row_size - 1] &= !unused_bits;
+        }
+    }
+    Ok(rows)
+}
+
+/// Reads raw bytes of the file, which the decoder only allows to read word by word.
+fn read_bytes<R: Read + Seek>(
+    decoder: &mut Decoder<R>,
+    offset: u64,
+    len: u64,
+) -> GeoTiffResult<Vec<u8>> {
+    // The first byte of the header is "I" for little-endian and "M" for big-endian files
+    decoder.goto_offset_u64(0)?;
+    let little_endian = decoder.read_byte()? == b'I';
+
+    decoder.goto_offset_u64(offset)?;
+    let mut bytes = Vec::with_capacity(len as usize);
+    for _ in 0..len / 8 {
+        let word = decoder.read_long8()?;
+        bytes.extend_from_slice(&if little_endian {
+            word.to_le_bytes()
+        } else {
+            word.to_be_bytes()
+        });
+    }
+    for _ in 0..len % 8 {
+        bytes.push(decoder.read_byte()?);
+    }
+    Ok(bytes)
+}
+
+fn decompress(compression: u16, bytes: Vec<u8>) -> GeoTiffResult<Vec<u8>> {
+    let mut decompressed = Vec::new();
+    match CompressionMethod::from_u16(compression) {
+        Some(CompressionMethod::None) => return Ok(bytes),
+        Some(CompressionMethod::LZW) => {
+            return weezl::decode::Decoder::with_tiff_size_switch(weezl::BitOrder::Msb, 8)
+                .decode(&bytes)
+                .map_err(|err| format_error(format!("LZW decompression failed: {}", err)))
+        }
+        Some(CompressionMethod::Deflate | CompressionMethod::OldDeflate) => {
+            flate2::read::ZlibDecoder::new(bytes.as_slice()).read_to_end(&mut decompressed)?;
+        }
+        Some(CompressionMethod::PackBits) => unpack_bits(&bytes, &mut decompressed),
+        #[cfg(feature = "zstd")]
+        _ if compression == ZSTD => {
+            zstd::stream::read::Decoder::new(bytes.as_slice())?.read_to_end(&mut decompressed)?;
+        }
+        _ => {
+            return Err(TiffError::UnsupportedError(
+                TiffUnsupportedError::UnsupportedCompressionMethod(
+                    CompressionMethod::from_u16_exhaustive(compression),
+                ),
+            )
+            .into())
+        }
+    }
+    Ok(decompressed)
+}
+
+/// Decodes a WebP image into pixels of `num_samples` samples, i.e. RGB or RGBA, adding opaque
+/// alpha samples to images without alpha channel.
+#[cfg(feature = "webp")]
+fn decompress_webp(bytes: &[u8], num_samples: usize) -> GeoTiffResult<Vec<u8>> {
+    let webp_error = |err| format_error(format!("WebP decompression failed: {}", err));
+    let mut decoder =
+        image_webp::WebPDecoder::new(std::io::Cursor::new(bytes)).map_err(webp_error)?;
+    let image_samples = if decoder.has_alpha() { 4 } else { 3 };
+    let (width, height) = decoder.dimensions();
+    let mut decompressed = vec![0; width as usize * height as usize * image_samples];
+    decoder.read_image(&mut decompressed).map_err(webp_error)?;
+    match (image_samples, num_samples) {
+        (4, 3) => Ok(decompressed
+            .chunks_exact(4)
+            .flat_map(|pixel| &pixel[..3])
+            .copied()
+            .collect()),
+        (3, 4) => Ok(decompressed
+            .chunks_exact(3)
+            .flat_map(|pixel| [pixel[0], pixel[1], pixel[2], u8::MAX])
+            .collect()),
+        (_, 3 | 4) => Ok(decompressed),
+        _ => Err(GeoTiffError::Unsupported(format!(
+            "WebP compression of {} bands is not supported",
+            num_samples
+        ))),
+    }
+}
+
+/// The sample type LERC blobs are decoded to, along with how the blobs are compressed.
+#[cfg(feature = "lerc")]
+struct LercDecoding {
+    sample_format: SampleFormat,
+    bits_per_sample: u16,
+    /// The compression of the blobs themselves, 0 for none, 1 for Deflate and 2 for ZSTD
+    additional_compression: u32,
+    little_endian: bool,
+}
+
+#[cfg(feature = "lerc")]
+impl LercDecoding {
+    fn new<R: Read + Seek>(decoder: &mut Decoder<R>) -> GeoTiffResult<Self> {
+        let sample_format = decoder
+            .find_tag_unsigned_vec::<u16>(Tag::SampleFormat)?
+            .and_then(|formats| formats.first().copied())
+            .map_or(SampleFormat::Uint, SampleFormat::from_u16_exhaustive);
+        let bits_per_sample = decoder
+            .find_tag_unsigned_vec::<u16>(Tag::BitsPerSample)?
+            .and_then(|bits| bits.first().copied())
+            .unwrap_or(1);
+        // The LERC version followed by the additional compression
+        let additional_compression = decoder
+            .find_tag_unsigned_vec::<u32>(Tag::Unknown(LERC_PARAMETERS))?
+            .and_then(|parameters| parameters.get(1).copied())
+            .unwrap_or(0);
+
+        // The first byte of the header is "I" for little-endian and "M" for big-endian files
+        decoder.goto_offset_u64(0)?;
+        let little_endian = decoder.read_byte()? == b'I';
+
+        Ok(Self {
+            sample_format,
+            bits_per_sample,
+            additional_compression,
+            little_endian,
+        })
+    }
+
+    /// Decodes a LERC blob into samples in the byte order of the file, as if the chunk was not
+    /// compressed.
+    ///
+    /// Samples of pixels LERC marks as invalid are NaN, or 0 for integer samples, like libtiff
+    /// decodes them.
+    fn decompress(&self, bytes: Vec<u8>) -> GeoTiffResult<Vec<u8>> {
+        let blob = match self.additional_compression {
+            0 => bytes,
+            1 => decompress(CompressionMethod::Deflate.to_u16(), bytes)?,
+            #[cfg(feature = "zstd")]
+            2 => decompress(ZSTD, bytes)?,
+            compression => {
+                return Err(GeoTiffError::Unsupported(format!(
+                    "LERC blobs with the additional compression {} are not supported",
+                    compression
+                )))
+            }
+        };
+        match (self.sample_format, self.bits_per_sample) {
+            (SampleFormat::Uint, 8) => self.decode(&blob, 0u8),
+            (SampleFormat::Uint, 16) => self.decode(&blob, 0u16),
+            (SampleFormat::Uint, 32) => self.decode(&blob, 0u32),
+            (SampleFormat::Int, 8) => self.decode(&blob, 0i8),
+            (SampleFormat::Int, 16) => self.decode(&blob, 0i16),
+            (SampleFormat::Int, 32) => self.decode(&blob, 0i32),
+            (SampleFormat::IEEEFP, 32) => self.decode(&blob, f32::NAN),
+            (SampleFormat::IEEEFP, 64) => self.decode(&blob, f64::NAN),
+            (sample_format, bits_per_sample) => Err(GeoTiffError::Unsupported(format!(
+                "LERC compression of {}-bit samples of format {:?} is not supported",
+                bits_per_sample, sample_format
+            ))),
+        }
+    }
+
+    /// Decodes a LERC blob into samples of type `T`, replacing those of invalid pixels by
+    /// `invalid`.
+    fn decode<T: lerc::LercDataType + num_traits::ToBytes>(
+        &self,
+        blob: &[u8],
+        invalid: T,
+    ) -> GeoTiffResult<Vec<u8>> {
+        let lerc_error = |err| format_error(format!("LERC decompression failed: {}", err));
+        let info = lerc::get_blob_info(blob).map_err(lerc_error)?;
+        let (values, mask) = lerc::decode_with_info::<T>(blob, &info).map_err(lerc_error)?;
+        // The samples of each pixel are interleaved, like in chunky TIFFs, and the masks, if not
+        // shared, are those of the bands
+        let depth = info.depth as usize;
+        let pixels = info.width as usize * info.height as usize;
+        let mut bytes = Vec::with_capacity(values.len() * std::mem::size_of::<T>());
+        for (index, value) in values.into_iter().enumerate() {
+            let value = match &mask {
+                Some(mask) => {
+                    let band = if info.masks > 1 {
+                        index / depth / pixels
+                    } else {
+                        0
+                    };
+                    if mask[band * pixels + index / depth % pixels] == 0 {
+                        invalid
+                    } else {
+                        value
+                    }
+                }
+                None => value,
+            };
+            if self.little_endian {
+                bytes.extend_from_slice(value.to_le_bytes().as_ref());
+            } else {
+                bytes.extend_from_slice(value.to_be_bytes().as_ref());
+            }
+        }
+        Ok(bytes)
+    }
+}
+
+/// Decodes PackBits run-length encoded bytes.
+fn unpack_bits(bytes: &[u8], output: &mut Vec<u8>) {
+    let mut index = 0;
+    while let Some(&header) = bytes.get(index) {
+        let header = header as i8;
+        index += 1;
+        if header >= 0 {
+            // A literal run of header + 1 bytes
+            let end = (index + header as usize + 1).min(bytes.len());
+            output.extend_from_slice(&bytes[index..end]);
+            index = end;
+        } else if header != -128 {
+            // A repetition of the next byte
+            if let Some(&byte) = bytes.get(index) {
+                output.extend(std::iter::repeat_n(byte, (1 - header as isize) as usize));
+            }
+            index += 1;
+        }
+    }
+}
+
+fn format_error(msg: impl ToString) -> GeoTiffError {
+    GeoTiffError::Tiff(TiffError::FormatError(TiffFormatError::Format(
+        msg.to_string(),
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unpack_bits() {
+        // The example of the TIFF 6.0 specification
+        let packed = [
+            0xfe, 0xaa, 0x02, 0x80, 0x00, 0x2a, 0xfd, 0xaa, 0x03, 0x80, 0x00, 0x2a, 0x22, 0xf7,
+            0xaa,
+        ];
+        let mut unpacked = Vec::new();
+        unpack_bits(&packed, &mut unpacked);
+        assert_eq!(
+            unpacked,
+            [
+                0xaa, 0xaa, 0xaa, 0x80, 0x00, 0x2a, 0xaa, 0xaa, 0xaa, 0xaa, 0x80, 0x00, 0x2a, 0x22,
+                0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa
+            ]
+        );
+    }
+}
@@ -0,0 +1,236 @@
+use std::collections::BTreeMap;
+use std::io::{Read, Seek, SeekFrom};
+
+use tiff::decoder::{ChunkType, Decoder};
+use tiff::tags::Tag;
+
+use crate::header::Header;
+use crate::reader::{REDUCED_RESOLUTION, TRANSPARENCY_MASK};
+use crate::GeoTiffResult;
+
+/// The size above which rasters should be tiled and have overviews, as checked by GDAL.
+const MAX_UNTILED_SIZE: usize = 512;
+
+/// The result of validating the structure of a Cloud Optimized GeoTIFF, see
+/// [`GeoTiff::validate_cog`](crate::GeoTiff::validate_cog).
+///
+/// The checks follow `validate_cloud_optimized_geotiff.py` of GDAL and `rio cogeo validate`:
+/// violations preventing efficient access by range requests are errors, others are warnings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CogReport {
+    /// The full-resolution image followed by its overviews, in the order of the IFD chain
+    pub images: Vec<CogImageReport>,
+    /// The key-value pairs of the structural metadata GDAL writes after the header, e.g.
+    /// `LAYOUT=IFDS_BEFORE_DATA`, or `None` if the file has no such ghost area
+    pub ghost_area: Option<BTreeMap<String, String>>,
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+/// The layout of an image or overview within the file, as reported by [`CogReport`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct CogImageReport {
+    pub raster_width: usize,
+    pub raster_height: usize,
+    /// The offset of the IFD in the file
+    pub ifd_offset: u64,
+    /// The width and height of the tiles, or `None` if the image is stored in strips
+    pub tile_dimensions: Option<[usize; 2]>,
+    /// The offset of the first strip or tile, or `None` if it is sparse
+    pub data_offset: Option<u64>,
+    /// Whether the strips or tiles are stored in ascending order, skipping sparse ones
+    pub offsets_sorted: bool,
+}
+
+impl CogReport {
+    /// Whether the file is a valid COG, i.e. without errors, while there may be warnings.
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    pub(crate) fn read<R: Read + Seek>(mut reader: R) -> GeoTiffResult<Self> {
+        let mut header = Header::read(&mut reader)?;
+        let ifd_offsets = header.read_ifd_offsets(&mut reader)?;
+        let ghost_area = header.read_ghost_area(&mut reader)?;
+
+        reader.seek(SeekFrom::Start(0))?;
+        let mut decoder = Decoder::new(reader)?;
+        let mut images = Vec::new();
+        for (ifd_index, ifd_offset) in ifd_offsets.into_iter().enumerate() {
+            if ifd_index > 0 {
+                decoder.next_image()?;
+            }
+            let subfile_type = decoder
+                .find_tag_unsigned::<u32>(Tag::NewSubfileType)?
+                .unwrap_or(0);
+            // Only the first image and its overviews make up the COG
+            if ifd_index > 0 {
+                if subfile_type & TRANSPARENCY_MASK != 0 {
+                    continue;
+                }
+                if subfile_type & REDUCED_RESOLUTION == 0 {
+                    break;
+                }
+            }
+            images.push(CogImageReport::read(&mut decoder, ifd_offset)?);
+        }
+
+        let mut report = CogReport {
+            images,
+            ghost_area,
+            errors: Vec::new(),
+            warnings: Vec::new(),
+        };
+        report.validate(header.len + header.ghost_area_len);
+        Ok(report)
+    }
+
+    /// Checks the layout of the images, given the offset the first IFD is expected at.
+    fn validate(&mut self, expected_ifd_offset: u64) {
+        let image = &self.images[0];
+        let is_large =
+            image.raster_width > MAX_UNTILED_SIZE || image.raster_height > MAX_UNTILED_SIZE;
+        if is_large && image.tile_dimensions.is_none() {
+            self.errors.push(format!(
+                "The raster of {}x{} pixels exceeds {} pixels, but is not tiled",
+                image.raster_width, image.raster_height, MAX_UNTILED_SIZE
+            ));
+        }
+        if is_large && self.images.len() == 1 {
+            self.warnings.push(format!(
+                "The raster of {}x{} pixels exceeds {} pixels, but has no overviews",
+                image.raster_width, image.raster_height, MAX_UNTILED_SIZE
+            ));
+        }
+        if image.ifd_offset != expected_ifd_offset {
+            self.errors.push(format!(
+                "The IFD of the image is at byte {} instead of directly following the header at \
+                 byte {}",
+                image.ifd_offset, expected_ifd_offset
+            ));
+        }
+
+        for (index, overview) in self.images.iter().enumerate().skip(1) {
+            if overview.tile_dimensions.is_none() {
+                self.errors
+                    .push(format!("Overview {} is not tiled", index - 1));
+            }
+            let previous = &self.images[index - 1];
+            if overview.ifd_offset < previous.ifd_offset {
+                self.errors.push(format!(
+                    "The IFD of overview {} at byte {} precedes the previous IFD at byte {}",
+                    index - 1,
+                    overview.ifd_offset,
+                    previous.ifd_offset
+                ));
+            }
+            // The data is ordered from the smallest overview to the full-resolution image
+            if let (Some(data_offset), Some(previous_data_offset)) =
+                (overview.data_offset, previous.data_offset)
+            {
+                if data_offset > previous_data_offset {
+                    self.errors.push(format!(
+                        "The data of overview {} follows the data of the {}",
+                        index - 1,
+                        match index {
+                            1 => "image".to_string(),
+                            _ => format!("overview {}", index - 2),
+                        }
+                    ));
+                }
+            }
+        }
+
+        for (index, image) in self.images.iter().enumerate() {
+            if !image.offsets_sorted {
+                self.errors.push(match index {
+                    0 => "The tiles of the image are not sorted by offset".to_string(),
+                    _ => format!(
+                        "The tiles of overview {} are not sorted by offset",
+                        index - 1
+                    ),
+                });
+            }
+        }
+        let last_ifd_offset = self.images.iter().map(|image| image.ifd_offset).max();
+        let first_data_offset = self
+            .images
+            .iter()
+            .filter_map(|image| image.data_offset)
+            .min();
+        if let (Some(last_ifd_offset), Some(first_data_offset)) =
+            (last_ifd_offset, first_data_offset)
+        {
+            if last_ifd_offset > first_data_offset {
+                self.warnings.push(
+                    "The IFDs do not all precede the image data, requiring further requests to \
+                     read them"
+                        .to_string(),
+                );
+            }
+        }
+    }
+}
+
+impl CogImageReport {
+    fn read<R: Read + Seek>(decoder: &mut Decoder<R>, ifd_offset: u64) -> GeoTiffResult<Self> {
+        let (width, height) = decoder.dimensions()?;
+        let (tile_dimensions, offsets_tag) = match decoder.get_chunk_type() {
+            ChunkType::Tile => {
+                let (tile_width, tile_height) = decoder.chunk_dimensions();
+                (
+                    Some([tile_width as usize, tile_height as usize]),
+                    Tag::TileOffsets,
+                )
+            }
+            ChunkType::Strip => (None, Tag::StripOffsets),
+        };
+        let offsets = decoder.get_tag_u64_vec(offsets_tag)?;
+        // Sparse strips or tiles have an offset of 0
+        let data_offset = offsets.first().copied().filter(|&offset| offset != 0);
+        let offsets_sorted = offsets.iter().filter(|&&offset| offset != 0).is_sorted();
+        Ok(CogImageReport {
+            raster_width: width as usize,
+            raster_height: height as usize,
+            ifd_offset,
+            tile_dimensions,
+            data_offset,
+            offsets_sorted,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::{CogWriter, GeoTiff, GeoTiffWriter};
+
+    #[test]
+    fn test_validate_cog() {
+        let data = vec![1u8; 600 * 40];
+        let mut bytes = Vec::new();
+        CogWriter::new(GeoTiffWriter::new().with_tiles(256, 256))
+            .write(&mut bytes, 600, 40, 1, &data)
+            .unwrap();
+        let report = GeoTiff::validate_cog(Cursor::new(bytes)).unwrap();
+        assert!(report.is_valid(), "{:?}", report.errors);
+        assert!(report.warnings.is_empty());
+        assert_eq!(report.ghost_area, None);
+        assert_eq!(report.images.len(), 3);
+        assert_eq!(report.images[0].ifd_offset, 8);
+        assert_eq!(report.images[2].tile_dimensions, Some([256, 256]));
+        assert!(report.images[2].data_offset < report.images[0].data_offset);
+
+        // Stored in strips and with the IFD after the data
+        let mut bytes = Vec::new();
+        GeoTiffWriter::new()
+            .write(&mut bytes, 600, 40, 1, &data)
+            .unwrap();
+        let report = GeoTiff::validate_cog(Cursor::new(bytes)).unwrap();
+        assert!(!report.is_valid());
+        assert_eq!(report.errors.len(), 2);
+        assert_eq!(report.warnings.len(), 2);
+        assert_eq!(report.images[0].tile_dimensions, None);
+    }
+}
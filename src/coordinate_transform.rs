@@ -0,0 +1,1838 @@
+use crate::extent::Extent;
+use crate::{GeoTiffError, GeoTiffResult, GeoTransform};
+
+use self::polynomial::Polynomial;
+pub use self::polynomial::PolynomialOrder;
+pub use self::rpc::RpcModel;
+use self::thin_plate_spline::ThinPlateSpline;
+
+mod polynomial;
+mod rpc;
+mod thin_plate_spline;
+mod tie_points;
+
+/// The transformation between raster space and model space.
+///
+/// GeoTIFF defines three ways of georeferencing a raster: a single tie point combined with a pixel
+/// scale, a full affine transformation matrix, or a set of tie points without further information
+/// about how to interpolate between them. In the latter case, coordinates are interpolated
+/// linearly over a Delaunay triangulation of the tie points, unless a polynomial or a thin plate
+/// spline is fitted to them instead.
+///
+/// Additionally, satellite imagery may come with an RPC sensor model, which maps raster space to
+/// geographic coordinates at a given height.
+///
+/// Raster coordinates always refer to the upper left corner of a pixel, i.e. the pixel `(0, 0)`
+/// covers raster space from `(0.0, 0.0)` to `(1.0, 1.0)`.
+#[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
+pub enum CoordinateTransform {
+    /// A 4x4 matrix in row-major order, as stored in the ModelTransformationTag
+    #[non_exhaustive]
+    AffineTransform {
+        transform: [f64; 16],
+        inverse_transform: [f64; 16],
+    },
+    /// A single tie point combined with the size of a pixel in model space, as stored in the
+    /// ModelTiepointTag and ModelPixelScaleTag
+    #[non_exhaustive]
+    TiePointAndPixelScale {
+        raster_point: [f64; 2],
+        model_point: [f64; 2],
+        pixel_scale: [f64; 2],
+    },
+    /// Linear interpolation over a Delaunay triangulation of multiple tie points
+    #[non_exhaustive]
+    TiePoints {
+        tie_points: Vec<TiePoint>,
+        triangles: Vec<[usize; 3]>,
+    },
+    /// Polynomials fitted to multiple tie points by least squares
+    #[non_exhaustive]
+    Polynomial {
+        tie_points: Vec<TiePoint>,
+        to_model: Polynomial,
+        to_raster: Polynomial,
+    },
+    /// Thin plate splines passing through multiple tie points
+    #[non_exhaustive]
+    ThinPlateSpline {
+        tie_points: Vec<TiePoint>,
+        to_model: ThinPlateSpline,
+        to_raster: ThinPlateSpline,
+    },
+    /// An RPC sensor model evaluated at a constant height
+    #[non_exhaustive]
+    Rpc {
+        model: RpcModel,
+        height: f64,
+        /// The offset from corner-based raster coordinates to the image coordinates of the model,
+        /// which refer to pixel centers
+        raster_offset: [f64; 2],
+    },
+}
+
+/// A single ModelTiePoint, mapping a raster coordinate to a model coordinate.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TiePoint {
+    pub raster_point: [f64; 2],
+    pub model_point: [f64; 2],
+}
+
+/// The residuals of fitting a transformation to a set of tie points.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransformResiduals {
+    /// The difference between the transformed raster point and the model point of each tie point
+    pub residuals: Vec<[f64; 2]>,
+    /// The root mean square of the residual distances
+    pub rms_error: f64,
+}
+
+impl TransformResiduals {
+    /// Computes the residuals of transforming the raster points of the tie points to model space.
+    fn new(tie_points: &[TiePoint], to_model: impl Fn([f64; 2]) -> [f64; 2]) -> Self {
+        let residuals = tie_points
+            .iter()
+            .map(|tie_point| {
+                let model_point = to_model(tie_point.raster_point);
+                [
+                    model_point[0] - tie_point.model_point[0],
+                    model_point[1] - tie_point.model_point[1],
+                ]
+            })
+            .collect::<Vec<_>>();
+        let rms_error = (residuals
+            .iter()
+            .map(|residual| residual[0] * residual[0] + residual[1] * residual[1])
+            .sum::<f64>()
+            / residuals.len() as f64)
+            .sqrt();
+        TransformResiduals {
+            residuals,
+            rms_error,
+        }
+    }
+}
+
+/// The values of the ModelPixelScaleTag, ModelTiepointTag and ModelTransformationTag.
+type TagData = (Option<Vec<f64>>, Option<Vec<f64>>, Option<Vec<f64>>);
+
+impl CoordinateTransform {
+    const TIE_POINT_TAG_LENGTH: usize = 6;
+    const PIXEL_SCALE_TAG_LENGTH: usize = 3;
+    const TRANSFORMATION_TAG_LENGTH: usize = 16;
+
+    pub(super) fn from_tag_data(
+        pixel_scale_data: Option<Vec<f64>>,
+        model_tie_points_data: Option<Vec<f64>>,
+        model_transformation_data: Option<Vec<f64>>,
+    ) -> GeoTiffResult<Self> {
+        if let Some(transformation_data) = model_transformation_data {
+            if pixel_scale_data.is_some() || model_tie_points_data.is_some() {
+                return Err(format_error(
+                    "ModelTransformationTag must not be combined with ModelTiepointTag or ModelPixelScaleTag",
+                ));
+            }
+            let transform = <[f64; Self::TRANSFORMATION_TAG_LENGTH]>::try_from(transformation_data)
+                .map_err(|data| {
+                    format_error(format!(
+                        "Expected {} values in ModelTransformationTag, found {}",
+                        Self::TRANSFORMATION_TAG_LENGTH,
+                        data.len()
+                    ))
+                })?;
+            return Self::from_model_transformation(transform);
+        }
+
+        let Some(tie_points_data) = model_tie_points_data else {
+            return Err(format_error(
+                "Georeferencing requires either ModelTransformationTag or ModelTiepointTag",
+            ));
+        };
+        if tie_points_data.is_empty() || tie_points_data.len() % Self::TIE_POINT_TAG_LENGTH != 0 {
+            return Err(format_error(format!(
+                "Expected a multiple of {} values in ModelTiepointTag, found {}",
+                Self::TIE_POINT_TAG_LENGTH,
+                tie_points_data.len()
+            )));
+        }
+        let tie_points = tie_points_data
+            .chunks_exact(Self::TIE_POINT_TAG_LENGTH)
+            .map(|chunk| TiePoint {
+                raster_point: [chunk[0], chunk[1]],
+                model_point: [chunk[3], chunk[4]],
+            })
+            .collect::<Vec<_>>();
+
+        match (tie_points.as_slice(), pixel_scale_data) {
+            ([tie_point], Some(pixel_scale_data)) => {
+                if pixel_scale_data.len() != Self::PIXEL_SCALE_TAG_LENGTH {
+                    return Err(format_error(format!(
+                        "Expected {} values in ModelPixelScaleTag, found {}",
+                        Self::PIXEL_SCALE_TAG_LENGTH,
+                        pixel_scale_data.len()
+                    )));
+                }
+                Self::from_tie_point_and_scale(
+                    tie_point.raster_point,
+                    tie_point.model_point,
+                    [pixel_scale_data[0], pixel_scale_data[1]],
+                )
+            }
+            ([_], None) => Err(format_error(
+                "A single ModelTiepointTag requires a ModelPixelScaleTag",
+            )),
+            _ => Self::from_tie_points(tie_points),
+        }
+    }
+
+    /// Encodes the transformation as the values of the ModelPixelScaleTag, ModelTiepointTag and
+    /// ModelTransformationTag, in the same order as accepted by
+    /// [`CoordinateTransform::from_tag_data`].
+    ///
+    /// Transformations fitted to multiple tie points are stored as the tie points only. RPC sensor
+    /// models cannot be stored in these tags.
+    pub(super) fn to_tag_data(&self) -> GeoTiffResult<TagData> {
+        let tie_points_data = |tie_points: &[TiePoint]| {
+            tie_points
+                .iter()
+                .flat_map(|tie_point| {
+                    let [column, row] = tie_point.raster_point;
+                    let [x, y] = tie_point.model_point;
+                    [column, row, 0.0, x, y, 0.0]
+                })
+                .collect::<Vec<_>>()
+        };
+        match self {
+            CoordinateTransform::AffineTransform { transform, .. } => {
+                Ok((None, None, Some(transform.to_vec())))
+            }
+            CoordinateTransform::TiePointAndPixelScale {
+                raster_point,
+                model_point,
+                pixel_scale,
+            } => Ok((
+                Some(vec![pixel_scale[0], pixel_scale[1], 0.0]),
+                Some(tie_points_data(&[TiePoint {
+                    raster_point: *raster_point,
+                    model_point: *model_point,
+                }])),
+                None,
+            )),
+            CoordinateTransform::TiePoints { tie_points, .. }
+            | CoordinateTransform::Polynomial { tie_points, .. }
+            | CoordinateTransform::ThinPlateSpline { tie_points, .. } => {
+                Ok((None, Some(tie_points_data(tie_points)), None))
+            }
+            CoordinateTransform::Rpc { .. } => Err(GeoTiffError::Unsupported(
+                "An RPC sensor model cannot be stored as GeoTIFF georeferencing tags".to_string(),
+            )),
+        }
+    }
+
+    /// Creates a transformation from a single tie point and the size of a pixel in model space.
+    ///
+    /// As in the ModelPixelScaleTag, the scale in Y direction is positive for north-up rasters,
+    /// since raster rows increase southwards.
+    pub fn from_tie_point_and_scale(
+        raster_point: [f64; 2],
+        model_point: [f64; 2],
+        pixel_scale: [f64; 2],
+    ) -> GeoTiffResult<Self> {
+        if pixel_scale[0] == 0.0 || pixel_scale[1] == 0.0 {
+            return Err(format_error(
+                "ModelPixelScaleTag must not contain zero scales",
+            ));
+        }
+        Ok(CoordinateTransform::TiePointAndPixelScale {
+            raster_point,
+            model_point,
+            pixel_scale,
+        })
+    }
+
+    /// Creates a transformation from the coefficients `[a, b, c, d, e, f]` of the affine mapping
+    /// `x = a * column + b * row + c` and `y = d * column + e * row + f`.
+    ///
+    /// Fails if the mapping is not invertible.
+    pub fn from_affine(affine: [f64; 6]) -> GeoTiffResult<Self> {
+        let [a, b, c, d, e, f] = affine;
+        Self::from_model_transformation([
+            a, b, 0.0, c, //
+            d, e, 0.0, f, //
+            0.0, 0.0, 0.0, 0.0, //
+            0.0, 0.0, 0.0, 1.0, //
+        ])
+    }
+
+    /// Creates a transformation from a GDAL geotransform `[origin_x, pixel_width, row_rotation,
+    /// origin_y, column_rotation, pixel_height]`.
+    ///
+    /// Fails if the mapping is not invertible.
+    pub fn from_geotransform(geotransform: [f64; 6]) -> GeoTiffResult<Self> {
+        let [c, a, b, f, d, e] = geotransform;
+        Self::from_affine([a, b, c, d, e, f])
+    }
+
+    /// Creates a transformation from a 4x4 matrix in row-major order, as stored in the
+    /// ModelTransformationTag.
+    ///
+    /// Fails if the matrix is singular.
+    pub fn from_model_transformation(transform: [f64; 16]) -> GeoTiffResult<Self> {
+        let inverse_transform = invert_matrix(&transform)
+            .ok_or_else(|| format_error("ModelTransformationTag contains a singular matrix"))?;
+        Ok(CoordinateTransform::AffineTransform {
+            transform,
+            inverse_transform,
+        })
+    }
+
+    /// Creates a transformation interpolating linearly between multiple tie points.
+    ///
+    /// Fails if there are less than three tie points or all of them are collinear.
+    pub fn from_tie_points(tie_points: Vec<TiePoint>) -> GeoTiffResult<Self> {
+        let triangles = tie_points::triangulate(&tie_points);
+        if triangles.is_empty() {
+            return Err(format_error(
+                "ModelTiepointTag requires at least three tie points that are not collinear",
+            ));
+        }
+        Ok(CoordinateTransform::TiePoints {
+            tie_points,
+            triangles,
+        })
+    }
+
+    /// Fits polynomials of the given order to the tie points by least squares, separately for
+    /// either direction, like GDAL's GCP based georeferencing does.
+    pub fn from_tie_points_polynomial(
+        tie_points: Vec<TiePoint>,
+        order: PolynomialOrder,
+    ) -> GeoTiffResult<(Self, TransformResiduals)> {
+        let raster_points = tie_points
+            .iter()
+            .map(|tie_point| tie_point.raster_point)
+            .collect::<Vec<_>>();
+        let model_points = tie_points
+            .iter()
+            .map(|tie_point| tie_point.model_point)
+            .collect::<Vec<_>>();
+        let fitting_error = || {
+            format_error(format!(
+                "Cannot fit a {:?} order polynomial to {} tie points, at least {} in general position are required",
+                order,
+                tie_points.len(),
+                order.num_coefficients()
+            ))
+        };
+        let to_model =
+            Polynomial::fit(order, &raster_points, &model_points).ok_or_else(fitting_error)?;
+        let to_raster =
+            Polynomial::fit(order, &model_points, &raster_points).ok_or_else(fitting_error)?;
+
+        let residuals = TransformResiduals::new(&tie_points, |point| to_model.apply(point));
+        Ok((
+            CoordinateTransform::Polynomial {
+                tie_points,
+                to_model,
+                to_raster,
+            },
+            residuals,
+        ))
+    }
+
+    /// Fits an affine transformation to the tie points of the transformation by least squares,
+    /// such that the raster can be treated as affine if the residuals are acceptable, e.g. to
+    /// compute its geotransform.
+    ///
+    /// Fails if the transformation is not derived from tie points, see
+    /// [`CoordinateTransform::tie_points`], or if they are collinear.
+    pub fn fit_affine(&self) -> GeoTiffResult<(Self, TransformResiduals)> {
+        let tie_points = self.tie_points().ok_or_else(|| {
+            GeoTiffError::InvalidArgument(
+                "Only transformations derived from tie points can be fitted".to_string(),
+            )
+        })?;
+        let raster_points = tie_points
+            .iter()
+            .map(|tie_point| tie_point.raster_point)
+            .collect::<Vec<_>>();
+        let model_points = tie_points
+            .iter()
+            .map(|tie_point| tie_point.model_point)
+            .collect::<Vec<_>>();
+        let to_model = Polynomial::fit(PolynomialOrder::First, &raster_points, &model_points)
+            .ok_or_else(|| {
+                format_error(format!(
+                    "Cannot fit an affine transformation to {} tie points, at least 3 that are not collinear are required",
+                    tie_points.len()
+                ))
+            })?;
+
+        // The coefficients of the affine mapping follow from the images of the unit vectors
+        let [c, f] = to_model.apply([0.0, 0.0]);
+        let [a, d] = to_model.apply([1.0, 0.0]);
+        let [b, e] = to_model.apply([0.0, 1.0]);
+        let transform = Self::from_affine([a - c, b - c, c, d - f, e - f, f])?;
+        let residuals =
+            TransformResiduals::new(tie_points, |point| transform.transform_to_model_f64(point));
+        Ok((transform, residuals))
+    }
+
+    /// Fits thin plate splines passing exactly through the tie points, separately for either
+    /// direction. This yields a smooth mapping for dense tie points, e.g. of scanline-georeferenced
+    /// imagery.
+    pub fn from_tie_points_thin_plate_spline(tie_points: Vec<TiePoint>) -> GeoTiffResult<Self> {
+        let raster_points = tie_points
+            .iter()
+            .map(|tie_point| tie_point.raster_point)
+            .collect::<Vec<_>>();
+        let model_points = tie_points
+            .iter()
+            .map(|tie_point| tie_point.model_point)
+            .collect::<Vec<_>>();
+        let fitting_error = || {
+            format_error(
+                "Thin plate splines require at least three tie points that are not collinear",
+            )
+        };
+        let to_model =
+            ThinPlateSpline::fit(&raster_points, &model_points).ok_or_else(fitting_error)?;
+        let to_raster =
+            ThinPlateSpline::fit(&model_points, &raster_points).ok_or_else(fitting_error)?;
+
+        Ok(CoordinateTransform::ThinPlateSpline {
+            tie_points,
+            to_model,
+            to_raster,
+        })
+    }
+
+    /// Uses the RPC model to transform between raster space and longitude/latitude at a constant
+    /// height above the ellipsoid.
+    pub fn from_rpc_model(model: RpcModel, height: f64) -> Self {
+        CoordinateTransform::Rpc {
+            model,
+            height,
+            raster_offset: [-0.5, -0.5],
+        }
+    }
+
+    /// The tie points the transformation was derived from, unless it is based on a single tie
+    /// point or an affine transformation.
+    pub fn tie_points(&self) -> Option<&[TiePoint]> {
+        match self {
+            CoordinateTransform::TiePoints { tie_points, .. }
+            | CoordinateTransform::Polynomial { tie_points, .. }
+            | CoordinateTransform::ThinPlateSpline { tie_points, .. } => Some(tie_points),
+            _ => None,
+        }
+    }
+
+    /// Moves the origin of raster space by the given offset, i.e. afterwards raster coordinate `c`
+    /// is transformed to where `c + offset` was transformed before.
+    ///
+    /// This is used to translate between the PixelIsArea and PixelIsPoint conventions.
+    pub(super) fn offset_raster_space(&mut self, offset: [f64; 2]) {
+        match self {
+            CoordinateTransform::AffineTransform {
+                transform,
+                inverse_transform,
+            } => {
+                transform[3] += transform[0] * offset[0] + transform[1] * offset[1];
+                transform[7] += transform[4] * offset[0] + transform[5] * offset[1];
+                inverse_transform[3] -= offset[0];
+                inverse_transform[7] -= offset[1];
+            }
+            CoordinateTransform::TiePointAndPixelScale { raster_point, .. } => {
+                raster_point[0] -= offset[0];
+                raster_point[1] -= offset[1];
+            }
+            CoordinateTransform::TiePoints { tie_points, .. } => {
+                for TiePoint { raster_point, .. } in tie_points {
+                    raster_point[0] -= offset[0];
+                    raster_point[1] -= offset[1];
+                }
+            }
+            CoordinateTransform::Polynomial {
+                tie_points,
+                to_model,
+                to_raster,
+            } => {
+                for TiePoint { raster_point, .. } in tie_points {
+                    raster_point[0] -= offset[0];
+                    raster_point[1] -= offset[1];
+                }
+                to_model.offset_input(offset);
+                to_raster.offset_output([-offset[0], -offset[1]]);
+            }
+            CoordinateTransform::ThinPlateSpline {
+                tie_points,
+                to_model,
+                to_raster,
+            } => {
+                for TiePoint { raster_point, .. } in tie_points {
+                    raster_point[0] -= offset[0];
+                    raster_point[1] -= offset[1];
+                }
+                to_model.offset_input(offset);
+                to_raster.offset_output([-offset[0], -offset[1]]);
+            }
+            CoordinateTransform::Rpc { raster_offset, .. } => {
+                raster_offset[0] += offset[0];
+                raster_offset[1] += offset[1];
+            }
+        }
+    }
+
+    /// Scales raster space by the given factors in X and Y direction, i.e. afterwards raster
+    /// coordinate `c` is transformed to where `c * factor` was transformed before.
+    ///
+    /// This is used to georeference reduced-resolution overviews of a raster.
+    pub(super) fn scale_raster_space(&mut self, factor: [f64; 2]) {
+        let scale_tie_points = |tie_points: &[TiePoint]| {
+            tie_points
+                .iter()
+                .map(|tie_point| TiePoint {
+                    raster_point: [
+                        tie_point.raster_point[0] / factor[0],
+                        tie_point.raster_point[1] / factor[1],
+                    ],
+                    model_point: tie_point.model_point,
+                })
+                .collect::<Vec<_>>()
+        };
+        match self {
+            CoordinateTransform::AffineTransform {
+                transform,
+                inverse_transform,
+            } => {
+                for row in 0..4 {
+                    transform[row * 4] *= factor[0];
+                    transform[row * 4 + 1] *= factor[1];
+                }
+                for (row, factor) in factor.iter().enumerate() {
+                    for value in &mut inverse_transform[row * 4..row * 4 + 4] {
+                        *value /= factor;
+                    }
+                }
+            }
+            CoordinateTransform::TiePointAndPixelScale {
+                raster_point,
+                pixel_scale,
+                ..
+            } => {
+                for axis in 0..2 {
+                    raster_point[axis] /= factor[axis];
+                    pixel_scale[axis] *= factor[axis];
+                }
+            }
+            CoordinateTransform::TiePoints { tie_points, .. } => {
+                // Linear interpolation within the triangles is not affected by scaling
+                *tie_points = scale_tie_points(tie_points);
+            }
+            CoordinateTransform::Polynomial {
+                tie_points,
+                to_model,
+                ..
+            } => {
+                let order = to_model.order();
+                let (transform, _) =
+                    Self::from_tie_points_polynomial(scale_tie_points(tie_points), order)
+                        .expect("scaling the tie points preserves the fit");
+                *self = transform;
+            }
+            CoordinateTransform::ThinPlateSpline { tie_points, .. } => {
+                *self = Self::from_tie_points_thin_plate_spline(scale_tie_points(tie_points))
+                    .expect("scaling the tie points preserves the fit");
+            }
+            CoordinateTransform::Rpc {
+                model,
+                raster_offset,
+                ..
+            } => {
+                model.scale_image_space(factor);
+                raster_offset[0] /= factor[0];
+                raster_offset[1] /= factor[1];
+            }
+        }
+    }
+
+    /// Scales model space by the given factor, i.e. afterwards raster coordinates are transformed
+    /// to `factor` times the model coordinates they were transformed to before.
+    ///
+    /// This is used to convert model coordinates between units. Transformations by an RPC sensor
+    /// model are not affected, as they always refer to WGS 84 longitude/latitude.
+    pub(super) fn scale_model_space(&mut self, factor: f64) {
+        match self {
+            CoordinateTransform::AffineTransform {
+                transform,
+                inverse_transform,
+            } => {
+                for value in &mut transform[0..8] {
+                    *value *= factor;
+                }
+                for row in 0..4 {
+                    inverse_transform[row * 4] /= factor;
+                    inverse_transform[row * 4 + 1] /= factor;
+                }
+            }
+            CoordinateTransform::TiePointAndPixelScale {
+                model_point,
+                pixel_scale,
+                ..
+            } => {
+                model_point[0] *= factor;
+                model_point[1] *= factor;
+                pixel_scale[0] *= factor;
+                pixel_scale[1] *= factor;
+            }
+            CoordinateTransform::TiePoints { tie_points, .. } => {
+                for TiePoint { model_point, .. } in tie_points {
+                    model_point[0] *= factor;
+                    model_point[1] *= factor;
+                }
+            }
+            CoordinateTransform::Polynomial {
+                tie_points,
+                to_model,
+                to_raster,
+            } => {
+                for TiePoint { model_point, .. } in tie_points {
+                    model_point[0] *= factor;
+                    model_point[1] *= factor;
+                }
+                to_model.scale_output(factor);
+                to_raster.scale_input(factor);
+            }
+            CoordinateTransform::ThinPlateSpline {
+                tie_points,
+                to_model,
+                to_raster,
+            } => {
+                for TiePoint { model_point, .. } in tie_points {
+                    model_point[0] *= factor;
+                    model_point[1] *= factor;
+                }
+                to_model.scale_output(factor);
+                to_raster.scale_input(factor);
+            }
+            CoordinateTransform::Rpc { .. } => {}
+        }
+    }
+
+    /// Transforms a raster coordinate (column, row) to model space.
+    pub fn transform_to_model(&self, coord: [usize; 2]) -> [f64; 2] {
+        self.transform_to_model_f64([coord[0] as f64, coord[1] as f64])
+    }
+
+    /// Like [`Self::transform_to_model`], but for a fractional raster coordinate, e.g.
+    /// `[0.5, 0.5]` for the center of the upper left pixel.
+    pub fn transform_to_model_f64(&self, coord: [f64; 2]) -> [f64; 2] {
+        match self {
+            CoordinateTransform::AffineTransform { transform, .. } => {
+                Self::transform_to_model_by_affine_transform(transform, coord)
+            }
+            CoordinateTransform::TiePointAndPixelScale {
+                raster_point,
+                model_point,
+                pixel_scale,
+            } => Self::transform_to_model_by_tie_point_and_pixel_scale(
+                raster_point,
+                model_point,
+                pixel_scale,
+                coord,
+            ),
+            CoordinateTransform::TiePoints {
+                tie_points,
+                triangles,
+            } => Self::transform_to_model_by_tie_points(tie_points, triangles, coord),
+            CoordinateTransform::Polynomial { to_model, .. } => to_model.apply(coord),
+            CoordinateTransform::ThinPlateSpline { to_model, .. } => to_model.apply(coord),
+            CoordinateTransform::Rpc {
+                model,
+                height,
+                raster_offset,
+            } => model.image_to_ground(
+                coord[0] + raster_offset[0],
+                coord[1] + raster_offset[1],
+                *height,
+            ),
+        }
+    }
+
+    /// Transforms a model coordinate to the raster coordinate (column, row) of the containing
+    /// pixel.
+    pub fn transform_to_raster(&self, coord: [f64; 2]) -> [usize; 2] {
+        let [x, y] = self.transform_to_raster_f64(coord);
+
+        // Negative positions lie outside the raster and saturate to zero
+        [x.floor() as usize, y.floor() as usize]
+    }
+
+    /// Like [`Self::transform_to_raster`], but retains the fractional position within the pixel.
+    pub fn transform_to_raster_f64(&self, coord: [f64; 2]) -> [f64; 2] {
+        match self {
+            CoordinateTransform::AffineTransform {
+                inverse_transform, ..
+            } => Self::transform_to_raster_by_affine_transform(inverse_transform, coord),
+            CoordinateTransform::TiePointAndPixelScale {
+                raster_point,
+                model_point,
+                pixel_scale,
+            } => Self::transform_to_raster_by_tie_point_and_pixel_scale(
+                raster_point,
+                model_point,
+                pixel_scale,
+                coord,
+            ),
+            CoordinateTransform::TiePoints {
+                tie_points,
+                triangles,
+            } => Self::transform_to_raster_by_tie_points(tie_points, triangles, coord),
+            CoordinateTransform::Polynomial { to_raster, .. } => to_raster.apply(coord),
+            CoordinateTransform::ThinPlateSpline { to_raster, .. } => to_raster.apply(coord),
+            CoordinateTransform::Rpc {
+                model,
+                height,
+                raster_offset,
+            } => {
+                let [sample, line] = model.ground_to_image(coord[0], coord[1], *height);
+                [sample - raster_offset[0], line - raster_offset[1]]
+            }
+        }
+    }
+
+    /// Transforms many raster coordinates to model space at once.
+    ///
+    /// The kind of transformation is only dispatched once, so that the common affine cases compile
+    /// to tight loops over the coordinates.
+    pub fn transform_to_model_many(&self, coords: &[[usize; 2]]) -> Vec<[f64; 2]> {
+        let to_f64 = |coord: &[usize; 2]| [coord[0] as f64, coord[1] as f64];
+        match self {
+            CoordinateTransform::AffineTransform { transform, .. } => coords
+                .iter()
+                .map(|coord| Self::transform_to_model_by_affine_transform(transform, to_f64(coord)))
+                .collect(),
+            CoordinateTransform::TiePointAndPixelScale {
+                raster_point,
+                model_point,
+                pixel_scale,
+            } => coords
+                .iter()
+                .map(|coord| {
+                    Self::transform_to_model_by_tie_point_and_pixel_scale(
+                        raster_point,
+                        model_point,
+                        pixel_scale,
+                        to_f64(coord),
+                    )
+                })
+                .collect(),
+            _ => coords
+                .iter()
+                .map(|coord| self.transform_to_model(*coord))
+                .collect(),
+        }
+    }
+
+    /// Transforms many model coordinates to the raster coordinates of the containing pixels at
+    /// once, see [`Self::transform_to_model_many`].
+    pub fn transform_to_raster_many(&self, coords: &[[f64; 2]]) -> Vec<[usize; 2]> {
+        self.transform_to_raster_f64_many(coords)
+            .into_iter()
+            .map(|[x, y]| [x.floor() as usize, y.floor() as usize])
+            .collect()
+    }
+
+    /// Transforms many model coordinates to fractional raster coordinates at once, see
+    /// [`Self::transform_to_model_many`].
+    pub fn transform_to_raster_f64_many(&self, coords: &[[f64; 2]]) -> Vec<[f64; 2]> {
+        match self {
+            CoordinateTransform::AffineTransform {
+                inverse_transform, ..
+            } => coords
+                .iter()
+                .map(|coord| {
+                    Self::transform_to_raster_by_affine_transform(inverse_transform, *coord)
+                })
+                .collect(),
+            CoordinateTransform::TiePointAndPixelScale {
+                raster_point,
+                model_point,
+                pixel_scale,
+            } => coords
+                .iter()
+                .map(|coord| {
+                    Self::transform_to_raster_by_tie_point_and_pixel_scale(
+                        raster_point,
+                        model_point,
+                        pixel_scale,
+                        *coord,
+                    )
+                })
+                .collect(),
+            _ => coords
+                .iter()
+                .map(|coord| self.transform_to_raster_f64(*coord))
+                .collect(),
+        }
+    }
+
+    /// The size of a pixel in model units in X and Y direction, i.e. the ground sample distance.
+    ///
+    /// For rotated or sheared affine transformations, this is the length of the step in model space
+    /// when moving by one column respectively one row. Returns `None` for transformations that are
+    /// not affine, since their resolution varies across the raster.
+    pub fn resolution(&self) -> Option<[f64; 2]> {
+        match self {
+            CoordinateTransform::AffineTransform { transform, .. } => Some([
+                transform[0].hypot(transform[4]),
+                transform[1].hypot(transform[5]),
+            ]),
+            CoordinateTransform::TiePointAndPixelScale { pixel_scale, .. } => {
+                Some([pixel_scale[0].abs(), pixel_scale[1].abs()])
+            }
+            _ => None,
+        }
+    }
+
+    /// The transformation as GDAL geotransform `[origin_x, pixel_width, row_rotation, origin_y,
+    /// column_rotation, pixel_height]`, see [`CoordinateTransform::from_geotransform`].
+    ///
+    /// Returns `None` for transformations that are not affine.
+    pub fn geotransform(&self) -> Option<[f64; 6]> {
+        match self {
+            CoordinateTransform::AffineTransform { transform, .. } => Some([
+                transform[3],
+                transform[0],
+                transform[1],
+                transform[7],
+                transform[4],
+                transform[5],
+            ]),
+            CoordinateTransform::TiePointAndPixelScale {
+                raster_point,
+                model_point,
+                pixel_scale,
+            } => Some([
+                model_point[0] - raster_point[0] * pixel_scale[0],
+                pixel_scale[0],
+                0.0,
+                model_point[1] + raster_point[1] * pixel_scale[1],
+                0.0,
+                -pixel_scale[1],
+            ]),
+            _ => None,
+        }
+    }
+
+    /// Whether the rows and columns of the raster are not aligned with the axes of model space,
+    /// i.e. the transformation is rotated or sheared, or not affine at all.
+    ///
+    /// Code assuming axis-aligned pixels, like computing the extent from the origin and the pixel
+    /// size, fails for such rasters.
+    pub fn has_rotation(&self) -> bool {
+        self.geotransform()
+            .is_none_or(|geotransform| GeoTransform(geotransform).has_rotation())
+    }
+
+    /// Whether the raster is north-up, i.e. the transformation is affine without rotation, with
+    /// columns increasing towards east and rows towards south, as many tools assume.
+    pub fn is_north_up(&self) -> bool {
+        self.geotransform()
+            .is_some_and(|geotransform| GeoTransform(geotransform).is_north_up())
+    }
+
+    /// The bounding box in model space of a raster with the given dimensions.
+    ///
+    /// Affine transformations (including rotated ones) map the raster onto a parallelogram, whose
+    /// extent is given by its corners. Other transformations may bend the edges of the raster, so
+    /// these are sampled along all edges.
+    pub fn model_extent(&self, raster_width: usize, raster_height: usize) -> Extent {
+        const EDGE_SAMPLES: usize = 20;
+
+        let is_affine = matches!(
+            self,
+            CoordinateTransform::AffineTransform { .. }
+                | CoordinateTransform::TiePointAndPixelScale { .. }
+        );
+        let raster_points = if is_affine {
+            vec![
+                [0, 0],
+                [raster_width, 0],
+                [0, raster_height],
+                [raster_width, raster_height],
+            ]
+        } else {
+            let x_step = (raster_width / EDGE_SAMPLES).max(1);
+            let y_step = (raster_height / EDGE_SAMPLES).max(1);
+            let xs = (0..raster_width).step_by(x_step).chain([raster_width]);
+            let ys = (0..raster_height).step_by(y_step).chain([raster_height]);
+            xs.clone()
+                .flat_map(|x| [[x, 0], [x, raster_height]])
+                .chain(ys.flat_map(|y| [[0, y], [raster_width, y]]))
+                .collect()
+        };
+
+        Extent::from_points(self.transform_to_model_many(&raster_points))
+            .expect("raster points are not empty")
+    }
+
+    /// Applies the matrix to the raster coordinate `(i, j, 0, 1)`, as specified for the
+    /// ModelTransformationTag.
+    fn transform_to_model_by_affine_transform(transform: &[f64; 16], coord: [f64; 2]) -> [f64; 2] {
+        apply_matrix(transform, coord)
+    }
+
+    /// Applies the inverse matrix to the model coordinate `(x, y, 0, 1)`.
+    fn transform_to_raster_by_affine_transform(
+        inverse_transform: &[f64; 16],
+        coord: [f64; 2],
+    ) -> [f64; 2] {
+        apply_matrix(inverse_transform, coord)
+    }
+
+    /// Model space is a right-handed coordinate system with Y pointing north, whereas raster space
+    /// has Y pointing down, hence the scale in Y direction is applied with a negative sign.
+    fn transform_to_model_by_tie_point_and_pixel_scale(
+        raster_point: &[f64; 2],
+        model_point: &[f64; 2],
+        pixel_scale: &[f64; 2],
+        coord: [f64; 2],
+    ) -> [f64; 2] {
+        [
+            (coord[0] - raster_point[0]) * pixel_scale[0] + model_point[0],
+            (coord[1] - raster_point[1]) * -pixel_scale[1] + model_point[1],
+        ]
+    }
+
+    fn transform_to_raster_by_tie_point_and_pixel_scale(
+        raster_point: &[f64; 2],
+        model_point: &[f64; 2],
+        pixel_scale: &[f64; 2],
+        coord: [f64; 2],
+    ) -> [f64; 2] {
+        [
+            (coord[0] - model_point[0]) / pixel_scale[0] + raster_point[0],
+            (coord[1] - model_point[1]) / -pixel_scale[1] + raster_point[1],
+        ]
+    }
+
+    fn transform_to_model_by_tie_points(
+        tie_points: &[TiePoint],
+        triangles: &[[usize; 3]],
+        coord: [f64; 2],
+    ) -> [f64; 2] {
+        tie_points::interpolate(
+            tie_points,
+            triangles,
+            coord,
+            |tie_point| tie_point.raster_point,
+            |tie_point| tie_point.model_point,
+        )
+    }
+
+    /// Uses the triangulation of raster space for model space as well, which is exact as long as
+    /// the tie points do not fold model space onto itself.
+    fn transform_to_raster_by_tie_points(
+        tie_points: &[TiePoint],
+        triangles: &[[usize; 3]],
+        coord: [f64; 2],
+    ) -> [f64; 2] {
+        tie_points::interpolate(
+            tie_points,
+            triangles,
+            coord,
+            |tie_point| tie_point.model_point,
+            |tie_point| tie_point.raster_point,
+        )
+    }
+}
+
+/// Applies a row-major 4x4 matrix to the homogeneous point `(x, y, 0, 1)`.
+fn apply_matrix(matrix: &[f64; 16], [x, y]: [f64; 2]) -> [f64; 2] {
+    let w = matrix[12] * x + matrix[13] * y + matrix[15];
+    [
+        (matrix[0] * x + matrix[1] * y + matrix[3]) / w,
+        (matrix[4] * x + matrix[5] * y + matrix[7]) / w,
+    ]
+}
+
+/// Inverts a row-major 4x4 matrix by Gauss-Jordan elimination with partial pivoting.
+///
+/// Most files leave the Z row of the ModelTransformationTag empty since raster space is
+/// two-dimensional. In that case Z is treated as the identity so that the matrix remains
+/// invertible. Returns `None` if the matrix is singular.
+fn invert_matrix(matrix: &[f64; 16]) -> Option<[f64; 16]> {
+    let mut matrix = *matrix;
+    if matrix[8..12].iter().all(|value| *value == 0.0) {
+        matrix[10] = 1.0;
+    }
+
+    let mut inverse = [0.0; 16];
+    for i in 0..4 {
+        inverse[i * 4 + i] = 1.0;
+    }
+
+    for column in 0..4 {
+        let pivot_row = (column..4)
+            .max_by(|a, b| {
+                matrix[a * 4 + column]
+                    .abs()
+                    .total_cmp(&matrix[b * 4 + column].abs())
+            })
+            .unwrap();
+        let pivot = matrix[pivot_row * 4 + column];
+        if pivot.abs() < f64::EPSILON * matrix.iter().fold(1.0f64, |max, v| max.max(v.abs())) {
+            return None;
+        }
+
+        for k in 0..4 {
+            matrix.swap(column * 4 + k, pivot_row * 4 + k);
+            inverse.swap(column * 4 + k, pivot_row * 4 + k);
+        }
+        for k in 0..4 {
+            matrix[column * 4 + k] /= pivot;
+            inverse[column * 4 + k] /= pivot;
+        }
+        for row in (0..4).filter(|row| *row != column) {
+            let factor = matrix[row * 4 + column];
+            for k in 0..4 {
+                matrix[row * 4 + k] -= factor * matrix[column * 4 + k];
+                inverse[row * 4 + k] -= factor * inverse[column * 4 + k];
+            }
+        }
+    }
+
+    Some(inverse)
+}
+
+/// Solves the square system `matrix * x = b` for two right hand sides by Gaussian elimination with
+/// partial pivoting. Returns `None` if the matrix is singular.
+fn solve_linear_system(
+    mut matrix: Vec<f64>,
+    mut right_hand_sides: [Vec<f64>; 2],
+) -> Option<[Vec<f64>; 2]> {
+    let n = right_hand_sides[0].len();
+    let tolerance = f64::EPSILON
+        * n as f64
+        * matrix
+            .iter()
+            .fold(0.0f64, |max, value| max.max(value.abs()));
+
+    for column in 0..n {
+        let pivot_row = (column..n)
+            .max_by(|a, b| {
+                matrix[a * n + column]
+                    .abs()
+                    .total_cmp(&matrix[b * n + column].abs())
+            })
+            .unwrap();
+        if matrix[pivot_row * n + column].abs() <= tolerance {
+            return None;
+        }
+        for k in 0..n {
+            matrix.swap(column * n + k, pivot_row * n + k);
+        }
+        for b in &mut right_hand_sides {
+            b.swap(column, pivot_row);
+        }
+
+        for row in column + 1..n {
+            let factor = matrix[row * n + column] / matrix[column * n + column];
+            for k in column..n {
+                matrix[row * n + k] -= factor * matrix[column * n + k];
+            }
+            for b in &mut right_hand_sides {
+                b[row] -= factor * b[column];
+            }
+        }
+    }
+
+    for b in &mut right_hand_sides {
+        for row in (0..n).rev() {
+            let sum = (row + 1..n).fold(b[row], |sum, k| sum - matrix[row * n + k] * b[k]);
+            b[row] = sum / matrix[row * n + row];
+        }
+    }
+
+    Some(right_hand_sides)
+}
+
+fn format_error(message: impl Into<String>) -> GeoTiffError {
+    GeoTiffError::GeoKey(message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Georeferencing of `resources/zh_dem_25.tif`
+    fn zh_dem_25_transform() -> CoordinateTransform {
+        CoordinateTransform::from_tag_data(
+            Some(vec![25.0, 25.0, 0.0]),
+            Some(vec![0.0, 0.0, 0.0, 677562.5, 253012.5, 0.0]),
+            None,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_transform_to_model_by_tie_point_and_pixel_scale() {
+        let transform = zh_dem_25_transform();
+
+        // Corner coordinates as reported by `gdalinfo resources/zh_dem_25.tif`
+        assert_eq!(transform.transform_to_model([0, 0]), [677562.5, 253012.5]);
+        assert_eq!(transform.transform_to_model([0, 366]), [677562.5, 243862.5]);
+        assert_eq!(transform.transform_to_model([399, 0]), [687537.5, 253012.5]);
+        assert_eq!(
+            transform.transform_to_model([399, 366]),
+            [687537.5, 243862.5]
+        );
+        assert_eq!(
+            transform.transform_to_model([199, 183]),
+            [682537.5, 248437.5]
+        );
+    }
+
+    #[test]
+    fn test_to_tag_data() {
+        assert_eq!(
+            zh_dem_25_transform().to_tag_data().unwrap(),
+            (
+                Some(vec![25.0, 25.0, 0.0]),
+                Some(vec![0.0, 0.0, 0.0, 677562.5, 253012.5, 0.0]),
+                None
+            )
+        );
+
+        let transform =
+            CoordinateTransform::from_affine([2.0, 1.0, 10.0, 0.0, -2.0, 20.0]).unwrap();
+        let (_, _, transformation_data) = transform.to_tag_data().unwrap();
+        assert_eq!(
+            CoordinateTransform::from_tag_data(None, None, transformation_data)
+                .unwrap()
+                .transform_to_model([3, 4]),
+            [20.0, 12.0]
+        );
+
+        let model = RpcModel::from_tag_data(vec![0.0; 92]).unwrap();
+        assert!(CoordinateTransform::from_rpc_model(model, 0.0)
+            .to_tag_data()
+            .is_err());
+    }
+
+    #[test]
+    fn test_transform_to_model_with_offset_tie_point() {
+        let transform = CoordinateTransform::from_tag_data(
+            Some(vec![0.5, 2.0, 0.0]),
+            Some(vec![10.0, 20.0, 0.0, 100.0, 200.0, 0.0]),
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(transform.transform_to_model([10, 20]), [100.0, 200.0]);
+        assert_eq!(transform.transform_to_model([0, 0]), [95.0, 240.0]);
+        assert_eq!(transform.transform_to_model([12, 25]), [101.0, 190.0]);
+    }
+
+    #[test]
+    fn test_transform_to_raster_by_tie_point_and_pixel_scale() {
+        let transform = zh_dem_25_transform();
+
+        assert_eq!(transform.transform_to_raster([677562.5, 253012.5]), [0, 0]);
+        assert_eq!(
+            transform.transform_to_raster([682537.5, 248437.5]),
+            [199, 183]
+        );
+        assert_eq!(
+            transform.transform_to_raster([682549.9, 248425.1]),
+            [199, 183]
+        );
+        assert_eq!(
+            transform.transform_to_raster([687537.5, 243862.5]),
+            [399, 366]
+        );
+    }
+
+    #[test]
+    fn test_transform_to_raster_f64_by_tie_point_and_pixel_scale() {
+        let transform = zh_dem_25_transform();
+
+        assert_eq!(
+            transform.transform_to_raster_f64([677562.5, 253012.5]),
+            [0.0, 0.0]
+        );
+        assert_eq!(
+            transform.transform_to_raster_f64([682550.0, 248425.0]),
+            [199.5, 183.5]
+        );
+        assert_eq!(
+            transform.transform_to_raster_f64([677550.0, 253025.0]),
+            [-0.5, -0.5]
+        );
+    }
+
+    #[test]
+    fn test_offset_raster_space() {
+        let mut transform = zh_dem_25_transform();
+        transform.offset_raster_space([-0.5, -0.5]);
+
+        assert_eq!(transform.transform_to_model([0, 0]), [677550.0, 253025.0]);
+        assert_eq!(
+            transform.transform_to_raster_f64([677562.5, 253012.5]),
+            [0.5, 0.5]
+        );
+
+        let mut transform = CoordinateTransform::from_tag_data(
+            None,
+            None,
+            Some(vec![
+                25.0, 0.0, 0.0, 677562.5, //
+                0.0, -25.0, 0.0, 253012.5, //
+                0.0, 0.0, 0.0, 0.0, //
+                0.0, 0.0, 0.0, 1.0, //
+            ]),
+        )
+        .unwrap();
+        transform.offset_raster_space([-0.5, -0.5]);
+
+        assert_eq!(transform.transform_to_model([0, 0]), [677550.0, 253025.0]);
+    }
+
+    fn assert_coord_eq(actual: [f64; 2], expected: [f64; 2]) {
+        assert!(
+            (actual[0] - expected[0]).abs() < 1e-6 && (actual[1] - expected[1]).abs() < 1e-6,
+            "{actual:?} != {expected:?}"
+        );
+    }
+
+    #[test]
+    fn test_scale_raster_space() {
+        let mut transform = zh_dem_25_transform();
+        transform.scale_raster_space([2.0, 4.0]);
+
+        assert_coord_eq(transform.transform_to_model([1, 1]), [677612.5, 252912.5]);
+        assert_coord_eq(
+            transform.transform_to_raster_f64([677612.5, 252912.5]),
+            [1.0, 1.0],
+        );
+
+        let mut transform =
+            CoordinateTransform::from_geotransform([8.0, 0.001, 0.0, 47.0, 0.0, -0.001]).unwrap();
+        transform.scale_raster_space([2.0, 2.0]);
+
+        assert_eq!(transform.resolution(), Some([0.002, 0.002]));
+        assert_coord_eq(transform.transform_to_model([500, 500]), [9.0, 46.0]);
+        assert_coord_eq(
+            transform.transform_to_raster_f64([9.0, 46.0]),
+            [500.0, 500.0],
+        );
+
+        let (mut transform, _) = CoordinateTransform::from_tie_points_polynomial(
+            quadratic_tie_points(),
+            PolynomialOrder::Second,
+        )
+        .unwrap();
+        let expected = transform.transform_to_model([60, 10]);
+        transform.scale_raster_space([2.0, 5.0]);
+
+        assert_coord_eq(transform.transform_to_model([30, 2]), expected);
+    }
+
+    #[test]
+    fn test_scale_model_space() {
+        let mut transform = zh_dem_25_transform();
+        transform.scale_model_space(0.001);
+
+        assert_coord_eq(transform.transform_to_model([0, 0]), [677.5625, 253.0125]);
+        assert_coord_eq(
+            transform.transform_to_raster_f64([677.5875, 252.9875]),
+            [1.0, 1.0],
+        );
+
+        let mut transform =
+            CoordinateTransform::from_geotransform([8.0, 0.001, 0.0, 47.0, 0.0, -0.001]).unwrap();
+        transform.scale_model_space(2.0);
+
+        assert_coord_eq(transform.transform_to_model([1000, 1000]), [18.0, 92.0]);
+        assert_coord_eq(
+            transform.transform_to_raster_f64([18.0, 92.0]),
+            [1000.0, 1000.0],
+        );
+
+        let (mut transform, _) = CoordinateTransform::from_tie_points_polynomial(
+            quadratic_tie_points(),
+            PolynomialOrder::Second,
+        )
+        .unwrap();
+        transform.scale_model_space(0.5);
+
+        assert_coord_eq(transform.transform_to_model([60, 10]), [300303.0, 99986.0]);
+        let [x, y] = transform.transform_to_raster_f64([300303.0, 99986.0]);
+        assert!((x - 60.0).abs() < 0.5 && (y - 10.0).abs() < 0.5);
+    }
+
+    #[test]
+    fn test_transform_by_affine_transform() {
+        // A north-up raster as written by GDAL, leaving the Z row empty
+        let transform = CoordinateTransform::from_tag_data(
+            None,
+            None,
+            Some(vec![
+                25.0, 0.0, 0.0, 677562.5, //
+                0.0, -25.0, 0.0, 253012.5, //
+                0.0, 0.0, 0.0, 0.0, //
+                0.0, 0.0, 0.0, 1.0, //
+            ]),
+        )
+        .unwrap();
+
+        assert_eq!(
+            transform.transform_to_model([399, 366]),
+            [687537.5, 243862.5]
+        );
+        assert_coord_eq(
+            transform.transform_to_raster_f64([682550.0, 248425.0]),
+            [199.5, 183.5],
+        );
+    }
+
+    #[test]
+    fn test_transform_by_rotated_affine_transform() {
+        // GDAL geotransform (1000, 8.660254, -5, 2000, -5, -8.660254), i.e. rotated by 30 degrees
+        let transform = CoordinateTransform::from_tag_data(
+            None,
+            None,
+            Some(vec![
+                8.660254, -5.0, 0.0, 1000.0, //
+                -5.0, -8.660254, 0.0, 2000.0, //
+                0.0, 0.0, 1.0, 0.0, //
+                0.0, 0.0, 0.0, 1.0, //
+            ]),
+        )
+        .unwrap();
+
+        assert_coord_eq(
+            transform.transform_to_model([10, 20]),
+            [986.60254, 1776.79492],
+        );
+        assert_coord_eq(
+            transform.transform_to_raster_f64([986.60254, 1776.79492]),
+            [10.0, 20.0],
+        );
+        assert_coord_eq(
+            transform.transform_to_raster_f64(transform.transform_to_model([123, 456])),
+            [123.0, 456.0],
+        );
+    }
+
+    #[test]
+    fn test_has_rotation() {
+        let rotated = CoordinateTransform::from_geotransform([
+            1000.0, 8.660254, -5.0, 2000.0, -5.0, -8.660254,
+        ])
+        .unwrap();
+        assert!(rotated.has_rotation());
+        assert!(!rotated.is_north_up());
+
+        let north_up = CoordinateTransform::from_tie_point_and_scale(
+            [0.0, 0.0],
+            [1000.0, 2000.0],
+            [10.0, 10.0],
+        )
+        .unwrap();
+        assert!(!north_up.has_rotation());
+        assert!(north_up.is_north_up());
+
+        let tie_points = [[0.0, 0.0], [10.0, 0.0], [0.0, 10.0], [10.0, 10.0]]
+            .into_iter()
+            .map(|point| TiePoint {
+                raster_point: point,
+                model_point: [point[0] + point[1] * 0.1, -point[1]],
+            })
+            .collect();
+        let tie_points = CoordinateTransform::from_tie_points(tie_points).unwrap();
+        assert!(tie_points.has_rotation());
+        assert!(!tie_points.is_north_up());
+    }
+
+    #[test]
+    fn test_singular_affine_transform() {
+        // A sheared matrix collapsing raster space onto a line
+        let result = CoordinateTransform::from_tag_data(
+            None,
+            None,
+            Some(vec![
+                1.0, 2.0, 0.0, 0.0, //
+                2.0, 4.0, 0.0, 0.0, //
+                0.0, 0.0, 0.0, 0.0, //
+                0.0, 0.0, 0.0, 1.0, //
+            ]),
+        );
+
+        assert!(result.is_err());
+    }
+
+    /// A 100x100 raster georeferenced by its corners and center, with the center shifted so that
+    /// the mapping is not affine.
+    fn tie_points_transform() -> CoordinateTransform {
+        CoordinateTransform::from_tag_data(
+            None,
+            Some(vec![
+                0.0, 0.0, 0.0, 1000.0, 2000.0, 0.0, //
+                100.0, 0.0, 0.0, 1100.0, 2000.0, 0.0, //
+                0.0, 100.0, 0.0, 1000.0, 1900.0, 0.0, //
+                100.0, 100.0, 0.0, 1100.0, 1900.0, 0.0, //
+                50.0, 50.0, 0.0, 1060.0, 1960.0, 0.0, //
+            ]),
+            None,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_transform_to_model_by_tie_points() {
+        let transform = tie_points_transform();
+
+        assert_coord_eq(transform.transform_to_model([0, 0]), [1000.0, 2000.0]);
+        assert_coord_eq(transform.transform_to_model([100, 100]), [1100.0, 1900.0]);
+        assert_coord_eq(transform.transform_to_model([50, 50]), [1060.0, 1960.0]);
+        assert_coord_eq(transform.transform_to_model([25, 25]), [1030.0, 1980.0]);
+        assert_coord_eq(transform.transform_to_model([50, 0]), [1050.0, 2000.0]);
+
+        // Extrapolation beyond the convex hull of the tie points
+        assert_coord_eq(transform.transform_to_model([50, 100]), [1050.0, 1900.0]);
+        assert_coord_eq(transform.transform_to_model([110, 50]), [1108.0, 1948.0]);
+    }
+
+    #[test]
+    fn test_transform_to_raster_by_tie_points() {
+        let transform = tie_points_transform();
+
+        assert_coord_eq(
+            transform.transform_to_raster_f64([1000.0, 2000.0]),
+            [0.0, 0.0],
+        );
+        assert_coord_eq(
+            transform.transform_to_raster_f64([1060.0, 1960.0]),
+            [50.0, 50.0],
+        );
+        assert_coord_eq(
+            transform.transform_to_raster_f64(transform.transform_to_model([20, 70])),
+            [20.0, 70.0],
+        );
+        assert_coord_eq(
+            transform.transform_to_raster_f64(transform.transform_to_model([120, 30])),
+            [120.0, 30.0],
+        );
+    }
+
+    #[test]
+    fn test_collinear_tie_points() {
+        let result = CoordinateTransform::from_tag_data(
+            None,
+            Some(vec![
+                0.0, 0.0, 0.0, 1000.0, 2000.0, 0.0, //
+                50.0, 50.0, 0.0, 1050.0, 1950.0, 0.0, //
+                100.0, 100.0, 0.0, 1100.0, 1900.0, 0.0, //
+            ]),
+            None,
+        );
+
+        assert!(result.is_err());
+    }
+
+    /// Tie points on a 5x5 grid following a quadratic warp of model space.
+    fn quadratic_tie_points() -> Vec<TiePoint> {
+        (0..5)
+            .flat_map(|i| (0..5).map(move |j| [i as f64 * 25.0, j as f64 * 25.0]))
+            .map(|[x, y]| TiePoint {
+                raster_point: [x, y],
+                model_point: [
+                    600000.0 + 10.0 * x + 0.01 * x * y,
+                    200000.0 - 10.0 * y + 0.02 * x * x,
+                ],
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_polynomial_transform() {
+        let (transform, residuals) = CoordinateTransform::from_tie_points_polynomial(
+            quadratic_tie_points(),
+            PolynomialOrder::Second,
+        )
+        .unwrap();
+
+        assert!(residuals.rms_error < 1e-6);
+        assert_eq!(residuals.residuals.len(), 25);
+        assert_coord_eq(transform.transform_to_model([0, 0]), [600000.0, 200000.0]);
+        assert_coord_eq(transform.transform_to_model([60, 10]), [600606.0, 199972.0]);
+
+        // The inverse of a quadratic warp is only approximated by the fitted polynomial
+        let [x, y] = transform.transform_to_raster_f64([600606.0, 199972.0]);
+        assert!((x - 60.0).abs() < 0.5 && (y - 10.0).abs() < 0.5);
+    }
+
+    #[test]
+    fn test_polynomial_transform_residuals() {
+        let (_, first_order_residuals) = CoordinateTransform::from_tie_points_polynomial(
+            quadratic_tie_points(),
+            PolynomialOrder::First,
+        )
+        .unwrap();
+        let (_, third_order_residuals) = CoordinateTransform::from_tie_points_polynomial(
+            quadratic_tie_points(),
+            PolynomialOrder::Third,
+        )
+        .unwrap();
+
+        assert!(first_order_residuals.rms_error > 1.0);
+        assert!(third_order_residuals.rms_error < 1e-6);
+    }
+
+    #[test]
+    fn test_fit_affine() {
+        // Nearly affine tie points, slightly off by alternating errors
+        let tie_points = (0..25)
+            .map(|index| {
+                let [x, y] = [(index % 5) as f64 * 25.0, (index / 5) as f64 * 25.0];
+                let error = if index % 2 == 0 { 0.1 } else { -0.1 };
+                TiePoint {
+                    raster_point: [x, y],
+                    model_point: [600000.0 + 10.0 * x + 2.0 * y + error, 200000.0 - 10.0 * y],
+                }
+            })
+            .collect::<Vec<_>>();
+        let exact = CoordinateTransform::from_tie_points(tie_points).unwrap();
+        let (affine, residuals) = exact.fit_affine().unwrap();
+        assert!(matches!(
+            affine,
+            CoordinateTransform::AffineTransform { .. }
+        ));
+        assert!(residuals.rms_error > 0.09 && residuals.rms_error < 0.11);
+        let [a, b, c, d, e, f] = affine
+            .geotransform()
+            .map(|[c, a, b, f, d, e]| [a, b, c, d, e, f])
+            .unwrap();
+        assert!((a - 10.0).abs() < 1e-3 && (b - 2.0).abs() < 1e-3 && (c - 600000.0).abs() < 0.1);
+        assert!(d.abs() < 1e-9 && (e + 10.0).abs() < 1e-9 && (f - 200000.0).abs() < 1e-6);
+
+        // The residuals of a nonlinear warp match those of a first order polynomial
+        let (polynomial, polynomial_residuals) = CoordinateTransform::from_tie_points_polynomial(
+            quadratic_tie_points(),
+            PolynomialOrder::Second,
+        )
+        .unwrap();
+        let (_, first_order_residuals) = CoordinateTransform::from_tie_points_polynomial(
+            quadratic_tie_points(),
+            PolynomialOrder::First,
+        )
+        .unwrap();
+        let (_, residuals) = polynomial.fit_affine().unwrap();
+        assert!(polynomial_residuals.rms_error < 1e-6);
+        assert!((residuals.rms_error - first_order_residuals.rms_error).abs() < 1e-6);
+
+        let affine = CoordinateTransform::from_affine([10.0, 0.0, 0.0, 0.0, -10.0, 0.0]).unwrap();
+        assert!(affine.fit_affine().is_err());
+    }
+
+    #[test]
+    fn test_polynomial_transform_with_too_few_tie_points() {
+        let tie_points = quadratic_tie_points()[..9].to_vec();
+
+        assert!(CoordinateTransform::from_tie_points_polynomial(
+            tie_points,
+            PolynomialOrder::Third
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_thin_plate_spline_transform() {
+        let tie_points = quadratic_tie_points();
+        let transform =
+            CoordinateTransform::from_tie_points_thin_plate_spline(tie_points.clone()).unwrap();
+
+        for tie_point in &tie_points {
+            assert_coord_eq(
+                transform.transform_to_model([
+                    tie_point.raster_point[0] as usize,
+                    tie_point.raster_point[1] as usize,
+                ]),
+                tie_point.model_point,
+            );
+            assert_coord_eq(
+                transform.transform_to_raster_f64(tie_point.model_point),
+                tie_point.raster_point,
+            );
+        }
+
+        // Between tie points the spline follows the smooth warp within a fraction of a pixel
+        let [x, y] = transform.transform_to_model([60, 10]);
+        assert!((x - 600606.0).abs() < 1.5 && (y - 199972.0).abs() < 1.5);
+    }
+
+    #[test]
+    fn test_thin_plate_spline_transform_is_affine_for_affine_tie_points() {
+        let tie_points = (0..4)
+            .flat_map(|i| (0..4).map(move |j| [i as f64 * 100.0, j as f64 * 100.0]))
+            .map(|[x, y]| TiePoint {
+                raster_point: [x, y],
+                model_point: [677562.5 + 25.0 * x, 253012.5 - 25.0 * y],
+            })
+            .collect();
+        let transform = CoordinateTransform::from_tie_points_thin_plate_spline(tie_points).unwrap();
+
+        assert_coord_eq(
+            transform.transform_to_model([399, 366]),
+            [687537.5, 243862.5],
+        );
+        assert_coord_eq(
+            transform.transform_to_raster_f64([682550.0, 248425.0]),
+            [199.5, 183.5],
+        );
+    }
+
+    /// An RPC model of a 1000x1000 image centered at 8.5°E 47.4°N with slightly nonlinear terms
+    /// and a height dependent displacement.
+    fn rpc_model() -> RpcModel {
+        let mut data = vec![
+            1.0, 0.5, // errors
+            500.0, 500.0, 47.4, 8.5, 400.0, // offsets
+            500.0, 500.0, 0.05, 0.07, 500.0, // scales
+        ];
+        let mut coefficients = [[0.0; 20]; 4];
+        // line = -P + 0.01 H + 0.002 L P, sample = L + 0.003 P² + 0.01 H
+        coefficients[0][2] = -1.0;
+        coefficients[0][3] = 0.01;
+        coefficients[0][4] = 0.002;
+        coefficients[1][0] = 1.0;
+        coefficients[1][9] = 0.0001;
+        coefficients[2][1] = 1.0;
+        coefficients[2][8] = 0.003;
+        coefficients[2][3] = 0.01;
+        coefficients[3][0] = 1.0;
+        coefficients[3][1] = 0.0002;
+        data.extend(coefficients.iter().flatten());
+
+        RpcModel::from_tag_data(data).unwrap()
+    }
+
+    #[test]
+    fn test_rpc_model() {
+        let model = rpc_model();
+
+        assert_coord_eq(model.ground_to_image(8.5, 47.4, 400.0), [500.0, 500.0]);
+        assert_coord_eq(
+            model.ground_to_image(8.5, 47.4, 900.0),
+            [505.0, 500.0 + 5.0 / 1.0001],
+        );
+
+        for [sample, line] in [[0.0, 0.0], [123.4, 876.5], [1000.0, 1000.0]] {
+            for height in [0.0, 400.0, 1500.0] {
+                let [longitude, latitude] = model.image_to_ground(sample, line, height);
+                assert_coord_eq(
+                    model.ground_to_image(longitude, latitude, height),
+                    [sample, line],
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_transform_by_rpc_model() {
+        let transform = CoordinateTransform::from_rpc_model(rpc_model(), 400.0);
+
+        // Raster coordinates refer to the corner, RPC image coordinates to the center of a pixel
+        assert_coord_eq(
+            transform.transform_to_raster_f64([8.5, 47.4]),
+            [500.5, 500.5],
+        );
+        assert_coord_eq(
+            transform.transform_to_model([500, 500]),
+            [8.49993, 47.40005],
+        );
+        assert_eq!(transform.transform_to_raster([8.5, 47.4]), [500, 500]);
+    }
+
+    #[test]
+    fn test_invalid_rpc_tag_data() {
+        assert!(RpcModel::from_tag_data(vec![0.0; 91]).is_err());
+    }
+
+    #[test]
+    fn test_geotransform() {
+        let geotransform = [677562.5, 25.0, 0.0, 253012.5, 0.0, -25.0];
+        let transform = CoordinateTransform::from_geotransform(geotransform).unwrap();
+        assert_eq!(transform.geotransform(), Some(geotransform));
+
+        let transform = CoordinateTransform::from_tie_point_and_scale(
+            [10.0, 20.0],
+            [677812.5, 252512.5],
+            [25.0, 25.0],
+        )
+        .unwrap();
+        assert_eq!(transform.geotransform(), Some(geotransform));
+    }
+
+    #[test]
+    fn test_from_geotransform() {
+        let transform =
+            CoordinateTransform::from_geotransform([677562.5, 25.0, 0.0, 253012.5, 0.0, -25.0])
+                .unwrap();
+
+        assert_eq!(
+            transform.transform_to_model([399, 366]),
+            [687537.5, 243862.5]
+        );
+        assert_eq!(
+            transform.transform_to_raster([682549.9, 248425.1]),
+            [199, 183]
+        );
+        assert!(CoordinateTransform::from_geotransform([0.0, 1.0, 2.0, 0.0, 2.0, 4.0]).is_err());
+    }
+
+    #[test]
+    fn test_from_affine() {
+        let transform =
+            CoordinateTransform::from_affine([25.0, 0.0, 677562.5, 0.0, -25.0, 253012.5]).unwrap();
+
+        assert_eq!(
+            transform.transform_to_model([399, 366]),
+            [687537.5, 243862.5]
+        );
+    }
+
+    #[test]
+    fn test_from_tie_point_and_scale() {
+        let transform = CoordinateTransform::from_tie_point_and_scale(
+            [0.0, 0.0],
+            [677562.5, 253012.5],
+            [25.0, 25.0],
+        )
+        .unwrap();
+
+        assert_eq!(
+            transform.transform_to_model([399, 366]),
+            [687537.5, 243862.5]
+        );
+        assert!(CoordinateTransform::from_tie_point_and_scale(
+            [0.0, 0.0],
+            [677562.5, 253012.5],
+            [25.0, 0.0]
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_transform_many() {
+        let raster_coords = [[0, 0], [399, 366], [199, 183], [20, 70]];
+        let model_coords = [[677562.5, 253012.5], [682549.9, 248425.1], [1030.0, 1980.0]];
+
+        for transform in [
+            zh_dem_25_transform(),
+            CoordinateTransform::from_geotransform([
+                1000.0, 8.660254, -5.0, 2000.0, -5.0, -8.660254,
+            ])
+            .unwrap(),
+            tie_points_transform(),
+        ] {
+            assert_eq!(
+                transform.transform_to_model_many(&raster_coords),
+                raster_coords
+                    .map(|coord| transform.transform_to_model(coord))
+                    .to_vec()
+            );
+            assert_eq!(
+                transform.transform_to_raster_f64_many(&model_coords),
+                model_coords
+                    .map(|coord| transform.transform_to_raster_f64(coord))
+                    .to_vec()
+            );
+            assert_eq!(
+                transform.transform_to_raster_many(&model_coords),
+                model_coords
+                    .map(|coord| transform.transform_to_raster(coord))
+                    .to_vec()
+            );
+        }
+    }
+
+    #[test]
+    fn test_resolution() {
+        assert_eq!(zh_dem_25_transform().resolution(), Some([25.0, 25.0]));
+        assert_eq!(
+            CoordinateTransform::from_geotransform([0.0, 0.5, 0.0, 0.0, 0.0, -2.0])
+                .unwrap()
+                .resolution(),
+            Some([0.5, 2.0])
+        );
+
+        let [x, y] = CoordinateTransform::from_geotransform([
+            1000.0, 8.660254, -5.0, 2000.0, -5.0, -8.660254,
+        ])
+        .unwrap()
+        .resolution()
+        .unwrap();
+        assert!((x - 10.0).abs() < 1e-6 && (y - 10.0).abs() < 1e-6);
+
+        assert_eq!(tie_points_transform().resolution(), None);
+    }
+
+    #[test]
+    fn test_model_extent() {
+        assert_eq!(
+            zh_dem_25_transform().model_extent(399, 366),
+            Extent::new(677562.5, 243862.5, 687537.5, 253012.5)
+        );
+
+        // Rotated by 30 degrees, so the corners of the raster do not coincide with the extent
+        let extent = CoordinateTransform::from_geotransform([
+            1000.0, 8.660254, -5.0, 2000.0, -5.0, -8.660254,
+        ])
+        .unwrap()
+        .model_extent(100, 100);
+        assert_coord_eq([extent.min_x, extent.min_y], [500.0, 633.9746]);
+        assert_coord_eq([extent.max_x, extent.max_y], [1866.0254, 2000.0]);
+    }
+
+    #[test]
+    fn test_model_extent_by_tie_points() {
+        let extent = tie_points_transform().model_extent(100, 100);
+
+        assert_coord_eq([extent.min_x, extent.min_y], [1000.0, 1900.0]);
+        assert_coord_eq([extent.max_x, extent.max_y], [1100.0, 2000.0]);
+    }
+
+    #[test]
+    fn test_invalid_tag_data() {
+        assert!(CoordinateTransform::from_tag_data(None, None, None).is_err());
+        assert!(CoordinateTransform::from_tag_data(
+            None,
+            Some(vec![0.0, 0.0, 0.0, 677562.5, 253012.5, 0.0]),
+            None
+        )
+        .is_err());
+        assert!(CoordinateTransform::from_tag_data(
+            Some(vec![25.0, 25.0]),
+            Some(vec![0.0, 0.0, 0.0, 677562.5, 253012.5, 0.0]),
+            None
+        )
+        .is_err());
+        assert!(CoordinateTransform::from_tag_data(
+            Some(vec![25.0, 25.0, 0.0]),
+            Some(vec![0.0, 0.0, 0.0, 677562.5, 253012.5]),
+            None
+        )
+        .is_err());
+        assert!(CoordinateTransform::from_tag_data(
+            Some(vec![25.0, 25.0, 0.0]),
+            None,
+            Some(vec![0.0; 16])
+        )
+        .is_err());
+    }
+}
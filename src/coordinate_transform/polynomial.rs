@@ -0,0 +1,149 @@
+use super::solve_linear_system;
+
+/// The order of a polynomial transformation fitted to ground control points.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolynomialOrder {
+    First,
+    Second,
+    Third,
+}
+
+impl PolynomialOrder {
+    fn degree(&self) -> usize {
+        match self {
+            PolynomialOrder::First => 1,
+            PolynomialOrder::Second => 2,
+            PolynomialOrder::Third => 3,
+        }
+    }
+
+    /// The number of coefficients per output dimension, which is also the minimum number of
+    /// ground control points required for a fit.
+    pub fn num_coefficients(&self) -> usize {
+        let degree = self.degree();
+        (degree + 1) * (degree + 2) / 2
+    }
+}
+
+/// A bivariate polynomial mapping of coordinates.
+///
+/// Inputs are normalized before evaluating the polynomial to keep the least squares problem well
+/// conditioned for large (e.g. projected) coordinates.
+#[derive(Debug, Clone)]
+pub struct Polynomial {
+    order: PolynomialOrder,
+    input_offset: [f64; 2],
+    input_scale: f64,
+    coefficients: [Vec<f64>; 2],
+}
+
+impl Polynomial {
+    /// Fits a polynomial mapping `inputs` to `outputs` by least squares. Returns `None` if the
+    /// points do not determine the polynomial, e.g. if there are too few of them.
+    pub(super) fn fit(
+        order: PolynomialOrder,
+        inputs: &[[f64; 2]],
+        outputs: &[[f64; 2]],
+    ) -> Option<Self> {
+        let num_coefficients = order.num_coefficients();
+        if inputs.len() < num_coefficients || inputs.len() != outputs.len() {
+            return None;
+        }
+
+        let n = inputs.len() as f64;
+        let input_offset = inputs.iter().fold([0.0, 0.0], |sum, input| {
+            [sum[0] + input[0] / n, sum[1] + input[1] / n]
+        });
+        let input_scale = inputs
+            .iter()
+            .map(|input| {
+                (input[0] - input_offset[0])
+                    .abs()
+                    .max((input[1] - input_offset[1]).abs())
+            })
+            .fold(0.0, f64::max);
+        if input_scale == 0.0 {
+            return None;
+        }
+
+        let mut polynomial = Polynomial {
+            order,
+            input_offset,
+            input_scale,
+            coefficients: [vec![], vec![]],
+        };
+
+        // Solve the normal equations (AᵀA) x = Aᵀb for both output dimensions
+        let mut normal_matrix = vec![0.0; num_coefficients * num_coefficients];
+        let mut right_hand_sides = [vec![0.0; num_coefficients], vec![0.0; num_coefficients]];
+        for (input, output) in inputs.iter().zip(outputs) {
+            let terms = polynomial.terms(*input);
+            for i in 0..num_coefficients {
+                for j in 0..num_coefficients {
+                    normal_matrix[i * num_coefficients + j] += terms[i] * terms[j];
+                }
+                right_hand_sides[0][i] += terms[i] * output[0];
+                right_hand_sides[1][i] += terms[i] * output[1];
+            }
+        }
+        polynomial.coefficients = solve_linear_system(normal_matrix, right_hand_sides)?;
+
+        Some(polynomial)
+    }
+
+    pub(super) fn order(&self) -> PolynomialOrder {
+        self.order
+    }
+
+    pub(super) fn apply(&self, coord: [f64; 2]) -> [f64; 2] {
+        let terms = self.terms(coord);
+        self.coefficients.each_ref().map(|coefficients| {
+            coefficients
+                .iter()
+                .zip(&terms)
+                .map(|(coefficient, term)| coefficient * term)
+                .sum()
+        })
+    }
+
+    /// Changes the polynomial such that it maps `c` to where `c + offset` was mapped before.
+    pub(super) fn offset_input(&mut self, offset: [f64; 2]) {
+        self.input_offset[0] -= offset[0];
+        self.input_offset[1] -= offset[1];
+    }
+
+    /// Adds the offset to all outputs of the polynomial.
+    pub(super) fn offset_output(&mut self, offset: [f64; 2]) {
+        self.coefficients[0][0] += offset[0];
+        self.coefficients[1][0] += offset[1];
+    }
+
+    /// Changes the polynomial such that it maps `c * factor` to where `c` was mapped before.
+    pub(super) fn scale_input(&mut self, factor: f64) {
+        self.input_offset[0] *= factor;
+        self.input_offset[1] *= factor;
+        self.input_scale *= factor;
+    }
+
+    /// Multiplies all outputs of the polynomial by the factor.
+    pub(super) fn scale_output(&mut self, factor: f64) {
+        for coefficients in &mut self.coefficients {
+            for coefficient in coefficients {
+                *coefficient *= factor;
+            }
+        }
+    }
+
+    /// The monomials `1, x, y, x², xy, y², …` of the normalized coordinate.
+    fn terms(&self, coord: [f64; 2]) -> Vec<f64> {
+        let x = (coord[0] - self.input_offset[0]) / self.input_scale;
+        let y = (coord[1] - self.input_offset[1]) / self.input_scale;
+        let mut terms = Vec::with_capacity(self.order.num_coefficients());
+        for degree in 0..=self.order.degree() {
+            for y_exponent in 0..=degree {
+                terms.push(x.powi((degree - y_exponent) as i32) * y.powi(y_exponent as i32));
+            }
+        }
+        terms
+    }
+}
@@ -0,0 +1,162 @@
+use super::format_error;
+use crate::GeoTiffResult;
+
+/// A rational polynomial coefficient (RPC) sensor model, as stored in the RPCCoefficientTag.
+///
+/// The model maps longitude, latitude and height above the ellipsoid (WGS 84) to image
+/// coordinates given as sample (column) and line (row) with respect to pixel centers. Each image
+/// coordinate is the ratio of two cubic polynomials in normalized ground coordinates, with terms
+/// in the order defined by RPC00B.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RpcModel {
+    pub error_bias: f64,
+    pub error_random: f64,
+    pub line_offset: f64,
+    pub sample_offset: f64,
+    pub latitude_offset: f64,
+    pub longitude_offset: f64,
+    pub height_offset: f64,
+    pub line_scale: f64,
+    pub sample_scale: f64,
+    pub latitude_scale: f64,
+    pub longitude_scale: f64,
+    pub height_scale: f64,
+    pub line_numerator: [f64; 20],
+    pub line_denominator: [f64; 20],
+    pub sample_numerator: [f64; 20],
+    pub sample_denominator: [f64; 20],
+}
+
+impl RpcModel {
+    const TAG_LENGTH: usize = 92;
+    const MAX_ITERATIONS: usize = 20;
+    const CONVERGENCE_THRESHOLD: f64 = 1e-10;
+
+    pub(crate) fn from_tag_data(data: Vec<f64>) -> GeoTiffResult<Self> {
+        if data.len() != Self::TAG_LENGTH {
+            return Err(format_error(format!(
+                "Expected {} values in RPCCoefficientTag, found {}",
+                Self::TAG_LENGTH,
+                data.len()
+            )));
+        }
+        let coefficients = |index: usize| <[f64; 20]>::try_from(&data[index..index + 20]).unwrap();
+
+        Ok(RpcModel {
+            error_bias: data[0],
+            error_random: data[1],
+            line_offset: data[2],
+            sample_offset: data[3],
+            latitude_offset: data[4],
+            longitude_offset: data[5],
+            height_offset: data[6],
+            line_scale: data[7],
+            sample_scale: data[8],
+            latitude_scale: data[9],
+            longitude_scale: data[10],
+            height_scale: data[11],
+            line_numerator: coefficients(12),
+            line_denominator: coefficients(32),
+            sample_numerator: coefficients(52),
+            sample_denominator: coefficients(72),
+        })
+    }
+
+    /// Scales image space by the given factors for samples and lines, i.e. afterwards the image
+    /// coordinates are `1 / factor` times the image coordinates before, as for an overview.
+    pub(crate) fn scale_image_space(&mut self, factor: [f64; 2]) {
+        self.sample_offset /= factor[0];
+        self.sample_scale /= factor[0];
+        self.line_offset /= factor[1];
+        self.line_scale /= factor[1];
+    }
+
+    /// Projects a ground coordinate to the image coordinate `[sample, line]`.
+    pub fn ground_to_image(&self, longitude: f64, latitude: f64, height: f64) -> [f64; 2] {
+        let [sample, line] = self.normalized_ground_to_image(
+            (longitude - self.longitude_offset) / self.longitude_scale,
+            (latitude - self.latitude_offset) / self.latitude_scale,
+            (height - self.height_offset) / self.height_scale,
+        );
+        [
+            sample * self.sample_scale + self.sample_offset,
+            line * self.line_scale + self.line_offset,
+        ]
+    }
+
+    /// Finds the ground coordinate `[longitude, latitude]` at the given height that projects to
+    /// the image coordinate `[sample, line]`, using Newton's method.
+    pub fn image_to_ground(&self, sample: f64, line: f64, height: f64) -> [f64; 2] {
+        let target = [
+            (sample - self.sample_offset) / self.sample_scale,
+            (line - self.line_offset) / self.line_scale,
+        ];
+        let h = (height - self.height_offset) / self.height_scale;
+        let step = 1e-7;
+
+        let [mut l, mut p] = [0.0, 0.0];
+        for _ in 0..Self::MAX_ITERATIONS {
+            let current = self.normalized_ground_to_image(l, p, h);
+            let residual = [target[0] - current[0], target[1] - current[1]];
+            if residual[0].abs().max(residual[1].abs()) < Self::CONVERGENCE_THRESHOLD {
+                break;
+            }
+
+            // Solve J Δ = residual using a finite difference Jacobian
+            let dl = self.normalized_ground_to_image(l + step, p, h);
+            let dp = self.normalized_ground_to_image(l, p + step, h);
+            let jacobian = [
+                [(dl[0] - current[0]) / step, (dp[0] - current[0]) / step],
+                [(dl[1] - current[1]) / step, (dp[1] - current[1]) / step],
+            ];
+            let determinant = jacobian[0][0] * jacobian[1][1] - jacobian[0][1] * jacobian[1][0];
+            if determinant == 0.0 {
+                break;
+            }
+            l += (jacobian[1][1] * residual[0] - jacobian[0][1] * residual[1]) / determinant;
+            p += (jacobian[0][0] * residual[1] - jacobian[1][0] * residual[0]) / determinant;
+        }
+
+        [
+            l * self.longitude_scale + self.longitude_offset,
+            p * self.latitude_scale + self.latitude_offset,
+        ]
+    }
+
+    fn normalized_ground_to_image(&self, l: f64, p: f64, h: f64) -> [f64; 2] {
+        let terms = [
+            1.0,
+            l,
+            p,
+            h,
+            l * p,
+            l * h,
+            p * h,
+            l * l,
+            p * p,
+            h * h,
+            p * l * h,
+            l * l * l,
+            l * p * p,
+            l * h * h,
+            l * l * p,
+            p * p * p,
+            p * h * h,
+            l * l * h,
+            p * p * h,
+            h * h * h,
+        ];
+        let evaluate = |coefficients: &[f64; 20]| -> f64 {
+            coefficients
+                .iter()
+                .zip(&terms)
+                .map(|(coefficient, term)| coefficient * term)
+                .sum()
+        };
+
+        [
+            evaluate(&self.sample_numerator) / evaluate(&self.sample_denominator),
+            evaluate(&self.line_numerator) / evaluate(&self.line_denominator),
+        ]
+    }
+}
@@ -0,0 +1,132 @@
+use super::solve_linear_system;
+
+/// A thin plate spline interpolating between control points, i.e. the smoothest mapping (in terms
+/// of bending energy) passing exactly through all of them.
+///
+/// Like [`super::polynomial::Polynomial`], inputs are normalized to keep the linear system well
+/// conditioned.
+#[derive(Debug, Clone)]
+pub struct ThinPlateSpline {
+    input_offset: [f64; 2],
+    input_scale: f64,
+    control_points: Vec<[f64; 2]>,
+    /// The weights of the radial basis functions, followed by the coefficients of the affine part
+    coefficients: [Vec<f64>; 2],
+}
+
+impl ThinPlateSpline {
+    /// Fits a thin plate spline mapping `inputs` to `outputs`. Duplicate inputs are only used once.
+    /// Returns `None` if there are less than three inputs or all of them are collinear.
+    pub(super) fn fit(inputs: &[[f64; 2]], outputs: &[[f64; 2]]) -> Option<Self> {
+        let mut control_points = Vec::<[f64; 2]>::with_capacity(inputs.len());
+        let mut values = Vec::<[f64; 2]>::with_capacity(inputs.len());
+        for (input, output) in inputs.iter().zip(outputs) {
+            if !control_points.contains(input) {
+                control_points.push(*input);
+                values.push(*output);
+            }
+        }
+        if control_points.len() < 3 {
+            return None;
+        }
+
+        let n = control_points.len() as f64;
+        let input_offset = control_points.iter().fold([0.0, 0.0], |sum, input| {
+            [sum[0] + input[0] / n, sum[1] + input[1] / n]
+        });
+        let input_scale = control_points
+            .iter()
+            .map(|input| {
+                (input[0] - input_offset[0])
+                    .abs()
+                    .max((input[1] - input_offset[1]).abs())
+            })
+            .fold(0.0, f64::max);
+        for control_point in &mut control_points {
+            control_point[0] = (control_point[0] - input_offset[0]) / input_scale;
+            control_point[1] = (control_point[1] - input_offset[1]) / input_scale;
+        }
+
+        // The system [K P; Pᵀ 0] [w; a] = [v; 0] with Kᵢⱼ = U(|pᵢ - pⱼ|) and Pᵢ = (1, xᵢ, yᵢ)
+        let num_points = control_points.len();
+        let size = num_points + 3;
+        let mut matrix = vec![0.0; size * size];
+        let mut right_hand_sides = [vec![0.0; size], vec![0.0; size]];
+        for (i, p) in control_points.iter().enumerate() {
+            for (j, q) in control_points.iter().enumerate() {
+                matrix[i * size + j] = radial_basis(*p, *q);
+            }
+            for (k, term) in [1.0, p[0], p[1]].into_iter().enumerate() {
+                matrix[i * size + num_points + k] = term;
+                matrix[(num_points + k) * size + i] = term;
+            }
+            right_hand_sides[0][i] = values[i][0];
+            right_hand_sides[1][i] = values[i][1];
+        }
+        let coefficients = solve_linear_system(matrix, right_hand_sides)?;
+
+        Some(ThinPlateSpline {
+            input_offset,
+            input_scale,
+            control_points,
+            coefficients,
+        })
+    }
+
+    pub(super) fn apply(&self, coord: [f64; 2]) -> [f64; 2] {
+        let x = (coord[0] - self.input_offset[0]) / self.input_scale;
+        let y = (coord[1] - self.input_offset[1]) / self.input_scale;
+        let num_points = self.control_points.len();
+
+        self.coefficients.each_ref().map(|coefficients| {
+            let affine = coefficients[num_points]
+                + coefficients[num_points + 1] * x
+                + coefficients[num_points + 2] * y;
+            self.control_points
+                .iter()
+                .zip(coefficients)
+                .fold(affine, |sum, (control_point, weight)| {
+                    sum + weight * radial_basis(*control_point, [x, y])
+                })
+        })
+    }
+
+    /// Changes the spline such that it maps `c` to where `c + offset` was mapped before.
+    pub(super) fn offset_input(&mut self, offset: [f64; 2]) {
+        self.input_offset[0] -= offset[0];
+        self.input_offset[1] -= offset[1];
+    }
+
+    /// Adds the offset to all outputs of the spline.
+    pub(super) fn offset_output(&mut self, offset: [f64; 2]) {
+        let num_points = self.control_points.len();
+        self.coefficients[0][num_points] += offset[0];
+        self.coefficients[1][num_points] += offset[1];
+    }
+
+    /// Changes the spline such that it maps `c * factor` to where `c` was mapped before.
+    pub(super) fn scale_input(&mut self, factor: f64) {
+        self.input_offset[0] *= factor;
+        self.input_offset[1] *= factor;
+        self.input_scale *= factor;
+    }
+
+    /// Multiplies all outputs of the spline by the factor.
+    pub(super) fn scale_output(&mut self, factor: f64) {
+        for coefficients in &mut self.coefficients {
+            for coefficient in coefficients {
+                *coefficient *= factor;
+            }
+        }
+    }
+}
+
+/// The fundamental solution of the biharmonic equation, U(r) = r² ln r.
+fn radial_basis(p: [f64; 2], q: [f64; 2]) -> f64 {
+    let squared_distance = (p[0] - q[0]).powi(2) + (p[1] - q[1]).powi(2);
+    if squared_distance == 0.0 {
+        0.0
+    } else {
+        squared_distance * squared_distance.ln() / 2.0
+    }
+}
@@ -0,0 +1,128 @@
+use super::TiePoint;
+
+/// Computes a Delaunay triangulation of the raster points of the tie points using the
+/// Bowyer-Watson algorithm. Tie points with duplicate raster points are only used once.
+pub(super) fn triangulate(tie_points: &[TiePoint]) -> Vec<[usize; 3]> {
+    let mut points = Vec::<[f64; 2]>::with_capacity(tie_points.len() + 3);
+    let mut indices = Vec::with_capacity(tie_points.len());
+    for (index, tie_point) in tie_points.iter().enumerate() {
+        if !points.contains(&tie_point.raster_point) {
+            points.push(tie_point.raster_point);
+            indices.push(index);
+        }
+    }
+    if points.len() < 3 {
+        return Vec::new();
+    }
+
+    // A triangle containing all points, which is removed again at the end
+    let (min, max) = points.iter().fold(
+        ([f64::INFINITY; 2], [f64::NEG_INFINITY; 2]),
+        |(min, max), point| {
+            (
+                [min[0].min(point[0]), min[1].min(point[1])],
+                [max[0].max(point[0]), max[1].max(point[1])],
+            )
+        },
+    );
+    let size = (max[0] - min[0]).max(max[1] - min[1]).max(1.0) * 100.0;
+    let center = [(min[0] + max[0]) / 2.0, (min[1] + max[1]) / 2.0];
+    let num_points = points.len();
+    points.push([center[0] - size, center[1] - size]);
+    points.push([center[0] + size, center[1] - size]);
+    points.push([center[0], center[1] + size]);
+
+    let mut triangles = vec![[num_points, num_points + 1, num_points + 2]];
+    for point_index in 0..num_points {
+        let point = points[point_index];
+        let (bad_triangles, good_triangles): (Vec<_>, Vec<_>) = triangles
+            .into_iter()
+            .partition(|triangle| in_circumcircle(&points, triangle, point));
+
+        // The boundary of the polygonal hole consists of the edges not shared by bad triangles
+        let edges = bad_triangles
+            .iter()
+            .flat_map(|&[a, b, c]| [[a, b], [b, c], [c, a]])
+            .collect::<Vec<_>>();
+        triangles = good_triangles;
+        for &[a, b] in &edges {
+            let is_shared = edges
+                .iter()
+                .filter(|&&[c, d]| (a == c && b == d) || (a == d && b == c))
+                .count()
+                > 1;
+            if !is_shared {
+                triangles.push([a, b, point_index]);
+            }
+        }
+    }
+
+    triangles
+        .into_iter()
+        .filter(|triangle| triangle.iter().all(|index| *index < num_points))
+        .filter(|&[a, b, c]| cross(points[a], points[b], points[c]).abs() > f64::EPSILON)
+        .map(|triangle| triangle.map(|index| indices[index]))
+        .collect()
+}
+
+/// Interpolates linearly within the triangle containing the coordinate. Coordinates outside the
+/// triangulation are extrapolated from the closest triangle.
+pub(super) fn interpolate(
+    tie_points: &[TiePoint],
+    triangles: &[[usize; 3]],
+    coord: [f64; 2],
+    source: impl Fn(&TiePoint) -> [f64; 2],
+    target: impl Fn(&TiePoint) -> [f64; 2],
+) -> [f64; 2] {
+    // The triangle with the largest minimum barycentric coordinate contains the coordinate, or is
+    // the closest one if no triangle contains it
+    let (triangle, weights) = triangles
+        .iter()
+        .map(|triangle| {
+            let [a, b, c] = triangle.map(|index| source(&tie_points[index]));
+            (triangle, barycentric_coordinates(a, b, c, coord))
+        })
+        .max_by(|(_, a), (_, b)| min_weight(a).total_cmp(&min_weight(b)))
+        .expect("triangulation is not empty");
+
+    triangle
+        .iter()
+        .zip(weights)
+        .fold([0.0, 0.0], |result, (index, weight)| {
+            let point = target(&tie_points[*index]);
+            [result[0] + weight * point[0], result[1] + weight * point[1]]
+        })
+}
+
+fn min_weight(weights: &[f64; 3]) -> f64 {
+    weights[0].min(weights[1]).min(weights[2])
+}
+
+fn barycentric_coordinates(a: [f64; 2], b: [f64; 2], c: [f64; 2], p: [f64; 2]) -> [f64; 3] {
+    let area = cross(a, b, c);
+    let u = cross(p, b, c) / area;
+    let v = cross(a, p, c) / area;
+    [u, v, 1.0 - u - v]
+}
+
+/// Twice the signed area of the triangle `(a, b, c)`.
+fn cross(a: [f64; 2], b: [f64; 2], c: [f64; 2]) -> f64 {
+    (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0])
+}
+
+fn in_circumcircle(points: &[[f64; 2]], &[a, b, c]: &[usize; 3], p: [f64; 2]) -> bool {
+    let [a, b, c] = [points[a], points[b], points[c]];
+    let [ax, ay] = [a[0] - p[0], a[1] - p[1]];
+    let [bx, by] = [b[0] - p[0], b[1] - p[1]];
+    let [cx, cy] = [c[0] - p[0], c[1] - p[1]];
+    let determinant = (ax * ax + ay * ay) * (bx * cy - cx * by)
+        - (bx * bx + by * by) * (ax * cy - cx * ay)
+        + (cx * cx + cy * cy) * (ax * by - bx * ay);
+
+    // The sign of the determinant depends on the orientation of the triangle
+    if cross(a, b, c) > 0.0 {
+        determinant > 0.0
+    } else {
+        determinant < 0.0
+    }
+}
@@ -0,0 +1,1751 @@
+use std::fmt::Write;
+
+use crate::geo_key_directory::{GeoKey, GeoKeyDirectory, GeoKeyValue, ModelType, RasterType};
+
+/// A coordinate reference system reconstructed from the GeoKeys, either from the built-in
+/// definitions of common EPSG codes or from the keys describing a user-defined CRS.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Crs {
+    Geographic(GeodeticCrs),
+    Geocentric(GeodeticCrs),
+    Projected(ProjectedCrs),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct GeodeticCrs {
+    name: String,
+    datum: Datum,
+    prime_meridian: PrimeMeridian,
+    angular_unit: Unit,
+    epsg_code: Option<u16>,
+    /// The transformation of the datum to WGS 84, as given by the GeogTOWGS84GeoKey
+    to_wgs84: Option<Conversion>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ProjectedCrs {
+    name: String,
+    base_crs: GeodeticCrs,
+    conversion: Conversion,
+    linear_unit: Unit,
+    epsg_code: Option<u16>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Datum {
+    name: String,
+    ellipsoid: Ellipsoid,
+    epsg_code: Option<u16>,
+}
+
+/// An ellipsoid with its semi-major axis in meters. An inverse flattening of 0 denotes a sphere.
+#[derive(Debug, Clone, PartialEq)]
+struct Ellipsoid {
+    name: String,
+    semi_major_axis: f64,
+    inverse_flattening: f64,
+    epsg_code: Option<u16>,
+}
+
+/// A prime meridian with its longitude in the angular unit of the CRS.
+#[derive(Debug, Clone, PartialEq)]
+struct PrimeMeridian {
+    name: String,
+    longitude: f64,
+    epsg_code: Option<u16>,
+}
+
+/// A coordinate operation given by its method and parameters, i.e. the conversion of a projected
+/// CRS or the transformation of a datum to WGS 84.
+#[derive(Debug, Clone, PartialEq)]
+struct Conversion {
+    name: String,
+    method_name: &'static str,
+    method_epsg_code: u16,
+    parameters: Vec<Parameter>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Parameter {
+    name: &'static str,
+    epsg_code: u16,
+    value: f64,
+    unit: Unit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum UnitKind {
+    Length,
+    Angle,
+    Scale,
+}
+
+/// A unit of measure with the factor converting it to meters, radians or unity.
+#[derive(Debug, Clone, PartialEq)]
+struct Unit {
+    kind: UnitKind,
+    name: String,
+    conversion_factor: f64,
+    epsg_code: Option<u16>,
+}
+
+/// The name, EPSG code and unit kind of a projection parameter, and the GeoKeys it is read from in
+/// order of precedence.
+type ParameterDefinition = (&'static str, u16, UnitKind, &'static [GeoKey]);
+
+/// A projection method as given by the ProjCoordTransGeoKey.
+struct Method {
+    coord_trans: u16,
+    name: &'static str,
+    epsg_code: u16,
+    parameters: &'static [ParameterDefinition],
+}
+
+const LINEAR_UNITS: &[(u16, &str, f64)] = &[
+    (9001, "metre", 1.0),
+    (9002, "foot", 0.3048),
+    (9003, "US survey foot", 0.304800609601219),
+    (9030, "nautical mile", 1852.0),
+    (9036, "kilometre", 1000.0),
+];
+
+const ANGULAR_UNITS: &[(u16, &str, f64)] = &[
+    (9101, "radian", 1.0),
+    (9102, "degree", 0.0174532925199433),
+    (9104, "arc-second", 4.84813681109536e-6),
+    (9105, "grad", 0.015707963267949),
+    (9122, "degree", 0.0174532925199433),
+];
+
+const ELLIPSOIDS: &[(u16, &str, f64, f64)] = &[
+    (7004, "Bessel 1841", 6377397.155, 299.1528128),
+    (7008, "Clarke 1866", 6378206.4, 294.978698213898),
+    (7019, "GRS 1980", 6378137.0, 298.257222101),
+    (7022, "International 1924", 6378388.0, 297.0),
+    (7030, "WGS 84", 6378137.0, 298.257223563),
+];
+
+const DATUMS: &[(u16, &str, u16)] = &[
+    (6230, "European Datum 1950", 7022),
+    (6258, "European Terrestrial Reference System 1989", 7019),
+    (6267, "North American Datum 1927", 7008),
+    (6269, "North American Datum 1983", 7019),
+    (6314, "Deutsches Hauptdreiecksnetz", 7004),
+    (6326, "World Geodetic System 1984", 7030),
+];
+
+const GEODETIC_CRSS: &[(u16, &str, u16)] = &[
+    (4230, "ED50", 6230),
+    (4258, "ETRS89", 6258),
+    (4267, "NAD27", 6267),
+    (4269, "NAD83", 6269),
+    (4314, "DHDN", 6314),
+    (4326, "WGS 84", 6326),
+];
+
+/// Projected CRS families using UTM zones, given by the range of EPSG codes, the code of the base
+/// CRS and whether they are on the southern hemisphere.
+const UTM_CRSS: &[(u16, u16, u16, bool)] = &[
+    (25828, 25838, 4258, false),
+    (26703, 26722, 4267, false),
+    (26901, 26923, 4269, false),
+    (32601, 32660, 4326, false),
+    (32701, 32760, 4326, true),
+];
+
+const GREENWICH: u16 = 8901;
+const METRE: u16 = 9001;
+const DEGREE: u16 = 9102;
+const ARC_SECOND: u16 = 9104;
+const UNITY: u16 = 9201;
+const PARTS_PER_MILLION: u16 = 9202;
+const PSEUDO_MERCATOR: u16 = 3857;
+const WGS_84: u16 = 4326;
+const WGS_84_GEOCENTRIC: u16 = 4978;
+const PSEUDO_MERCATOR_METHOD: u16 = 1024;
+const LAMBERT_CONIC_CONFORMAL_1SP_METHOD: u16 = 9801;
+const PROJJSON_SCHEMA: &str = "https://proj.org/schemas/v0.7/projjson.schema.json";
+
+const TRANSVERSE_MERCATOR: Method = Method {
+    coord_trans: 1,
+    name: "Transverse Mercator",
+    epsg_code: 9807,
+    parameters: &[
+        LATITUDE_OF_NATURAL_ORIGIN,
+        LONGITUDE_OF_NATURAL_ORIGIN,
+        SCALE_FACTOR_AT_NATURAL_ORIGIN,
+        FALSE_EASTING,
+        FALSE_NORTHING,
+    ],
+};
+
+const METHODS: &[Method] = &[
+    TRANSVERSE_MERCATOR,
+    Method {
+        coord_trans: 3,
+        name: "Hotine Oblique Mercator (variant B)",
+        epsg_code: 9815,
+        parameters: &[
+            (
+                "Latitude of projection centre",
+                8811,
+                UnitKind::Angle,
+                &[GeoKey::ProjCenterLat, GeoKey::ProjNatOriginLat],
+            ),
+            (
+                "Longitude of projection centre",
+                8812,
+                UnitKind::Angle,
+                &[GeoKey::ProjCenterLong, GeoKey::ProjNatOriginLong],
+            ),
+            (
+                "Azimuth of initial line",
+                8813,
+                UnitKind::Angle,
+                &[GeoKey::ProjAzimuthAngle],
+            ),
+            (
+                "Angle from Rectified to Skew Grid",
+                8814,
+                UnitKind::Angle,
+                &[GeoKey::ProjRectifiedGridAngle, GeoKey::ProjAzimuthAngle],
+            ),
+            (
+                "Scale factor on initial line",
+                8815,
+                UnitKind::Scale,
+                &[GeoKey::ProjScaleAtCenter, GeoKey::ProjScaleAtNatOrigin],
+            ),
+            (
+                "Easting at projection centre",
+                8816,
+                UnitKind::Length,
+                &[GeoKey::ProjCenterEasting, GeoKey::ProjFalseEasting],
+            ),
+            (
+                "Northing at projection centre",
+                8817,
+                UnitKind::Length,
+                &[GeoKey::ProjCenterNorthing, GeoKey::ProjFalseNorthing],
+            ),
+        ],
+    },
+    Method {
+        coord_trans: 7,
+        name: "Mercator (variant A)",
+        epsg_code: 9804,
+        parameters: &[
+            LATITUDE_OF_NATURAL_ORIGIN,
+            LONGITUDE_OF_NATURAL_ORIGIN,
+            SCALE_FACTOR_AT_NATURAL_ORIGIN,
+            FALSE_EASTING,
+            FALSE_NORTHING,
+        ],
+    },
+    Method {
+        coord_trans: 8,
+        name: "Lambert Conic Conformal (2SP)",
+        epsg_code: 9802,
+        parameters: &[
+            LATITUDE_OF_FALSE_ORIGIN,
+            LONGITUDE_OF_FALSE_ORIGIN,
+            LATITUDE_OF_1ST_STANDARD_PARALLEL,
+            LATITUDE_OF_2ND_STANDARD_PARALLEL,
+            EASTING_AT_FALSE_ORIGIN,
+            NORTHING_AT_FALSE_ORIGIN,
+        ],
+    },
+    Method {
+        coord_trans: 9,
+        name: "Lambert Conic Conformal (1SP)",
+        epsg_code: 9801,
+        parameters: &[
+            LATITUDE_OF_NATURAL_ORIGIN,
+            LONGITUDE_OF_NATURAL_ORIGIN,
+            SCALE_FACTOR_AT_NATURAL_ORIGIN,
+            FALSE_EASTING,
+            FALSE_NORTHING,
+        ],
+    },
+    Method {
+        coord_trans: 10,
+        name: "Lambert Azimuthal Equal Area",
+        epsg_code: 9820,
+        parameters: &[
+            (
+                "Latitude of natural origin",
+                8801,
+                UnitKind::Angle,
+                &[GeoKey::ProjCenterLat, GeoKey::ProjNatOriginLat],
+            ),
+            (
+                "Longitude of natural origin",
+                8802,
+                UnitKind::Angle,
+                &[GeoKey::ProjCenterLong, GeoKey::ProjNatOriginLong],
+            ),
+            FALSE_EASTING,
+            FALSE_NORTHING,
+        ],
+    },
+    Method {
+        coord_trans: 11,
+        name: "Albers Equal Area",
+        epsg_code: 9822,
+        parameters: &[
+            LATITUDE_OF_FALSE_ORIGIN,
+            LONGITUDE_OF_FALSE_ORIGIN,
+            LATITUDE_OF_1ST_STANDARD_PARALLEL,
+            LATITUDE_OF_2ND_STANDARD_PARALLEL,
+            EASTING_AT_FALSE_ORIGIN,
+            NORTHING_AT_FALSE_ORIGIN,
+        ],
+    },
+    Method {
+        coord_trans: 15,
+        name: "Polar Stereographic (variant A)",
+        epsg_code: 9810,
+        parameters: &[
+            LATITUDE_OF_NATURAL_ORIGIN,
+            (
+                "Longitude of natural origin",
+                8802,
+                UnitKind::Angle,
+                &[GeoKey::ProjStraightVertPoleLong, GeoKey::ProjNatOriginLong],
+            ),
+            SCALE_FACTOR_AT_NATURAL_ORIGIN,
+            FALSE_EASTING,
+            FALSE_NORTHING,
+        ],
+    },
+    Method {
+        coord_trans: 16,
+        name: "Oblique Stereographic",
+        epsg_code: 9809,
+        parameters: &[
+            LATITUDE_OF_NATURAL_ORIGIN,
+            LONGITUDE_OF_NATURAL_ORIGIN,
+            SCALE_FACTOR_AT_NATURAL_ORIGIN,
+            FALSE_EASTING,
+            FALSE_NORTHING,
+        ],
+    },
+    Method {
+        coord_trans: 17,
+        name: "Equidistant Cylindrical",
+        epsg_code: 1028,
+        parameters: &[
+            (
+                "Latitude of 1st standard parallel",
+                8823,
+                UnitKind::Angle,
+                &[GeoKey::ProjStdParallel1, GeoKey::ProjNatOriginLat],
+            ),
+            (
+                "Longitude of natural origin",
+                8802,
+                UnitKind::Angle,
+                &[GeoKey::ProjNatOriginLong, GeoKey::ProjCenterLong],
+            ),
+            FALSE_EASTING,
+            FALSE_NORTHING,
+        ],
+    },
+    Method {
+        coord_trans: 18,
+        name: "Cassini-Soldner",
+        epsg_code: 9806,
+        parameters: &[
+            LATITUDE_OF_NATURAL_ORIGIN,
+            LONGITUDE_OF_NATURAL_ORIGIN,
+            FALSE_EASTING,
+            FALSE_NORTHING,
+        ],
+    },
+];
+
+/// The parameters of the transformation to WGS 84 in the order of the GeogTOWGS84GeoKey.
+const TO_WGS84_PARAMETERS: &[ParameterDefinition] = &[
+    ("X-axis translation", 8605, UnitKind::Length, &[]),
+    ("Y-axis translation", 8606, UnitKind::Length, &[]),
+    ("Z-axis translation", 8607, UnitKind::Length, &[]),
+    ("X-axis rotation", 8608, UnitKind::Angle, &[]),
+    ("Y-axis rotation", 8609, UnitKind::Angle, &[]),
+    ("Z-axis rotation", 8610, UnitKind::Angle, &[]),
+    ("Scale difference", 8611, UnitKind::Scale, &[]),
+];
+
+const LATITUDE_OF_NATURAL_ORIGIN: ParameterDefinition = (
+    "Latitude of natural origin",
+    8801,
+    UnitKind::Angle,
+    &[GeoKey::ProjNatOriginLat],
+);
+const LONGITUDE_OF_NATURAL_ORIGIN: ParameterDefinition = (
+    "Longitude of natural origin",
+    8802,
+    UnitKind::Angle,
+    &[GeoKey::ProjNatOriginLong],
+);
+const SCALE_FACTOR_AT_NATURAL_ORIGIN: ParameterDefinition = (
+    "Scale factor at natural origin",
+    8805,
+    UnitKind::Scale,
+    &[GeoKey::ProjScaleAtNatOrigin],
+);
+const FALSE_EASTING: ParameterDefinition = (
+    "False easting",
+    8806,
+    UnitKind::Length,
+    &[GeoKey::ProjFalseEasting],
+);
+const FALSE_NORTHING: ParameterDefinition = (
+    "False northing",
+    8807,
+    UnitKind::Length,
+    &[GeoKey::ProjFalseNorthing],
+);
+const LATITUDE_OF_FALSE_ORIGIN: ParameterDefinition = (
+    "Latitude of false origin",
+    8821,
+    UnitKind::Angle,
+    &[GeoKey::ProjFalseOriginLat, GeoKey::ProjNatOriginLat],
+);
+const LONGITUDE_OF_FALSE_ORIGIN: ParameterDefinition = (
+    "Longitude of false origin",
+    8822,
+    UnitKind::Angle,
+    &[GeoKey::ProjFalseOriginLong, GeoKey::ProjNatOriginLong],
+);
+const LATITUDE_OF_1ST_STANDARD_PARALLEL: ParameterDefinition = (
+    "Latitude of 1st standard parallel",
+    8823,
+    UnitKind::Angle,
+    &[GeoKey::ProjStdParallel1],
+);
+const LATITUDE_OF_2ND_STANDARD_PARALLEL: ParameterDefinition = (
+    "Latitude of 2nd standard parallel",
+    8824,
+    UnitKind::Angle,
+    &[GeoKey::ProjStdParallel2],
+);
+const EASTING_AT_FALSE_ORIGIN: ParameterDefinition = (
+    "Easting at false origin",
+    8826,
+    UnitKind::Length,
+    &[GeoKey::ProjFalseOriginEasting, GeoKey::ProjFalseEasting],
+);
+const NORTHING_AT_FALSE_ORIGIN: ParameterDefinition = (
+    "Northing at false origin",
+    8827,
+    UnitKind::Length,
+    &[GeoKey::ProjFalseOriginNorthing, GeoKey::ProjFalseNorthing],
+);
+
+impl Crs {
+    /// Reconstructs the model CRS, or returns `None` if the GeoKeys do not describe one that is
+    /// supported.
+    pub(crate) fn from_geo_keys(geo_keys: &GeoKeyDirectory) -> Option<Self> {
+        let mut crs = match geo_keys.model_type() {
+            Some(ModelType::Projected) => ProjectedCrs::from_geo_keys(geo_keys).map(Crs::Projected),
+            Some(ModelType::Geographic) => {
+                GeodeticCrs::from_geo_keys(geo_keys).map(Crs::Geographic)
+            }
+            Some(ModelType::Geocentric) => {
+                GeodeticCrs::from_geo_keys(geo_keys).map(Crs::Geocentric)
+            }
+            Some(ModelType::UserDefined) => None,
+            None if geo_keys.projected_type().is_some() => {
+                ProjectedCrs::from_geo_keys(geo_keys).map(Crs::Projected)
+            }
+            None => GeodeticCrs::from_geo_keys(geo_keys).map(Crs::Geographic),
+        }?;
+        if let Some(values) = geo_keys.geog_towgs84() {
+            let geocentric = matches!(crs, Crs::Geocentric(_));
+            crs.geodetic_crs_mut().to_wgs84 = Conversion::to_wgs84(values, geocentric);
+        }
+        Some(crs)
+    }
+
+    /// Looks up a CRS by its EPSG code among the built-in definitions.
+    pub(crate) fn from_epsg_code(code: u16) -> Option<Self> {
+        ProjectedCrs::from_epsg_code(code)
+            .map(Crs::Projected)
+            .or_else(|| GeodeticCrs::from_epsg_code(code).map(Crs::Geographic))
+    }
+
+    /// The parameters of the transformation of the datum to WGS 84, if given.
+    pub(crate) fn to_wgs84(&self) -> Option<Vec<f64>> {
+        let transformation = self.geodetic_crs().to_wgs84.as_ref()?;
+        Some(
+            transformation
+                .parameters
+                .iter()
+                .map(|parameter| parameter.value)
+                .collect(),
+        )
+    }
+
+    /// The geodetic CRS, or the base CRS of a projected CRS.
+    fn geodetic_crs(&self) -> &GeodeticCrs {
+        match self {
+            Crs::Geographic(crs) | Crs::Geocentric(crs) => crs,
+            Crs::Projected(crs) => &crs.base_crs,
+        }
+    }
+
+    fn geodetic_crs_mut(&mut self) -> &mut GeodeticCrs {
+        match self {
+            Crs::Geographic(crs) | Crs::Geocentric(crs) => crs,
+            Crs::Projected(crs) => &mut crs.base_crs,
+        }
+    }
+
+    /// WGS 84 as the target of the datum transformation, which is geocentric for geocentric CRSs.
+    fn wgs_84(&self) -> Self {
+        let crs = GeodeticCrs::from_epsg_code(WGS_84).expect("WGS 84 is a known CRS");
+        match self {
+            Crs::Geocentric(_) => Crs::Geocentric(GeodeticCrs {
+                epsg_code: Some(WGS_84_GEOCENTRIC),
+                ..crs
+            }),
+            _ => Crs::Geographic(crs),
+        }
+    }
+
+    /// Replaces the GeoKeys of the model CRS by those of this CRS, keeping the
+    /// GTRasterTypeGeoKey, which defaults to PixelIsArea, and the vertical CRS keys.
+    pub(crate) fn replace_geo_keys(&self, geo_keys: &mut GeoKeyDirectory) {
+        remove_model_crs_keys(geo_keys);
+        self.insert_geo_keys(geo_keys);
+    }
+
+    /// Replaces the GeoKeys of the model CRS by the model type and the ProjectedCSTypeGeoKey of
+    /// projected CRSs or the GeographicTypeGeoKey of geographic and geocentric CRSs only, for EPSG
+    /// codes without a built-in definition.
+    pub(crate) fn replace_geo_keys_by_epsg_code(
+        code: u16,
+        model_type: ModelType,
+        geo_keys: &mut GeoKeyDirectory,
+    ) {
+        remove_model_crs_keys(geo_keys);
+        geo_keys.set_model_type(model_type);
+        let key = match model_type {
+            ModelType::Projected => GeoKey::ProjectedCSType,
+            _ => GeoKey::GeographicType,
+        };
+        geo_keys.insert(key, GeoKeyValue::Short(vec![code]));
+    }
+
+    /// Inserts the GeoKeys referring to the CRS by its EPSG code, together with its units and
+    /// citations like GDAL writes them.
+    pub(crate) fn insert_geo_keys(&self, geo_keys: &mut GeoKeyDirectory) {
+        let short = |value: u16| GeoKeyValue::Short(vec![value]);
+        let (model_type, base_crs) = match self {
+            Crs::Geographic(crs) => (ModelType::Geographic, crs),
+            Crs::Geocentric(crs) => (ModelType::Geocentric, crs),
+            Crs::Projected(crs) => (ModelType::Projected, &crs.base_crs),
+        };
+        geo_keys.set_model_type(model_type);
+        if let Some(code) = base_crs
+            .epsg_code
+            .filter(|_| model_type != ModelType::Projected)
+        {
+            geo_keys.insert(GeoKey::GeographicType, short(code));
+        }
+        geo_keys.insert(
+            GeoKey::GeogCitation,
+            GeoKeyValue::Ascii(base_crs.name.clone()),
+        );
+        if let Some(code) = base_crs.angular_unit.epsg_code {
+            geo_keys.insert(GeoKey::GeogAngularUnits, short(code));
+        }
+        if let Some(values) = self.to_wgs84() {
+            geo_keys.insert(GeoKey::GeogTOWGS84, GeoKeyValue::Double(values));
+        }
+        if let Crs::Projected(crs) = self {
+            if let Some(code) = crs.epsg_code {
+                geo_keys.insert(GeoKey::ProjectedCSType, short(code));
+            }
+            geo_keys.insert(GeoKey::GTCitation, GeoKeyValue::Ascii(crs.name.clone()));
+            if let Some(code) = crs.linear_unit.epsg_code {
+                geo_keys.insert(GeoKey::ProjLinearUnits, short(code));
+            }
+        }
+    }
+
+    /// The CRS as WKT2 (ISO 19162:2019) string, which is a bound CRS if the transformation of
+    /// the datum to WGS 84 is given.
+    pub(crate) fn to_wkt(&self) -> String {
+        match &self.geodetic_crs().to_wgs84 {
+            Some(transformation) => format!(
+                "BOUNDCRS[SOURCECRS[{}],TARGETCRS[{}],{}]",
+                self.unbound_wkt(),
+                self.wgs_84().unbound_wkt(),
+                transformation.to_wkt("ABRIDGEDTRANSFORMATION")
+            ),
+            None => self.unbound_wkt(),
+        }
+    }
+
+    fn unbound_wkt(&self) -> String {
+        match self {
+            Crs::Geographic(crs) => {
+                let unit = crs.angular_unit.to_wkt();
+                format!(
+                    "GEOGCRS[{},{},{},CS[ellipsoidal,2],\
+                     AXIS[\"geodetic latitude (Lat)\",north,ORDER[1],{unit}],\
+                     AXIS[\"geodetic longitude (Lon)\",east,ORDER[2],{unit}]{}]",
+                    quote(&crs.name),
+                    crs.datum.to_wkt(),
+                    crs.prime_meridian.to_wkt(&crs.angular_unit),
+                    id(crs.epsg_code),
+                )
+            }
+            Crs::Geocentric(crs) => {
+                let unit = Unit::linear(METRE).expect("metre is a known unit").to_wkt();
+                format!(
+                    "GEODCRS[{},{},{},CS[Cartesian,3],\
+                     AXIS[\"(X)\",geocentricX,ORDER[1],{unit}],\
+                     AXIS[\"(Y)\",geocentricY,ORDER[2],{unit}],\
+                     AXIS[\"(Z)\",geocentricZ,ORDER[3],{unit}]{}]",
+                    quote(&crs.name),
+                    crs.datum.to_wkt(),
+                    crs.prime_meridian.to_wkt(&crs.angular_unit),
+                    id(crs.epsg_code),
+                )
+            }
+            Crs::Projected(crs) => {
+                let base_crs = &crs.base_crs;
+                let unit = crs.linear_unit.to_wkt();
+                format!(
+                    "PROJCRS[{},BASEGEOGCRS[{},{},{},{}{}],{},CS[Cartesian,2],\
+                     AXIS[\"(E)\",east,ORDER[1],{unit}],\
+                     AXIS[\"(N)\",north,ORDER[2],{unit}]{}]",
+                    quote(&crs.name),
+                    quote(&base_crs.name),
+                    base_crs.datum.to_wkt(),
+                    base_crs.prime_meridian.to_wkt(&base_crs.angular_unit),
+                    base_crs.angular_unit.to_wkt(),
+                    id(base_crs.epsg_code),
+                    crs.conversion.to_wkt("CONVERSION"),
+                    id(crs.epsg_code),
+                )
+            }
+        }
+    }
+
+    /// The CRS as PROJJSON string, see [`Crs::to_wkt`].
+    pub(crate) fn to_projjson(&self) -> String {
+        let members = match &self.geodetic_crs().to_wgs84 {
+            Some(transformation) => format!(
+                "\"type\":\"BoundCRS\",\"source_crs\":{{{}}},\"target_crs\":{{{}}},\
+                 \"transformation\":{}",
+                self.unbound_projjson_members(),
+                self.wgs_84().unbound_projjson_members(),
+                transformation.to_projjson()
+            ),
+            None => self.unbound_projjson_members(),
+        };
+        format!("{{\"$schema\":\"{}\",{}}}", PROJJSON_SCHEMA, members)
+    }
+
+    fn unbound_projjson_members(&self) -> String {
+        match self {
+            Crs::Geographic(crs) => {
+                format!("\"type\":\"GeographicCRS\",{}", crs.projjson_members(true))
+            }
+            Crs::Geocentric(crs) => {
+                format!("\"type\":\"GeodeticCRS\",{}", crs.projjson_members(false))
+            }
+            Crs::Projected(crs) => {
+                let unit = crs.linear_unit.to_projjson();
+                format!(
+                    "\"type\":\"ProjectedCRS\",\"name\":{},\
+                     \"base_crs\":{{\"type\":\"GeographicCRS\",{}}},\"conversion\":{},\
+                     \"coordinate_system\":{{\"subtype\":\"Cartesian\",\"axis\":[\
+                     {{\"name\":\"Easting\",\"abbreviation\":\"E\",\"direction\":\"east\",\"unit\":{unit}}},\
+                     {{\"name\":\"Northing\",\"abbreviation\":\"N\",\"direction\":\"north\",\"unit\":{unit}}}\
+                     ]}}{}",
+                    json_string(&crs.name),
+                    crs.base_crs.projjson_members(true),
+                    crs.conversion.to_projjson(),
+                    projjson_id(crs.epsg_code),
+                )
+            }
+        }
+    }
+
+    /// The CRS as PROJ string, e.g. `+proj=longlat +datum=WGS84 +no_defs +type=crs`.
+    pub(crate) fn to_proj4(&self) -> String {
+        let proj4 = match self {
+            Crs::Geographic(crs) => format!("+proj=longlat{}", crs.to_proj4()),
+            Crs::Geocentric(crs) => format!("+proj=geocent{} +units=m", crs.to_proj4()),
+            Crs::Projected(crs) if crs.conversion.method_epsg_code == PSEUDO_MERCATOR_METHOD => {
+                format!(
+                    "{} +a=6378137 +b=6378137 +nadgrids=@null +wktext{}",
+                    crs.conversion.to_proj4(),
+                    crs.linear_unit.to_proj4()
+                )
+            }
+            Crs::Projected(crs) => format!(
+                "{}{}{}",
+                crs.conversion.to_proj4(),
+                crs.base_crs.to_proj4(),
+                crs.linear_unit.to_proj4()
+            ),
+        };
+        format!("{} +no_defs +type=crs", proj4)
+    }
+}
+
+impl GeodeticCrs {
+    fn from_geo_keys(geo_keys: &GeoKeyDirectory) -> Option<Self> {
+        let code = geo_keys
+            .geographic_type()
+            .filter(|code| *code != GeoKeyDirectory::USER_DEFINED);
+        if let Some(crs) = code.and_then(Self::from_epsg_code) {
+            return Some(crs);
+        }
+
+        let angular_unit = match geo_keys.geog_angular_units() {
+            None => Unit::angular(DEGREE)?,
+            Some(GeoKeyDirectory::USER_DEFINED) => {
+                Unit::user_defined(UnitKind::Angle, geo_keys.geog_angular_unit_size()?)
+            }
+            Some(code) => Unit::angular(code)?,
+        };
+
+        let datum = match geo_keys.geog_geodetic_datum() {
+            Some(code) if code != GeoKeyDirectory::USER_DEFINED => Datum::from_epsg_code(code)?,
+            _ => Datum {
+                name: "unknown".to_string(),
+                ellipsoid: Ellipsoid::from_geo_keys(geo_keys)?,
+                epsg_code: None,
+            },
+        };
+
+        let prime_meridian = match (
+            geo_keys.geog_prime_meridian(),
+            geo_keys.geog_prime_meridian_long(),
+        ) {
+            (None | Some(GREENWICH), None) => PrimeMeridian::greenwich(),
+            (_, Some(longitude)) => PrimeMeridian {
+                name: "unknown".to_string(),
+                longitude,
+                epsg_code: None,
+            },
+            (Some(_), None) => return None,
+        };
+
+        Some(GeodeticCrs {
+            name: geo_keys
+                .geog_citation()
+                .or(geo_keys.citation())
+                .unwrap_or("unknown")
+                .to_string(),
+            datum,
+            prime_meridian,
+            angular_unit,
+            epsg_code: code,
+            to_wgs84: None,
+        })
+    }
+
+    fn from_epsg_code(code: u16) -> Option<Self> {
+        let &(_, name, datum_code) = GEODETIC_CRSS.iter().find(|crs| crs.0 == code)?;
+        Some(GeodeticCrs {
+            name: name.to_string(),
+            datum: Datum::from_epsg_code(datum_code)?,
+            prime_meridian: PrimeMeridian::greenwich(),
+            angular_unit: Unit::angular(DEGREE)?,
+            epsg_code: Some(code),
+            to_wgs84: None,
+        })
+    }
+
+    /// The members of the PROJJSON object of the CRS except its type.
+    fn projjson_members(&self, ellipsoidal: bool) -> String {
+        let coordinate_system = if ellipsoidal {
+            let unit = self.angular_unit.to_projjson();
+            format!(
+                "{{\"subtype\":\"ellipsoidal\",\"axis\":[\
+                 {{\"name\":\"Geodetic latitude\",\"abbreviation\":\"Lat\",\"direction\":\"north\",\"unit\":{unit}}},\
+                 {{\"name\":\"Geodetic longitude\",\"abbreviation\":\"Lon\",\"direction\":\"east\",\"unit\":{unit}}}\
+                 ]}}"
+            )
+        } else {
+            let axis = |name: &str| {
+                format!(
+                    "{{\"name\":\"Geocentric {name}\",\"abbreviation\":\"{name}\",\
+                     \"direction\":\"geocentric{name}\",\"unit\":\"metre\"}}"
+                )
+            };
+            format!(
+                "{{\"subtype\":\"Cartesian\",\"axis\":[{},{},{}]}}",
+                axis("X"),
+                axis("Y"),
+                axis("Z")
+            )
+        };
+        format!(
+            "\"name\":{},\"datum\":{},\"coordinate_system\":{}{}",
+            json_string(&self.name),
+            self.datum
+                .to_projjson(&self.prime_meridian, &self.angular_unit),
+            coordinate_system,
+            projjson_id(self.epsg_code)
+        )
+    }
+
+    /// The datum and prime meridian parameters of a PROJ string.
+    fn to_proj4(&self) -> String {
+        let datum_name = self
+            .datum
+            .epsg_code
+            .filter(|_| self.to_wgs84.is_none())
+            .and_then(proj4_datum_name);
+        let mut proj4 = match datum_name {
+            Some(name) => format!(" +datum={}", name),
+            None => self.datum.ellipsoid.to_proj4(),
+        };
+        if let Some(transformation) = &self.to_wgs84 {
+            let values = transformation
+                .parameters
+                .iter()
+                .map(|parameter| parameter.value.to_string())
+                .collect::<Vec<_>>();
+            write!(proj4, " +towgs84={}", values.join(",")).unwrap();
+        }
+        if self.prime_meridian.longitude != 0.0 {
+            write!(
+                proj4,
+                " +pm={}",
+                self.angular_unit.to_degrees(self.prime_meridian.longitude)
+            )
+            .unwrap();
+        }
+        proj4
+    }
+}
+
+impl ProjectedCrs {
+    fn from_geo_keys(geo_keys: &GeoKeyDirectory) -> Option<Self> {
+        let code = geo_keys
+            .projected_type()
+            .filter(|code| *code != GeoKeyDirectory::USER_DEFINED);
+        if let Some(crs) = code.and_then(Self::from_epsg_code) {
+            return Some(crs);
+        }
+
+        let base_crs = GeodeticCrs::from_geo_keys(geo_keys)?;
+        let linear_unit = match geo_keys.proj_linear_units() {
+            None => Unit::linear(METRE)?,
+            Some(GeoKeyDirectory::USER_DEFINED) => {
+                Unit::user_defined(UnitKind::Length, geo_keys.proj_linear_unit_size()?)
+            }
+            Some(code) => Unit::linear(code)?,
+        };
+        let conversion = match geo_keys.projection() {
+            Some(code) if code != GeoKeyDirectory::USER_DEFINED => {
+                Conversion::from_epsg_code(code)?
+            }
+            _ => Conversion::from_geo_keys(geo_keys, &base_crs.angular_unit, &linear_unit)?,
+        };
+
+        Some(ProjectedCrs {
+            name: geo_keys
+                .proj_citation()
+                .or(geo_keys.citation())
+                .unwrap_or("unknown")
+                .to_string(),
+            base_crs,
+            conversion,
+            linear_unit,
+            epsg_code: code,
+        })
+    }
+
+    fn from_epsg_code(code: u16) -> Option<Self> {
+        if code == PSEUDO_MERCATOR {
+            let base_crs = GeodeticCrs::from_epsg_code(WGS_84)?;
+            let degree = Unit::angular(DEGREE)?;
+            let metre = Unit::linear(METRE)?;
+            return Some(ProjectedCrs {
+                name: "WGS 84 / Pseudo-Mercator".to_string(),
+                base_crs,
+                conversion: Conversion {
+                    name: "Popular Visualisation Pseudo-Mercator".to_string(),
+                    method_name: "Popular Visualisation Pseudo Mercator",
+                    method_epsg_code: PSEUDO_MERCATOR_METHOD,
+                    parameters: vec![
+                        Parameter::new(LATITUDE_OF_NATURAL_ORIGIN, 0.0, &degree),
+                        Parameter::new(LONGITUDE_OF_NATURAL_ORIGIN, 0.0, &degree),
+                        Parameter::new(FALSE_EASTING, 0.0, &metre),
+                        Parameter::new(FALSE_NORTHING, 0.0, &metre),
+                    ],
+                },
+                linear_unit: metre,
+                epsg_code: Some(code),
+            });
+        }
+
+        let &(first_code, _, base_code, south) = UTM_CRSS
+            .iter()
+            .find(|(first_code, last_code, _, _)| (*first_code..=*last_code).contains(&code))?;
+        // The zone numbers of the NAD27 and ETRS89 families do not start at 1
+        let zone = code - first_code + first_code % 100;
+        let base_crs = GeodeticCrs::from_epsg_code(base_code)?;
+        let conversion = Conversion::utm(zone, south)?;
+        Some(ProjectedCrs {
+            name: format!("{} / {}", base_crs.name, conversion.name),
+            base_crs,
+            conversion,
+            linear_unit: Unit::linear(METRE)?,
+            epsg_code: Some(code),
+        })
+    }
+}
+
+impl Conversion {
+    /// Looks up a projection (ProjectionGeoKey) by its EPSG code, which is only supported for UTM
+    /// zones.
+    fn from_epsg_code(code: u16) -> Option<Self> {
+        match code {
+            16001..=16060 => Self::utm(code - 16000, false),
+            16101..=16160 => Self::utm(code - 16100, true),
+            _ => None,
+        }
+    }
+
+    fn from_geo_keys(
+        geo_keys: &GeoKeyDirectory,
+        angular_unit: &Unit,
+        linear_unit: &Unit,
+    ) -> Option<Self> {
+        let coord_trans = geo_keys.proj_coord_trans()?;
+        let method = METHODS
+            .iter()
+            .find(|method| method.coord_trans == coord_trans)?;
+        let scale_unit = Unit::scale();
+        let parameters = method
+            .parameters
+            .iter()
+            .map(|definition @ (_, _, kind, keys)| {
+                let value = keys.iter().find_map(|key| geo_keys.get_double(*key));
+                let unit = match kind {
+                    UnitKind::Angle => angular_unit,
+                    UnitKind::Length => linear_unit,
+                    UnitKind::Scale => &scale_unit,
+                };
+                let default = if *kind == UnitKind::Scale { 1.0 } else { 0.0 };
+                Parameter::new(*definition, value.unwrap_or(default), unit)
+            })
+            .collect();
+
+        Some(Conversion {
+            name: "unknown".to_string(),
+            method_name: method.name,
+            method_epsg_code: method.epsg_code,
+            parameters,
+        })
+    }
+
+    fn utm(zone: u16, south: bool) -> Option<Self> {
+        if !(1..=60).contains(&zone) {
+            return None;
+        }
+        let degree = Unit::angular(DEGREE)?;
+        let metre = Unit::linear(METRE)?;
+        let false_northing = if south { 10000000.0 } else { 0.0 };
+        Some(Conversion {
+            name: format!("UTM zone {}{}", zone, if south { 'S' } else { 'N' }),
+            method_name: TRANSVERSE_MERCATOR.name,
+            method_epsg_code: TRANSVERSE_MERCATOR.epsg_code,
+            parameters: vec![
+                Parameter::new(LATITUDE_OF_NATURAL_ORIGIN, 0.0, &degree),
+                Parameter::new(
+                    LONGITUDE_OF_NATURAL_ORIGIN,
+                    zone as f64 * 6.0 - 183.0,
+                    &degree,
+                ),
+                Parameter::new(SCALE_FACTOR_AT_NATURAL_ORIGIN, 0.9996, &Unit::scale()),
+                Parameter::new(FALSE_EASTING, 500000.0, &metre),
+                Parameter::new(FALSE_NORTHING, false_northing, &metre),
+            ],
+        })
+    }
+
+    /// The transformation to WGS 84 given by the 3 or 7 values of the GeogTOWGS84GeoKey, i.e.
+    /// the translations in meters, the rotations in arc-seconds and the scale difference in parts
+    /// per million, following the position vector convention.
+    fn to_wgs84(values: &[f64], geocentric: bool) -> Option<Self> {
+        let (method_name, method_epsg_code) = match (values.len(), geocentric) {
+            (3, false) => ("Geocentric translations (geog2D domain)", 9603),
+            (3, true) => ("Geocentric translations (geocentric domain)", 1031),
+            (7, false) => ("Position Vector transformation (geog2D domain)", 9606),
+            (7, true) => ("Position Vector transformation (geocentric domain)", 1033),
+            _ => return None,
+        };
+        let metre = Unit::linear(METRE)?;
+        let arc_second = Unit::angular(ARC_SECOND)?;
+        let parts_per_million = Unit {
+            kind: UnitKind::Scale,
+            name: "parts per million".to_string(),
+            conversion_factor: 1e-6,
+            epsg_code: Some(PARTS_PER_MILLION),
+        };
+        let parameters = TO_WGS84_PARAMETERS
+            .iter()
+            .zip(values)
+            .map(|(definition @ (_, _, kind, _), value)| {
+                let unit = match kind {
+                    UnitKind::Length => &metre,
+                    UnitKind::Angle => &arc_second,
+                    UnitKind::Scale => &parts_per_million,
+                };
+                Parameter::new(*definition, *value, unit)
+            })
+            .collect();
+
+        Some(Conversion {
+            name: "Transformation to WGS 84".to_string(),
+            method_name,
+            method_epsg_code,
+            parameters,
+        })
+    }
+
+    /// The operation as WKT with the given keyword, e.g. `CONVERSION`.
+    fn to_wkt(&self, keyword: &str) -> String {
+        let mut wkt = format!(
+            "{}[{},METHOD[{},ID[\"EPSG\",{}]]",
+            keyword,
+            quote(&self.name),
+            quote(self.method_name),
+            self.method_epsg_code
+        );
+        for parameter in &self.parameters {
+            write!(
+                wkt,
+                ",PARAMETER[{},{},{},ID[\"EPSG\",{}]]",
+                quote(parameter.name),
+                parameter.value,
+                parameter.unit.to_wkt(),
+                parameter.epsg_code
+            )
+            .unwrap();
+        }
+        wkt.push(']');
+        wkt
+    }
+
+    fn to_projjson(&self) -> String {
+        let parameters = self
+            .parameters
+            .iter()
+            .map(|parameter| {
+                format!(
+                    "{{\"name\":{},\"value\":{},\"unit\":{}{}}}",
+                    json_string(parameter.name),
+                    parameter.value,
+                    parameter.unit.to_projjson(),
+                    projjson_id(Some(parameter.epsg_code))
+                )
+            })
+            .collect::<Vec<_>>();
+        format!(
+            "{{\"name\":{},\"method\":{{\"name\":{}{}}},\"parameters\":[{}]}}",
+            json_string(&self.name),
+            json_string(self.method_name),
+            projjson_id(Some(self.method_epsg_code)),
+            parameters.join(",")
+        )
+    }
+
+    /// The projection parameters of a PROJ string, with angles in degrees and lengths in meters.
+    fn to_proj4(&self) -> String {
+        let mut proj4 = format!("+proj={}", proj4_method_name(self.method_epsg_code));
+        for parameter in &self.parameters {
+            let value = match parameter.unit.kind {
+                UnitKind::Angle => parameter.unit.to_degrees(parameter.value),
+                UnitKind::Length => parameter.value * parameter.unit.conversion_factor,
+                UnitKind::Scale => parameter.value,
+            };
+            let name = proj4_parameter_name(self.method_epsg_code, parameter.epsg_code);
+            write!(proj4, " +{}={}", name, value).unwrap();
+            // PROJ expects the standard parallel of the single parallel Lambert projection
+            if self.method_epsg_code == LAMBERT_CONIC_CONFORMAL_1SP_METHOD && name == "lat_0" {
+                write!(proj4, " +lat_1={}", value).unwrap();
+            }
+        }
+        proj4
+    }
+}
+
+impl Parameter {
+    fn new((name, epsg_code, _, _): ParameterDefinition, value: f64, unit: &Unit) -> Self {
+        Parameter {
+            name,
+            epsg_code,
+            value,
+            unit: unit.clone(),
+        }
+    }
+}
+
+impl Datum {
+    fn from_epsg_code(code: u16) -> Option<Self> {
+        let &(_, name, ellipsoid_code) = DATUMS.iter().find(|datum| datum.0 == code)?;
+        Some(Datum {
+            name: name.to_string(),
+            ellipsoid: Ellipsoid::from_epsg_code(ellipsoid_code)?,
+            epsg_code: Some(code),
+        })
+    }
+
+    fn to_wkt(&self) -> String {
+        format!(
+            "DATUM[{},{}{}]",
+            quote(&self.name),
+            self.ellipsoid.to_wkt(),
+            id(self.epsg_code)
+        )
+    }
+
+    fn to_projjson(&self, prime_meridian: &PrimeMeridian, angular_unit: &Unit) -> String {
+        format!(
+            "{{\"type\":\"GeodeticReferenceFrame\",\"name\":{},\"ellipsoid\":{},\
+             \"prime_meridian\":{}{}}}",
+            json_string(&self.name),
+            self.ellipsoid.to_projjson(),
+            prime_meridian.to_projjson(angular_unit),
+            projjson_id(self.epsg_code)
+        )
+    }
+}
+
+impl Ellipsoid {
+    fn from_geo_keys(geo_keys: &GeoKeyDirectory) -> Option<Self> {
+        if let Some(code) = geo_keys
+            .geog_ellipsoid()
+            .filter(|code| *code != GeoKeyDirectory::USER_DEFINED)
+        {
+            return Self::from_epsg_code(code);
+        }
+
+        let unit_size = match geo_keys.geog_linear_units() {
+            None => 1.0,
+            Some(GeoKeyDirectory::USER_DEFINED) => geo_keys.geog_linear_unit_size()?,
+            Some(code) => Unit::linear(code)?.conversion_factor,
+        };
+        let semi_major_axis = geo_keys.geog_semi_major_axis()?;
+        let inverse_flattening = match (
+            geo_keys.geog_inv_flattening(),
+            geo_keys.geog_semi_minor_axis(),
+        ) {
+            (Some(inverse_flattening), _) => inverse_flattening,
+            (None, Some(semi_minor_axis)) if semi_minor_axis == semi_major_axis => 0.0,
+            (None, Some(semi_minor_axis)) => semi_major_axis / (semi_major_axis - semi_minor_axis),
+            (None, None) => return None,
+        };
+
+        Some(Ellipsoid {
+            name: "unknown".to_string(),
+            semi_major_axis: semi_major_axis * unit_size,
+            inverse_flattening,
+            epsg_code: None,
+        })
+    }
+
+    fn from_epsg_code(code: u16) -> Option<Self> {
+        let &(_, name, semi_major_axis, inverse_flattening) =
+            ELLIPSOIDS.iter().find(|ellipsoid| ellipsoid.0 == code)?;
+        Some(Ellipsoid {
+            name: name.to_string(),
+            semi_major_axis,
+            inverse_flattening,
+            epsg_code: Some(code),
+        })
+    }
+
+    fn to_wkt(&self) -> String {
+        format!(
+            "ELLIPSOID[{},{},{},LENGTHUNIT[\"metre\",1]{}]",
+            quote(&self.name),
+            self.semi_major_axis,
+            self.inverse_flattening,
+            id(self.epsg_code)
+        )
+    }
+
+    fn to_projjson(&self) -> String {
+        let axes = if self.inverse_flattening == 0.0 {
+            format!("\"radius\":{}", self.semi_major_axis)
+        } else {
+            format!(
+                "\"semi_major_axis\":{},\"inverse_flattening\":{}",
+                self.semi_major_axis, self.inverse_flattening
+            )
+        };
+        format!(
+            "{{\"name\":{},{}{}}}",
+            json_string(&self.name),
+            axes,
+            projjson_id(self.epsg_code)
+        )
+    }
+
+    fn to_proj4(&self) -> String {
+        match self.epsg_code.and_then(proj4_ellipsoid_name) {
+            Some(name) => format!(" +ellps={}", name),
+            None if self.inverse_flattening == 0.0 => format!(" +R={}", self.semi_major_axis),
+            None => format!(
+                " +a={} +rf={}",
+                self.semi_major_axis, self.inverse_flattening
+            ),
+        }
+    }
+}
+
+impl PrimeMeridian {
+    fn greenwich() -> Self {
+        PrimeMeridian {
+            name: "Greenwich".to_string(),
+            longitude: 0.0,
+            epsg_code: Some(GREENWICH),
+        }
+    }
+
+    fn to_wkt(&self, angular_unit: &Unit) -> String {
+        format!(
+            "PRIMEM[{},{},{}{}]",
+            quote(&self.name),
+            self.longitude,
+            angular_unit.to_wkt(),
+            id(self.epsg_code)
+        )
+    }
+
+    fn to_projjson(&self, angular_unit: &Unit) -> String {
+        let longitude = if angular_unit.epsg_code == Some(DEGREE) {
+            self.longitude.to_string()
+        } else {
+            format!(
+                "{{\"value\":{},\"unit\":{}}}",
+                self.longitude,
+                angular_unit.to_projjson()
+            )
+        };
+        format!(
+            "{{\"name\":{},\"longitude\":{}{}}}",
+            json_string(&self.name),
+            longitude,
+            projjson_id(self.epsg_code)
+        )
+    }
+}
+
+impl Unit {
+    fn linear(code: u16) -> Option<Self> {
+        Self::from_table(UnitKind::Length, LINEAR_UNITS, code)
+    }
+
+    fn angular(code: u16) -> Option<Self> {
+        Self::from_table(UnitKind::Angle, ANGULAR_UNITS, code)
+    }
+
+    fn scale() -> Self {
+        Unit {
+            kind: UnitKind::Scale,
+            name: "unity".to_string(),
+            conversion_factor: 1.0,
+            epsg_code: Some(UNITY),
+        }
+    }
+
+    fn user_defined(kind: UnitKind, conversion_factor: f64) -> Self {
+        Unit {
+            kind,
+            name: "unknown".to_string(),
+            conversion_factor,
+            epsg_code: None,
+        }
+    }
+
+    fn from_table(kind: UnitKind, table: &[(u16, &str, f64)], code: u16) -> Option<Self> {
+        let &(_, name, conversion_factor) = table.iter().find(|unit| unit.0 == code)?;
+        Some(Unit {
+            kind,
+            name: name.to_string(),
+            conversion_factor,
+            epsg_code: Some(code),
+        })
+    }
+
+    fn to_wkt(&self) -> String {
+        let keyword = match self.kind {
+            UnitKind::Length => "LENGTHUNIT",
+            UnitKind::Angle => "ANGLEUNIT",
+            UnitKind::Scale => "SCALEUNIT",
+        };
+        format!(
+            "{}[{},{}{}]",
+            keyword,
+            quote(&self.name),
+            self.conversion_factor,
+            id(self.epsg_code)
+        )
+    }
+
+    fn to_degrees(&self, value: f64) -> f64 {
+        if self.epsg_code == Some(DEGREE) {
+            value
+        } else {
+            value * self.conversion_factor / 0.0174532925199433
+        }
+    }
+
+    /// The unit as PROJJSON value, which is just the name for the most common units.
+    fn to_projjson(&self) -> String {
+        match self.epsg_code {
+            Some(METRE) => "\"metre\"".to_string(),
+            Some(DEGREE) => "\"degree\"".to_string(),
+            Some(UNITY) => "\"unity\"".to_string(),
+            _ => {
+                let unit_type = match self.kind {
+                    UnitKind::Length => "LinearUnit",
+                    UnitKind::Angle => "AngularUnit",
+                    UnitKind::Scale => "ScaleUnit",
+                };
+                format!(
+                    "{{\"type\":\"{}\",\"name\":{},\"conversion_factor\":{}{}}}",
+                    unit_type,
+                    json_string(&self.name),
+                    self.conversion_factor,
+                    projjson_id(self.epsg_code)
+                )
+            }
+        }
+    }
+
+    /// The linear unit parameter of a PROJ string.
+    fn to_proj4(&self) -> String {
+        match self.epsg_code.and_then(proj4_unit_name) {
+            Some(name) => format!(" +units={}", name),
+            None => format!(" +to_meter={}", self.conversion_factor),
+        }
+    }
+}
+
+/// Removes the GeoKeys of the model CRS except for the GTRasterTypeGeoKey, which defaults to
+/// PixelIsArea, keeping the vertical CRS keys.
+fn remove_model_crs_keys(geo_keys: &mut GeoKeyDirectory) {
+    let model_crs_keys = geo_keys
+        .keys()
+        .map(|(key, _)| key)
+        .filter(|key| *key != GeoKey::GTRasterType && key.to_u16() < 4096)
+        .collect::<Vec<_>>();
+    for key in model_crs_keys {
+        geo_keys.remove(key);
+    }
+    if geo_keys.raster_type().is_none() {
+        geo_keys.set_raster_type(RasterType::PixelIsArea);
+    }
+}
+
+/// The size of the linear unit with the given EPSG code in meters.
+pub(crate) fn linear_unit_size(code: u16) -> Option<f64> {
+    Unit::linear(code).map(|unit| unit.conversion_factor)
+}
+
+/// The size of the angular unit with the given EPSG code in radians.
+pub(crate) fn angular_unit_size(code: u16) -> Option<f64> {
+    Unit::angular(code).map(|unit| unit.conversion_factor)
+}
+
+/// Quotes a WKT string, escaping quotes by doubling them.
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('"', "\"\""))
+}
+
+fn id(epsg_code: Option<u16>) -> String {
+    epsg_code
+        .map(|code| format!(",ID[\"EPSG\",{}]", code))
+        .unwrap_or_default()
+}
+
+/// Quotes a JSON string, escaping quotes, backslashes and control characters.
+fn json_string(text: &str) -> String {
+    let mut json = String::with_capacity(text.len() + 2);
+    json.push('"');
+    for character in text.chars() {
+        match character {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            character if character.is_control() => {
+                write!(json, "\\u{:04x}", character as u32).unwrap()
+            }
+            character => json.push(character),
+        }
+    }
+    json.push('"');
+    json
+}
+
+fn projjson_id(epsg_code: Option<u16>) -> String {
+    epsg_code
+        .map(|code| format!(",\"id\":{{\"authority\":\"EPSG\",\"code\":{}}}", code))
+        .unwrap_or_default()
+}
+
+fn proj4_method_name(method_epsg_code: u16) -> &'static str {
+    match method_epsg_code {
+        9801 | 9802 => "lcc",
+        9804 | PSEUDO_MERCATOR_METHOD => "merc",
+        9806 => "cass",
+        9807 => "tmerc",
+        9809 => "sterea",
+        9810 => "stere",
+        9815 => "omerc",
+        9820 => "laea",
+        9822 => "aea",
+        1028 => "eqc",
+        _ => unreachable!("unsupported projection method {}", method_epsg_code),
+    }
+}
+
+fn proj4_parameter_name(method_epsg_code: u16, parameter_epsg_code: u16) -> &'static str {
+    match parameter_epsg_code {
+        8801 | 8811 | 8821 => "lat_0",
+        8802 | 8822 => "lon_0",
+        8805 | 8815 => "k_0",
+        8806 | 8816 | 8826 => "x_0",
+        8807 | 8817 | 8827 => "y_0",
+        8812 => "lonc",
+        8813 => "alpha",
+        8814 => "gamma",
+        // The Equidistant Cylindrical projection has a single latitude of true scale
+        8823 if method_epsg_code == 1028 => "lat_ts",
+        8823 => "lat_1",
+        8824 => "lat_2",
+        _ => unreachable!("unsupported projection parameter {}", parameter_epsg_code),
+    }
+}
+
+fn proj4_datum_name(datum_epsg_code: u16) -> Option<&'static str> {
+    match datum_epsg_code {
+        6267 => Some("NAD27"),
+        6269 => Some("NAD83"),
+        6326 => Some("WGS84"),
+        _ => None,
+    }
+}
+
+fn proj4_ellipsoid_name(ellipsoid_epsg_code: u16) -> Option<&'static str> {
+    match ellipsoid_epsg_code {
+        7004 => Some("bessel"),
+        7008 => Some("clrk66"),
+        7019 => Some("GRS80"),
+        7022 => Some("intl"),
+        7030 => Some("WGS84"),
+        _ => None,
+    }
+}
+
+fn proj4_unit_name(unit_epsg_code: u16) -> Option<&'static str> {
+    match unit_epsg_code {
+        9001 => Some("m"),
+        9002 => Some("ft"),
+        9003 => Some("us-ft"),
+        9030 => Some("kmi"),
+        9036 => Some("km"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn geo_keys(directory_data: Vec<u16>, double_params: Vec<f64>) -> GeoKeyDirectory {
+        GeoKeyDirectory::from_tag_data(directory_data, Some(double_params), None).unwrap()
+    }
+
+    #[test]
+    fn test_utm_wkt() {
+        let crs = Crs::from_geo_keys(&geo_keys(
+            vec![1, 1, 0, 2, 1024, 0, 1, 1, 3072, 0, 1, 32632],
+            vec![],
+        ))
+        .unwrap();
+
+        assert_eq!(
+            crs.to_wkt(),
+            "PROJCRS[\"WGS 84 / UTM zone 32N\",BASEGEOGCRS[\"WGS 84\",\
+             DATUM[\"World Geodetic System 1984\",\
+             ELLIPSOID[\"WGS 84\",6378137,298.257223563,LENGTHUNIT[\"metre\",1],ID[\"EPSG\",7030]],\
+             ID[\"EPSG\",6326]],\
+             PRIMEM[\"Greenwich\",0,ANGLEUNIT[\"degree\",0.0174532925199433,ID[\"EPSG\",9102]],\
+             ID[\"EPSG\",8901]],\
+             ANGLEUNIT[\"degree\",0.0174532925199433,ID[\"EPSG\",9102]],ID[\"EPSG\",4326]],\
+             CONVERSION[\"UTM zone 32N\",METHOD[\"Transverse Mercator\",ID[\"EPSG\",9807]],\
+             PARAMETER[\"Latitude of natural origin\",0,\
+             ANGLEUNIT[\"degree\",0.0174532925199433,ID[\"EPSG\",9102]],ID[\"EPSG\",8801]],\
+             PARAMETER[\"Longitude of natural origin\",9,\
+             ANGLEUNIT[\"degree\",0.0174532925199433,ID[\"EPSG\",9102]],ID[\"EPSG\",8802]],\
+             PARAMETER[\"Scale factor at natural origin\",0.9996,\
+             SCALEUNIT[\"unity\",1,ID[\"EPSG\",9201]],ID[\"EPSG\",8805]],\
+             PARAMETER[\"False easting\",500000,\
+             LENGTHUNIT[\"metre\",1,ID[\"EPSG\",9001]],ID[\"EPSG\",8806]],\
+             PARAMETER[\"False northing\",0,\
+             LENGTHUNIT[\"metre\",1,ID[\"EPSG\",9001]],ID[\"EPSG\",8807]]],\
+             CS[Cartesian,2],\
+             AXIS[\"(E)\",east,ORDER[1],LENGTHUNIT[\"metre\",1,ID[\"EPSG\",9001]]],\
+             AXIS[\"(N)\",north,ORDER[2],LENGTHUNIT[\"metre\",1,ID[\"EPSG\",9001]]],\
+             ID[\"EPSG\",32632]]"
+        );
+    }
+
+    #[test]
+    fn test_utm_zone_numbers() {
+        let name = |code| match ProjectedCrs::from_epsg_code(code) {
+            Some(crs) => crs.name,
+            None => String::new(),
+        };
+
+        assert_eq!(name(32760), "WGS 84 / UTM zone 60S");
+        assert_eq!(name(25832), "ETRS89 / UTM zone 32N");
+        assert_eq!(name(26710), "NAD27 / UTM zone 10N");
+        assert_eq!(name(26761), "");
+    }
+
+    #[test]
+    fn test_geographic_wkt() {
+        let crs = Crs::from_geo_keys(&geo_keys(
+            vec![1, 1, 0, 2, 1024, 0, 1, 2, 2048, 0, 1, 4258],
+            vec![],
+        ))
+        .unwrap();
+
+        assert!(crs.to_wkt().starts_with(
+            "GEOGCRS[\"ETRS89\",DATUM[\"European Terrestrial Reference System 1989\","
+        ));
+        assert!(crs.to_wkt().ends_with(",ID[\"EPSG\",4258]]"));
+    }
+
+    #[test]
+    fn test_user_defined_wkt() {
+        let crs = Crs::from_geo_keys(&geo_keys(
+            vec![
+                1, 1, 0, 11, //
+                1024, 0, 1, 1, //
+                2048, 0, 1, 32767, //
+                2057, 34736, 1, 0, //
+                2059, 34736, 1, 1, //
+                3072, 0, 1, 32767, //
+                3074, 0, 1, 32767, //
+                3075, 0, 1, 8, //
+                3076, 0, 1, 9002, //
+                3078, 34736, 1, 2, //
+                3079, 34736, 1, 3, //
+                3082, 34736, 1, 4, //
+            ],
+            vec![6378137.0, 298.257222101, 33.0, 45.0, 2000000.0],
+        ))
+        .unwrap();
+        let wkt = crs.to_wkt();
+
+        assert!(wkt.starts_with("PROJCRS[\"unknown\",BASEGEOGCRS[\"unknown\",DATUM[\"unknown\","));
+        assert!(wkt.contains("ELLIPSOID[\"unknown\",6378137,298.257222101,"));
+        assert!(wkt.contains("METHOD[\"Lambert Conic Conformal (2SP)\",ID[\"EPSG\",9802]]"));
+        assert!(wkt.contains("PARAMETER[\"Latitude of 2nd standard parallel\",45,"));
+        assert!(wkt.contains(
+            "PARAMETER[\"Easting at false origin\",2000000,\
+             LENGTHUNIT[\"foot\",0.3048,ID[\"EPSG\",9002]],ID[\"EPSG\",8826]]"
+        ));
+        assert!(!wkt.ends_with(",ID[\"EPSG\",32767]]"));
+    }
+
+    #[test]
+    fn test_utm_projjson() {
+        let crs = ProjectedCrs::from_epsg_code(32632).unwrap();
+
+        assert_eq!(
+            Crs::Projected(crs).to_projjson(),
+            "{\"$schema\":\"https://proj.org/schemas/v0.7/projjson.schema.json\",\
+             \"type\":\"ProjectedCRS\",\"name\":\"WGS 84 / UTM zone 32N\",\
+             \"base_crs\":{\"type\":\"GeographicCRS\",\"name\":\"WGS 84\",\
+             \"datum\":{\"type\":\"GeodeticReferenceFrame\",\"name\":\"World Geodetic System 1984\",\
+             \"ellipsoid\":{\"name\":\"WGS 84\",\"semi_major_axis\":6378137,\
+             \"inverse_flattening\":298.257223563,\"id\":{\"authority\":\"EPSG\",\"code\":7030}},\
+             \"prime_meridian\":{\"name\":\"Greenwich\",\"longitude\":0,\
+             \"id\":{\"authority\":\"EPSG\",\"code\":8901}},\
+             \"id\":{\"authority\":\"EPSG\",\"code\":6326}},\
+             \"coordinate_system\":{\"subtype\":\"ellipsoidal\",\"axis\":[\
+             {\"name\":\"Geodetic latitude\",\"abbreviation\":\"Lat\",\"direction\":\"north\",\"unit\":\"degree\"},\
+             {\"name\":\"Geodetic longitude\",\"abbreviation\":\"Lon\",\"direction\":\"east\",\"unit\":\"degree\"}]},\
+             \"id\":{\"authority\":\"EPSG\",\"code\":4326}},\
+             \"conversion\":{\"name\":\"UTM zone 32N\",\"method\":{\"name\":\"Transverse Mercator\",\
+             \"id\":{\"authority\":\"EPSG\",\"code\":9807}},\"parameters\":[\
+             {\"name\":\"Latitude of natural origin\",\"value\":0,\"unit\":\"degree\",\
+             \"id\":{\"authority\":\"EPSG\",\"code\":8801}},\
+             {\"name\":\"Longitude of natural origin\",\"value\":9,\"unit\":\"degree\",\
+             \"id\":{\"authority\":\"EPSG\",\"code\":8802}},\
+             {\"name\":\"Scale factor at natural origin\",\"value\":0.9996,\"unit\":\"unity\",\
+             \"id\":{\"authority\":\"EPSG\",\"code\":8805}},\
+             {\"name\":\"False easting\",\"value\":500000,\"unit\":\"metre\",\
+             \"id\":{\"authority\":\"EPSG\",\"code\":8806}},\
+             {\"name\":\"False northing\",\"value\":0,\"unit\":\"metre\",\
+             \"id\":{\"authority\":\"EPSG\",\"code\":8807}}]},\
+             \"coordinate_system\":{\"subtype\":\"Cartesian\",\"axis\":[\
+             {\"name\":\"Easting\",\"abbreviation\":\"E\",\"direction\":\"east\",\"unit\":\"metre\"},\
+             {\"name\":\"Northing\",\"abbreviation\":\"N\",\"direction\":\"north\",\"unit\":\"metre\"}]},\
+             \"id\":{\"authority\":\"EPSG\",\"code\":32632}}"
+        );
+    }
+
+    #[test]
+    fn test_proj4() {
+        let proj4 = |code| Crs::Projected(ProjectedCrs::from_epsg_code(code).unwrap()).to_proj4();
+
+        assert_eq!(
+            proj4(32733),
+            "+proj=tmerc +lat_0=0 +lon_0=15 +k_0=0.9996 +x_0=500000 +y_0=10000000 \
+             +datum=WGS84 +units=m +no_defs +type=crs"
+        );
+        assert_eq!(
+            proj4(25832),
+            "+proj=tmerc +lat_0=0 +lon_0=9 +k_0=0.9996 +x_0=500000 +y_0=0 \
+             +ellps=GRS80 +units=m +no_defs +type=crs"
+        );
+        assert_eq!(
+            proj4(3857),
+            "+proj=merc +lat_0=0 +lon_0=0 +x_0=0 +y_0=0 \
+             +a=6378137 +b=6378137 +nadgrids=@null +wktext +units=m +no_defs +type=crs"
+        );
+        assert_eq!(
+            Crs::Geographic(GeodeticCrs::from_epsg_code(4326).unwrap()).to_proj4(),
+            "+proj=longlat +datum=WGS84 +no_defs +type=crs"
+        );
+    }
+
+    #[test]
+    fn test_user_defined_proj4() {
+        let crs = Crs::from_geo_keys(&geo_keys(
+            vec![
+                1, 1, 0, 9, //
+                1024, 0, 1, 1, //
+                2048, 0, 1, 32767, //
+                2057, 34736, 1, 0, //
+                2058, 34736, 1, 0, //
+                3072, 0, 1, 32767, //
+                3075, 0, 1, 9, //
+                3076, 0, 1, 9002, //
+                3081, 34736, 1, 1, //
+                3082, 34736, 1, 2, //
+            ],
+            vec![6371000.0, 40.0, 1000.0],
+        ))
+        .unwrap();
+
+        assert_eq!(
+            crs.to_proj4(),
+            "+proj=lcc +lat_0=40 +lat_1=40 +lon_0=0 +k_0=1 +x_0=304.8 +y_0=0 \
+             +R=6371000 +units=ft +no_defs +type=crs"
+        );
+        assert!(crs
+            .to_projjson()
+            .contains("\"ellipsoid\":{\"name\":\"unknown\",\"radius\":6371000}"));
+    }
+
+    #[test]
+    fn test_to_wgs84() {
+        let crs = Crs::from_geo_keys(&geo_keys(
+            vec![
+                1, 1, 0, 3, //
+                1024, 0, 1, 2, //
+                2048, 0, 1, 4314, //
+                2062, 34736, 7, 0, //
+            ],
+            vec![598.1, 73.7, 418.2, 0.202, 0.045, -2.455, 6.7],
+        ))
+        .unwrap();
+
+        assert_eq!(
+            crs.to_wgs84(),
+            Some(vec![598.1, 73.7, 418.2, 0.202, 0.045, -2.455, 6.7])
+        );
+        assert_eq!(
+            crs.to_proj4(),
+            "+proj=longlat +ellps=bessel +towgs84=598.1,73.7,418.2,0.202,0.045,-2.455,6.7 \
+             +no_defs +type=crs"
+        );
+        let wkt = crs.to_wkt();
+        assert!(wkt.starts_with("BOUNDCRS[SOURCECRS[GEOGCRS[\"DHDN\","));
+        assert!(wkt.contains(",ID[\"EPSG\",4314]]],TARGETCRS[GEOGCRS[\"WGS 84\","));
+        assert!(wkt.contains(
+            "],ABRIDGEDTRANSFORMATION[\"Transformation to WGS 84\",\
+             METHOD[\"Position Vector transformation (geog2D domain)\",ID[\"EPSG\",9606]],\
+             PARAMETER[\"X-axis translation\",598.1,LENGTHUNIT[\"metre\",1,ID[\"EPSG\",9001]],\
+             ID[\"EPSG\",8605]],"
+        ));
+        assert!(wkt.ends_with(
+            "PARAMETER[\"Scale difference\",6.7,\
+             SCALEUNIT[\"parts per million\",0.000001,ID[\"EPSG\",9202]],ID[\"EPSG\",8611]]]]"
+        ));
+        let projjson = crs.to_projjson();
+        assert!(projjson.contains(
+            "\"type\":\"BoundCRS\",\"source_crs\":{\"type\":\"GeographicCRS\",\"name\":\"DHDN\","
+        ));
+        assert!(projjson.contains(
+            "\"transformation\":{\"name\":\"Transformation to WGS 84\",\
+             \"method\":{\"name\":\"Position Vector transformation (geog2D domain)\","
+        ));
+
+        // The keys of the datum transformation are written along with those of the CRS
+        let mut directory = GeoKeyDirectory::default();
+        crs.replace_geo_keys(&mut directory);
+        assert_eq!(
+            Crs::from_geo_keys(&directory).unwrap().to_wgs84(),
+            crs.to_wgs84()
+        );
+
+        let crs = Crs::from_geo_keys(&geo_keys(
+            vec![1, 1, 0, 2, 3072, 0, 1, 32632, 2062, 34736, 3, 0],
+            vec![1.0, 2.0, 3.0],
+        ))
+        .unwrap();
+        assert_eq!(
+            crs.to_proj4(),
+            "+proj=tmerc +lat_0=0 +lon_0=9 +k_0=0.9996 +x_0=500000 +y_0=0 \
+             +ellps=WGS84 +towgs84=1,2,3 +units=m +no_defs +type=crs"
+        );
+        assert!(crs
+            .to_wkt()
+            .contains("METHOD[\"Geocentric translations (geog2D domain)\",ID[\"EPSG\",9603]]"));
+
+        // Datum transformations of an unexpected number of parameters are ignored
+        let crs = Crs::from_geo_keys(&geo_keys(
+            vec![1, 1, 0, 2, 3072, 0, 1, 32632, 2062, 34736, 2, 0],
+            vec![1.0, 2.0],
+        ))
+        .unwrap();
+        assert_eq!(crs.to_wgs84(), None);
+        assert!(crs.to_wkt().starts_with("PROJCRS["));
+    }
+
+    #[test]
+    fn test_json_string() {
+        assert_eq!(json_string("a \"b\"\\\n"), "\"a \\\"b\\\"\\\\\\u000a\"");
+    }
+
+    #[test]
+    fn test_unsupported_crs() {
+        assert_eq!(Crs::from_geo_keys(&GeoKeyDirectory::default()), None);
+        assert_eq!(
+            Crs::from_geo_keys(&geo_keys(vec![1, 1, 0, 1, 3072, 0, 1, 2056], vec![])),
+            None
+        );
+    }
+}
@@ -0,0 +1,83 @@
+use proj::Proj;
+
+use crate::coordinate_transform::CoordinateTransform;
+use crate::{GeoTiffError, GeoTiffResult};
+
+/// A transformation between raster space and a target CRS, combining the transformation between
+/// raster space and model space with a PROJ transformation between the model CRS and the target
+/// CRS.
+///
+/// Coordinates in the target CRS are given as longitude/latitude or easting/northing, regardless
+/// of the axis order of its definition.
+#[derive(Debug)]
+pub struct CrsTransform {
+    coordinate_transform: CoordinateTransform,
+    to_target: Proj,
+    to_model: Proj,
+}
+
+impl CrsTransform {
+    /// Creates a transformation from the model CRS, given as PROJ-compatible definition (e.g.
+    /// `EPSG:32632` or WKT), to the target CRS.
+    pub(crate) fn new(
+        coordinate_transform: CoordinateTransform,
+        model_crs: &str,
+        target_crs: &str,
+    ) -> GeoTiffResult<Self> {
+        let create = |from: &str, to: &str| {
+            Proj::new_known_crs(from, to, None).map_err(|err| {
+                GeoTiffError::Unsupported(format!(
+                    "Cannot create transformation to {}: {}",
+                    target_crs, err
+                ))
+            })
+        };
+        Ok(CrsTransform {
+            coordinate_transform,
+            to_target: create(model_crs, target_crs)?,
+            to_model: create(target_crs, model_crs)?,
+        })
+    }
+
+    /// The transformation between raster space and model space.
+    pub fn coordinate_transform(&self) -> &CoordinateTransform {
+        &self.coordinate_transform
+    }
+
+    /// Transforms a raster coordinate (column, row) to the target CRS.
+    pub fn transform_to_target(&self, coord: [usize; 2]) -> GeoTiffResult<[f64; 2]> {
+        let [x, y] = self.coordinate_transform.transform_to_model(coord);
+        let (x, y) = self.to_target.convert((x, y)).map_err(projection_error)?;
+        Ok([x, y])
+    }
+
+    /// Transforms a fractional raster coordinate (column, row) to the target CRS.
+    pub fn transform_to_target_f64(&self, coord: [f64; 2]) -> GeoTiffResult<[f64; 2]> {
+        let [x, y] = self.coordinate_transform.transform_to_model_f64(coord);
+        let (x, y) = self.to_target.convert((x, y)).map_err(projection_error)?;
+        Ok([x, y])
+    }
+
+    /// Transforms a coordinate of the target CRS to the raster coordinate (column, row) of the
+    /// containing pixel.
+    pub fn transform_to_raster(&self, coord: [f64; 2]) -> GeoTiffResult<[usize; 2]> {
+        let (x, y) = self
+            .to_model
+            .convert((coord[0], coord[1]))
+            .map_err(projection_error)?;
+        Ok(self.coordinate_transform.transform_to_raster([x, y]))
+    }
+
+    /// Transforms a coordinate of the target CRS to a fractional raster coordinate (column, row).
+    pub fn transform_to_raster_f64(&self, coord: [f64; 2]) -> GeoTiffResult<[f64; 2]> {
+        let (x, y) = self
+            .to_model
+            .convert((coord[0], coord[1]))
+            .map_err(projection_error)?;
+        Ok(self.coordinate_transform.transform_to_raster_f64([x, y]))
+    }
+}
+
+fn projection_error(err: proj::ProjError) -> GeoTiffError {
+    GeoTiffError::OutOfBounds(err.to_string())
+}
@@ -0,0 +1,196 @@
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::io;
+
+use tiff::TiffError;
+
+/// The result of reading or writing a GeoTIFF.
+pub type GeoTiffResult<T> = Result<T, GeoTiffError>;
+
+/// An error reading or writing a GeoTIFF, classified such that callers can tell e.g. a corrupt
+/// file from a query beyond the raster.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum GeoTiffError {
+    /// Reading or writing the underlying data failed
+    Io(io::Error),
+    /// The TIFF structure is invalid, e.g. a tag or a strip or tile is malformed or missing
+    Tiff(TiffError),
+    /// The GeoKeys or georeferencing tags violate the GeoTIFF specification, contradict each
+    /// other, or are missing for an operation requiring them
+    GeoKey(String),
+    /// The file or the operation requires a feature that is not supported, e.g. a compression
+    /// method, sample format or CRS
+    Unsupported(String),
+    /// A query exceeds the raster, e.g. a window, band, tile or overview that does not exist
+    OutOfBounds(String),
+    /// An argument is invalid, e.g. an empty target size or a buffer of the wrong length
+    InvalidArgument(String),
+    /// A request of a remote source failed, transiently or permanently, see
+    /// [`GeoTiffError::is_transient`]
+    Remote(RemoteError),
+}
+
+impl GeoTiffError {
+    /// Whether the operation may succeed if repeated later, as a request of a remote source
+    /// failed transiently, see [`RemoteError::is_transient`].
+    pub fn is_transient(&self) -> bool {
+        matches!(self, GeoTiffError::Remote(err) if err.is_transient())
+    }
+}
+
+/// A failed request of a remote source, telling failures that may succeed when repeated, like
+/// timeouts, dropped connections or an overloaded server, from permanent ones, like a missing
+/// file or denied access.
+///
+/// A `RangeSource` returns it wrapped in an [`io::Error`], which is
+/// unwrapped into [`GeoTiffError::Remote`] by the reader.
+#[derive(Debug)]
+pub struct RemoteError {
+    transient: bool,
+    attempts: u32,
+    source: io::Error,
+}
+
+impl RemoteError {
+    /// A failure that may not recur when the request is repeated.
+    pub fn transient(source: io::Error) -> Self {
+        Self {
+            transient: true,
+            attempts: 1,
+            source,
+        }
+    }
+
+    /// A failure that recurs when the request is repeated.
+    pub fn permanent(source: io::Error) -> Self {
+        Self {
+            transient: false,
+            attempts: 1,
+            source,
+        }
+    }
+
+    pub fn is_transient(&self) -> bool {
+        self.transient
+    }
+
+    /// The number of times the request was made, including retries.
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    #[cfg_attr(not(any(feature = "http", feature = "object_store")), allow(dead_code))]
+    pub(crate) fn with_attempts(self, attempts: u32) -> Self {
+        Self { attempts, ..self }
+    }
+}
+
+impl Display for RemoteError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.attempts {
+            1 => self.source.fmt(f),
+            attempts => write!(f, "{} (after {} attempts)", self.source, attempts),
+        }
+    }
+}
+
+impl Error for RemoteError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+impl Display for GeoTiffError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            GeoTiffError::Io(err) => write!(f, "I/O error: {}", err),
+            GeoTiffError::Tiff(err) => err.fmt(f),
+            GeoTiffError::GeoKey(message) => write!(f, "Invalid GeoTIFF: {}", message),
+            GeoTiffError::Unsupported(message) => write!(f, "Unsupported: {}", message),
+            GeoTiffError::OutOfBounds(message) => write!(f, "Out of bounds: {}", message),
+            GeoTiffError::InvalidArgument(message) => write!(f, "Invalid argument: {}", message),
+            GeoTiffError::Remote(err) => write!(f, "Remote error: {}", err),
+        }
+    }
+}
+
+impl Error for GeoTiffError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            GeoTiffError::Io(err) => Some(err),
+            GeoTiffError::Tiff(err) => Some(err),
+            GeoTiffError::Remote(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Unwraps the failed requests of remote sources, which are passed through the I/O of the
+/// reader.
+impl From<io::Error> for GeoTiffError {
+    fn from(err: io::Error) -> Self {
+        if err.get_ref().is_some_and(|inner| inner.is::<RemoteError>()) {
+            let inner = err.into_inner().expect("the error wraps a RemoteError");
+            return GeoTiffError::Remote(*inner.downcast().expect("the error is a RemoteError"));
+        }
+        GeoTiffError::Io(err)
+    }
+}
+
+/// Separates I/O errors and unsupported features from errors of the TIFF structure.
+impl From<TiffError> for GeoTiffError {
+    fn from(err: TiffError) -> Self {
+        match err {
+            TiffError::IoError(err) => err.into(),
+            TiffError::UnsupportedError(err) => GeoTiffError::Unsupported(err.to_string()),
+            err => GeoTiffError::Tiff(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tiff::{TiffFormatError, TiffUnsupportedError};
+
+    use super::*;
+
+    #[test]
+    fn test_from_tiff_error() {
+        let err = GeoTiffError::from(TiffError::IoError(io::Error::other("closed")));
+        assert!(matches!(err, GeoTiffError::Io(_)));
+        assert!(err.source().is_some());
+
+        let err = GeoTiffError::from(TiffError::UnsupportedError(
+            TiffUnsupportedError::UnknownInterpretation,
+        ));
+        assert!(matches!(err, GeoTiffError::Unsupported(_)));
+
+        let err = GeoTiffError::from(TiffError::FormatError(TiffFormatError::Format(
+            "Chunk 3 is missing".to_string(),
+        )));
+        assert!(matches!(err, GeoTiffError::Tiff(_)));
+        assert!(err.to_string().contains("Chunk 3 is missing"));
+    }
+
+    #[test]
+    fn test_remote_error() {
+        let remote = RemoteError::transient(io::Error::other("Service unavailable"));
+        let err = GeoTiffError::from(io::Error::other(remote.with_attempts(4)));
+        assert!(err.is_transient());
+        assert_eq!(
+            err.to_string(),
+            "Remote error: Service unavailable (after 4 attempts)"
+        );
+        let GeoTiffError::Remote(remote) = &err else {
+            panic!("Expected a remote error, found {:?}", err);
+        };
+        assert_eq!(remote.attempts(), 4);
+
+        let remote = RemoteError::permanent(io::Error::other("Not found"));
+        let err = GeoTiffError::from(TiffError::IoError(io::Error::other(remote)));
+        assert!(matches!(err, GeoTiffError::Remote(_)));
+        assert!(!err.is_transient());
+        assert!(!GeoTiffError::from(io::Error::other("closed")).is_transient());
+    }
+}
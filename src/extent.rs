@@ -0,0 +1,43 @@
+/// An axis-aligned bounding box in model space.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Extent {
+    pub min_x: f64,
+    pub min_y: f64,
+    pub max_x: f64,
+    pub max_y: f64,
+}
+
+impl Extent {
+    pub fn new(min_x: f64, min_y: f64, max_x: f64, max_y: f64) -> Self {
+        Self {
+            min_x,
+            min_y,
+            max_x,
+            max_y,
+        }
+    }
+
+    /// The smallest extent containing all points, or `None` if there are no points.
+    pub fn from_points(points: impl IntoIterator<Item = [f64; 2]>) -> Option<Self> {
+        points.into_iter().fold(None, |extent, [x, y]| {
+            Some(match extent {
+                None => Extent::new(x, y, x, y),
+                Some(extent) => Extent::new(
+                    extent.min_x.min(x),
+                    extent.min_y.min(y),
+                    extent.max_x.max(x),
+                    extent.max_y.max(y),
+                ),
+            })
+        })
+    }
+
+    pub fn width(&self) -> f64 {
+        self.max_x - self.min_x
+    }
+
+    pub fn height(&self) -> f64 {
+        self.max_y - self.min_y
+    }
+}
@@ -0,0 +1,291 @@
+use std::future::Future;
+use std::io;
+use std::ops::Range;
+use std::time::Duration;
+
+use futures_util::{stream, StreamExt, TryStreamExt};
+
+use crate::RemoteError;
+
+/// How the remote sources fetch the byte ranges of the strips or tiles of a window, see
+/// `HttpSource::with_fetch_options` and `ObjectStoreSource::with_fetch_options`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FetchOptions {
+    /// The maximum number of requests in flight at once
+    pub concurrency: usize,
+    /// The maximum number of bytes between two ranges fetched by a single request, as fewer but
+    /// larger requests are usually faster than many small ones
+    pub coalesce_gap: u64,
+}
+
+impl Default for FetchOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: 8,
+            coalesce_gap: 64 * 1024,
+        }
+    }
+}
+
+/// How the remote sources repeat requests failing transiently, waiting exponentially longer
+/// between the attempts, see [`RemoteError`], `HttpSource::with_retry_policy` and
+/// `ObjectStoreSource::with_retry_policy`.
+///
+/// Waiting and timeouts require a Tokio runtime with the time driver enabled. On wasm32, where
+/// no timer is available, retries are not delayed and requests never time out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// The maximum number of times a request is repeated
+    pub max_retries: u32,
+    /// The delay before the first retry, doubled for each further retry
+    pub initial_backoff: Duration,
+    /// The maximum delay before a retry
+    pub max_backoff: Duration,
+    /// The time after which a request is aborted, failing transiently, or `None` to wait
+    /// indefinitely
+    pub timeout: Option<Duration>,
+}
+
+impl RetryPolicy {
+    /// Makes each request only once, without a timeout.
+    pub fn never() -> Self {
+        Self {
+            max_retries: 0,
+            initial_backoff: Duration::ZERO,
+            max_backoff: Duration::ZERO,
+            timeout: None,
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            timeout: Some(Duration::from_secs(30)),
+        }
+    }
+}
+
+/// Makes the request until it succeeds, fails permanently or the retries of the policy are
+/// exhausted, returning the [`RemoteError`] of the last attempt wrapped in an [`io::Error`].
+pub(crate) async fn retry<T, F, Fut>(policy: RetryPolicy, mut request: F) -> io::Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, RemoteError>>,
+{
+    let mut backoff = policy.initial_backoff;
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        let result = match policy.timeout {
+            Some(timeout) => with_timeout(timeout, request()).await,
+            None => request().await,
+        };
+        match result {
+            Ok(value) => return Ok(value),
+            Err(err) if err.is_transient() && attempts <= policy.max_retries => {
+                sleep(backoff).await;
+                backoff = backoff.saturating_mul(2).min(policy.max_backoff);
+            }
+            Err(err) => return Err(io::Error::other(err.with_attempts(attempts))),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+async fn with_timeout<T>(
+    timeout: Duration,
+    request: impl Future<Output = Result<T, RemoteError>>,
+) -> Result<T, RemoteError> {
+    tokio::time::timeout(timeout, request)
+        .await
+        .unwrap_or_else(|_| {
+            Err(RemoteError::transient(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("The request timed out after {:?}", timeout),
+            )))
+        })
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn with_timeout<T>(
+    _timeout: Duration,
+    request: impl Future<Output = Result<T, RemoteError>>,
+) -> Result<T, RemoteError> {
+    request.await
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+async fn sleep(duration: Duration) {
+    tokio::time::sleep(duration).await
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn sleep(_duration: Duration) {}
+
+/// Fetches the bytes in each of the given ranges, coalescing ranges closer than the gap of the
+/// options into a single request and running up to the given number of requests concurrently.
+pub(crate) async fn fetch_ranges<F, Fut>(
+    ranges: &[Range<u64>],
+    options: FetchOptions,
+    fetch: F,
+) -> io::Result<Vec<Vec<u8>>>
+where
+    F: FnMut(Range<u64>) -> Fut,
+    Fut: Future<Output = io::Result<Vec<u8>>>,
+{
+    let mut sorted = ranges
+        .iter()
+        .filter(|range| !range.is_empty())
+        .collect::<Vec<_>>();
+    sorted.sort_by_key(|range| range.start);
+    let mut requests: Vec<Range<u64>> = Vec::new();
+    for range in sorted {
+        match requests.last_mut() {
+            Some(last) if range.start <= last.end.saturating_add(options.coalesce_gap) => {
+                last.end = last.end.max(range.end)
+            }
+            _ => requests.push(range.clone()),
+        }
+    }
+
+    let responses = stream::iter(requests.clone())
+        .map(fetch)
+        .buffered(options.concurrency.max(1))
+        .try_collect::<Vec<_>>()
+        .await?;
+    for (request, bytes) in requests.iter().zip(&responses) {
+        if bytes.len() as u64 != request.end - request.start {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!(
+                    "Read {} bytes instead of the range {:?}",
+                    bytes.len(),
+                    request
+                ),
+            ));
+        }
+    }
+    Ok(ranges
+        .iter()
+        .map(|range| {
+            if range.is_empty() {
+                return Vec::new();
+            }
+            // The requests are disjoint and sorted, the last one starting before the range
+            // contains it
+            let index = requests.partition_point(|request| request.start <= range.start) - 1;
+            let start = requests[index].start;
+            responses[index][(range.start - start) as usize..(range.end - start) as usize].to_vec()
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_fetch_ranges() {
+        let data = (0..200).map(|value| value as u8).collect::<Vec<_>>();
+        let requests = AtomicUsize::new(0);
+        let in_flight = AtomicUsize::new(0);
+        let max_in_flight = AtomicUsize::new(0);
+        let fetch = |range: Range<u64>| {
+            let data = &data;
+            let (requests, in_flight, max_in_flight) = (&requests, &in_flight, &max_in_flight);
+            async move {
+                requests.fetch_add(1, Ordering::SeqCst);
+                let count = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                max_in_flight.fetch_max(count, Ordering::SeqCst);
+                tokio::task::yield_now().await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                Ok(data[range.start as usize..range.end.min(150) as usize].to_vec())
+            }
+        };
+
+        let ranges = [0..4, 10..12, 5..6, 100..110, 20..20];
+        let options = FetchOptions {
+            concurrency: 8,
+            coalesce_gap: 4,
+        };
+        let bytes = fetch_ranges(&ranges, options, fetch).await.unwrap();
+        assert_eq!(
+            bytes,
+            [
+                vec![0, 1, 2, 3],
+                vec![10, 11],
+                vec![5],
+                (100..110).collect(),
+                vec![]
+            ]
+        );
+        // 0..12 and 100..110
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+
+        let ranges = (0..6)
+            .map(|index| index * 10..index * 10 + 2)
+            .collect::<Vec<_>>();
+        let options = FetchOptions {
+            concurrency: 2,
+            coalesce_gap: 0,
+        };
+        max_in_flight.store(0, Ordering::SeqCst);
+        let bytes = fetch_ranges(&ranges, options, fetch).await.unwrap();
+        assert_eq!(bytes[5], [50, 51]);
+        assert_eq!(requests.load(Ordering::SeqCst), 8);
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 2);
+
+        // Responses shorter than the range
+        assert!(fetch_ranges(&[140..145, 148..160], options, fetch)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_retry() {
+        let policy = RetryPolicy {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(2),
+            timeout: Some(Duration::from_millis(50)),
+        };
+        let attempts = AtomicUsize::new(0);
+        // Succeeds on the third attempt after failing and timing out
+        let result = retry(policy, || async {
+            match attempts.fetch_add(1, Ordering::SeqCst) {
+                0 => Err(RemoteError::transient(io::Error::other("Unavailable"))),
+                1 => {
+                    tokio::time::sleep(Duration::from_secs(10)).await;
+                    Ok(1)
+                }
+                _ => Ok(2),
+            }
+        })
+        .await;
+        assert_eq!(result.unwrap(), 2);
+
+        let result = retry(policy, || async {
+            Err::<(), _>(RemoteError::transient(io::Error::other("Unavailable")))
+        })
+        .await;
+        let err = crate::GeoTiffError::from(result.unwrap_err());
+        assert!(err.is_transient());
+        assert!(err.to_string().ends_with("(after 4 attempts)"));
+
+        // Permanent failures are not retried
+        attempts.store(0, Ordering::SeqCst);
+        let result = retry(policy, || async {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Err::<(), _>(RemoteError::permanent(io::Error::other("Not found")))
+        })
+        .await;
+        assert!(!crate::GeoTiffError::from(result.unwrap_err()).is_transient());
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,227 @@
+use std::collections::BTreeMap;
+
+use tiff::{TiffError, TiffFormatError};
+
+use crate::{GeoTiffError, GeoTiffResult};
+
+/// The metadata GDAL stores in the GDAL_METADATA tag as XML, e.g. band descriptions, units,
+/// scale and offset, statistics and custom metadata.
+///
+/// Each item has a name and a value, and belongs to a domain, the empty string being the default
+/// domain, and either to the dataset or to a band.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GdalMetadata {
+    /// The values by domain, band and name
+    items: BTreeMap<(String, Option<usize>, String), String>,
+}
+
+impl GdalMetadata {
+    /// Parses the XML of the GDAL_METADATA tag, like
+    ///
+    /// ```xml
+    /// <GDALMetadata>
+    ///   <Item name="AREA_OR_POINT">Area</Item>
+    ///   <Item name="DESCRIPTION" sample="0" role="description">Elevation</Item>
+    ///   <Item name="SCALE" sample="0" role="scale">0.1</Item>
+    /// </GDALMetadata>
+    /// ```
+    pub fn from_xml(xml: &str) -> GeoTiffResult<Self> {
+        let mut items = BTreeMap::new();
+        let mut rest = xml;
+        while let Some(start) = rest.find("<Item") {
+            rest = &rest[start + "<Item".len()..];
+            let end = rest
+                .find('>')
+                .ok_or_else(|| format_error("Unterminated Item element in GDAL_METADATA"))?;
+            let (attributes, self_closing) = match rest[..end].strip_suffix('/') {
+                Some(attributes) => (attributes, true),
+                None => (&rest[..end], false),
+            };
+            rest = &rest[end + 1..];
+
+            let mut name = None;
+            let mut domain = String::new();
+            let mut band = None;
+            for (key, value) in parse_attributes(attributes)? {
+                match key {
+                    "name" => name = Some(value),
+                    "domain" => domain = value,
+                    "sample" => {
+                        band = Some(value.parse().map_err(|_| {
+                            format_error(format!("Invalid sample {} in GDAL_METADATA", value))
+                        })?)
+                    }
+                    _ => {}
+                }
+            }
+            let name = name.ok_or_else(|| format_error("Item without name in GDAL_METADATA"))?;
+
+            let value = if self_closing {
+                String::new()
+            } else {
+                let end = rest.find("</Item>").ok_or_else(|| {
+                    format_error(format!("Unterminated item {} in GDAL_METADATA", name))
+                })?;
+                let value = unescape(&rest[..end])?;
+                rest = &rest[end + "</Item>".len()..];
+                value
+            };
+            items.insert((domain, band, name), value);
+        }
+        Ok(Self { items })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// All items as `(domain, band, name, value)`, ordered by domain, band and name, with the
+    /// items of the dataset before those of the bands.
+    pub fn items(&self) -> impl Iterator<Item = (&str, Option<usize>, &str, &str)> {
+        self.items.iter().map(|((domain, band, name), value)| {
+            (domain.as_str(), *band, name.as_str(), value.as_str())
+        })
+    }
+
+    /// The value of the item with the given name in the given domain, e.g. `""` for the default
+    /// domain, of the given band or of the dataset for `None`.
+    pub fn get(&self, domain: &str, band: Option<usize>, name: &str) -> Option<&str> {
+        self.items
+            .get(&(domain.to_string(), band, name.to_string()))
+            .map(String::as_str)
+    }
+
+    /// The description of the band, e.g. "Red" or "Elevation".
+    pub fn band_description(&self, band: usize) -> Option<&str> {
+        self.get("", Some(band), "DESCRIPTION")
+    }
+
+    /// The unit of the values of the band, e.g. "m" or "metre".
+    pub fn band_unit(&self, band: usize) -> Option<&str> {
+        self.get("", Some(band), "UNITTYPE")
+    }
+
+    /// The factor to convert the stored values of the band to physical values, see
+    /// [`GdalMetadata::band_offset`].
+    pub fn band_scale(&self, band: usize) -> Option<f64> {
+        self.get("", Some(band), "SCALE")?.trim().parse().ok()
+    }
+
+    /// The offset to add to the scaled values of the band, i.e. the physical value is
+    /// `value * scale + offset`.
+    pub fn band_offset(&self, band: usize) -> Option<f64> {
+        self.get("", Some(band), "OFFSET")?.trim().parse().ok()
+    }
+}
+
+/// Parses attributes like `name="SCALE" sample="0"`, with values in single or double quotes.
+fn parse_attributes(mut attributes: &str) -> GeoTiffResult<Vec<(&str, String)>> {
+    let mut parsed = Vec::new();
+    loop {
+        attributes = attributes.trim_start();
+        if attributes.is_empty() {
+            return Ok(parsed);
+        }
+        let (key, rest) = attributes
+            .split_once('=')
+            .ok_or_else(|| format_error("Invalid attribute in GDAL_METADATA"))?;
+        let rest = rest.trim_start();
+        let quote = rest
+            .chars()
+            .next()
+            .filter(|&quote| quote == '"' || quote == '\'')
+            .ok_or_else(|| format_error("Unquoted attribute in GDAL_METADATA"))?;
+        let (value, rest) = rest[1..]
+            .split_once(quote)
+            .ok_or_else(|| format_error("Unterminated attribute in GDAL_METADATA"))?;
+        parsed.push((key.trim(), unescape(value)?));
+        attributes = rest;
+    }
+}
+
+/// Replaces the XML entities and character references of text.
+fn unescape(text: &str) -> GeoTiffResult<String> {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        unescaped.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find(';')
+            .ok_or_else(|| format_error("Unterminated entity in GDAL_METADATA"))?;
+        let entity = &rest[start + 1..start + end];
+        let character = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => match entity
+                .strip_prefix("#x")
+                .or_else(|| entity.strip_prefix("#X"))
+            {
+                Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                None => entity
+                    .strip_prefix('#')
+                    .and_then(|decimal| decimal.parse().ok()),
+            }
+            .and_then(char::from_u32),
+        }
+        .ok_or_else(|| format_error(format!("Unknown entity &{}; in GDAL_METADATA", entity)))?;
+        unescaped.push(character);
+        rest = &rest[start + end + 1..];
+    }
+    unescaped.push_str(rest);
+    Ok(unescaped)
+}
+
+fn format_error(msg: impl ToString) -> GeoTiffError {
+    GeoTiffError::Tiff(TiffError::FormatError(TiffFormatError::Format(
+        msg.to_string(),
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_xml() {
+        let metadata = GdalMetadata::from_xml(
+            r#"<GDALMetadata>
+  <Item name="AREA_OR_POINT">Area</Item>
+  <Item name="COMPANY" domain="CUSTOM">Smith &amp; Sons &#x263A;</Item>
+  <Item name="DESCRIPTION" sample="0" role="description">Elevation</Item>
+  <Item name="SCALE" sample="0" role="scale">0.1</Item>
+  <Item name="OFFSET" sample="0" role="offset">-100</Item>
+  <Item name="UNITTYPE" sample="0" role="unittype">m</Item>
+  <Item name='EMPTY' sample='1'/>
+</GDALMetadata>
+"#,
+        )
+        .unwrap();
+        assert_eq!(metadata.get("", None, "AREA_OR_POINT"), Some("Area"));
+        assert_eq!(
+            metadata.get("CUSTOM", None, "COMPANY"),
+            Some("Smith & Sons \u{263A}")
+        );
+        assert_eq!(metadata.get("", None, "COMPANY"), None);
+        assert_eq!(metadata.band_description(0), Some("Elevation"));
+        assert_eq!(metadata.band_unit(0), Some("m"));
+        assert_eq!(metadata.band_scale(0), Some(0.1));
+        assert_eq!(metadata.band_offset(0), Some(-100.0));
+        assert_eq!(metadata.band_scale(1), None);
+        assert_eq!(metadata.get("", Some(1), "EMPTY"), Some(""));
+        assert_eq!(metadata.items().count(), 7);
+        assert_eq!(
+            metadata.items().next(),
+            Some(("", None, "AREA_OR_POINT", "Area"))
+        );
+
+        assert!(GdalMetadata::from_xml("<GDALMetadata></GDALMetadata>")
+            .unwrap()
+            .is_empty());
+        assert!(GdalMetadata::from_xml(r#"<Item sample="0">1</Item>"#).is_err());
+        assert!(GdalMetadata::from_xml(r#"<Item name="A">1"#).is_err());
+        assert!(GdalMetadata::from_xml(r#"<Item name="A">&unknown;</Item>"#).is_err());
+    }
+}
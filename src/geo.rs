@@ -0,0 +1,481 @@
+use std::io::{Read, Seek};
+
+use geo_types::{Coord, Geometry, MultiPolygon, Point, Rect};
+
+use crate::{
+    CoordinateTransform, Extent, GeoTiff, GeoTiffError, GeoTiffReader, GeoTiffResult, WindowSnap,
+};
+
+impl From<Extent> for Rect {
+    fn from(extent: Extent) -> Self {
+        Rect::new(
+            Coord {
+                x: extent.min_x,
+                y: extent.min_y,
+            },
+            Coord {
+                x: extent.max_x,
+                y: extent.max_y,
+            },
+        )
+    }
+}
+
+impl From<Rect> for Extent {
+    fn from(rect: Rect) -> Self {
+        let (min, max) = (rect.min(), rect.max());
+        Extent::new(min.x, min.y, max.x, max.y)
+    }
+}
+
+impl GeoTiff {
+    /// Transforms a raster coordinate (column, row) to model space, see
+    /// [`GeoTiff::transform_to_model`].
+    ///
+    /// Returns `None` if the GeoTIFF is not georeferenced.
+    pub fn transform_to_model_coord(&self, coord: Coord<usize>) -> Option<Coord> {
+        self.transform_to_model([coord.x, coord.y]).map(Coord::from)
+    }
+
+    /// Transforms a model coordinate, given as [`Coord`] or [`Point`], to the raster coordinate
+    /// (column, row) of the containing pixel, see [`GeoTiff::transform_to_raster`].
+    ///
+    /// Returns `None` if the GeoTIFF is not georeferenced.
+    pub fn transform_to_raster_coord(&self, coord: impl Into<Coord>) -> Option<Coord<usize>> {
+        let coord = coord.into();
+        self.transform_to_raster([coord.x, coord.y])
+            .map(Coord::from)
+    }
+
+    /// The model coordinate of the upper left corner of the pixel at `(x, y)` as a point.
+    ///
+    /// Returns `None` if the GeoTIFF is not georeferenced.
+    pub fn model_point(&self, x: usize, y: usize) -> Option<Point> {
+        self.transform_to_model_coord(Coord { x, y })
+            .map(Point::from)
+    }
+
+    /// The bounding box of the raster in model space as a rectangle, see
+    /// [`GeoTiff::model_extent`].
+    ///
+    /// Returns `None` if the GeoTIFF is not georeferenced.
+    pub fn model_rect(&self) -> Option<Rect> {
+        self.model_extent().map(Rect::from)
+    }
+}
+
+impl<R: Read + Seek> GeoTiffReader<R> {
+    /// Reads the pixels intersecting a rectangle in model space, see
+    /// [`GeoTiffReader::read_bbox`].
+    pub fn read_rect(&mut self, rect: Rect) -> GeoTiffResult<GeoTiff> {
+        let (min, max) = (rect.min(), rect.max());
+        self.read_bbox(min.x, min.y, max.x, max.y)
+    }
+
+    /// The window `[x, y, width, height]` of the selected image or overview covering the
+    /// bounding box of a geometry in model space, e.g. of an area of interest, to be read by
+    /// [`GeoTiffReader::read_window`] or the other windowed reads.
+    ///
+    /// The edges of the bounding box are snapped to the pixel grid as given, and the window is
+    /// clipped to the raster. Fails if the GeoTIFF is not georeferenced, the geometry is empty or
+    /// the window would not cover any pixel of the raster.
+    pub fn window_from_geometry(
+        &self,
+        geometry: impl Into<Geometry>,
+        snap: WindowSnap,
+    ) -> GeoTiffResult<[usize; 4]> {
+        let mut coords = Vec::new();
+        geometry_coords(&geometry.into(), &mut coords);
+        let rect = bounding_rect(coords)
+            .ok_or_else(|| GeoTiffError::InvalidArgument("The geometry is empty".to_string()))?;
+        let (min, max) = (rect.min(), rect.max());
+        self.bbox_window(min.x, min.y, max.x, max.y, snap)
+    }
+
+    /// Reads the pixels intersecting the bounding box of a polygon or multipolygon in model
+    /// space, see [`GeoTiffReader::read_bbox`], masking the pixels outside of it, like `gdalwarp
+    /// -cutline -crop_to_cutline` does.
+    ///
+    /// A pixel is inside if its center is, taking holes into account. The returned GeoTiff holds
+    /// the values of all pixels of the window, but its mask marks the pixels outside as invalid
+    /// in addition to those invalid according to the mask of the file, see [`GeoTiff::mask`], so
+    /// that [`GeoTiff::get_value_at_checked`] returns `None` for them. Fails if the GeoTIFF is not
+    /// georeferenced or the polygon does not intersect the raster.
+    pub fn read_clipped(&mut self, polygon: impl Into<MultiPolygon>) -> GeoTiffResult<GeoTiff> {
+        let polygon = polygon.into();
+        let coords = polygon
+            .iter()
+            .flat_map(|polygon| polygon.exterior().coords().copied());
+        let rect = bounding_rect(coords)
+            .ok_or_else(|| GeoTiffError::InvalidArgument("The polygon is empty".to_string()))?;
+        let mut geotiff = self.read_rect(rect)?;
+        let (width, height) = (geotiff.raster_width, geotiff.raster_height);
+        let coordinate_transform = geotiff
+            .coordinate_transform
+            .as_ref()
+            .ok_or_else(|| GeoTiffError::GeoKey("The GeoTIFF is not georeferenced".to_string()))?;
+        let mut mask =
+            RasterPolygon::new(coordinate_transform, &polygon).center_mask([0, 0, width, height]);
+        if let Some(file_mask) = &geotiff.mask {
+            mask.iter_mut()
+                .zip(file_mask)
+                .for_each(|(inside, valid)| *inside &= valid);
+        }
+        geotiff.mask = Some(mask);
+        Ok(geotiff)
+    }
+}
+
+/// Adds the coordinates of the geometry, which determine its bounding box, to `coords`.
+fn geometry_coords(geometry: &Geometry, coords: &mut Vec<Coord>) {
+    match geometry {
+        Geometry::Point(point) => coords.push(point.0),
+        Geometry::Line(line) => coords.extend([line.start, line.end]),
+        Geometry::LineString(line_string) => coords.extend(line_string.coords()),
+        Geometry::Polygon(polygon) => coords.extend(polygon.exterior().coords()),
+        Geometry::MultiPoint(points) => coords.extend(points.iter().map(|point| point.0)),
+        Geometry::MultiLineString(line_strings) => coords.extend(
+            line_strings
+                .iter()
+                .flat_map(|line_string| line_string.coords()),
+        ),
+        Geometry::MultiPolygon(polygons) => coords.extend(
+            polygons
+                .iter()
+                .flat_map(|polygon| polygon.exterior().coords()),
+        ),
+        Geometry::GeometryCollection(geometries) => geometries
+            .iter()
+            .for_each(|geometry| geometry_coords(geometry, coords)),
+        Geometry::Rect(rect) => coords.extend([rect.min(), rect.max()]),
+        Geometry::Triangle(triangle) => coords.extend(triangle.to_array()),
+    }
+}
+
+/// The bounding box of the coordinates, or `None` if there are none.
+pub(crate) fn bounding_rect(coords: impl IntoIterator<Item = Coord>) -> Option<Rect> {
+    let mut coords = coords.into_iter();
+    let first = coords.next()?;
+    let (min, max) = coords.fold((first, first), |(min, max), coord| {
+        (
+            Coord {
+                x: min.x.min(coord.x),
+                y: min.y.min(coord.y),
+            },
+            Coord {
+                x: max.x.max(coord.x),
+                y: max.y.max(coord.y),
+            },
+        )
+    });
+    Some(Rect::new(min, max))
+}
+
+/// Polygons transformed to the raster space of a GeoTIFF, to be rasterized to windows of the
+/// raster.
+pub(crate) struct RasterPolygon {
+    /// The rings of each polygon, the exterior first
+    polygons: Vec<Vec<Ring>>,
+}
+
+struct Ring {
+    coords: Vec<[f64; 2]>,
+    /// The bounding box `[min_x, min_y, max_x, max_y]`
+    bounds: [f64; 4],
+}
+
+impl RasterPolygon {
+    pub(crate) fn new(coordinate_transform: &CoordinateTransform, polygon: &MultiPolygon) -> Self {
+        let polygons = polygon
+            .iter()
+            .map(|polygon| {
+                std::iter::once(polygon.exterior())
+                    .chain(polygon.interiors())
+                    .map(|ring| {
+                        let coords = ring
+                            .coords()
+                            .map(|coord| [coord.x, coord.y])
+                            .collect::<Vec<_>>();
+                        let coords = coordinate_transform.transform_to_raster_f64_many(&coords);
+                        let bounds = coords.iter().fold(
+                            [
+                                f64::INFINITY,
+                                f64::INFINITY,
+                                f64::NEG_INFINITY,
+                                f64::NEG_INFINITY,
+                            ],
+                            |[min_x, min_y, max_x, max_y], &[x, y]| {
+                                [min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y)]
+                            },
+                        );
+                        Ring { coords, bounds }
+                    })
+                    .collect()
+            })
+            .collect();
+        Self { polygons }
+    }
+
+    /// Tells whether the center of each pixel of the window `[x, y, width, height]` is inside,
+    /// row by row.
+    ///
+    /// The rings are scanned along the row centers by the even-odd rule, so that holes are
+    /// excluded.
+    pub(crate) fn center_mask(&self, [x, y, width, height]: [usize; 4]) -> Vec<bool> {
+        let mut mask = vec![false; width * height];
+        let mut crossings = Vec::new();
+        for row in 0..height {
+            let center_y = (y + row) as f64 + 0.5;
+            crossings.clear();
+            for ring in self.polygons.iter().flatten() {
+                for edge in ring.coords.windows(2) {
+                    let ([x0, y0], [x1, y1]) = (edge[0], edge[1]);
+                    if (y0 <= center_y) != (y1 <= center_y) {
+                        crossings.push(x0 + (center_y - y0) * (x1 - x0) / (y1 - y0));
+                    }
+                }
+            }
+            crossings.sort_by(f64::total_cmp);
+            for span in crossings.chunks_exact(2) {
+                // The columns whose centers lie within the span, clipped to the window
+                let start = ((span[0] - 0.5).ceil().max(x as f64) as usize).min(x + width);
+                let end = ((span[1] - 0.5).ceil().max(x as f64) as usize).min(x + width);
+                for column in start..end {
+                    mask[row * width + column - x] = true;
+                }
+            }
+        }
+        mask
+    }
+
+    /// The fraction of each pixel of the window `[x, y, width, height]` covered by the polygons,
+    /// row by row.
+    pub(crate) fn coverage(&self, [x, y, width, height]: [usize; 4]) -> Vec<f64> {
+        let mut coverage = vec![0.0; width * height];
+        for row in 0..height {
+            for column in 0..width {
+                let (min_x, min_y) = ((x + column) as f64, (y + row) as f64);
+                let pixel = [min_x, min_y, min_x + 1.0, min_y + 1.0];
+                let area = self
+                    .polygons
+                    .iter()
+                    .map(|rings| {
+                        // The area of the exterior minus those of the holes
+                        rings
+                            .iter()
+                            .enumerate()
+                            .map(|(index, ring)| {
+                                let area = ring.clipped_area(pixel);
+                                if index == 0 {
+                                    area
+                                } else {
+                                    -area
+                                }
+                            })
+                            .sum::<f64>()
+                    })
+                    .sum::<f64>();
+                coverage[row * width + column] = area.clamp(0.0, 1.0);
+            }
+        }
+        coverage
+    }
+}
+
+impl Ring {
+    /// The area of the ring within the rectangle `[min_x, min_y, max_x, max_y]`, clipping the
+    /// ring by the Sutherland-Hodgman algorithm, which yields the correct area for concave rings
+    /// as well.
+    fn clipped_area(&self, rect: [f64; 4]) -> f64 {
+        let [min_x, min_y, max_x, max_y] = self.bounds;
+        if min_x >= rect[2] || max_x <= rect[0] || min_y >= rect[3] || max_y <= rect[1] {
+            return 0.0;
+        }
+        let mut polygon = self.coords.clone();
+        // Each edge of the rectangle as the axis and the value, and whether the inside is above it
+        for (axis, value, above) in [
+            (0, rect[0], true),
+            (0, rect[2], false),
+            (1, rect[1], true),
+            (1, rect[3], false),
+        ] {
+            let inside = |point: &[f64; 2]| (point[axis] >= value) == above;
+            let mut clipped = Vec::with_capacity(polygon.len() + 4);
+            for (index, point) in polygon.iter().enumerate() {
+                let previous = polygon[(index + polygon.len() - 1) % polygon.len()];
+                if inside(point) != inside(&previous) {
+                    let t = (value - previous[axis]) / (point[axis] - previous[axis]);
+                    clipped.push([
+                        previous[0] + t * (point[0] - previous[0]),
+                        previous[1] + t * (point[1] - previous[1]),
+                    ]);
+                }
+                if inside(point) {
+                    clipped.push(*point);
+                }
+            }
+            polygon = clipped;
+            if polygon.is_empty() {
+                return 0.0;
+            }
+        }
+        // The shoelace formula
+        let twice_area = (0..polygon.len())
+            .map(|index| {
+                let ([x0, y0], [x1, y1]) = (polygon[index], polygon[(index + 1) % polygon.len()]);
+                x0 * y1 - x1 * y0
+            })
+            .sum::<f64>();
+        twice_area.abs() / 2.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use geo_types::{GeometryCollection, LineString, Polygon};
+
+    use super::*;
+    use crate::GeoTiffWriter;
+
+    #[test]
+    fn test_geo_types() {
+        let transform =
+            CoordinateTransform::from_geotransform([1000.0, 10.0, 0.0, 2000.0, 0.0, -10.0])
+                .unwrap();
+        let mut bytes = Vec::new();
+        GeoTiffWriter::new()
+            .with_coordinate_transform(transform)
+            .write(&mut bytes, 4, 3, 1, &[0u8; 12])
+            .unwrap();
+        let mut georeferenced = GeoTiffReader::new(Cursor::new(bytes)).unwrap();
+        let geotiff = georeferenced.read().unwrap();
+
+        assert_eq!(
+            geotiff.transform_to_model_coord(Coord { x: 1, y: 2 }),
+            Some(Coord {
+                x: 1010.0,
+                y: 1980.0
+            })
+        );
+        assert_eq!(
+            geotiff.transform_to_raster_coord(Point::new(1025.0, 1995.0)),
+            Some(Coord { x: 2, y: 0 })
+        );
+        assert_eq!(geotiff.model_point(4, 3), Some(Point::new(1040.0, 1970.0)));
+        let rect = geotiff.model_rect().unwrap();
+        assert_eq!(rect, Rect::new((1000.0, 1970.0), (1040.0, 2000.0)));
+        assert_eq!(Extent::from(rect), geotiff.model_extent().unwrap());
+
+        let window = georeferenced
+            .read_rect(Rect::new((1015.0, 1985.0), (1025.0, 1995.0)))
+            .unwrap();
+        assert_eq!((window.raster_width, window.raster_height), (2, 2));
+    }
+
+    #[test]
+    fn test_read_clipped() {
+        let transform =
+            CoordinateTransform::from_geotransform([1000.0, 10.0, 0.0, 2000.0, 0.0, -10.0])
+                .unwrap();
+        let data = (0..100).collect::<Vec<u8>>();
+        let mut bytes = Vec::new();
+        GeoTiffWriter::new()
+            .with_coordinate_transform(transform)
+            .write(&mut bytes, 10, 10, 1, &data)
+            .unwrap();
+        let mut reader = GeoTiffReader::new(Cursor::new(bytes)).unwrap();
+
+        // A triangle with its right angle at the upper left corner of pixel (1, 1), and a hole at
+        // pixel (3, 2)
+        let exterior = LineString::from(vec![(1010.0, 1990.0), (1080.0, 1990.0), (1010.0, 1920.0)]);
+        let hole = LineString::from(vec![
+            (1030.0, 1980.0),
+            (1040.0, 1980.0),
+            (1040.0, 1970.0),
+            (1030.0, 1970.0),
+        ]);
+        let geotiff = reader
+            .read_clipped(Polygon::new(exterior, vec![hole]))
+            .unwrap();
+        assert_eq!((geotiff.raster_width, geotiff.raster_height), (7, 7));
+        assert_eq!(geotiff.geotransform().unwrap()[0], 1010.0);
+        let mask = geotiff.mask().unwrap();
+        // Pixels whose centers lie below the diagonal are outside
+        #[rustfmt::skip]
+        assert_eq!(
+            mask.iter().map(|&inside| inside as u8).collect::<Vec<_>>(),
+            [
+                1, 1, 1, 1, 1, 1, 0,
+                1, 1, 0, 1, 1, 0, 0,
+                1, 1, 1, 1, 0, 0, 0,
+                1, 1, 1, 0, 0, 0, 0,
+                1, 1, 0, 0, 0, 0, 0,
+                1, 0, 0, 0, 0, 0, 0,
+                0, 0, 0, 0, 0, 0, 0,
+            ]
+        );
+        assert_eq!(geotiff.get_value_at_checked::<u8>(0, 0, 0), Some(11));
+        assert_eq!(geotiff.get_value_at::<u8>(6, 6, 0), 77);
+        assert_eq!(geotiff.get_value_at_checked::<u8>(6, 6, 0), None);
+
+        let outside = Rect::new((0.0, 0.0), (10.0, 10.0)).to_polygon();
+        assert!(reader.read_clipped(outside).is_err());
+        assert!(reader.read_clipped(MultiPolygon::new(Vec::new())).is_err());
+    }
+
+    #[test]
+    fn test_window_from_geometry() {
+        let transform =
+            CoordinateTransform::from_geotransform([1000.0, 10.0, 0.0, 2000.0, 0.0, -10.0])
+                .unwrap();
+        let mut bytes = Vec::new();
+        GeoTiffWriter::new()
+            .with_coordinate_transform(transform)
+            .write(&mut bytes, 10, 10, 1, &[0u8; 100])
+            .unwrap();
+        let reader = GeoTiffReader::new(Cursor::new(bytes)).unwrap();
+
+        // Spanning the columns 1.2 to 3.7 and the rows 2.4 to 5.0
+        let line = LineString::from(vec![(1012.0, 1976.0), (1037.0, 1950.0)]);
+        let window = |snap| reader.window_from_geometry(line.clone(), snap);
+        assert_eq!(window(WindowSnap::Outward).unwrap(), [1, 2, 3, 3]);
+        assert_eq!(window(WindowSnap::Nearest).unwrap(), [1, 2, 3, 3]);
+        assert_eq!(window(WindowSnap::Inward).unwrap(), [2, 3, 1, 2]);
+
+        let points = Geometry::GeometryCollection(GeometryCollection::new_from(vec![
+            Point::new(1004.0, 1994.0).into(),
+            Point::new(1046.0, 1984.0).into(),
+        ]));
+        assert_eq!(
+            reader
+                .window_from_geometry(points, WindowSnap::Nearest)
+                .unwrap(),
+            [0, 1, 5, 1]
+        );
+        // Clipped to the raster
+        let rect = Rect::new((950.0, 1950.0), (1200.0, 2100.0));
+        assert_eq!(
+            reader
+                .window_from_geometry(rect, WindowSnap::Outward)
+                .unwrap(),
+            [0, 0, 10, 5]
+        );
+
+        // Less than a pixel
+        let rect = Rect::new((1012.0, 1952.0), (1018.0, 1958.0));
+        assert_eq!(
+            reader
+                .window_from_geometry(rect, WindowSnap::Outward)
+                .unwrap(),
+            [1, 4, 1, 1]
+        );
+        assert!(reader
+            .window_from_geometry(rect, WindowSnap::Inward)
+            .is_err());
+        assert!(reader
+            .window_from_geometry(MultiPolygon::new(Vec::new()), WindowSnap::Outward)
+            .is_err());
+    }
+}
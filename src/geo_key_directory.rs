@@ -0,0 +1,1098 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::RangeInclusive;
+
+use tiff::tags::Tag;
+
+use crate::projection::ProjectionDefinition;
+use crate::{crs, GeoTiffError, GeoTiffResult, TagValue};
+
+macro_rules! geo_keys {
+    {
+        $(
+            $(#[$attr:meta])*
+            $key:ident = $id:literal $(=> $accessor:ident -> $value_type:ty, $getter:ident)?;
+        )*
+    } => {
+        /// The GeoKeys defined by the GeoTIFF standard, named as in GeoTIFF 1.0.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        #[non_exhaustive]
+        pub enum GeoKey {
+            $($(#[$attr])* $key,)*
+            /// A private or unsupported key
+            Unknown(u16),
+        }
+
+        impl GeoKey {
+            pub fn from_u16(id: u16) -> Self {
+                match id {
+                    $($id => GeoKey::$key,)*
+                    id => GeoKey::Unknown(id),
+                }
+            }
+
+            pub fn to_u16(&self) -> u16 {
+                match self {
+                    $(GeoKey::$key => $id,)*
+                    GeoKey::Unknown(id) => *id,
+                }
+            }
+        }
+
+        /// Serializes known keys by name, e.g. `"ProjectedCSType"`, and unknown keys by number as
+        /// a string, such that keys can be used as the keys of JSON objects.
+        #[cfg(feature = "serde")]
+        impl serde::Serialize for GeoKey {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                match self {
+                    $(GeoKey::$key => serializer.serialize_str(stringify!($key)),)*
+                    GeoKey::Unknown(id) => serializer.serialize_str(&id.to_string()),
+                }
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl<'de> serde::Deserialize<'de> for GeoKey {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let name = String::deserialize(deserializer)?;
+                match name.as_str() {
+                    $(stringify!($key) => Ok(GeoKey::$key),)*
+                    name => name.parse().map(GeoKey::from_u16).map_err(|_| {
+                        serde::de::Error::custom(format!("Unknown GeoKey {}", name))
+                    }),
+                }
+            }
+        }
+
+        impl GeoKeyDirectory {
+            $(geo_key_accessor!($(#[$attr])* $key $(=> $accessor -> $value_type, $getter)?);)*
+        }
+    };
+}
+
+macro_rules! geo_key_accessor {
+    ($(#[$attr:meta])* $key:ident) => {};
+    ($(#[$attr:meta])* $key:ident => $accessor:ident -> $value_type:ty, $getter:ident) => {
+        $(#[$attr])*
+        pub fn $accessor(&self) -> Option<$value_type> {
+            self.$getter(GeoKey::$key)
+        }
+    };
+}
+
+geo_keys! {
+    /// The type of model coordinate system (GTModelTypeGeoKey)
+    GTModelType = 1024;
+    /// The pixel convention of raster space (GTRasterTypeGeoKey)
+    GTRasterType = 1025;
+    /// A description of the coordinate system (GTCitationGeoKey)
+    GTCitation = 1026 => citation -> &str, get_ascii;
+
+    /// The EPSG code of the geodetic CRS (GeographicTypeGeoKey, GeodeticCRSGeoKey in GeoTIFF 1.1)
+    GeographicType = 2048 => geographic_type -> u16, get_short;
+    /// A description of the geodetic CRS (GeogCitationGeoKey, GeodeticCitationGeoKey in GeoTIFF 1.1)
+    GeogCitation = 2049 => geog_citation -> &str, get_ascii;
+    /// The EPSG code of the geodetic datum (GeogGeodeticDatumGeoKey, GeodeticDatumGeoKey in GeoTIFF 1.1)
+    GeogGeodeticDatum = 2050 => geog_geodetic_datum -> u16, get_short;
+    /// The EPSG code of the prime meridian (GeogPrimeMeridianGeoKey)
+    GeogPrimeMeridian = 2051 => geog_prime_meridian -> u16, get_short;
+    /// The EPSG code of the linear unit of the ellipsoid (GeogLinearUnitsGeoKey)
+    GeogLinearUnits = 2052 => geog_linear_units -> u16, get_short;
+    /// The size of a user-defined linear unit in meters (GeogLinearUnitSizeGeoKey)
+    GeogLinearUnitSize = 2053 => geog_linear_unit_size -> f64, get_double;
+    /// The EPSG code of the angular unit (GeogAngularUnitsGeoKey)
+    GeogAngularUnits = 2054 => geog_angular_units -> u16, get_short;
+    /// The size of a user-defined angular unit in radians (GeogAngularUnitSizeGeoKey)
+    GeogAngularUnitSize = 2055 => geog_angular_unit_size -> f64, get_double;
+    /// The EPSG code of the ellipsoid (GeogEllipsoidGeoKey)
+    GeogEllipsoid = 2056 => geog_ellipsoid -> u16, get_short;
+    /// The semi-major axis of a user-defined ellipsoid (GeogSemiMajorAxisGeoKey)
+    GeogSemiMajorAxis = 2057 => geog_semi_major_axis -> f64, get_double;
+    /// The semi-minor axis of a user-defined ellipsoid (GeogSemiMinorAxisGeoKey)
+    GeogSemiMinorAxis = 2058 => geog_semi_minor_axis -> f64, get_double;
+    /// The inverse flattening of a user-defined ellipsoid (GeogInvFlatteningGeoKey)
+    GeogInvFlattening = 2059 => geog_inv_flattening -> f64, get_double;
+    /// The EPSG code of the unit of azimuth angles (GeogAzimuthUnitsGeoKey)
+    GeogAzimuthUnits = 2060 => geog_azimuth_units -> u16, get_short;
+    /// The longitude of a user-defined prime meridian (GeogPrimeMeridianLongGeoKey)
+    GeogPrimeMeridianLong = 2061 => geog_prime_meridian_long -> f64, get_double;
+    /// The 3 or 7 parameters of the transformation of the datum to WGS 84 (GeogTOWGS84GeoKey)
+    GeogTOWGS84 = 2062 => geog_towgs84 -> &[f64], get_doubles;
+
+    /// The EPSG code of the projected CRS (ProjectedCSTypeGeoKey, ProjectedCRSGeoKey in GeoTIFF 1.1)
+    ProjectedCSType = 3072 => projected_type -> u16, get_short;
+    /// A description of the projected CRS (PCSCitationGeoKey)
+    PCSCitation = 3073 => proj_citation -> &str, get_ascii;
+    /// The EPSG code of the projection (ProjectionGeoKey)
+    Projection = 3074 => projection -> u16, get_short;
+    /// The method of a user-defined projection (ProjCoordTransGeoKey)
+    ProjCoordTrans = 3075 => proj_coord_trans -> u16, get_short;
+    /// The EPSG code of the linear unit of the projected CRS (ProjLinearUnitsGeoKey)
+    ProjLinearUnits = 3076 => proj_linear_units -> u16, get_short;
+    /// The size of a user-defined linear unit in meters (ProjLinearUnitSizeGeoKey)
+    ProjLinearUnitSize = 3077 => proj_linear_unit_size -> f64, get_double;
+    /// The latitude of the first standard parallel (ProjStdParallel1GeoKey)
+    ProjStdParallel1 = 3078 => proj_std_parallel1 -> f64, get_double;
+    /// The latitude of the second standard parallel (ProjStdParallel2GeoKey)
+    ProjStdParallel2 = 3079 => proj_std_parallel2 -> f64, get_double;
+    /// The longitude of the natural origin (ProjNatOriginLongGeoKey)
+    ProjNatOriginLong = 3080 => proj_nat_origin_long -> f64, get_double;
+    /// The latitude of the natural origin (ProjNatOriginLatGeoKey)
+    ProjNatOriginLat = 3081 => proj_nat_origin_lat -> f64, get_double;
+    /// The false easting (ProjFalseEastingGeoKey)
+    ProjFalseEasting = 3082 => proj_false_easting -> f64, get_double;
+    /// The false northing (ProjFalseNorthingGeoKey)
+    ProjFalseNorthing = 3083 => proj_false_northing -> f64, get_double;
+    /// The longitude of the false origin (ProjFalseOriginLongGeoKey)
+    ProjFalseOriginLong = 3084 => proj_false_origin_long -> f64, get_double;
+    /// The latitude of the false origin (ProjFalseOriginLatGeoKey)
+    ProjFalseOriginLat = 3085 => proj_false_origin_lat -> f64, get_double;
+    /// The easting at the false origin (ProjFalseOriginEastingGeoKey)
+    ProjFalseOriginEasting = 3086 => proj_false_origin_easting -> f64, get_double;
+    /// The northing at the false origin (ProjFalseOriginNorthingGeoKey)
+    ProjFalseOriginNorthing = 3087 => proj_false_origin_northing -> f64, get_double;
+    /// The longitude of the projection center (ProjCenterLongGeoKey)
+    ProjCenterLong = 3088 => proj_center_long -> f64, get_double;
+    /// The latitude of the projection center (ProjCenterLatGeoKey)
+    ProjCenterLat = 3089 => proj_center_lat -> f64, get_double;
+    /// The easting at the projection center (ProjCenterEastingGeoKey)
+    ProjCenterEasting = 3090 => proj_center_easting -> f64, get_double;
+    /// The northing at the projection center (ProjCenterNorthingGeoKey)
+    ProjCenterNorthing = 3091 => proj_center_northing -> f64, get_double;
+    /// The scale factor at the natural origin (ProjScaleAtNatOriginGeoKey)
+    ProjScaleAtNatOrigin = 3092 => proj_scale_at_nat_origin -> f64, get_double;
+    /// The scale factor at the projection center (ProjScaleAtCenterGeoKey)
+    ProjScaleAtCenter = 3093 => proj_scale_at_center -> f64, get_double;
+    /// The azimuth of the initial line (ProjAzimuthAngleGeoKey)
+    ProjAzimuthAngle = 3094 => proj_azimuth_angle -> f64, get_double;
+    /// The longitude of the straight vertical pole (ProjStraightVertPoleLongGeoKey)
+    ProjStraightVertPoleLong = 3095 => proj_straight_vert_pole_long -> f64, get_double;
+    /// The angle from the rectified to the skewed grid (ProjRectifiedGridAngleGeoKey)
+    ProjRectifiedGridAngle = 3096 => proj_rectified_grid_angle -> f64, get_double;
+
+    /// The EPSG code of the vertical CRS (VerticalCSTypeGeoKey, VerticalGeoKey in GeoTIFF 1.1)
+    VerticalCSType = 4096 => vertical_cs_type -> u16, get_short;
+    /// A description of the vertical CRS (VerticalCitationGeoKey)
+    VerticalCitation = 4097 => vertical_citation -> &str, get_ascii;
+    /// The EPSG code of the vertical datum (VerticalDatumGeoKey)
+    VerticalDatum = 4098 => vertical_datum -> u16, get_short;
+    /// The EPSG code of the unit of raster values referring to heights (VerticalUnitsGeoKey)
+    VerticalUnits = 4099 => vertical_units -> u16, get_short;
+}
+
+/// The GeoKeys of a GeoTIFF, as stored in the GeoKeyDirectoryTag, GeoDoubleParamsTag and
+/// GeoAsciiParamsTag.
+///
+/// The standard keys are available through named accessors, which return `None` if a key is
+/// missing or its value is not of the expected type. All keys, including unknown ones, can be
+/// accessed by [`GeoKeyDirectory::get`]. Private and vendor-specific keys are kept with their
+/// values, such that they are written back by [`GeoTiffWriter::with_geo_key_directory`].
+///
+/// [`GeoTiffWriter::with_geo_key_directory`]: crate::GeoTiffWriter::with_geo_key_directory
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GeoKeyDirectory {
+    key_directory_version: u16,
+    key_revision: u16,
+    minor_revision: u16,
+    keys: BTreeMap<GeoKey, GeoKeyValue>,
+}
+
+/// The values of the tags storing a [`GeoKeyDirectory`].
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct GeoTagData {
+    pub(super) directory_data: Vec<u16>,
+    pub(super) double_params_data: Option<Vec<f64>>,
+    pub(super) ascii_params_data: Option<String>,
+    /// The values of the tags other than the GeoTIFF tags that keys refer to, ordered by tag
+    pub(super) referenced_data: Vec<(u16, TagValue)>,
+}
+
+/// The value of a GeoKey.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GeoKeyValue {
+    Short(Vec<u16>),
+    Double(Vec<f64>),
+    Ascii(String),
+    /// The values of a key stored in a TIFF tag other than the GeoTIFF tags, as used by
+    /// vendor-specific keys, given by the tag and the values read from it. The writer stores the
+    /// values of all keys referring to the same tag in that tag.
+    Reference {
+        location: u16,
+        values: TagValue,
+    },
+    /// The `count` values at index `offset` of a TIFF tag other than the GeoTIFF tags, which is
+    /// missing or too short. The entry is kept as is, while no values are written to the tag.
+    UnresolvedReference {
+        location: u16,
+        count: u16,
+        offset: u16,
+    },
+}
+
+/// The type of model coordinate system, as given by the GTModelTypeGeoKey.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ModelType {
+    Projected,
+    Geographic,
+    Geocentric,
+    UserDefined,
+}
+
+/// The units of model coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ModelUnits {
+    /// The units of the model CRS, as given by the ProjLinearUnitsGeoKey or GeogAngularUnitsGeoKey
+    #[default]
+    Native,
+    /// Meters for projected and degrees for geographic CRSs
+    Standard,
+}
+
+/// Whether a raster coordinate refers to the upper left corner (area) or the center (point) of a
+/// pixel, as given by the GTRasterTypeGeoKey.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RasterType {
+    #[default]
+    PixelIsArea,
+    PixelIsPoint,
+}
+
+/// The revision of the GeoTIFF specification the GeoKeys follow, as given by the KeyRevision and
+/// MinorRevision of the GeoKeyDirectoryTag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GeoTiffVersion {
+    /// The original GeoTIFF specification 1.0 of 1995
+    #[default]
+    V1_0,
+    /// The OGC GeoTIFF standard 1.1 of 2019, which renames some keys and clarifies their use
+    V1_1,
+}
+
+impl fmt::Display for GeoTiffVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GeoTiffVersion::V1_0 => write!(f, "1.0"),
+            GeoTiffVersion::V1_1 => write!(f, "1.1"),
+        }
+    }
+}
+
+impl ModelType {
+    fn from_u16(value: u16) -> Option<Self> {
+        match value {
+            1 => Some(ModelType::Projected),
+            2 => Some(ModelType::Geographic),
+            3 => Some(ModelType::Geocentric),
+            GeoKeyDirectory::USER_DEFINED => Some(ModelType::UserDefined),
+            _ => None,
+        }
+    }
+
+    fn to_u16(self) -> u16 {
+        match self {
+            ModelType::Projected => 1,
+            ModelType::Geographic => 2,
+            ModelType::Geocentric => 3,
+            ModelType::UserDefined => GeoKeyDirectory::USER_DEFINED,
+        }
+    }
+}
+
+impl RasterType {
+    fn from_u16(value: u16) -> Option<Self> {
+        match value {
+            1 => Some(RasterType::PixelIsArea),
+            2 => Some(RasterType::PixelIsPoint),
+            _ => None,
+        }
+    }
+
+    fn to_u16(self) -> u16 {
+        match self {
+            RasterType::PixelIsArea => 1,
+            RasterType::PixelIsPoint => 2,
+        }
+    }
+
+    /// The offset from corner-based raster coordinates to the raster coordinates used by the
+    /// georeferencing tags.
+    pub(super) fn raster_space_offset(&self) -> [f64; 2] {
+        match self {
+            RasterType::PixelIsArea => [0.0, 0.0],
+            RasterType::PixelIsPoint => [-0.5, -0.5],
+        }
+    }
+}
+
+impl Default for GeoKeyDirectory {
+    fn default() -> Self {
+        Self {
+            key_directory_version: 1,
+            key_revision: 1,
+            minor_revision: 0,
+            keys: BTreeMap::new(),
+        }
+    }
+}
+
+impl GeoKeyDirectory {
+    /// The value of a key indicating a user-defined rather than an EPSG-coded definition.
+    pub const USER_DEFINED: u16 = 32767;
+
+    const HEADER_LENGTH: usize = 4;
+    const ENTRY_LENGTH: usize = 4;
+    /// The codes of the VerticalCSTypeGeoKey denoting ellipsoidal heights in GeoTIFF 1.0
+    const ELLIPSOIDAL_HEIGHT_CODES: RangeInclusive<u16> = 5001..=5033;
+
+    /// Parses the GeoKeys of a directory none of whose keys refer to tags other than the GeoTIFF
+    /// tags, see [`GeoKeyDirectory::from_tags`].
+    #[cfg(test)]
+    pub(super) fn from_tag_data(
+        directory_data: Vec<u16>,
+        double_params_data: Option<Vec<f64>>,
+        ascii_params_data: Option<String>,
+    ) -> GeoTiffResult<Self> {
+        Self::from_tags(
+            directory_data,
+            double_params_data,
+            ascii_params_data,
+            |_| Ok(None),
+        )
+    }
+
+    /// Parses the GeoKeys of the GeoKeyDirectoryTag, GeoDoubleParamsTag and GeoAsciiParamsTag,
+    /// reading the values of keys stored in other tags by `read_tag`, which returns `None` if
+    /// the tag is missing. Keys referring to missing or too short tags are kept as
+    /// [`GeoKeyValue::UnresolvedReference`].
+    pub(super) fn from_tags(
+        directory_data: Vec<u16>,
+        double_params_data: Option<Vec<f64>>,
+        ascii_params_data: Option<String>,
+        mut read_tag: impl FnMut(u16) -> GeoTiffResult<Option<TagValue>>,
+    ) -> GeoTiffResult<Self> {
+        if directory_data.len() < Self::HEADER_LENGTH {
+            return Err(format_error("GeoKeyDirectoryTag is too short"));
+        }
+        let number_of_keys = directory_data[3] as usize;
+
+        let entries = directory_data[Self::HEADER_LENGTH..]
+            .chunks_exact(Self::ENTRY_LENGTH)
+            .take(number_of_keys)
+            .collect::<Vec<_>>();
+        if entries.len() != number_of_keys {
+            return Err(format_error(format!(
+                "Expected {} GeoKeys, found {}",
+                number_of_keys,
+                entries.len()
+            )));
+        }
+
+        let mut keys = BTreeMap::new();
+        for entry in entries {
+            let [key_id, location, count, value_offset] = [entry[0], entry[1], entry[2], entry[3]];
+            let (count, offset) = (count as usize, value_offset as usize);
+            let value = match location {
+                0 => GeoKeyValue::Short(vec![value_offset]),
+                location if location == Tag::GeoKeyDirectoryTag.to_u16() => GeoKeyValue::Short(
+                    directory_data
+                        .get(offset..offset + count)
+                        .ok_or_else(|| out_of_range_error(key_id, Tag::GeoKeyDirectoryTag))?
+                        .to_vec(),
+                ),
+                location if location == Tag::GeoDoubleParamsTag.to_u16() => GeoKeyValue::Double(
+                    double_params_data
+                        .as_ref()
+                        .and_then(|data| data.get(offset..offset + count))
+                        .ok_or_else(|| out_of_range_error(key_id, Tag::GeoDoubleParamsTag))?
+                        .to_vec(),
+                ),
+                location if location == Tag::GeoAsciiParamsTag.to_u16() => {
+                    let value = ascii_params_data
+                        .as_ref()
+                        .and_then(|data| data.get(offset..offset + count))
+                        .ok_or_else(|| out_of_range_error(key_id, Tag::GeoAsciiParamsTag))?;
+                    // Strings in the GeoAsciiParamsTag are terminated by a pipe character
+                    GeoKeyValue::Ascii(value.trim_end_matches(['|', '\0']).to_string())
+                }
+                location => {
+                    match read_tag(location)?.and_then(|values| values.slice(offset, count)) {
+                        Some(values) => GeoKeyValue::Reference { location, values },
+                        None => GeoKeyValue::UnresolvedReference {
+                            location,
+                            count: entry[2],
+                            offset: value_offset,
+                        },
+                    }
+                }
+            };
+            keys.insert(GeoKey::from_u16(key_id), value);
+        }
+
+        let directory = Self {
+            key_directory_version: directory_data[0],
+            key_revision: directory_data[1],
+            minor_revision: directory_data[2],
+            keys,
+        };
+        if let Some(value) = directory.get_short(GeoKey::GTModelType) {
+            if ModelType::from_u16(value).is_none() {
+                return Err(format_error(format!(
+                    "Unknown GTModelTypeGeoKey value {}",
+                    value
+                )));
+            }
+        }
+        if let Some(value) = directory.get_short(GeoKey::GTRasterType) {
+            if RasterType::from_u16(value).is_none() {
+                return Err(format_error(format!(
+                    "Unknown GTRasterTypeGeoKey value {}",
+                    value
+                )));
+            }
+        }
+
+        Ok(directory)
+    }
+
+    /// Encodes the directory as the values of the GeoKeyDirectoryTag, GeoDoubleParamsTag and
+    /// GeoAsciiParamsTag, omitting the latter two if no key refers to them, and the values of the
+    /// other tags keys refer to.
+    ///
+    /// Fails if keys referring to the same tag have values of different types, or the values
+    /// exceed the offsets of the directory.
+    pub(super) fn to_tag_data(&self) -> GeoTiffResult<GeoTagData> {
+        let mut keys = self.keys().collect::<Vec<_>>();
+        keys.sort_by_key(|(key, _)| key.to_u16());
+
+        let mut directory_data = vec![
+            self.key_directory_version,
+            self.key_revision,
+            self.minor_revision,
+            keys.len() as u16,
+        ];
+        let mut short_params_data = Vec::new();
+        let mut double_params_data = Vec::new();
+        let mut ascii_params_data = String::new();
+        let mut referenced_data = BTreeMap::<u16, TagValue>::new();
+        let short_params_offset = Self::HEADER_LENGTH + keys.len() * Self::ENTRY_LENGTH;
+        for (key, value) in keys {
+            let (location, count, value_offset) = match value {
+                GeoKeyValue::Short(values) if values.len() == 1 => (0, 1, values[0] as usize),
+                GeoKeyValue::Short(values) => {
+                    let offset = short_params_offset + short_params_data.len();
+                    short_params_data.extend_from_slice(values);
+                    (Tag::GeoKeyDirectoryTag.to_u16(), values.len(), offset)
+                }
+                GeoKeyValue::Double(values) => {
+                    let offset = double_params_data.len();
+                    double_params_data.extend_from_slice(values);
+                    (Tag::GeoDoubleParamsTag.to_u16(), values.len(), offset)
+                }
+                GeoKeyValue::Ascii(value) => {
+                    let offset = ascii_params_data.len();
+                    // Strings in the GeoAsciiParamsTag are terminated by a pipe character
+                    ascii_params_data.push_str(value);
+                    ascii_params_data.push('|');
+                    (Tag::GeoAsciiParamsTag.to_u16(), value.len() + 1, offset)
+                }
+                GeoKeyValue::Reference { location, values } => {
+                    let offset = match referenced_data.get_mut(location) {
+                        Some(data) => {
+                            let offset = data.len();
+                            if !data.append(values) {
+                                return Err(format_error(format!(
+                                    "GeoKeys referring to tag {} have values of different types",
+                                    location
+                                )));
+                            }
+                            offset
+                        }
+                        None => {
+                            referenced_data.insert(*location, values.clone());
+                            0
+                        }
+                    };
+                    (*location, values.len(), offset)
+                }
+                GeoKeyValue::UnresolvedReference {
+                    location,
+                    count,
+                    offset,
+                } => (*location, *count as usize, *offset as usize),
+            };
+            if count > u16::MAX as usize || value_offset > u16::MAX as usize {
+                return Err(out_of_range_error(
+                    key.to_u16(),
+                    Tag::from_u16_exhaustive(location),
+                ));
+            }
+            directory_data.extend_from_slice(&[
+                key.to_u16(),
+                location,
+                count as u16,
+                value_offset as u16,
+            ]);
+        }
+        directory_data.extend_from_slice(&short_params_data);
+
+        Ok(GeoTagData {
+            directory_data,
+            double_params_data: Some(double_params_data).filter(|data| !data.is_empty()),
+            ascii_params_data: Some(ascii_params_data).filter(|data| !data.is_empty()),
+            referenced_data: referenced_data.into_iter().collect(),
+        })
+    }
+
+    /// The version of the GeoKeyDirectoryTag structure, which is always 1.
+    pub fn key_directory_version(&self) -> u16 {
+        self.key_directory_version
+    }
+
+    /// The major revision of the set of keys.
+    pub fn key_revision(&self) -> u16 {
+        self.key_revision
+    }
+
+    /// The minor revision of the set of keys.
+    pub fn minor_revision(&self) -> u16 {
+        self.minor_revision
+    }
+
+    /// The revision of the GeoTIFF specification the keys follow, or `None` if the revision is
+    /// unknown.
+    pub fn version(&self) -> Option<GeoTiffVersion> {
+        match (
+            self.key_directory_version,
+            self.key_revision,
+            self.minor_revision,
+        ) {
+            (1, 1, 0) => Some(GeoTiffVersion::V1_0),
+            (1, 1, 1) => Some(GeoTiffVersion::V1_1),
+            _ => None,
+        }
+    }
+
+    /// Sets the revision of the GeoTIFF specification the keys follow.
+    pub fn set_version(&mut self, version: GeoTiffVersion) {
+        self.key_directory_version = 1;
+        self.key_revision = 1;
+        self.minor_revision = match version {
+            GeoTiffVersion::V1_0 => 0,
+            GeoTiffVersion::V1_1 => 1,
+        };
+    }
+
+    /// Checks that the revision is known and that the keys follow its conventions, i.e. that a
+    /// GeoTIFF 1.1 directory does not use the ellipsoidal height codes of GeoTIFF 1.0 for the
+    /// VerticalCSTypeGeoKey, see [`GeoKeyDirectory::vertical_ellipsoid`].
+    pub(super) fn check_version(&self) -> GeoTiffResult<()> {
+        match self.version() {
+            None => Err(format_error(format!(
+                "Unknown GeoKey directory revision {}.{}.{}",
+                self.key_directory_version, self.key_revision, self.minor_revision
+            ))),
+            Some(GeoTiffVersion::V1_1) => match self.vertical_cs_type() {
+                Some(code) if Self::ELLIPSOIDAL_HEIGHT_CODES.contains(&code) => {
+                    Err(format_error(format!(
+                        "VerticalCSTypeGeoKey {} is a GeoTIFF 1.0 ellipsoidal height code, which \
+                         GeoTIFF 1.1 does not define",
+                        code
+                    )))
+                }
+                _ => Ok(()),
+            },
+            Some(GeoTiffVersion::V1_0) => Ok(()),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// All keys with their values, ordered by key ID.
+    pub fn keys(&self) -> impl Iterator<Item = (GeoKey, &GeoKeyValue)> {
+        self.keys.iter().map(|(key, value)| (*key, value))
+    }
+
+    pub fn get(&self, key: GeoKey) -> Option<&GeoKeyValue> {
+        self.keys.get(&key)
+    }
+
+    /// Sets the value of a key, returning the previous value.
+    pub fn insert(&mut self, key: GeoKey, value: GeoKeyValue) -> Option<GeoKeyValue> {
+        self.keys.insert(key, value)
+    }
+
+    /// Removes a key, returning its value.
+    pub fn remove(&mut self, key: GeoKey) -> Option<GeoKeyValue> {
+        self.keys.remove(&key)
+    }
+
+    /// The first value of a key stored as SHORT.
+    pub fn get_short(&self, key: GeoKey) -> Option<u16> {
+        match self.get(key) {
+            Some(GeoKeyValue::Short(values)) => values.first().copied(),
+            _ => None,
+        }
+    }
+
+    /// The first value of a key stored as DOUBLE.
+    pub fn get_double(&self, key: GeoKey) -> Option<f64> {
+        match self.get(key) {
+            Some(GeoKeyValue::Double(values)) => values.first().copied(),
+            _ => None,
+        }
+    }
+
+    /// All values of a key stored as DOUBLE.
+    pub fn get_doubles(&self, key: GeoKey) -> Option<&[f64]> {
+        match self.get(key) {
+            Some(GeoKeyValue::Double(values)) => Some(values),
+            _ => None,
+        }
+    }
+
+    /// The value of a key stored as ASCII.
+    pub fn get_ascii(&self, key: GeoKey) -> Option<&str> {
+        match self.get(key) {
+            Some(GeoKeyValue::Ascii(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// The type of model coordinate system (GTModelTypeGeoKey)
+    pub fn model_type(&self) -> Option<ModelType> {
+        self.get_short(GeoKey::GTModelType)
+            .and_then(ModelType::from_u16)
+    }
+
+    /// The pixel convention of raster space (GTRasterTypeGeoKey)
+    pub fn raster_type(&self) -> Option<RasterType> {
+        self.get_short(GeoKey::GTRasterType)
+            .and_then(RasterType::from_u16)
+    }
+
+    /// Sets the type of model coordinate system (GTModelTypeGeoKey)
+    pub fn set_model_type(&mut self, model_type: ModelType) {
+        self.insert(
+            GeoKey::GTModelType,
+            GeoKeyValue::Short(vec![model_type.to_u16()]),
+        );
+    }
+
+    /// Sets the pixel convention of raster space (GTRasterTypeGeoKey)
+    pub fn set_raster_type(&mut self, raster_type: RasterType) {
+        self.insert(
+            GeoKey::GTRasterType,
+            GeoKeyValue::Short(vec![raster_type.to_u16()]),
+        );
+    }
+
+    /// The projection of a user-defined projected CRS, see [`ProjectionDefinition`].
+    ///
+    /// Returns `None` if the ProjCoordTransGeoKey is missing.
+    pub fn projection_definition(&self) -> Option<ProjectionDefinition> {
+        ProjectionDefinition::from_geo_keys(self)
+    }
+
+    /// The size of the linear unit of a projected CRS in meters, as given by the ProjLinearUnitsGeoKey
+    /// or the ProjLinearUnitSizeGeoKey for user-defined units.
+    ///
+    /// Returns `None` if the key is missing or refers to an unknown unit.
+    pub fn linear_unit_size(&self) -> Option<f64> {
+        match self.proj_linear_units()? {
+            Self::USER_DEFINED => self.proj_linear_unit_size(),
+            code => crs::linear_unit_size(code),
+        }
+    }
+
+    /// The size of the angular unit of a geodetic CRS in radians, as given by the
+    /// GeogAngularUnitsGeoKey or the GeogAngularUnitSizeGeoKey for user-defined units.
+    ///
+    /// Returns `None` if the key is missing or refers to an unknown unit.
+    pub fn angular_unit_size(&self) -> Option<f64> {
+        match self.geog_angular_units()? {
+            Self::USER_DEFINED => self.geog_angular_unit_size(),
+            code => crs::angular_unit_size(code),
+        }
+    }
+
+    /// The size of the vertical unit in meters, e.g. to convert heights of a DEM in feet to meters.
+    ///
+    /// Returns `None` if the VerticalUnitsGeoKey is missing or refers to an unknown unit.
+    pub fn vertical_unit_size(&self) -> Option<f64> {
+        self.vertical_units().and_then(crs::linear_unit_size)
+    }
+
+    /// The EPSG code of the ellipsoid heights refer to, if the VerticalCSTypeGeoKey is one of the
+    /// ellipsoidal height codes 5001 to 5033 of GeoTIFF 1.0, e.g. 5030 for heights above the WGS 84
+    /// ellipsoid. GeoTIFF 1.1 no longer defines these codes, so `None` is returned for directories
+    /// following GeoTIFF 1.1.
+    pub fn vertical_ellipsoid(&self) -> Option<u16> {
+        if self.version() == Some(GeoTiffVersion::V1_1) {
+            return None;
+        }
+        self.vertical_cs_type()
+            .filter(|code| Self::ELLIPSOIDAL_HEIGHT_CODES.contains(code))
+            // The codes follow the EPSG codes of the ellipsoids
+            .map(|code| code + 2000)
+    }
+
+    /// The EPSG code of the model CRS, i.e. the ProjectedCSTypeGeoKey for projected models and the
+    /// GeographicTypeGeoKey otherwise.
+    ///
+    /// Returns `None` if the CRS is undefined or user-defined, see
+    /// [`GeoKeyDirectory::is_user_defined_crs`].
+    pub fn epsg_code(&self) -> Option<u16> {
+        self.crs_code().filter(|code| *code != Self::USER_DEFINED)
+    }
+
+    /// Whether the model CRS is user-defined, i.e. described by GeoKeys instead of an EPSG code.
+    pub fn is_user_defined_crs(&self) -> bool {
+        self.crs_code() == Some(Self::USER_DEFINED)
+    }
+
+    fn crs_code(&self) -> Option<u16> {
+        let code = match self.model_type() {
+            Some(ModelType::Projected) => self.projected_type(),
+            Some(ModelType::Geographic | ModelType::Geocentric) => self.geographic_type(),
+            Some(ModelType::UserDefined) => Some(Self::USER_DEFINED),
+            None => self.projected_type().or_else(|| self.geographic_type()),
+        };
+        // A value of 0 means undefined
+        code.filter(|code| *code != 0)
+    }
+}
+
+fn out_of_range_error(key_id: u16, tag: Tag) -> GeoTiffError {
+    format_error(format!(
+        "Value of GeoKey {} is out of range of {:?}",
+        key_id, tag
+    ))
+}
+
+fn format_error(message: impl Into<String>) -> GeoTiffError {
+    GeoTiffError::GeoKey(message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let mut directory = GeoKeyDirectory::default();
+        directory.insert(GeoKey::GTModelType, GeoKeyValue::Short(vec![1]));
+        directory.insert(GeoKey::ProjectedCSType, GeoKeyValue::Short(vec![32632]));
+        directory.insert(GeoKey::Unknown(60000), GeoKeyValue::Double(vec![0.5]));
+        let json = serde_json::to_string(&directory).unwrap();
+        assert!(
+            json.contains(r#""ProjectedCSType":{"Short":[32632]}"#),
+            "{}",
+            json
+        );
+        assert!(json.contains(r#""60000":{"Double":[0.5]}"#), "{}", json);
+        assert_eq!(
+            serde_json::from_str::<GeoKeyDirectory>(&json).unwrap(),
+            directory
+        );
+        assert!(serde_json::from_str::<GeoKey>(r#""Projected""#).is_err());
+    }
+
+    #[test]
+    fn test_from_tag_data() {
+        let directory_data = vec![
+            1, 1, 0, 6, //
+            1024, 0, 1, 2, //
+            1025, 0, 1, 2, //
+            2049, 34737, 7, 0, //
+            2057, 34736, 1, 1, //
+            60000, 34735, 2, 28, //
+            60001, 50000, 3, 1, //
+            3, 4, //
+        ];
+        let double_params_data = Some(vec![0.0, 6378137.0]);
+        let ascii_params_data = Some("WGS 84|".to_string());
+        let directory = GeoKeyDirectory::from_tags(
+            directory_data.clone(),
+            double_params_data.clone(),
+            ascii_params_data.clone(),
+            |location| {
+                assert_eq!(location, 50000);
+                Ok(Some(TagValue::Unsigned(vec![10, 11, 12, 13])))
+            },
+        )
+        .unwrap();
+
+        assert_eq!(directory.key_directory_version(), 1);
+        assert_eq!(directory.model_type(), Some(ModelType::Geographic));
+        assert_eq!(directory.raster_type(), Some(RasterType::PixelIsPoint));
+        assert_eq!(directory.citation(), None);
+        assert_eq!(directory.geog_citation(), Some("WGS 84"));
+        assert_eq!(directory.geog_semi_major_axis(), Some(6378137.0));
+        assert_eq!(
+            directory.get(GeoKey::Unknown(60000)),
+            Some(&GeoKeyValue::Short(vec![3, 4]))
+        );
+        assert_eq!(
+            directory.get(GeoKey::Unknown(60001)),
+            Some(&GeoKeyValue::Reference {
+                location: 50000,
+                values: TagValue::Unsigned(vec![11, 12, 13])
+            })
+        );
+        assert_eq!(directory.keys().count(), 6);
+
+        // The entries of keys referring to missing or too short tags are kept as is
+        let unresolved = GeoKeyValue::UnresolvedReference {
+            location: 50000,
+            count: 3,
+            offset: 1,
+        };
+        for values in [None, Some(TagValue::Unsigned(vec![10, 11, 12]))] {
+            let directory = GeoKeyDirectory::from_tags(
+                directory_data.clone(),
+                double_params_data.clone(),
+                ascii_params_data.clone(),
+                |_| Ok(values.clone()),
+            )
+            .unwrap();
+            assert_eq!(directory.model_type(), Some(ModelType::Geographic));
+            assert_eq!(directory.get(GeoKey::Unknown(60001)), Some(&unresolved));
+            let data = directory.to_tag_data().unwrap();
+            assert_eq!(data.directory_data[24..28], [60001, 50000, 3, 1]);
+            assert_eq!(data.referenced_data, Vec::new());
+        }
+    }
+
+    #[test]
+    fn test_unexpected_value_type() {
+        let directory =
+            GeoKeyDirectory::from_tag_data(vec![1, 1, 0, 1, 2057, 0, 1, 7], None, None).unwrap();
+
+        assert_eq!(directory.get_short(GeoKey::GeogSemiMajorAxis), Some(7));
+        assert_eq!(directory.geog_semi_major_axis(), None);
+    }
+
+    #[test]
+    fn test_geo_key_ids() {
+        assert_eq!(GeoKey::from_u16(3072), GeoKey::ProjectedCSType);
+        assert_eq!(GeoKey::ProjectedCSType.to_u16(), 3072);
+        assert_eq!(GeoKey::from_u16(1), GeoKey::Unknown(1));
+        assert_eq!(GeoKey::Unknown(1).to_u16(), 1);
+    }
+
+    #[test]
+    fn test_to_tag_data() {
+        let directory_data = vec![
+            1, 1, 0, 7, //
+            1024, 0, 1, 1, //
+            1026, 34737, 22, 0, //
+            2049, 34737, 7, 22, //
+            3072, 0, 1, 32767, //
+            3078, 34736, 2, 0, //
+            60000, 50000, 3, 0, //
+            60001, 50000, 2, 3, //
+        ];
+        let double_params_data = vec![49.0, 46.0];
+        let ascii_params_data = "WGS 84 / UTM zone 32N|WGS 84|".to_string();
+        let referenced_data = TagValue::Float(vec![0.5, 1.5, 2.5, 3.5, 4.5]);
+        let directory = GeoKeyDirectory::from_tags(
+            directory_data.clone(),
+            Some(double_params_data.clone()),
+            Some(ascii_params_data.clone()),
+            |_| Ok(Some(referenced_data.clone())),
+        )
+        .unwrap();
+
+        assert_eq!(
+            directory.to_tag_data().unwrap(),
+            GeoTagData {
+                directory_data,
+                double_params_data: Some(double_params_data),
+                ascii_params_data: Some(ascii_params_data),
+                referenced_data: vec![(50000, referenced_data)],
+            }
+        );
+        assert_eq!(
+            GeoKeyDirectory::default().to_tag_data().unwrap(),
+            GeoTagData {
+                directory_data: vec![1, 1, 0, 0],
+                double_params_data: None,
+                ascii_params_data: None,
+                referenced_data: Vec::new(),
+            }
+        );
+
+        // Keys referring to the same tag must have values of the same type
+        let mut directory = GeoKeyDirectory::default();
+        for (key, values) in [
+            (60000, TagValue::Float(vec![0.5])),
+            (60001, TagValue::Unsigned(vec![1])),
+        ] {
+            directory.insert(
+                GeoKey::Unknown(key),
+                GeoKeyValue::Reference {
+                    location: 50000,
+                    values,
+                },
+            );
+        }
+        assert!(directory.to_tag_data().is_err());
+    }
+
+    #[test]
+    fn test_insert() {
+        let mut directory = GeoKeyDirectory::default();
+        directory.set_model_type(ModelType::Geographic);
+        directory.set_raster_type(RasterType::PixelIsPoint);
+        directory.insert(GeoKey::GeographicType, GeoKeyValue::Short(vec![4326]));
+        directory.insert(GeoKey::Unknown(5000), GeoKeyValue::Short(vec![1, 2, 3]));
+
+        let data = directory.to_tag_data().unwrap();
+        let mut directory = GeoKeyDirectory::from_tag_data(
+            data.directory_data,
+            data.double_params_data,
+            data.ascii_params_data,
+        )
+        .unwrap();
+        assert_eq!(directory.model_type(), Some(ModelType::Geographic));
+        assert_eq!(directory.raster_type(), Some(RasterType::PixelIsPoint));
+        assert_eq!(directory.epsg_code(), Some(4326));
+        assert_eq!(
+            directory.get(GeoKey::Unknown(5000)),
+            Some(&GeoKeyValue::Short(vec![1, 2, 3]))
+        );
+
+        directory.remove(GeoKey::GeographicType);
+        assert_eq!(directory.epsg_code(), None);
+    }
+
+    #[test]
+    fn test_unit_sizes() {
+        let directory = GeoKeyDirectory::from_tag_data(
+            vec![
+                1, 1, 0, 3, //
+                2054, 0, 1, 32767, //
+                2055, 34736, 1, 0, //
+                3076, 0, 1, 9002, //
+            ],
+            Some(vec![0.5]),
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(directory.linear_unit_size(), Some(0.3048));
+        assert_eq!(directory.angular_unit_size(), Some(0.5));
+        assert_eq!(GeoKeyDirectory::default().linear_unit_size(), None);
+    }
+
+    #[test]
+    fn test_vertical_keys() {
+        let directory = GeoKeyDirectory::from_tag_data(
+            vec![
+                1, 1, 0, 3, //
+                4096, 0, 1, 5703, //
+                4097, 34737, 12, 0, //
+                4099, 0, 1, 9003, //
+            ],
+            None,
+            Some("NAVD88 (ft)|".to_string()),
+        )
+        .unwrap();
+
+        assert_eq!(directory.vertical_cs_type(), Some(5703));
+        assert_eq!(directory.vertical_citation(), Some("NAVD88 (ft)"));
+        assert_eq!(directory.vertical_datum(), None);
+        assert_eq!(directory.vertical_units(), Some(9003));
+        assert_eq!(directory.vertical_unit_size(), Some(0.304800609601219));
+        assert_eq!(GeoKeyDirectory::default().vertical_unit_size(), None);
+    }
+
+    #[test]
+    fn test_version() {
+        let mut directory = GeoKeyDirectory::default();
+        assert_eq!(directory.version(), Some(GeoTiffVersion::V1_0));
+        directory.set_version(GeoTiffVersion::V1_1);
+        assert_eq!(
+            directory.to_tag_data().unwrap().directory_data,
+            vec![1, 1, 1, 0]
+        );
+        assert_eq!(GeoTiffVersion::V1_1.to_string(), "1.1");
+
+        let directory_data = vec![1, 1, 0, 1, 4096, 0, 1, 5030];
+        let directory = GeoKeyDirectory::from_tag_data(directory_data, None, None).unwrap();
+        assert_eq!(directory.vertical_ellipsoid(), Some(7030));
+        assert!(directory.check_version().is_ok());
+        // GeoTIFF 1.1 does not define the ellipsoidal height codes
+        let directory_data = vec![1, 1, 1, 1, 4096, 0, 1, 5030];
+        let directory = GeoKeyDirectory::from_tag_data(directory_data, None, None).unwrap();
+        assert_eq!(directory.version(), Some(GeoTiffVersion::V1_1));
+        assert_eq!(directory.vertical_ellipsoid(), None);
+        assert!(directory.check_version().is_err());
+        let directory_data = vec![1, 1, 1, 1, 4096, 0, 1, 5703];
+        let directory = GeoKeyDirectory::from_tag_data(directory_data, None, None).unwrap();
+        assert_eq!(directory.vertical_ellipsoid(), None);
+        assert!(directory.check_version().is_ok());
+
+        let directory = GeoKeyDirectory::from_tag_data(vec![1, 0, 2, 0], None, None).unwrap();
+        assert_eq!(directory.version(), None);
+        assert!(directory.check_version().is_err());
+    }
+
+    #[test]
+    fn test_epsg_code() {
+        let projected = GeoKeyDirectory::from_tag_data(
+            vec![
+                1, 1, 0, 3, 1024, 0, 1, 1, 2048, 0, 1, 4326, 3072, 0, 1, 32632,
+            ],
+            None,
+            None,
+        )
+        .unwrap();
+        let geographic =
+            GeoKeyDirectory::from_tag_data(vec![1, 1, 0, 1, 2048, 0, 1, 4326], None, None).unwrap();
+        let user_defined = GeoKeyDirectory::from_tag_data(
+            vec![1, 1, 0, 2, 1024, 0, 1, 1, 3072, 0, 1, 32767],
+            None,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(projected.epsg_code(), Some(32632));
+        assert!(!projected.is_user_defined_crs());
+        assert_eq!(geographic.epsg_code(), Some(4326));
+        assert_eq!(user_defined.epsg_code(), None);
+        assert!(user_defined.is_user_defined_crs());
+        assert_eq!(GeoKeyDirectory::default().epsg_code(), None);
+        assert!(!GeoKeyDirectory::default().is_user_defined_crs());
+    }
+
+    #[test]
+    fn test_empty_directory() {
+        let directory = GeoKeyDirectory::from_tag_data(vec![1, 1, 0, 0], None, None).unwrap();
+
+        assert!(directory.is_empty());
+        assert_eq!(directory.raster_type(), None);
+    }
+
+    #[test]
+    fn test_invalid_tag_data() {
+        assert!(GeoKeyDirectory::from_tag_data(vec![1, 1, 0], None, None).is_err());
+        assert!(GeoKeyDirectory::from_tag_data(vec![1, 1, 0, 1], None, None).is_err());
+        assert!(
+            GeoKeyDirectory::from_tag_data(vec![1, 1, 0, 1, 2057, 34736, 1, 0], None, None)
+                .is_err()
+        );
+        assert!(
+            GeoKeyDirectory::from_tag_data(vec![1, 1, 0, 1, 1025, 0, 1, 3], None, None).is_err()
+        );
+    }
+}
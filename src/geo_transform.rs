@@ -0,0 +1,163 @@
+use crate::{CoordinateTransform, GeoTiffError};
+
+/// A GDAL geotransform `[origin_x, pixel_width, row_rotation, origin_y, column_rotation,
+/// pixel_height]`, mapping the raster coordinate (column, row) to the model coordinate
+/// `x = origin_x + column * pixel_width + row * row_rotation` and
+/// `y = origin_y + column * column_rotation + row * pixel_height`.
+///
+/// This is the representation of affine transformations used by GDAL, rasterio and most other
+/// tools, see [`CoordinateTransform::from_geotransform`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeoTransform(pub [f64; 6]);
+
+impl GeoTransform {
+    /// The model coordinate of the upper left corner of the raster.
+    pub fn origin(&self) -> [f64; 2] {
+        [self.0[0], self.0[3]]
+    }
+
+    /// The width and height of a pixel in model units, the height being negative for north-up
+    /// rasters.
+    pub fn pixel_size(&self) -> [f64; 2] {
+        [self.0[1], self.0[5]]
+    }
+
+    /// The rotation terms `[row_rotation, column_rotation]`, which are zero for north-up rasters.
+    pub fn rotation(&self) -> [f64; 2] {
+        [self.0[2], self.0[4]]
+    }
+
+    /// Whether the rotation terms are not zero, i.e. the rows and columns of the raster are rotated
+    /// or sheared with respect to the axes of model space.
+    pub fn has_rotation(&self) -> bool {
+        self.rotation() != [0.0, 0.0]
+    }
+
+    /// Whether the raster is north-up, i.e. without rotation, with columns increasing towards
+    /// east and rows towards south.
+    pub fn is_north_up(&self) -> bool {
+        let [pixel_width, pixel_height] = self.pixel_size();
+        !self.has_rotation() && pixel_width > 0.0 && pixel_height < 0.0
+    }
+
+    /// Transforms a raster coordinate (column, row) to model space.
+    pub fn apply(&self, [column, row]: [f64; 2]) -> [f64; 2] {
+        let [origin_x, pixel_width, row_rotation, origin_y, column_rotation, pixel_height] = self.0;
+        [
+            origin_x + column * pixel_width + row * row_rotation,
+            origin_y + column * column_rotation + row * pixel_height,
+        ]
+    }
+
+    /// The inverse geotransform, mapping model coordinates to raster coordinates, like
+    /// `GDALInvGeoTransform`.
+    ///
+    /// Returns `None` if the geotransform is not invertible.
+    pub fn invert(&self) -> Option<GeoTransform> {
+        let [origin_x, pixel_width, row_rotation, origin_y, column_rotation, pixel_height] = self.0;
+        let determinant = pixel_width * pixel_height - row_rotation * column_rotation;
+        let scale = pixel_width.abs().max(row_rotation.abs())
+            * column_rotation.abs().max(pixel_height.abs());
+        if determinant.abs() <= f64::EPSILON * scale {
+            return None;
+        }
+        let inverse = [
+            pixel_height / determinant,
+            -row_rotation / determinant,
+            -column_rotation / determinant,
+            pixel_width / determinant,
+        ];
+        Some(GeoTransform([
+            -origin_x * inverse[0] - origin_y * inverse[1],
+            inverse[0],
+            inverse[1],
+            -origin_x * inverse[2] - origin_y * inverse[3],
+            inverse[2],
+            inverse[3],
+        ]))
+    }
+}
+
+impl From<[f64; 6]> for GeoTransform {
+    fn from(geotransform: [f64; 6]) -> Self {
+        GeoTransform(geotransform)
+    }
+}
+
+impl From<GeoTransform> for [f64; 6] {
+    fn from(geotransform: GeoTransform) -> Self {
+        geotransform.0
+    }
+}
+
+impl TryFrom<GeoTransform> for CoordinateTransform {
+    type Error = GeoTiffError;
+
+    /// Fails if the geotransform is not invertible.
+    fn try_from(geotransform: GeoTransform) -> Result<Self, Self::Error> {
+        CoordinateTransform::from_geotransform(geotransform.0)
+    }
+}
+
+impl TryFrom<&CoordinateTransform> for GeoTransform {
+    type Error = GeoTiffError;
+
+    /// Fails if the transformation is not affine, see [`CoordinateTransform::geotransform`].
+    fn try_from(coordinate_transform: &CoordinateTransform) -> Result<Self, Self::Error> {
+        coordinate_transform
+            .geotransform()
+            .map(GeoTransform)
+            .ok_or_else(|| {
+                GeoTiffError::Unsupported("The transformation is not affine".to_string())
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TiePoint;
+
+    #[test]
+    fn test_geo_transform() {
+        // Rotated by 30 degrees
+        let geotransform = GeoTransform([1000.0, 8.660254, -5.0, 2000.0, -5.0, -8.660254]);
+        assert_eq!(geotransform.origin(), [1000.0, 2000.0]);
+        assert_eq!(geotransform.pixel_size(), [8.660254, -8.660254]);
+        assert_eq!(geotransform.rotation(), [-5.0, -5.0]);
+        assert!(geotransform.has_rotation() && !geotransform.is_north_up());
+        let north_up = GeoTransform([1000.0, 10.0, 0.0, 2000.0, 0.0, -10.0]);
+        assert!(!north_up.has_rotation() && north_up.is_north_up());
+        assert!(!GeoTransform([1000.0, 10.0, 0.0, 2000.0, 0.0, 10.0]).is_north_up());
+
+        let [x, y] = geotransform.apply([2.0, 1.0]);
+        assert_eq!(
+            [x, y],
+            [1000.0 + 2.0 * 8.660254 - 5.0, 2000.0 - 10.0 - 8.660254]
+        );
+        let [column, row] = geotransform.invert().unwrap().apply([x, y]);
+        assert!((column - 2.0).abs() < 1e-9 && (row - 1.0).abs() < 1e-9);
+        assert_eq!(GeoTransform([0.0, 1.0, 2.0, 0.0, 2.0, 4.0]).invert(), None);
+
+        let coordinate_transform = CoordinateTransform::try_from(geotransform).unwrap();
+        let [model_x, model_y] = coordinate_transform.transform_to_model_f64([2.0, 1.0]);
+        assert!((model_x - x).abs() < 1e-9 && (model_y - y).abs() < 1e-9);
+        let round_trip = GeoTransform::try_from(&coordinate_transform).unwrap();
+        for (value, expected) in round_trip.0.iter().zip(geotransform.0) {
+            assert!((value - expected).abs() < 1e-9);
+        }
+
+        let tie_points = CoordinateTransform::from_tie_points(
+            [[0.0, 0.0], [10.0, 0.0], [0.0, 10.0]]
+                .into_iter()
+                .map(|point| TiePoint {
+                    raster_point: point,
+                    model_point: point,
+                })
+                .collect(),
+        )
+        .unwrap();
+        assert!(GeoTransform::try_from(&tie_points).is_err());
+        assert_eq!(<[f64; 6]>::from(geotransform), geotransform.0);
+    }
+}
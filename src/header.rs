@@ -0,0 +1,248 @@
+use std::collections::{BTreeMap, HashSet};
+use std::io::{Read, Seek, SeekFrom};
+
+use tiff::{TiffError, TiffFormatError};
+
+use crate::{GeoTiffError, GeoTiffResult};
+
+/// The start of the ghost area GDAL writes after the header of COGs.
+const GHOST_AREA_PREFIX: &str = "GDAL_STRUCTURAL_METADATA_SIZE=";
+
+/// The header of a TIFF file, which is parsed independently of the `tiff` crate to inspect the
+/// layout and all entries of the IFDs.
+pub(crate) struct Header {
+    pub(crate) is_little_endian: bool,
+    pub(crate) is_big_tiff: bool,
+    /// The length of the header in bytes
+    pub(crate) len: u64,
+    first_ifd_offset: u64,
+    /// The length of the ghost area following the header in bytes, set by
+    /// [`Header::read_ghost_area`]
+    pub(crate) ghost_area_len: u64,
+}
+
+/// An entry of an IFD as stored in the file.
+pub(crate) struct IfdEntry {
+    pub(crate) tag: u16,
+    pub(crate) field_type: u16,
+    pub(crate) count: u64,
+    /// The value or the offset of the value, of 4 bytes in TIFF and 8 bytes in BigTIFF
+    value_field: Vec<u8>,
+}
+
+impl Header {
+    pub(crate) fn read<R: Read>(reader: &mut R) -> GeoTiffResult<Self> {
+        let mut header = Header {
+            is_little_endian: true,
+            is_big_tiff: false,
+            len: 8,
+            first_ifd_offset: 0,
+            ghost_area_len: 0,
+        };
+        header.is_little_endian = match header.read_bytes::<_, 2>(reader)? {
+            [b'I', b'I'] => true,
+            [b'M', b'M'] => false,
+            _ => return Err(format_error("Invalid TIFF byte order")),
+        };
+        match header.read_uint::<_, 2>(reader)? {
+            42 => header.first_ifd_offset = header.read_uint::<_, 4>(reader)?,
+            43 => {
+                // The size of offsets, followed by a reserved value
+                header.read_uint::<_, 4>(reader)?;
+                header.is_big_tiff = true;
+                header.len = 16;
+                header.first_ifd_offset = header.read_uint::<_, 8>(reader)?;
+            }
+            version => return Err(format_error(format!("Invalid TIFF version {}", version))),
+        }
+        Ok(header)
+    }
+
+    /// Follows the IFD chain, returning the offsets of the IFDs.
+    pub(crate) fn read_ifd_offsets<R: Read + Seek>(
+        &self,
+        reader: &mut R,
+    ) -> GeoTiffResult<Vec<u64>> {
+        let mut ifd_offsets = Vec::new();
+        let mut visited = HashSet::new();
+        let mut offset = self.first_ifd_offset;
+        while offset != 0 {
+            if !visited.insert(offset) {
+                return Err(format_error("The IFD chain contains a cycle"));
+            }
+            ifd_offsets.push(offset);
+            reader.seek(SeekFrom::Start(offset))?;
+            let (num_entries, entry_size) = match self.is_big_tiff {
+                false => (self.read_uint::<_, 2>(reader)?, 12),
+                true => (self.read_uint::<_, 8>(reader)?, 20),
+            };
+            reader.seek(SeekFrom::Current((num_entries * entry_size) as i64))?;
+            offset = match self.is_big_tiff {
+                false => self.read_uint::<_, 4>(reader)?,
+                true => self.read_uint::<_, 8>(reader)?,
+            };
+        }
+        if ifd_offsets.is_empty() {
+            return Err(format_error("The TIFF contains no IFD"));
+        }
+        Ok(ifd_offsets)
+    }
+
+    /// Reads the key-value pairs of GDAL's ghost area following the header, if any, e.g.
+    ///
+    /// ```text
+    /// GDAL_STRUCTURAL_METADATA_SIZE=000140 bytes
+    /// LAYOUT=IFDS_BEFORE_DATA
+    /// BLOCK_ORDER=ROW_MAJOR
+    /// ```
+    pub(crate) fn read_ghost_area<R: Read + Seek>(
+        &mut self,
+        reader: &mut R,
+    ) -> GeoTiffResult<Option<BTreeMap<String, String>>> {
+        // The prefix, a size of 6 digits and " bytes\n"
+        let mut first_line = vec![0; GHOST_AREA_PREFIX.len() + 13];
+        reader.seek(SeekFrom::Start(self.len))?;
+        if reader.read_exact(&mut first_line).is_err() {
+            return Ok(None);
+        }
+        let size = std::str::from_utf8(&first_line)
+            .ok()
+            .and_then(|line| line.strip_prefix(GHOST_AREA_PREFIX))
+            .and_then(|line| line.strip_suffix(" bytes\n"))
+            .and_then(|size| size.parse::<u64>().ok());
+        let Some(size) = size else {
+            return Ok(None);
+        };
+        let mut metadata = vec![0; size as usize];
+        reader.read_exact(&mut metadata)?;
+        self.ghost_area_len = first_line.len() as u64 + size;
+        let entries = String::from_utf8_lossy(&metadata)
+            .lines()
+            .filter_map(|line| line.split_once('='))
+            .map(|(key, value)| (key.to_string(), value.trim().to_string()))
+            .collect();
+        Ok(Some(entries))
+    }
+
+    /// Reads the entries of the IFD at the given offset.
+    pub(crate) fn read_ifd_entries<R: Read + Seek>(
+        &self,
+        reader: &mut R,
+        ifd_offset: u64,
+    ) -> GeoTiffResult<Vec<IfdEntry>> {
+        reader.seek(SeekFrom::Start(ifd_offset))?;
+        let num_entries = match self.is_big_tiff {
+            false => self.read_uint::<_, 2>(reader)?,
+            true => self.read_uint::<_, 8>(reader)?,
+        };
+        let mut entries = Vec::new();
+        for _ in 0..num_entries {
+            let tag = self.read_uint::<_, 2>(reader)? as u16;
+            let field_type = self.read_uint::<_, 2>(reader)? as u16;
+            let (count, value_field) = match self.is_big_tiff {
+                false => (
+                    self.read_uint::<_, 4>(reader)?,
+                    self.read_bytes::<_, 4>(reader)?.to_vec(),
+                ),
+                true => (
+                    self.read_uint::<_, 8>(reader)?,
+                    self.read_bytes::<_, 8>(reader)?.to_vec(),
+                ),
+            };
+            entries.push(IfdEntry {
+                tag,
+                field_type,
+                count,
+                value_field,
+            });
+        }
+        Ok(entries)
+    }
+
+    /// Reads the bytes of the value of an entry whose values have `value_size` bytes, in the byte
+    /// order of the file.
+    pub(crate) fn read_entry_bytes<R: Read + Seek>(
+        &self,
+        reader: &mut R,
+        entry: &IfdEntry,
+        value_size: u64,
+    ) -> GeoTiffResult<Vec<u8>> {
+        let len = entry
+            .count
+            .checked_mul(value_size)
+            .ok_or_else(|| format_error("The value is too long"))?;
+        if len <= entry.value_field.len() as u64 {
+            return Ok(entry.value_field[..len as usize].to_vec());
+        }
+        let offset = match self.is_big_tiff {
+            false => self.read_uint::<_, 4>(&mut entry.value_field.as_slice())?,
+            true => self.read_uint::<_, 8>(&mut entry.value_field.as_slice())?,
+        };
+        reader.seek(SeekFrom::Start(offset))?;
+        // Reading only the bytes available, as the count of a corrupt entry may be arbitrarily large
+        let mut bytes = Vec::new();
+        reader.take(len).read_to_end(&mut bytes)?;
+        if bytes.len() as u64 != len {
+            return Err(format_error(format!(
+                "The value at byte {} exceeds the file",
+                offset
+            )));
+        }
+        Ok(bytes)
+    }
+
+    fn read_bytes<R: Read, const N: usize>(&self, reader: &mut R) -> GeoTiffResult<[u8; N]> {
+        let mut bytes = [0; N];
+        reader.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Reads an unsigned integer of `N` bytes in the byte order of the file.
+    fn read_uint<R: Read, const N: usize>(&self, reader: &mut R) -> GeoTiffResult<u64> {
+        Ok(self.decode_uint(&self.read_bytes::<_, N>(reader)?))
+    }
+
+    /// Decodes an unsigned integer of up to 8 bytes in the byte order of the file.
+    pub(crate) fn decode_uint(&self, bytes: &[u8]) -> u64 {
+        let mut value = [0; 8];
+        value[..bytes.len()].copy_from_slice(bytes);
+        if !self.is_little_endian {
+            value[..bytes.len()].reverse();
+        }
+        u64::from_le_bytes(value)
+    }
+}
+
+fn format_error(message: impl Into<String>) -> GeoTiffError {
+    GeoTiffError::Tiff(TiffError::FormatError(TiffFormatError::Format(
+        message.into(),
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn test_read_ghost_area() {
+        let metadata = "LAYOUT=IFDS_BEFORE_DATA\nBLOCK_ORDER=ROW_MAJOR\n";
+        let mut bytes = b"II\x2a\x00\x00\x00\x00\x00".to_vec();
+        bytes.extend_from_slice(
+            format!(
+                "{}{:06} bytes\n{}",
+                GHOST_AREA_PREFIX,
+                metadata.len(),
+                metadata
+            )
+            .as_bytes(),
+        );
+        let mut reader = Cursor::new(bytes);
+        let mut header = Header::read(&mut reader).unwrap();
+        let ghost_area = header.read_ghost_area(&mut reader).unwrap().unwrap();
+        assert_eq!(ghost_area["LAYOUT"], "IFDS_BEFORE_DATA");
+        assert_eq!(ghost_area.len(), 2);
+        assert_eq!(header.ghost_area_len, 43 + metadata.len() as u64);
+    }
+}
@@ -0,0 +1,291 @@
+use std::io;
+use std::ops::Range;
+
+use reqwest::header::{CONTENT_RANGE, RANGE};
+use reqwest::{Client, StatusCode, Url};
+
+use crate::fetch::{self, FetchOptions, RetryPolicy};
+use crate::{GeoTiffError, GeoTiffResult, RangeSource, RemoteError};
+
+/// A [`RangeSource`] reading a remote file over HTTP(S) by range requests, so that an
+/// [`AsyncGeoTiffReader`](crate::AsyncGeoTiffReader) fetches only the header, the tags and the
+/// strips or tiles actually read, e.g. of a cloud optimized GeoTIFF in object storage.
+///
+/// The server must support range requests. The strips or tiles of a window are fetched by
+/// concurrent requests, see [`HttpSource::with_fetch_options`], and requests failing
+/// transiently, e.g. with status 503, are repeated, see [`HttpSource::with_retry_policy`].
+#[derive(Debug, Clone)]
+pub struct HttpSource {
+    client: Client,
+    url: Url,
+    fetch_options: FetchOptions,
+    retry_policy: RetryPolicy,
+}
+
+impl HttpSource {
+    pub fn new(url: &str) -> GeoTiffResult<Self> {
+        Self::with_client(Client::new(), url)
+    }
+
+    /// Creates a source using the given client, e.g. configured with default headers for
+    /// authentication or with timeouts.
+    pub fn with_client(client: Client, url: &str) -> GeoTiffResult<Self> {
+        let url = Url::parse(url).map_err(|err| {
+            GeoTiffError::InvalidArgument(format!("Invalid URL {}: {}", url, err))
+        })?;
+        Ok(Self {
+            client,
+            url,
+            fetch_options: FetchOptions::default(),
+            retry_policy: RetryPolicy::default(),
+        })
+    }
+
+    /// Sets how many requests fetch the strips or tiles of a window concurrently and which
+    /// ranges are coalesced into a single request.
+    pub fn with_fetch_options(mut self, fetch_options: FetchOptions) -> Self {
+        self.fetch_options = fetch_options;
+        self
+    }
+
+    /// Sets how often and how long after failing transiently requests are repeated, and after
+    /// which time they are aborted.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Requests the given range of bytes, with an exclusive end, returning the Content-Range
+    /// header and the body of the response, and repeats the request according to the retry
+    /// policy.
+    async fn get(&self, range: Range<u64>) -> io::Result<(String, Vec<u8>)> {
+        fetch::retry(self.retry_policy, || self.request(range.clone())).await
+    }
+
+    /// Requests the given range of bytes once, see [`HttpSource::get`].
+    async fn request(&self, range: Range<u64>) -> Result<(String, Vec<u8>), RemoteError> {
+        let response = self
+            .client
+            .get(self.url.clone())
+            .header(RANGE, format!("bytes={}-{}", range.start, range.end - 1))
+            .send()
+            .await
+            .map_err(remote_error)?;
+        match response.status() {
+            StatusCode::PARTIAL_CONTENT => {}
+            StatusCode::OK => {
+                return Err(RemoteError::permanent(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    format!("{} does not support range requests", self.url),
+                )))
+            }
+            status => {
+                let err = io::Error::other(format!(
+                    "Requesting {} failed with status {}",
+                    self.url, status
+                ));
+                // Timeouts, rate limits and unavailable servers may recover
+                return Err(match status {
+                    StatusCode::REQUEST_TIMEOUT
+                    | StatusCode::TOO_MANY_REQUESTS
+                    | StatusCode::INTERNAL_SERVER_ERROR
+                    | StatusCode::BAD_GATEWAY
+                    | StatusCode::SERVICE_UNAVAILABLE
+                    | StatusCode::GATEWAY_TIMEOUT => RemoteError::transient(err),
+                    _ => RemoteError::permanent(err),
+                });
+            }
+        }
+        let content_range = response
+            .headers()
+            .get(CONTENT_RANGE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+        let bytes = response.bytes().await.map_err(remote_error)?;
+        Ok((content_range, bytes.to_vec()))
+    }
+}
+
+/// Classifies errors of connecting or of transferring the response as transient, unlike those
+/// of building the request or following redirects.
+fn remote_error(err: reqwest::Error) -> RemoteError {
+    match err.is_builder() || err.is_redirect() {
+        true => RemoteError::permanent(io::Error::other(err)),
+        false => RemoteError::transient(io::Error::other(err)),
+    }
+}
+
+impl RangeSource for HttpSource {
+    async fn length(&mut self) -> io::Result<u64> {
+        // The length follows the range as in "bytes 0-0/1234"
+        let (content_range, _) = self.get(0..1).await?;
+        content_range
+            .rsplit_once('/')
+            .and_then(|(_, length)| length.parse().ok())
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("The length of {} is unknown", self.url),
+                )
+            })
+    }
+
+    async fn read_range(&mut self, range: Range<u64>) -> io::Result<Vec<u8>> {
+        if range.is_empty() {
+            return Ok(Vec::new());
+        }
+        Ok(self.get(range).await?.1)
+    }
+
+    async fn read_ranges(&mut self, ranges: &[Range<u64>]) -> io::Result<Vec<Vec<u8>>> {
+        let source = &*self;
+        fetch::fetch_ranges(ranges, self.fetch_options, |range| async move {
+            Ok(source.get(range).await?.1)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{AsyncGeoTiffReader, Compression, GeoTiffWriter};
+
+    /// Serves the data on a local port, answering range requests only and counting the bytes sent.
+    /// The first requests are answered by the given error statuses.
+    fn serve(data: Vec<u8>, mut failures: VecDeque<u16>) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/image.tif", listener.local_addr().unwrap());
+        let bytes_sent = Arc::new(AtomicUsize::new(0));
+        let counter = bytes_sent.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut range = None;
+                for line in BufReader::new(&stream).lines() {
+                    let line = line.unwrap();
+                    if line.is_empty() {
+                        break;
+                    }
+                    if let Some(value) = line.to_lowercase().strip_prefix("range: bytes=") {
+                        let (start, end) = value.split_once('-').unwrap();
+                        range = Some((
+                            start.parse::<usize>().unwrap(),
+                            end.parse::<usize>().unwrap(),
+                        ));
+                    }
+                }
+                if let Some(status) = failures.pop_front() {
+                    write!(
+                        stream,
+                        "HTTP/1.1 {} Error\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                        status
+                    )
+                    .unwrap();
+                    continue;
+                }
+                let (start, end) = range.unwrap();
+                let body = &data[start..=end.min(data.len() - 1)];
+                counter.fetch_add(body.len(), Ordering::SeqCst);
+                write!(
+                    stream,
+                    "HTTP/1.1 206 Partial Content\r\nContent-Range: bytes {}-{}/{}\r\n\
+                    Content-Length: {}\r\nConnection: close\r\n\r\n",
+                    start,
+                    start + body.len() - 1,
+                    data.len(),
+                    body.len()
+                )
+                .unwrap();
+                stream.write_all(body).unwrap();
+            }
+        });
+        (url, bytes_sent)
+    }
+
+    #[tokio::test]
+    async fn test_read_tile() {
+        // Hardly compressible samples
+        let sample = |index: u32| (index.wrapping_mul(2654435761) >> 24) as u8;
+        let data = (0..2048 * 2048).map(sample).collect::<Vec<_>>();
+        let mut bytes = Vec::new();
+        GeoTiffWriter::new()
+            .with_tiles(256, 256)
+            .with_compression(Compression::Deflate { level: 1 })
+            .write(&mut bytes, 2048, 2048, 1, &data)
+            .unwrap();
+        let file_length = bytes.len();
+        let (url, bytes_sent) = serve(bytes, VecDeque::new());
+
+        let mut reader = AsyncGeoTiffReader::new(HttpSource::new(&url).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(reader.tile_count(), Some([8, 8]));
+        let tile = reader.read_tile(3, 5).await.unwrap();
+        assert_eq!(
+            tile.get_value_at::<u8>(1, 2, 0),
+            sample((5 * 256 + 2) * 2048 + 3 * 256 + 1)
+        );
+        // Only a fraction of the file has been transferred
+        assert!(bytes_sent.load(Ordering::SeqCst) < file_length / 4);
+
+        // The tiles of the window are fetched by concurrent requests
+        let source = HttpSource::new(&url)
+            .unwrap()
+            .with_fetch_options(FetchOptions {
+                concurrency: 4,
+                coalesce_gap: 0,
+            });
+        let mut reader = AsyncGeoTiffReader::new(source).await.unwrap();
+        let window = reader.read_window(200, 300, 600, 400).await.unwrap();
+        assert_eq!(
+            window.get_value_at::<u8>(599, 399, 0),
+            sample(699 * 2048 + 799)
+        );
+
+        assert!(HttpSource::new("not a url").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_retry() {
+        let mut bytes = Vec::new();
+        GeoTiffWriter::new()
+            .write(&mut bytes, 40, 20, 1, &[7u8; 800])
+            .unwrap();
+        let policy = RetryPolicy {
+            max_retries: 2,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(1),
+            timeout: Some(Duration::from_secs(10)),
+        };
+        let source = |failures: &[u16]| {
+            let (url, _) = serve(bytes.clone(), failures.iter().copied().collect());
+            HttpSource::new(&url).unwrap().with_retry_policy(policy)
+        };
+
+        let mut reader = AsyncGeoTiffReader::new(source(&[503, 429])).await.unwrap();
+        let geotiff = reader.read().await.unwrap();
+        assert_eq!(geotiff.get_value_at::<u8>(39, 19, 0), 7);
+
+        let err = AsyncGeoTiffReader::new(source(&[503, 503, 503]))
+            .await
+            .err()
+            .unwrap();
+        assert!(err.is_transient());
+        assert!(matches!(&err, GeoTiffError::Remote(remote) if remote.attempts() == 3));
+
+        // Permanent failures are not retried
+        let err = AsyncGeoTiffReader::new(source(&[404])).await.err().unwrap();
+        assert!(!err.is_transient());
+        assert!(matches!(&err, GeoTiffError::Remote(remote) if remote.attempts() == 1));
+    }
+}
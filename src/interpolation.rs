@@ -0,0 +1,141 @@
+use crate::GeoTiff;
+
+/// The method of interpolating the value at a position between pixel centers, see
+/// [`GeoTiff::sample`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Interpolation {
+    /// The value of the pixel containing the position
+    #[default]
+    Nearest,
+    /// The distance-weighted mean of the 2x2 pixels around the position
+    Bilinear,
+    /// Cubic convolution of the 4x4 pixels around the position, like GDAL's cubic resampling
+    Cubic,
+}
+
+impl Interpolation {
+    /// The number of neighbouring pixels on each side of a position the interpolation reads.
+    pub(crate) fn radius(self) -> usize {
+        match self {
+            Interpolation::Nearest => 0,
+            Interpolation::Bilinear => 1,
+            Interpolation::Cubic => 2,
+        }
+    }
+}
+
+/// Interpolates the value of the band at a fractional raster coordinate (column, row), pixel
+/// centers being at half-integer coordinates.
+///
+/// Nodata neighbours are left out and the weights of the others are renormalized, so that
+/// values next to nodata remain defined. Pixels beyond the edges repeat the edge pixels.
+/// Returns `None` outside the raster or if no neighbour is valid.
+pub(crate) fn interpolate(
+    geotiff: &GeoTiff,
+    [column, row]: [f64; 2],
+    band: usize,
+    interpolation: Interpolation,
+) -> Option<f64> {
+    let (width, height) = (geotiff.raster_width, geotiff.raster_height);
+    if !(0.0..width as f64).contains(&column) || !(0.0..height as f64).contains(&row) {
+        return None;
+    }
+    if interpolation == Interpolation::Nearest {
+        return geotiff.get_value_at_checked(column as usize, row as usize, band);
+    }
+
+    let radius = interpolation.radius() as isize;
+    let kernel: fn(f64) -> f64 = match interpolation {
+        Interpolation::Bilinear => |distance| 1.0 - distance.abs(),
+        _ => cubic_kernel,
+    };
+    // The position relative to the center of the pixel above and left of it
+    let (x, y) = (column - 0.5, row - 0.5);
+    let (left, top) = (x.floor() as isize, y.floor() as isize);
+    let mut sum = 0.0;
+    let mut weights = 0.0;
+    for neighbour_y in top + 1 - radius..=top + radius {
+        let weight_y = kernel(y - neighbour_y as f64);
+        for neighbour_x in left + 1 - radius..=left + radius {
+            let weight = weight_y * kernel(x - neighbour_x as f64);
+            let value = geotiff.get_value_at_checked::<f64>(
+                neighbour_x.clamp(0, width as isize - 1) as usize,
+                neighbour_y.clamp(0, height as isize - 1) as usize,
+                band,
+            );
+            if let Some(value) = value.filter(|value| !value.is_nan()) {
+                sum += weight * value;
+                weights += weight;
+            }
+        }
+    }
+    (weights.abs() > f64::EPSILON).then(|| sum / weights)
+}
+
+/// The cubic convolution kernel of Keys with `a = -0.5`.
+fn cubic_kernel(distance: f64) -> f64 {
+    let distance = distance.abs();
+    if distance <= 1.0 {
+        (1.5 * distance - 2.5) * distance * distance + 1.0
+    } else if distance < 2.0 {
+        ((-0.5 * distance + 2.5) * distance - 4.0) * distance + 2.0
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{CoordinateTransform, GeoTiffWriter};
+
+    #[test]
+    fn test_sample() {
+        // A ramp increasing by 1 per column and 10 per row, with nodata at the lower right
+        let mut data = (0..36)
+            .map(|index| (index % 6 + index / 6 * 10) as f32)
+            .collect::<Vec<_>>();
+        data[35] = -9999.0;
+        let transform =
+            CoordinateTransform::from_geotransform([1000.0, 10.0, 0.0, 2000.0, 0.0, -10.0])
+                .unwrap();
+        let mut bytes = Vec::new();
+        GeoTiffWriter::new()
+            .with_coordinate_transform(transform)
+            .with_nodata(-9999.0)
+            .write(&mut bytes, 6, 6, 1, &data)
+            .unwrap();
+        let geotiff = GeoTiff::read(Cursor::new(bytes)).unwrap();
+
+        // Between the centers of pixels (1, 1) and (2, 2)
+        let coord = [1020.0, 1980.0];
+        assert_eq!(geotiff.sample(coord, 0, Interpolation::Nearest), Some(22.0));
+        assert_eq!(
+            geotiff.sample(coord, 0, Interpolation::Bilinear),
+            Some(16.5)
+        );
+        // Cubic convolution reproduces linear ramps
+        let cubic = geotiff.sample(coord, 0, Interpolation::Cubic).unwrap();
+        assert!((cubic - 16.5).abs() < 1e-9);
+        // At a pixel center
+        assert_eq!(
+            geotiff.sample([1015.0, 1985.0], 0, Interpolation::Bilinear),
+            Some(11.0)
+        );
+
+        // The nodata pixel is left out
+        let value = geotiff.sample([1050.0, 1950.0], 0, Interpolation::Bilinear);
+        assert_eq!(value, Some((44.0 + 45.0 + 54.0) / 3.0));
+        assert_eq!(
+            geotiff.sample([1055.0, 1945.0], 0, Interpolation::Nearest),
+            None
+        );
+        // Beyond the edge
+        assert_eq!(
+            geotiff.sample([1070.0, 1980.0], 0, Interpolation::Bilinear),
+            None
+        );
+    }
+}
@@ -1,15 +1,124 @@
 //! A [GeoTIFF](https://www.ogc.org/standard/geotiff) library for Rust
+//!
+//! GeoTIFFs are read from and written to any source implementing [`Read`] and [`Seek`], e.g. a
+//! [`Cursor`] over bytes in memory, without assuming a filesystem. The crate thus
+//! compiles to WebAssembly (`wasm32-unknown-unknown`) to parse and display GeoTIFFs in browsers,
+//! where the `AsyncGeoTiffReader` of the `tokio` feature reads from a `RangeSource`, which may be
+//! implemented by `fetch` requests. The `lerc`, `mmap`, `object_store`, `proj`, `rayon` and
+//! `zstd` features depend on a filesystem, native libraries or threads and are not supported on
+//! wasm32.
 use std::any::type_name;
-use std::io::{Read, Seek};
+use std::io::{Cursor, Read, Seek, Write};
+use std::sync::Arc;
 
 use num_traits::FromPrimitive;
-use tiff::decoder::{Decoder, DecodingResult};
-use tiff::tags::Tag;
-use tiff::TiffResult;
+use tiff::decoder::Decoder;
+use tiff::tags::{PlanarConfiguration, SampleFormat, Tag};
 
+use crate::block_cache::ImageBlocks;
+use crate::chunk::{BandDecoder, RawChunks};
+use crate::crs::Crs;
 use crate::raster_data::*;
+use crate::statistics::{HistogramAccumulator, StatisticsAccumulator};
 
+#[cfg(feature = "tokio")]
+pub use crate::async_reader::{AsyncGeoTiffReader, MaybeSend, RangeSource};
+pub use crate::band_math::Expr;
+pub use crate::block_cache::BlockCacheCapacity;
+pub use crate::cog_report::{CogImageReport, CogReport};
+pub use crate::coordinate_transform::{
+    CoordinateTransform, PolynomialOrder, RpcModel, TiePoint, TransformResiduals,
+};
+#[cfg(feature = "proj")]
+pub use crate::crs_transform::CrsTransform;
+pub use crate::error::{GeoTiffError, GeoTiffResult, RemoteError};
+pub use crate::extent::Extent;
+#[cfg(any(feature = "http", feature = "object_store"))]
+pub use crate::fetch::{FetchOptions, RetryPolicy};
+pub use crate::gdal_metadata::GdalMetadata;
+pub use crate::geo_key_directory::{
+    GeoKey, GeoKeyDirectory, GeoKeyValue, GeoTiffVersion, ModelType, ModelUnits, RasterType,
+};
+pub use crate::geo_transform::GeoTransform;
+#[cfg(feature = "http")]
+pub use crate::http::HttpSource;
+pub use crate::interpolation::Interpolation;
+pub use crate::metadata::{ImageMetadata, Metadata, TagEntry, TagValue};
+#[cfg(feature = "mmap")]
+pub use crate::mmap::MmapGeoTiffReader;
+#[cfg(feature = "object_store")]
+pub use crate::object_store::ObjectStoreSource;
+pub use crate::pixels::{Pixel, Pixels};
+pub use crate::projection::{ProjectionDefinition, ProjectionMethod};
+pub use crate::raster_data::{Layout, Sample};
+pub use crate::reader::{
+    Block, Blocks, Compliance, GeoTiffReader, Overview, PointQuery, WindowSnap,
+};
+pub use crate::statistics::Statistics;
+pub use crate::terrain::{SlopeUnits, Terrain};
+#[cfg(feature = "proj")]
+pub use crate::warp::WarpGrid;
+pub use crate::writer::{
+    CogWriter, Compression, GeoTiffWriter, Resampling, StreamingWriter, TileWriter,
+};
+#[cfg(feature = "geo")]
+pub use crate::zonal_statistics::ZonalCoverage;
+
+#[cfg(feature = "tokio")]
+mod async_reader;
+mod band_math;
+mod block_cache;
+mod chunk;
+mod cog_report;
+mod coordinate_transform;
+mod crs;
+#[cfg(feature = "proj")]
+mod crs_transform;
+mod error;
+mod extent;
+#[cfg(any(feature = "http", feature = "object_store"))]
+mod fetch;
+mod gdal_metadata;
+#[cfg(feature = "geo")]
+mod geo;
+mod geo_key_directory;
+mod geo_transform;
+mod header;
+#[cfg(feature = "http")]
+mod http;
+mod interpolation;
+mod mask;
+mod metadata;
+#[cfg(feature = "mmap")]
+mod mmap;
+#[cfg(feature = "ndarray")]
+mod ndarray;
+#[cfg(feature = "object_store")]
+mod object_store;
+mod pixels;
+mod predictor;
+mod projection;
 mod raster_data;
+mod reader;
+mod statistics;
+mod terrain;
+#[cfg(feature = "proj")]
+mod warp;
+mod writer;
+#[cfg(feature = "geo")]
+mod zonal_statistics;
+
+/// The RPCCoefficientTag, see <http://geotiff.maptools.org/rpc_prop.html>
+const RPC_COEFFICIENT_TAG: u16 = 50844;
+/// The GDAL_METADATA tag, see <https://gdal.org/en/stable/drivers/raster/gtiff.html#metadata>
+const GDAL_METADATA_TAG: u16 = 42112;
+/// The EPSG code of WGS 84 longitude/latitude
+#[cfg(feature = "proj")]
+const WGS_84: u16 = 4326;
+/// The ExtraSamples value of alpha premultiplied into the color samples.
+const ASSOCIATED_ALPHA: u16 = 1;
+/// The ExtraSamples value of alpha independent of the color samples.
+const UNASSOCIATED_ALPHA: u16 = 2;
 
 macro_rules! unwrap_primitive_type {
     ($result: expr, $actual: ty, $expected: ty) => {
@@ -28,46 +137,1261 @@ macro_rules! unwrap_primitive_type {
 /// The basic GeoTIFF struct. This includes any metadata as well as the actual raster data.
 ///
 /// The raster data has a size of raster_width * raster_height * num_samples
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct GeoTiff {
     pub raster_width: usize,
     pub raster_height: usize,
     pub num_samples: usize,
+    geo_key_directory: GeoKeyDirectory,
+    raster_type: RasterType,
+    model_units: ModelUnits,
+    rpc_model: Option<RpcModel>,
+    coordinate_transform: Option<CoordinateTransform>,
     raster_data: RasterData,
+    /// Whether each pixel is valid according to the transparency mask, row by row
+    mask: Option<Vec<bool>>,
+    /// Whether the last sample of each pixel is an alpha value
+    has_alpha: bool,
+    zero_alpha_as_nodata: bool,
+    /// The nodata value of the GDAL_NODATA tag as a single sample of the raster's type
+    nodata: Option<RasterData>,
+    gdal_metadata: Option<GdalMetadata>,
+    /// The scale and offset converting the stored values of each band to physical values
+    band_scale_offset: Vec<Option<[f64; 2]>>,
+    /// The violations of the GeoTIFF specification recovered from when reading the metadata
+    warnings: Vec<String>,
 }
 
 impl GeoTiff {
-    pub fn read<R: Read + Seek>(reader: R) -> TiffResult<Self> {
-        let mut decoder = Decoder::new(reader)?;
+    /// Reads the full-resolution raster of the first image from any source implementing
+    /// [`Read`] and [`Seek`], e.g. a [`File`](std::fs::File) wrapped in a
+    /// [`BufReader`](std::io::BufReader) or a [`Cursor`] over bytes in memory.
+    pub fn read<R: Read + Seek>(reader: R) -> GeoTiffResult<Self> {
+        GeoTiffReader::new(reader)?.read()
+    }
+
+    /// Reads a GeoTIFF held in memory, e.g. as received from a network service, without writing
+    /// it to a temporary file first, see [`GeoTiff::read`].
+    pub fn from_bytes(bytes: &[u8]) -> GeoTiffResult<Self> {
+        GeoTiff::read(Cursor::new(bytes))
+    }
+
+    /// Reads a window of `width * height` pixels with its upper left corner at pixel `(x, y)`,
+    /// see [`GeoTiffReader::read_window`].
+    pub fn read_window<R: Read + Seek>(
+        reader: R,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    ) -> GeoTiffResult<Self> {
+        GeoTiffReader::new(reader)?.read_window(x, y, width, height)
+    }
+
+    /// Reads the top-level image with the given index of a file containing multiple images, see
+    /// [`GeoTiffReader::select_image`].
+    pub fn read_image<R: Read + Seek>(reader: R, index: usize) -> GeoTiffResult<Self> {
+        let mut reader = GeoTiffReader::new(reader)?;
+        reader.select_image(index)?;
+        reader.read()
+    }
+
+    /// Reads the pixels intersecting a bounding box in model space, see
+    /// [`GeoTiffReader::read_bbox`].
+    pub fn read_bbox<R: Read + Seek>(
+        reader: R,
+        min_x: f64,
+        min_y: f64,
+        max_x: f64,
+        max_y: f64,
+    ) -> GeoTiffResult<Self> {
+        GeoTiffReader::new(reader)?.read_bbox(min_x, min_y, max_x, max_y)
+    }
+
+    /// Validates that a file is a Cloud Optimized GeoTIFF, like `rio cogeo validate` does,
+    /// checking the order of the IFDs, the tiling, the order of the tiles and the overviews of
+    /// the first image, see [`CogReport`].
+    ///
+    /// Fails only if the file cannot be read, while violations are listed in the report.
+    pub fn validate_cog<R: Read + Seek>(reader: R) -> GeoTiffResult<CogReport> {
+        CogReport::read(reader)
+    }
+
+    /// Adds or replaces tags of the first image of an existing little-endian TIFF, e.g. the
+    /// ImageDescription, DateTime, GDAL_METADATA or GDAL_NODATA tag, without rewriting the raster
+    /// data.
+    ///
+    /// The values are replaced in place if all tags exist and each new value fits into the space
+    /// of the old one. Otherwise, the modified IFD is appended to the file, leaving the previous
+    /// one as unused space. Unsigned and signed integers are stored in the smallest type holding
+    /// them and floating-point values as DOUBLE. The tags describing the layout of the raster
+    /// data, e.g. the strip offsets, must not be changed.
+    pub fn update_tags<F: Read + Write + Seek>(
+        file: F,
+        tags: &[(Tag, TagValue)],
+    ) -> GeoTiffResult<()> {
+        writer::update_tags(file, tags)
+    }
+
+    /// Lists all TIFF tags and GeoKeys of all images of a file, known and unknown ones, like
+    /// `tiffdump` and `listgeo` do, see [`Metadata`].
+    ///
+    /// Only the structure of the IFDs is required to be valid, such that malformed files can be
+    /// inspected: values that cannot be read are reported in [`ImageMetadata::errors`].
+    pub fn metadata<R: Read + Seek>(reader: R) -> GeoTiffResult<Metadata> {
+        Metadata::read(reader)
+    }
+
+    /// The metadata of a reduced-resolution overview of the raster, whose coordinate
+    /// transformation is scaled accordingly, as overviews don't carry their own georeferencing.
+    fn overview_metadata(&self, raster_width: usize, raster_height: usize) -> Self {
+        let factor = [
+            self.raster_width as f64 / raster_width as f64,
+            self.raster_height as f64 / raster_height as f64,
+        ];
+        let mut overview = self.clone();
+        overview.raster_width = raster_width;
+        overview.raster_height = raster_height;
+        if let Some(coordinate_transform) = &mut overview.coordinate_transform {
+            coordinate_transform.scale_raster_space(factor);
+        }
+        if let Some(rpc_model) = &mut overview.rpc_model {
+            rpc_model.scale_image_space(factor);
+        }
+        overview
+    }
+
+    /// Resamples the raster to `raster_width * raster_height` pixels covering the same extent by
+    /// nearest neighbour, scaling the coordinate transformation accordingly.
+    fn resample_nearest(&self, raster_width: usize, raster_height: usize) -> Self {
+        let mut resampled = self.overview_metadata(raster_width, raster_height);
+        resampled.raster_data = self.raster_data.resample_nearest(
+            [self.raster_width, self.raster_height],
+            self.num_samples,
+            [raster_width, raster_height],
+        );
+        resampled.mask = self.mask.as_ref().map(|mask| {
+            resample_nearest(
+                mask,
+                [self.raster_width, self.raster_height],
+                1,
+                [raster_width, raster_height],
+            )
+        });
+        resampled
+    }
+
+    /// Resamples the raster to `raster_width * raster_height` pixels covering the given areas of
+    /// it by nearest neighbour or weighted average, adjusting the coordinate transformation
+    /// accordingly.
+    fn resample_areas(
+        &self,
+        areas: PixelAreas,
+        raster_width: usize,
+        raster_height: usize,
+        resampling: Resampling,
+    ) -> Self {
+        let size = [self.raster_width, self.raster_height];
+        let target_size = [raster_width, raster_height];
+        let valid = (0..self.raster_data.len())
+            .map(|index| {
+                let pixel = index / self.num_samples;
+                let is_nodata = self
+                    .nodata
+                    .as_ref()
+                    .is_some_and(|nodata| self.raster_data.sample_equals(index, nodata));
+                !is_nodata && self.is_valid_at(pixel % self.raster_width, pixel / self.raster_width)
+            })
+            .collect::<Vec<_>>();
+
+        let mut resampled = self.clone();
+        resampled.raster_width = raster_width;
+        resampled.raster_height = raster_height;
+        resampled.raster_data = self.raster_data.resample_areas(
+            size,
+            self.num_samples,
+            &valid,
+            areas,
+            target_size,
+            resampling,
+        );
+        // A pixel is valid if the nearest pixel or, for averages, any covered pixel is
+        resampled.mask = self.mask.as_ref().map(|mask| {
+            let flags = mask.iter().map(|&valid| valid as u8).collect::<Vec<_>>();
+            resample_areas(&flags, size, 1, mask, areas, target_size, resampling)
+                .into_iter()
+                .map(|flag| flag != 0)
+                .collect()
+        });
+        if let Some(coordinate_transform) = &mut resampled.coordinate_transform {
+            coordinate_transform.offset_raster_space(areas.origin);
+            coordinate_transform.scale_raster_space(areas.step);
+        }
+        if let Some(rpc_model) = &mut resampled.rpc_model {
+            rpc_model.sample_offset -= areas.origin[0];
+            rpc_model.line_offset -= areas.origin[1];
+            rpc_model.scale_image_space(areas.step);
+        }
+        resampled
+    }
+
+    /// Fails if the window `[x, y, width, height]` is empty or exceeds the raster.
+    fn check_window(&self, [x, y, width, height]: [usize; 4]) -> GeoTiffResult<()> {
+        if width == 0
+            || height == 0
+            || x.saturating_add(width) > self.raster_width
+            || y.saturating_add(height) > self.raster_height
+        {
+            return Err(GeoTiffError::OutOfBounds(format!(
+                "The window of {}x{} pixels at ({}, {}) exceeds the raster of {}x{} pixels",
+                width, height, x, y, self.raster_width, self.raster_height
+            )));
+        }
+        Ok(())
+    }
+
+    /// Decodes the strips or tiles intersecting the window `[x, y, width, height]` of the current
+    /// image of the decoder and restricts the GeoTiff to the window and to the given bands, in
+    /// that order.
+    ///
+    /// Of planar rasters, only the planes of the given bands are decoded. The decoded strips or
+    /// tiles are taken from and added to the cache, if any.
+    fn read_window_data<R: Read + Seek>(
+        &mut self,
+        decoder: &mut Decoder<R>,
+        [x, y, width, height]: [usize; 4],
+        bands: &[usize],
+        mut cache: Option<ImageBlocks>,
+    ) -> GeoTiffResult<()> {
+        let GeoTiff {
+            raster_width,
+            raster_height,
+            num_samples,
+            ..
+        } = *self;
+        self.check_window([x, y, width, height])?;
+        if bands.is_empty() {
+            return Err(GeoTiffError::InvalidArgument(
+                "No bands to read".to_string(),
+            ));
+        }
+        if let Some(band) = bands.iter().find(|&&band| band >= num_samples) {
+            return Err(GeoTiffError::OutOfBounds(format!(
+                "Band {} exceeds the {} bands of the raster",
+                band, num_samples
+            )));
+        }
+        let planar = decoder.find_tag_unsigned::<u16>(Tag::PlanarConfiguration)?
+            == Some(PlanarConfiguration::Planar.to_u16());
+        let all_bands = bands.iter().copied().eq(0..num_samples);
+        // Single bands and rasters the decoder does not support, like those of many bands or
+        // compressed with ZSTD, are decoded here, falling back to the decoder for compressions
+        // like JPEG
+        let chunks = RawChunks::new(decoder)?;
+        let fill = chunks
+            .has_sparse()
+            .then(|| chunk::sparse_fill(decoder, self.nodata.as_ref()))
+            .transpose()?;
+        let band_decoder = match BandDecoder::new(decoder)? {
+            Some(band_decoder)
+                if planar
+                    || !all_bands
+                    || band_decoder.is_unsupported_by_decoder()
+                    || decoder.colortype().is_err() =>
+            {
+                Some(band_decoder)
+            }
+            _ => None,
+        };
+
+        let (chunk_width, chunk_height) = decoder.chunk_dimensions();
+        let (chunk_width, chunk_height) = (chunk_width as usize, chunk_height as usize);
+        let chunks_across = raster_width.div_ceil(chunk_width);
+        // The chunks of planar rasters are stored plane by plane
+        let chunks_per_plane = chunks_across * raster_height.div_ceil(chunk_height);
+        let target_samples = bands.len();
+        let len = width * height * target_samples;
+        let mut raster_data: Option<RasterData> = None;
+        for chunk_y in y / chunk_height..(y + height).div_ceil(chunk_height) {
+            for chunk_x in x / chunk_width..(x + width).div_ceil(chunk_width) {
+                let chunk_index = chunk_y * chunks_across + chunk_x;
+                // Taken from the first plane, as the decoder does not pad the tiles of further
+                // planes
+                let (data_width, data_height) = decoder.chunk_data_dimensions(chunk_index as u32);
+                let (data_width, data_height) = (data_width as usize, data_height as usize);
+                // The intersection of the chunk and the window in raster coordinates
+                let (left, top) = (x.max(chunk_x * chunk_width), y.max(chunk_y * chunk_height));
+                let right = (x + width).min(chunk_x * chunk_width + data_width);
+                let bottom = (y + height).min(chunk_y * chunk_height + data_height);
+                let target_layout = RowLayout {
+                    start: ((top - y) * width + left - x) * target_samples,
+                    stride: width * target_samples,
+                };
+                let source_pixel =
+                    (top - chunk_y * chunk_height) * data_width + left - chunk_x * chunk_width;
+
+                let size = [right - left, bottom - top];
+                let target_band_layout = |target_band| RowLayout {
+                    start: target_layout.start + target_band,
+                    stride: target_layout.stride,
+                };
 
+                if let Some(band_decoder) = &band_decoder {
+                    let chunk =
+                        block_cache::get_or_decode(cache.as_mut(), chunk_index, bands, || {
+                            band_decoder.read_bands(
+                                decoder,
+                                chunk_index,
+                                bands,
+                                [data_width, data_height],
+                                fill.as_ref(),
+                            )
+                        })?;
+                    raster_data
+                        .get_or_insert_with(|| chunk.zeros_like(len))
+                        .copy_rows(
+                            target_layout,
+                            &chunk,
+                            RowLayout {
+                                start: source_pixel * target_samples,
+                                stride: data_width * target_samples,
+                            },
+                            size[0] * target_samples,
+                            size[1],
+                        );
+                } else if planar {
+                    for (target_band, &band) in bands.iter().enumerate() {
+                        let plane_chunk_index = band * chunks_per_plane + chunk_index;
+                        let chunk = match &fill {
+                            Some(fill) if chunks.is_sparse(plane_chunk_index) => {
+                                Arc::new(fill.filled(data_width * data_height))
+                            }
+                            _ => block_cache::get_or_decode(
+                                cache.as_mut(),
+                                plane_chunk_index,
+                                &[],
+                                || Ok(decoder.read_chunk(plane_chunk_index as u32)?.into()),
+                            )?,
+                        };
+                        raster_data
+                            .get_or_insert_with(|| chunk.zeros_like(len))
+                            .copy_band(
+                                target_band_layout(target_band),
+                                target_samples,
+                                &chunk,
+                                RowLayout {
+                                    start: source_pixel,
+                                    stride: data_width,
+                                },
+                                1,
+                                size,
+                            );
+                    }
+                } else {
+                    let chunk = match &fill {
+                        Some(fill) if chunks.is_sparse(chunk_index) => {
+                            Arc::new(fill.filled(data_width * data_height * num_samples))
+                        }
+                        _ => block_cache::get_or_decode(cache.as_mut(), chunk_index, &[], || {
+                            Ok(decoder.read_chunk(chunk_index as u32)?.into())
+                        })?,
+                    };
+                    let target = raster_data.get_or_insert_with(|| chunk.zeros_like(len));
+                    if all_bands {
+                        target.copy_rows(
+                            target_layout,
+                            &chunk,
+                            RowLayout {
+                                start: source_pixel * num_samples,
+                                stride: data_width * num_samples,
+                            },
+                            size[0] * num_samples,
+                            size[1],
+                        );
+                    } else {
+                        for (target_band, &band) in bands.iter().enumerate() {
+                            target.copy_band(
+                                target_band_layout(target_band),
+                                target_samples,
+                                &chunk,
+                                RowLayout {
+                                    start: source_pixel * num_samples + band,
+                                    stride: data_width * num_samples,
+                                },
+                                num_samples,
+                                size,
+                            );
+                        }
+                    }
+                }
+            }
+        }
+
+        self.raster_width = width;
+        self.raster_height = height;
+        self.raster_data = raster_data.expect("the window is not empty");
+        self.select_bands(bands);
+        let offset = [x as f64, y as f64];
+        if let Some(coordinate_transform) = &mut self.coordinate_transform {
+            coordinate_transform.offset_raster_space(offset);
+        }
+        if let Some(rpc_model) = &mut self.rpc_model {
+            rpc_model.sample_offset -= offset[0];
+            rpc_model.line_offset -= offset[1];
+        }
+        Ok(())
+    }
+
+    /// Restricts the metadata of the bands to the given bands, in that order.
+    fn select_bands(&mut self, bands: &[usize]) {
+        self.has_alpha = self.has_alpha && bands.last() == Some(&(self.num_samples - 1));
+        self.band_scale_offset = bands
+            .iter()
+            .map(|&band| self.band_scale_offset[band])
+            .collect();
+        self.num_samples = bands.len();
+    }
+
+    fn read_num_samples<R: Read + Seek>(decoder: &mut Decoder<R>) -> GeoTiffResult<usize> {
+        Ok(match decoder.find_tag(Tag::SamplesPerPixel)? {
+            None => 1,
+            Some(value) => value.into_u16()? as usize,
+        })
+    }
+
+    /// Reads the metadata of the current image of the decoder, leaving the raster data empty.
+    fn read_metadata<R: Read + Seek>(
+        decoder: &mut Decoder<R>,
+        compliance: Compliance,
+    ) -> GeoTiffResult<Self> {
+        let mut warnings = Vec::new();
         let (raster_width, raster_height) = decoder
             .dimensions()
             .map(|(width, height)| (width as usize, height as usize))?;
-        let num_samples = match decoder.find_tag(Tag::SamplesPerPixel)? {
-            None => 1,
-            Some(value) => value.into_u16()? as usize,
+        let num_samples = Self::read_num_samples(decoder)?;
+        // The extra samples are the last samples of each pixel
+        let has_alpha = decoder
+            .find_tag_unsigned_vec::<u16>(Tag::ExtraSamples)?
+            .and_then(|extra_samples| extra_samples.last().copied())
+            .is_some_and(|extra_sample| {
+                extra_sample == ASSOCIATED_ALPHA || extra_sample == UNASSOCIATED_ALPHA
+            });
+
+        let sample_format = decoder
+            .find_tag_unsigned_vec::<u16>(Tag::SampleFormat)?
+            .and_then(|formats| formats.first().copied())
+            .map_or(SampleFormat::Uint, SampleFormat::from_u16_exhaustive);
+        let bits_per_sample = decoder
+            .find_tag_unsigned_vec::<u16>(Tag::BitsPerSample)?
+            .and_then(|bits| bits.first().copied())
+            .unwrap_or(1);
+        let nodata = decoder
+            .find_tag(Tag::GdalNodata)?
+            .map(|value| value.into_string())
+            .transpose()?
+            .and_then(|text| {
+                // GDAL terminates the value like any ASCII tag, but some writers add further NULs
+                RasterData::parse_sample(
+                    text.trim_end_matches('\0'),
+                    sample_format,
+                    bits_per_sample,
+                )
+            });
+
+        let gdal_metadata = decoder
+            .find_tag(Tag::Unknown(GDAL_METADATA_TAG))?
+            .map(|value| GdalMetadata::from_xml(&value.into_string()?))
+            .transpose()?;
+        let band_scale_offset = (0..num_samples)
+            .map(|band| {
+                let metadata = gdal_metadata.as_ref()?;
+                match (metadata.band_scale(band), metadata.band_offset(band)) {
+                    (None, None) => None,
+                    (scale, offset) => Some([scale.unwrap_or(1.0), offset.unwrap_or(0.0)]),
+                }
+            })
+            .collect();
+
+        let geo_key_directory = match decoder.find_tag(Tag::GeoKeyDirectoryTag)? {
+            None => GeoKeyDirectory::default(),
+            Some(value) => {
+                let double_params_data = decoder
+                    .find_tag(Tag::GeoDoubleParamsTag)?
+                    .map(|value| value.into_f64_vec())
+                    .transpose()?;
+                let ascii_params_data = decoder
+                    .find_tag(Tag::GeoAsciiParamsTag)?
+                    .map(|value| value.into_string())
+                    .transpose()?;
+                let geo_key_directory = GeoKeyDirectory::from_tags(
+                    value.into_u16_vec()?,
+                    double_params_data,
+                    ascii_params_data,
+                    |location| {
+                        Ok(decoder
+                            .find_tag(Tag::from_u16_exhaustive(location))?
+                            .and_then(TagValue::from_tiff_value))
+                    },
+                );
+                let geo_key_directory = compliance
+                    .recover(geo_key_directory, &mut warnings)?
+                    .unwrap_or_default();
+                compliance.recover(geo_key_directory.check_version(), &mut warnings)?;
+                geo_key_directory
+            }
         };
-        let raster_data = match decoder.read_image()? {
-            DecodingResult::U8(data) => RasterData::U8(data),
-            DecodingResult::U16(data) => RasterData::U16(data),
-            DecodingResult::U32(data) => RasterData::U32(data),
-            DecodingResult::U64(data) => RasterData::U64(data),
-            DecodingResult::F32(data) => RasterData::F32(data),
-            DecodingResult::F64(data) => RasterData::F64(data),
-            DecodingResult::I8(data) => RasterData::I8(data),
-            DecodingResult::I16(data) => RasterData::I16(data),
-            DecodingResult::I32(data) => RasterData::I32(data),
-            DecodingResult::I64(data) => RasterData::I64(data),
+        let raster_type = geo_key_directory.raster_type().unwrap_or_default();
+
+        let pixel_scale_data = decoder
+            .find_tag(Tag::ModelPixelScaleTag)?
+            .map(|value| value.into_f64_vec())
+            .transpose()?;
+        let model_tie_points_data = decoder
+            .find_tag(Tag::ModelTiepointTag)?
+            .map(|value| value.into_f64_vec())
+            .transpose()?;
+        let model_transformation_data = decoder
+            .find_tag(Tag::ModelTransformationTag)?
+            .map(|value| value.into_f64_vec())
+            .transpose()?;
+        let coordinate_transform = if pixel_scale_data.is_some()
+            || model_tie_points_data.is_some()
+            || model_transformation_data.is_some()
+        {
+            let coordinate_transform = Self::read_coordinate_transform(
+                pixel_scale_data,
+                model_tie_points_data,
+                model_transformation_data,
+                compliance,
+                &mut warnings,
+            );
+            compliance
+                .recover(coordinate_transform, &mut warnings)?
+                .map(|mut coordinate_transform| {
+                    coordinate_transform.offset_raster_space(raster_type.raster_space_offset());
+                    coordinate_transform
+                })
+        } else {
+            None
         };
 
+        let rpc_model = match decoder.find_tag(Tag::Unknown(RPC_COEFFICIENT_TAG))? {
+            None => None,
+            Some(value) => compliance.recover(
+                RpcModel::from_tag_data(value.into_f64_vec()?),
+                &mut warnings,
+            )?,
+        };
+        // Fall back to the sensor model if the raster is not georeferenced otherwise
+        let coordinate_transform = coordinate_transform.or_else(|| {
+            rpc_model.as_ref().map(|model| {
+                CoordinateTransform::from_rpc_model(model.clone(), model.height_offset)
+            })
+        });
+
         Ok(Self {
             raster_width,
             raster_height,
             num_samples,
-            raster_data,
+            geo_key_directory,
+            raster_type,
+            model_units: ModelUnits::Native,
+            rpc_model,
+            coordinate_transform,
+            raster_data: RasterData::U8(Vec::new()),
+            mask: None,
+            has_alpha,
+            zero_alpha_as_nodata: false,
+            nodata,
+            gdal_metadata,
+            band_scale_offset,
+            warnings,
         })
     }
 
+    /// Creates the transformation from the values of the georeferencing tags.
+    ///
+    /// In lenient mode, a ModelTransformationTag combined with a ModelTiepointTag or
+    /// ModelPixelScaleTag is ignored as GDAL does, unless the latter don't form a valid
+    /// transformation.
+    fn read_coordinate_transform(
+        pixel_scale_data: Option<Vec<f64>>,
+        model_tie_points_data: Option<Vec<f64>>,
+        model_transformation_data: Option<Vec<f64>>,
+        compliance: Compliance,
+        warnings: &mut Vec<String>,
+    ) -> GeoTiffResult<CoordinateTransform> {
+        let is_conflicting = model_transformation_data.is_some()
+            && (pixel_scale_data.is_some() || model_tie_points_data.is_some());
+        if compliance == Compliance::Lenient && is_conflicting {
+            return match CoordinateTransform::from_tag_data(
+                pixel_scale_data,
+                model_tie_points_data,
+                None,
+            ) {
+                Ok(coordinate_transform) => {
+                    warnings.push(
+                        "Ignoring the ModelTransformationTag combined with ModelTiepointTag or \
+                         ModelPixelScaleTag"
+                            .to_string(),
+                    );
+                    Ok(coordinate_transform)
+                }
+                Err(GeoTiffError::GeoKey(_)) => {
+                    warnings.push(
+                        "Ignoring the invalid ModelTiepointTag or ModelPixelScaleTag combined with \
+                         ModelTransformationTag"
+                            .to_string(),
+                    );
+                    CoordinateTransform::from_tag_data(None, None, model_transformation_data)
+                }
+                Err(err) => Err(err),
+            };
+        }
+        CoordinateTransform::from_tag_data(
+            pixel_scale_data,
+            model_tie_points_data,
+            model_transformation_data,
+        )
+    }
+
+    /// The violations of the GeoTIFF specification recovered from when reading the metadata
+    /// with [`Compliance::Lenient`], e.g. conflicting georeferencing tags. Empty if the metadata
+    /// complies with the specification or was read strictly.
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// The GeoKeys describing the model coordinate system.
+    pub fn geo_key_directory(&self) -> &GeoKeyDirectory {
+        &self.geo_key_directory
+    }
+
+    /// The revision of the GeoTIFF specification the GeoKeys follow, or `None` if there are no
+    /// GeoKeys or their revision is unknown.
+    pub fn geotiff_version(&self) -> Option<GeoTiffVersion> {
+        if self.geo_key_directory.is_empty() {
+            return None;
+        }
+        self.geo_key_directory.version()
+    }
+
+    /// The EPSG code of the model CRS, see [`GeoKeyDirectory::epsg_code`].
+    ///
+    /// Returns `None` if the CRS is undefined or user-defined.
+    pub fn epsg_code(&self) -> Option<u16> {
+        self.geo_key_directory.epsg_code()
+    }
+
+    /// Whether the model CRS is user-defined rather than given by an EPSG code.
+    pub fn is_user_defined_crs(&self) -> bool {
+        self.geo_key_directory.is_user_defined_crs()
+    }
+
+    /// A human-readable description of the model CRS (GTCitationGeoKey).
+    pub fn citation(&self) -> Option<&str> {
+        self.geo_key_directory.citation()
+    }
+
+    /// A human-readable description of the geodetic CRS (GeogCitationGeoKey).
+    pub fn geog_citation(&self) -> Option<&str> {
+        self.geo_key_directory.geog_citation()
+    }
+
+    /// A human-readable description of the projected CRS (PCSCitationGeoKey).
+    pub fn proj_citation(&self) -> Option<&str> {
+        self.geo_key_directory.proj_citation()
+    }
+
+    /// The most specific human-readable description of the model CRS, i.e. the citation of the
+    /// projected or geodetic CRS depending on the model type, falling back to the GTCitationGeoKey.
+    pub fn crs_description(&self) -> Option<&str> {
+        match self.geo_key_directory.model_type() {
+            Some(ModelType::Projected) => self.proj_citation(),
+            Some(ModelType::Geographic | ModelType::Geocentric) => self.geog_citation(),
+            _ => None,
+        }
+        .or_else(|| self.citation())
+    }
+
+    /// The projection of a user-defined projected CRS, see [`ProjectionDefinition`].
+    pub fn projection_definition(&self) -> Option<ProjectionDefinition> {
+        self.geo_key_directory.projection_definition()
+    }
+
+    /// The model CRS as WKT2 string, reconstructed from the GeoKeys. A CRS with a transformation
+    /// of its datum to WGS 84 (GeogTOWGS84GeoKey) is given as bound CRS.
+    ///
+    /// Returns `None` if the CRS is undefined or not supported, i.e. if it is neither among the
+    /// built-in EPSG definitions nor fully described by the GeoKeys.
+    pub fn crs_wkt(&self) -> Option<String> {
+        Crs::from_geo_keys(&self.geo_key_directory).map(|crs| crs.to_wkt())
+    }
+
+    /// The model CRS as PROJJSON string, see [`GeoTiff::crs_wkt`].
+    pub fn crs_projjson(&self) -> Option<String> {
+        Crs::from_geo_keys(&self.geo_key_directory).map(|crs| crs.to_projjson())
+    }
+
+    /// The model CRS as PROJ string, see [`GeoTiff::crs_wkt`].
+    ///
+    /// PROJ strings cannot express all details of a CRS, so prefer WKT or PROJJSON where possible.
+    pub fn crs_proj4(&self) -> Option<String> {
+        Crs::from_geo_keys(&self.geo_key_directory).map(|crs| crs.to_proj4())
+    }
+
+    /// Creates a transformation from raster space to the CRS with the given EPSG code, e.g. to
+    /// convert raster coordinates of a UTM raster to WGS 84 longitude/latitude. The transformation
+    /// of the datum to WGS 84 given by the GeogTOWGS84GeoKey is used if present.
+    ///
+    /// Fails if the GeoTIFF is not georeferenced or PROJ does not support its CRS.
+    #[cfg(feature = "proj")]
+    pub fn transform_to_crs(&self, target_epsg: u16) -> GeoTiffResult<CrsTransform> {
+        let mut coordinate_transform = self
+            .coordinate_transform
+            .clone()
+            .ok_or_else(|| GeoTiffError::GeoKey("The GeoTIFF is not georeferenced".to_string()))?;
+        let crs = Crs::from_geo_keys(&self.geo_key_directory);
+        // A datum transformation given by the GeoTIFF takes precedence over those PROJ knows for
+        // the EPSG code, so the CRS is passed as bound CRS instead
+        let to_wgs84 = crs.as_ref().and_then(|crs| crs.to_wgs84());
+        let model_crs = match self.epsg_code().filter(|_| to_wgs84.is_none()) {
+            Some(code) => format!("EPSG:{}", code),
+            None => crs.map(|crs| crs.to_wkt()).ok_or_else(|| {
+                GeoTiffError::Unsupported("The CRS of the GeoTIFF is not supported".to_string())
+            })?,
+        };
+        // PROJ expects coordinates in the units of the model CRS
+        if self.model_units == ModelUnits::Standard {
+            coordinate_transform.scale_model_space(1.0 / self.standard_unit_factor()?);
+        }
+        CrsTransform::new(
+            coordinate_transform,
+            &model_crs,
+            &format!("EPSG:{}", target_epsg),
+        )
+    }
+
+    /// Transforms a fractional raster coordinate (column, row) to WGS 84 longitude/latitude, e.g.
+    /// `(column + 0.5, row + 0.5)` for the center of a pixel, see [`GeoTiff::transform_to_crs`].
+    ///
+    /// The PROJ transformation is created on each call, so prefer a [`CrsTransform`] to transform
+    /// many coordinates.
+    #[cfg(feature = "proj")]
+    pub fn pixel_to_lonlat(&self, column: f64, row: f64) -> GeoTiffResult<[f64; 2]> {
+        self.transform_to_crs(WGS_84)?
+            .transform_to_target_f64([column, row])
+    }
+
+    /// Transforms WGS 84 longitude/latitude to a fractional raster coordinate (column, row), the
+    /// inverse of [`GeoTiff::pixel_to_lonlat`].
+    #[cfg(feature = "proj")]
+    pub fn lonlat_to_pixel(&self, lon: f64, lat: f64) -> GeoTiffResult<[f64; 2]> {
+        self.transform_to_crs(WGS_84)?
+            .transform_to_raster_f64([lon, lat])
+    }
+
+    /// The transformation between raster space and model space, if the GeoTIFF is georeferenced.
+    pub fn coordinate_transform(&self) -> Option<&CoordinateTransform> {
+        self.coordinate_transform.as_ref()
+    }
+
+    /// Replaces the transformation between raster space and model space, e.g. to georeference a
+    /// raster lacking georeferencing tags.
+    pub fn set_coordinate_transform(&mut self, coordinate_transform: Option<CoordinateTransform>) {
+        self.coordinate_transform = coordinate_transform;
+    }
+
+    /// The pixel convention of the raster coordinates, as given by the GTRasterTypeGeoKey.
+    ///
+    /// Regardless of this convention, the raster coordinates accepted and returned by the
+    /// transformations always refer to the upper left corner of a pixel.
+    pub fn raster_type(&self) -> RasterType {
+        self.raster_type
+    }
+
+    /// Overrides the pixel convention read from the file, e.g. for files that declare
+    /// PixelIsPoint without having their tie points adjusted accordingly.
+    pub fn set_raster_type(&mut self, raster_type: RasterType) {
+        if let Some(coordinate_transform) = &mut self.coordinate_transform {
+            let [old_x, old_y] = self.raster_type.raster_space_offset();
+            let [new_x, new_y] = raster_type.raster_space_offset();
+            coordinate_transform.offset_raster_space([new_x - old_x, new_y - old_y]);
+        }
+        self.raster_type = raster_type;
+    }
+
+    /// The units of the model coordinates accepted and returned by the transformations.
+    pub fn model_units(&self) -> ModelUnits {
+        self.model_units
+    }
+
+    /// Converts the model coordinates accepted and returned by the transformations, e.g. to get
+    /// metric extents of a raster in US survey feet.
+    ///
+    /// Fails if the model CRS refers to an unknown unit. Transformations by an RPC sensor model
+    /// always use degrees and are not affected.
+    pub fn set_model_units(&mut self, model_units: ModelUnits) -> GeoTiffResult<()> {
+        if model_units != self.model_units {
+            let factor = self.standard_unit_factor()?;
+            if let Some(coordinate_transform) = &mut self.coordinate_transform {
+                coordinate_transform.scale_model_space(match model_units {
+                    ModelUnits::Native => 1.0 / factor,
+                    ModelUnits::Standard => factor,
+                });
+            }
+            self.model_units = model_units;
+        }
+        Ok(())
+    }
+
+    /// The factor converting native to standard model units. Missing units keys default to meters
+    /// and degrees.
+    fn standard_unit_factor(&self) -> GeoTiffResult<f64> {
+        let geo_keys = &self.geo_key_directory;
+        let (size, standard_size) = match geo_keys.model_type() {
+            Some(ModelType::Geographic) => (
+                geo_keys
+                    .geog_angular_units()
+                    .map(|_| geo_keys.angular_unit_size()),
+                1f64.to_radians(),
+            ),
+            _ => (
+                geo_keys
+                    .proj_linear_units()
+                    .map(|_| geo_keys.linear_unit_size()),
+                1.0,
+            ),
+        };
+        match size {
+            None => Ok(1.0),
+            Some(Some(size)) => Ok(size / standard_size),
+            Some(None) => Err(GeoTiffError::Unsupported(
+                "The model CRS refers to an unknown unit".to_string(),
+            )),
+        }
+    }
+
+    /// Replaces the transformation derived from multiple tie points (ground control points) by
+    /// polynomials of the given order fitted to them, and reports how well they fit.
+    ///
+    /// Fails if the GeoTIFF is not georeferenced by multiple tie points, or if there are not
+    /// enough tie points for the requested order.
+    pub fn set_polynomial_transform(
+        &mut self,
+        order: PolynomialOrder,
+    ) -> GeoTiffResult<TransformResiduals> {
+        let tie_points = self.tie_points()?;
+        let (coordinate_transform, residuals) =
+            CoordinateTransform::from_tie_points_polynomial(tie_points, order)?;
+        self.coordinate_transform = Some(coordinate_transform);
+        Ok(residuals)
+    }
+
+    /// Replaces the transformation derived from multiple tie points by thin plate splines, which
+    /// map smoothly between dense tie points while still passing exactly through them.
+    ///
+    /// Fails if the GeoTIFF is not georeferenced by multiple tie points.
+    pub fn set_thin_plate_spline_transform(&mut self) -> GeoTiffResult<()> {
+        let tie_points = self.tie_points()?;
+        self.coordinate_transform = Some(CoordinateTransform::from_tie_points_thin_plate_spline(
+            tie_points,
+        )?);
+        Ok(())
+    }
+
+    /// The RPC sensor model given by the RPCCoefficientTag, if any.
+    pub fn rpc_model(&self) -> Option<&RpcModel> {
+        self.rpc_model.as_ref()
+    }
+
+    /// Uses the RPC sensor model to transform between raster space and longitude/latitude at the
+    /// given height above the ellipsoid.
+    ///
+    /// Files without other georeferencing use the RPC sensor model at its height offset by default.
+    pub fn set_rpc_transform(&mut self, height: f64) -> GeoTiffResult<()> {
+        let model = self.rpc_model.clone().ok_or_else(|| {
+            GeoTiffError::GeoKey("The GeoTIFF does not contain an RPC sensor model".to_string())
+        })?;
+        self.coordinate_transform = Some(CoordinateTransform::from_rpc_model(model, height));
+        Ok(())
+    }
+
+    fn tie_points(&self) -> GeoTiffResult<Vec<TiePoint>> {
+        self.coordinate_transform
+            .as_ref()
+            .and_then(|transform| transform.tie_points())
+            .map(|tie_points| tie_points.to_vec())
+            .ok_or_else(|| {
+                GeoTiffError::GeoKey(
+                    "The GeoTIFF is not georeferenced by multiple tie points".to_string(),
+                )
+            })
+    }
+
+    /// The bounding box of the raster in model space.
+    ///
+    /// Returns `None` if the GeoTIFF is not georeferenced.
+    pub fn model_extent(&self) -> Option<Extent> {
+        self.coordinate_transform
+            .as_ref()
+            .map(|transform| transform.model_extent(self.raster_width, self.raster_height))
+    }
+
+    /// The size of a pixel in model units in X and Y direction, see
+    /// [`CoordinateTransform::resolution`].
+    ///
+    /// Returns `None` if the GeoTIFF is not georeferenced by an affine transformation.
+    pub fn resolution(&self) -> Option<[f64; 2]> {
+        self.coordinate_transform
+            .as_ref()
+            .and_then(|transform| transform.resolution())
+    }
+
+    /// The coordinate transformation as GDAL geotransform, see
+    /// [`CoordinateTransform::geotransform`].
+    ///
+    /// Returns `None` if the GeoTIFF is not georeferenced by an affine transformation.
+    pub fn geotransform(&self) -> Option<[f64; 6]> {
+        self.coordinate_transform
+            .as_ref()
+            .and_then(|transform| transform.geotransform())
+    }
+
+    /// Whether the raster is rotated or sheared with respect to model space, see
+    /// [`CoordinateTransform::has_rotation`].
+    ///
+    /// Returns `false` if the GeoTIFF is not georeferenced.
+    pub fn has_rotation(&self) -> bool {
+        self.coordinate_transform
+            .as_ref()
+            .is_some_and(CoordinateTransform::has_rotation)
+    }
+
+    /// Whether the raster is north-up, see [`CoordinateTransform::is_north_up`].
+    ///
+    /// Returns `false` if the GeoTIFF is not georeferenced.
+    pub fn is_north_up(&self) -> bool {
+        self.coordinate_transform
+            .as_ref()
+            .is_some_and(CoordinateTransform::is_north_up)
+    }
+
+    /// Transforms a raster coordinate (column, row) to model space.
+    ///
+    /// Returns `None` if the GeoTIFF is not georeferenced.
+    pub fn transform_to_model(&self, coord: [usize; 2]) -> Option<[f64; 2]> {
+        self.coordinate_transform
+            .as_ref()
+            .map(|transform| transform.transform_to_model(coord))
+    }
+
+    /// Transforms a model coordinate to the raster coordinate (column, row) of the containing pixel.
+    ///
+    /// Returns `None` if the GeoTIFF is not georeferenced.
+    pub fn transform_to_raster(&self, coord: [f64; 2]) -> Option<[usize; 2]> {
+        self.coordinate_transform
+            .as_ref()
+            .map(|transform| transform.transform_to_raster(coord))
+    }
+
+    /// Transforms a model coordinate to a fractional raster coordinate (column, row), e.g. for
+    /// sub-pixel sampling or interpolation.
+    ///
+    /// Returns `None` if the GeoTIFF is not georeferenced.
+    pub fn transform_to_raster_f64(&self, coord: [f64; 2]) -> Option<[f64; 2]> {
+        self.coordinate_transform
+            .as_ref()
+            .map(|transform| transform.transform_to_raster_f64(coord))
+    }
+
+    /// Interpolates the value of the band at a model coordinate, e.g. to extract the elevation
+    /// of a point from a DEM.
+    ///
+    /// Nodata neighbours, see [`GeoTiff::get_value_at_checked`], are left out of the
+    /// interpolation. Returns `None` if the GeoTIFF is not georeferenced, the coordinate lies
+    /// outside the raster or no neighbour is valid.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the band does not exist.
+    pub fn sample(
+        &self,
+        coord: [f64; 2],
+        band: usize,
+        interpolation: Interpolation,
+    ) -> Option<f64> {
+        if band >= self.num_samples {
+            panic!(
+                "sample out of bounds: the number of samples is {} but the sample is {}",
+                self.num_samples, band
+            )
+        }
+        let raster_coord = self.transform_to_raster_f64(coord)?;
+        interpolation::interpolate(self, raster_coord, band, interpolation)
+    }
+
+    /// Like [`GeoTiff::get_value_at`], but converts a height given in the unit of the
+    /// VerticalUnitsGeoKey to meters. If the vertical unit is missing or unknown, the value is
+    /// returned unchanged.
+    pub fn get_value_in_meters_at(&self, x: usize, y: usize, sample: usize) -> f64 {
+        let value = self.get_value_at::<f64>(x, y, sample);
+        match self.geo_key_directory.vertical_unit_size() {
+            Some(unit_size) => value * unit_size,
+            None => value,
+        }
+    }
+
+    /// The transparency mask of the raster, telling whether each pixel is valid, row by row, or
+    /// `None` if the file has no mask for the raster.
+    ///
+    /// Masks are stored in separate IFDs (NewSubfileType 4), like GDAL writes internal masks to
+    /// mark the pixels actually covered by data, e.g. after reprojection or compression with loss.
+    pub fn mask(&self) -> Option<&[bool]> {
+        self.mask.as_deref()
+    }
+
+    /// Whether the last band is an alpha band, i.e. the last sample of each pixel is its opacity
+    /// according to the ExtraSamples tag, as in RGBA images.
+    pub fn has_alpha(&self) -> bool {
+        self.has_alpha
+    }
+
+    /// Whether pixels with an alpha value of zero are invalid, see
+    /// [`GeoTiff::set_zero_alpha_as_nodata`].
+    pub fn zero_alpha_as_nodata(&self) -> bool {
+        self.zero_alpha_as_nodata
+    }
+
+    /// Sets whether fully transparent pixels, i.e. pixels with an alpha value of zero, are
+    /// treated as nodata by [`GeoTiff::is_valid_at`], like GDAL treats the alpha band as a mask.
+    ///
+    /// Has no effect if the GeoTIFF has no alpha band, see [`GeoTiff::has_alpha`].
+    pub fn set_zero_alpha_as_nodata(&mut self, zero_alpha_as_nodata: bool) {
+        self.zero_alpha_as_nodata = zero_alpha_as_nodata;
+    }
+
+    /// Whether the pixel is valid according to the transparency mask, see [`GeoTiff::mask`], and
+    /// the alpha band, if zero alpha is treated as nodata, see
+    /// [`GeoTiff::set_zero_alpha_as_nodata`].
+    ///
+    /// All pixels are valid if there is neither a mask nor an alpha band treated as nodata.
+    pub fn is_valid_at(&self, x: usize, y: usize) -> bool {
+        if let Some(mask) = &self.mask {
+            if !mask[y * self.raster_width + x] {
+                return false;
+            }
+        }
+        !(self.has_alpha
+            && self.zero_alpha_as_nodata
+            && self.get_value_at::<f64>(x, y, self.num_samples - 1) == 0.0)
+    }
+
+    /// The metadata written by GDAL in the GDAL_METADATA tag, e.g. band descriptions, units,
+    /// scale and offset, or `None` if the tag is missing.
+    pub fn gdal_metadata(&self) -> Option<&GdalMetadata> {
+        self.gdal_metadata.as_ref()
+    }
+
+    /// The scale and offset converting the stored values of the band to physical values, i.e.
+    /// `value * scale + offset`, as given by the GDAL_METADATA tag or set by
+    /// [`GeoTiff::set_band_scale_offset`]. Returns `None` if the values are not scaled.
+    pub fn band_scale_offset(&self, band: usize) -> Option<[f64; 2]> {
+        self.band_scale_offset.get(band).copied().flatten()
+    }
+
+    /// Sets the scale and offset of the band, e.g. as given by the documentation of a product,
+    /// replacing those of the GDAL_METADATA tag, or removes them for `None`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the band does not exist.
+    pub fn set_band_scale_offset(&mut self, band: usize, scale_offset: Option<[f64; 2]>) {
+        self.band_scale_offset[band] = scale_offset;
+    }
+
+    /// Converts the raster data to physical `f64` values given by the scale and offset of each
+    /// band, see [`GeoTiff::band_scale_offset`], e.g. to read temperatures stored as scaled
+    /// integers. Bands without scale and offset keep their values.
+    ///
+    /// Nodata samples become NaN, which is the nodata value afterwards. The scale and offset of
+    /// all bands are removed, as the values are physical already. Does nothing if no band is
+    /// scaled.
+    pub fn apply_scale_offset(&mut self) {
+        if self.band_scale_offset.iter().all(Option::is_none) {
+            return;
+        }
+        let values = (0..self.raster_data.len())
+            .map(|index| {
+                let is_nodata = self
+                    .nodata
+                    .as_ref()
+                    .is_some_and(|nodata| self.raster_data.sample_equals(index, nodata));
+                let value = sample_value::<f64>(&self.raster_data, index);
+                match self.band_scale_offset[index % self.num_samples] {
+                    _ if is_nodata => f64::NAN,
+                    Some([scale, offset]) => value * scale + offset,
+                    None => value,
+                }
+            })
+            .collect();
+        self.raster_data = RasterData::F64(values);
+        if self.nodata.is_some() {
+            self.nodata = Some(RasterData::F64(vec![f64::NAN]));
+        }
+        self.band_scale_offset.fill(None);
+    }
+
+    /// The nodata value of the GDAL_NODATA tag, i.e. the value of pixels not covered by data,
+    /// converted to `T`, or `None` if the tag is missing or not a number of the sample type.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the nodata value cannot be represented as `T`, like [`GeoTiff::get_value_at`].
+    pub fn nodata<T: FromPrimitive + 'static>(&self) -> Option<T> {
+        self.nodata.as_ref().map(|nodata| sample_value(nodata, 0))
+    }
+
+    /// Like [`GeoTiff::get_value_at`], but returns `None` for nodata, i.e. if the sample equals
+    /// the nodata value, see [`GeoTiff::nodata`], or the pixel is invalid according to the mask
+    /// or the alpha band, see [`GeoTiff::is_valid_at`].
+    ///
+    /// This keeps fill values like -9999 from being mistaken for data, e.g. in statistics.
+    /// Unlike [`GeoTiff::get_value_at`], it returns `None` rather than panicking if the sample
+    /// cannot be represented as `T`, e.g. a nodata value of -9999 read as `u8`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the sample does not exist.
+    pub fn get_value_at_checked<T: FromPrimitive + 'static>(
+        &self,
+        x: usize,
+        y: usize,
+        sample: usize,
+    ) -> Option<T> {
+        if sample >= self.num_samples {
+            panic!(
+                "sample out of bounds: the number of samples is {} but the sample is {}",
+                self.num_samples, sample
+            )
+        }
+        let index = (y * self.raster_width + x) * self.num_samples + sample;
+        let is_nodata = self
+            .nodata
+            .as_ref()
+            .is_some_and(|nodata| self.raster_data.sample_equals(index, nodata));
+        if is_nodata || !self.is_valid_at(x, y) {
+            return None;
+        }
+        try_sample_value(&self.raster_data, index)
+    }
+
+    /// The samples of a window of `width * height` pixels with its upper left corner at pixel
+    /// `(x, y)`, row by row, with `None` for nodata and samples not representable as `T`, see
+    /// [`GeoTiff::get_value_at_checked`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the window exceeds the raster or the sample does not exist.
+    pub fn get_window_values_checked<T: FromPrimitive + 'static>(
+        &self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+        sample: usize,
+    ) -> Vec<Option<T>> {
+        if x + width > self.raster_width || y + height > self.raster_height {
+            panic!(
+                "window out of bounds: the raster has {}x{} pixels but the window is {}x{} pixels at ({}, {})",
+                self.raster_width, self.raster_height, width, height, x, y
+            )
+        }
+        (y..y + height)
+            .flat_map(|row| (x..x + width).map(move |column| (column, row)))
+            .map(|(column, row)| self.get_value_at_checked(column, row, sample))
+            .collect()
+    }
+
+    /// Iterates over the pixels of the band along with the model coordinates of their centers,
+    /// row by row, e.g. to export the raster as points.
+    ///
+    /// Returns `None` if the GeoTIFF is not georeferenced.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the band does not exist.
+    pub fn pixels<T: FromPrimitive + 'static>(&self, band: usize) -> Option<Pixels<'_, T>> {
+        self.pixels_in_window(0, 0, self.raster_width, self.raster_height, band)
+    }
+
+    /// Like [`GeoTiff::pixels`], but only iterates over the pixels of a window of
+    /// `width * height` pixels with its upper left corner at pixel `(x, y)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the window exceeds the raster or the band does not exist.
+    pub fn pixels_in_window<T: FromPrimitive + 'static>(
+        &self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+        band: usize,
+    ) -> Option<Pixels<'_, T>> {
+        if x + width > self.raster_width || y + height > self.raster_height {
+            panic!(
+                "window out of bounds: the raster has {}x{} pixels but the window is {}x{} pixels at ({}, {})",
+                self.raster_width, self.raster_height, width, height, x, y
+            )
+        }
+        if band >= self.num_samples {
+            panic!(
+                "sample out of bounds: the number of samples is {} but the sample is {}",
+                self.num_samples, band
+            )
+        }
+        let coordinate_transform = self.coordinate_transform.as_ref()?;
+        Some(Pixels::new(
+            self,
+            coordinate_transform,
+            band,
+            [x, y, width, height],
+        ))
+    }
+
+    /// Computes the minimum, maximum, mean and standard deviation of the valid samples of the
+    /// band, excluding nodata, see [`GeoTiff::get_value_at_checked`], and NaN. Returns `None` if
+    /// there are no valid samples.
+    ///
+    /// See [`GeoTiffReader::statistics`] to compute the statistics of a raster too large to be
+    /// read at once.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the band does not exist.
+    pub fn statistics(&self, band: usize) -> Option<Statistics> {
+        let mut statistics = StatisticsAccumulator::default();
+        statistics.add_band(self, band);
+        statistics.finish()
+    }
+
+    /// Counts the valid samples of the band, excluding nodata, in `bins` bins of equal width
+    /// dividing the range `[min, max]`, e.g. to derive a contrast stretch. Values outside the
+    /// range are not counted, the maximum itself falls into the last bin.
+    ///
+    /// See [`GeoTiffReader::histogram`] to compute the histogram of a raster too large to be read
+    /// at once. Fails if there are no bins or the range is empty.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the band does not exist.
+    pub fn histogram(&self, band: usize, bins: usize, range: [f64; 2]) -> GeoTiffResult<Vec<u64>> {
+        let mut histogram = HistogramAccumulator::new(bins, range)?;
+        histogram.add_band(self, band);
+        Ok(histogram.finish())
+    }
+
     pub fn get_value_at<T: FromPrimitive + 'static>(&self, x: usize, y: usize, sample: usize) -> T {
         let GeoTiff {
             raster_width,
@@ -83,18 +1407,96 @@ impl GeoTiff {
             )
         }
 
-        let index = (y * raster_width + x) * num_samples + sample;
-        match raster_data {
-            RasterData::U8(data) => unwrap_primitive_type!(T::from_u8(data[index]), u8, T),
-            RasterData::U16(data) => unwrap_primitive_type!(T::from_u16(data[index]), u16, T),
-            RasterData::U32(data) => unwrap_primitive_type!(T::from_u32(data[index]), u32, T),
-            RasterData::U64(data) => unwrap_primitive_type!(T::from_u64(data[index]), u64, T),
-            RasterData::F32(data) => unwrap_primitive_type!(T::from_f32(data[index]), f32, T),
-            RasterData::F64(data) => unwrap_primitive_type!(T::from_f64(data[index]), f64, T),
-            RasterData::I8(data) => unwrap_primitive_type!(T::from_i8(data[index]), i8, T),
-            RasterData::I16(data) => unwrap_primitive_type!(T::from_i16(data[index]), i16, T),
-            RasterData::I32(data) => unwrap_primitive_type!(T::from_i32(data[index]), i32, T),
-            RasterData::I64(data) => unwrap_primitive_type!(T::from_i64(data[index]), i64, T),
+        sample_value(raster_data, (y * raster_width + x) * num_samples + sample)
+    }
+
+    /// All samples of the raster converted to `T`, in the given layout.
+    pub fn get_values<T: FromPrimitive + 'static>(&self, layout: Layout) -> Vec<T> {
+        let num_pixels = self.raster_width * self.raster_height;
+        match layout {
+            Layout::PixelInterleaved => (0..num_pixels * self.num_samples)
+                .map(|index| sample_value(&self.raster_data, index))
+                .collect(),
+            Layout::BandSequential => (0..self.num_samples)
+                .flat_map(|band| {
+                    (0..num_pixels).map(move |pixel| {
+                        sample_value(&self.raster_data, pixel * self.num_samples + band)
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    /// Like [`GeoTiff::get_value_at`], but fails instead of converting the sample if `T` is not
+    /// the sample type of the raster, e.g. `f32` for `u16` samples, and instead of panicking if
+    /// the pixel or sample does not exist.
+    pub fn try_get_value_at<T: Sample + 'static>(
+        &self,
+        x: usize,
+        y: usize,
+        sample: usize,
+    ) -> GeoTiffResult<T> {
+        self.check_sample_type::<T>()?;
+        if x >= self.raster_width || y >= self.raster_height || sample >= self.num_samples {
+            return Err(GeoTiffError::OutOfBounds(format!(
+                "Sample {} of pixel ({}, {}) exceeds the raster of {}x{} pixels of {} samples",
+                sample, x, y, self.raster_width, self.raster_height, self.num_samples
+            )));
+        }
+        Ok(self.get_value_at(x, y, sample))
+    }
+
+    /// Like [`GeoTiff::get_values`], but fails instead of converting the samples if `T` is not
+    /// the sample type of the raster.
+    pub fn try_get_values<T: Sample + 'static>(&self, layout: Layout) -> GeoTiffResult<Vec<T>> {
+        self.check_sample_type::<T>()?;
+        Ok(self.get_values(layout))
+    }
+
+    /// Fails if `T` is not the sample type of the raster, i.e. not of its SampleFormat and
+    /// BitsPerSample, samples of less than 8 bits being unpacked into `u8`, or `f64` after applying
+    /// scale and offset.
+    fn check_sample_type<T: Sample + 'static>(&self) -> GeoTiffResult<()> {
+        if self.raster_data.is_of_type::<T>() {
+            Ok(())
+        } else {
+            Err(GeoTiffError::InvalidArgument(format!(
+                "The raster has samples of type {}, not {}",
+                self.raster_data.type_name(),
+                type_name::<T>()
+            )))
         }
     }
 }
+
+/// The sample at the index converted to `T`, or `None` if it cannot be represented as `T`.
+fn try_sample_value<T: FromPrimitive>(raster_data: &RasterData, index: usize) -> Option<T> {
+    match raster_data {
+        RasterData::U8(data) => T::from_u8(data[index]),
+        RasterData::U16(data) => T::from_u16(data[index]),
+        RasterData::U32(data) => T::from_u32(data[index]),
+        RasterData::U64(data) => T::from_u64(data[index]),
+        RasterData::F32(data) => T::from_f32(data[index]),
+        RasterData::F64(data) => T::from_f64(data[index]),
+        RasterData::I8(data) => T::from_i8(data[index]),
+        RasterData::I16(data) => T::from_i16(data[index]),
+        RasterData::I32(data) => T::from_i32(data[index]),
+        RasterData::I64(data) => T::from_i64(data[index]),
+    }
+}
+
+/// The sample with the given index, converted to `T`.
+fn sample_value<T: FromPrimitive + 'static>(raster_data: &RasterData, index: usize) -> T {
+    match raster_data {
+        RasterData::U8(data) => unwrap_primitive_type!(T::from_u8(data[index]), u8, T),
+        RasterData::U16(data) => unwrap_primitive_type!(T::from_u16(data[index]), u16, T),
+        RasterData::U32(data) => unwrap_primitive_type!(T::from_u32(data[index]), u32, T),
+        RasterData::U64(data) => unwrap_primitive_type!(T::from_u64(data[index]), u64, T),
+        RasterData::F32(data) => unwrap_primitive_type!(T::from_f32(data[index]), f32, T),
+        RasterData::F64(data) => unwrap_primitive_type!(T::from_f64(data[index]), f64, T),
+        RasterData::I8(data) => unwrap_primitive_type!(T::from_i8(data[index]), i8, T),
+        RasterData::I16(data) => unwrap_primitive_type!(T::from_i16(data[index]), i16, T),
+        RasterData::I32(data) => unwrap_primitive_type!(T::from_i32(data[index]), i32, T),
+        RasterData::I64(data) => unwrap_primitive_type!(T::from_i64(data[index]), i64, T),
+    }
+}
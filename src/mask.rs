@@ -0,0 +1,77 @@
+use std::io::{Read, Seek};
+
+use tiff::decoder::Decoder;
+use tiff::tags::Tag;
+use tiff::{TiffError, TiffFormatError};
+
+use crate::chunk::RawChunks;
+use crate::{GeoTiffError, GeoTiffResult};
+
+/// Decodes the window `[x, y, width, height]` of the transparency mask in the current image of
+/// the decoder, returning whether each pixel is valid, row by row.
+///
+/// Masks are decoded here as the decoder does not support the TransparencyMask photometric
+/// interpretation. Masks of 1 bit per pixel, as written by GDAL, and of 8 bits per pixel are
+/// supported, nonzero values marking valid pixels.
+pub(crate) fn read_mask_window<R: Read + Seek>(
+    decoder: &mut Decoder<R>,
+    [x, y, width, height]: [usize; 4],
+) -> GeoTiffResult<Vec<bool>> {
+    let bits_per_sample = decoder
+        .find_tag_unsigned::<u16>(Tag::BitsPerSample)?
+        .unwrap_or(1);
+    if bits_per_sample != 1 && bits_per_sample != 8 {
+        return Err(GeoTiffError::Unsupported(format!(
+            "Masks with {} bits per pixel are not supported",
+            bits_per_sample
+        )));
+    }
+    let chunks = RawChunks::new(decoder)?;
+    let (mask_width, _) = decoder.dimensions()?;
+
+    // Tiles are padded to their full width, whereas strips span the width of the mask
+    let (chunk_width, chunk_height) = decoder.chunk_dimensions();
+    let (chunk_width, chunk_height) = (chunk_width as usize, chunk_height as usize);
+    let chunks_across = (mask_width as usize).div_ceil(chunk_width);
+    let row_size = (chunk_width * bits_per_sample as usize).div_ceil(8);
+
+    let mut mask = vec![false; width * height];
+    for chunk_y in y / chunk_height..(y + height).div_ceil(chunk_height) {
+        for chunk_x in x / chunk_width..(x + width).div_ceil(chunk_width) {
+            let chunk_index = chunk_y * chunks_across + chunk_x;
+            // The pixels of sparse chunks are invalid like those of chunks stored as 0
+            if chunks.is_sparse(chunk_index) {
+                continue;
+            }
+            let chunk = chunks.read(decoder, chunk_index)?;
+            let (data_width, data_height) = decoder.chunk_data_dimensions(chunk_index as u32);
+            let (left, top) = (x.max(chunk_x * chunk_width), y.max(chunk_y * chunk_height));
+            let right = (x + width).min(chunk_x * chunk_width + data_width as usize);
+            let bottom = (y + height).min(chunk_y * chunk_height + data_height as usize);
+            for row in top..bottom {
+                let row_start = (row - chunk_y * chunk_height) * row_size;
+                for column in left..right {
+                    let chunk_column = column - chunk_x * chunk_width;
+                    let valid = if bits_per_sample == 1 {
+                        chunk.get(row_start + chunk_column / 8).map(|byte| {
+                            // The most significant bit comes first
+                            byte & (0x80 >> (chunk_column % 8)) != 0
+                        })
+                    } else {
+                        chunk.get(row_start + chunk_column).map(|&byte| byte != 0)
+                    };
+                    mask[(row - y) * width + column - x] = valid.ok_or_else(|| {
+                        format_error(format!("Mask chunk {} is truncated", chunk_index))
+                    })?;
+                }
+            }
+        }
+    }
+    Ok(mask)
+}
+
+fn format_error(msg: impl ToString) -> GeoTiffError {
+    GeoTiffError::Tiff(TiffError::FormatError(TiffFormatError::Format(
+        msg.to_string(),
+    )))
+}
@@ -0,0 +1,487 @@
+use std::fmt::{self, Display, Formatter};
+use std::io::{Read, Seek};
+use std::slice;
+
+use tiff::decoder::ifd::Value;
+use tiff::tags::Tag;
+
+use crate::header::{Header, IfdEntry};
+use crate::{
+    GeoKey, GeoKeyDirectory, GeoKeyValue, GeoTiffError, GeoTiffResult, GDAL_METADATA_TAG,
+    RPC_COEFFICIENT_TAG,
+};
+
+/// All TIFF tags and GeoKeys of the images of a file, known and unknown ones, as printed by
+/// `tiffdump` and `listgeo`, see [`GeoTiff::metadata`](crate::GeoTiff::metadata).
+///
+/// The IFDs are parsed without interpreting the tags, such that malformed files can be
+/// inspected: tags whose values cannot be read are listed without value, with their problems
+/// reported in [`ImageMetadata::errors`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Metadata {
+    /// The images in the order of the IFD chain, including overviews and masks
+    pub images: Vec<ImageMetadata>,
+}
+
+/// The tags and GeoKeys of an image, as listed by [`Metadata`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ImageMetadata {
+    /// The offset of the IFD in the file
+    pub ifd_offset: u64,
+    /// The entries of the IFD in the order they are stored, which should be sorted by tag
+    pub tags: Vec<TagEntry>,
+    /// The GeoKeys, or `None` if the image has no GeoKeyDirectoryTag or it is invalid
+    pub geo_key_directory: Option<GeoKeyDirectory>,
+    /// The reasons why values of tags or the GeoKeys cannot be read
+    pub errors: Vec<String>,
+}
+
+/// An entry of an IFD.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TagEntry {
+    /// The numeric tag, e.g. 256 for the ImageWidth tag
+    pub code: u16,
+    /// The TIFF field type, e.g. 3 for SHORT
+    pub field_type: u16,
+    /// The number of values
+    pub count: u64,
+    /// The value, or `None` if it cannot be read
+    pub value: Option<TagValue>,
+}
+
+/// The value of a tag, by field type.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TagValue {
+    /// The values of the BYTE, SHORT, LONG, LONG8, IFD and IFD8 types
+    Unsigned(Vec<u64>),
+    /// The values of the SBYTE, SSHORT, SLONG and SLONG8 types
+    Signed(Vec<i64>),
+    /// The values of the FLOAT and DOUBLE types
+    Float(Vec<f64>),
+    /// The numerators and denominators of the RATIONAL type
+    Rational(Vec<[u32; 2]>),
+    /// The numerators and denominators of the SRATIONAL type
+    SignedRational(Vec<[i32; 2]>),
+    /// The string of the ASCII type, without the terminating NUL
+    Ascii(String),
+    /// The bytes of the UNDEFINED type
+    Undefined(Vec<u8>),
+}
+
+impl Metadata {
+    pub(crate) fn read<R: Read + Seek>(mut reader: R) -> GeoTiffResult<Self> {
+        let header = Header::read(&mut reader)?;
+        let images = header
+            .read_ifd_offsets(&mut reader)?
+            .into_iter()
+            .map(|ifd_offset| ImageMetadata::read(&header, &mut reader, ifd_offset))
+            .collect::<GeoTiffResult<_>>()?;
+        Ok(Metadata { images })
+    }
+
+    pub fn iter(&self) -> slice::Iter<'_, ImageMetadata> {
+        self.images.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Metadata {
+    type Item = &'a ImageMetadata;
+    type IntoIter = slice::Iter<'a, ImageMetadata>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl ImageMetadata {
+    fn read<R: Read + Seek>(
+        header: &Header,
+        reader: &mut R,
+        ifd_offset: u64,
+    ) -> GeoTiffResult<Self> {
+        let mut errors = Vec::new();
+        let tags = header
+            .read_ifd_entries(reader, ifd_offset)?
+            .into_iter()
+            .map(|entry| {
+                let value = TagValue::read(header, reader, &entry)
+                    .map_err(|err| errors.push(format!("Tag {}: {}", entry.tag, err)))
+                    .ok();
+                TagEntry {
+                    code: entry.tag,
+                    field_type: entry.field_type,
+                    count: entry.count,
+                    value,
+                }
+            })
+            .collect();
+        let mut image = ImageMetadata {
+            ifd_offset,
+            tags,
+            geo_key_directory: None,
+            errors,
+        };
+        image.geo_key_directory = image.read_geo_key_directory();
+        Ok(image)
+    }
+
+    /// Parses the GeoKeys of the GeoKeyDirectoryTag, GeoDoubleParamsTag and GeoAsciiParamsTag.
+    fn read_geo_key_directory(&mut self) -> Option<GeoKeyDirectory> {
+        let directory_data = match self.get(Tag::GeoKeyDirectoryTag.to_u16())? {
+            TagValue::Unsigned(values) => values.iter().map(|&value| value as u16).collect(),
+            _ => {
+                self.errors
+                    .push("The GeoKeyDirectoryTag is not of type SHORT".to_string());
+                return None;
+            }
+        };
+        let double_params_data = match self.get(Tag::GeoDoubleParamsTag.to_u16()) {
+            Some(TagValue::Float(values)) => Some(values.clone()),
+            _ => None,
+        };
+        let ascii_params_data = match self.get(Tag::GeoAsciiParamsTag.to_u16()) {
+            Some(TagValue::Ascii(value)) => Some(value.clone()),
+            _ => None,
+        };
+        GeoKeyDirectory::from_tags(
+            directory_data,
+            double_params_data,
+            ascii_params_data,
+            |location| Ok(self.get(location).cloned()),
+        )
+        .map_err(|err| self.errors.push(err.to_string()))
+        .ok()
+    }
+
+    /// The value of a tag, or `None` if the tag is missing or its value cannot be read.
+    pub fn get(&self, code: u16) -> Option<&TagValue> {
+        self.tags
+            .iter()
+            .find(|entry| entry.code == code)
+            .and_then(|entry| entry.value.as_ref())
+    }
+
+    /// The GeoKeys ordered by key, which is empty if there is no valid GeoKeyDirectoryTag.
+    pub fn geo_keys(&self) -> impl Iterator<Item = (GeoKey, &GeoKeyValue)> {
+        self.geo_key_directory
+            .iter()
+            .flat_map(|directory| directory.keys())
+    }
+}
+
+impl TagEntry {
+    /// The name of the tag as defined by the TIFF, GeoTIFF and GDAL specifications, e.g.
+    /// `ImageWidth`, or `None` if the tag is unknown.
+    pub fn name(&self) -> Option<String> {
+        match self.code {
+            GDAL_METADATA_TAG => Some("GdalMetadata".to_string()),
+            RPC_COEFFICIENT_TAG => Some("RpcCoefficientTag".to_string()),
+            code => Tag::from_u16(code).map(|tag| format!("{:?}", tag)),
+        }
+    }
+}
+
+impl TagValue {
+    fn read<R: Read + Seek>(
+        header: &Header,
+        reader: &mut R,
+        entry: &IfdEntry,
+    ) -> GeoTiffResult<Self> {
+        let value_size = match entry.field_type {
+            1 | 2 | 6 | 7 => 1,
+            3 | 8 => 2,
+            4 | 9 | 11 | 13 => 4,
+            5 | 10 | 12 | 16..=18 => 8,
+            field_type => {
+                return Err(GeoTiffError::Unsupported(format!(
+                    "Unknown field type {}",
+                    field_type
+                )))
+            }
+        };
+        let bytes = header.read_entry_bytes(reader, entry, value_size)?;
+        let values = bytes
+            .chunks_exact(value_size as usize)
+            .map(|value| header.decode_uint(value));
+        // Extends the sign of signed values of the value size
+        let shift = 64 - 8 * value_size;
+        Ok(match entry.field_type {
+            2 => TagValue::Ascii(
+                String::from_utf8_lossy(&bytes)
+                    .trim_end_matches('\0')
+                    .to_string(),
+            ),
+            7 => TagValue::Undefined(bytes),
+            6 | 8 | 9 | 17 => TagValue::Signed(
+                values
+                    .map(|value| (value << shift) as i64 >> shift)
+                    .collect(),
+            ),
+            11 => TagValue::Float(
+                values
+                    .map(|value| f32::from_bits(value as u32) as f64)
+                    .collect(),
+            ),
+            12 => TagValue::Float(values.map(f64::from_bits).collect()),
+            5 => TagValue::Rational(
+                bytes
+                    .chunks_exact(4)
+                    .map(|value| header.decode_uint(value) as u32)
+                    .collect::<Vec<_>>()
+                    .chunks_exact(2)
+                    .map(|fraction| [fraction[0], fraction[1]])
+                    .collect(),
+            ),
+            10 => TagValue::SignedRational(
+                bytes
+                    .chunks_exact(4)
+                    .map(|value| header.decode_uint(value) as i32)
+                    .collect::<Vec<_>>()
+                    .chunks_exact(2)
+                    .map(|fraction| [fraction[0], fraction[1]])
+                    .collect(),
+            ),
+            _ => TagValue::Unsigned(values.collect()),
+        })
+    }
+
+    /// Converts a value read by the tiff decoder, which does not tell apart UNDEFINED from BYTE
+    /// values, nor the sizes of the types. Returns `None` for empty lists and mixed types.
+    pub(crate) fn from_tiff_value(value: Value) -> Option<Self> {
+        let values = match value {
+            Value::List(values) => values,
+            value => vec![value],
+        };
+        let mut values = values.into_iter().map(|value| match value {
+            Value::Byte(value) => Some(TagValue::Unsigned(vec![value as u64])),
+            Value::Short(value) => Some(TagValue::Unsigned(vec![value as u64])),
+            Value::Unsigned(value) | Value::Ifd(value) => {
+                Some(TagValue::Unsigned(vec![value as u64]))
+            }
+            Value::UnsignedBig(value) | Value::IfdBig(value) => {
+                Some(TagValue::Unsigned(vec![value]))
+            }
+            Value::Signed(value) => Some(TagValue::Signed(vec![value as i64])),
+            Value::SignedBig(value) => Some(TagValue::Signed(vec![value])),
+            Value::Float(value) => Some(TagValue::Float(vec![value as f64])),
+            Value::Double(value) => Some(TagValue::Float(vec![value])),
+            Value::Rational(numerator, denominator) => {
+                Some(TagValue::Rational(vec![[numerator, denominator]]))
+            }
+            Value::SRational(numerator, denominator) => {
+                Some(TagValue::SignedRational(vec![[numerator, denominator]]))
+            }
+            Value::Ascii(value) => Some(TagValue::Ascii(value)),
+            _ => None,
+        });
+        let mut tag_value = values.next()??;
+        for value in values {
+            if !tag_value.append(&value?) {
+                return None;
+            }
+        }
+        Some(tag_value)
+    }
+
+    /// The `count` values from index `offset` on, counting the bytes of ASCII strings, or `None`
+    /// if they exceed the value.
+    pub(crate) fn slice(&self, offset: usize, count: usize) -> Option<Self> {
+        let range = offset..offset.checked_add(count)?;
+        Some(match self {
+            TagValue::Unsigned(values) => TagValue::Unsigned(values.get(range)?.to_vec()),
+            TagValue::Signed(values) => TagValue::Signed(values.get(range)?.to_vec()),
+            TagValue::Float(values) => TagValue::Float(values.get(range)?.to_vec()),
+            TagValue::Rational(values) => TagValue::Rational(values.get(range)?.to_vec()),
+            TagValue::SignedRational(values) => {
+                TagValue::SignedRational(values.get(range)?.to_vec())
+            }
+            TagValue::Ascii(value) => TagValue::Ascii(value.get(range)?.to_string()),
+            TagValue::Undefined(bytes) => TagValue::Undefined(bytes.get(range)?.to_vec()),
+        })
+    }
+
+    /// The number of values, counting the bytes of ASCII strings.
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            TagValue::Unsigned(values) => values.len(),
+            TagValue::Signed(values) => values.len(),
+            TagValue::Float(values) => values.len(),
+            TagValue::Rational(values) => values.len(),
+            TagValue::SignedRational(values) => values.len(),
+            TagValue::Ascii(value) => value.len(),
+            TagValue::Undefined(bytes) => bytes.len(),
+        }
+    }
+
+    /// Appends the values of another value of the same type, or returns `false` if the types
+    /// differ.
+    pub(crate) fn append(&mut self, other: &TagValue) -> bool {
+        match (self, other) {
+            (TagValue::Unsigned(values), TagValue::Unsigned(other)) => values.extend(other),
+            (TagValue::Signed(values), TagValue::Signed(other)) => values.extend(other),
+            (TagValue::Float(values), TagValue::Float(other)) => values.extend(other),
+            (TagValue::Rational(values), TagValue::Rational(other)) => values.extend(other),
+            (TagValue::SignedRational(values), TagValue::SignedRational(other)) => {
+                values.extend(other)
+            }
+            (TagValue::Ascii(value), TagValue::Ascii(other)) => value.push_str(other),
+            (TagValue::Undefined(bytes), TagValue::Undefined(other)) => bytes.extend(other),
+            _ => return false,
+        }
+        true
+    }
+}
+
+/// Lists the values separated by commas, like `tiffdump` does.
+impl Display for TagValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        fn list<T: Display>(f: &mut Formatter<'_>, values: &[T]) -> fmt::Result {
+            for (index, value) in values.iter().enumerate() {
+                if index > 0 {
+                    write!(f, ",")?;
+                }
+                write!(f, "{}", value)?;
+            }
+            Ok(())
+        }
+        match self {
+            TagValue::Unsigned(values) => list(f, values),
+            TagValue::Signed(values) => list(f, values),
+            TagValue::Float(values) => list(f, values),
+            TagValue::Rational(values) => list(
+                f,
+                &values
+                    .iter()
+                    .map(|[numerator, denominator]| format!("{}/{}", numerator, denominator))
+                    .collect::<Vec<_>>(),
+            ),
+            TagValue::SignedRational(values) => list(
+                f,
+                &values
+                    .iter()
+                    .map(|[numerator, denominator]| format!("{}/{}", numerator, denominator))
+                    .collect::<Vec<_>>(),
+            ),
+            TagValue::Ascii(value) => write!(f, "{:?}", value),
+            TagValue::Undefined(bytes) => list(f, bytes),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{CoordinateTransform, GeoTiff, GeoTiffWriter, Resampling};
+
+    #[test]
+    fn test_metadata() {
+        let data = (0..40 * 20).map(|value| value as u16).collect::<Vec<_>>();
+        let mut bytes = Vec::new();
+        GeoTiffWriter::new()
+            .with_epsg(32632)
+            .unwrap()
+            .with_coordinate_transform(
+                CoordinateTransform::from_geotransform([
+                    500000.0, 10.0, 0.0, 5000000.0, 0.0, -10.0,
+                ])
+                .unwrap(),
+            )
+            .with_nodata(-1.0)
+            .add_overviews(&[2], Resampling::Average)
+            .write(&mut bytes, 40, 20, 1, &data)
+            .unwrap();
+        let metadata = GeoTiff::metadata(Cursor::new(bytes)).unwrap();
+        assert_eq!(metadata.images.len(), 2);
+
+        let image = metadata.iter().next().unwrap();
+        assert!(image.errors.is_empty(), "{:?}", image.errors);
+        assert_eq!(image.get(256), Some(&TagValue::Unsigned(vec![40])));
+        assert_eq!(image.tags[0].name().as_deref(), Some("ImageWidth"));
+        assert!(image
+            .tags
+            .windows(2)
+            .all(|tags| tags[0].code < tags[1].code));
+        assert!(matches!(
+            image.get(Tag::ModelTransformationTag.to_u16()),
+            Some(TagValue::Float(values)) if values[..4] == [10.0, 0.0, 0.0, 500000.0]
+        ));
+        assert_eq!(
+            image.get(Tag::GdalNodata.to_u16()),
+            Some(&TagValue::Ascii("-1".to_string()))
+        );
+        assert_eq!(
+            image.get(Tag::GdalNodata.to_u16()).unwrap().to_string(),
+            "\"-1\""
+        );
+        assert!(image
+            .geo_keys()
+            .any(|(key, value)| key == GeoKey::ProjectedCSType
+                && value == &GeoKeyValue::Short(vec![32632])));
+
+        // The overview has no GeoKeys
+        let overview = &metadata.images[1];
+        assert_eq!(overview.get(256), Some(&TagValue::Unsigned(vec![20])));
+        assert_eq!(overview.geo_keys().count(), 0);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let mut bytes = Vec::new();
+        GeoTiffWriter::new()
+            .with_epsg(4326)
+            .unwrap()
+            .write(&mut bytes, 2, 2, 1, &[1u8, 2, 3, 4])
+            .unwrap();
+        let metadata = GeoTiff::metadata(Cursor::new(bytes)).unwrap();
+        let json = serde_json::to_string(&metadata).unwrap();
+        assert!(
+            json.contains(r#""GeographicType":{"Short":[4326]}"#),
+            "{}",
+            json
+        );
+        assert_eq!(serde_json::from_str::<Metadata>(&json).unwrap(), metadata);
+    }
+
+    #[test]
+    fn test_malformed_metadata() {
+        // An IFD with an unknown tag of signed shorts, a rational, a tag of an unknown type and
+        // a tag whose value exceeds the file
+        let mut bytes = b"MM\x00\x2a\x00\x00\x00\x08\x00\x04".to_vec();
+        for entry in [
+            [
+                0xc3, 0x50, 0x00, 0x08, 0x00, 0x00, 0x00, 0x02, 0xff, 0xfe, 0x00, 0x07,
+            ],
+            [
+                0x01, 0x1a, 0x00, 0x05, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x3e,
+            ],
+            [
+                0xc3, 0x51, 0x00, 0x63, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00,
+            ],
+            [
+                0xc3, 0x52, 0x00, 0x04, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x3e,
+            ],
+        ] {
+            bytes.extend_from_slice(&entry);
+        }
+        bytes.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 72, 0, 0, 0, 1]);
+
+        let metadata = Metadata::read(Cursor::new(bytes)).unwrap();
+        let image = &metadata.images[0];
+        assert_eq!(image.tags.len(), 4);
+        assert_eq!(image.get(50000), Some(&TagValue::Signed(vec![-2, 7])));
+        assert_eq!(image.tags[0].name(), None);
+        assert_eq!(image.get(282), Some(&TagValue::Rational(vec![[72, 1]])));
+        assert_eq!(image.tags[1].name().as_deref(), Some("XResolution"));
+        assert_eq!(image.get(50001), None);
+        assert_eq!(image.tags[3].value, None);
+        assert_eq!(image.errors.len(), 2);
+        assert_eq!(image.geo_key_directory, None);
+    }
+}
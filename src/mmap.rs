@@ -0,0 +1,119 @@
+use std::fs::File;
+use std::io::{Cursor, Read, Seek};
+use std::path::Path;
+use std::sync::Arc;
+
+use memmap2::Mmap;
+use tiff::{TiffError, TiffFormatError};
+
+use crate::{GeoTiffError, GeoTiffReader, GeoTiffResult};
+
+/// A [`GeoTiffReader`] over a memory-mapped file.
+///
+/// Besides decoding the raster data like any reader, it gives direct access to the raw data of
+/// uncompressed strips and tiles as slices into the mapping, without copying them into buffers on
+/// the heap.
+pub struct MmapGeoTiffReader {
+    mmap: Arc<Mmap>,
+    reader: GeoTiffReader<Cursor<SharedMmap>>,
+}
+
+impl MmapGeoTiffReader {
+    /// Maps the file at the given path into memory and reads its metadata.
+    ///
+    /// The file must not be modified while it is mapped, as this would change the data of the
+    /// mapping, see [`Mmap::map`].
+    pub fn open(path: impl AsRef<Path>) -> GeoTiffResult<Self> {
+        let file = File::open(path)?;
+        // SAFETY: The mapping is read-only and the caller must not modify the file while mapped
+        let mmap = Arc::new(unsafe { Mmap::map(&file)? });
+        let reader = GeoTiffReader::new(Cursor::new(SharedMmap(mmap.clone())))?;
+        Ok(Self { mmap, reader })
+    }
+
+    /// The reader decoding the raster data from the mapping.
+    pub fn reader(&mut self) -> &mut GeoTiffReader<impl Read + Seek> {
+        &mut self.reader
+    }
+
+    /// The raw data of the strip or tile with the given index as a slice into the mapping, see
+    /// [`GeoTiffReader::chunk_byte_range`].
+    ///
+    /// The samples are stored in the byte order of the file, and tiles at the right and bottom
+    /// edges include their padding, while sparse chunks are empty. Fails if the raster data is
+    /// compressed.
+    pub fn chunk_bytes(&mut self, index: usize) -> GeoTiffResult<&[u8]> {
+        if !self.reader.is_uncompressed()? {
+            return Err(GeoTiffError::Unsupported(
+                "Only uncompressed raster data can be accessed in place".to_string(),
+            ));
+        }
+        let range = self.reader.chunk_byte_range(index)?;
+        usize::try_from(range.start)
+            .ok()
+            .zip(usize::try_from(range.end).ok())
+            .and_then(|(start, end)| self.mmap.get(start..end))
+            .ok_or_else(|| format_error(format!("Chunk {} exceeds the file", index)))
+    }
+}
+
+/// A mapping shared between the reader and the slices returned by [`MmapGeoTiffReader`].
+struct SharedMmap(Arc<Mmap>);
+
+impl AsRef<[u8]> for SharedMmap {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+fn format_error(msg: impl ToString) -> GeoTiffError {
+    GeoTiffError::Tiff(TiffError::FormatError(TiffFormatError::Format(
+        msg.to_string(),
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::{Compression, GeoTiffWriter};
+
+    #[test]
+    fn test_chunk_bytes() {
+        let path = std::env::temp_dir().join(format!("geotiff-mmap-{}.tif", std::process::id()));
+        let data = (0..40 * 20).map(|value| value as u16).collect::<Vec<_>>();
+        let mut bytes = Vec::new();
+        GeoTiffWriter::new()
+            .with_strips(8)
+            .write(&mut bytes, 40, 20, 1, &data)
+            .unwrap();
+        fs::write(&path, &bytes).unwrap();
+
+        let mut reader = MmapGeoTiffReader::open(&path).unwrap();
+        let strip = reader.chunk_bytes(1).unwrap();
+        assert_eq!(strip.len(), 8 * 40 * 2);
+        // The file is little-endian
+        assert_eq!(u16::from_le_bytes([strip[0], strip[1]]), 8 * 40);
+        assert!(reader.chunk_bytes(3).is_err());
+        assert_eq!(
+            reader
+                .reader()
+                .read_strip(2)
+                .unwrap()
+                .get_value_at::<u16>(0, 0, 0),
+            16 * 40
+        );
+
+        bytes.clear();
+        GeoTiffWriter::new()
+            .with_compression(Compression::Lzw)
+            .write(&mut bytes, 40, 20, 1, &data)
+            .unwrap();
+        fs::write(&path, &bytes).unwrap();
+        let mut reader = MmapGeoTiffReader::open(&path).unwrap();
+        assert!(reader.chunk_bytes(0).is_err());
+        drop(reader);
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,201 @@
+use std::any::type_name;
+use std::io::Write;
+
+use ndarray::{Array2, Array3, ArrayView2, ArrayView3};
+use num_traits::FromPrimitive;
+
+use crate::{
+    try_sample_value, GeoTiff, GeoTiffError, GeoTiffResult, GeoTiffWriter, Layout, Sample,
+};
+
+impl GeoTiff {
+    /// The samples of a raster of a single band converted to `T`, as an array of rows and
+    /// columns.
+    ///
+    /// Fails if the raster has more than one band, see [`GeoTiff::read_to_array3`], or a sample
+    /// cannot be represented as `T`, e.g. NaN or 300 as `u8`.
+    pub fn read_to_array2<T: FromPrimitive + 'static>(&self) -> GeoTiffResult<Array2<T>> {
+        if self.num_samples != 1 {
+            return Err(GeoTiffError::InvalidArgument(format!(
+                "The raster has {} bands instead of one",
+                self.num_samples
+            )));
+        }
+        let data = self.convert_values(Layout::PixelInterleaved)?;
+        Ok(
+            Array2::from_shape_vec((self.raster_height, self.raster_width), data)
+                .expect("The raster has a sample per pixel"),
+        )
+    }
+
+    /// The samples of the raster converted to `T`, as an array of bands, rows and columns.
+    ///
+    /// Fails if a sample cannot be represented as `T`.
+    pub fn read_to_array3<T: FromPrimitive + 'static>(&self) -> GeoTiffResult<Array3<T>> {
+        let data = self.convert_values(Layout::BandSequential)?;
+        Ok(Array3::from_shape_vec(
+            (self.num_samples, self.raster_height, self.raster_width),
+            data,
+        )
+        .expect("The raster has a sample per band and pixel"))
+    }
+
+    /// Like [`GeoTiff::get_values`], but fails instead of panicking on the first sample that
+    /// cannot be represented as `T`.
+    fn convert_values<T: FromPrimitive + 'static>(&self, layout: Layout) -> GeoTiffResult<Vec<T>> {
+        let num_pixels = self.raster_width * self.raster_height;
+        (0..num_pixels * self.num_samples)
+            .map(|index| {
+                let index = match layout {
+                    Layout::PixelInterleaved => index,
+                    Layout::BandSequential => {
+                        index % num_pixels * self.num_samples + index / num_pixels
+                    }
+                };
+                try_sample_value(&self.raster_data, index).ok_or_else(|| {
+                    let pixel = index / self.num_samples;
+                    GeoTiffError::InvalidArgument(format!(
+                        "Sample {} of pixel ({}, {}) cannot be represented as {}",
+                        index % self.num_samples,
+                        pixel % self.raster_width,
+                        pixel / self.raster_width,
+                        type_name::<T>()
+                    ))
+                })
+            })
+            .collect()
+    }
+}
+
+impl GeoTiffWriter {
+    /// Writes a raster of a single band given as an array of rows and columns, see
+    /// [`GeoTiffWriter::write`]. The SampleFormat and BitsPerSample follow from the element type.
+    ///
+    /// The array may be any view, e.g. a slice with steps or a transposed array, whose elements
+    /// are written in logical order.
+    pub fn write_array2<W: Write, T: Sample>(
+        &self,
+        writer: W,
+        array: ArrayView2<T>,
+    ) -> GeoTiffResult<()> {
+        let (raster_height, raster_width) = array.dim();
+        let data = array.iter().copied().collect::<Vec<_>>();
+        self.write(writer, raster_width, raster_height, 1, &data)
+    }
+
+    /// Writes a raster given as an array of bands, rows and columns, see
+    /// [`GeoTiffWriter::write_array2`]. The bands are interleaved by pixel, unless the writer
+    /// stores them plane by plane, see [`GeoTiffWriter::with_planar`].
+    pub fn write_array3<W: Write, T: Sample>(
+        &self,
+        writer: W,
+        array: ArrayView3<T>,
+    ) -> GeoTiffResult<()> {
+        let (num_samples, raster_height, raster_width) = array.dim();
+        // Iterates over the samples of each pixel in turn
+        let data = array
+            .permuted_axes([1, 2, 0])
+            .iter()
+            .copied()
+            .collect::<Vec<_>>();
+        self.write(writer, raster_width, raster_height, num_samples, &data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use ndarray::{s, Array2, Array3};
+
+    use super::*;
+
+    #[test]
+    fn test_write_array() {
+        let array = Array2::from_shape_fn((20, 40), |(y, x)| (y * 40 + x) as f32);
+        let mut bytes = Vec::new();
+        GeoTiffWriter::new()
+            .write_array2(&mut bytes, array.view())
+            .unwrap();
+        let geotiff = GeoTiff::read(Cursor::new(bytes)).unwrap();
+        assert_eq!((geotiff.raster_width, geotiff.raster_height), (40, 20));
+        assert_eq!(
+            geotiff.get_values::<f32>(Layout::PixelInterleaved),
+            array.iter().copied().collect::<Vec<_>>()
+        );
+
+        // Every other column of the array, transposed
+        let view = array.t();
+        let view = view.slice(s![..;2, ..]);
+        let mut bytes = Vec::new();
+        GeoTiffWriter::new().write_array2(&mut bytes, view).unwrap();
+        let geotiff = GeoTiff::read(Cursor::new(bytes)).unwrap();
+        assert_eq!((geotiff.raster_width, geotiff.raster_height), (20, 20));
+        assert_eq!(geotiff.get_value_at::<f32>(3, 2, 0), 124.0);
+
+        let array = Array3::from_shape_fn((3, 20, 40), |(band, y, x)| {
+            (band * 1000 + y * 40 + x) as u16
+        });
+        for writer in [GeoTiffWriter::new(), GeoTiffWriter::new().with_planar(true)] {
+            let mut bytes = Vec::new();
+            writer.write_array3(&mut bytes, array.view()).unwrap();
+            let geotiff = GeoTiff::read(Cursor::new(bytes)).unwrap();
+            assert_eq!(geotiff.num_samples, 3);
+            assert_eq!(geotiff.get_value_at::<u16>(5, 2, 0), 85);
+            assert_eq!(geotiff.get_value_at::<u16>(5, 2, 2), 2085);
+        }
+    }
+
+    #[test]
+    fn test_read_to_array() {
+        let data = (0..4 * 3 * 2).map(|value| value as u16).collect::<Vec<_>>();
+        let mut bytes = Vec::new();
+        GeoTiffWriter::new()
+            .write(&mut bytes, 4, 3, 2, &data)
+            .unwrap();
+        let geotiff = GeoTiff::read(Cursor::new(bytes)).unwrap();
+
+        let array = geotiff.read_to_array3::<f32>().unwrap();
+        assert_eq!(array.dim(), (2, 3, 4));
+        // The samples are interleaved by pixel in the file
+        assert_eq!(array[[0, 0, 1]], 2.0);
+        assert_eq!(array[[1, 0, 1]], 3.0);
+        assert_eq!(array[[1, 2, 3]], 23.0);
+        assert_eq!(
+            array.iter().take(5).copied().collect::<Vec<_>>(),
+            [0.0, 2.0, 4.0, 6.0, 8.0]
+        );
+        assert!(geotiff.read_to_array2::<u16>().is_err());
+
+        let array = Array2::from_shape_fn((3, 4), |(y, x)| (y * 4 + x) as u8);
+        let mut bytes = Vec::new();
+        GeoTiffWriter::new()
+            .write_array2(&mut bytes, array.view())
+            .unwrap();
+        let geotiff = GeoTiff::read(Cursor::new(bytes)).unwrap();
+        assert_eq!(geotiff.read_to_array2::<u8>().unwrap(), array);
+        assert_eq!(geotiff.read_to_array3::<u8>().unwrap().dim(), (1, 3, 4));
+
+        // Samples that cannot be represented as the element type
+        for value in [f32::NAN, 300.0] {
+            let array =
+                Array2::from_shape_fn((3, 4), |(y, x)| if (x, y) == (2, 1) { value } else { 1.0 });
+            let mut bytes = Vec::new();
+            GeoTiffWriter::new()
+                .write_array2(&mut bytes, array.view())
+                .unwrap();
+            let geotiff = GeoTiff::read(Cursor::new(bytes)).unwrap();
+            assert!(matches!(
+                geotiff.read_to_array2::<u8>(),
+                Err(GeoTiffError::InvalidArgument(_))
+            ));
+            assert!(matches!(
+                geotiff.read_to_array3::<u8>(),
+                Err(GeoTiffError::InvalidArgument(_))
+            ));
+            // Comparing the bits, as NaN differs from itself
+            let array = geotiff.read_to_array2::<f32>().unwrap();
+            assert_eq!(array[[1, 2]].to_bits(), value.to_bits());
+        }
+    }
+}
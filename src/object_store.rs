@@ -0,0 +1,186 @@
+use std::io;
+use std::ops::Range;
+use std::sync::Arc;
+
+use object_store::path::Path;
+use object_store::ObjectStore;
+use url::Url;
+
+use crate::fetch::{self, FetchOptions, RetryPolicy};
+use crate::{GeoTiffError, GeoTiffResult, RangeSource, RemoteError};
+
+/// A [`RangeSource`] reading an object of an [`ObjectStore`], e.g. in Amazon S3, Google Cloud
+/// Storage, Azure Blob Storage or the local file system, fetching the byte ranges read by an
+/// [`AsyncGeoTiffReader`](crate::AsyncGeoTiffReader) by range requests. The strips or tiles of a
+/// window are fetched by concurrent requests, see [`ObjectStoreSource::with_fetch_options`].
+///
+/// Requests failing transiently, e.g. by a dropped connection, are repeated, see
+/// [`ObjectStoreSource::with_retry_policy`], in addition to any retries of the store itself.
+#[derive(Debug, Clone)]
+pub struct ObjectStoreSource {
+    store: Arc<dyn ObjectStore>,
+    path: Path,
+    fetch_options: FetchOptions,
+    retry_policy: RetryPolicy,
+}
+
+impl ObjectStoreSource {
+    /// Creates a source reading the object at the given path of a store configured beforehand,
+    /// e.g. with explicit credentials.
+    pub fn new(store: Arc<dyn ObjectStore>, path: Path) -> Self {
+        Self {
+            store,
+            path,
+            fetch_options: FetchOptions::default(),
+            retry_policy: RetryPolicy::default(),
+        }
+    }
+
+    /// Sets how many requests fetch the strips or tiles of a window concurrently and which
+    /// ranges are coalesced into a single request.
+    pub fn with_fetch_options(mut self, fetch_options: FetchOptions) -> Self {
+        self.fetch_options = fetch_options;
+        self
+    }
+
+    /// Sets how often and how long after failing transiently requests are repeated, and after
+    /// which time they are aborted.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Creates a source reading the object at the given URL, e.g. `s3://bucket/path/image.tif`,
+    /// `gs://bucket/image.tif`, `az://container/image.tif` or `file:///path/image.tif`.
+    ///
+    /// The store is configured by the environment like the builders of `object_store` do, e.g.
+    /// with the credentials in `AWS_ACCESS_KEY_ID` and `AWS_SECRET_ACCESS_KEY`.
+    pub fn from_url(url: &str) -> GeoTiffResult<Self> {
+        let options = std::env::vars().map(|(key, value)| (key.to_ascii_lowercase(), value));
+        Self::from_url_with_options(url, options)
+    }
+
+    /// Creates a source reading the object at the given URL, see
+    /// [`ObjectStoreSource::from_url`], with the store configured by the given options instead of
+    /// the environment, e.g. `("aws_access_key_id", "...")` or `("aws_region", "eu-central-1")`.
+    ///
+    /// Options unknown to the store are ignored.
+    pub fn from_url_with_options<I, K, V>(url: &str, options: I) -> GeoTiffResult<Self>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: Into<String>,
+    {
+        let parsed_url = Url::parse(url).map_err(|err| {
+            GeoTiffError::InvalidArgument(format!("Invalid URL {}: {}", url, err))
+        })?;
+        let (store, path) = object_store::parse_url_opts(&parsed_url, options).map_err(|err| {
+            GeoTiffError::Unsupported(format!("Unsupported URL {}: {}", url, err))
+        })?;
+        Ok(Self::new(store.into(), path))
+    }
+
+    /// Requests the given range of bytes, repeating the request according to the retry policy.
+    async fn get_range(&self, range: Range<u64>) -> io::Result<Vec<u8>> {
+        fetch::retry(self.retry_policy, || async {
+            let bytes = self
+                .store
+                .get_range(&self.path, range.clone())
+                .await
+                .map_err(remote_error)?;
+            Ok(bytes.to_vec())
+        })
+        .await
+    }
+}
+
+/// Classifies the errors of requests, which are reported as generic errors by the stores, as
+/// transient, unlike e.g. missing objects or denied access.
+fn remote_error(err: object_store::Error) -> RemoteError {
+    match err {
+        object_store::Error::Generic { .. } | object_store::Error::JoinError { .. } => {
+            RemoteError::transient(io::Error::other(err))
+        }
+        err => RemoteError::permanent(io::Error::other(err)),
+    }
+}
+
+impl RangeSource for ObjectStoreSource {
+    async fn length(&mut self) -> io::Result<u64> {
+        let meta = fetch::retry(self.retry_policy, || async {
+            self.store.head(&self.path).await.map_err(remote_error)
+        })
+        .await?;
+        Ok(meta.size)
+    }
+
+    async fn read_range(&mut self, range: Range<u64>) -> io::Result<Vec<u8>> {
+        if range.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.get_range(range).await
+    }
+
+    async fn read_ranges(&mut self, ranges: &[Range<u64>]) -> io::Result<Vec<Vec<u8>>> {
+        let source = &*self;
+        fetch::fetch_ranges(ranges, self.fetch_options, |range| source.get_range(range)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use object_store::memory::InMemory;
+    use object_store::PutPayload;
+
+    use super::*;
+    use crate::{AsyncGeoTiffReader, GeoTiffWriter};
+
+    fn geotiff() -> Vec<u8> {
+        let data = (0..300 * 200).map(|value| value as u16).collect::<Vec<_>>();
+        let mut bytes = Vec::new();
+        GeoTiffWriter::new()
+            .with_tiles(64, 64)
+            .write(&mut bytes, 300, 200, 1, &data)
+            .unwrap();
+        bytes
+    }
+
+    #[tokio::test]
+    async fn test_read_in_memory() {
+        let store = Arc::new(InMemory::new());
+        let path = Path::from("images/image.tif");
+        store.put(&path, PutPayload::from(geotiff())).await.unwrap();
+
+        let mut reader = AsyncGeoTiffReader::new(ObjectStoreSource::new(store.clone(), path))
+            .await
+            .unwrap();
+        let tile = reader.read_tile(4, 3).await.unwrap();
+        assert_eq!((tile.raster_width, tile.raster_height), (44, 8));
+        assert_eq!(tile.get_value_at::<u16>(0, 0, 0), 192 * 300 + 256);
+
+        // Missing objects are not retried
+        let source = ObjectStoreSource::new(store, Path::from("missing.tif"));
+        let err = AsyncGeoTiffReader::new(source).await.err().unwrap();
+        assert!(!err.is_transient());
+        assert!(matches!(&err, GeoTiffError::Remote(remote) if remote.attempts() == 1));
+    }
+
+    #[tokio::test]
+    async fn test_read_from_url() {
+        let path =
+            std::env::temp_dir().join(format!("geotiff-object-store-{}.tif", std::process::id()));
+        fs::write(&path, geotiff()).unwrap();
+
+        let url = format!("file://{}", path.display());
+        let mut reader = AsyncGeoTiffReader::new(ObjectStoreSource::from_url(&url).unwrap())
+            .await
+            .unwrap();
+        let window = reader.read_window(100, 100, 2, 2).await.unwrap();
+        assert_eq!(window.get_value_at::<u16>(1, 1, 0), 101 * 300 + 101);
+        fs::remove_file(&path).unwrap();
+
+        assert!(ObjectStoreSource::from_url("unknown://bucket/image.tif").is_err());
+    }
+}
@@ -0,0 +1,125 @@
+use std::marker::PhantomData;
+
+use num_traits::FromPrimitive;
+
+use crate::{CoordinateTransform, GeoTiff};
+
+/// A pixel of a band along with its position in model space, see [`GeoTiff::pixels`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pixel<T> {
+    /// The column of the pixel in the raster
+    pub column: usize,
+    /// The row of the pixel in the raster
+    pub row: usize,
+    /// The model X coordinate of the center of the pixel
+    pub model_x: f64,
+    /// The model Y coordinate of the center of the pixel
+    pub model_y: f64,
+    /// The value of the band, or `None` for nodata, see [`GeoTiff::get_value_at_checked`]
+    pub value: Option<T>,
+}
+
+/// An iterator over the pixels of a window of a band, row by row, see [`GeoTiff::pixels`].
+pub struct Pixels<'a, T> {
+    geotiff: &'a GeoTiff,
+    coordinate_transform: &'a CoordinateTransform,
+    band: usize,
+    window: [usize; 4],
+    index: usize,
+    sample_type: PhantomData<T>,
+}
+
+impl<'a, T> Pixels<'a, T> {
+    pub(crate) fn new(
+        geotiff: &'a GeoTiff,
+        coordinate_transform: &'a CoordinateTransform,
+        band: usize,
+        window: [usize; 4],
+    ) -> Self {
+        Self {
+            geotiff,
+            coordinate_transform,
+            band,
+            window,
+            index: 0,
+            sample_type: PhantomData,
+        }
+    }
+}
+
+impl<T: FromPrimitive + 'static> Iterator for Pixels<'_, T> {
+    type Item = Pixel<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let [x, y, width, height] = self.window;
+        if self.index >= width * height {
+            return None;
+        }
+        let column = x + self.index % width;
+        let row = y + self.index / width;
+        self.index += 1;
+
+        let [model_x, model_y] = self
+            .coordinate_transform
+            .transform_to_model_f64([column as f64 + 0.5, row as f64 + 0.5]);
+        Some(Pixel {
+            column,
+            row,
+            model_x,
+            model_y,
+            value: self.geotiff.get_value_at_checked(column, row, self.band),
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.window[2] * self.window[3] - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<T: FromPrimitive + 'static> ExactSizeIterator for Pixels<'_, T> {}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::{CoordinateTransform, GeoTiff, GeoTiffWriter};
+
+    #[test]
+    fn test_pixels() {
+        let transform =
+            CoordinateTransform::from_geotransform([1000.0, 10.0, 0.0, 2000.0, 0.0, -10.0])
+                .unwrap();
+        let mut bytes = Vec::new();
+        GeoTiffWriter::new()
+            .with_coordinate_transform(transform)
+            .with_nodata(0.0)
+            .write(&mut bytes, 3, 2, 1, &[0u8, 1, 2, 3, 4, 5])
+            .unwrap();
+        let geotiff = GeoTiff::read(Cursor::new(bytes)).unwrap();
+
+        let pixels = geotiff.pixels::<u8>(0).unwrap();
+        assert_eq!(pixels.len(), 6);
+        let pixels = pixels.collect::<Vec<_>>();
+        assert_eq!(pixels[0].value, None);
+        let last = pixels[5];
+        assert_eq!((last.column, last.row), (2, 1));
+        assert_eq!([last.model_x, last.model_y], [1025.0, 1985.0]);
+        assert_eq!(last.value, Some(5));
+
+        let values = geotiff
+            .pixels_in_window::<f64>(1, 0, 2, 2, 0)
+            .unwrap()
+            .map(|pixel| (pixel.column, pixel.row, pixel.value))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            values,
+            [
+                (1, 0, Some(1.0)),
+                (2, 0, Some(2.0)),
+                (1, 1, Some(4.0)),
+                (2, 1, Some(5.0))
+            ]
+        );
+    }
+}
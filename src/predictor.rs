@@ -0,0 +1,181 @@
+/// Adds to each sample from `start` on the sample `pixel_size` bytes before it.
+macro_rules! accumulate {
+    ($type: ty, $row: expr, $start: expr, $pixel_size: expr) => {{
+        const SIZE: usize = std::mem::size_of::<$type>();
+        let sample = |row: &[u8], start: usize| {
+            <$type>::from_le_bytes(row[start..start + SIZE].try_into().unwrap())
+        };
+        for start in ($start..$row.len()).step_by(SIZE) {
+            let value = sample($row, start).wrapping_add(sample($row, start - $pixel_size));
+            $row[start..start + SIZE].copy_from_slice(&value.to_le_bytes());
+        }
+    }};
+}
+
+/// Reverses the horizontal predictor on a row of little-endian samples of `sample_size` bytes,
+/// adding to each sample the respective sample of the previous pixel of `pixel_size` bytes with
+/// wrapping arithmetic.
+///
+/// With `sample_size` 1, this also reverses the byte differencing of the floating point
+/// predictor. With the `simd` feature, rows of pixels of 1, 2, 4 or 8 bytes are processed 16
+/// bytes at a time on x86_64.
+pub(crate) fn undo_horizontal(row: &mut [u8], sample_size: usize, pixel_size: usize) {
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    let start = simd::undo_horizontal(row, sample_size, pixel_size);
+    #[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
+    let start = 0;
+
+    let start = start.max(pixel_size);
+    match sample_size {
+        1 => {
+            for index in start..row.len() {
+                row[index] = row[index].wrapping_add(row[index - pixel_size]);
+            }
+        }
+        2 => accumulate!(u16, row, start, pixel_size),
+        4 => accumulate!(u32, row, start, pixel_size),
+        8 => accumulate!(u64, row, start, pixel_size),
+        _ => unreachable!("Samples of {} bytes are not supported", sample_size),
+    }
+}
+
+/// Reverses the byte order of each sample of `sample_size` bytes, e.g. to convert samples of a
+/// big-endian file to little-endian.
+///
+/// With the `simd` feature, 16 bytes are swapped at a time on x86_64 processors supporting SSSE3.
+pub(crate) fn swap_bytes(row: &mut [u8], sample_size: usize) {
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    let start = simd::swap_bytes(row, sample_size);
+    #[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
+    let start = 0;
+
+    for sample in row[start..].chunks_exact_mut(sample_size) {
+        sample.reverse();
+    }
+}
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+mod simd {
+    use std::arch::x86_64::*;
+
+    /// Reverses the horizontal predictor on the leading 16-byte blocks of the row, returning the
+    /// number of bytes processed, which is 0 if the pixel size is not supported.
+    pub(super) fn undo_horizontal(row: &mut [u8], sample_size: usize, pixel_size: usize) -> usize {
+        if !matches!(pixel_size, 1 | 2 | 4 | 8) {
+            return 0;
+        }
+        // SAFETY: SSE2 is part of the x86_64 baseline, and the blocks lie within the row
+        unsafe {
+            let add = |a, b| match sample_size {
+                1 => _mm_add_epi8(a, b),
+                2 => _mm_add_epi16(a, b),
+                4 => _mm_add_epi32(a, b),
+                _ => _mm_add_epi64(a, b),
+            };
+            let mut carry = _mm_setzero_si128();
+            let blocks = row.len() / 16;
+            for block in 0..blocks {
+                let pointer = row.as_mut_ptr().add(block * 16) as *mut __m128i;
+                // The prefix sums of the samples of each position within a pixel, doubling the
+                // distance summed over with each step
+                let mut sum = _mm_loadu_si128(pointer);
+                if pixel_size == 1 {
+                    sum = add(sum, _mm_slli_si128::<1>(sum));
+                }
+                if pixel_size <= 2 {
+                    sum = add(sum, _mm_slli_si128::<2>(sum));
+                }
+                if pixel_size <= 4 {
+                    sum = add(sum, _mm_slli_si128::<4>(sum));
+                }
+                sum = add(add(sum, _mm_slli_si128::<8>(sum)), carry);
+                _mm_storeu_si128(pointer, sum);
+                // The last pixel, repeated across the register
+                carry = match pixel_size {
+                    1 => _mm_set1_epi8((_mm_extract_epi16::<7>(sum) >> 8) as i8),
+                    2 => _mm_set1_epi16(_mm_extract_epi16::<7>(sum) as i16),
+                    4 => _mm_shuffle_epi32::<0xff>(sum),
+                    _ => _mm_unpackhi_epi64(sum, sum),
+                };
+            }
+            blocks * 16
+        }
+    }
+
+    /// Reverses the byte order of the samples of the leading 16-byte blocks of the row, returning
+    /// the number of bytes processed, which is 0 if SSSE3 is not supported.
+    pub(super) fn swap_bytes(row: &mut [u8], sample_size: usize) -> usize {
+        if !is_x86_feature_detected!("ssse3") || !matches!(sample_size, 2 | 4 | 8) {
+            return 0;
+        }
+        // SAFETY: SSSE3 is supported, as checked above
+        unsafe { swap_bytes_ssse3(row, sample_size) }
+    }
+
+    #[target_feature(enable = "ssse3")]
+    unsafe fn swap_bytes_ssse3(row: &mut [u8], sample_size: usize) -> usize {
+        let shuffle = match sample_size {
+            2 => _mm_setr_epi8(1, 0, 3, 2, 5, 4, 7, 6, 9, 8, 11, 10, 13, 12, 15, 14),
+            4 => _mm_setr_epi8(3, 2, 1, 0, 7, 6, 5, 4, 11, 10, 9, 8, 15, 14, 13, 12),
+            _ => _mm_setr_epi8(7, 6, 5, 4, 3, 2, 1, 0, 15, 14, 13, 12, 11, 10, 9, 8),
+        };
+        let blocks = row.len() / 16;
+        for block in 0..blocks {
+            let pointer = row.as_mut_ptr().add(block * 16) as *mut __m128i;
+            _mm_storeu_si128(pointer, _mm_shuffle_epi8(_mm_loadu_si128(pointer), shuffle));
+        }
+        blocks * 16
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_undo_horizontal() {
+        let differences = (0..75u32)
+            .map(|value| (value * 37 % 256) as u8)
+            .collect::<Vec<_>>();
+        for sample_size in [1, 2, 4, 8] {
+            for samples_per_pixel in [1, 2, 3, 4] {
+                let pixel_size = sample_size * samples_per_pixel;
+                let len = differences.len() / pixel_size * pixel_size;
+                let mut row = differences[..len].to_vec();
+                undo_horizontal(&mut row, sample_size, pixel_size);
+
+                // Sums up the differences sample by sample, byte by byte with carry
+                let mut expected = differences[..len].to_vec();
+                for start in (pixel_size..len).step_by(sample_size) {
+                    let mut carry = 0;
+                    for byte in start..start + sample_size {
+                        let sum =
+                            expected[byte] as u16 + expected[byte - pixel_size] as u16 + carry;
+                        expected[byte] = sum as u8;
+                        carry = sum >> 8;
+                    }
+                }
+                assert_eq!(
+                    row, expected,
+                    "{} x {} bytes",
+                    samples_per_pixel, sample_size
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_swap_bytes() {
+        let bytes = (0..70).collect::<Vec<u8>>();
+        for sample_size in [2, 4, 8] {
+            let len = bytes.len() / sample_size * sample_size;
+            let mut row = bytes[..len].to_vec();
+            swap_bytes(&mut row, sample_size);
+            let expected = bytes[..len]
+                .chunks_exact(sample_size)
+                .flat_map(|sample| sample.iter().rev().copied())
+                .collect::<Vec<_>>();
+            assert_eq!(row, expected);
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use crate::geo_key_directory::{GeoKey, GeoKeyDirectory};
+
+/// A projection method as given by the ProjCoordTransGeoKey, see
+/// <http://geotiff.maptools.org/spec/geotiff6.html#6.3.3.3>.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum ProjectionMethod {
+    TransverseMercator,
+    TransverseMercatorModifiedAlaska,
+    ObliqueMercator,
+    ObliqueMercatorLaborde,
+    ObliqueMercatorRosenmund,
+    ObliqueMercatorSpherical,
+    Mercator,
+    LambertConformalConic2SP,
+    LambertConformalConic1SP,
+    LambertAzimuthalEqualArea,
+    AlbersEqualArea,
+    AzimuthalEquidistant,
+    EquidistantConic,
+    Stereographic,
+    PolarStereographic,
+    ObliqueStereographic,
+    Equirectangular,
+    CassiniSoldner,
+    Gnomonic,
+    MillerCylindrical,
+    Orthographic,
+    Polyconic,
+    Robinson,
+    Sinusoidal,
+    VanDerGrinten,
+    NewZealandMapGrid,
+    TransverseMercatorSouthOriented,
+    UserDefined,
+    Unknown(u16),
+}
+
+/// The projection of a user-defined projected CRS, as given by the ProjCoordTransGeoKey and the
+/// projection parameter GeoKeys.
+///
+/// Angles are given in the units of the GeogAngularUnitsGeoKey (the azimuth in the units of the
+/// GeogAzimuthUnitsGeoKey), lengths in the units of the ProjLinearUnitsGeoKey. Parameters missing
+/// from the GeoKey directory are `None`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProjectionDefinition {
+    pub method: ProjectionMethod,
+    pub std_parallel1: Option<f64>,
+    pub std_parallel2: Option<f64>,
+    pub nat_origin_long: Option<f64>,
+    pub nat_origin_lat: Option<f64>,
+    pub false_easting: Option<f64>,
+    pub false_northing: Option<f64>,
+    pub false_origin_long: Option<f64>,
+    pub false_origin_lat: Option<f64>,
+    pub false_origin_easting: Option<f64>,
+    pub false_origin_northing: Option<f64>,
+    pub center_long: Option<f64>,
+    pub center_lat: Option<f64>,
+    pub center_easting: Option<f64>,
+    pub center_northing: Option<f64>,
+    pub scale_at_nat_origin: Option<f64>,
+    pub scale_at_center: Option<f64>,
+    pub azimuth_angle: Option<f64>,
+    pub straight_vert_pole_long: Option<f64>,
+    pub rectified_grid_angle: Option<f64>,
+}
+
+impl ProjectionMethod {
+    pub fn from_u16(value: u16) -> Self {
+        use ProjectionMethod::*;
+        match value {
+            1 => TransverseMercator,
+            2 => TransverseMercatorModifiedAlaska,
+            3 => ObliqueMercator,
+            4 => ObliqueMercatorLaborde,
+            5 => ObliqueMercatorRosenmund,
+            6 => ObliqueMercatorSpherical,
+            7 => Mercator,
+            8 => LambertConformalConic2SP,
+            9 => LambertConformalConic1SP,
+            10 => LambertAzimuthalEqualArea,
+            11 => AlbersEqualArea,
+            12 => AzimuthalEquidistant,
+            13 => EquidistantConic,
+            14 => Stereographic,
+            15 => PolarStereographic,
+            16 => ObliqueStereographic,
+            17 => Equirectangular,
+            18 => CassiniSoldner,
+            19 => Gnomonic,
+            20 => MillerCylindrical,
+            21 => Orthographic,
+            22 => Polyconic,
+            23 => Robinson,
+            24 => Sinusoidal,
+            25 => VanDerGrinten,
+            26 => NewZealandMapGrid,
+            27 => TransverseMercatorSouthOriented,
+            GeoKeyDirectory::USER_DEFINED => UserDefined,
+            value => Unknown(value),
+        }
+    }
+
+    pub fn to_u16(&self) -> u16 {
+        use ProjectionMethod::*;
+        match self {
+            TransverseMercator => 1,
+            TransverseMercatorModifiedAlaska => 2,
+            ObliqueMercator => 3,
+            ObliqueMercatorLaborde => 4,
+            ObliqueMercatorRosenmund => 5,
+            ObliqueMercatorSpherical => 6,
+            Mercator => 7,
+            LambertConformalConic2SP => 8,
+            LambertConformalConic1SP => 9,
+            LambertAzimuthalEqualArea => 10,
+            AlbersEqualArea => 11,
+            AzimuthalEquidistant => 12,
+            EquidistantConic => 13,
+            Stereographic => 14,
+            PolarStereographic => 15,
+            ObliqueStereographic => 16,
+            Equirectangular => 17,
+            CassiniSoldner => 18,
+            Gnomonic => 19,
+            MillerCylindrical => 20,
+            Orthographic => 21,
+            Polyconic => 22,
+            Robinson => 23,
+            Sinusoidal => 24,
+            VanDerGrinten => 25,
+            NewZealandMapGrid => 26,
+            TransverseMercatorSouthOriented => 27,
+            UserDefined => GeoKeyDirectory::USER_DEFINED,
+            Unknown(value) => *value,
+        }
+    }
+}
+
+impl ProjectionDefinition {
+    /// Reads the projection from the GeoKeys, returning `None` if the ProjCoordTransGeoKey is
+    /// missing.
+    pub(crate) fn from_geo_keys(geo_keys: &GeoKeyDirectory) -> Option<Self> {
+        let get = |key: GeoKey| geo_keys.get_double(key);
+        Some(ProjectionDefinition {
+            method: ProjectionMethod::from_u16(geo_keys.proj_coord_trans()?),
+            std_parallel1: get(GeoKey::ProjStdParallel1),
+            std_parallel2: get(GeoKey::ProjStdParallel2),
+            nat_origin_long: get(GeoKey::ProjNatOriginLong),
+            nat_origin_lat: get(GeoKey::ProjNatOriginLat),
+            false_easting: get(GeoKey::ProjFalseEasting),
+            false_northing: get(GeoKey::ProjFalseNorthing),
+            false_origin_long: get(GeoKey::ProjFalseOriginLong),
+            false_origin_lat: get(GeoKey::ProjFalseOriginLat),
+            false_origin_easting: get(GeoKey::ProjFalseOriginEasting),
+            false_origin_northing: get(GeoKey::ProjFalseOriginNorthing),
+            center_long: get(GeoKey::ProjCenterLong),
+            center_lat: get(GeoKey::ProjCenterLat),
+            center_easting: get(GeoKey::ProjCenterEasting),
+            center_northing: get(GeoKey::ProjCenterNorthing),
+            scale_at_nat_origin: get(GeoKey::ProjScaleAtNatOrigin),
+            scale_at_center: get(GeoKey::ProjScaleAtCenter),
+            azimuth_angle: get(GeoKey::ProjAzimuthAngle),
+            straight_vert_pole_long: get(GeoKey::ProjStraightVertPoleLong),
+            rectified_grid_angle: get(GeoKey::ProjRectifiedGridAngle),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_geo_keys() {
+        let directory = GeoKeyDirectory::from_tag_data(
+            vec![
+                1, 1, 0, 6, //
+                3072, 0, 1, 32767, //
+                3075, 0, 1, 8, //
+                3078, 34736, 1, 0, //
+                3079, 34736, 1, 1, //
+                3084, 34736, 1, 3, //
+                3086, 34736, 1, 2, //
+            ],
+            Some(vec![49.0, 46.0, 700000.0, 3.0]),
+            None,
+        )
+        .unwrap();
+
+        let definition = ProjectionDefinition::from_geo_keys(&directory).unwrap();
+        assert_eq!(
+            definition.method,
+            ProjectionMethod::LambertConformalConic2SP
+        );
+        assert_eq!(definition.std_parallel1, Some(49.0));
+        assert_eq!(definition.std_parallel2, Some(46.0));
+        assert_eq!(definition.false_origin_easting, Some(700000.0));
+        assert_eq!(definition.false_origin_long, Some(3.0));
+        assert_eq!(definition.false_origin_lat, None);
+        assert_eq!(definition.scale_at_nat_origin, None);
+
+        assert_eq!(
+            ProjectionDefinition::from_geo_keys(&GeoKeyDirectory::default()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_method_codes() {
+        for code in 1..=32767 {
+            assert_eq!(ProjectionMethod::from_u16(code).to_u16(), code);
+        }
+        assert_eq!(
+            ProjectionMethod::from_u16(28),
+            ProjectionMethod::Unknown(28)
+        );
+    }
+}
@@ -1,6 +1,13 @@
 use std::fmt;
 use std::fmt::{Debug, Formatter};
 
+use num_traits::{FromPrimitive, ToPrimitive};
+use tiff::decoder::{DecodingBuffer, DecodingResult};
+use tiff::tags::SampleFormat;
+
+use crate::Resampling;
+
+#[derive(Clone)]
 pub(super) enum RasterData {
     U8(Vec<u8>),
     U16(Vec<u16>),
@@ -18,25 +25,265 @@ impl Debug for RasterData {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         f.write_fmt(format_args!(
             "RasterData {{ type: {}, len: {} }}",
-            match self {
-                RasterData::U8(_) => "u8",
-                RasterData::U16(_) => "u16",
-                RasterData::U32(_) => "u32",
-                RasterData::U64(_) => "u64",
-                RasterData::F32(_) => "f32",
-                RasterData::F64(_) => "f64",
-                RasterData::I8(_) => "i8",
-                RasterData::I16(_) => "i16",
-                RasterData::I32(_) => "i32",
-                RasterData::I64(_) => "i64",
-            },
+            self.type_name(),
             self.len()
         ))
     }
 }
 
+impl From<DecodingResult> for RasterData {
+    fn from(result: DecodingResult) -> Self {
+        match result {
+            DecodingResult::U8(data) => RasterData::U8(data),
+            DecodingResult::U16(data) => RasterData::U16(data),
+            DecodingResult::U32(data) => RasterData::U32(data),
+            DecodingResult::U64(data) => RasterData::U64(data),
+            DecodingResult::F32(data) => RasterData::F32(data),
+            DecodingResult::F64(data) => RasterData::F64(data),
+            DecodingResult::I8(data) => RasterData::I8(data),
+            DecodingResult::I16(data) => RasterData::I16(data),
+            DecodingResult::I32(data) => RasterData::I32(data),
+            DecodingResult::I64(data) => RasterData::I64(data),
+        }
+    }
+}
+
+/// Applies an expression to the data of two rasters of the same sample type.
+macro_rules! zip_raster_data {
+    ($target: expr, $source: expr, |$target_data: ident, $source_data: ident| $body: expr) => {
+        match ($target, $source) {
+            (RasterData::U8($target_data), RasterData::U8($source_data)) => $body,
+            (RasterData::U16($target_data), RasterData::U16($source_data)) => $body,
+            (RasterData::U32($target_data), RasterData::U32($source_data)) => $body,
+            (RasterData::U64($target_data), RasterData::U64($source_data)) => $body,
+            (RasterData::F32($target_data), RasterData::F32($source_data)) => $body,
+            (RasterData::F64($target_data), RasterData::F64($source_data)) => $body,
+            (RasterData::I8($target_data), RasterData::I8($source_data)) => $body,
+            (RasterData::I16($target_data), RasterData::I16($source_data)) => $body,
+            (RasterData::I32($target_data), RasterData::I32($source_data)) => $body,
+            (RasterData::I64($target_data), RasterData::I64($source_data)) => $body,
+            _ => unreachable!("The chunks of an image have the same sample type"),
+        }
+    };
+}
+
+/// Applies an expression to the data of a raster, yielding a raster of the same sample type.
+macro_rules! map_raster_data {
+    ($source: expr, |$data: ident| $body: expr) => {
+        match $source {
+            RasterData::U8($data) => RasterData::U8($body),
+            RasterData::U16($data) => RasterData::U16($body),
+            RasterData::U32($data) => RasterData::U32($body),
+            RasterData::U64($data) => RasterData::U64($body),
+            RasterData::F32($data) => RasterData::F32($body),
+            RasterData::F64($data) => RasterData::F64($body),
+            RasterData::I8($data) => RasterData::I8($body),
+            RasterData::I16($data) => RasterData::I16($body),
+            RasterData::I32($data) => RasterData::I32($body),
+            RasterData::I64($data) => RasterData::I64($body),
+        }
+    };
+}
+
+/// The arrangement of a rectangle of samples within a raster stored row by row.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct RowLayout {
+    /// The index of the first sample of the rectangle
+    pub(super) start: usize,
+    /// The number of samples between the starts of consecutive rows
+    pub(super) stride: usize,
+}
+
 impl RasterData {
-    fn len(&self) -> usize {
+    /// Parses a single sample of the given format and size from text like the GDAL_NODATA tag,
+    /// e.g. "-9999", "1e+20" or "nan". Returns `None` if the text is not a number representable
+    /// by the sample type.
+    pub(super) fn parse_sample(
+        text: &str,
+        sample_format: SampleFormat,
+        bits_per_sample: u16,
+    ) -> Option<Self> {
+        let text = text.trim();
+        let value = text.parse::<f64>().ok()?;
+        // Integers are parsed exactly, as large 64-bit integers are not representable as f64
+        let integer = text
+            .parse::<i128>()
+            .ok()
+            .or_else(|| (value.fract() == 0.0).then_some(value as i128));
+        Some(match (sample_format, bits_per_sample) {
+            // Samples of other sizes are unpacked into the next larger type
+            (SampleFormat::Uint, 1..=8) => RasterData::U8(vec![integer?.try_into().ok()?]),
+            (SampleFormat::Uint, 9..=16) => RasterData::U16(vec![integer?.try_into().ok()?]),
+            (SampleFormat::Uint, 17..=32) => RasterData::U32(vec![integer?.try_into().ok()?]),
+            (SampleFormat::Uint, 33..=64) => RasterData::U64(vec![integer?.try_into().ok()?]),
+            (SampleFormat::Int, 8) => RasterData::I8(vec![integer?.try_into().ok()?]),
+            (SampleFormat::Int, 16) => RasterData::I16(vec![integer?.try_into().ok()?]),
+            (SampleFormat::Int, 32) => RasterData::I32(vec![integer?.try_into().ok()?]),
+            (SampleFormat::Int, 64) => RasterData::I64(vec![integer?.try_into().ok()?]),
+            (SampleFormat::IEEEFP, 32) => RasterData::F32(vec![value as f32]),
+            (SampleFormat::IEEEFP, 64) => RasterData::F64(vec![value]),
+            _ => return None,
+        })
+    }
+
+    /// Whether the sample with the given index equals the first sample of `value`, which is of
+    /// the same type, like a nodata value. NaN equals NaN, as it commonly marks nodata.
+    pub(super) fn sample_equals(&self, index: usize, value: &RasterData) -> bool {
+        match (self, value) {
+            (RasterData::U8(data), RasterData::U8(value)) => data[index] == value[0],
+            (RasterData::U16(data), RasterData::U16(value)) => data[index] == value[0],
+            (RasterData::U32(data), RasterData::U32(value)) => data[index] == value[0],
+            (RasterData::U64(data), RasterData::U64(value)) => data[index] == value[0],
+            (RasterData::F32(data), RasterData::F32(value)) => {
+                data[index] == value[0] || data[index].is_nan() && value[0].is_nan()
+            }
+            (RasterData::F64(data), RasterData::F64(value)) => {
+                data[index] == value[0] || data[index].is_nan() && value[0].is_nan()
+            }
+            (RasterData::I8(data), RasterData::I8(value)) => data[index] == value[0],
+            (RasterData::I16(data), RasterData::I16(value)) => data[index] == value[0],
+            (RasterData::I32(data), RasterData::I32(value)) => data[index] == value[0],
+            (RasterData::I64(data), RasterData::I64(value)) => data[index] == value[0],
+            _ => false,
+        }
+    }
+
+    /// Creates a raster of `len` zero samples of the same type as `self`.
+    pub(super) fn zeros_like(&self, len: usize) -> Self {
+        match self {
+            RasterData::U8(_) => RasterData::U8(vec![0; len]),
+            RasterData::U16(_) => RasterData::U16(vec![0; len]),
+            RasterData::U32(_) => RasterData::U32(vec![0; len]),
+            RasterData::U64(_) => RasterData::U64(vec![0; len]),
+            RasterData::F32(_) => RasterData::F32(vec![0.0; len]),
+            RasterData::F64(_) => RasterData::F64(vec![0.0; len]),
+            RasterData::I8(_) => RasterData::I8(vec![0; len]),
+            RasterData::I16(_) => RasterData::I16(vec![0; len]),
+            RasterData::I32(_) => RasterData::I32(vec![0; len]),
+            RasterData::I64(_) => RasterData::I64(vec![0; len]),
+        }
+    }
+
+    /// Creates a raster of `len` copies of the first sample of `self`, e.g. the nodata value.
+    pub(super) fn filled(&self, len: usize) -> Self {
+        map_raster_data!(self, |data| vec![data[0]; len])
+    }
+
+    /// Copies `num_rows` rows of `row_length` samples from `source` to `self`.
+    pub(super) fn copy_rows(
+        &mut self,
+        target_layout: RowLayout,
+        source: &RasterData,
+        source_layout: RowLayout,
+        row_length: usize,
+        num_rows: usize,
+    ) {
+        zip_raster_data!(self, source, |target_data, source_data| {
+            for row in 0..num_rows {
+                let target_start = target_layout.start + row * target_layout.stride;
+                let source_start = source_layout.start + row * source_layout.stride;
+                target_data[target_start..target_start + row_length]
+                    .copy_from_slice(&source_data[source_start..source_start + row_length]);
+            }
+        })
+    }
+
+    /// Copies one sample of each pixel of `num_rows` rows of `row_length` pixels from `source` to
+    /// `self`, the layouts giving the index of the sample of the first pixel and the pixels having
+    /// `target_samples` and `source_samples` samples, respectively.
+    pub(super) fn copy_band(
+        &mut self,
+        target_layout: RowLayout,
+        target_samples: usize,
+        source: &RasterData,
+        source_layout: RowLayout,
+        source_samples: usize,
+        [row_length, num_rows]: [usize; 2],
+    ) {
+        zip_raster_data!(self, source, |target_data, source_data| {
+            for row in 0..num_rows {
+                let target_start = target_layout.start + row * target_layout.stride;
+                let source_start = source_layout.start + row * source_layout.stride;
+                for pixel in 0..row_length {
+                    target_data[target_start + pixel * target_samples] =
+                        source_data[source_start + pixel * source_samples];
+                }
+            }
+        })
+    }
+
+    /// Resamples a raster of `width * height` pixels to `target_width * target_height` pixels,
+    /// taking the pixel nearest to the center of each target pixel.
+    pub(super) fn resample_nearest(
+        &self,
+        size: [usize; 2],
+        num_samples: usize,
+        target_size: [usize; 2],
+    ) -> Self {
+        map_raster_data!(self, |data| resample_nearest(
+            data,
+            size,
+            num_samples,
+            target_size
+        ))
+    }
+
+    /// Resamples a raster of `width * height` pixels to `target_width * target_height` pixels
+    /// covering the given areas of it, see [`resample_areas`].
+    pub(super) fn resample_areas(
+        &self,
+        size: [usize; 2],
+        num_samples: usize,
+        valid: &[bool],
+        areas: PixelAreas,
+        target_size: [usize; 2],
+        resampling: Resampling,
+    ) -> Self {
+        map_raster_data!(self, |data| resample_areas(
+            data,
+            size,
+            num_samples,
+            valid,
+            areas,
+            target_size,
+            resampling
+        ))
+    }
+
+    /// The name of the Rust type of the samples, e.g. "u16".
+    pub(super) fn type_name(&self) -> &'static str {
+        match self {
+            RasterData::U8(_) => "u8",
+            RasterData::U16(_) => "u16",
+            RasterData::U32(_) => "u32",
+            RasterData::U64(_) => "u64",
+            RasterData::F32(_) => "f32",
+            RasterData::F64(_) => "f64",
+            RasterData::I8(_) => "i8",
+            RasterData::I16(_) => "i16",
+            RasterData::I32(_) => "i32",
+            RasterData::I64(_) => "i64",
+        }
+    }
+
+    /// Whether the samples are of type `T`.
+    pub(super) fn is_of_type<T: Sample>(&self) -> bool {
+        let (sample_format, bits_per_sample) = match self {
+            RasterData::U8(_) => (SampleFormat::Uint, 8),
+            RasterData::U16(_) => (SampleFormat::Uint, 16),
+            RasterData::U32(_) => (SampleFormat::Uint, 32),
+            RasterData::U64(_) => (SampleFormat::Uint, 64),
+            RasterData::F32(_) => (SampleFormat::IEEEFP, 32),
+            RasterData::F64(_) => (SampleFormat::IEEEFP, 64),
+            RasterData::I8(_) => (SampleFormat::Int, 8),
+            RasterData::I16(_) => (SampleFormat::Int, 16),
+            RasterData::I32(_) => (SampleFormat::Int, 32),
+            RasterData::I64(_) => (SampleFormat::Int, 64),
+        };
+        sample_format == T::SAMPLE_FORMAT && bits_per_sample == T::BITS_PER_SAMPLE
+    }
+
+    pub(super) fn len(&self) -> usize {
         match self {
             RasterData::U8(data) => data.len(),
             RasterData::U16(data) => data.len(),
@@ -50,4 +297,197 @@ impl RasterData {
             RasterData::I64(data) => data.len(),
         }
     }
+
+    /// The size of the samples in bytes.
+    pub(super) fn byte_len(&self) -> usize {
+        match self {
+            RasterData::U8(data) => size_of_val(data.as_slice()),
+            RasterData::U16(data) => size_of_val(data.as_slice()),
+            RasterData::U32(data) => size_of_val(data.as_slice()),
+            RasterData::U64(data) => size_of_val(data.as_slice()),
+            RasterData::F32(data) => size_of_val(data.as_slice()),
+            RasterData::F64(data) => size_of_val(data.as_slice()),
+            RasterData::I8(data) => size_of_val(data.as_slice()),
+            RasterData::I16(data) => size_of_val(data.as_slice()),
+            RasterData::I32(data) => size_of_val(data.as_slice()),
+            RasterData::I64(data) => size_of_val(data.as_slice()),
+        }
+    }
+}
+
+/// The order of the samples of a multi-band raster in memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    /// The samples of each pixel are adjacent, pixel by pixel and row by row, e.g. RGBRGB, like
+    /// chunky TIFFs and most graphics libraries store them
+    PixelInterleaved,
+    /// The raster of each band is stored band by band, each row by row, e.g. RRGGBB, like planar
+    /// TIFFs store them and machine learning frameworks expect them as channels-first tensors
+    BandSequential,
+}
+
+/// A primitive type of raster samples that can be written to a GeoTIFF.
+pub trait Sample: Copy + ToPrimitive + FromPrimitive {
+    /// The SampleFormat of the type
+    const SAMPLE_FORMAT: SampleFormat;
+    /// The BitsPerSample of the type
+    const BITS_PER_SAMPLE: u16;
+
+    /// Appends the little-endian representation of the sample to `bytes`.
+    fn write_le_bytes(self, bytes: &mut Vec<u8>);
+
+    /// The samples as a buffer for the decoder to decode into.
+    fn decoding_buffer(samples: &mut [Self]) -> DecodingBuffer<'_>;
+}
+
+macro_rules! impl_sample {
+    ($($type: ty => $sample_format: ident, $variant: ident),*) => {
+        $(
+            impl Sample for $type {
+                const SAMPLE_FORMAT: SampleFormat = SampleFormat::$sample_format;
+                const BITS_PER_SAMPLE: u16 = <$type>::BITS as u16;
+
+                fn write_le_bytes(self, bytes: &mut Vec<u8>) {
+                    bytes.extend_from_slice(&self.to_le_bytes());
+                }
+
+                fn decoding_buffer(samples: &mut [Self]) -> DecodingBuffer<'_> {
+                    DecodingBuffer::$variant(samples)
+                }
+            }
+        )*
+    };
+}
+
+impl_sample!(
+    u8 => Uint, U8, u16 => Uint, U16, u32 => Uint, U32, u64 => Uint, U64,
+    i8 => Int, I8, i16 => Int, I16, i32 => Int, I32, i64 => Int, I64
+);
+
+impl Sample for f32 {
+    const SAMPLE_FORMAT: SampleFormat = SampleFormat::IEEEFP;
+    const BITS_PER_SAMPLE: u16 = 32;
+
+    fn write_le_bytes(self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.to_le_bytes());
+    }
+
+    fn decoding_buffer(samples: &mut [Self]) -> DecodingBuffer<'_> {
+        DecodingBuffer::F32(samples)
+    }
+}
+
+impl Sample for f64 {
+    const SAMPLE_FORMAT: SampleFormat = SampleFormat::IEEEFP;
+    const BITS_PER_SAMPLE: u16 = 64;
+
+    fn write_le_bytes(self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.to_le_bytes());
+    }
+
+    fn decoding_buffer(samples: &mut [Self]) -> DecodingBuffer<'_> {
+        DecodingBuffer::F64(samples)
+    }
+}
+
+/// Resamples pixels of `num_samples` values each, row by row, by nearest neighbour.
+pub(super) fn resample_nearest<T: Copy>(
+    data: &[T],
+    [width, height]: [usize; 2],
+    num_samples: usize,
+    [target_width, target_height]: [usize; 2],
+) -> Vec<T> {
+    let source_index = |target: usize, target_length: usize, length: usize| {
+        (((target as f64 + 0.5) * length as f64 / target_length as f64) as usize).min(length - 1)
+    };
+    let mut resampled = Vec::with_capacity(target_width * target_height * num_samples);
+    for y in 0..target_height {
+        let source_y = source_index(y, target_height, height);
+        for x in 0..target_width {
+            let start = (source_y * width + source_index(x, target_width, width)) * num_samples;
+            resampled.extend_from_slice(&data[start..start + num_samples]);
+        }
+    }
+    resampled
+}
+
+/// The rectangles of fractional source pixels covered by the pixels of a resampled raster.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct PixelAreas {
+    /// The source coordinate (column, row) of the upper left corner of the first target pixel
+    pub origin: [f64; 2],
+    /// The width and height of a target pixel in source pixels
+    pub step: [f64; 2],
+}
+
+/// Resamples pixels of `num_samples` values each, row by row, such that each target pixel covers
+/// the given area of source pixels.
+///
+/// Each target sample is the sample of the source pixel nearest to the center of the area or the
+/// average of the valid samples weighted by the covered part of their pixels, rounded for
+/// integers. Samples not marked as valid and NaN are left out of the average, and the nearest
+/// sample is taken if none remains.
+pub(super) fn resample_areas<T: Sample>(
+    data: &[T],
+    [width, height]: [usize; 2],
+    num_samples: usize,
+    valid: &[bool],
+    PixelAreas { origin, step }: PixelAreas,
+    [target_width, target_height]: [usize; 2],
+    resampling: Resampling,
+) -> Vec<T> {
+    // The source pixels overlapping a target pixel along an axis with the overlapping lengths
+    let overlaps = |target: usize, axis: usize, length: usize| {
+        let start = origin[axis] + target as f64 * step[axis];
+        let end = start + step[axis];
+        let first = (start.floor().max(0.0) as usize).min(length - 1);
+        let last = (end.ceil() as usize).clamp(first + 1, length);
+        (first..last).map(move |source| {
+            let overlap = end.min(source as f64 + 1.0) - start.max(source as f64);
+            (source, overlap.max(0.0))
+        })
+    };
+    let nearest = |target: usize, axis: usize, length: usize| {
+        let center = origin[axis] + (target as f64 + 0.5) * step[axis];
+        (center.floor().max(0.0) as usize).min(length - 1)
+    };
+
+    let mut resampled = Vec::with_capacity(target_width * target_height * num_samples);
+    for y in 0..target_height {
+        let source_y = nearest(y, 1, height);
+        for x in 0..target_width {
+            let start = (source_y * width + nearest(x, 0, width)) * num_samples;
+            match resampling {
+                Resampling::Average => {
+                    for sample in 0..num_samples {
+                        let (mut sum, mut weights) = (0.0, 0.0);
+                        for (source_y, weight_y) in overlaps(y, 1, height) {
+                            for (source_x, weight_x) in overlaps(x, 0, width) {
+                                let index = (source_y * width + source_x) * num_samples + sample;
+                                let value = data[index].to_f64().unwrap();
+                                if valid[index] && !value.is_nan() {
+                                    sum += weight_x * weight_y * value;
+                                    weights += weight_x * weight_y;
+                                }
+                            }
+                        }
+                        let average = match T::SAMPLE_FORMAT {
+                            SampleFormat::IEEEFP => sum / weights,
+                            _ => (sum / weights).round(),
+                        };
+                        resampled.push(
+                            (weights > 0.0)
+                                .then_some(average)
+                                .and_then(T::from_f64)
+                                .unwrap_or(data[start + sample]),
+                        );
+                    }
+                }
+                Resampling::Nearest => {
+                    resampled.extend_from_slice(&data[start..start + num_samples])
+                }
+            }
+        }
+    }
+    resampled
 }
@@ -0,0 +1,2550 @@
+use std::any::{type_name, Any};
+use std::io::{Cursor, Read, Seek};
+use std::ops::Range;
+
+use num_traits::FromPrimitive;
+use tiff::decoder::{ChunkType, Decoder};
+use tiff::tags::{CompressionMethod, PlanarConfiguration, Predictor, SampleFormat, Tag};
+
+use crate::block_cache::BlockCache;
+use crate::chunk::{self, BandDecoder, RawChunks};
+use crate::interpolation::{self, Interpolation};
+use crate::raster_data::PixelAreas;
+use crate::statistics::{HistogramAccumulator, StatisticsAccumulator};
+use crate::{
+    mask, BlockCacheCapacity, Extent, GeoTiff, GeoTiffError, GeoTiffResult, Layout, Resampling,
+    Sample, Statistics,
+};
+
+/// The bit of the NewSubfileType marking reduced-resolution images.
+pub(crate) const REDUCED_RESOLUTION: u32 = 1;
+/// The bit of the NewSubfileType marking transparency masks.
+pub(crate) const TRANSPARENCY_MASK: u32 = 4;
+/// The minimum number of pixels of the overview approximate statistics are computed from, as
+/// GDAL_STAT_APPROX_NUMSAMPLES of GDAL
+const APPROXIMATE_MIN_PIXELS: usize = 2500;
+
+/// How strictly the reader enforces the GeoTIFF specification when reading the metadata.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Compliance {
+    /// Fails on any violation, e.g. conflicting georeferencing tags or malformed GeoKeys
+    #[default]
+    Strict,
+    /// Recovers from common violations found in real-world files, e.g. by preferring the
+    /// ModelTiepointTag and ModelPixelScaleTag over a ModelTransformationTag present as well, as
+    /// GDAL does, or by ignoring malformed GeoKeys, recording a warning for each, see
+    /// [`GeoTiff::warnings`]
+    Lenient,
+}
+
+/// How the edges of a bounding box in model space are snapped to the pixel grid when computing
+/// the window covering it, see `GeoTiffReader::window_from_geometry`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WindowSnap {
+    /// Includes all pixels intersecting the bounding box, like [`GeoTiffReader::read_bbox`]
+    #[default]
+    Outward,
+    /// Rounds the edges to the nearest pixel boundary, including the pixels mostly within the
+    /// bounding box
+    Nearest,
+    /// Includes only the pixels lying entirely within the bounding box
+    Inward,
+}
+
+impl Compliance {
+    /// Returns the value, or `None` with a warning in lenient mode if the metadata violates the
+    /// GeoTIFF specification.
+    pub(crate) fn recover<T>(
+        self,
+        result: GeoTiffResult<T>,
+        warnings: &mut Vec<String>,
+    ) -> GeoTiffResult<Option<T>> {
+        match (self, result) {
+            (Compliance::Lenient, Err(GeoTiffError::GeoKey(message))) => {
+                warnings.push(message);
+                Ok(None)
+            }
+            (_, result) => result.map(Some),
+        }
+    }
+}
+
+/// A reader decoding the raster data of a GeoTIFF on demand, e.g. window by window or tile by
+/// tile, so that only the requested parts of large rasters are held in memory.
+///
+/// The metadata is read once when creating the reader. Each read returns a [`GeoTiff`] covering
+/// just the requested part of the raster, with its coordinate transformation adjusted accordingly.
+pub struct GeoTiffReader<R: Read + Seek> {
+    decoder: Decoder<R>,
+    /// The metadata of the selected image or overview without any raster data
+    metadata: GeoTiff,
+    images: Vec<Image>,
+    selected_image: usize,
+    selected_overview: Option<usize>,
+    apply_scale_offset: bool,
+    /// The buffer of the strips or tiles partly within the windows read by
+    /// [`GeoTiffReader::read_window_into`], kept as `Vec<T>` to be reused across calls
+    scratch: Option<Box<dyn Any + Send>>,
+    /// The decoded strips or tiles shared across reads, see [`GeoTiffReader::set_block_cache`]
+    block_cache: Option<BlockCache>,
+}
+
+/// A top-level image of the file along with its overviews.
+struct Image {
+    /// The index of the IFD in the IFD chain
+    ifd_index: usize,
+    /// The metadata of the full-resolution image
+    metadata: GeoTiff,
+    overviews: Vec<Overview>,
+    /// The index of the IFD of the transparency mask, if any
+    mask_ifd_index: Option<usize>,
+}
+
+/// A reduced-resolution overview of the raster stored in the same file, as listed by
+/// [`GeoTiffReader::overviews`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Overview {
+    pub raster_width: usize,
+    pub raster_height: usize,
+    /// The ratio of the full-resolution raster size to the overview size in X and Y direction,
+    /// e.g. 2 for an overview of half the width and height
+    pub scale: [f64; 2],
+    /// The index of the IFD in the IFD chain
+    ifd_index: usize,
+    /// The index of the IFD of the transparency mask of the overview, if any
+    mask_ifd_index: Option<usize>,
+}
+
+impl<'a> GeoTiffReader<Cursor<&'a [u8]>> {
+    /// Creates a reader of a GeoTIFF held in memory, see [`GeoTiff::from_bytes`].
+    pub fn from_bytes(bytes: &'a [u8]) -> GeoTiffResult<Self> {
+        Self::new(Cursor::new(bytes))
+    }
+}
+
+impl<R: Read + Seek> GeoTiffReader<R> {
+    /// Creates a reader of any source implementing [`Read`] and [`Seek`], reading the metadata of
+    /// all images while deferring the raster data to the read methods.
+    pub fn new(reader: R) -> GeoTiffResult<Self> {
+        Self::with_compliance(reader, Compliance::Strict)
+    }
+
+    /// Creates a reader enforcing the GeoTIFF specification as strictly as given when reading the
+    /// metadata of the images.
+    pub fn with_compliance(reader: R, compliance: Compliance) -> GeoTiffResult<Self> {
+        let mut decoder = Decoder::new(reader)?;
+        let images = Self::read_images(&mut decoder, compliance)?;
+        Ok(GeoTiffReader {
+            decoder,
+            metadata: images[0].metadata.clone(),
+            images,
+            selected_image: 0,
+            selected_overview: None,
+            apply_scale_offset: false,
+            scratch: None,
+            block_cache: None,
+        })
+    }
+
+    /// Reads the metadata of all images in the IFD chain, assigning the reduced-resolution images
+    /// (NewSubfileType 1) to the preceding top-level image as its overviews and the transparency
+    /// masks (NewSubfileType 4 or 5) to the preceding image or overview of the same size, then
+    /// returns to the first image.
+    fn read_images(decoder: &mut Decoder<R>, compliance: Compliance) -> GeoTiffResult<Vec<Image>> {
+        let mut images = vec![Image {
+            ifd_index: 0,
+            metadata: GeoTiff::read_metadata(decoder, compliance)?,
+            overviews: Vec::new(),
+            mask_ifd_index: None,
+        }];
+        let mut ifd_index = 0;
+        while decoder.more_images() {
+            decoder.next_image()?;
+            ifd_index += 1;
+            let subfile_type = decoder
+                .find_tag_unsigned::<u32>(Tag::NewSubfileType)?
+                .unwrap_or(0);
+            match subfile_type & (REDUCED_RESOLUTION | TRANSPARENCY_MASK) {
+                0 => images.push(Image {
+                    ifd_index,
+                    metadata: GeoTiff::read_metadata(decoder, compliance)?,
+                    overviews: Vec::new(),
+                    mask_ifd_index: None,
+                }),
+                REDUCED_RESOLUTION => {
+                    let (width, height) = decoder.dimensions()?;
+                    let (raster_width, raster_height) = (width as usize, height as usize);
+                    let image = images.last_mut().expect("the first image is top-level");
+                    image.overviews.push(Overview {
+                        raster_width,
+                        raster_height,
+                        scale: [
+                            image.metadata.raster_width as f64 / raster_width as f64,
+                            image.metadata.raster_height as f64 / raster_height as f64,
+                        ],
+                        ifd_index,
+                        mask_ifd_index: None,
+                    });
+                }
+                TRANSPARENCY_MASK => {
+                    let (width, height) = decoder.dimensions()?;
+                    let image = images.last_mut().expect("the first image is top-level");
+                    if (image.metadata.raster_width, image.metadata.raster_height)
+                        == (width as usize, height as usize)
+                    {
+                        image.mask_ifd_index.get_or_insert(ifd_index);
+                    }
+                }
+                _ => {
+                    let (width, height) = decoder.dimensions()?;
+                    let image = images.last_mut().expect("the first image is top-level");
+                    if let Some(overview) = image.overviews.iter_mut().rev().find(|overview| {
+                        (overview.raster_width, overview.raster_height)
+                            == (width as usize, height as usize)
+                    }) {
+                        overview.mask_ifd_index.get_or_insert(ifd_index);
+                    }
+                }
+            }
+        }
+        if ifd_index > 0 {
+            decoder.seek_to_image(0)?;
+        }
+        Ok(images)
+    }
+
+    /// The number of top-level images in the file, e.g. of different variables or dates, not
+    /// counting their overviews.
+    pub fn image_count(&self) -> usize {
+        self.images.len()
+    }
+
+    /// The metadata of the top-level image with the given index, i.e. its dimensions and
+    /// georeferencing, without any raster data. Returns `None` if the image does not exist.
+    pub fn image_metadata(&self, index: usize) -> Option<&GeoTiff> {
+        self.images.get(index).map(|image| &image.metadata)
+    }
+
+    /// The index of the top-level image subsequent reads refer to.
+    pub fn selected_image(&self) -> usize {
+        self.selected_image
+    }
+
+    /// Selects the top-level image with the given index, at full resolution, for all subsequent
+    /// reads.
+    ///
+    /// The image is read as an independent GeoTIFF with its own georeferencing, and
+    /// [`GeoTiffReader::overviews`] lists its overviews. Fails if the image does not exist.
+    pub fn select_image(&mut self, index: usize) -> GeoTiffResult<()> {
+        let image = self.images.get(index).ok_or_else(|| {
+            GeoTiffError::OutOfBounds(format!(
+                "Image {} exceeds the {} images of the file",
+                index,
+                self.images.len()
+            ))
+        })?;
+        self.decoder.seek_to_image(image.ifd_index)?;
+        self.metadata = image.metadata.clone();
+        self.selected_image = index;
+        self.selected_overview = None;
+        Ok(())
+    }
+
+    /// The reduced-resolution overviews of the selected image stored in the file, usually from
+    /// the largest to the smallest.
+    pub fn overviews(&self) -> &[Overview] {
+        &self.images[self.selected_image].overviews
+    }
+
+    /// The index of the overview subsequent reads refer to, or `None` for the full-resolution
+    /// raster.
+    pub fn selected_overview(&self) -> Option<usize> {
+        self.selected_overview
+    }
+
+    /// Selects the overview with the given index in [`GeoTiffReader::overviews`], or the
+    /// full-resolution raster for `None`, for all subsequent reads.
+    ///
+    /// The raster size, the strips or tiles and all reads then refer to the overview, whose
+    /// coordinate transformation is derived from the full-resolution raster. Fails if the
+    /// overview does not exist.
+    pub fn select_overview(&mut self, overview: Option<usize>) -> GeoTiffResult<()> {
+        let image = &self.images[self.selected_image];
+        let (ifd_index, metadata) = match overview {
+            None => (image.ifd_index, image.metadata.clone()),
+            Some(index) => {
+                let overview = image.overviews.get(index).ok_or_else(|| {
+                    GeoTiffError::OutOfBounds(format!(
+                        "Overview {} exceeds the {} overviews of the raster",
+                        index,
+                        image.overviews.len()
+                    ))
+                })?;
+                (
+                    overview.ifd_index,
+                    image
+                        .metadata
+                        .overview_metadata(overview.raster_width, overview.raster_height),
+                )
+            }
+        };
+        self.decoder.seek_to_image(ifd_index)?;
+        self.metadata = metadata;
+        self.selected_overview = overview;
+        Ok(())
+    }
+
+    /// The metadata of the selected image or overview, without raster data.
+    #[cfg_attr(not(feature = "proj"), allow(dead_code))]
+    pub(crate) fn metadata(&self) -> &GeoTiff {
+        &self.metadata
+    }
+
+    pub fn raster_width(&self) -> usize {
+        self.metadata.raster_width
+    }
+
+    pub fn raster_height(&self) -> usize {
+        self.metadata.raster_height
+    }
+
+    pub fn num_samples(&self) -> usize {
+        self.metadata.num_samples
+    }
+
+    /// Sets whether reads return physical values, i.e. the raster data converted by the scale
+    /// and offset of each band, see [`GeoTiff::apply_scale_offset`].
+    pub fn set_apply_scale_offset(&mut self, apply_scale_offset: bool) {
+        self.apply_scale_offset = apply_scale_offset;
+    }
+
+    /// Sets the capacity of a cache of decoded strips or tiles shared across reads, so that
+    /// repeated reads of windows or samples of the same region decode each strip or tile only
+    /// once, or disables the cache if `None`. The strips or tiles used least recently are evicted
+    /// once the capacity is exceeded. Any cached strips or tiles are discarded.
+    ///
+    /// With a cache, [`GeoTiffReader::read_window_into`] decodes the strips or tiles into the
+    /// cache instead of directly into the buffer. Whole rasters read by [`GeoTiffReader::read`]
+    /// and transparency masks are not cached.
+    pub fn set_block_cache(&mut self, capacity: Option<BlockCacheCapacity>) {
+        self.block_cache = capacity.map(BlockCache::new);
+    }
+
+    /// Whether the selected image or overview has a transparency mask, which is then read along
+    /// with the raster data, see [`GeoTiff::mask`].
+    pub fn has_mask(&self) -> bool {
+        self.ifd_indices().1.is_some()
+    }
+
+    /// Whether the decoder decodes whole pixels of the raster, unlike those of planar rasters,
+    /// rasters of many bands, ZSTD-compressed rasters and samples of less than 8 bits.
+    fn decoder_reads_pixels(&mut self) -> GeoTiffResult<bool> {
+        // The decoder reads only the first band of planar rasters, and fails on sparse chunks
+        Ok(!(self.is_planar()?
+            || BandDecoder::new(&mut self.decoder)?
+                .is_some_and(|band_decoder| band_decoder.is_unsupported_by_decoder())
+            || self.decoder.colortype().is_err()
+            || RawChunks::new(&mut self.decoder)?.has_sparse()))
+    }
+
+    /// Whether the bands of the selected image or overview are stored plane by plane.
+    fn is_planar(&mut self) -> GeoTiffResult<bool> {
+        Ok(self
+            .decoder
+            .find_tag_unsigned::<u16>(Tag::PlanarConfiguration)?
+            == Some(PlanarConfiguration::Planar.to_u16()))
+    }
+
+    /// The index of the IFD of the selected image or overview and of its transparency mask.
+    fn ifd_indices(&self) -> (usize, Option<usize>) {
+        let image = &self.images[self.selected_image];
+        match self.selected_overview {
+            None => (image.ifd_index, image.mask_ifd_index),
+            Some(index) => {
+                let overview = &image.overviews[index];
+                (overview.ifd_index, overview.mask_ifd_index)
+            }
+        }
+    }
+
+    /// Decodes the given bands of the window `[x, y, width, height]` of the selected image or
+    /// overview into the metadata, see [`GeoTiff::read_window_data`], using the cache if any.
+    fn read_window_data(
+        &mut self,
+        geotiff: &mut GeoTiff,
+        window: [usize; 4],
+        bands: &[usize],
+    ) -> GeoTiffResult<()> {
+        let (ifd_index, _) = self.ifd_indices();
+        let cache = self
+            .block_cache
+            .as_mut()
+            .map(|block_cache| block_cache.image(ifd_index));
+        geotiff.read_window_data(&mut self.decoder, window, bands, cache)
+    }
+
+    /// Reads the window `[x, y, width, height]` of the transparency mask, if any, returning to
+    /// the selected image afterwards.
+    fn read_mask(&mut self, window: [usize; 4]) -> GeoTiffResult<Option<Vec<bool>>> {
+        let (ifd_index, Some(mask_ifd_index)) = self.ifd_indices() else {
+            return Ok(None);
+        };
+        self.decoder.seek_to_image(mask_ifd_index)?;
+        let mask = mask::read_mask_window(&mut self.decoder, window);
+        self.decoder.seek_to_image(ifd_index)?;
+        mask.map(Some)
+    }
+
+    /// Reads the whole raster.
+    pub fn read(&mut self) -> GeoTiffResult<GeoTiff> {
+        if !self.decoder_reads_pixels()? {
+            return self.read_window(0, 0, self.raster_width(), self.raster_height());
+        }
+        let mut geotiff = self.metadata.clone();
+        geotiff.raster_data = self.decoder.read_image()?.into();
+        geotiff.mask = self.read_mask([0, 0, geotiff.raster_width, geotiff.raster_height])?;
+        if self.apply_scale_offset {
+            geotiff.apply_scale_offset();
+        }
+        Ok(geotiff)
+    }
+
+    /// Reads a window of `width * height` pixels with its upper left corner at pixel `(x, y)`,
+    /// decoding only the strips or tiles intersecting the window.
+    ///
+    /// The returned GeoTiff covers just the window, i.e. its raster coordinates are relative to
+    /// the window and its coordinate transformation is adjusted accordingly, like
+    /// `gdal_translate -srcwin` does. Fails if the window is empty or exceeds the raster.
+    pub fn read_window(
+        &mut self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    ) -> GeoTiffResult<GeoTiff> {
+        let bands = (0..self.metadata.num_samples).collect::<Vec<_>>();
+        self.read_window_bands([x, y, width, height], &bands)
+    }
+
+    /// Reads the samples of a window of `width * height` pixels with its upper left corner at
+    /// pixel `(x, y)`, pixel by pixel, row by row.
+    ///
+    /// Unlike [`GeoTiff::get_values`], fails if `T` is not the sample type of the raster instead
+    /// of converting the samples, see [`GeoTiff::try_get_values`].
+    pub fn read_window_values<T: Sample + 'static>(
+        &mut self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    ) -> GeoTiffResult<Vec<T>> {
+        self.read_window(x, y, width, height)?
+            .try_get_values(Layout::PixelInterleaved)
+    }
+
+    /// Reads a window of `width * height` pixels with its upper left corner at pixel `(x, y)` into
+    /// a buffer of the samples of the window, pixel by pixel, row by row, e.g. to process many
+    /// windows without allocating for each.
+    ///
+    /// The strips or tiles within the window are decoded directly into the buffer, those partly
+    /// within it into a scratch buffer reused across calls. Rasters the decoder does not decode
+    /// itself, like planar rasters, are decoded into intermediate buffers. Unlike
+    /// [`GeoTiffReader::read_window`], neither the mask nor scale and offset are applied.
+    ///
+    /// Fails if the window is empty or exceeds the raster, if the length of the buffer differs from
+    /// the number of samples of the window, or if `T` is not the sample type of the raster, see
+    /// [`GeoTiff::try_get_values`].
+    pub fn read_window_into<T: Sample + Send + 'static>(
+        &mut self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+        buffer: &mut [T],
+    ) -> GeoTiffResult<()> {
+        let window = [x, y, width, height];
+        self.metadata.check_window(window)?;
+        let num_samples = self.metadata.num_samples;
+        if buffer.len() != width * height * num_samples {
+            return Err(GeoTiffError::InvalidArgument(format!(
+                "The buffer of {} samples does not fit the {} samples of the window",
+                buffer.len(),
+                width * height * num_samples
+            )));
+        }
+        if self.block_cache.is_some() || !self.decoder_reads_pixels()? {
+            let mut geotiff = self.metadata.clone();
+            let bands = (0..num_samples).collect::<Vec<_>>();
+            self.read_window_data(&mut geotiff, window, &bands)?;
+            buffer.copy_from_slice(&geotiff.try_get_values(Layout::PixelInterleaved)?);
+            return Ok(());
+        }
+        let sample_format = self
+            .decoder
+            .find_tag_unsigned_vec::<u16>(Tag::SampleFormat)?
+            .and_then(|formats| formats.first().copied())
+            .map_or(SampleFormat::Uint, SampleFormat::from_u16_exhaustive);
+        let bits_per_sample = self.bits_per_sample()?;
+        if sample_format != T::SAMPLE_FORMAT || bits_per_sample != T::BITS_PER_SAMPLE {
+            return Err(GeoTiffError::InvalidArgument(format!(
+                "The raster has samples of {} bits and format {:?}, not {}",
+                bits_per_sample,
+                sample_format,
+                type_name::<T>()
+            )));
+        }
+
+        let mut scratch = self
+            .scratch
+            .take()
+            .and_then(|scratch| scratch.downcast::<Vec<T>>().ok())
+            .map_or_else(Vec::new, |scratch| *scratch);
+        let chunks = RawChunks::new(&mut self.decoder)?;
+        // Sparse chunks are filled with the nodata value or 0
+        let fill = self
+            .metadata
+            .nodata::<T>()
+            .or_else(|| T::from_u8(0))
+            .unwrap_or(buffer[0]);
+        let (chunk_width, chunk_height) = self.decoder.chunk_dimensions();
+        let (chunk_width, chunk_height) = (chunk_width as usize, chunk_height as usize);
+        let chunks_across = self.metadata.raster_width.div_ceil(chunk_width);
+        for chunk_y in y / chunk_height..(y + height).div_ceil(chunk_height) {
+            for chunk_x in x / chunk_width..(x + width).div_ceil(chunk_width) {
+                let chunk_index = (chunk_y * chunks_across + chunk_x) as u32;
+                let (data_width, data_height) = self.decoder.chunk_data_dimensions(chunk_index);
+                let (data_width, data_height) = (data_width as usize, data_height as usize);
+                let (chunk_left, chunk_top) = (chunk_x * chunk_width, chunk_y * chunk_height);
+                let sparse = chunks.is_sparse(chunk_index as usize);
+                if !sparse
+                    && chunk_left >= x
+                    && chunk_top >= y
+                    && chunk_left + data_width <= x + width
+                    && chunk_top + data_height <= y + height
+                {
+                    let start = ((chunk_top - y) * width + chunk_left - x) * num_samples;
+                    self.decoder.read_chunk_to_buffer(
+                        T::decoding_buffer(&mut buffer[start..]),
+                        chunk_index,
+                        width,
+                    )?;
+                    continue;
+                }
+
+                if sparse {
+                    scratch.clear();
+                    scratch.resize(data_width * data_height * num_samples, fill);
+                } else {
+                    // The samples are overwritten by decoding
+                    scratch.resize(data_width * data_height * num_samples, buffer[0]);
+                    self.decoder.read_chunk_to_buffer(
+                        T::decoding_buffer(&mut scratch),
+                        chunk_index,
+                        data_width,
+                    )?;
+                }
+                let (left, top) = (x.max(chunk_left), y.max(chunk_top));
+                let right = (x + width).min(chunk_left + data_width);
+                let bottom = (y + height).min(chunk_top + data_height);
+                let row_length = (right - left) * num_samples;
+                for row in top..bottom {
+                    let target = ((row - y) * width + left - x) * num_samples;
+                    let source = ((row - chunk_top) * data_width + left - chunk_left) * num_samples;
+                    buffer[target..target + row_length]
+                        .copy_from_slice(&scratch[source..source + row_length]);
+                }
+            }
+        }
+        self.scratch = Some(Box::new(scratch));
+        Ok(())
+    }
+
+    /// Reads a single band of the whole raster, see [`GeoTiffReader::read_band_window`].
+    pub fn read_band(&mut self, band: usize) -> GeoTiffResult<GeoTiff> {
+        self.read_band_window(band, 0, 0, self.raster_width(), self.raster_height())
+    }
+
+    /// Reads a single band of a window of `width * height` pixels with its upper left corner at
+    /// pixel `(x, y)`, returning a GeoTiff of one sample per pixel, see
+    /// [`GeoTiffReader::read_window`].
+    ///
+    /// The other bands are neither decoded nor allocated: Of planar rasters, only the strips or
+    /// tiles of the band are read, and of chunky rasters, only the samples of the band are
+    /// converted after decompression. This works for any number of bands, e.g. to read a band
+    /// of a hyperspectral raster of hundreds of bands. Fails if the band does not exist.
+    pub fn read_band_window(
+        &mut self,
+        band: usize,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    ) -> GeoTiffResult<GeoTiff> {
+        self.read_window_bands([x, y, width, height], &[band])
+    }
+
+    /// Reads the given bands of the window `[x, y, width, height]` along with the mask.
+    pub(crate) fn read_window_bands(
+        &mut self,
+        window: [usize; 4],
+        bands: &[usize],
+    ) -> GeoTiffResult<GeoTiff> {
+        let mut geotiff = self.metadata.clone();
+        self.read_window_data(&mut geotiff, window, bands)?;
+        geotiff.mask = self.read_mask(window)?;
+        if self.apply_scale_offset {
+            geotiff.apply_scale_offset();
+        }
+        Ok(geotiff)
+    }
+
+    /// Reads the given bands of the window `[x, y, width, height]`, in that order, returning the
+    /// samples converted to `T` in the given layout regardless of the PlanarConfiguration of the
+    /// file, e.g. `&[3, 2, 1]` as [`Layout::BandSequential`] for a channels-first tensor of the
+    /// red, green and blue bands of a multispectral image.
+    ///
+    /// Only the strips or tiles intersecting the window are decoded, and of planar files only
+    /// those of the given bands. Fails if the window is empty or exceeds the raster, or if a band
+    /// does not exist.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a sample cannot be represented as `T`, like [`GeoTiff::get_value_at`].
+    pub fn read_bands<T: FromPrimitive + 'static>(
+        &mut self,
+        window: [usize; 4],
+        bands: &[usize],
+        layout: Layout,
+    ) -> GeoTiffResult<Vec<T>> {
+        let mut geotiff = self.metadata.clone();
+        self.read_window_data(&mut geotiff, window, bands)?;
+        if self.apply_scale_offset {
+            geotiff.apply_scale_offset();
+        }
+        Ok(geotiff.get_values(layout))
+    }
+
+    /// Reads the pixels intersecting a bounding box in model space, see
+    /// [`GeoTiffReader::read_window`].
+    ///
+    /// The window covers all pixels intersecting the bounding box, clipped to the raster, like
+    /// `gdal_translate -projwin` does. Its position in model space is given by the coordinate
+    /// transformation of the returned GeoTiff, e.g. as [`GeoTiff::geotransform`]. Fails if the
+    /// GeoTIFF is not georeferenced or the bounding box does not intersect the raster.
+    pub fn read_bbox(
+        &mut self,
+        min_x: f64,
+        min_y: f64,
+        max_x: f64,
+        max_y: f64,
+    ) -> GeoTiffResult<GeoTiff> {
+        let [x, y, width, height] =
+            self.bbox_window(min_x, min_y, max_x, max_y, WindowSnap::Outward)?;
+        self.read_window(x, y, width, height)
+    }
+
+    /// Reads the pixels intersecting a bounding box in model space at approximately the given
+    /// size of a pixel in model units, like GDAL serves zoomed-out requests.
+    ///
+    /// The coarsest overview whose pixels are not larger than the requested pixel size is read,
+    /// or the full-resolution raster if there is none, see [`GeoTiffReader::read_bbox`]. The
+    /// window is then resampled by nearest neighbour to the requested pixel size, rounded such
+    /// that the window is covered by whole pixels, but never upsampled. The selected overview is
+    /// left unchanged. Fails if the raster is not georeferenced by an affine transformation.
+    pub fn read_window_at_resolution(
+        &mut self,
+        bbox: Extent,
+        pixel_size: f64,
+    ) -> GeoTiffResult<GeoTiff> {
+        let selected_overview = self.selected_overview;
+        self.select_overview(self.overview_for_pixel_size(pixel_size)?)?;
+        let window = self.read_bbox(bbox.min_x, bbox.min_y, bbox.max_x, bbox.max_y);
+        self.select_overview(selected_overview)?;
+        resample_to_pixel_size(window?, pixel_size)
+    }
+
+    /// Reads the window `[x, y, width, height]` resampled to `out_width * out_height` pixels, like
+    /// rasterio's `out_shape`, e.g. for thumbnails or zoomed-out views.
+    ///
+    /// The coarsest overview whose pixels are not larger than the target pixels is read, or the
+    /// full-resolution raster if there is none, decoding only the strips or tiles covering the
+    /// window. Each target pixel then takes the pixel nearest to its center or the average of the
+    /// valid pixels it covers, weighted by the covered part of each pixel. The returned GeoTiff
+    /// covers exactly the window, with its coordinate transformation scaled accordingly. The
+    /// selected overview is left unchanged. Fails if the window or the target size is empty or
+    /// the window exceeds the raster.
+    pub fn read_window_resampled(
+        &mut self,
+        [x, y, width, height]: [usize; 4],
+        out_width: usize,
+        out_height: usize,
+        resampling: Resampling,
+    ) -> GeoTiffResult<GeoTiff> {
+        self.metadata.check_window([x, y, width, height])?;
+        if out_width == 0 || out_height == 0 {
+            return Err(GeoTiffError::InvalidArgument(format!(
+                "The target size of {}x{} pixels is empty",
+                out_width, out_height
+            )));
+        }
+        let selected_overview = self.selected_overview;
+        let scale = |overview: Option<usize>| {
+            overview.map_or([1.0, 1.0], |index| self.overviews()[index].scale)
+        };
+        // The size of a target pixel in pixels of the full-resolution raster, tolerating rounding
+        // errors of the scales
+        let [selected_x, selected_y] = scale(selected_overview);
+        let max_scale = [
+            width as f64 / out_width as f64 * selected_x * (1.0 + 1e-9),
+            height as f64 / out_height as f64 * selected_y * (1.0 + 1e-9),
+        ];
+        let overview = std::iter::once(None)
+            .chain((0..self.overviews().len()).map(Some))
+            .filter(|&overview| {
+                let [scale_x, scale_y] = scale(overview);
+                scale_x <= max_scale[0] && scale_y <= max_scale[1]
+            })
+            .max_by(|&a, &b| scale(a)[0].total_cmp(&scale(b)[0]))
+            .unwrap_or(selected_overview);
+
+        // The window in the raster that is read
+        let [scale_x, scale_y] = scale(overview);
+        let ratio = [scale_x / selected_x, scale_y / selected_y];
+        let window = [
+            x as f64 / ratio[0],
+            y as f64 / ratio[1],
+            (x + width) as f64 / ratio[0],
+            (y + height) as f64 / ratio[1],
+        ];
+        self.select_overview(overview)?;
+        let (raster_width, raster_height) = (self.raster_width(), self.raster_height());
+        let left = (window[0].floor() as usize).min(raster_width - 1);
+        let top = (window[1].floor() as usize).min(raster_height - 1);
+        let right = (window[2].ceil() as usize).clamp(left + 1, raster_width);
+        let bottom = (window[3].ceil() as usize).clamp(top + 1, raster_height);
+        let source = self.read_window(left, top, right - left, bottom - top);
+        self.select_overview(selected_overview)?;
+
+        let areas = PixelAreas {
+            origin: [window[0] - left as f64, window[1] - top as f64],
+            step: [
+                (window[2] - window[0]) / out_width as f64,
+                (window[3] - window[1]) / out_height as f64,
+            ],
+        };
+        Ok(source?.resample_areas(areas, out_width, out_height, resampling))
+    }
+
+    /// Interpolates the values of the band at many model coordinates, returned in the order of
+    /// the coordinates, see [`GeoTiff::sample`].
+    ///
+    /// The coordinates are grouped by the strip or tile containing them, so that each block is
+    /// decoded once, along with the margin of neighbouring pixels the interpolation needs, which
+    /// is much faster than sampling a fully read raster for millions of points. `None` is
+    /// returned for coordinates outside the raster or without valid neighbours. Fails if the
+    /// GeoTIFF is not georeferenced or the band does not exist.
+    pub fn sample_many(
+        &mut self,
+        coords: &[[f64; 2]],
+        band: usize,
+        interpolation: Interpolation,
+    ) -> GeoTiffResult<Vec<Option<f64>>> {
+        self.check_band(band)?;
+        let coordinate_transform =
+            self.metadata.coordinate_transform.as_ref().ok_or_else(|| {
+                GeoTiffError::GeoKey("The GeoTIFF is not georeferenced".to_string())
+            })?;
+        let (width, height) = (self.metadata.raster_width, self.metadata.raster_height);
+        let raster_coords = coordinate_transform.transform_to_raster_f64_many(coords);
+
+        let [block_width, block_height] = self.block_size();
+        let block_of = |[column, row]: [f64; 2]| {
+            ((0.0..width as f64).contains(&column) && (0.0..height as f64).contains(&row))
+                .then(|| [row as usize / block_height, column as usize / block_width])
+        };
+        let mut order = (0..coords.len())
+            .filter(|&index| block_of(raster_coords[index]).is_some())
+            .collect::<Vec<_>>();
+        order.sort_by_key(|&index| block_of(raster_coords[index]));
+
+        let radius = interpolation.radius();
+        let mut values = vec![None; coords.len()];
+        for group in
+            order.chunk_by(|&a, &b| block_of(raster_coords[a]) == block_of(raster_coords[b]))
+        {
+            let [block_row, block_column] = block_of(raster_coords[group[0]]).unwrap();
+            let x = (block_column * block_width).saturating_sub(radius);
+            let y = (block_row * block_height).saturating_sub(radius);
+            let right = ((block_column + 1) * block_width + radius).min(width);
+            let bottom = ((block_row + 1) * block_height + radius).min(height);
+            let window = self.read_window_bands([x, y, right - x, bottom - y], &[band])?;
+            for &index in group {
+                let [column, row] = raster_coords[index];
+                values[index] = interpolation::interpolate(
+                    &window,
+                    [column - x as f64, row - y as f64],
+                    0,
+                    interpolation,
+                );
+            }
+        }
+        Ok(values)
+    }
+
+    /// Reads the values of all bands at a point in model space, like `gdallocationinfo -geoloc`,
+    /// decoding only the strip or tile containing it.
+    ///
+    /// The values are read from the selected image or overview, see
+    /// [`GeoTiffReader::select_overview`]. Fails if the GeoTIFF is not georeferenced or the point
+    /// is outside the raster.
+    pub fn query_model_point(&mut self, x: f64, y: f64) -> GeoTiffResult<PointQuery> {
+        let coordinate_transform =
+            self.metadata.coordinate_transform.as_ref().ok_or_else(|| {
+                GeoTiffError::GeoKey("The GeoTIFF is not georeferenced".to_string())
+            })?;
+        let coord = coordinate_transform.transform_to_raster_f64([x, y]);
+        self.query_pixel(coord)
+    }
+
+    /// Reads the values of all bands at WGS 84 longitude/latitude, like
+    /// `gdallocationinfo -wgs84`, see [`GeoTiffReader::query_model_point`] and
+    /// [`GeoTiff::transform_to_crs`].
+    #[cfg(feature = "proj")]
+    pub fn query_point(&mut self, lon: f64, lat: f64) -> GeoTiffResult<PointQuery> {
+        let coord = self
+            .metadata
+            .transform_to_crs(crate::WGS_84)?
+            .transform_to_raster_f64([lon, lat])?;
+        self.query_pixel(coord)
+    }
+
+    /// Reads the values of the pixel containing the fractional raster coordinate.
+    fn query_pixel(&mut self, [column, row]: [f64; 2]) -> GeoTiffResult<PointQuery> {
+        if !((0.0..self.raster_width() as f64).contains(&column)
+            && (0.0..self.raster_height() as f64).contains(&row))
+        {
+            return Err(GeoTiffError::OutOfBounds(format!(
+                "The point at pixel ({}, {}) is outside the raster",
+                column, row
+            )));
+        }
+        let [column, row] = [column as usize, row as usize];
+        let pixel = self.read_window(column, row, 1, 1)?;
+        Ok(PointQuery {
+            column,
+            row,
+            values: (0..pixel.num_samples)
+                .map(|band| pixel.get_value_at_checked(0, 0, band))
+                .collect(),
+            overview: self.selected_overview,
+        })
+    }
+
+    /// The coarsest overview whose pixels are not larger than the given pixel size, or `None` if
+    /// the full-resolution raster is to be read.
+    pub(crate) fn overview_for_pixel_size(&self, pixel_size: f64) -> GeoTiffResult<Option<usize>> {
+        let metadata = &self.images[self.selected_image].metadata;
+        let [resolution_x, resolution_y] = metadata.resolution().ok_or_else(|| {
+            GeoTiffError::Unsupported(
+                "The GeoTIFF is not georeferenced by an affine transformation".to_string(),
+            )
+        })?;
+        if pixel_size <= 0.0 || !pixel_size.is_finite() {
+            return Err(GeoTiffError::InvalidArgument(format!(
+                "The pixel size must be positive, found {}",
+                pixel_size
+            )));
+        }
+        // Tolerate rounding errors of the resolution
+        let max_pixel_size = pixel_size * (1.0 + 1e-9);
+        Ok(self
+            .overviews()
+            .iter()
+            .enumerate()
+            .filter(|(_, overview)| {
+                resolution_x * overview.scale[0] <= max_pixel_size
+                    && resolution_y * overview.scale[1] <= max_pixel_size
+            })
+            .max_by(|(_, a), (_, b)| a.scale[0].total_cmp(&b.scale[0]))
+            .map(|(index, _)| index))
+    }
+
+    /// The window `[x, y, width, height]` covering a bounding box in model space, whose edges are
+    /// snapped to the pixel grid as given and clipped to the raster, e.g. read by
+    /// [`GeoTiffReader::read_bbox`] snapped outward.
+    pub(crate) fn bbox_window(
+        &self,
+        min_x: f64,
+        min_y: f64,
+        max_x: f64,
+        max_y: f64,
+        snap_mode: WindowSnap,
+    ) -> GeoTiffResult<[usize; 4]> {
+        let coordinate_transform =
+            self.metadata.coordinate_transform.as_ref().ok_or_else(|| {
+                GeoTiffError::GeoKey("The GeoTIFF is not georeferenced".to_string())
+            })?;
+
+        let corners = coordinate_transform.transform_to_raster_f64_many(&[
+            [min_x, min_y],
+            [max_x, min_y],
+            [min_x, max_y],
+            [max_x, max_y],
+        ]);
+        // Avoid including an additional pixel due to rounding errors
+        let snap = |value: f64| {
+            if (value - value.round()).abs() < 1e-6 {
+                value.round()
+            } else {
+                value
+            }
+        };
+        let xs = corners.iter().map(|corner| snap(corner[0]));
+        let ys = corners.iter().map(|corner| snap(corner[1]));
+        // The minimum and maximum edges snapped to the pixel grid
+        let snap_edges = |min: f64, max: f64| match snap_mode {
+            WindowSnap::Outward => (min.floor(), max.ceil()),
+            WindowSnap::Nearest => (min.round(), max.round()),
+            WindowSnap::Inward => (min.ceil(), max.floor()),
+        };
+        let (left, right) = snap_edges(
+            xs.clone().fold(f64::INFINITY, f64::min),
+            xs.fold(f64::NEG_INFINITY, f64::max),
+        );
+        let (top, bottom) = snap_edges(
+            ys.clone().fold(f64::INFINITY, f64::min),
+            ys.fold(f64::NEG_INFINITY, f64::max),
+        );
+        let (left, right) = (left.max(0.0), right.min(self.metadata.raster_width as f64));
+        let (top, bottom) = (top.max(0.0), bottom.min(self.metadata.raster_height as f64));
+        if !(left < right && top < bottom) {
+            return Err(GeoTiffError::OutOfBounds(
+                "The bounding box does not cover any pixel of the raster".to_string(),
+            ));
+        }
+        Ok([
+            left as usize,
+            top as usize,
+            (right - left) as usize,
+            (bottom - top) as usize,
+        ])
+    }
+
+    /// The number of tile columns and rows, or `None` if the raster is stored in strips.
+    pub fn tile_count(&self) -> Option<[usize; 2]> {
+        let [tile_width, tile_height] = self.tile_dimensions()?;
+        Some([
+            self.metadata.raster_width.div_ceil(tile_width),
+            self.metadata.raster_height.div_ceil(tile_height),
+        ])
+    }
+
+    /// The width and height of a tile in pixels, or `None` if the raster is stored in strips.
+    ///
+    /// Tiles at the right and bottom edges may extend beyond the raster.
+    pub fn tile_dimensions(&self) -> Option<[usize; 2]> {
+        match self.decoder.get_chunk_type() {
+            ChunkType::Tile => {
+                let (tile_width, tile_height) = self.decoder.chunk_dimensions();
+                Some([tile_width as usize, tile_height as usize])
+            }
+            ChunkType::Strip => None,
+        }
+    }
+
+    /// Reads the tile in the given column and row of the tile grid, without the padding of tiles
+    /// extending beyond the raster.
+    ///
+    /// Fails if the raster is stored in strips or the tile does not exist.
+    pub fn read_tile(&mut self, column: usize, row: usize) -> GeoTiffResult<GeoTiff> {
+        let [x, y, width, height] = self.tile_window(column, row)?;
+        self.read_window(x, y, width, height)
+    }
+
+    /// The window `[x, y, width, height]` read by [`GeoTiffReader::read_tile`].
+    pub(crate) fn tile_window(&self, column: usize, row: usize) -> GeoTiffResult<[usize; 4]> {
+        let ([tile_width, tile_height], [columns, rows]) =
+            match (self.tile_dimensions(), self.tile_count()) {
+                (Some(dimensions), Some(count)) => (dimensions, count),
+                _ => {
+                    return Err(GeoTiffError::Unsupported(
+                        "The raster is not tiled".to_string(),
+                    ))
+                }
+            };
+        if column >= columns || row >= rows {
+            return Err(GeoTiffError::OutOfBounds(format!(
+                "Tile ({}, {}) exceeds the grid of {}x{} tiles",
+                column, row, columns, rows
+            )));
+        }
+        let (x, y) = (column * tile_width, row * tile_height);
+        Ok([
+            x,
+            y,
+            tile_width.min(self.metadata.raster_width - x),
+            tile_height.min(self.metadata.raster_height - y),
+        ])
+    }
+
+    /// The number of strips, or `None` if the raster is tiled.
+    pub fn strip_count(&self) -> Option<usize> {
+        Some(self.metadata.raster_height.div_ceil(self.rows_per_strip()?))
+    }
+
+    /// The number of rows per strip (RowsPerStrip), or `None` if the raster is tiled.
+    ///
+    /// The last strip may contain fewer rows.
+    pub fn rows_per_strip(&self) -> Option<usize> {
+        match self.decoder.get_chunk_type() {
+            ChunkType::Strip => Some(self.decoder.chunk_dimensions().1 as usize),
+            ChunkType::Tile => None,
+        }
+    }
+
+    /// Reads the strip with the given index, counted from the top of the raster.
+    ///
+    /// Fails if the raster is tiled or the strip does not exist.
+    pub fn read_strip(&mut self, index: usize) -> GeoTiffResult<GeoTiff> {
+        let [x, y, width, height] = self.strip_window(index)?;
+        self.read_window(x, y, width, height)
+    }
+
+    /// The window `[x, y, width, height]` read by [`GeoTiffReader::read_strip`].
+    pub(crate) fn strip_window(&self, index: usize) -> GeoTiffResult<[usize; 4]> {
+        let (rows_per_strip, count) = match (self.rows_per_strip(), self.strip_count()) {
+            (Some(rows_per_strip), Some(count)) => (rows_per_strip, count),
+            _ => {
+                return Err(GeoTiffError::Unsupported(
+                    "The raster is not stored in strips".to_string(),
+                ))
+            }
+        };
+        if index >= count {
+            return Err(GeoTiffError::OutOfBounds(format!(
+                "Strip {} exceeds the {} strips of the raster",
+                index, count
+            )));
+        }
+        let y = index * rows_per_strip;
+        Ok([
+            0,
+            y,
+            self.metadata.raster_width,
+            rows_per_strip.min(self.metadata.raster_height - y),
+        ])
+    }
+
+    /// The number of bits of each sample as stored in the file, e.g. 1 for bilevel rasters, which
+    /// are unpacked into `u8` when reading, see [`GeoTiffReader::read_packed`].
+    pub fn bits_per_sample(&mut self) -> GeoTiffResult<u16> {
+        Ok(self
+            .decoder
+            .find_tag_unsigned_vec::<u16>(Tag::BitsPerSample)?
+            .and_then(|bits| bits.first().copied())
+            .unwrap_or(1))
+    }
+
+    /// Reads the samples of the raster without unpacking those of less than 8 bits, e.g. 8 pixels
+    /// per byte of a 1-bit raster, which takes an eighth of the memory of the unpacked samples.
+    ///
+    /// Each row of `width * num_samples` samples of
+    /// [`bits_per_sample`](GeoTiffReader::bits_per_sample) bits starts at a byte, the samples
+    /// being packed with the most significant bit first. The rows of planar rasters follow plane
+    /// by plane. Fails for JPEG compression and predictors, and does not apply any mask or scale
+    /// and offset.
+    pub fn read_packed(&mut self) -> GeoTiffResult<Vec<u8>> {
+        chunk::read_packed_rows(&mut self.decoder)
+    }
+
+    /// Whether the raster data is stored as is, i.e. neither compressed nor transformed by a
+    /// predictor, so that the raw data of a strip or tile equals its samples in the byte order of
+    /// the file.
+    pub fn is_uncompressed(&mut self) -> GeoTiffResult<bool> {
+        let compression = self
+            .decoder
+            .find_tag_unsigned::<u16>(Tag::Compression)?
+            .unwrap_or(CompressionMethod::None.to_u16());
+        let predictor = self
+            .decoder
+            .find_tag_unsigned::<u16>(Tag::Predictor)?
+            .unwrap_or(Predictor::None.to_u16());
+        Ok(
+            compression == CompressionMethod::None.to_u16()
+                && predictor == Predictor::None.to_u16(),
+        )
+    }
+
+    /// The byte range of the raw data of the strip or tile with the given index in the file.
+    ///
+    /// Tiles are indexed in row-major order and strips from top to bottom. The chunks of planar
+    /// rasters follow band by band, i.e. those of the second band start at the index of the
+    /// chunk count of a single band. The range of sparse chunks, which are not stored, is empty.
+    /// Fails if the chunk does not exist.
+    pub fn chunk_byte_range(&mut self, index: usize) -> GeoTiffResult<Range<u64>> {
+        let (offsets_tag, byte_counts_tag) = match self.decoder.get_chunk_type() {
+            ChunkType::Strip => (Tag::StripOffsets, Tag::StripByteCounts),
+            ChunkType::Tile => (Tag::TileOffsets, Tag::TileByteCounts),
+        };
+        let offsets = self.decoder.get_tag_u64_vec(offsets_tag)?;
+        let byte_counts = self.decoder.get_tag_u64_vec(byte_counts_tag)?;
+        match (offsets.get(index), byte_counts.get(index)) {
+            (Some(0), _) | (_, Some(0)) => Ok(0..0),
+            (Some(&offset), Some(&byte_count)) => Ok(offset..offset + byte_count),
+            _ => Err(GeoTiffError::OutOfBounds(format!(
+                "Chunk {} exceeds the {} chunks of the raster",
+                index,
+                offsets.len()
+            ))),
+        }
+    }
+
+    /// An iterator over the blocks of the raster, i.e. its tiles in row-major order or its strips
+    /// from top to bottom, each decoded only when the iterator advances.
+    ///
+    /// This allows scanning huge rasters while holding only a single block in memory.
+    pub fn blocks(&mut self) -> Blocks<'_, R> {
+        Blocks {
+            block_size: self.block_size(),
+            reader: self,
+            index: 0,
+        }
+    }
+
+    /// The width and height of a strip or tile.
+    pub(crate) fn block_size(&self) -> [usize; 2] {
+        self.tile_dimensions()
+            .or_else(|| Some([self.metadata.raster_width, self.rows_per_strip()?]))
+            .unwrap_or([self.metadata.raster_width, self.metadata.raster_height])
+    }
+
+    /// Computes the statistics of the valid samples of the band in the selected image or
+    /// overview block by block, see [`GeoTiff::statistics`], so that only a single block is held
+    /// in memory.
+    ///
+    /// If `approximate` is `true`, the statistics are computed from the smallest overview of at
+    /// least 2500 pixels instead, like `gdalinfo -approx_stats` does, which is much faster for
+    /// large rasters. The selected overview is left unchanged. Fails if the band does not exist.
+    pub fn statistics(
+        &mut self,
+        band: usize,
+        approximate: bool,
+    ) -> GeoTiffResult<Option<Statistics>> {
+        let selected_overview = self.selected_overview;
+        if approximate {
+            self.select_overview(self.approximate_overview())?;
+        }
+        let statistics = self.band_statistics(band);
+        self.select_overview(selected_overview)?;
+        statistics
+    }
+
+    fn band_statistics(&mut self, band: usize) -> GeoTiffResult<Option<Statistics>> {
+        self.check_band(band)?;
+        let mut statistics = StatisticsAccumulator::default();
+        for block in self.blocks() {
+            statistics.add_band(&block?.geotiff, band);
+        }
+        Ok(statistics.finish())
+    }
+
+    /// Computes the histogram of the valid samples of the band in the selected image or overview
+    /// block by block, see [`GeoTiff::histogram`], so that only a single block is held in memory.
+    ///
+    /// Fails if the band does not exist, there are no bins or the range is empty.
+    pub fn histogram(
+        &mut self,
+        band: usize,
+        bins: usize,
+        range: [f64; 2],
+    ) -> GeoTiffResult<Vec<u64>> {
+        self.check_band(band)?;
+        let mut histogram = HistogramAccumulator::new(bins, range)?;
+        for block in self.blocks() {
+            histogram.add_band(&block?.geotiff, band);
+        }
+        Ok(histogram.finish())
+    }
+
+    /// The smallest overview of at least [`APPROXIMATE_MIN_PIXELS`] pixels that is not larger
+    /// than the selected image or overview, which is kept if there is none.
+    fn approximate_overview(&self) -> Option<usize> {
+        let pixels = self.raster_width() * self.raster_height();
+        self.overviews()
+            .iter()
+            .enumerate()
+            .map(|(index, overview)| (index, overview.raster_width * overview.raster_height))
+            .filter(|&(_, overview_pixels)| {
+                (APPROXIMATE_MIN_PIXELS..=pixels).contains(&overview_pixels)
+            })
+            .min_by_key(|&(_, overview_pixels)| overview_pixels)
+            .map(|(index, _)| index)
+            .or(self.selected_overview)
+    }
+
+    pub(crate) fn check_band(&self, band: usize) -> GeoTiffResult<()> {
+        if band >= self.num_samples() {
+            return Err(GeoTiffError::OutOfBounds(format!(
+                "Band {} exceeds the {} bands of the raster",
+                band,
+                self.num_samples()
+            )));
+        }
+        Ok(())
+    }
+
+    /// The byte ranges of the strips or tiles read for the given bands of the window
+    /// `[x, y, width, height]`, or for all bands for `None`, including those of the transparency
+    /// mask.
+    #[cfg_attr(not(feature = "tokio"), allow(dead_code))]
+    pub(crate) fn window_byte_ranges(
+        &mut self,
+        window: [usize; 4],
+        bands: Option<&[usize]>,
+    ) -> GeoTiffResult<Vec<Range<u64>>> {
+        let mut chunks = self.window_chunks(window);
+        if self.is_planar()? {
+            // The chunks of the further bands follow plane by plane
+            let [block_width, block_height] = self.block_size();
+            let chunks_per_plane = self.metadata.raster_width.div_ceil(block_width)
+                * self.metadata.raster_height.div_ceil(block_height);
+            let all_bands = (0..self.metadata.num_samples).collect::<Vec<_>>();
+            chunks = bands
+                .unwrap_or(&all_bands)
+                .iter()
+                .flat_map(|band| {
+                    chunks
+                        .iter()
+                        .map(move |chunk| band * chunks_per_plane + chunk)
+                })
+                .collect();
+        }
+        let mut ranges = chunks
+            .into_iter()
+            .map(|index| self.chunk_byte_range(index))
+            .collect::<GeoTiffResult<Vec<_>>>()?;
+        if let (ifd_index, Some(mask_ifd_index)) = self.ifd_indices() {
+            self.decoder.seek_to_image(mask_ifd_index)?;
+            let mask_ranges = self
+                .window_chunks(window)
+                .into_iter()
+                .map(|index| self.chunk_byte_range(index))
+                .collect::<GeoTiffResult<Vec<_>>>();
+            self.decoder.seek_to_image(ifd_index)?;
+            ranges.extend(mask_ranges?);
+        }
+        // Sparse chunks are not stored
+        ranges.retain(|range| !range.is_empty());
+        Ok(ranges)
+    }
+
+    /// The indices of the strips or tiles intersecting the window `[x, y, width, height]`.
+    fn window_chunks(&self, [x, y, width, height]: [usize; 4]) -> Vec<usize> {
+        let [block_width, block_height] = self.block_size();
+        let columns = self.metadata.raster_width.div_ceil(block_width);
+        let column_range = x / block_width..(x + width).div_ceil(block_width);
+        (y / block_height..(y + height).div_ceil(block_height))
+            .flat_map(|row| {
+                column_range
+                    .clone()
+                    .map(move |column| row * columns + column)
+            })
+            .collect()
+    }
+}
+
+/// The values of the bands at a point, see [`GeoTiffReader::query_model_point`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PointQuery {
+    /// The column of the pixel containing the point in the raster the values are read from
+    pub column: usize,
+    /// The row of the pixel containing the point in the raster the values are read from
+    pub row: usize,
+    /// The value of each band, or `None` for nodata, see [`GeoTiff::get_value_at_checked`]
+    pub values: Vec<Option<f64>>,
+    /// The overview the values are read from, or `None` for the full-resolution raster
+    pub overview: Option<usize>,
+}
+
+/// A strip or tile of a raster read by [`GeoTiffReader::blocks`].
+#[derive(Debug, Clone)]
+pub struct Block {
+    /// The column of the upper left pixel of the block in the raster
+    pub x: usize,
+    /// The row of the upper left pixel of the block in the raster
+    pub y: usize,
+    /// The bounding box of the block in model space, if the raster is georeferenced
+    pub model_extent: Option<Extent>,
+    /// The raster data of the block, with raster coordinates relative to the block
+    pub geotiff: GeoTiff,
+}
+
+impl Block {
+    pub fn width(&self) -> usize {
+        self.geotiff.raster_width
+    }
+
+    pub fn height(&self) -> usize {
+        self.geotiff.raster_height
+    }
+}
+
+/// An iterator decoding the blocks of a raster one at a time, see [`GeoTiffReader::blocks`].
+pub struct Blocks<'a, R: Read + Seek> {
+    reader: &'a mut GeoTiffReader<R>,
+    /// The width and height of a block, which may extend beyond the raster at its edges
+    block_size: [usize; 2],
+    index: usize,
+}
+
+impl<R: Read + Seek> Blocks<'_, R> {
+    fn columns(&self) -> usize {
+        self.reader
+            .metadata
+            .raster_width
+            .div_ceil(self.block_size[0])
+    }
+
+    fn block_count(&self) -> usize {
+        self.columns()
+            * self
+                .reader
+                .metadata
+                .raster_height
+                .div_ceil(self.block_size[1])
+    }
+}
+
+impl<R: Read + Seek> Iterator for Blocks<'_, R> {
+    type Item = GeoTiffResult<Block>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.block_count() {
+            return None;
+        }
+        let columns = self.columns();
+        let [block_width, block_height] = self.block_size;
+        let x = self.index % columns * block_width;
+        let y = self.index / columns * block_height;
+        self.index += 1;
+
+        let width = block_width.min(self.reader.metadata.raster_width - x);
+        let height = block_height.min(self.reader.metadata.raster_height - y);
+        Some(
+            self.reader
+                .read_window(x, y, width, height)
+                .map(|geotiff| Block {
+                    x,
+                    y,
+                    model_extent: geotiff.model_extent(),
+                    geotiff,
+                }),
+        )
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.block_count() - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<R: Read + Seek> ExactSizeIterator for Blocks<'_, R> {}
+
+/// Resamples a window read at a finer resolution to approximately the given pixel size.
+pub(crate) fn resample_to_pixel_size(window: GeoTiff, pixel_size: f64) -> GeoTiffResult<GeoTiff> {
+    let [resolution_x, resolution_y] = window.resolution().ok_or_else(|| {
+        GeoTiffError::Unsupported(
+            "The GeoTIFF is not georeferenced by an affine transformation".to_string(),
+        )
+    })?;
+    let target_size = |length: usize, resolution: f64| {
+        ((length as f64 * resolution / pixel_size).round() as usize).clamp(1, length)
+    };
+    let width = target_size(window.raster_width, resolution_x);
+    let height = target_size(window.raster_height, resolution_y);
+    if (width, height) == (window.raster_width, window.raster_height) {
+        return Ok(window);
+    }
+    Ok(window.resample_nearest(width, height))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Write};
+
+    use tiff::encoder::{colortype, TiffEncoder};
+
+    use super::*;
+    use crate::{Compression, CoordinateTransform, GeoTiffVersion, GeoTiffWriter, Resampling};
+
+    fn reader(writer: GeoTiffWriter) -> GeoTiffReader<Cursor<Vec<u8>>> {
+        let data = (0..40 * 20).map(|value| value as u16).collect::<Vec<_>>();
+        let transform =
+            CoordinateTransform::from_geotransform([1000.0, 10.0, 0.0, 2000.0, 0.0, -10.0])
+                .unwrap();
+        let mut bytes = Vec::new();
+        writer
+            .with_coordinate_transform(transform)
+            .write(&mut bytes, 40, 20, 1, &data)
+            .unwrap();
+        GeoTiffReader::new(Cursor::new(bytes)).unwrap()
+    }
+
+    fn reader_tiled() -> GeoTiffReader<Cursor<Vec<u8>>> {
+        reader(GeoTiffWriter::new().with_tiles(16, 16))
+    }
+
+    #[test]
+    fn test_read_tile() {
+        let mut striped = reader(GeoTiffWriter::new());
+        assert!(matches!(
+            striped.read_tile(0, 0),
+            Err(GeoTiffError::Unsupported(_))
+        ));
+
+        let mut reader = reader_tiled();
+        assert_eq!(reader.tile_count(), Some([3, 2]));
+        assert_eq!(reader.tile_dimensions(), Some([16, 16]));
+
+        let tile = reader.read_tile(1, 0).unwrap();
+        assert_eq!((tile.raster_width, tile.raster_height), (16, 16));
+        assert_eq!(tile.get_value_at::<u16>(0, 0, 0), 16);
+        assert_eq!(tile.transform_to_model([0, 0]), Some([1160.0, 2000.0]));
+
+        // The tile at the lower right corner is cropped to the raster
+        let tile = reader.read_tile(2, 1).unwrap();
+        assert_eq!((tile.raster_width, tile.raster_height), (8, 4));
+        assert_eq!(tile.get_value_at::<u16>(7, 3, 0), 799);
+
+        assert!(matches!(
+            reader.read_tile(3, 0),
+            Err(GeoTiffError::OutOfBounds(_))
+        ));
+    }
+
+    #[test]
+    fn test_overviews() {
+        let mut reader = reader(GeoTiffWriter::new().add_overviews(&[2, 4], Resampling::Nearest));
+        let overviews = reader.overviews();
+        assert_eq!(overviews.len(), 2);
+        assert_eq!(
+            (overviews[1].raster_width, overviews[1].raster_height),
+            (10, 5)
+        );
+        assert_eq!(overviews[1].scale, [4.0, 4.0]);
+
+        reader.select_overview(Some(1)).unwrap();
+        assert_eq!(reader.selected_overview(), Some(1));
+        assert_eq!((reader.raster_width(), reader.raster_height()), (10, 5));
+        let overview = reader.read().unwrap();
+        assert_eq!(overview.raster_width, 10);
+        // The nearest neighbour of pixel (1, 1) is pixel (6, 6) of the full-resolution raster
+        assert_eq!(overview.get_value_at::<u16>(1, 1, 0), 6 * 40 + 6);
+        assert_eq!(overview.transform_to_model([1, 1]), Some([1040.0, 1960.0]));
+        let window = reader.read_window(9, 4, 1, 1).unwrap();
+        assert_eq!(window.transform_to_model([0, 0]), Some([1360.0, 1840.0]));
+
+        reader.select_overview(None).unwrap();
+        assert_eq!(reader.raster_width(), 40);
+        assert_eq!(reader.read().unwrap().get_value_at::<u16>(39, 19, 0), 799);
+        assert!(reader.select_overview(Some(2)).is_err());
+        assert!(reader_tiled().overviews().is_empty());
+    }
+
+    #[test]
+    fn test_compliance() {
+        let write = |tie_points: &[f64], geo_keys: &[u16]| {
+            let mut bytes = Vec::new();
+            let mut encoder = TiffEncoder::new(Cursor::new(&mut bytes)).unwrap();
+            let mut image = encoder.new_image::<colortype::Gray8>(4, 2).unwrap();
+            image
+                .encoder()
+                .write_tag(Tag::ModelPixelScaleTag, &[10.0, 10.0, 0.0][..])
+                .unwrap();
+            image
+                .encoder()
+                .write_tag(Tag::ModelTiepointTag, tie_points)
+                .unwrap();
+            let mut transformation = [0.0; 16];
+            transformation[..8].copy_from_slice(&[1.0, 0.0, 0.0, 100.0, 0.0, -1.0, 0.0, 200.0]);
+            transformation[15] = 1.0;
+            image
+                .encoder()
+                .write_tag(Tag::ModelTransformationTag, &transformation[..])
+                .unwrap();
+            image
+                .encoder()
+                .write_tag(Tag::GeoKeyDirectoryTag, geo_keys)
+                .unwrap();
+            image.write_data(&[1; 8]).unwrap();
+            bytes
+        };
+
+        // Both a tie point with a pixel scale and a transformation, and truncated GeoKeys
+        let bytes = write(&[0.0, 0.0, 0.0, 500.0, 900.0, 0.0], &[1, 1, 0]);
+        assert!(matches!(
+            GeoTiffReader::new(Cursor::new(bytes.clone())),
+            Err(GeoTiffError::GeoKey(_))
+        ));
+        let reader =
+            GeoTiffReader::with_compliance(Cursor::new(bytes), Compliance::Lenient).unwrap();
+        let metadata = reader.image_metadata(0).unwrap();
+        assert_eq!(
+            metadata.geotransform(),
+            Some([500.0, 10.0, 0.0, 900.0, 0.0, -10.0])
+        );
+        assert_eq!(metadata.warnings().len(), 2);
+        assert_eq!(metadata.epsg_code(), None);
+
+        // The transformation, as two tie points don't define one
+        let bytes = write(
+            &[
+                0.0, 0.0, 0.0, 500.0, 900.0, 0.0, 4.0, 2.0, 0.0, 540.0, 880.0, 0.0,
+            ],
+            &[1, 1, 0, 0],
+        );
+        let geotiff = GeoTiffReader::with_compliance(Cursor::new(bytes), Compliance::Lenient)
+            .unwrap()
+            .read()
+            .unwrap();
+        assert_eq!(
+            geotiff.geotransform(),
+            Some([100.0, 1.0, 0.0, 200.0, 0.0, -1.0])
+        );
+        assert_eq!(geotiff.warnings().len(), 1);
+        assert!(reader_tiled().metadata().warnings().is_empty());
+
+        // A GeoTIFF 1.1 directory with an ellipsoidal height code of GeoTIFF 1.0, which is kept
+        let tie_point = [0.0, 0.0, 0.0, 500.0, 900.0, 0.0];
+        let bytes = write(&tie_point, &[1, 1, 1, 1, 4096, 0, 1, 5030]);
+        assert!(matches!(
+            GeoTiffReader::new(Cursor::new(bytes.clone())),
+            Err(GeoTiffError::GeoKey(_))
+        ));
+        let reader =
+            GeoTiffReader::with_compliance(Cursor::new(bytes), Compliance::Lenient).unwrap();
+        let metadata = reader.image_metadata(0).unwrap();
+        assert_eq!(metadata.geotiff_version(), Some(GeoTiffVersion::V1_1));
+        assert_eq!(metadata.geo_key_directory().vertical_cs_type(), Some(5030));
+        assert!(metadata.warnings()[0].contains("GeoTIFF 1.0"));
+        let bytes = write(&tie_point, &[1, 2, 0, 1, 4096, 0, 1, 5703]);
+        let reader =
+            GeoTiffReader::with_compliance(Cursor::new(bytes), Compliance::Lenient).unwrap();
+        let metadata = reader.image_metadata(0).unwrap();
+        assert_eq!(metadata.geotiff_version(), None);
+        assert!(metadata.warnings()[0].contains("revision 1.2.0"));
+    }
+
+    #[test]
+    fn test_images() {
+        let mut bytes = Vec::new();
+        let mut encoder = TiffEncoder::new(Cursor::new(&mut bytes)).unwrap();
+        encoder
+            .write_image::<colortype::Gray8>(4, 2, &[1; 8])
+            .unwrap();
+        let mut image = encoder.new_image::<colortype::Gray16>(3, 3).unwrap();
+        image
+            .encoder()
+            .write_tag(Tag::ModelPixelScaleTag, &[10.0, 10.0, 0.0][..])
+            .unwrap();
+        image
+            .encoder()
+            .write_tag(
+                Tag::ModelTiepointTag,
+                &[0.0, 0.0, 0.0, 500.0, 900.0, 0.0][..],
+            )
+            .unwrap();
+        image.write_data(&[2u16; 9]).unwrap();
+
+        let mut reader = GeoTiffReader::new(Cursor::new(bytes)).unwrap();
+        assert_eq!(reader.image_count(), 2);
+        let metadata = reader.image_metadata(0).unwrap();
+        assert_eq!((metadata.raster_width, metadata.raster_height), (4, 2));
+        assert!(metadata.coordinate_transform().is_none());
+        let metadata = reader.image_metadata(1).unwrap();
+        assert_eq!(
+            metadata.model_extent(),
+            Some(Extent::new(500.0, 870.0, 530.0, 900.0))
+        );
+        assert!(reader.image_metadata(2).is_none());
+
+        reader.select_image(1).unwrap();
+        assert_eq!(reader.selected_image(), 1);
+        let geotiff = reader.read().unwrap();
+        assert_eq!(geotiff.raster_width, 3);
+        assert_eq!(geotiff.get_value_at::<u16>(2, 2, 0), 2);
+        assert_eq!(geotiff.transform_to_model([1, 1]), Some([510.0, 890.0]));
+
+        reader.select_image(0).unwrap();
+        assert_eq!(reader.read().unwrap().get_value_at::<u8>(3, 1, 0), 1);
+        assert!(reader.select_image(2).is_err());
+    }
+
+    #[test]
+    fn test_mask() {
+        // Writes a mask of 1 bit per pixel as bytes of 8 pixels each
+        fn write_mask<W: Write + Seek>(
+            encoder: &mut TiffEncoder<W>,
+            subfile_type: u32,
+            width: u32,
+            rows: &[u8],
+        ) {
+            let row_size = width.div_ceil(8);
+            let height = rows.len() as u32 / row_size;
+            let mut image = encoder
+                .new_image::<colortype::Gray8>(row_size, height)
+                .unwrap();
+            let directory = image.encoder();
+            directory
+                .write_tag(Tag::NewSubfileType, subfile_type)
+                .unwrap();
+            directory.write_tag(Tag::ImageWidth, width).unwrap();
+            directory.write_tag(Tag::BitsPerSample, 1u16).unwrap();
+            directory
+                .write_tag(Tag::PhotometricInterpretation, TRANSPARENCY_MASK as u16)
+                .unwrap();
+            image.write_data(rows).unwrap();
+        }
+
+        let mut bytes = Vec::new();
+        let mut encoder = TiffEncoder::new(Cursor::new(&mut bytes)).unwrap();
+        encoder
+            .write_image::<colortype::Gray8>(16, 4, &[1; 64])
+            .unwrap();
+        write_mask(&mut encoder, TRANSPARENCY_MASK, 16, &[0xf0, 0xff].repeat(4));
+        let mut overview = encoder.new_image::<colortype::Gray8>(8, 2).unwrap();
+        overview
+            .encoder()
+            .write_tag(Tag::NewSubfileType, REDUCED_RESOLUTION)
+            .unwrap();
+        overview.write_data(&[2; 16]).unwrap();
+        write_mask(
+            &mut encoder,
+            REDUCED_RESOLUTION | TRANSPARENCY_MASK,
+            8,
+            &[0x0f, 0x0f],
+        );
+
+        let mut masked = GeoTiffReader::new(Cursor::new(bytes)).unwrap();
+        assert_eq!(masked.image_count(), 1);
+        assert_eq!(masked.overviews().len(), 1);
+        assert!(masked.has_mask());
+        let geotiff = masked.read().unwrap();
+        let mask = geotiff.mask().unwrap();
+        assert_eq!(mask.len(), 64);
+        assert!(mask[..4].iter().all(|&valid| valid));
+        assert!(mask[4..8].iter().all(|&valid| !valid));
+        assert!(mask[8..16].iter().all(|&valid| valid));
+        assert!(geotiff.is_valid_at(3, 3));
+        assert!(!geotiff.is_valid_at(4, 3));
+        assert_eq!(geotiff.get_value_at_checked::<u8>(3, 3, 0), Some(1));
+        assert_eq!(geotiff.get_value_at_checked::<u8>(4, 3, 0), None);
+
+        let window = masked.read_window(2, 1, 4, 2).unwrap();
+        assert_eq!(
+            window.mask().unwrap(),
+            [true, true, false, false, true, true, false, false]
+        );
+        // The selected image is read again after the mask
+        assert_eq!(window.get_value_at::<u8>(3, 1, 0), 1);
+
+        masked.select_overview(Some(0)).unwrap();
+        let overview = masked.read().unwrap();
+        assert_eq!(overview.get_value_at::<u8>(0, 0, 0), 2);
+        assert!(!overview.is_valid_at(3, 1));
+        assert!(overview.is_valid_at(4, 1));
+
+        let unmasked = reader(GeoTiffWriter::new()).read().unwrap();
+        assert!(unmasked.mask().is_none());
+        assert!(unmasked.is_valid_at(0, 0));
+    }
+
+    #[test]
+    fn test_sub_byte_samples() {
+        // Writes packed rows of samples of the given size
+        fn write_packed(width: u32, bits_per_sample: u16, rows: &[u8], nodata: &str) -> Vec<u8> {
+            let row_size = (width * bits_per_sample as u32).div_ceil(8);
+            let mut bytes = Vec::new();
+            let mut encoder = TiffEncoder::new(Cursor::new(&mut bytes)).unwrap();
+            let mut image = encoder
+                .new_image::<colortype::Gray8>(row_size, rows.len() as u32 / row_size)
+                .unwrap();
+            let directory = image.encoder();
+            directory.write_tag(Tag::ImageWidth, width).unwrap();
+            directory
+                .write_tag(Tag::BitsPerSample, bits_per_sample)
+                .unwrap();
+            directory.write_tag(Tag::GdalNodata, nodata).unwrap();
+            image.rows_per_strip(2).unwrap();
+            image.write_data(rows).unwrap();
+            bytes
+        }
+
+        // 3 rows of 5 classes of 4 bits, the last being nodata
+        let rows = [0x01, 0x23, 0x40, 0x56, 0x78, 0x90, 0xab, 0xcd, 0xf0];
+        let mut classified =
+            GeoTiffReader::new(Cursor::new(write_packed(5, 4, &rows, "15"))).unwrap();
+        assert_eq!(classified.bits_per_sample().unwrap(), 4);
+        let geotiff = classified.read().unwrap();
+        assert_eq!(
+            geotiff.get_values::<u8>(Layout::PixelInterleaved),
+            [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 15]
+        );
+        assert_eq!(geotiff.nodata::<u8>(), Some(15));
+        assert_eq!(geotiff.get_value_at_checked::<u8>(4, 2, 0), None);
+        let window = classified.read_window(1, 1, 3, 2).unwrap();
+        assert_eq!(
+            window.get_values::<u8>(Layout::PixelInterleaved),
+            [6, 7, 8, 11, 12, 13]
+        );
+        assert_eq!(classified.read_packed().unwrap(), rows);
+
+        // Unused bits at the end of the rows are cleared
+        let mut bilevel = GeoTiffReader::new(Cursor::new(write_packed(
+            10,
+            1,
+            &[0xa5, 0xff, 0x0f, 0x00],
+            "0",
+        )))
+        .unwrap();
+        let geotiff = bilevel.read().unwrap();
+        assert_eq!(
+            geotiff.get_values::<u8>(Layout::PixelInterleaved),
+            [1, 0, 1, 0, 0, 1, 0, 1, 1, 1, 0, 0, 0, 0, 1, 1, 1, 1, 0, 0]
+        );
+        assert_eq!(bilevel.read_packed().unwrap(), [0xa5, 0xc0, 0x0f, 0x00]);
+    }
+
+    #[test]
+    fn test_alpha() {
+        let mut bytes = Vec::new();
+        let mut encoder = TiffEncoder::new(Cursor::new(&mut bytes)).unwrap();
+        let mut image = encoder.new_image::<colortype::RGBA8>(2, 1).unwrap();
+        image
+            .encoder()
+            .write_tag(Tag::ExtraSamples, &[2u16][..])
+            .unwrap();
+        image.write_data(&[10, 20, 30, 255, 40, 50, 60, 0]).unwrap();
+        encoder
+            .write_image::<colortype::RGB8>(2, 1, &[0; 6])
+            .unwrap();
+
+        let mut rgba = GeoTiffReader::new(Cursor::new(bytes)).unwrap();
+        let mut geotiff = rgba.read().unwrap();
+        assert!(geotiff.has_alpha());
+        assert!(geotiff.is_valid_at(1, 0));
+        geotiff.set_zero_alpha_as_nodata(true);
+        assert!(geotiff.is_valid_at(0, 0));
+        assert!(!geotiff.is_valid_at(1, 0));
+
+        rgba.select_image(1).unwrap();
+        let mut geotiff = rgba.read().unwrap();
+        assert!(!geotiff.has_alpha());
+        geotiff.set_zero_alpha_as_nodata(true);
+        assert!(geotiff.is_valid_at(1, 0));
+    }
+
+    #[test]
+    fn test_apply_scale_offset() {
+        let mut bytes = Vec::new();
+        let mut encoder = TiffEncoder::new(Cursor::new(&mut bytes)).unwrap();
+        let mut image = encoder.new_image::<colortype::Gray16>(3, 1).unwrap();
+        image
+            .encoder()
+            .write_tag(
+                Tag::Unknown(crate::GDAL_METADATA_TAG),
+                "<GDALMetadata>\n  <Item name=\"SCALE\" sample=\"0\" role=\"scale\">0.5</Item>\n  \
+                <Item name=\"OFFSET\" sample=\"0\" role=\"offset\">-10</Item>\n</GDALMetadata>",
+            )
+            .unwrap();
+        image.encoder().write_tag(Tag::GdalNodata, "0").unwrap();
+        image.write_data(&[0u16, 20, 40]).unwrap();
+
+        let mut scaled = GeoTiffReader::new(Cursor::new(bytes)).unwrap();
+        let geotiff = scaled.read().unwrap();
+        assert_eq!(geotiff.band_scale_offset(0), Some([0.5, -10.0]));
+        assert_eq!(geotiff.get_value_at::<u16>(1, 0, 0), 20);
+
+        scaled.set_apply_scale_offset(true);
+        let geotiff = scaled.read_window(1, 0, 2, 1).unwrap();
+        assert_eq!(geotiff.band_scale_offset(0), None);
+        assert_eq!(geotiff.get_value_at::<f64>(0, 0, 0), 0.0);
+        assert_eq!(geotiff.get_value_at::<f64>(1, 0, 0), 10.0);
+        let geotiff = scaled.read().unwrap();
+        assert!(geotiff.get_value_at::<f64>(0, 0, 0).is_nan());
+        assert_eq!(geotiff.get_value_at_checked::<f64>(0, 0, 0), None);
+
+        let mut geotiff = reader(GeoTiffWriter::new())
+            .read_window(0, 1, 2, 1)
+            .unwrap();
+        assert_eq!(geotiff.band_scale_offset(0), None);
+        geotiff.set_band_scale_offset(0, Some([0.5, 1.0]));
+        geotiff.apply_scale_offset();
+        assert_eq!(geotiff.get_value_at::<f64>(1, 0, 0), 21.5);
+    }
+
+    #[test]
+    fn test_statistics() {
+        let mut striped = reader(GeoTiffWriter::new().with_strips(3));
+        let statistics = striped.statistics(0, false).unwrap().unwrap();
+        assert_eq!((statistics.min, statistics.max), (0.0, 799.0));
+        assert_eq!(statistics.mean, 399.5);
+        assert!((statistics.std_dev - (639999.0f64 / 12.0).sqrt()).abs() < 1e-9);
+        assert_eq!(statistics.count, 800);
+        assert_eq!(striped.read().unwrap().statistics(0), Some(statistics));
+        assert!(striped.statistics(1, false).is_err());
+
+        let mut nodata = reader(GeoTiffWriter::new().with_nodata(0.0));
+        let statistics = nodata.statistics(0, false).unwrap().unwrap();
+        assert_eq!((statistics.min, statistics.count), (1.0, 799));
+
+        let data = (0..120 * 100).map(|value| value as u16).collect::<Vec<_>>();
+        let mut bytes = Vec::new();
+        GeoTiffWriter::new()
+            .add_overviews(&[2, 4], Resampling::Nearest)
+            .write(&mut bytes, 120, 100, 1, &data)
+            .unwrap();
+        let mut overviews = GeoTiffReader::new(Cursor::new(bytes)).unwrap();
+        let statistics = overviews.statistics(0, true).unwrap().unwrap();
+        // The overview of 60x50 pixels is the smallest of at least 2500 pixels
+        assert_eq!(statistics.count, 3000);
+        assert_eq!(overviews.selected_overview(), None);
+        assert_eq!(
+            overviews.statistics(0, false).unwrap().unwrap().count,
+            12000
+        );
+    }
+
+    #[test]
+    fn test_histogram() {
+        let mut tiled = reader_tiled();
+        assert_eq!(
+            tiled.histogram(0, 4, [0.0, 800.0]).unwrap(),
+            [200, 200, 200, 200]
+        );
+        // Values outside the range are not counted, the maximum falls into the last bin
+        assert_eq!(tiled.histogram(0, 2, [100.0, 199.0]).unwrap(), [50, 50]);
+        assert_eq!(
+            tiled.read().unwrap().histogram(0, 3, [0.0, 799.0]).unwrap(),
+            tiled.histogram(0, 3, [0.0, 799.0]).unwrap()
+        );
+        assert!(tiled.histogram(0, 0, [0.0, 1.0]).is_err());
+        assert!(tiled.histogram(0, 1, [1.0, 1.0]).is_err());
+        assert!(tiled.histogram(1, 1, [0.0, 1.0]).is_err());
+
+        let mut nodata = reader(GeoTiffWriter::new().with_nodata(0.0));
+        assert_eq!(nodata.histogram(0, 1, [0.0, 799.0]).unwrap(), [799]);
+    }
+
+    #[test]
+    fn test_read_bands() {
+        let (width, height) = (4, 2);
+        // Band b of pixel i has the value 100 * b + i
+        let planes = (0..3u16)
+            .flat_map(|band| (0..width * height).map(move |pixel| 100 * band + pixel))
+            .collect::<Vec<_>>();
+        let mut bytes = Vec::new();
+        let mut encoder = TiffEncoder::new(Cursor::new(&mut bytes)).unwrap();
+        // The planes are written as a grayscale image of three strips
+        let mut image = encoder
+            .new_image::<colortype::Gray16>(width as u32, 3 * height as u32)
+            .unwrap();
+        image.rows_per_strip(height as u32).unwrap();
+        let directory = image.encoder();
+        directory
+            .write_tag(Tag::ImageLength, height as u32)
+            .unwrap();
+        directory.write_tag(Tag::SamplesPerPixel, 3u16).unwrap();
+        directory
+            .write_tag(Tag::BitsPerSample, &[16u16, 16, 16][..])
+            .unwrap();
+        directory
+            .write_tag(Tag::SampleFormat, &[1u16, 1, 1][..])
+            .unwrap();
+        directory
+            .write_tag(Tag::PhotometricInterpretation, 2u16)
+            .unwrap();
+        directory
+            .write_tag(
+                Tag::PlanarConfiguration,
+                PlanarConfiguration::Planar.to_u16(),
+            )
+            .unwrap();
+        image.write_data(&planes).unwrap();
+        let mut planar = GeoTiffReader::new(Cursor::new(bytes)).unwrap();
+
+        let pixels = (0..width * height)
+            .flat_map(|pixel| (0..3).map(move |band| 100 * band + pixel))
+            .collect::<Vec<_>>();
+        let mut bytes = Vec::new();
+        GeoTiffWriter::new()
+            .write(&mut bytes, width as usize, height as usize, 3, &pixels)
+            .unwrap();
+        let mut chunky = GeoTiffReader::new(Cursor::new(bytes)).unwrap();
+
+        for bands in [&mut planar, &mut chunky] {
+            assert_eq!(
+                bands
+                    .read_bands::<u16>([1, 0, 2, 2], &[2, 0], Layout::BandSequential)
+                    .unwrap(),
+                [201, 202, 205, 206, 1, 2, 5, 6]
+            );
+            assert_eq!(
+                bands
+                    .read_bands::<u16>([1, 0, 2, 2], &[2, 0], Layout::PixelInterleaved)
+                    .unwrap(),
+                [201, 1, 202, 2, 205, 5, 206, 6]
+            );
+            let geotiff = bands.read().unwrap();
+            assert_eq!(geotiff.num_samples, 3);
+            assert_eq!(geotiff.get_value_at::<u16>(3, 1, 1), 107);
+            let band = bands.read_band(1).unwrap();
+            assert_eq!(band.num_samples, 1);
+            assert_eq!(band.get_value_at::<u16>(3, 1, 0), 107);
+            assert!(bands
+                .read_bands::<u16>([0, 0, 1, 1], &[3], Layout::BandSequential)
+                .is_err());
+            assert!(bands
+                .read_bands::<u16>([0, 0, 1, 1], &[], Layout::BandSequential)
+                .is_err());
+        }
+    }
+
+    #[test]
+    fn test_sample_many() {
+        // Points in every tile, on tile edges, at the raster edges and outside
+        let coords = (0..45)
+            .flat_map(|x| (0..22).map(move |y| [995.0 + x as f64 * 9.7, 2005.0 - y as f64 * 10.3]))
+            .collect::<Vec<_>>();
+        for mut buffered in [reader(GeoTiffWriter::new()), reader_tiled()] {
+            let geotiff = buffered.read().unwrap();
+            for interpolation in [
+                Interpolation::Nearest,
+                Interpolation::Bilinear,
+                Interpolation::Cubic,
+            ] {
+                let values = buffered.sample_many(&coords, 0, interpolation).unwrap();
+                let expected = coords
+                    .iter()
+                    .map(|&coord| geotiff.sample(coord, 0, interpolation))
+                    .collect::<Vec<_>>();
+                assert_eq!(values, expected);
+            }
+            assert!(buffered
+                .sample_many(&coords, 1, Interpolation::Nearest)
+                .is_err());
+        }
+    }
+
+    #[test]
+    fn test_query_model_point() {
+        let mut reader = reader(
+            GeoTiffWriter::new()
+                .with_nodata(45.0)
+                .add_overviews(&[2], Resampling::Nearest),
+        );
+        let query = reader.query_model_point(1075.0, 1985.0).unwrap();
+        assert_eq!(
+            query,
+            PointQuery {
+                column: 7,
+                row: 1,
+                values: vec![Some(47.0)],
+                overview: None,
+            }
+        );
+        assert_eq!(
+            reader.query_model_point(1055.0, 1985.0).unwrap().values,
+            vec![None]
+        );
+        assert!(matches!(
+            reader.query_model_point(995.0, 1985.0),
+            Err(GeoTiffError::OutOfBounds(_))
+        ));
+        assert!(reader.query_model_point(1075.0, 1800.0).is_err());
+
+        reader.select_overview(Some(0)).unwrap();
+        let query = reader.query_model_point(1075.0, 1985.0).unwrap();
+        assert_eq!((query.column, query.row), (3, 0));
+        assert_eq!(query.overview, Some(0));
+    }
+
+    #[test]
+    fn test_read_window_into() {
+        for mut buffered in [reader(GeoTiffWriter::new()), reader_tiled()] {
+            let mut buffer = vec![0u16; 40 * 20];
+            for [x, y, width, height] in [[0, 0, 40, 20], [3, 1, 35, 18], [16, 0, 16, 16]] {
+                let buffer = &mut buffer[..width * height];
+                buffered
+                    .read_window_into(x, y, width, height, buffer)
+                    .unwrap();
+                let window = buffered.read_window(x, y, width, height).unwrap();
+                assert_eq!(buffer, window.get_values::<u16>(Layout::PixelInterleaved));
+            }
+            assert!(buffered
+                .read_window_into(0, 0, 2, 2, &mut [0u16; 3])
+                .is_err());
+            assert!(buffered
+                .read_window_into(0, 0, 2, 2, &mut [0i16; 4])
+                .is_err());
+            assert!(buffered
+                .read_window_into(39, 0, 2, 2, &mut [0u16; 4])
+                .is_err());
+        }
+
+        // Planar rasters are decoded by band
+        let mut planar = reader(GeoTiffWriter::new().with_planar(true));
+        let mut buffer = [0u16; 4];
+        planar.read_window_into(38, 18, 2, 2, &mut buffer).unwrap();
+        assert_eq!(buffer, [758, 759, 798, 799]);
+    }
+
+    #[test]
+    fn test_read_window_values() {
+        let mut typed = reader(GeoTiffWriter::new());
+        assert_eq!(
+            typed.read_window_values::<u16>(38, 19, 2, 1).unwrap(),
+            [798, 799]
+        );
+        assert!(matches!(
+            typed.read_window_values::<f32>(38, 19, 2, 1),
+            Err(GeoTiffError::InvalidArgument(_))
+        ));
+        assert!(typed.read_window_values::<i16>(38, 19, 2, 1).is_err());
+        assert!(typed.read_window_values::<u8>(38, 19, 2, 1).is_err());
+
+        let geotiff = typed.read().unwrap();
+        assert_eq!(geotiff.try_get_value_at::<u16>(39, 0, 0).unwrap(), 39);
+        assert!(geotiff.try_get_value_at::<u16>(40, 0, 0).is_err());
+        assert!(geotiff.try_get_value_at::<u16>(0, 0, 1).is_err());
+        assert!(geotiff.try_get_value_at::<u64>(39, 0, 0).is_err());
+        assert_eq!(
+            geotiff
+                .try_get_values::<u16>(Layout::PixelInterleaved)
+                .unwrap()[799],
+            799
+        );
+        assert!(geotiff
+            .try_get_values::<f64>(Layout::PixelInterleaved)
+            .is_err());
+    }
+
+    #[test]
+    fn test_read_band() {
+        let (width, height, num_samples) = (20, 10, 200);
+        // Band b of pixel i has the value 100 * b + i
+        let data = (0..width * height)
+            .flat_map(|pixel| (0..num_samples).map(move |band| (100 * band + pixel) as u16))
+            .collect::<Vec<_>>();
+        let mut bytes = Vec::new();
+        GeoTiffWriter::new()
+            .with_tiles(16, 16)
+            .with_compression(Compression::Deflate { level: 6 })
+            .with_predictor(Predictor::Horizontal)
+            .write(&mut bytes, width, height, num_samples, &data)
+            .unwrap();
+        let mut hyperspectral = GeoTiffReader::new(Cursor::new(bytes)).unwrap();
+
+        let band = hyperspectral.read_band_window(42, 15, 5, 3, 2).unwrap();
+        assert_eq!((band.raster_width, band.raster_height), (3, 2));
+        assert_eq!(band.num_samples, 1);
+        assert_eq!(
+            band.get_window_values_checked::<u16>(0, 0, 3, 2, 0),
+            [4315, 4316, 4317, 4335, 4336, 4337].map(Some)
+        );
+        let band = hyperspectral.read_band(199).unwrap();
+        assert_eq!(band.get_value_at::<u16>(19, 9, 0), 20099);
+        assert!(hyperspectral.read_band(200).is_err());
+        let geotiff = hyperspectral.read().unwrap();
+        assert_eq!(geotiff.get_value_at::<u16>(19, 9, 42), 4399);
+
+        let data = (0..8 * 4 * 5)
+            .map(|value| value as f32 / 4.0)
+            .collect::<Vec<_>>();
+        let mut bytes = Vec::new();
+        GeoTiffWriter::new()
+            .with_strips(3)
+            .with_compression(Compression::Lzw)
+            .with_predictor(Predictor::FloatingPoint)
+            .write(&mut bytes, 8, 4, 5, &data)
+            .unwrap();
+        let band = GeoTiffReader::new(Cursor::new(bytes))
+            .unwrap()
+            .read_band(3)
+            .unwrap();
+        assert_eq!(band.get_value_at::<f32>(0, 0, 0), 0.75);
+        assert_eq!(band.get_value_at::<f32>(7, 3, 0), 39.5);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_read_zstd() {
+        let data = (0..30 * 20 * 3)
+            .map(|value| value as u8)
+            .collect::<Vec<_>>();
+        let mut bytes = Vec::new();
+        GeoTiffWriter::new()
+            .with_tiles(16, 16)
+            .with_compression(Compression::Zstd { level: 3 })
+            .with_predictor(Predictor::Horizontal)
+            .write(&mut bytes, 30, 20, 3, &data)
+            .unwrap();
+        let mut rgb = GeoTiffReader::new(Cursor::new(bytes)).unwrap();
+        let geotiff = rgb.read().unwrap();
+        assert_eq!(geotiff.get_values::<u8>(Layout::PixelInterleaved), data);
+        let window = rgb.read_window(14, 15, 4, 3).unwrap();
+        assert_eq!(
+            window.get_value_at::<u8>(3, 2, 1),
+            ((17 * 3 + 1 + 30 * 17 * 3) % 256) as u8
+        );
+        let band = rgb.read_band(2).unwrap();
+        assert_eq!(band.get_value_at::<u8>(29, 19, 0), data[data.len() - 1]);
+
+        let data = (0..8 * 4)
+            .map(|value| value as f64 / 8.0)
+            .collect::<Vec<_>>();
+        let mut bytes = Vec::new();
+        GeoTiffWriter::new()
+            .with_strips(3)
+            .with_compression(Compression::Zstd { level: 9 })
+            .with_predictor(Predictor::FloatingPoint)
+            .write(&mut bytes, 8, 4, 1, &data)
+            .unwrap();
+        let geotiff = GeoTiffReader::new(Cursor::new(bytes))
+            .unwrap()
+            .read()
+            .unwrap();
+        assert_eq!(geotiff.get_values::<f64>(Layout::PixelInterleaved), data);
+    }
+
+    #[cfg(feature = "webp")]
+    #[test]
+    fn test_read_webp() {
+        for num_samples in [3, 4] {
+            let data = (0..30 * 20 * num_samples)
+                .map(|value| (value * 7 % 256) as u8)
+                .collect::<Vec<_>>();
+            for writer in [
+                GeoTiffWriter::new().with_tiles(16, 16),
+                GeoTiffWriter::new().with_strips(7),
+            ] {
+                let mut bytes = Vec::new();
+                writer
+                    .with_compression(Compression::WebP)
+                    // Ignored, as WebP has its own prediction
+                    .with_predictor(Predictor::Horizontal)
+                    .write(&mut bytes, 30, 20, num_samples, &data)
+                    .unwrap();
+                let mut reader = GeoTiffReader::new(Cursor::new(bytes)).unwrap();
+                let geotiff = reader.read().unwrap();
+                assert_eq!(geotiff.num_samples, num_samples);
+                assert_eq!(geotiff.get_values::<u8>(Layout::PixelInterleaved), data);
+                let window = reader.read_window(14, 15, 4, 3).unwrap();
+                assert_eq!(
+                    window.get_value_at::<u8>(3, 2, 1),
+                    data[(17 * 30 + 17) * num_samples + 1]
+                );
+                let band = reader.read_band(num_samples - 1).unwrap();
+                assert_eq!(band.get_value_at::<u8>(29, 19, 0), data[data.len() - 1]);
+            }
+        }
+
+        // WebP only stores RGB(A) images of 8 bits
+        let writer = GeoTiffWriter::new().with_compression(Compression::WebP);
+        assert!(writer.write(&mut Vec::new(), 4, 4, 1, &[0u8; 16]).is_err());
+        assert!(writer.write(&mut Vec::new(), 4, 4, 3, &[0u16; 48]).is_err());
+        assert!(writer
+            .with_planar(true)
+            .write(&mut Vec::new(), 4, 4, 3, &[0u8; 48])
+            .is_err());
+    }
+
+    #[test]
+    fn test_planar() {
+        // Band b of pixel i has the value 1000 * b + i
+        let data = (0..40 * 20)
+            .flat_map(|pixel| (0..3).map(move |band| (1000 * band + pixel) as u16))
+            .collect::<Vec<_>>();
+        for writer in [
+            GeoTiffWriter::new().with_strips(7),
+            GeoTiffWriter::new()
+                .with_tiles(16, 16)
+                .with_compression(Compression::Deflate { level: 6 })
+                .with_predictor(Predictor::Horizontal),
+        ] {
+            let mut bytes = Vec::new();
+            writer
+                .with_planar(true)
+                .add_overviews(&[2], Resampling::Nearest)
+                .write(&mut bytes, 40, 20, 3, &data)
+                .unwrap();
+            let geotiff = GeoTiff::read(Cursor::new(bytes.as_slice())).unwrap();
+            assert_eq!(geotiff.num_samples, 3);
+            assert_eq!(geotiff.get_value_at::<u16>(17, 3, 1), 1137);
+
+            let mut planar = GeoTiffReader::new(Cursor::new(bytes)).unwrap();
+            assert!(planar.is_planar().unwrap());
+            let window = planar.read_window(30, 15, 10, 5).unwrap();
+            assert_eq!(window.get_value_at::<u16>(0, 0, 0), 630);
+            assert_eq!(window.get_value_at::<u16>(9, 4, 2), 2799);
+            for block in planar.blocks() {
+                let block = block.unwrap();
+                let pixel = (block.y * 40 + block.x) as u16;
+                assert_eq!(block.geotiff.get_value_at::<u16>(0, 0, 2), 2000 + pixel);
+            }
+            let statistics = planar.statistics(1, false).unwrap().unwrap();
+            assert_eq!((statistics.min, statistics.max), (1000.0, 1799.0));
+
+            planar.select_overview(Some(0)).unwrap();
+            let overview = planar.read().unwrap();
+            assert_eq!(overview.num_samples, 3);
+            // The nearest neighbour of pixel (1, 1) is pixel (3, 3) of the full-resolution raster
+            assert_eq!(overview.get_value_at::<u16>(1, 1, 2), 2000 + 3 * 40 + 3);
+        }
+    }
+
+    #[test]
+    fn test_block_cache() {
+        let data = (0..40 * 20)
+            .flat_map(|pixel| (0..3).map(move |band| (1000 * band + pixel) as u16))
+            .collect::<Vec<_>>();
+        for writer in [
+            GeoTiffWriter::new().with_tiles(16, 16),
+            GeoTiffWriter::new().with_strips(7).with_planar(true),
+        ] {
+            let mut bytes = Vec::new();
+            writer
+                .add_overviews(&[2], Resampling::Nearest)
+                .write(&mut bytes, 40, 20, 3, &data)
+                .unwrap();
+            let mut uncached = GeoTiffReader::new(Cursor::new(bytes.clone())).unwrap();
+            let mut reader = GeoTiffReader::new(Cursor::new(bytes)).unwrap();
+            reader.set_block_cache(Some(BlockCacheCapacity::Blocks(4)));
+
+            let window = reader.read_window(10, 5, 20, 10).unwrap();
+            assert_eq!(window.get_value_at::<u16>(0, 0, 1), 1210);
+            let cached = reader.block_cache.as_ref().unwrap().len();
+            assert!(cached > 0 && cached <= 4);
+            // Decoded again from the cache
+            let window = reader.read_band_window(2, 10, 5, 20, 10).unwrap();
+            assert_eq!(window.get_value_at::<u16>(19, 9, 0), 2589);
+            let mut buffer = vec![0; 20 * 10 * 3];
+            reader.read_window_into(10, 5, 20, 10, &mut buffer).unwrap();
+            assert_eq!(
+                buffer,
+                uncached.read_window_values::<u16>(10, 5, 20, 10).unwrap()
+            );
+
+            // The blocks of the overview are distinct from those of the full resolution
+            reader.select_overview(Some(0)).unwrap();
+            uncached.select_overview(Some(0)).unwrap();
+            assert_eq!(
+                reader.read_window_values::<u16>(0, 0, 20, 10).unwrap(),
+                uncached.read_window_values::<u16>(0, 0, 20, 10).unwrap()
+            );
+            assert!(reader.block_cache.as_ref().unwrap().len() <= 4);
+
+            reader.set_block_cache(Some(BlockCacheCapacity::Bytes(0)));
+            reader.read_window(0, 0, 20, 10).unwrap();
+            assert!(reader.block_cache.as_ref().unwrap().len() == 0);
+        }
+    }
+
+    #[test]
+    fn test_read_window_at_resolution() {
+        let mut reader = reader(GeoTiffWriter::new().add_overviews(&[2, 4], Resampling::Nearest));
+        let bbox = Extent::new(1000.0, 1800.0, 1400.0, 2000.0);
+
+        let window = reader.read_window_at_resolution(bbox, 40.0).unwrap();
+        assert_eq!((window.raster_width, window.raster_height), (10, 5));
+        assert_eq!(window.resolution(), Some([40.0, 40.0]));
+
+        // The overview with factor 2 is resampled from 20x10 pixels
+        let window = reader.read_window_at_resolution(bbox, 30.0).unwrap();
+        assert_eq!((window.raster_width, window.raster_height), (13, 7));
+        assert_eq!(window.model_extent(), Some(bbox));
+
+        // The overview with factor 4 is resampled from 10x5 pixels, whose pixel (1, 0) is the
+        // full-resolution pixel (6, 2)
+        let window = reader.read_window_at_resolution(bbox, 80.0).unwrap();
+        assert_eq!((window.raster_width, window.raster_height), (5, 3));
+        assert_eq!(window.get_value_at::<u16>(0, 0, 0), 2 * 40 + 6);
+
+        // Finer pixel sizes yield the full resolution
+        let window = reader
+            .read_window_at_resolution(Extent::new(1000.0, 1990.0, 1020.0, 2000.0), 5.0)
+            .unwrap();
+        assert_eq!((window.raster_width, window.raster_height), (2, 1));
+        assert_eq!(reader.selected_overview(), None);
+        assert!(reader.read_window_at_resolution(bbox, 0.0).is_err());
+    }
+
+    #[test]
+    fn test_rotated() {
+        // Rotated by 30 degrees
+        let transform = CoordinateTransform::from_geotransform([
+            1000.0, 8.660254, -5.0, 2000.0, -5.0, -8.660254,
+        ])
+        .unwrap();
+        let data = (0..40 * 20).map(|value| value as u16).collect::<Vec<_>>();
+        let mut bytes = Vec::new();
+        GeoTiffWriter::new()
+            .with_tiles(16, 16)
+            .with_coordinate_transform(transform)
+            .write(&mut bytes, 40, 20, 1, &data)
+            .unwrap();
+        let mut reader = GeoTiffReader::new(Cursor::new(bytes)).unwrap();
+        let geotiff = reader.read().unwrap();
+        assert!(geotiff.has_rotation() && !geotiff.is_north_up());
+        assert_eq!(
+            geotiff.resolution().map(|[x, y]| [x.round(), y.round()]),
+            Some([10.0, 10.0])
+        );
+
+        // The extent is spanned by the corners (0, 20) and (40, 0)
+        let extent = geotiff.model_extent().unwrap();
+        assert!((extent.min_x - 900.0).abs() < 1e-3 && (extent.max_x - 1346.41).abs() < 1e-3);
+        assert!((extent.min_y - 1626.79).abs() < 1e-2 && (extent.max_y - 2000.0).abs() < 1e-9);
+
+        // Around the center of pixel (10, 5)
+        let transform = geotiff.coordinate_transform().unwrap();
+        let [x, y] = transform.transform_to_model_f64([10.5, 5.5]);
+        let window = reader
+            .read_bbox(x - 1.0, y - 1.0, x + 1.0, y + 1.0)
+            .unwrap();
+        assert_eq!((window.raster_width, window.raster_height), (1, 1));
+        assert_eq!(window.get_value_at::<u16>(0, 0, 0), 5 * 40 + 10);
+        let [window_x, window_y] = window
+            .coordinate_transform()
+            .unwrap()
+            .transform_to_model_f64([0.5, 0.5]);
+        assert!((window_x - x).abs() < 1e-9 && (window_y - y).abs() < 1e-9);
+
+        let window = reader
+            .read_window_at_resolution(Extent::new(x, y - 10.0, x + 10.0, y), 20.0)
+            .unwrap();
+        assert_eq!(
+            window.resolution().map(|[x, y]| [x.round(), y.round()]),
+            Some([20.0, 20.0])
+        );
+
+        let values = reader
+            .read_window_resampled([0, 0, 40, 20], 20, 10, Resampling::Nearest)
+            .unwrap();
+        assert_eq!(values.get_value_at::<u16>(5, 2, 0), 5 * 40 + 11);
+        assert_eq!(
+            reader
+                .sample_many(&[[x, y]], 0, Interpolation::Bilinear)
+                .unwrap(),
+            [geotiff.sample([x, y], 0, Interpolation::Bilinear)]
+        );
+    }
+
+    #[test]
+    fn test_read_window_resampled() {
+        let mut reader = reader(GeoTiffWriter::new().add_overviews(&[2, 4], Resampling::Nearest));
+
+        // The overview with factor 4, whose pixel (0, 0) is the full-resolution pixel (2, 2)
+        let window = reader
+            .read_window_resampled([0, 0, 40, 20], 10, 5, Resampling::Nearest)
+            .unwrap();
+        assert_eq!((window.raster_width, window.raster_height), (10, 5));
+        assert_eq!(
+            window.geotransform(),
+            Some([1000.0, 40.0, 0.0, 2000.0, 0.0, -40.0])
+        );
+        assert_eq!(window.get_value_at::<u16>(0, 0, 0), 2 * 40 + 2);
+        assert_eq!(reader.selected_overview(), None);
+
+        // The full-resolution pixels (5 + 2 * x, 3 + 2 * y)
+        let window = reader
+            .read_window_resampled([4, 2, 8, 4], 4, 2, Resampling::Nearest)
+            .unwrap();
+        assert_eq!(
+            window.get_values::<u16>(Layout::PixelInterleaved),
+            [125, 127, 129, 131, 205, 207, 209, 211]
+        );
+        assert_eq!(
+            window.model_extent(),
+            Some(Extent::new(1040.0, 1940.0, 1120.0, 1980.0))
+        );
+
+        // Averages of 2x2 pixels of the full resolution, rounded half away from zero
+        let mut full_resolution = reader_tiled();
+        let window = full_resolution
+            .read_window_resampled([0, 0, 40, 20], 20, 10, Resampling::Average)
+            .unwrap();
+        let expected = (0..10)
+            .flat_map(|y| (0..20).map(move |x| 80 * y + 2 * x + 21))
+            .collect::<Vec<u16>>();
+        assert_eq!(window.get_values::<u16>(Layout::PixelInterleaved), expected);
+
+        // Target pixels of 4/3 pixels, partially covering 2x2 pixels
+        let window = full_resolution
+            .read_window_resampled([0, 0, 4, 4], 3, 3, Resampling::Average)
+            .unwrap();
+        let [resolution_x, resolution_y] = window.resolution().unwrap();
+        assert!(
+            (resolution_x - 40.0 / 3.0).abs() < 1e-9 && (resolution_y - 40.0 / 3.0).abs() < 1e-9
+        );
+        // (0 + 1 / 3 + 40 / 3 + 41 / 9) / (16 / 9)
+        assert_eq!(window.get_value_at::<u16>(0, 0, 0), 10);
+        assert!(full_resolution
+            .read_window_resampled([0, 0, 4, 4], 0, 3, Resampling::Average)
+            .is_err());
+        assert!(full_resolution
+            .read_window_resampled([38, 0, 4, 4], 2, 2, Resampling::Average)
+            .is_err());
+    }
+
+    #[test]
+    fn test_blocks() {
+        let blocks = reader_tiled()
+            .blocks()
+            .collect::<GeoTiffResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(blocks.len(), 6);
+        let windows = blocks
+            .iter()
+            .map(|block| [block.x, block.y, block.width(), block.height()])
+            .collect::<Vec<_>>();
+        assert_eq!(
+            windows,
+            [
+                [0, 0, 16, 16],
+                [16, 0, 16, 16],
+                [32, 0, 8, 16],
+                [0, 16, 16, 4],
+                [16, 16, 16, 4],
+                [32, 16, 8, 4]
+            ]
+        );
+        let block = &blocks[5];
+        assert_eq!(block.geotiff.get_value_at::<u16>(0, 0, 0), 16 * 40 + 32);
+        assert_eq!(
+            block.model_extent,
+            Some(Extent::new(1320.0, 1800.0, 1400.0, 1840.0))
+        );
+
+        let mut reader = reader(GeoTiffWriter::new().with_strips(8));
+        let mut blocks = reader.blocks();
+        assert_eq!(blocks.len(), 3);
+        blocks.next();
+        let block = blocks.next().unwrap().unwrap();
+        assert_eq!(
+            [block.x, block.y, block.width(), block.height()],
+            [0, 8, 40, 8]
+        );
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks.next().unwrap().unwrap().height(), 4);
+        assert!(blocks.next().is_none());
+    }
+
+    #[test]
+    fn test_window_chunks() {
+        let tiled = reader_tiled();
+        assert_eq!(tiled.window_chunks([10, 10, 10, 10]), [0, 1, 3, 4]);
+        assert_eq!(tiled.window_chunks([32, 0, 8, 20]), [2, 5]);
+
+        let striped = reader(GeoTiffWriter::new().with_strips(8));
+        assert_eq!(striped.window_chunks([5, 7, 1, 2]), [0, 1]);
+    }
+
+    #[test]
+    fn test_chunk_byte_range() {
+        let mut striped = reader(GeoTiffWriter::new().with_strips(8));
+        assert!(striped.is_uncompressed().unwrap());
+        // The last strip contains 4 rows of 40 u16 samples
+        let range = striped.chunk_byte_range(2).unwrap();
+        assert_eq!(range.end - range.start, 4 * 40 * 2);
+        assert!(striped.chunk_byte_range(3).is_err());
+
+        let mut compressed = reader(GeoTiffWriter::new().with_compression(Compression::Lzw));
+        assert!(!compressed.is_uncompressed().unwrap());
+    }
+
+    #[test]
+    fn test_sparse() {
+        // The upper left 16 x 16 pixels of both bands are nodata
+        let data = (0..40 * 20 * 2)
+            .map(|index| match index / 2 % 40 < 16 && index / 80 < 16 {
+                true => 9999,
+                false => index as u16,
+            })
+            .collect::<Vec<_>>();
+        for writer in [
+            GeoTiffWriter::new().with_tiles(16, 16),
+            GeoTiffWriter::new().with_tiles(16, 16).with_planar(true),
+        ] {
+            let mut bytes = Vec::new();
+            writer
+                .with_nodata(9999.0)
+                .with_sparse(true)
+                .write(&mut bytes, 40, 20, 2, &data)
+                .unwrap();
+            let mut sparse = GeoTiffReader::new(Cursor::new(bytes)).unwrap();
+            assert_eq!(sparse.chunk_byte_range(0).unwrap(), 0..0);
+            assert!(!sparse.chunk_byte_range(1).unwrap().is_empty());
+
+            let geotiff = sparse.read_window(0, 0, 40, 20).unwrap();
+            assert_eq!(geotiff.get_values::<u16>(Layout::PixelInterleaved), data);
+            let mut buffer = vec![0u16; 20 * 2 * 2];
+            sparse.read_window_into(10, 14, 20, 2, &mut buffer).unwrap();
+            assert_eq!(buffer[..2], [9999, 9999]);
+            let value = (14 * 40 + 26) * 2;
+            assert_eq!(buffer[2 * 16..2 * 16 + 2], [value, value + 1]);
+            let band = sparse.read_band(1).unwrap();
+            assert_eq!(band.get_value_at::<u16>(15, 15, 0), 9999);
+        }
+
+        // Without nodata, chunks of zeros are sparse
+        let mut bytes = Vec::new();
+        GeoTiffWriter::new()
+            .with_strips(8)
+            .with_sparse(true)
+            .write(
+                &mut bytes,
+                40,
+                20,
+                1,
+                &[vec![0u16; 40 * 16], vec![1; 40 * 4]].concat(),
+            )
+            .unwrap();
+        let mut sparse = GeoTiffReader::new(Cursor::new(bytes)).unwrap();
+        assert_eq!(sparse.chunk_byte_range(1).unwrap(), 0..0);
+        assert!(!sparse.chunk_byte_range(2).unwrap().is_empty());
+        let geotiff = sparse.read_window(0, 6, 40, 14).unwrap();
+        assert_eq!(geotiff.get_value_at::<u16>(0, 0, 0), 0);
+        assert_eq!(geotiff.get_value_at::<u16>(39, 13, 0), 1);
+        let geotiff = sparse.read().unwrap();
+        assert_eq!(geotiff.get_value_at::<u16>(0, 0, 0), 0);
+        assert_eq!(geotiff.get_value_at::<u16>(39, 19, 0), 1);
+    }
+
+    #[test]
+    fn test_read_strip() {
+        let mut reader = reader(GeoTiffWriter::new().with_strips(3));
+        assert_eq!(reader.strip_count(), Some(7));
+        assert_eq!(reader.rows_per_strip(), Some(3));
+        assert_eq!(reader.tile_count(), None);
+        assert_eq!(reader.tile_dimensions(), None);
+        assert!(reader.read_tile(0, 0).is_err());
+
+        let strip = reader.read_strip(1).unwrap();
+        assert_eq!((strip.raster_width, strip.raster_height), (40, 3));
+        assert_eq!(strip.get_value_at::<u16>(0, 0, 0), 120);
+        assert_eq!(strip.transform_to_model([0, 0]), Some([1000.0, 1970.0]));
+
+        // The last strip contains the remaining rows
+        let strip = reader.read_strip(6).unwrap();
+        assert_eq!(strip.raster_height, 2);
+        assert_eq!(strip.get_value_at::<u16>(39, 1, 0), 799);
+
+        assert!(reader.read_strip(7).is_err());
+        assert_eq!(reader.read().unwrap().get_value_at::<u16>(39, 19, 0), 799);
+
+        let mut reader = reader_tiled();
+        assert_eq!(reader.strip_count(), None);
+        assert!(reader.read_strip(0).is_err());
+    }
+}
@@ -0,0 +1,129 @@
+use crate::{GeoTiff, GeoTiffError, GeoTiffResult};
+
+/// Statistics of the valid samples of a band, excluding nodata, as computed by
+/// [`GeoTiff::statistics`] or [`GeoTiffReader::statistics`](crate::GeoTiffReader::statistics).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Statistics {
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    /// The population standard deviation, like GDAL computes it
+    pub std_dev: f64,
+    /// The number of valid samples
+    pub count: usize,
+}
+
+/// Accumulates statistics sample by sample with Welford's algorithm, weighted as by West, so
+/// that rasters can be processed block by block.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct StatisticsAccumulator {
+    count: usize,
+    /// The sum of the weights of the samples
+    weight: f64,
+    min: f64,
+    max: f64,
+    mean: f64,
+    /// The sum of the squared differences from the mean
+    m2: f64,
+}
+
+impl StatisticsAccumulator {
+    /// Adds the valid samples of the band, skipping nodata and NaN.
+    pub(crate) fn add_band(&mut self, geotiff: &GeoTiff, band: usize) {
+        for y in 0..geotiff.raster_height {
+            for x in 0..geotiff.raster_width {
+                match geotiff.get_value_at_checked::<f64>(x, y, band) {
+                    Some(value) if !value.is_nan() => self.add(value),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    fn add(&mut self, value: f64) {
+        self.add_weighted(value, 1.0);
+    }
+
+    /// Adds a sample contributing to the mean and the standard deviation by the given positive
+    /// weight, e.g. the fraction of a pixel covered by a polygon.
+    #[cfg_attr(not(feature = "geo"), allow(dead_code))]
+    pub(crate) fn add_weighted(&mut self, value: f64, weight: f64) {
+        if self.count == 0 {
+            self.min = value;
+            self.max = value;
+        } else {
+            self.min = self.min.min(value);
+            self.max = self.max.max(value);
+        }
+        self.count += 1;
+        self.weight += weight;
+        let delta = value - self.mean;
+        self.mean += delta * weight / self.weight;
+        self.m2 += weight * delta * (value - self.mean);
+    }
+
+    /// The statistics of all samples added, or `None` if there are none.
+    pub(crate) fn finish(&self) -> Option<Statistics> {
+        (self.count > 0).then(|| Statistics {
+            min: self.min,
+            max: self.max,
+            mean: self.mean,
+            std_dev: (self.m2 / self.weight).sqrt(),
+            count: self.count,
+        })
+    }
+}
+
+/// Counts the valid samples in bins of equal width, so that rasters can be processed block by
+/// block.
+#[derive(Debug, Clone)]
+pub(crate) struct HistogramAccumulator {
+    min: f64,
+    max: f64,
+    counts: Vec<u64>,
+}
+
+impl HistogramAccumulator {
+    /// Creates a histogram of the given number of bins dividing the range `[min, max]`.
+    ///
+    /// Fails if there are no bins or the range is empty.
+    pub(crate) fn new(bins: usize, [min, max]: [f64; 2]) -> GeoTiffResult<Self> {
+        if bins == 0 {
+            return Err(GeoTiffError::InvalidArgument(
+                "A histogram needs at least one bin".to_string(),
+            ));
+        }
+        if min >= max || !min.is_finite() || !max.is_finite() {
+            return Err(GeoTiffError::InvalidArgument(format!(
+                "Invalid histogram range [{}, {}]",
+                min, max
+            )));
+        }
+        Ok(Self {
+            min,
+            max,
+            counts: vec![0; bins],
+        })
+    }
+
+    /// Adds the valid samples of the band within the range, skipping nodata and NaN.
+    pub(crate) fn add_band(&mut self, geotiff: &GeoTiff, band: usize) {
+        let bins = self.counts.len();
+        for y in 0..geotiff.raster_height {
+            for x in 0..geotiff.raster_width {
+                let Some(value) = geotiff.get_value_at_checked::<f64>(x, y, band) else {
+                    continue;
+                };
+                // Values equal to the maximum fall into the last bin, NaN fails both tests
+                if value >= self.min && value <= self.max {
+                    let bin = ((value - self.min) / (self.max - self.min) * bins as f64) as usize;
+                    self.counts[bin.min(bins - 1)] += 1;
+                }
+            }
+        }
+    }
+
+    pub(crate) fn finish(self) -> Vec<u64> {
+        self.counts
+    }
+}
@@ -0,0 +1,305 @@
+use std::io::{Read, Seek, Write};
+
+use crate::band_math::Rows;
+use crate::{
+    GeoTiff, GeoTiffError, GeoTiffReader, GeoTiffResult, GeoTiffWriter, ModelType, ModelUnits,
+    Sample,
+};
+
+/// The radius of the sphere geographic coordinates are converted to meters on, the equatorial
+/// radius of WGS 84
+const EARTH_RADIUS: f64 = 6_378_137.0;
+
+/// A derivative of an elevation model computed by [`GeoTiffReader::terrain`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Terrain {
+    /// The steepness of the surface, like `gdaldem slope` computes it
+    Slope(SlopeUnits),
+    /// The compass direction the surface faces downhill in degrees, clockwise from north, like
+    /// `gdaldem aspect` computes it. Flat pixels have no aspect and are nodata.
+    Aspect,
+}
+
+/// The units of [`Terrain::Slope`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SlopeUnits {
+    /// The angle to the horizontal, from 0 to 90 degrees
+    #[default]
+    Degrees,
+    /// The rise over the run in percent, e.g. 100 for 45 degrees
+    Percent,
+}
+
+impl<R: Read + Seek> GeoTiffReader<R> {
+    /// Computes the slope or aspect of each pixel of the band of elevations in the selected image
+    /// or overview by Horn's method, returning a single band of `f64` values with the
+    /// georeferencing of the raster and NaN as nodata, see [`GeoTiffReader::evaluate`].
+    ///
+    /// The cell size is taken from the coordinate transformation, in meters for projected CRSs,
+    /// and the elevations are converted to meters according to the VerticalUnitsGeoKey, if
+    /// present. The cell size of geographic CRSs is converted from degrees to meters at the
+    /// latitude of each row, on a sphere with the equatorial radius of WGS 84. Pixels at the edge
+    /// of the raster or next to nodata are nodata, like `gdaldem` computes them without
+    /// `-compute_edges`.
+    ///
+    /// The raster is read in rows of strips or tiles along with the adjacent rows. Fails if the
+    /// band does not exist or the raster is not georeferenced by an affine transformation
+    /// without rotation.
+    pub fn terrain(&mut self, band: usize, terrain: Terrain) -> GeoTiffResult<GeoTiff> {
+        self.collect_band(|reader, rows| reader.terrain_rows(band, terrain, rows))
+    }
+
+    /// Computes the slope or aspect like [`GeoTiffReader::terrain`], streaming the result as a
+    /// new GeoTIFF of samples of type `T` to the sink, see [`GeoTiffReader::evaluate_to`].
+    pub fn terrain_to<W: Write, T: Sample>(
+        &mut self,
+        band: usize,
+        terrain: Terrain,
+        writer: &GeoTiffWriter,
+        sink: W,
+    ) -> GeoTiffResult<W> {
+        self.stream_band::<W, T>(writer, sink, |reader, rows| {
+            reader.terrain_rows(band, terrain, rows)
+        })
+    }
+
+    /// Computes the slope or aspect row of strips or tiles by row, passing the values of the
+    /// rows of each to `rows`.
+    fn terrain_rows(&mut self, band: usize, terrain: Terrain, rows: Rows<'_>) -> GeoTiffResult<()> {
+        self.check_band(band)?;
+        let mut metadata = self.metadata().clone();
+        metadata.set_model_units(ModelUnits::Standard)?;
+        if metadata.has_rotation() {
+            return Err(GeoTiffError::Unsupported(
+                "Terrain analysis of rotated rasters is not supported".to_string(),
+            ));
+        }
+        let [_, pixel_width, _, top, _, pixel_height] =
+            metadata.geotransform().ok_or_else(|| {
+                GeoTiffError::GeoKey(
+                    "The GeoTIFF is not georeferenced by an affine transformation".to_string(),
+                )
+            })?;
+        let geo_keys = metadata.geo_key_directory();
+        let geographic = geo_keys.model_type() == Some(ModelType::Geographic);
+        let vertical_unit_size = geo_keys.vertical_unit_size().unwrap_or(1.0);
+        // The signed size of a pixel in meters along the model axes at the given row
+        let cell_size = |row: usize| {
+            if geographic {
+                let meters_per_degree = EARTH_RADIUS.to_radians();
+                let latitude = top + (row as f64 + 0.5) * pixel_height;
+                [
+                    pixel_width * meters_per_degree * latitude.to_radians().cos(),
+                    pixel_height * meters_per_degree,
+                ]
+            } else {
+                [pixel_width, pixel_height]
+            }
+        };
+
+        let (width, height) = (self.raster_width(), self.raster_height());
+        let [_, block_height] = self.block_size();
+        for y in (0..height).step_by(block_height) {
+            let block_height = block_height.min(height - y);
+            // The rows of the block along with the adjacent rows
+            let first_row = y.saturating_sub(1);
+            let last_row = (y + block_height + 1).min(height);
+            let window =
+                self.read_window_bands([0, first_row, width, last_row - first_row], &[band])?;
+            let elevation = |column: usize, row: usize| {
+                window
+                    .get_value_at_checked::<f64>(column, row - first_row, 0)
+                    .filter(|value| !value.is_nan())
+                    .map(|value| value * vertical_unit_size)
+            };
+
+            let mut row_values = Vec::with_capacity(width * block_height);
+            for row in y..y + block_height {
+                for column in 0..width {
+                    let value =
+                        if column == 0 || row == 0 || column == width - 1 || row == height - 1 {
+                            None
+                        } else {
+                            horn(|dx, dy| {
+                                elevation(
+                                    (column as isize + dx) as usize,
+                                    (row as isize + dy) as usize,
+                                )
+                            })
+                            .and_then(|gradient| derivative(gradient, cell_size(row), terrain))
+                        };
+                    row_values.push(value);
+                }
+            }
+            rows(row_values)?;
+        }
+        Ok(())
+    }
+}
+
+/// The gradient of the elevation in units per pixel along the columns and the rows by Horn's
+/// method, i.e. the weighted differences of the 3x3 neighbourhood given by the offsets from the
+/// center, or `None` if a neighbour is nodata.
+fn horn(elevation: impl Fn(isize, isize) -> Option<f64>) -> Option<[f64; 2]> {
+    let [a, b, c] = [elevation(-1, -1)?, elevation(0, -1)?, elevation(1, -1)?];
+    let [d, f] = [elevation(-1, 0)?, elevation(1, 0)?];
+    let [g, h, i] = [elevation(-1, 1)?, elevation(0, 1)?, elevation(1, 1)?];
+    Some([
+        ((c + 2.0 * f + i) - (a + 2.0 * d + g)) / 8.0,
+        ((g + 2.0 * h + i) - (a + 2.0 * b + c)) / 8.0,
+    ])
+}
+
+/// The slope or aspect of the gradient per pixel given the signed size of a pixel along the
+/// model axes, or `None` for the aspect of flat pixels.
+fn derivative(
+    [per_column, per_row]: [f64; 2],
+    [pixel_width, pixel_height]: [f64; 2],
+    terrain: Terrain,
+) -> Option<f64> {
+    // The gradient along the model axes, e.g. east and north
+    let (gradient_x, gradient_y) = (per_column / pixel_width, per_row / pixel_height);
+    match terrain {
+        Terrain::Slope(units) => {
+            let rise = gradient_x.hypot(gradient_y);
+            Some(match units {
+                SlopeUnits::Degrees => rise.atan().to_degrees(),
+                SlopeUnits::Percent => rise * 100.0,
+            })
+        }
+        Terrain::Aspect => {
+            if gradient_x == 0.0 && gradient_y == 0.0 {
+                return None;
+            }
+            // The direction downhill, opposite to the gradient
+            let aspect = (-gradient_x).atan2(-gradient_y).to_degrees();
+            Some(if aspect < 0.0 { aspect + 360.0 } else { aspect })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::CoordinateTransform;
+
+    fn dem(
+        geotransform: [f64; 6],
+        epsg: u16,
+        elevation: impl Fn(f64, f64) -> f32,
+    ) -> GeoTiffReader<Cursor<Vec<u8>>> {
+        let data = (0..30)
+            .flat_map(|row| (0..20).map(move |column| (column as f64, row as f64)))
+            .map(|(column, row)| elevation(column, row))
+            .collect::<Vec<_>>();
+        let mut bytes = Vec::new();
+        GeoTiffWriter::new()
+            .with_strips(4)
+            .with_nodata(-9999.0)
+            .with_epsg(epsg)
+            .unwrap()
+            .with_coordinate_transform(
+                CoordinateTransform::from_geotransform(geotransform).unwrap(),
+            )
+            .write(&mut bytes, 20, 30, 1, &data)
+            .unwrap();
+        GeoTiffReader::new(Cursor::new(bytes)).unwrap()
+    }
+
+    fn assert_close(value: Option<f64>, expected: f64) {
+        let value = value.unwrap();
+        assert!((value - expected).abs() < 1e-6, "{} != {}", value, expected);
+    }
+
+    #[test]
+    fn test_terrain() {
+        // Rising by 2 m per 10 m pixel towards the east, with a nodata pixel at (10, 10)
+        let geotransform = [500000.0, 10.0, 0.0, 4000000.0, 0.0, -10.0];
+        let mut reader = dem(geotransform, 32632, |column, row| {
+            if (column, row) == (10.0, 10.0) {
+                -9999.0
+            } else {
+                (2.0 * column) as f32
+            }
+        });
+        let slope = reader
+            .terrain(0, Terrain::Slope(SlopeUnits::Degrees))
+            .unwrap();
+        assert_eq!((slope.raster_width, slope.raster_height), (20, 30));
+        assert_eq!(slope.geotransform(), Some(geotransform));
+        assert_close(
+            slope.get_value_at_checked(5, 5, 0),
+            0.2f64.atan().to_degrees(),
+        );
+        // Across the strips
+        assert_close(
+            slope.get_value_at_checked(5, 4, 0),
+            0.2f64.atan().to_degrees(),
+        );
+        assert_close(
+            slope.get_value_at_checked(18, 28, 0),
+            0.2f64.atan().to_degrees(),
+        );
+        // Edges and the neighbours of nodata
+        assert_eq!(slope.get_value_at_checked::<f64>(0, 5, 0), None);
+        assert_eq!(slope.get_value_at_checked::<f64>(5, 29, 0), None);
+        assert_eq!(slope.get_value_at_checked::<f64>(11, 9, 0), None);
+        let slope = reader
+            .terrain(0, Terrain::Slope(SlopeUnits::Percent))
+            .unwrap();
+        assert_close(slope.get_value_at_checked(5, 5, 0), 20.0);
+        // Facing west
+        let aspect = reader.terrain(0, Terrain::Aspect).unwrap();
+        assert_close(aspect.get_value_at_checked(5, 5, 0), 270.0);
+
+        // Rising towards the north, i.e. facing south
+        let mut reader = dem(geotransform, 32632, |_, row| (100.0 - row) as f32);
+        let aspect = reader.terrain(0, Terrain::Aspect).unwrap();
+        assert_close(aspect.get_value_at_checked(5, 5, 0), 180.0);
+        // Facing north-east
+        let mut reader = dem(geotransform, 32632, |column, row| (row - column) as f32);
+        let aspect = reader.terrain(0, Terrain::Aspect).unwrap();
+        assert_close(aspect.get_value_at_checked(5, 5, 0), 45.0);
+        // Flat
+        let mut reader = dem(geotransform, 32632, |_, _| 1.0);
+        let slope = reader
+            .terrain(0, Terrain::Slope(SlopeUnits::Degrees))
+            .unwrap();
+        assert_eq!(slope.get_value_at_checked::<f64>(5, 5, 0), Some(0.0));
+        let aspect = reader.terrain(0, Terrain::Aspect).unwrap();
+        assert_eq!(aspect.get_value_at_checked::<f64>(5, 5, 0), None);
+
+        assert!(reader.terrain(1, Terrain::Aspect).is_err());
+    }
+
+    #[test]
+    fn test_terrain_geographic() {
+        // Pixels of 0.001 degrees at 60 degrees north, i.e. half as wide as high in meters,
+        // rising by 1 m per pixel towards the east
+        let geotransform = [10.0, 0.001, 0.0, 60.015, 0.0, -0.001];
+        let mut reader = dem(geotransform, 4326, |column, _| column as f32);
+        let slope = reader
+            .terrain(0, Terrain::Slope(SlopeUnits::Percent))
+            .unwrap();
+        let latitude = 60.015f64 - 15.5 * 0.001;
+        let pixel_width = 0.001 * EARTH_RADIUS.to_radians() * latitude.to_radians().cos();
+        assert_close(slope.get_value_at_checked(5, 15, 0), 100.0 / pixel_width);
+    }
+
+    #[test]
+    fn test_terrain_to() {
+        let geotransform = [500000.0, 10.0, 0.0, 4000000.0, 0.0, -10.0];
+        let mut reader = dem(geotransform, 32632, |column, row| (column + row) as f32);
+        let bytes = reader
+            .terrain_to::<_, f32>(0, Terrain::Aspect, &GeoTiffWriter::new(), Vec::new())
+            .unwrap();
+        let geotiff = GeoTiff::read(Cursor::new(bytes)).unwrap();
+        assert_eq!(geotiff.epsg_code(), Some(32632));
+        assert_eq!(geotiff.geotransform(), Some(geotransform));
+        // Rising towards the south-east, i.e. facing north-west
+        assert_close(geotiff.get_value_at_checked(5, 5, 0), 315.0);
+        assert_eq!(geotiff.get_value_at_checked::<f32>(0, 0, 0), None);
+    }
+}
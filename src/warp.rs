@@ -0,0 +1,220 @@
+use std::io::{Read, Seek};
+
+use crate::crs::Crs;
+use crate::interpolation::{self, Interpolation};
+use crate::raster_data::RasterData;
+use crate::{
+    CoordinateTransform, CrsTransform, GeoTiff, GeoTiffError, GeoTiffReader, GeoTiffResult,
+    ModelUnits,
+};
+
+/// The grid of pixels in a target CRS a raster is reprojected into, see
+/// [`GeoTiffReader::read_warped`].
+#[derive(Debug, Clone)]
+pub struct WarpGrid {
+    /// The EPSG code of the target CRS
+    pub epsg: u16,
+    /// The transformation between the raster space of the grid and the target CRS
+    pub coordinate_transform: CoordinateTransform,
+    pub raster_width: usize,
+    pub raster_height: usize,
+}
+
+impl WarpGrid {
+    pub fn new(
+        epsg: u16,
+        coordinate_transform: CoordinateTransform,
+        raster_width: usize,
+        raster_height: usize,
+    ) -> Self {
+        Self {
+            epsg,
+            coordinate_transform,
+            raster_width,
+            raster_height,
+        }
+    }
+
+    /// The fractional raster coordinates in the source raster of the pixel centers of the grid,
+    /// row by row, or `None` where PROJ cannot transform them.
+    fn source_coords(&self, crs_transform: &CrsTransform) -> Vec<Option<[f64; 2]>> {
+        (0..self.raster_height)
+            .flat_map(|row| (0..self.raster_width).map(move |column| (column, row)))
+            .map(|(column, row)| {
+                let coord = self
+                    .coordinate_transform
+                    .transform_to_model_f64([column as f64 + 0.5, row as f64 + 0.5]);
+                crs_transform.transform_to_raster_f64(coord).ok()
+            })
+            .collect()
+    }
+}
+
+impl<R: Read + Seek> GeoTiffReader<R> {
+    /// Reads the raster reprojected into a grid in another CRS, like `gdalwarp` does, decoding
+    /// only the strips or tiles the grid covers.
+    ///
+    /// Each pixel of the grid is interpolated at its center from the bands of the selected image
+    /// or overview, see [`GeoTiff::sample`]. The returned GeoTiff holds the values as `f64`, with
+    /// NaN as nodata for pixels outside the raster or without valid neighbours. Fails if the
+    /// GeoTIFF is not georeferenced, PROJ does not support its CRS or the target CRS is not
+    /// among the built-in CRS definitions.
+    pub fn read_warped(
+        &mut self,
+        grid: &WarpGrid,
+        interpolation: Interpolation,
+    ) -> GeoTiffResult<GeoTiff> {
+        let target_crs = Crs::from_epsg_code(grid.epsg)
+            .ok_or_else(|| GeoTiffError::Unsupported(format!("EPSG code {}", grid.epsg)))?;
+        let metadata = self.metadata();
+        let coords = grid.source_coords(&metadata.transform_to_crs(grid.epsg)?);
+        let mut warped = warped_metadata(metadata, grid, &target_crs);
+
+        let window = source_window(
+            &coords,
+            [metadata.raster_width, metadata.raster_height],
+            interpolation.radius(),
+        );
+        let values = match window {
+            Some([x, y, width, height]) => {
+                let source = self.read_window(x, y, width, height)?;
+                resample(&source, [x, y], &coords, interpolation)
+            }
+            None => vec![f64::NAN; coords.len() * metadata.num_samples],
+        };
+        warped.raster_data = RasterData::F64(values);
+        Ok(warped)
+    }
+}
+
+/// The metadata of the source raster reprojected into the grid.
+fn warped_metadata(metadata: &GeoTiff, grid: &WarpGrid, target_crs: &Crs) -> GeoTiff {
+    let mut warped = metadata.clone();
+    warped.raster_width = grid.raster_width;
+    warped.raster_height = grid.raster_height;
+    target_crs.replace_geo_keys(&mut warped.geo_key_directory);
+    warped.model_units = ModelUnits::Native;
+    warped.rpc_model = None;
+    warped.coordinate_transform = Some(grid.coordinate_transform.clone());
+    warped.mask = None;
+    warped.nodata = Some(RasterData::F64(vec![f64::NAN]));
+    warped
+}
+
+/// The window `[x, y, width, height]` of the raster of the given size containing the pixels the
+/// interpolation at the source coordinates reads, or `None` if no coordinate lies within it.
+fn source_window(
+    coords: &[Option<[f64; 2]>],
+    [width, height]: [usize; 2],
+    radius: usize,
+) -> Option<[usize; 4]> {
+    let (width, height) = (width as f64, height as f64);
+    let [mut left, mut top, mut right, mut bottom] = [
+        f64::INFINITY,
+        f64::INFINITY,
+        f64::NEG_INFINITY,
+        f64::NEG_INFINITY,
+    ];
+    for &[column, row] in coords.iter().flatten() {
+        if (0.0..width).contains(&column) && (0.0..height).contains(&row) {
+            left = left.min(column);
+            top = top.min(row);
+            right = right.max(column);
+            bottom = bottom.max(row);
+        }
+    }
+    if left > right {
+        return None;
+    }
+    let radius = radius as f64;
+    let x = (left.floor() - radius).max(0.0);
+    let y = (top.floor() - radius).max(0.0);
+    let right = (right.floor() + 1.0 + radius).min(width);
+    let bottom = (bottom.floor() + 1.0 + radius).min(height);
+    Some([
+        x as usize,
+        y as usize,
+        (right - x) as usize,
+        (bottom - y) as usize,
+    ])
+}
+
+/// Interpolates all bands of the window at `[x, y]` of the raster at the source coordinates,
+/// pixel by pixel, with NaN where no value can be interpolated.
+fn resample(
+    source: &GeoTiff,
+    [x, y]: [usize; 2],
+    coords: &[Option<[f64; 2]>],
+    interpolation: Interpolation,
+) -> Vec<f64> {
+    coords
+        .iter()
+        .flat_map(|coord| {
+            (0..source.num_samples).map(move |band| {
+                coord
+                    .and_then(|[column, row]| {
+                        interpolation::interpolate(
+                            source,
+                            [column - x as f64, row - y as f64],
+                            band,
+                            interpolation,
+                        )
+                    })
+                    .unwrap_or(f64::NAN)
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{GeoTiffWriter, Layout};
+
+    #[test]
+    fn test_read_warped() {
+        let transform =
+            CoordinateTransform::from_geotransform([500000.0, 10.0, 0.0, 5000000.0, 0.0, -10.0])
+                .unwrap();
+        let data = (0..200).map(|value| value as u16).collect::<Vec<_>>();
+        let mut bytes = Vec::new();
+        GeoTiffWriter::new()
+            .with_epsg(32632)
+            .unwrap()
+            .with_coordinate_transform(transform.clone())
+            .write(&mut bytes, 20, 10, 1, &data)
+            .unwrap();
+        let mut reader = GeoTiffReader::new(Cursor::new(bytes)).unwrap();
+
+        // Into the grid of the raster itself
+        let grid = WarpGrid::new(32632, transform, 20, 10);
+        let warped = reader.read_warped(&grid, Interpolation::Nearest).unwrap();
+        assert_eq!(warped.epsg_code(), Some(32632));
+        let values = warped.get_values::<f64>(Layout::PixelInterleaved);
+        assert_eq!(
+            values,
+            data.iter().map(|&value| value as f64).collect::<Vec<_>>()
+        );
+
+        // Into a grid of half the resolution, extending beyond the raster to the right
+        let transform =
+            CoordinateTransform::from_geotransform([500000.0, 20.0, 0.0, 5000000.0, 0.0, -20.0])
+                .unwrap();
+        let grid = WarpGrid::new(32632, transform, 12, 5);
+        let warped = reader.read_warped(&grid, Interpolation::Bilinear).unwrap();
+        assert_eq!((warped.raster_width, warped.raster_height), (12, 5));
+        assert_eq!(warped.get_value_at::<f64>(0, 0, 0), 10.5);
+        assert_eq!(warped.get_value_at::<f64>(3, 2, 0), 96.5);
+        assert_eq!(warped.get_value_at_checked::<f64>(10, 0, 0), None);
+
+        // Into longitude/latitude
+        let transform =
+            CoordinateTransform::from_geotransform([9.0, 0.001, 0.0, 45.15, 0.0, -0.001]).unwrap();
+        let grid = WarpGrid::new(4326, transform, 10, 10);
+        let warped = reader.read_warped(&grid, Interpolation::Nearest).unwrap();
+        assert_eq!(warped.epsg_code(), Some(4326));
+        assert_eq!(warped.crs_description(), Some("WGS 84"));
+    }
+}
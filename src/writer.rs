@@ -0,0 +1,1836 @@
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use tiff::tags::{PhotometricInterpretation, PlanarConfiguration, Predictor, Tag};
+use tiff::{TiffError, TiffFormatError};
+
+use crate::coordinate_transform::CoordinateTransform;
+use crate::crs::Crs;
+use crate::geo_key_directory::{GeoKeyDirectory, GeoTiffVersion, ModelType};
+use crate::raster_data::Sample;
+use crate::{GeoTiffError, GeoTiffResult, TagValue};
+
+pub use self::cog::CogWriter;
+pub use self::compression::Compression;
+use self::ifd::{Ifd, IfdValue, TiffVariant};
+pub use self::overview::Resampling;
+pub use self::stream::StreamingWriter;
+pub use self::tiles::TileWriter;
+
+mod cog;
+mod compression;
+mod ifd;
+mod overview;
+mod stream;
+mod tiles;
+
+/// A writer for GeoTIFFs, storing raster data together with its georeferencing.
+///
+/// The raster is written as a little-endian TIFF or BigTIFF, by default uncompressed and with strips of about
+/// 8 KiB like libtiff does. Single samples are written as grayscale, three and four samples per
+/// pixel as RGB, the latter with an unspecified extra sample. With the `rayon` feature, the
+/// strips or tiles are compressed in parallel.
+#[derive(Debug, Clone, Default)]
+pub struct GeoTiffWriter {
+    geo_key_directory: GeoKeyDirectory,
+    coordinate_transform: Option<CoordinateTransform>,
+    layout: Layout,
+    compression: Compression,
+    predictor: Option<Predictor>,
+    big_tiff: Option<bool>,
+    overviews: Vec<usize>,
+    resampling: Resampling,
+    cloud_optimized: bool,
+    nodata: Option<f64>,
+    planar: bool,
+    sparse: bool,
+    subfile_type: u32,
+}
+
+/// The arrangement of the raster data in chunks.
+#[derive(Debug, Clone, Copy)]
+enum Layout {
+    /// Strips of the given number of rows, or of about 8 KiB by default
+    Strips { rows_per_strip: Option<usize> },
+    Tiles {
+        tile_width: usize,
+        tile_height: usize,
+    },
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Layout::Strips {
+            rows_per_strip: None,
+        }
+    }
+}
+
+impl GeoTiffWriter {
+    const STRIP_SIZE: usize = 8192;
+    /// The NewSubfileType of overviews
+    const REDUCED_RESOLUTION: u32 = 1;
+    /// The NewSubfileType of transparency masks
+    const TRANSPARENCY_MASK: u32 = 4;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the GeoKeys describing the model CRS.
+    pub fn with_geo_key_directory(mut self, geo_key_directory: GeoKeyDirectory) -> Self {
+        self.geo_key_directory = geo_key_directory;
+        self
+    }
+
+    /// Sets the GeoKeys describing the model CRS given by an EPSG code, i.e. the model type, the
+    /// EPSG code of the CRS together with its units and citations. The keys of a previously set
+    /// CRS are replaced, while the GTRasterTypeGeoKey and the vertical CRS keys are kept.
+    ///
+    /// Fails if the EPSG code is not among the built-in CRS definitions, i.e. WGS 84, Web
+    /// Mercator, the UTM zones on WGS 84, ETRS89, NAD27 and NAD83 and a few national grids. Other
+    /// codes are written by [`GeoTiffWriter::with_epsg_code`].
+    pub fn with_epsg(mut self, code: u16) -> GeoTiffResult<Self> {
+        let crs = Crs::from_epsg_code(code).ok_or_else(|| {
+            GeoTiffError::InvalidArgument(format!(
+                "EPSG code {} has no built-in definition, its model type must be given",
+                code
+            ))
+        })?;
+        crs.replace_geo_keys(&mut self.geo_key_directory);
+        Ok(self)
+    }
+
+    /// Sets the GeoKeys describing the model CRS given by an EPSG code of the model type, i.e.
+    /// only the model type and the ProjectedCSTypeGeoKey of projected CRSs or the
+    /// GeographicTypeGeoKey of geographic and geocentric CRSs, for CRSs without a built-in
+    /// definition, see [`GeoTiffWriter::with_epsg`]. Readers need an EPSG database to resolve the
+    /// CRS, as neither its units nor its citations are written.
+    ///
+    /// Fails if the model type is user-defined, or the code is below 2000 or 32767, which do not
+    /// denote an EPSG CRS.
+    pub fn with_epsg_code(mut self, code: u16, model_type: ModelType) -> GeoTiffResult<Self> {
+        if code < 2000 || code == GeoKeyDirectory::USER_DEFINED {
+            return Err(GeoTiffError::InvalidArgument(format!(
+                "Invalid EPSG code {}",
+                code
+            )));
+        }
+        if model_type == ModelType::UserDefined {
+            return Err(GeoTiffError::InvalidArgument(
+                "The model type of an EPSG code cannot be user-defined".to_string(),
+            ));
+        }
+        Crs::replace_geo_keys_by_epsg_code(code, model_type, &mut self.geo_key_directory);
+        Ok(self)
+    }
+
+    /// Sets the revision of the GeoTIFF specification the GeoKeys are declared to follow, which is
+    /// GeoTIFF 1.0 by default. The revision of a directory set by
+    /// [`GeoTiffWriter::with_geo_key_directory`] afterwards takes precedence.
+    pub fn with_geotiff_version(mut self, version: GeoTiffVersion) -> Self {
+        self.geo_key_directory.set_version(version);
+        self
+    }
+
+    /// Sets the transformation between raster space and model space, which is stored in the
+    /// ModelPixelScaleTag and ModelTiepointTag, or the ModelTransformationTag.
+    ///
+    /// Raster coordinates refer to the upper left corner of a pixel. They are converted to the
+    /// pixel convention given by the GTRasterTypeGeoKey when writing.
+    pub fn with_coordinate_transform(mut self, coordinate_transform: CoordinateTransform) -> Self {
+        self.coordinate_transform = Some(coordinate_transform);
+        self
+    }
+
+    /// Arranges the raster data in strips of the given number of rows (RowsPerStrip) instead of
+    /// strips of about 8 KiB. Strips of a single row suit streaming consumers, a single strip
+    /// spanning the whole raster avoids any chunking overhead for small rasters.
+    pub fn with_strips(mut self, rows_per_strip: usize) -> Self {
+        self.layout = Layout::Strips {
+            rows_per_strip: Some(rows_per_strip),
+        };
+        self
+    }
+
+    /// Arranges the raster data in tiles of the given size instead of strips, as required for
+    /// Cloud Optimized GeoTIFFs. Tiles at the right and bottom edges of the raster are padded.
+    ///
+    /// The TIFF specification requires tile dimensions to be multiples of 16, e.g. 256x256 or
+    /// 512x512.
+    pub fn with_tiles(mut self, tile_width: usize, tile_height: usize) -> Self {
+        self.layout = Layout::Tiles {
+            tile_width,
+            tile_height,
+        };
+        self
+    }
+
+    /// Stores the bands plane by plane, each in its own strips or tiles (PlanarConfiguration 2),
+    /// if `true`, like `gdal_translate -co INTERLEAVE=BAND` does, instead of the samples of each
+    /// pixel together, which is the default.
+    pub fn with_planar(mut self, planar: bool) -> Self {
+        self.planar = planar;
+        self
+    }
+
+    /// Compresses the raster data, e.g. with `Compression::Deflate { level: 6 }` like
+    /// `gdal_translate -co COMPRESS=DEFLATE` does.
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Applies a predictor to the raster data before compression, which usually improves the
+    /// compression ratio of continuous data like elevation models considerably.
+    ///
+    /// The horizontal predictor is applicable to integer samples, the floating point predictor to
+    /// floating point samples. It has no effect on uncompressed data.
+    pub fn with_predictor(mut self, predictor: Predictor) -> Self {
+        self.predictor = Some(predictor);
+        self
+    }
+
+    /// Writes a BigTIFF with 64-bit offsets if `true`, or a classic TIFF limited to 4 GiB if
+    /// `false`. By default, a BigTIFF is only written if the file would exceed 4 GiB otherwise,
+    /// like `gdal_translate -co BIGTIFF=IF_NEEDED` does.
+    pub fn with_big_tiff(mut self, big_tiff: bool) -> Self {
+        self.big_tiff = Some(big_tiff);
+        self
+    }
+
+    /// Marks pixels of the given value as not covered by data in the GDAL_NODATA tag, like
+    /// `gdal_translate -a_nodata` does, see [`GeoTiff::nodata`](crate::GeoTiff::nodata).
+    pub fn with_nodata(mut self, nodata: f64) -> Self {
+        self.nodata = Some(nodata);
+        self
+    }
+
+    /// The nodata value set by [`GeoTiffWriter::with_nodata`].
+    pub(crate) fn nodata(&self) -> Option<f64> {
+        self.nodata
+    }
+
+    /// Omits the strips or tiles of only nodata, or of only zeros without a nodata value, if
+    /// `true`, storing them with an offset and byte count of 0 like `gdal_translate -co
+    /// SPARSE_OK=TRUE` does. Readers fill such sparse chunks with the nodata value or 0.
+    pub fn with_sparse(mut self, sparse: bool) -> Self {
+        self.sparse = sparse;
+        self
+    }
+
+    /// Marks the raster as a reduced-resolution version of the preceding top-level image, e.g. an
+    /// overview computed elsewhere and added with [`GeoTiffWriter::append`]. Like for masks, the
+    /// GeoKeys and the coordinate transformation are not written, as they are those of the
+    /// full-resolution image.
+    pub fn with_reduced_resolution(mut self, reduced_resolution: bool) -> Self {
+        self.set_subfile_type(Self::REDUCED_RESOLUTION, reduced_resolution);
+        self
+    }
+
+    /// Marks the raster as the transparency mask of the preceding image, or of the preceding
+    /// overview if combined with [`GeoTiffWriter::with_reduced_resolution`], like the internal
+    /// masks of GDAL. Masks have a single band of `u8` samples, nonzero samples marking valid
+    /// pixels.
+    pub fn with_mask(mut self, mask: bool) -> Self {
+        self.set_subfile_type(Self::TRANSPARENCY_MASK, mask);
+        self
+    }
+
+    /// Appends overviews reduced by the given factors, e.g. `&[2, 4, 8, 16]`, computed with the
+    /// given resampling method like `gdaladdo` does. The overviews are stored as reduced-resolution
+    /// images following the full-resolution image, with the same layout and compression.
+    pub fn add_overviews(mut self, factors: &[usize], resampling: Resampling) -> Self {
+        self.overviews.extend_from_slice(factors);
+        self.overviews.sort_unstable();
+        self.overviews.dedup();
+        self.resampling = resampling;
+        self
+    }
+
+    /// Writes a raster of `raster_width * raster_height` pixels with `num_samples` interleaved
+    /// samples each, stored row by row.
+    pub fn write<W: Write, T: Sample>(
+        &self,
+        writer: W,
+        raster_width: usize,
+        raster_height: usize,
+        num_samples: usize,
+        data: &[T],
+    ) -> GeoTiffResult<()> {
+        let images = self.encode_images(raster_width, raster_height, num_samples, data)?;
+        self.write_images(writer, images)
+    }
+
+    /// Appends a raster like [`GeoTiffWriter::write`] writes it to an existing little-endian
+    /// TIFF, e.g. another band stored as a separate image, or a mask or overview of the last
+    /// image, see [`GeoTiffWriter::with_mask`] and [`GeoTiffWriter::with_reduced_resolution`].
+    /// The georeferencing is only written for full-resolution images, not for masks and
+    /// overviews.
+    ///
+    /// The new images are written to the end of the file and linked to the end of its IFD chain,
+    /// leaving everything already in the file untouched. The variant of the file is kept, so
+    /// [`GeoTiffWriter::with_big_tiff`] is ignored, as is the order of a Cloud Optimized GeoTIFF,
+    /// which the file no longer follows.
+    ///
+    /// Fails with [`GeoTiffError::Unsupported`] for big-endian TIFFs, as the writer only encodes
+    /// little-endian ones.
+    pub fn append<F: Read + Write + Seek, T: Sample>(
+        &self,
+        mut file: F,
+        raster_width: usize,
+        raster_height: usize,
+        num_samples: usize,
+        data: &[T],
+    ) -> GeoTiffResult<()> {
+        file.seek(SeekFrom::Start(0))?;
+        let (variant, mut ifd_offset) = TiffVariant::read_header(&mut file)?;
+        let mut next_ifd_offset_position = variant.first_ifd_offset_position();
+        let mut ifd_offsets = HashSet::new();
+        while ifd_offset != 0 {
+            if !ifd_offsets.insert(ifd_offset) {
+                return Err(format_error("The IFD chain contains a cycle"));
+            }
+            (next_ifd_offset_position, ifd_offset) =
+                Ifd::read_next_ifd_offset(&mut file, ifd_offset, variant)?;
+        }
+
+        let mut images = self.encode_images(raster_width, raster_height, num_samples, data)?;
+        let mut offset = file.seek(SeekFrom::End(0))?;
+        // Chunks are written on a word boundary like the IFDs
+        if offset % 2 != 0 {
+            file.write_all(&[0])?;
+            offset += 1;
+        }
+        let (ifd_offsets, length) = layout_images(&mut images, variant, offset, false);
+        if variant == TiffVariant::Classic && length > u32::MAX as u64 {
+            return Err(GeoTiffError::Unsupported(
+                "The GeoTIFF exceeds the size limit of 4 GiB".to_string(),
+            ));
+        }
+        for chunk in images.iter().flat_map(|image| &image.chunks) {
+            file.write_all(chunk)?;
+            offset += chunk.len() as u64;
+        }
+        if offset % 2 != 0 {
+            file.write_all(&[0])?;
+        }
+        for (index, image) in images.iter().enumerate() {
+            let next_ifd_offset = ifd_offsets.get(index + 1).copied().unwrap_or(0);
+            file.write_all(
+                &image
+                    .ifd
+                    .encode(ifd_offsets[index], next_ifd_offset, variant),
+            )?;
+        }
+        file.seek(SeekFrom::Start(next_ifd_offset_position))?;
+        match variant {
+            TiffVariant::Classic => file.write_all(&(ifd_offsets[0] as u32).to_le_bytes())?,
+            TiffVariant::Big => file.write_all(&ifd_offsets[0].to_le_bytes())?,
+        }
+        file.flush()?;
+        Ok(())
+    }
+
+    /// Encodes the raster and its overviews, adding the georeferencing to the former unless it is
+    /// a mask or overview itself.
+    fn encode_images<T: Sample>(
+        &self,
+        raster_width: usize,
+        raster_height: usize,
+        num_samples: usize,
+        data: &[T],
+    ) -> GeoTiffResult<Vec<EncodedImage>> {
+        let mut image = self.encode_image(raster_width, raster_height, num_samples, data)?;
+        if self.subfile_type == 0 {
+            self.insert_geo_tags(&mut image.ifd)?;
+        }
+        let mut images = vec![image];
+        for &factor in &self.overviews {
+            if factor < 2 {
+                return Err(GeoTiffError::InvalidArgument(format!(
+                    "Overview factors must be at least 2, found {}",
+                    factor
+                )));
+            }
+            let (overview, [width, height]) = overview::downsample(
+                data,
+                [raster_width, raster_height],
+                num_samples,
+                factor,
+                self.resampling,
+            );
+            let mut image = self.encode_image(width, height, num_samples, &overview)?;
+            image.ifd.insert(
+                Tag::NewSubfileType,
+                IfdValue::Long(vec![self.subfile_type | Self::REDUCED_RESOLUTION]),
+            );
+            images.push(image);
+        }
+        Ok(images)
+    }
+
+    fn set_subfile_type(&mut self, flag: u32, value: bool) {
+        match value {
+            true => self.subfile_type |= flag,
+            false => self.subfile_type &= !flag,
+        }
+    }
+
+    /// Replaces the georeferencing of the first image of an existing little-endian TIFF with the
+    /// GeoKeys and the coordinate transformation of this writer, without touching the raster
+    /// data, like `gdal_edit.py -a_ullr` or `-a_srs` does. All other options of the writer are
+    /// ignored.
+    ///
+    /// The ModelPixelScaleTag, ModelTiepointTag, ModelTransformationTag and the GeoKey directory
+    /// are removed and written anew, so georeferencing that should be kept has to be set on the
+    /// writer, e.g. from the [`GeoTiff`](crate::GeoTiff) read before. The modified IFD is
+    /// appended to the file, leaving the previous one as unused space.
+    pub fn edit_georeferencing<F: Read + Write + Seek>(&self, mut file: F) -> GeoTiffResult<()> {
+        file.seek(SeekFrom::Start(0))?;
+        let (variant, ifd_offset) = TiffVariant::read_header(&mut file)?;
+        let (mut ifd, next_ifd_offset) = Ifd::read(&mut file, ifd_offset, variant)?;
+        for tag in [
+            Tag::ModelPixelScaleTag,
+            Tag::ModelTiepointTag,
+            Tag::ModelTransformationTag,
+            Tag::GeoKeyDirectoryTag,
+            Tag::GeoDoubleParamsTag,
+            Tag::GeoAsciiParamsTag,
+        ] {
+            ifd.remove(tag);
+        }
+        self.insert_geo_tags(&mut ifd)?;
+        replace_first_ifd(file, &ifd, next_ifd_offset, variant)
+    }
+
+    /// Encodes the raster data into compressed chunks described by an IFD lacking the chunk
+    /// offsets and byte counts.
+    fn encode_image<T: Sample>(
+        &self,
+        raster_width: usize,
+        raster_height: usize,
+        num_samples: usize,
+        data: &[T],
+    ) -> GeoTiffResult<EncodedImage> {
+        if raster_width == 0 || raster_height == 0 || num_samples == 0 {
+            return Err(GeoTiffError::InvalidArgument(
+                "The raster must not be empty".to_string(),
+            ));
+        }
+        if data.len() != raster_width * raster_height * num_samples {
+            return Err(GeoTiffError::InvalidArgument(format!(
+                "Expected {} samples for a raster of {}x{} pixels with {} samples, found {}",
+                raster_width * raster_height * num_samples,
+                raster_width,
+                raster_height,
+                num_samples,
+                data.len()
+            )));
+        }
+
+        let planar = self.planar && num_samples > 1;
+        // The samples of each chunk, which are those of a single band if the bands are planar
+        let (planes, chunk_samples) = if planar {
+            let planes = (0..num_samples)
+                .map(|band| {
+                    Cow::Owned(
+                        data.iter()
+                            .skip(band)
+                            .step_by(num_samples)
+                            .copied()
+                            .collect(),
+                    )
+                })
+                .collect::<Vec<Cow<[T]>>>();
+            (planes, 1)
+        } else {
+            (vec![Cow::Borrowed(data)], num_samples)
+        };
+
+        let is_fill = |sample: &T| self.is_fill(sample);
+        let mut ifd = self.image_ifd::<T>(raster_width, raster_height, num_samples, planar)?;
+        let (chunks, sparse, chunk_width, offsets_tag, byte_counts_tag) = match self.layout {
+            Layout::Strips { .. } => {
+                let rows_per_strip =
+                    self.rows_per_strip::<T>(raster_width * chunk_samples, raster_height)?;
+                ifd.insert(
+                    Tag::RowsPerStrip,
+                    IfdValue::Long(vec![to_u32(rows_per_strip)?]),
+                );
+                let strips = planes
+                    .iter()
+                    .flat_map(|plane| plane.chunks(raster_width * chunk_samples * rows_per_strip))
+                    .collect::<Vec<_>>();
+                (
+                    strips.iter().map(|strip| encode_samples(strip)).collect(),
+                    strips
+                        .iter()
+                        .map(|strip| self.sparse && strip.iter().all(is_fill))
+                        .collect::<Vec<_>>(),
+                    raster_width,
+                    Tag::StripOffsets,
+                    Tag::StripByteCounts,
+                )
+            }
+            Layout::Tiles {
+                tile_width,
+                tile_height,
+            } => {
+                insert_tile_dimensions(&mut ifd, [tile_width, tile_height])?;
+                let tiles = planes
+                    .iter()
+                    .flat_map(|plane| {
+                        encode_tiles(
+                            plane,
+                            [raster_width, raster_height],
+                            chunk_samples,
+                            [tile_width, tile_height],
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                let sparse = match self.sparse {
+                    true => planes
+                        .iter()
+                        .flat_map(|plane| {
+                            sparse_tiles(
+                                plane,
+                                [raster_width, raster_height],
+                                chunk_samples,
+                                [tile_width, tile_height],
+                                is_fill,
+                            )
+                        })
+                        .collect(),
+                    false => vec![false; tiles.len()],
+                };
+                (
+                    tiles,
+                    sparse,
+                    tile_width,
+                    Tag::TileOffsets,
+                    Tag::TileByteCounts,
+                )
+            }
+        };
+
+        let chunks =
+            self.encode_chunks::<T>(chunks, &sparse, chunk_width * chunk_samples, chunk_samples)?;
+        Ok(EncodedImage {
+            ifd,
+            chunks,
+            offsets_tag,
+            byte_counts_tag,
+        })
+    }
+
+    /// The IFD of an image without the tags of its layout, i.e. the tags of the strips or tiles
+    /// and their offsets and byte counts.
+    fn image_ifd<T: Sample>(
+        &self,
+        raster_width: usize,
+        raster_height: usize,
+        num_samples: usize,
+        planar: bool,
+    ) -> GeoTiffResult<Ifd> {
+        self.compression.validate()?;
+        let predictor = self.predictor();
+        compression::validate_predictor(predictor, T::SAMPLE_FORMAT)?;
+
+        #[cfg(feature = "webp")]
+        if self.compression == Compression::WebP
+            && (T::SAMPLE_FORMAT != tiff::tags::SampleFormat::Uint
+                || T::BITS_PER_SAMPLE != 8
+                || !matches!(num_samples, 3 | 4)
+                || planar)
+        {
+            return Err(GeoTiffError::InvalidArgument(format!(
+                "WebP compression requires 3 or 4 bands of u8 samples interleaved by pixel, \
+                 found {} bands of {} bits",
+                num_samples,
+                T::BITS_PER_SAMPLE
+            )));
+        }
+
+        let mask = self.subfile_type & Self::TRANSPARENCY_MASK != 0;
+        if mask && (num_samples != 1 || T::BITS_PER_SAMPLE != 8) {
+            return Err(GeoTiffError::InvalidArgument(format!(
+                "Masks must have a single band of 8 bits, found {} bands of {} bits",
+                num_samples,
+                T::BITS_PER_SAMPLE
+            )));
+        }
+
+        let mut ifd = Ifd::default();
+        if self.subfile_type != 0 {
+            ifd.insert(Tag::NewSubfileType, IfdValue::Long(vec![self.subfile_type]));
+        }
+        if predictor != Predictor::None {
+            ifd.insert(Tag::Predictor, IfdValue::Short(vec![predictor.to_u16()]));
+        }
+        ifd.insert(Tag::ImageWidth, IfdValue::Long(vec![to_u32(raster_width)?]));
+        ifd.insert(
+            Tag::ImageLength,
+            IfdValue::Long(vec![to_u32(raster_height)?]),
+        );
+        ifd.insert(
+            Tag::BitsPerSample,
+            IfdValue::Short(vec![T::BITS_PER_SAMPLE; num_samples]),
+        );
+        ifd.insert(
+            Tag::Compression,
+            IfdValue::Short(vec![self.compression.to_u16()]),
+        );
+        let photometric_interpretation = match num_samples {
+            _ if mask => PhotometricInterpretation::TransparencyMask,
+            3 | 4 => PhotometricInterpretation::RGB,
+            _ => PhotometricInterpretation::BlackIsZero,
+        };
+        ifd.insert(
+            Tag::PhotometricInterpretation,
+            IfdValue::Short(vec![photometric_interpretation.to_u16()]),
+        );
+        let color_samples = match photometric_interpretation {
+            PhotometricInterpretation::RGB => 3,
+            _ => 1,
+        };
+        if num_samples > color_samples {
+            // Unspecified data
+            ifd.insert(
+                Tag::ExtraSamples,
+                IfdValue::Short(vec![0; num_samples - color_samples]),
+            );
+        }
+        ifd.insert(
+            Tag::SamplesPerPixel,
+            IfdValue::Short(vec![num_samples as u16]),
+        );
+        ifd.insert(
+            Tag::PlanarConfiguration,
+            IfdValue::Short(vec![if planar {
+                PlanarConfiguration::Planar.to_u16()
+            } else {
+                PlanarConfiguration::Chunky.to_u16()
+            }]),
+        );
+        ifd.insert(
+            Tag::SampleFormat,
+            IfdValue::Short(vec![T::SAMPLE_FORMAT.to_u16(); num_samples]),
+        );
+        if let Some(nodata) = self.nodata {
+            // Formatted like GDAL does, e.g. "-9999" or "nan"
+            let text = if nodata.is_nan() {
+                "nan".to_string()
+            } else {
+                nodata.to_string()
+            };
+            ifd.insert(Tag::GdalNodata, IfdValue::Ascii(text));
+        }
+        Ok(ifd)
+    }
+
+    /// The number of rows per strip of rows of `row_samples` samples, which is about 8 KiB by
+    /// default.
+    fn rows_per_strip<T: Sample>(
+        &self,
+        row_samples: usize,
+        raster_height: usize,
+    ) -> GeoTiffResult<usize> {
+        let row_size = row_samples * T::BITS_PER_SAMPLE as usize / 8;
+        match self.layout {
+            Layout::Strips {
+                rows_per_strip: Some(0),
+            } => Err(GeoTiffError::InvalidArgument(
+                "RowsPerStrip must be positive".to_string(),
+            )),
+            Layout::Strips {
+                rows_per_strip: Some(rows_per_strip),
+            } => Ok(rows_per_strip.min(raster_height)),
+            _ => Ok((Self::STRIP_SIZE / row_size).clamp(1, raster_height)),
+        }
+    }
+
+    /// Whether the sample is the nodata value, or 0 without a nodata value, such that chunks of
+    /// only such samples may be omitted.
+    fn is_fill<T: Sample>(&self, sample: &T) -> bool {
+        let fill = self.nodata.unwrap_or(0.0);
+        sample
+            .to_f64()
+            .is_some_and(|value| value == fill || value.is_nan() && fill.is_nan())
+    }
+
+    /// The variant of a file of a single image with `chunk_count` chunks of `data_len` bytes in
+    /// total, which is a classic TIFF unless a BigTIFF is requested or required.
+    fn single_image_variant(
+        &self,
+        ifd: &Ifd,
+        [offsets_tag, byte_counts_tag]: [Tag; 2],
+        chunk_count: usize,
+        data_len: u64,
+    ) -> TiffVariant {
+        match self.big_tiff {
+            Some(true) => TiffVariant::Big,
+            Some(false) => TiffVariant::Classic,
+            None => {
+                let mut ifd = ifd.clone();
+                for tag in [offsets_tag, byte_counts_tag] {
+                    ifd.insert(
+                        tag,
+                        IfdValue::offsets(vec![0; chunk_count], TiffVariant::Classic),
+                    );
+                }
+                // Including the padding of the IFD to a word boundary
+                let length = TiffVariant::Classic.header_len()
+                    + data_len
+                    + 1
+                    + ifd.encoded_len(TiffVariant::Classic);
+                match length <= u32::MAX as u64 {
+                    true => TiffVariant::Classic,
+                    false => TiffVariant::Big,
+                }
+            }
+        }
+    }
+
+    /// The predictor applied before compression, which is none for uncompressed data.
+    fn predictor(&self) -> Predictor {
+        match self.compression {
+            Compression::None => Predictor::None,
+            // The WebP codec applies its own prediction
+            #[cfg(feature = "webp")]
+            Compression::WebP => Predictor::None,
+            _ => self.predictor.unwrap_or(Predictor::None),
+        }
+    }
+
+    /// Applies the predictor to a strip or tile with rows of `row_samples` samples and pixels of
+    /// `num_samples` samples, and compresses it.
+    fn encode_chunk<T: Sample>(
+        &self,
+        mut chunk: Vec<u8>,
+        row_samples: usize,
+        num_samples: usize,
+    ) -> GeoTiffResult<Vec<u8>> {
+        let sample_size = T::BITS_PER_SAMPLE as usize / 8;
+        compression::apply_predictor(
+            self.predictor(),
+            &mut chunk,
+            row_samples * sample_size,
+            sample_size,
+            num_samples,
+        );
+        #[cfg(feature = "webp")]
+        if self.compression == Compression::WebP {
+            return compression::compress_webp(&chunk, row_samples / num_samples, num_samples);
+        }
+        self.compression.compress(chunk)
+    }
+
+    /// Encodes the chunks like [`GeoTiffWriter::encode_chunk`], leaving sparse chunks empty.
+    ///
+    /// With the `rayon` feature, the chunks are compressed in parallel on the current thread
+    /// pool, keeping their order.
+    fn encode_chunks<T: Sample>(
+        &self,
+        chunks: Vec<Vec<u8>>,
+        sparse: &[bool],
+        row_samples: usize,
+        num_samples: usize,
+    ) -> GeoTiffResult<Vec<Vec<u8>>> {
+        let encode = |(chunk, &sparse): (Vec<u8>, &bool)| match sparse {
+            // Sparse chunks are not stored at all
+            true => Ok(Vec::new()),
+            false => self.encode_chunk::<T>(chunk, row_samples, num_samples),
+        };
+        #[cfg(feature = "rayon")]
+        {
+            use rayon::prelude::*;
+            chunks.into_par_iter().zip(sparse).map(encode).collect()
+        }
+        #[cfg(not(feature = "rayon"))]
+        chunks.into_iter().zip(sparse).map(encode).collect()
+    }
+
+    /// Writes the chunks of all images, followed by the chain of their IFDs, or in the order of
+    /// a Cloud Optimized GeoTIFF.
+    fn write_images<W: Write>(
+        &self,
+        mut writer: W,
+        mut images: Vec<EncodedImage>,
+    ) -> GeoTiffResult<()> {
+        let ifds_first = self.cloud_optimized;
+        let variant = match self.big_tiff {
+            Some(true) => TiffVariant::Big,
+            Some(false) => TiffVariant::Classic,
+            None if layout_images(
+                &mut images,
+                TiffVariant::Classic,
+                TiffVariant::Classic.header_len(),
+                ifds_first,
+            )
+            .1 <= u32::MAX as u64 =>
+            {
+                TiffVariant::Classic
+            }
+            None => TiffVariant::Big,
+        };
+        let (ifd_offsets, length) =
+            layout_images(&mut images, variant, variant.header_len(), ifds_first);
+        if variant == TiffVariant::Classic && length > u32::MAX as u64 {
+            return Err(GeoTiffError::Unsupported(
+                "The GeoTIFF exceeds the size limit of 4 GiB, consider writing a BigTIFF"
+                    .to_string(),
+            ));
+        }
+
+        writer.write_all(&variant.encode_header(ifd_offsets[0]))?;
+        let write_ifds = |writer: &mut W| -> GeoTiffResult<()> {
+            for (index, image) in images.iter().enumerate() {
+                let next_ifd_offset = ifd_offsets.get(index + 1).copied().unwrap_or(0);
+                writer.write_all(&image.ifd.encode(
+                    ifd_offsets[index],
+                    next_ifd_offset,
+                    variant,
+                ))?;
+            }
+            Ok(())
+        };
+        if ifds_first {
+            write_ifds(&mut writer)?;
+            for chunk in images.iter().rev().flat_map(|image| &image.chunks) {
+                writer.write_all(chunk)?;
+            }
+        } else {
+            let mut offset = variant.header_len();
+            for chunk in images.iter().flat_map(|image| &image.chunks) {
+                writer.write_all(chunk)?;
+                offset += chunk.len() as u64;
+            }
+            if offset % 2 != 0 {
+                writer.write_all(&[0])?;
+            }
+            write_ifds(&mut writer)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Adds the georeferencing tags and the GeoKey directory to the IFD.
+    fn insert_geo_tags(&self, ifd: &mut Ifd) -> GeoTiffResult<()> {
+        if let Some(coordinate_transform) = &self.coordinate_transform {
+            let mut coordinate_transform = coordinate_transform.clone();
+            // Undo the offset applied when reading
+            let [x, y] = self
+                .geo_key_directory
+                .raster_type()
+                .unwrap_or_default()
+                .raster_space_offset();
+            coordinate_transform.offset_raster_space([-x, -y]);
+
+            let (pixel_scale_data, model_tie_points_data, model_transformation_data) =
+                coordinate_transform.to_tag_data()?;
+            for (tag, data) in [
+                (Tag::ModelPixelScaleTag, pixel_scale_data),
+                (Tag::ModelTiepointTag, model_tie_points_data),
+                (Tag::ModelTransformationTag, model_transformation_data),
+            ] {
+                if let Some(data) = data {
+                    ifd.insert(tag, IfdValue::Double(data));
+                }
+            }
+        }
+
+        if !self.geo_key_directory.is_empty() {
+            let data = self.geo_key_directory.to_tag_data()?;
+            ifd.insert(
+                Tag::GeoKeyDirectoryTag,
+                IfdValue::Short(data.directory_data),
+            );
+            if let Some(data) = data.double_params_data {
+                ifd.insert(Tag::GeoDoubleParamsTag, IfdValue::Double(data));
+            }
+            if let Some(data) = data.ascii_params_data {
+                ifd.insert(Tag::GeoAsciiParamsTag, IfdValue::Ascii(data));
+            }
+            // The tags vendor-specific keys refer to, which must not replace other tags
+            for (location, values) in data.referenced_data {
+                let tag = Tag::from_u16_exhaustive(location);
+                let value = IfdValue::from_tag_value(&values, TiffVariant::Classic)?;
+                if ifd.get(tag).is_some_and(|existing| *existing != value) {
+                    return Err(GeoTiffError::InvalidArgument(format!(
+                        "GeoKeys refer to tag {}, which holds other values",
+                        location
+                    )));
+                }
+                ifd.insert(tag, value);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Adds or replaces tags of the first image of an existing little-endian TIFF, see
+/// [`GeoTiff::update_tags`](crate::GeoTiff::update_tags).
+pub(crate) fn update_tags<F: Read + Write + Seek>(
+    mut file: F,
+    tags: &[(Tag, TagValue)],
+) -> GeoTiffResult<()> {
+    file.seek(SeekFrom::Start(0))?;
+    let (variant, ifd_offset) = TiffVariant::read_header(&mut file)?;
+    let values = tags
+        .iter()
+        .map(|(tag, value)| Ok((*tag, IfdValue::from_tag_value(value, variant)?)))
+        .collect::<GeoTiffResult<Vec<_>>>()?;
+    if Ifd::update_in_place(&mut file, ifd_offset, variant, &values)? {
+        file.flush()?;
+        return Ok(());
+    }
+
+    let (mut ifd, next_ifd_offset) = Ifd::read(&mut file, ifd_offset, variant)?;
+    for (tag, value) in values {
+        ifd.insert(tag, value);
+    }
+    replace_first_ifd(file, &ifd, next_ifd_offset, variant)
+}
+
+/// Appends the IFD to the file and makes it the first one, leaving the previous one as unused
+/// space.
+fn replace_first_ifd<F: Write + Seek>(
+    mut file: F,
+    ifd: &Ifd,
+    next_ifd_offset: u64,
+    variant: TiffVariant,
+) -> GeoTiffResult<()> {
+    let mut offset = file.seek(SeekFrom::End(0))?;
+    // The IFD must begin on a word boundary
+    if offset % 2 != 0 {
+        file.write_all(&[0])?;
+        offset += 1;
+    }
+    if variant == TiffVariant::Classic && offset + ifd.encoded_len(variant) > u32::MAX as u64 {
+        return Err(GeoTiffError::Unsupported(
+            "The GeoTIFF exceeds the size limit of 4 GiB".to_string(),
+        ));
+    }
+    file.write_all(&ifd.encode(offset, next_ifd_offset, variant))?;
+    file.seek(SeekFrom::Start(variant.first_ifd_offset_position()))?;
+    match variant {
+        TiffVariant::Classic => file.write_all(&(offset as u32).to_le_bytes())?,
+        TiffVariant::Big => file.write_all(&offset.to_le_bytes())?,
+    }
+    file.flush()?;
+    Ok(())
+}
+
+/// An image encoded by a [`GeoTiffWriter`], with the tag of its chunk offsets and byte counts.
+struct EncodedImage {
+    ifd: Ifd,
+    chunks: Vec<Vec<u8>>,
+    offsets_tag: Tag,
+    byte_counts_tag: Tag,
+}
+
+impl EncodedImage {
+    fn insert_chunk_offsets(&mut self, chunk_offsets: Vec<u64>, variant: TiffVariant) {
+        let byte_counts = self.chunks.iter().map(|chunk| chunk.len() as u64);
+        self.ifd
+            .insert(self.offsets_tag, IfdValue::offsets(chunk_offsets, variant));
+        self.ifd.insert(
+            self.byte_counts_tag,
+            IfdValue::offsets(byte_counts.collect(), variant),
+        );
+    }
+}
+
+/// Arranges the chunks of all images from `offset` on, usually the end of the header, followed by
+/// the IFDs, and inserts the chunk offsets and byte counts into the IFDs. Returns the offsets of
+/// the IFDs and the length of the file.
+///
+/// If `ifds_first` is set, the IFDs directly follow the header instead, and the chunks of the
+/// images follow in reverse order, i.e. the smallest overview first, as Cloud Optimized GeoTIFFs
+/// require.
+fn layout_images(
+    images: &mut [EncodedImage],
+    variant: TiffVariant,
+    mut offset: u64,
+    ifds_first: bool,
+) -> (Vec<u64>, u64) {
+    let mut chunk_offsets = vec![Vec::new(); images.len()];
+    let mut ifd_offsets = Vec::with_capacity(images.len());
+    if ifds_first {
+        // The lengths of the IFDs do not depend on the values of the chunk offsets
+        for image in images.iter_mut() {
+            image.insert_chunk_offsets(vec![0; image.chunks.len()], variant);
+            ifd_offsets.push(offset);
+            offset += image.ifd.encoded_len(variant);
+        }
+    }
+    let order = match ifds_first {
+        true => (0..images.len()).rev().collect::<Vec<_>>(),
+        false => (0..images.len()).collect(),
+    };
+    for index in order {
+        for chunk in &images[index].chunks {
+            // Only sparse chunks are empty, which have an offset of 0
+            chunk_offsets[index].push(if chunk.is_empty() { 0 } else { offset });
+            offset += chunk.len() as u64;
+        }
+    }
+    for (image, chunk_offsets) in images.iter_mut().zip(chunk_offsets) {
+        image.insert_chunk_offsets(chunk_offsets, variant);
+    }
+    if !ifds_first {
+        // IFDs must begin on a word boundary
+        offset += offset % 2;
+        for image in images.iter() {
+            ifd_offsets.push(offset);
+            offset += image.ifd.encoded_len(variant);
+        }
+    }
+    (ifd_offsets, offset)
+}
+
+fn encode_samples<T: Sample>(samples: &[T]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(samples.len() * T::BITS_PER_SAMPLE as usize / 8);
+    for sample in samples {
+        sample.write_le_bytes(&mut bytes);
+    }
+    bytes
+}
+
+/// Encodes the tiles of a raster row by row, padding the tiles at the right and bottom edges with
+/// zeros.
+fn encode_tiles<T: Sample>(
+    data: &[T],
+    [raster_width, raster_height]: [usize; 2],
+    num_samples: usize,
+    [tile_width, tile_height]: [usize; 2],
+) -> Vec<Vec<u8>> {
+    let tile_row_size = tile_width * num_samples * T::BITS_PER_SAMPLE as usize / 8;
+    let mut tiles = Vec::new();
+    for tile_y in (0..raster_height).step_by(tile_height) {
+        for tile_x in (0..raster_width).step_by(tile_width) {
+            let mut bytes = Vec::with_capacity(tile_row_size * tile_height);
+            for y in tile_y..(tile_y + tile_height).min(raster_height) {
+                let row_start = bytes.len();
+                let start = (y * raster_width + tile_x) * num_samples;
+                let end =
+                    (y * raster_width + (tile_x + tile_width).min(raster_width)) * num_samples;
+                for sample in &data[start..end] {
+                    sample.write_le_bytes(&mut bytes);
+                }
+                bytes.resize(row_start + tile_row_size, 0);
+            }
+            bytes.resize(tile_row_size * tile_height, 0);
+            tiles.push(bytes);
+        }
+    }
+    tiles
+}
+
+/// Whether each tile of a raster, row by row, contains only samples for which `is_fill` holds,
+/// disregarding the padding at the right and bottom edges.
+fn sparse_tiles<T: Sample>(
+    data: &[T],
+    [raster_width, raster_height]: [usize; 2],
+    num_samples: usize,
+    [tile_width, tile_height]: [usize; 2],
+    is_fill: impl Fn(&T) -> bool,
+) -> Vec<bool> {
+    let mut sparse = Vec::new();
+    for tile_y in (0..raster_height).step_by(tile_height) {
+        for tile_x in (0..raster_width).step_by(tile_width) {
+            sparse.push(
+                (tile_y..(tile_y + tile_height).min(raster_height)).all(|y| {
+                    let start = (y * raster_width + tile_x) * num_samples;
+                    let end =
+                        (y * raster_width + (tile_x + tile_width).min(raster_width)) * num_samples;
+                    data[start..end].iter().all(&is_fill)
+                }),
+            );
+        }
+    }
+    sparse
+}
+
+/// Validates the dimensions of tiles, which are multiples of 16, and adds them to the IFD.
+fn insert_tile_dimensions(
+    ifd: &mut Ifd,
+    [tile_width, tile_height]: [usize; 2],
+) -> GeoTiffResult<()> {
+    if tile_width == 0 || tile_width % 16 != 0 || tile_height == 0 || tile_height % 16 != 0 {
+        return Err(GeoTiffError::InvalidArgument(format!(
+            "Tile dimensions must be positive multiples of 16, found {}x{}",
+            tile_width, tile_height
+        )));
+    }
+    ifd.insert(Tag::TileWidth, IfdValue::Long(vec![to_u32(tile_width)?]));
+    ifd.insert(Tag::TileLength, IfdValue::Long(vec![to_u32(tile_height)?]));
+    Ok(())
+}
+
+/// Converts a dimension to a TIFF LONG.
+fn to_u32(value: usize) -> GeoTiffResult<u32> {
+    u32::try_from(value).map_err(|_| {
+        GeoTiffError::Unsupported(format!("{} exceeds the range of a TIFF LONG", value))
+    })
+}
+
+fn format_error(message: impl Into<String>) -> GeoTiffError {
+    GeoTiffError::Tiff(TiffError::FormatError(TiffFormatError::Format(
+        message.into(),
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use tiff::decoder::{Decoder, DecodingResult};
+
+    use crate::geo_key_directory::{GeoKey, GeoKeyValue, RasterType};
+    use crate::{GeoTiff, GeoTiffReader, Layout};
+
+    use super::*;
+
+    fn write<T: Sample>(
+        writer: &GeoTiffWriter,
+        raster_width: usize,
+        raster_height: usize,
+        num_samples: usize,
+        data: &[T],
+    ) -> GeoTiff {
+        let mut bytes = Vec::new();
+        writer
+            .write(&mut bytes, raster_width, raster_height, num_samples, data)
+            .unwrap();
+        GeoTiff::read(Cursor::new(bytes)).unwrap()
+    }
+
+    #[test]
+    fn test_write_raster_data() {
+        let data = (0..300 * 20).map(|value| value as f32).collect::<Vec<_>>();
+        let geotiff = write(&GeoTiffWriter::new(), 300, 20, 1, &data);
+
+        assert_eq!(geotiff.raster_width, 300);
+        assert_eq!(geotiff.raster_height, 20);
+        assert_eq!(geotiff.num_samples, 1);
+        assert_eq!(geotiff.get_value_at::<f32>(0, 0, 0), 0.0);
+        assert_eq!(geotiff.get_value_at::<f32>(299, 19, 0), 5999.0);
+        assert_eq!(geotiff.get_value_at::<f32>(7, 13, 0), 3907.0);
+        assert!(geotiff.coordinate_transform().is_none());
+        assert!(geotiff.geo_key_directory().is_empty());
+
+        let data = (0..5 * 3 * 3).map(|value| value as u16).collect::<Vec<_>>();
+        let geotiff = write(&GeoTiffWriter::new(), 5, 3, 3, &data);
+        assert_eq!(geotiff.num_samples, 3);
+        assert_eq!(geotiff.get_value_at::<u16>(4, 2, 1), 43);
+
+        let geotiff = write(&GeoTiffWriter::new(), 3, 1, 1, &[-1i8, 0, 1]);
+        assert_eq!(geotiff.get_value_at::<i8>(0, 0, 0), -1);
+    }
+
+    #[test]
+    fn test_write_nodata() {
+        let writer = GeoTiffWriter::new().with_nodata(-9999.0);
+        let geotiff = write(&writer, 3, 2, 1, &[1i16, -9999, 3, 4, 5, -9999]);
+        assert_eq!(geotiff.nodata::<i16>(), Some(-9999));
+        assert_eq!(geotiff.nodata::<f64>(), Some(-9999.0));
+        assert_eq!(geotiff.get_value_at::<i16>(1, 0, 0), -9999);
+        assert_eq!(geotiff.get_value_at_checked::<i16>(1, 0, 0), None);
+        assert_eq!(geotiff.get_value_at_checked::<i16>(2, 0, 0), Some(3));
+        assert_eq!(
+            geotiff.get_window_values_checked::<f64>(1, 0, 2, 2, 0),
+            [None, Some(3.0), Some(5.0), None]
+        );
+        // Nodata and samples not representable by the requested type yield None without panicking
+        assert_eq!(geotiff.get_value_at_checked::<u8>(1, 0, 0), None);
+        assert_eq!(geotiff.get_value_at_checked::<u8>(2, 0, 0), Some(3));
+        assert_eq!(
+            geotiff.get_window_values_checked::<u16>(0, 0, 3, 2, 0),
+            [Some(1), None, Some(3), Some(4), Some(5), None]
+        );
+        let geotiff = write(&writer, 2, 1, 1, &[300i16, -5]);
+        assert_eq!(
+            geotiff.get_window_values_checked::<u8>(0, 0, 2, 1, 0),
+            [None, None]
+        );
+
+        let writer = GeoTiffWriter::new().with_nodata(f64::NAN);
+        let geotiff = write(&writer, 2, 1, 1, &[f32::NAN, 0.5]);
+        assert!(geotiff.nodata::<f32>().unwrap().is_nan());
+        assert_eq!(geotiff.get_value_at_checked::<f32>(0, 0, 0), None);
+        assert_eq!(geotiff.get_value_at_checked::<f32>(1, 0, 0), Some(0.5));
+
+        // Nodata values not representable by the sample type are ignored
+        let geotiff = write(&GeoTiffWriter::new().with_nodata(-1.0), 2, 1, 1, &[0u8; 2]);
+        assert_eq!(geotiff.nodata::<u8>(), None);
+
+        let geotiff = write(&GeoTiffWriter::new(), 2, 1, 1, &[0u8; 2]);
+        assert_eq!(geotiff.nodata::<u8>(), None);
+        assert_eq!(geotiff.get_value_at_checked::<u8>(0, 0, 0), Some(0));
+    }
+
+    #[test]
+    fn test_write_strips() {
+        let data = (0..10 * 7).collect::<Vec<i32>>();
+        for rows_per_strip in [1, 3, 7, 100] {
+            let geotiff = write(
+                &GeoTiffWriter::new().with_strips(rows_per_strip),
+                10,
+                7,
+                1,
+                &data,
+            );
+            assert_eq!(geotiff.get_value_at::<i32>(0, 0, 0), 0);
+            assert_eq!(geotiff.get_value_at::<i32>(4, 5, 0), 54);
+            assert_eq!(geotiff.get_value_at::<i32>(9, 6, 0), 69);
+        }
+
+        let mut bytes = Vec::new();
+        assert!(GeoTiffWriter::new()
+            .with_strips(0)
+            .write(&mut bytes, 2, 2, 1, &[0u8; 4])
+            .is_err());
+    }
+
+    #[test]
+    fn test_write_tiles() {
+        let data = (0..40 * 20).map(|value| value as u16).collect::<Vec<_>>();
+        let geotiff = write(&GeoTiffWriter::new().with_tiles(16, 32), 40, 20, 1, &data);
+
+        assert_eq!(geotiff.raster_width, 40);
+        assert_eq!(geotiff.raster_height, 20);
+        assert_eq!(geotiff.get_value_at::<u16>(0, 0, 0), 0);
+        assert_eq!(geotiff.get_value_at::<u16>(17, 3, 0), 137);
+        assert_eq!(geotiff.get_value_at::<u16>(39, 19, 0), 799);
+
+        let data = (0..20 * 17 * 3)
+            .map(|value| value as u8)
+            .collect::<Vec<_>>();
+        let geotiff = write(&GeoTiffWriter::new().with_tiles(16, 16), 20, 17, 3, &data);
+        assert_eq!(geotiff.get_value_at::<u8>(19, 16, 2), 251);
+
+        let mut bytes = Vec::new();
+        assert!(GeoTiffWriter::new()
+            .with_tiles(100, 100)
+            .write(&mut bytes, 2, 2, 1, &[0u8; 4])
+            .is_err());
+    }
+
+    #[test]
+    fn test_write_compressed() {
+        let data = (0..64 * 40)
+            .map(|value| (value / 64 + value % 64) as u16)
+            .collect::<Vec<_>>();
+        let uncompressed_size = data.len() * 2;
+        for compression in [Compression::Deflate { level: 6 }, Compression::Lzw] {
+            for predictor in [Predictor::None, Predictor::Horizontal] {
+                for writer in [
+                    GeoTiffWriter::new(),
+                    GeoTiffWriter::new().with_tiles(32, 16),
+                ] {
+                    let writer = writer
+                        .with_compression(compression)
+                        .with_predictor(predictor);
+                    let mut bytes = Vec::new();
+                    writer.write(&mut bytes, 64, 40, 1, &data).unwrap();
+                    assert!(bytes.len() < uncompressed_size);
+
+                    let geotiff = GeoTiff::read(Cursor::new(bytes)).unwrap();
+                    assert_eq!(geotiff.get_value_at::<u16>(0, 0, 0), 0);
+                    assert_eq!(geotiff.get_value_at::<u16>(17, 3, 0), 20);
+                    assert_eq!(geotiff.get_value_at::<u16>(63, 39, 0), 102);
+                }
+            }
+        }
+
+        // Smoothly varying floats, which the floating point predictor makes compressible
+        let data = (0..64 * 40)
+            .map(|value| 100.0 + ((value / 64) as f32 * 0.1).sin() + (value % 64) as f32 * 0.01)
+            .collect::<Vec<_>>();
+        let size_without_predictor = |compression| {
+            let mut bytes = Vec::new();
+            GeoTiffWriter::new()
+                .with_compression(compression)
+                .write(&mut bytes, 64, 40, 1, &data)
+                .unwrap();
+            bytes.len()
+        };
+        for compression in [Compression::Deflate { level: 6 }, Compression::Lzw] {
+            for (num_samples, writer) in [
+                (1, GeoTiffWriter::new()),
+                (2, GeoTiffWriter::new().with_tiles(16, 16)),
+            ] {
+                let writer = writer
+                    .with_compression(compression)
+                    .with_predictor(Predictor::FloatingPoint);
+                let mut bytes = Vec::new();
+                writer
+                    .write(&mut bytes, 64 / num_samples, 40, num_samples, &data)
+                    .unwrap();
+                if num_samples == 1 {
+                    assert!(bytes.len() < size_without_predictor(compression));
+                }
+
+                let geotiff = GeoTiff::read(Cursor::new(bytes)).unwrap();
+                assert_eq!(geotiff.get_values::<f32>(Layout::PixelInterleaved), data);
+            }
+        }
+        let data = data.iter().map(|&value| value as f64).collect::<Vec<_>>();
+        let geotiff = write(
+            &GeoTiffWriter::new()
+                .with_compression(Compression::Deflate { level: 6 })
+                .with_predictor(Predictor::FloatingPoint),
+            64,
+            40,
+            1,
+            &data,
+        );
+        assert_eq!(geotiff.get_values::<f64>(Layout::PixelInterleaved), data);
+
+        let mut bytes = Vec::new();
+        assert!(GeoTiffWriter::new()
+            .with_compression(Compression::Lzw)
+            .with_predictor(Predictor::FloatingPoint)
+            .write(&mut bytes, 2, 2, 1, &[0u8; 4])
+            .is_err());
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_write_parallel() {
+        let data = (0..100 * 60)
+            .map(|value| (value / 100 * 3 + value % 100) as u16)
+            .collect::<Vec<_>>();
+        for writer in [
+            GeoTiffWriter::new().with_strips(4),
+            GeoTiffWriter::new()
+                .with_tiles(32, 16)
+                .with_sparse(true)
+                .add_overviews(&[2], Resampling::Average),
+        ] {
+            let writer = writer.with_compression(Compression::Lzw);
+            let write = |threads| {
+                let pool = rayon::ThreadPoolBuilder::new()
+                    .num_threads(threads)
+                    .build()
+                    .unwrap();
+                let mut bytes = Vec::new();
+                pool.install(|| writer.write(&mut bytes, 100, 60, 1, &data))
+                    .unwrap();
+                bytes
+            };
+            // The chunks are written in the same order regardless of the threads
+            let sequential = write(1);
+            for threads in [2, 3, 64] {
+                assert_eq!(write(threads), sequential);
+            }
+            let geotiff = GeoTiff::read(Cursor::new(sequential)).unwrap();
+            assert_eq!(geotiff.get_values::<u16>(Layout::PixelInterleaved), data);
+        }
+    }
+
+    #[test]
+    fn test_write_georeferencing() {
+        let mut geo_key_directory = GeoKeyDirectory::default();
+        geo_key_directory.set_model_type(ModelType::Projected);
+        geo_key_directory.set_raster_type(RasterType::PixelIsPoint);
+        geo_key_directory.insert(GeoKey::ProjectedCSType, GeoKeyValue::Short(vec![32632]));
+        geo_key_directory.insert(
+            GeoKey::GTCitation,
+            GeoKeyValue::Ascii("WGS 84 / UTM zone 32N".to_string()),
+        );
+        // Vendor-specific keys are written as they are
+        let vendor_keys = [
+            (GeoKey::Unknown(60000), GeoKeyValue::Double(vec![0.5, 1.5])),
+            (
+                GeoKey::Unknown(60001),
+                GeoKeyValue::Reference {
+                    location: 50000,
+                    values: TagValue::Signed(vec![-1, 1]),
+                },
+            ),
+        ];
+        for (key, value) in vendor_keys.clone() {
+            geo_key_directory.insert(key, value);
+        }
+        let transform = CoordinateTransform::from_tie_point_and_scale(
+            [0.0, 0.0],
+            [500000.0, 5300000.0],
+            [30.0, 30.0],
+        )
+        .unwrap();
+        let writer = GeoTiffWriter::new()
+            .with_geo_key_directory(geo_key_directory)
+            .with_geotiff_version(GeoTiffVersion::V1_1)
+            .with_coordinate_transform(transform);
+
+        let geotiff = write(&writer, 4, 4, 1, &[0u8; 16]);
+        assert_eq!(geotiff.geotiff_version(), Some(GeoTiffVersion::V1_1));
+        assert_eq!(geotiff.epsg_code(), Some(32632));
+        assert_eq!(geotiff.citation(), Some("WGS 84 / UTM zone 32N"));
+        assert_eq!(geotiff.raster_type(), RasterType::PixelIsPoint);
+        for (key, value) in &vendor_keys {
+            assert_eq!(geotiff.geo_key_directory().get(*key), Some(value));
+        }
+        let mut bytes = Vec::new();
+        writer.write(&mut bytes, 4, 4, 1, &[0u8; 16]).unwrap();
+        let metadata = GeoTiff::metadata(Cursor::new(bytes)).unwrap();
+        assert_eq!(
+            metadata.images[0].get(50000),
+            Some(&TagValue::Signed(vec![-1, 1]))
+        );
+        assert_eq!(metadata.images[0].geo_keys().count(), 6);
+        assert_eq!(
+            geotiff.transform_to_model([0, 0]),
+            Some([500000.0, 5300000.0])
+        );
+
+        // Keys must not refer to tags written for the image
+        let mut geo_key_directory = GeoKeyDirectory::default();
+        geo_key_directory.insert(
+            GeoKey::Unknown(60000),
+            GeoKeyValue::Reference {
+                location: Tag::ImageWidth.to_u16(),
+                values: TagValue::Unsigned(vec![5]),
+            },
+        );
+        assert!(GeoTiffWriter::new()
+            .with_geo_key_directory(geo_key_directory)
+            .write(&mut Vec::new(), 4, 4, 1, &[0u8; 16])
+            .is_err());
+
+        let transform =
+            CoordinateTransform::from_affine([2.0, 1.0, 10.0, 0.0, -2.0, 20.0]).unwrap();
+        let geotiff = write(
+            &GeoTiffWriter::new().with_coordinate_transform(transform),
+            4,
+            4,
+            1,
+            &[0u8; 16],
+        );
+        assert_eq!(geotiff.transform_to_model([3, 4]), Some([20.0, 12.0]));
+    }
+
+    #[test]
+    fn test_write_epsg() {
+        let writer = GeoTiffWriter::new().with_epsg(32633).unwrap();
+        let geotiff = write(&writer, 4, 4, 1, &[0u8; 16]);
+        let geo_key_directory = geotiff.geo_key_directory();
+        assert_eq!(geo_key_directory.model_type(), Some(ModelType::Projected));
+        assert_eq!(
+            geo_key_directory.raster_type(),
+            Some(RasterType::PixelIsArea)
+        );
+        assert_eq!(geo_key_directory.projected_type(), Some(32633));
+        assert_eq!(geo_key_directory.proj_linear_units(), Some(9001));
+        assert_eq!(geo_key_directory.geog_angular_units(), Some(9102));
+        assert_eq!(geotiff.citation(), Some("WGS 84 / UTM zone 33N"));
+        assert_eq!(geotiff.geog_citation(), Some("WGS 84"));
+
+        let mut geo_key_directory = GeoKeyDirectory::default();
+        geo_key_directory.set_raster_type(RasterType::PixelIsPoint);
+        let writer = GeoTiffWriter::new()
+            .with_geo_key_directory(geo_key_directory)
+            .with_epsg(32633)
+            .unwrap()
+            .with_epsg(4326)
+            .unwrap();
+        let geotiff = write(&writer, 4, 4, 1, &[0u8; 16]);
+        let geo_key_directory = geotiff.geo_key_directory();
+        assert_eq!(geo_key_directory.model_type(), Some(ModelType::Geographic));
+        assert_eq!(
+            geo_key_directory.raster_type(),
+            Some(RasterType::PixelIsPoint)
+        );
+        assert_eq!(geo_key_directory.geographic_type(), Some(4326));
+        assert_eq!(geo_key_directory.projected_type(), None);
+        assert_eq!(geo_key_directory.citation(), None);
+        assert_eq!(geotiff.epsg_code(), Some(4326));
+
+        // EPSG codes without a built-in definition are written without citations and units, given
+        // their model type
+        assert!(GeoTiffWriter::new().with_epsg(2056).is_err());
+        let writer = GeoTiffWriter::new()
+            .with_epsg(4326)
+            .unwrap()
+            .with_epsg_code(2056, ModelType::Projected)
+            .unwrap();
+        let geotiff = write(&writer, 4, 4, 1, &[0u8; 16]);
+        let geo_key_directory = geotiff.geo_key_directory();
+        assert_eq!(geo_key_directory.model_type(), Some(ModelType::Projected));
+        assert_eq!(geo_key_directory.projected_type(), Some(2056));
+        assert_eq!(geo_key_directory.geographic_type(), None);
+        assert_eq!(geo_key_directory.geog_citation(), None);
+        assert_eq!(geotiff.epsg_code(), Some(2056));
+        // GDA2020 and the geocentric ITRF2014
+        for (code, model_type) in [(7844, ModelType::Geographic), (7789, ModelType::Geocentric)] {
+            let writer = GeoTiffWriter::new()
+                .with_epsg_code(code, model_type)
+                .unwrap();
+            let geo_key_directory = write(&writer, 4, 4, 1, &[0u8; 16])
+                .geo_key_directory()
+                .clone();
+            assert_eq!(geo_key_directory.model_type(), Some(model_type));
+            assert_eq!(geo_key_directory.geographic_type(), Some(code));
+            assert_eq!(geo_key_directory.projected_type(), None);
+        }
+
+        assert!(GeoTiffWriter::new().with_epsg(1).is_err());
+        assert!(GeoTiffWriter::new()
+            .with_epsg_code(1, ModelType::Projected)
+            .is_err());
+        assert!(GeoTiffWriter::new()
+            .with_epsg_code(GeoKeyDirectory::USER_DEFINED, ModelType::Projected)
+            .is_err());
+        assert!(GeoTiffWriter::new()
+            .with_epsg_code(7844, ModelType::UserDefined)
+            .is_err());
+    }
+
+    #[test]
+    fn test_edit_georeferencing() {
+        let data = (0..40 * 20).map(|value| value as u16).collect::<Vec<_>>();
+        let transform = CoordinateTransform::from_tie_point_and_scale(
+            [0.0, 0.0],
+            [500000.0, 5300000.0],
+            [30.0, 30.0],
+        )
+        .unwrap();
+        for (writer, has_overviews) in [
+            (GeoTiffWriter::new(), false),
+            (
+                GeoTiffWriter::new()
+                    .with_big_tiff(true)
+                    .with_tiles(16, 16)
+                    .add_overviews(&[2], Resampling::Average),
+                true,
+            ),
+        ] {
+            let mut bytes = Vec::new();
+            writer
+                .with_epsg(32632)
+                .unwrap()
+                .with_coordinate_transform(transform.clone())
+                .write(&mut bytes, 40, 20, 1, &data)
+                .unwrap();
+            let length = bytes.len();
+
+            let mut file = Cursor::new(bytes);
+            let transform =
+                CoordinateTransform::from_affine([10.0, 0.0, 1000.0, 0.0, -10.0, 2000.0]).unwrap();
+            GeoTiffWriter::new()
+                .with_epsg(32633)
+                .unwrap()
+                .with_coordinate_transform(transform)
+                .edit_georeferencing(&mut file)
+                .unwrap();
+            let bytes = file.into_inner();
+            // The raster data is left as is
+            assert!(bytes.len() > length);
+
+            let geotiff = GeoTiff::read(Cursor::new(bytes.as_slice())).unwrap();
+            assert_eq!(geotiff.epsg_code(), Some(32633));
+            assert_eq!(geotiff.transform_to_model([1, 2]), Some([1010.0, 1980.0]));
+            assert_eq!(geotiff.get_value_at::<u16>(17, 3, 0), 137);
+
+            let mut decoder = Decoder::new(Cursor::new(bytes.as_slice())).unwrap();
+            assert!(decoder.find_tag(Tag::ModelPixelScaleTag).unwrap().is_none());
+            assert_eq!(decoder.more_images(), has_overviews);
+        }
+
+        let mut file = Cursor::new(b"MM\0\x2a\0\0\0\x08".to_vec());
+        assert!(GeoTiffWriter::new().edit_georeferencing(&mut file).is_err());
+    }
+
+    #[test]
+    fn test_append() {
+        let data = (0..40 * 20).map(|value| value as u16).collect::<Vec<_>>();
+        let mask = (0..40 * 20)
+            .map(|index| (index % 40 < 20) as u8)
+            .collect::<Vec<_>>();
+        let transform =
+            CoordinateTransform::from_geotransform([500000.0, 30.0, 0.0, 5300000.0, 0.0, -30.0])
+                .unwrap();
+        for writer in [
+            GeoTiffWriter::new(),
+            GeoTiffWriter::new().with_big_tiff(true).with_tiles(16, 16),
+        ] {
+            let mut bytes = Vec::new();
+            writer
+                .clone()
+                .with_epsg(32632)
+                .unwrap()
+                .write(&mut bytes, 40, 20, 1, &data)
+                .unwrap();
+            let original = bytes.clone();
+
+            let mut file = Cursor::new(bytes);
+            // Masks and overviews share the georeferencing of the full-resolution image
+            writer
+                .clone()
+                .with_epsg(32632)
+                .unwrap()
+                .with_coordinate_transform(transform.clone())
+                .with_mask(true)
+                .append(&mut file, 40, 20, 1, &mask)
+                .unwrap();
+            writer
+                .clone()
+                .with_epsg(32632)
+                .unwrap()
+                .with_coordinate_transform(transform.clone())
+                .with_reduced_resolution(true)
+                .append(&mut file, 20, 10, 1, &vec![7u16; 20 * 10])
+                .unwrap();
+            // Another band stored as a separate image
+            writer
+                .clone()
+                .with_compression(Compression::Lzw)
+                .with_epsg(32632)
+                .unwrap()
+                .append(&mut file, 40, 20, 1, &vec![3u16; 40 * 20])
+                .unwrap();
+            let bytes = file.into_inner();
+            // Only the offset of the next IFD of the last image is changed
+            let changed = original
+                .iter()
+                .zip(&bytes)
+                .filter(|(before, after)| before != after)
+                .count();
+            assert!((1..=8).contains(&changed));
+
+            let metadata = GeoTiff::metadata(Cursor::new(&bytes)).unwrap();
+            let georeferenced = metadata
+                .iter()
+                .map(|image| {
+                    [
+                        Tag::GeoKeyDirectoryTag,
+                        Tag::ModelPixelScaleTag,
+                        Tag::ModelTiepointTag,
+                    ]
+                    .map(|tag| image.get(tag.to_u16()).is_some())
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(
+                georeferenced,
+                [
+                    [true, false, false],
+                    [false; 3],
+                    [false; 3],
+                    [true, false, false]
+                ]
+            );
+
+            let mut reader = GeoTiffReader::new(Cursor::new(bytes)).unwrap();
+            assert_eq!(reader.image_count(), 2);
+            assert_eq!(reader.overviews().len(), 1);
+            assert!(reader.has_mask());
+            let geotiff = reader.read().unwrap();
+            assert_eq!(geotiff.get_values::<u16>(Layout::PixelInterleaved), data);
+            assert_eq!(
+                geotiff.mask().unwrap(),
+                mask.iter().map(|&valid| valid != 0).collect::<Vec<_>>()
+            );
+            reader.select_overview(Some(0)).unwrap();
+            assert_eq!(reader.read().unwrap().get_value_at::<u16>(5, 5, 0), 7);
+            reader.select_image(1).unwrap();
+            let geotiff = reader.read().unwrap();
+            assert_eq!(geotiff.epsg_code(), Some(32632));
+            assert_eq!(geotiff.get_value_at::<u16>(30, 10, 0), 3);
+        }
+
+        let mut file = Cursor::new(Vec::new());
+        GeoTiffWriter::new()
+            .write(&mut file, 40, 20, 1, &data)
+            .unwrap();
+        assert!(GeoTiffWriter::new()
+            .with_mask(true)
+            .append(&mut file, 40, 20, 1, &data)
+            .is_err());
+        let mut file = Cursor::new(b"MM\0\x2a\0\0\0\x08".to_vec());
+        assert!(matches!(
+            GeoTiffWriter::new().append(&mut file, 40, 20, 1, &data),
+            Err(GeoTiffError::Unsupported(_))
+        ));
+        assert_eq!(file.into_inner(), b"MM\0\x2a\0\0\0\x08");
+    }
+
+    #[test]
+    fn test_update_tags() {
+        let data = (0..40 * 20).map(|value| value as u16).collect::<Vec<_>>();
+        let mut bytes = Vec::new();
+        GeoTiffWriter::new()
+            .with_nodata(-9999.0)
+            .write(&mut bytes, 40, 20, 1, &data)
+            .unwrap();
+        let length = bytes.len();
+        let mut file = Cursor::new(bytes);
+
+        // The shorter nodata value replaces the previous one in place
+        GeoTiff::update_tags(
+            &mut file,
+            &[(Tag::GdalNodata, TagValue::Ascii("0".to_string()))],
+        )
+        .unwrap();
+        assert_eq!(file.get_ref().len(), length);
+        let geotiff = GeoTiff::read(Cursor::new(file.get_ref())).unwrap();
+        assert_eq!(geotiff.nodata::<u16>(), Some(0));
+
+        // New tags require relocating the IFD
+        GeoTiff::update_tags(
+            &mut file,
+            &[
+                (
+                    Tag::ImageDescription,
+                    TagValue::Ascii("Elevation".to_string()),
+                ),
+                (Tag::Unknown(65000), TagValue::Unsigned(vec![70000, 1])),
+                (Tag::Unknown(65001), TagValue::Float(vec![0.5])),
+            ],
+        )
+        .unwrap();
+        let length = file.get_ref().len();
+        GeoTiff::update_tags(
+            &mut file,
+            &[(Tag::ImageDescription, TagValue::Ascii("DEM".to_string()))],
+        )
+        .unwrap();
+        assert_eq!(file.get_ref().len(), length);
+
+        let metadata = GeoTiff::metadata(Cursor::new(file.get_ref())).unwrap();
+        let image = &metadata.images[0];
+        assert_eq!(
+            image.get(Tag::ImageDescription.to_u16()),
+            Some(&TagValue::Ascii("DEM".to_string()))
+        );
+        assert_eq!(image.get(65000), Some(&TagValue::Unsigned(vec![70000, 1])));
+        assert_eq!(image.get(65001), Some(&TagValue::Float(vec![0.5])));
+        let geotiff = GeoTiff::read(Cursor::new(file.get_ref())).unwrap();
+        assert_eq!(geotiff.nodata::<u16>(), Some(0));
+        assert_eq!(geotiff.get_values::<u16>(Layout::PixelInterleaved), data);
+
+        for value in [
+            TagValue::Ascii("a\0b".to_string()),
+            TagValue::Unsigned(Vec::new()),
+            TagValue::Unsigned(vec![u64::MAX]),
+        ] {
+            assert!(GeoTiff::update_tags(&mut file, &[(Tag::Unknown(65000), value)]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_write_big_tiff() {
+        let data = (0..40 * 20).map(|value| value as u16).collect::<Vec<_>>();
+        for writer in [
+            GeoTiffWriter::new(),
+            GeoTiffWriter::new().with_tiles(16, 16),
+        ] {
+            let mut bytes = Vec::new();
+            writer
+                .with_big_tiff(true)
+                .write(&mut bytes, 40, 20, 1, &data)
+                .unwrap();
+            assert_eq!(bytes[..8], [b'I', b'I', 43, 0, 8, 0, 0, 0]);
+
+            let geotiff = GeoTiff::read(Cursor::new(bytes)).unwrap();
+            assert_eq!(geotiff.raster_width, 40);
+            assert_eq!(geotiff.get_value_at::<u16>(17, 3, 0), 137);
+            assert_eq!(geotiff.get_value_at::<u16>(39, 19, 0), 799);
+        }
+
+        let mut bytes = Vec::new();
+        GeoTiffWriter::new()
+            .write(&mut bytes, 40, 20, 1, &data)
+            .unwrap();
+        assert_eq!(bytes[..4], [b'I', b'I', 42, 0]);
+    }
+
+    #[test]
+    fn test_write_planar() {
+        let data = (0..40 * 20 * 2)
+            .map(|value| value as u16)
+            .collect::<Vec<_>>();
+        for writer in [
+            GeoTiffWriter::new().with_strips(8),
+            GeoTiffWriter::new().with_tiles(16, 16),
+        ] {
+            let mut bytes = Vec::new();
+            writer
+                .with_planar(true)
+                .write(&mut bytes, 40, 20, 2, &data)
+                .unwrap();
+
+            let mut decoder = Decoder::new(Cursor::new(bytes.as_slice())).unwrap();
+            assert_eq!(
+                decoder.get_tag_u32(Tag::PlanarConfiguration).unwrap(),
+                PlanarConfiguration::Planar.to_u16() as u32
+            );
+            // The chunks of the first band are followed by those of the second band
+            let (offsets_tag, chunks_per_plane) = match decoder.get_chunk_type() {
+                tiff::decoder::ChunkType::Strip => (Tag::StripOffsets, 3),
+                tiff::decoder::ChunkType::Tile => (Tag::TileOffsets, 6),
+            };
+            let offsets = decoder.get_tag_u64_vec(offsets_tag).unwrap();
+            assert_eq!(offsets.len(), 2 * chunks_per_plane);
+            let offset = offsets[chunks_per_plane] as usize;
+            assert_eq!(bytes[offset..offset + 4], [1, 0, 3, 0]);
+
+            let geotiff = GeoTiff::read(Cursor::new(bytes)).unwrap();
+            assert_eq!(geotiff.get_value_at::<u16>(17, 3, 0), 274);
+            assert_eq!(geotiff.get_value_at::<u16>(39, 19, 1), 1599);
+        }
+    }
+
+    #[test]
+    fn test_write_overviews() {
+        let data = (0..40 * 20)
+            .map(|value| (value % 40) as u8)
+            .collect::<Vec<_>>();
+        let writer = GeoTiffWriter::new()
+            .with_tiles(16, 16)
+            .with_compression(Compression::Deflate { level: 6 })
+            .add_overviews(&[4, 2], Resampling::Average);
+        let mut bytes = Vec::new();
+        writer.write(&mut bytes, 40, 20, 1, &data).unwrap();
+
+        let mut decoder = Decoder::new(Cursor::new(bytes.as_slice())).unwrap();
+        assert_eq!(decoder.dimensions().unwrap(), (40, 20));
+        for (dimensions, value) in [((20, 10), 3), ((10, 5), 6)] {
+            decoder.next_image().unwrap();
+            assert_eq!(decoder.dimensions().unwrap(), dimensions);
+            assert_eq!(
+                decoder.get_tag_u32(Tag::NewSubfileType).unwrap(),
+                GeoTiffWriter::REDUCED_RESOLUTION
+            );
+            assert!(decoder.find_tag(Tag::GeoKeyDirectoryTag).unwrap().is_none());
+            match decoder.read_image().unwrap() {
+                DecodingResult::U8(overview) => assert_eq!(overview[1], value),
+                _ => panic!("Unexpected sample type"),
+            }
+        }
+        assert!(!decoder.more_images());
+
+        let geotiff = GeoTiff::read(Cursor::new(bytes)).unwrap();
+        assert_eq!(geotiff.raster_width, 40);
+        assert_eq!(geotiff.get_value_at::<u8>(17, 3, 0), 17);
+
+        assert!(GeoTiffWriter::new()
+            .add_overviews(&[1], Resampling::Nearest)
+            .write(&mut Vec::new(), 2, 2, 1, &[0u8; 4])
+            .is_err());
+    }
+
+    #[test]
+    fn test_invalid_data() {
+        let mut bytes = Vec::new();
+        assert!(GeoTiffWriter::new()
+            .write(&mut bytes, 2, 2, 1, &[0u8; 3])
+            .is_err());
+        assert!(GeoTiffWriter::new()
+            .write::<_, u8>(&mut bytes, 0, 0, 1, &[])
+            .is_err());
+    }
+}
@@ -0,0 +1,114 @@
+use std::io::Write;
+
+use super::{GeoTiffWriter, Layout};
+use crate::raster_data::Sample;
+use crate::GeoTiffResult;
+
+/// A writer for Cloud Optimized GeoTIFFs (COGs), see <https://docs.ogc.org/is/21-026/21-026.html>.
+///
+/// The raster is written with the options of the given [`GeoTiffWriter`], but always tiled and with
+/// overviews. All IFDs directly follow the header, so that readers can fetch the tile offsets of all
+/// resolutions with a single request, and the tiles are ordered from the smallest overview to the
+/// full-resolution image. Like the COG driver of GDAL, tiles default to 512x512 pixels and
+/// overviews to the factors 2, 4, 8, ... until an overview fits into a single tile.
+#[derive(Debug, Clone)]
+pub struct CogWriter {
+    writer: GeoTiffWriter,
+}
+
+impl CogWriter {
+    const TILE_SIZE: usize = 512;
+
+    pub fn new(mut writer: GeoTiffWriter) -> Self {
+        if let Layout::Strips { .. } = writer.layout {
+            writer = writer.with_tiles(Self::TILE_SIZE, Self::TILE_SIZE);
+        }
+        writer.cloud_optimized = true;
+        CogWriter { writer }
+    }
+
+    /// Writes a raster of `raster_width * raster_height` pixels with `num_samples` interleaved
+    /// samples each, stored row by row.
+    pub fn write<W: Write, T: Sample>(
+        &self,
+        writer: W,
+        raster_width: usize,
+        raster_height: usize,
+        num_samples: usize,
+        data: &[T],
+    ) -> GeoTiffResult<()> {
+        let mut geotiff_writer = self.writer.clone();
+        if let (
+            [],
+            Layout::Tiles {
+                tile_width,
+                tile_height,
+            },
+        ) = (geotiff_writer.overviews.as_slice(), geotiff_writer.layout)
+        {
+            let mut factor = 1;
+            while raster_width.div_ceil(factor) > tile_width
+                || raster_height.div_ceil(factor) > tile_height
+            {
+                factor *= 2;
+                geotiff_writer.overviews.push(factor);
+            }
+        }
+        geotiff_writer.write(writer, raster_width, raster_height, num_samples, data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use tiff::decoder::Decoder;
+    use tiff::tags::Tag;
+
+    use super::*;
+    use crate::{Compression, GeoTiff, Resampling};
+
+    #[test]
+    fn test_write_cog() {
+        let data = (0..100 * 40).map(|value| value as u16).collect::<Vec<_>>();
+        let writer = CogWriter::new(
+            GeoTiffWriter::new()
+                .with_tiles(16, 16)
+                .with_compression(Compression::Deflate { level: 6 }),
+        );
+        let mut bytes = Vec::new();
+        writer.write(&mut bytes, 100, 40, 1, &data).unwrap();
+        // The first IFD directly follows the header
+        assert_eq!(bytes[4..8], [8, 0, 0, 0]);
+
+        let mut decoder = Decoder::new(Cursor::new(bytes.as_slice())).unwrap();
+        let mut dimensions = Vec::new();
+        let mut tile_offsets = Vec::new();
+        loop {
+            dimensions.push(decoder.dimensions().unwrap());
+            tile_offsets.push(decoder.get_tag_u64_vec(Tag::TileOffsets).unwrap());
+            if !decoder.more_images() {
+                break;
+            }
+            decoder.next_image().unwrap();
+        }
+        assert_eq!(dimensions, [(100, 40), (50, 20), (25, 10), (13, 5)]);
+        // The tiles of the smallest overview come first, those of the full-resolution image last
+        for offsets in tile_offsets.windows(2) {
+            assert!(offsets[0].iter().min() > offsets[1].iter().max());
+        }
+        assert!(tile_offsets.iter().flatten().all(|offset| *offset > 8));
+
+        let geotiff = GeoTiff::read(Cursor::new(bytes)).unwrap();
+        assert_eq!(geotiff.get_value_at::<u16>(57, 31, 0), 3157);
+
+        let writer = CogWriter::new(GeoTiffWriter::new().add_overviews(&[2], Resampling::Nearest));
+        let mut bytes = Vec::new();
+        writer.write(&mut bytes, 20, 20, 1, &[0u8; 400]).unwrap();
+        let mut decoder = Decoder::new(Cursor::new(bytes.as_slice())).unwrap();
+        assert_eq!(decoder.get_tag_u32(Tag::TileWidth).unwrap(), 512);
+        decoder.next_image().unwrap();
+        assert_eq!(decoder.dimensions().unwrap(), (10, 10));
+        assert!(!decoder.more_images());
+    }
+}
@@ -0,0 +1,258 @@
+use std::io::Write;
+
+use tiff::tags::{CompressionMethod, Predictor, SampleFormat};
+
+use super::format_error;
+use crate::{GeoTiffError, GeoTiffResult};
+
+/// The compression of the raster data written by a [`GeoTiffWriter`](super::GeoTiffWriter).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum Compression {
+    #[default]
+    None,
+    /// Deflate with a level from 0 (no compression) to 9 (best compression), 6 being the default
+    /// of zlib and GDAL
+    Deflate {
+        level: u32,
+    },
+    Lzw,
+    /// Zstandard with a level from 1 (fastest) to 22 (best compression), 9 being the default of
+    /// GDAL
+    #[cfg(feature = "zstd")]
+    Zstd {
+        level: i32,
+    },
+    /// Lossless WebP, which GDAL reads and writes for rasters of 3 or 4 bands of `u8` samples
+    /// interleaved by pixel, i.e. RGB or RGBA imagery
+    #[cfg(feature = "webp")]
+    WebP,
+}
+
+impl Compression {
+    /// The compression code of the ZSTD extension, which is not part of
+    /// [`CompressionMethod`].
+    #[cfg(feature = "zstd")]
+    const ZSTD: u16 = 50000;
+    /// The compression code of the WebP extension, which is not part of [`CompressionMethod`].
+    #[cfg(feature = "webp")]
+    const WEBP: u16 = 50001;
+
+    /// The value of the Compression tag.
+    pub(super) fn to_u16(self) -> u16 {
+        match self {
+            Compression::None => CompressionMethod::None.to_u16(),
+            Compression::Deflate { .. } => CompressionMethod::Deflate.to_u16(),
+            Compression::Lzw => CompressionMethod::LZW.to_u16(),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd { .. } => Self::ZSTD,
+            #[cfg(feature = "webp")]
+            Compression::WebP => Self::WEBP,
+        }
+    }
+
+    pub(super) fn validate(self) -> GeoTiffResult<()> {
+        match self {
+            Compression::Deflate { level } if level > 9 => {
+                Err(GeoTiffError::InvalidArgument(format!(
+                    "Deflate compression level must be between 0 and 9, found {}",
+                    level
+                )))
+            }
+            #[cfg(feature = "zstd")]
+            Compression::Zstd { level } if !(1..=22).contains(&level) => {
+                Err(GeoTiffError::InvalidArgument(format!(
+                    "ZSTD compression level must be between 1 and 22, found {}",
+                    level
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub(super) fn compress(self, bytes: Vec<u8>) -> GeoTiffResult<Vec<u8>> {
+        match self {
+            Compression::None => Ok(bytes),
+            Compression::Deflate { level } => {
+                let mut encoder = flate2::write::ZlibEncoder::new(
+                    Vec::with_capacity(bytes.len() / 2),
+                    flate2::Compression::new(level),
+                );
+                encoder.write_all(&bytes)?;
+                Ok(encoder.finish()?)
+            }
+            Compression::Lzw => {
+                weezl::encode::Encoder::with_tiff_size_switch(weezl::BitOrder::Msb, 8)
+                    .encode(&bytes)
+                    .map_err(|err| format_error(format!("LZW compression failed: {}", err)))
+            }
+            #[cfg(feature = "zstd")]
+            Compression::Zstd { level } => Ok(zstd::bulk::compress(&bytes, level)?),
+            #[cfg(feature = "webp")]
+            Compression::WebP => unreachable!("WebP chunks are compressed by compress_webp"),
+        }
+    }
+}
+
+/// Compresses a strip or tile of `chunk_width` pixels of 3 or 4 `u8` samples each with lossless
+/// WebP.
+#[cfg(feature = "webp")]
+pub(super) fn compress_webp(
+    bytes: &[u8],
+    chunk_width: usize,
+    num_samples: usize,
+) -> GeoTiffResult<Vec<u8>> {
+    let color_type = match num_samples {
+        3 => image_webp::ColorType::Rgb8,
+        _ => image_webp::ColorType::Rgba8,
+    };
+    let height = bytes.len() / (chunk_width * num_samples);
+    let mut compressed = Vec::new();
+    image_webp::WebPEncoder::new(&mut compressed)
+        .encode(bytes, chunk_width as u32, height as u32, color_type)
+        .map_err(|err| format_error(format!("WebP compression failed: {}", err)))?;
+    Ok(compressed)
+}
+
+/// Checks whether the predictor is applicable to samples of the given format.
+pub(super) fn validate_predictor(
+    predictor: Predictor,
+    sample_format: SampleFormat,
+) -> GeoTiffResult<()> {
+    match (predictor, sample_format) {
+        (Predictor::Horizontal, SampleFormat::IEEEFP) => Err(GeoTiffError::InvalidArgument(
+            "The horizontal predictor is not applicable to floating point samples".to_string(),
+        )),
+        (Predictor::FloatingPoint, SampleFormat::Uint | SampleFormat::Int) => {
+            Err(GeoTiffError::InvalidArgument(
+                "The floating point predictor is only applicable to floating point samples"
+                    .to_string(),
+            ))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Applies the predictor to each row of a chunk of little-endian samples, as done before
+/// compression to improve the compression ratio.
+///
+/// The horizontal predictor replaces each sample with its difference to the respective sample of
+/// the previous pixel. The floating point predictor first arranges the bytes of a row by
+/// significance, then applies the horizontal predictor to the bytes.
+pub(super) fn apply_predictor(
+    predictor: Predictor,
+    chunk: &mut [u8],
+    row_size: usize,
+    sample_size: usize,
+    num_samples: usize,
+) {
+    match predictor {
+        Predictor::Horizontal => {
+            for row in chunk.chunks_exact_mut(row_size) {
+                horizontal_differencing(row, sample_size, num_samples);
+            }
+        }
+        Predictor::FloatingPoint => {
+            let mut shuffled = vec![0; row_size];
+            let row_length = row_size / sample_size;
+            for row in chunk.chunks_exact_mut(row_size) {
+                for (index, sample) in row.chunks_exact(sample_size).enumerate() {
+                    // The most significant bytes come first
+                    for (byte_index, byte) in sample.iter().rev().enumerate() {
+                        shuffled[byte_index * row_length + index] = *byte;
+                    }
+                }
+                row.copy_from_slice(&shuffled);
+                horizontal_differencing(row, 1, num_samples);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn horizontal_differencing(row: &mut [u8], sample_size: usize, num_samples: usize) {
+    let stride = sample_size * num_samples;
+    for end in (stride..row.len()).step_by(sample_size).rev() {
+        let (previous, current) = row.split_at_mut(end);
+        let previous = &previous[end - stride..end - stride + sample_size];
+        let current = &mut current[..sample_size];
+        // Subtract the little-endian integers byte by byte
+        let mut borrow = false;
+        for (current, previous) in current.iter_mut().zip(previous) {
+            let (difference, overflow1) = current.overflowing_sub(*previous);
+            let (difference, overflow2) = difference.overflowing_sub(borrow as u8);
+            *current = difference;
+            borrow = overflow1 || overflow2;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::*;
+
+    #[test]
+    fn test_horizontal_predictor() {
+        let samples = [1u16, 300, 2, 100, 5, 102];
+        let mut chunk = samples
+            .iter()
+            .flat_map(|sample| sample.to_le_bytes())
+            .collect::<Vec<_>>();
+        apply_predictor(Predictor::Horizontal, &mut chunk, 6, 2, 1);
+
+        let differences = chunk
+            .chunks_exact(2)
+            .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+            .collect::<Vec<_>>();
+        // Each row of three pixels is differenced separately
+        assert_eq!(differences, [1, 299, 65238, 100, 65441, 97]);
+    }
+
+    #[test]
+    fn test_floating_point_predictor() {
+        let mut chunk = [1.0f32, 2.0]
+            .iter()
+            .flat_map(|sample| sample.to_le_bytes())
+            .collect::<Vec<_>>();
+        apply_predictor(Predictor::FloatingPoint, &mut chunk, 8, 4, 1);
+
+        // 1.0 = 0x3f800000 and 2.0 = 0x40000000, arranged as 3f 40 80 00 00 00 00 00
+        assert_eq!(chunk, [0x3f, 0x01, 0x40, 0x80, 0x00, 0x00, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn test_compress() {
+        let bytes = (0..1000).map(|value| (value % 7) as u8).collect::<Vec<_>>();
+
+        let compressed = Compression::Deflate { level: 6 }
+            .compress(bytes.clone())
+            .unwrap();
+        assert!(compressed.len() < bytes.len());
+        let mut decompressed = Vec::new();
+        flate2::read::ZlibDecoder::new(compressed.as_slice())
+            .read_to_end(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, bytes);
+
+        let compressed = Compression::Lzw.compress(bytes.clone()).unwrap();
+        let decompressed = weezl::decode::Decoder::with_tiff_size_switch(weezl::BitOrder::Msb, 8)
+            .decode(&compressed)
+            .unwrap();
+        assert_eq!(decompressed, bytes);
+
+        assert!(Compression::Deflate { level: 10 }.validate().is_err());
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_compress_zstd() {
+        let bytes = (0..1000).map(|value| (value % 7) as u8).collect::<Vec<_>>();
+        let compressed = Compression::Zstd { level: 9 }
+            .compress(bytes.clone())
+            .unwrap();
+        assert_eq!(zstd::decode_all(compressed.as_slice()).unwrap(), bytes);
+        assert!(Compression::Zstd { level: 0 }.validate().is_err());
+    }
+}
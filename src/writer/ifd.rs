@@ -0,0 +1,521 @@
+use std::collections::BTreeMap;
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use tiff::tags::{Tag, Type};
+
+use super::format_error;
+use crate::{GeoTiffError, GeoTiffResult, TagValue};
+
+/// The value of a TIFF tag.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum IfdValue {
+    Short(Vec<u16>),
+    Long(Vec<u32>),
+    Long8(Vec<u64>),
+    Double(Vec<f64>),
+    Ascii(String),
+    /// A value read from an existing little-endian TIFF
+    Raw {
+        field_type: Type,
+        count: u64,
+        bytes: Vec<u8>,
+    },
+}
+
+/// The variant of the TIFF format, which determines the size of offsets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum TiffVariant {
+    /// Classic TIFF with 32-bit offsets, limiting the file size to 4 GiB
+    Classic,
+    /// BigTIFF with 64-bit offsets
+    Big,
+}
+
+/// An image file directory, i.e. the tags describing a single image, ordered by tag ID.
+#[derive(Debug, Clone, Default)]
+pub(super) struct Ifd {
+    entries: BTreeMap<u16, IfdValue>,
+}
+
+impl IfdValue {
+    fn field_type(&self) -> Type {
+        match self {
+            IfdValue::Short(_) => Type::SHORT,
+            IfdValue::Long(_) => Type::LONG,
+            IfdValue::Long8(_) => Type::LONG8,
+            IfdValue::Double(_) => Type::DOUBLE,
+            IfdValue::Ascii(_) => Type::ASCII,
+            IfdValue::Raw { field_type, .. } => *field_type,
+        }
+    }
+
+    fn count(&self) -> usize {
+        match self {
+            IfdValue::Short(values) => values.len(),
+            IfdValue::Long(values) => values.len(),
+            IfdValue::Long8(values) => values.len(),
+            IfdValue::Double(values) => values.len(),
+            // Including the terminating NUL
+            IfdValue::Ascii(value) => value.len() + 1,
+            IfdValue::Raw { count, .. } => *count as usize,
+        }
+    }
+
+    fn to_le_bytes(&self) -> Vec<u8> {
+        match self {
+            IfdValue::Short(values) => values.iter().flat_map(|v| v.to_le_bytes()).collect(),
+            IfdValue::Long(values) => values.iter().flat_map(|v| v.to_le_bytes()).collect(),
+            IfdValue::Long8(values) => values.iter().flat_map(|v| v.to_le_bytes()).collect(),
+            IfdValue::Double(values) => values.iter().flat_map(|v| v.to_le_bytes()).collect(),
+            IfdValue::Ascii(value) => value.bytes().chain([0]).collect(),
+            IfdValue::Raw { bytes, .. } => bytes.clone(),
+        }
+    }
+
+    /// Converts the value of a tag to the smallest type holding it, e.g. SHORT for unsigned
+    /// values of up to 16 bits. LONG8 and SLONG8 are only available in BigTIFFs.
+    pub(super) fn from_tag_value(value: &TagValue, variant: TiffVariant) -> GeoTiffResult<Self> {
+        fn raw<V, const N: usize>(
+            field_type: Type,
+            values: &[V],
+            to_le_bytes: impl Fn(&V) -> [u8; N],
+        ) -> IfdValue {
+            IfdValue::Raw {
+                field_type,
+                count: values.len() as u64,
+                bytes: values.iter().flat_map(to_le_bytes).collect(),
+            }
+        }
+
+        let big = variant == TiffVariant::Big;
+        let value = match value {
+            TagValue::Unsigned(values) => match values.iter().max().copied().unwrap_or(0) {
+                max if max <= u16::MAX as u64 => {
+                    raw(Type::SHORT, values, |v| (*v as u16).to_le_bytes())
+                }
+                max if max <= u32::MAX as u64 => {
+                    raw(Type::LONG, values, |v| (*v as u32).to_le_bytes())
+                }
+                _ if big => raw(Type::LONG8, values, |v| v.to_le_bytes()),
+                max => {
+                    return Err(GeoTiffError::InvalidArgument(format!(
+                        "The value {} exceeds 32 bits, which requires a BigTIFF",
+                        max
+                    )))
+                }
+            },
+            TagValue::Signed(values) => {
+                let fits = |min: i64, max: i64| values.iter().all(|v| (min..=max).contains(v));
+                if fits(i16::MIN as i64, i16::MAX as i64) {
+                    raw(Type::SSHORT, values, |v| (*v as i16).to_le_bytes())
+                } else if fits(i32::MIN as i64, i32::MAX as i64) {
+                    raw(Type::SLONG, values, |v| (*v as i32).to_le_bytes())
+                } else if big {
+                    raw(Type::SLONG8, values, |v| v.to_le_bytes())
+                } else {
+                    return Err(GeoTiffError::InvalidArgument(
+                        "Values beyond 32 bits require a BigTIFF".to_string(),
+                    ));
+                }
+            }
+            TagValue::Float(values) => raw(Type::DOUBLE, values, |v| v.to_le_bytes()),
+            TagValue::Rational(values) => raw(Type::RATIONAL, values, |[numerator, denominator]| {
+                let mut bytes = [0; 8];
+                bytes[..4].copy_from_slice(&numerator.to_le_bytes());
+                bytes[4..].copy_from_slice(&denominator.to_le_bytes());
+                bytes
+            }),
+            TagValue::SignedRational(values) => {
+                raw(Type::SRATIONAL, values, |[numerator, denominator]| {
+                    let mut bytes = [0; 8];
+                    bytes[..4].copy_from_slice(&numerator.to_le_bytes());
+                    bytes[4..].copy_from_slice(&denominator.to_le_bytes());
+                    bytes
+                })
+            }
+            TagValue::Ascii(value) => {
+                if value.contains('\0') {
+                    return Err(GeoTiffError::InvalidArgument(
+                        "ASCII values must not contain NUL characters".to_string(),
+                    ));
+                }
+                IfdValue::Ascii(value.clone())
+            }
+            TagValue::Undefined(bytes) => raw(Type::UNDEFINED, bytes, |v| [*v]),
+        };
+        if value.count() == 0 {
+            return Err(GeoTiffError::InvalidArgument(
+                "Tags must have at least one value".to_string(),
+            ));
+        }
+        Ok(value)
+    }
+
+    /// Offsets or byte counts, stored as LONG in classic TIFFs and as LONG8 in BigTIFFs.
+    ///
+    /// The values must fit into 32 bits for classic TIFFs.
+    pub(super) fn offsets(values: Vec<u64>, variant: TiffVariant) -> Self {
+        match variant {
+            TiffVariant::Classic => {
+                IfdValue::Long(values.into_iter().map(|value| value as u32).collect())
+            }
+            TiffVariant::Big => IfdValue::Long8(values),
+        }
+    }
+}
+
+impl TiffVariant {
+    pub(super) fn header_len(self) -> u64 {
+        match self {
+            TiffVariant::Classic => 8,
+            TiffVariant::Big => 16,
+        }
+    }
+
+    /// The position of the offset of the first IFD within the header.
+    pub(super) fn first_ifd_offset_position(self) -> u64 {
+        match self {
+            TiffVariant::Classic => 4,
+            TiffVariant::Big => 8,
+        }
+    }
+
+    /// Reads the header of a little-endian TIFF file, returning its variant and the offset of the
+    /// first IFD.
+    pub(super) fn read_header<R: Read>(reader: &mut R) -> GeoTiffResult<(Self, u64)> {
+        let mut header = [0; 8];
+        reader.read_exact(&mut header)?;
+        match &header[..2] {
+            b"II" => {}
+            b"MM" => {
+                return Err(GeoTiffError::Unsupported(
+                    "Big-endian TIFFs are not supported, only little-endian ones".to_string(),
+                ))
+            }
+            _ => return Err(format_error("Invalid TIFF byte order")),
+        }
+        match u16::from_le_bytes([header[2], header[3]]) {
+            42 => Ok((
+                TiffVariant::Classic,
+                u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as u64,
+            )),
+            43 => {
+                let mut offset = [0; 8];
+                reader.read_exact(&mut offset)?;
+                Ok((TiffVariant::Big, u64::from_le_bytes(offset)))
+            }
+            version => Err(format_error(format!("Invalid TIFF version {}", version))),
+        }
+    }
+
+    /// Encodes the header of a little-endian TIFF file.
+    pub(super) fn encode_header(self, first_ifd_offset: u64) -> Vec<u8> {
+        let mut header = b"II".to_vec();
+        match self {
+            TiffVariant::Classic => {
+                header.extend_from_slice(&42u16.to_le_bytes());
+                header.extend_from_slice(&(first_ifd_offset as u32).to_le_bytes());
+            }
+            TiffVariant::Big => {
+                header.extend_from_slice(&43u16.to_le_bytes());
+                // The size of offsets, followed by a reserved value
+                header.extend_from_slice(&8u16.to_le_bytes());
+                header.extend_from_slice(&0u16.to_le_bytes());
+                header.extend_from_slice(&first_ifd_offset.to_le_bytes());
+            }
+        }
+        header
+    }
+
+    /// The size of the number of entries, an entry, the value or offset within an entry and of the
+    /// offset of the next IFD.
+    fn sizes(self) -> (usize, usize, usize, usize) {
+        match self {
+            TiffVariant::Classic => (2, 12, 4, 4),
+            TiffVariant::Big => (8, 20, 8, 8),
+        }
+    }
+}
+
+impl Ifd {
+    /// Reads the IFD at `offset` of a little-endian TIFF file, returning it together with the
+    /// offset of the next IFD.
+    pub(super) fn read<R: Read + Seek>(
+        reader: &mut R,
+        offset: u64,
+        variant: TiffVariant,
+    ) -> GeoTiffResult<(Self, u64)> {
+        let (count_size, entry_size, inline_size, offset_size) = variant.sizes();
+        reader.seek(SeekFrom::Start(offset))?;
+        let mut num_entries = vec![0; count_size];
+        reader.read_exact(&mut num_entries)?;
+        let mut entries = vec![0; from_le_bytes(&num_entries) as usize * entry_size];
+        reader.read_exact(&mut entries)?;
+        let mut next_ifd_offset = vec![0; offset_size];
+        reader.read_exact(&mut next_ifd_offset)?;
+
+        let mut ifd = Ifd::default();
+        for entry in entries.chunks_exact(entry_size) {
+            let tag = u16::from_le_bytes([entry[0], entry[1]]);
+            let field_type = u16::from_le_bytes([entry[2], entry[3]]);
+            let value_field = &entry[entry_size - inline_size..];
+            let count = from_le_bytes(&entry[4..entry_size - inline_size]);
+            let length = Type::from_u16(field_type)
+                .and_then(type_size)
+                .and_then(|size| usize::try_from(count).ok()?.checked_mul(size))
+                .ok_or_else(|| {
+                    format_error(format!("Invalid type {} of tag {}", field_type, tag))
+                })?;
+            let bytes = if length <= inline_size {
+                value_field[..length].to_vec()
+            } else {
+                reader.seek(SeekFrom::Start(from_le_bytes(value_field)))?;
+                let mut bytes = vec![0; length];
+                reader.read_exact(&mut bytes)?;
+                bytes
+            };
+            ifd.entries.insert(
+                tag,
+                IfdValue::Raw {
+                    field_type: Type::from_u16(field_type).unwrap(),
+                    count,
+                    bytes,
+                },
+            );
+        }
+        Ok((ifd, from_le_bytes(&next_ifd_offset)))
+    }
+
+    /// Reads the offset of the IFD following the one at `offset`, returning the position of the
+    /// offset in the file together with the offset.
+    pub(super) fn read_next_ifd_offset<R: Read + Seek>(
+        reader: &mut R,
+        offset: u64,
+        variant: TiffVariant,
+    ) -> GeoTiffResult<(u64, u64)> {
+        let (count_size, entry_size, _, offset_size) = variant.sizes();
+        reader.seek(SeekFrom::Start(offset))?;
+        let mut num_entries = vec![0; count_size];
+        reader.read_exact(&mut num_entries)?;
+        let position =
+            offset + (count_size as u64) + from_le_bytes(&num_entries) * entry_size as u64;
+        reader.seek(SeekFrom::Start(position))?;
+        let mut next_ifd_offset = vec![0; offset_size];
+        reader.read_exact(&mut next_ifd_offset)?;
+        Ok((position, from_le_bytes(&next_ifd_offset)))
+    }
+
+    /// Replaces the values of tags of the IFD at `offset` in place, which requires each tag to
+    /// exist and its new value to fit into the space of the old one. Returns whether the values
+    /// were replaced, leaving the file untouched otherwise.
+    pub(super) fn update_in_place<F: Read + Write + Seek>(
+        file: &mut F,
+        offset: u64,
+        variant: TiffVariant,
+        values: &[(Tag, IfdValue)],
+    ) -> GeoTiffResult<bool> {
+        let (count_size, entry_size, inline_size, _) = variant.sizes();
+        file.seek(SeekFrom::Start(offset))?;
+        let mut num_entries = vec![0; count_size];
+        file.read_exact(&mut num_entries)?;
+        let mut entries = vec![0; from_le_bytes(&num_entries) as usize * entry_size];
+        file.read_exact(&mut entries)?;
+
+        // The position and the new bytes of each entry and of the values not fitting into it
+        let mut updates = Vec::with_capacity(values.len());
+        for (tag, value) in values {
+            let Some(index) = entries
+                .chunks_exact(entry_size)
+                .position(|entry| u16::from_le_bytes([entry[0], entry[1]]) == tag.to_u16())
+            else {
+                return Ok(false);
+            };
+            let entry = &entries[index * entry_size..(index + 1) * entry_size];
+            let value_field = &entry[entry_size - inline_size..];
+            let old_length = Type::from_u16(u16::from_le_bytes([entry[2], entry[3]]))
+                .and_then(type_size)
+                .and_then(|size| {
+                    usize::try_from(from_le_bytes(&entry[4..entry_size - inline_size]))
+                        .ok()?
+                        .checked_mul(size)
+                });
+
+            let mut bytes = value.to_le_bytes();
+            let mut new_entry = entry[..2].to_vec();
+            new_entry.extend_from_slice(&value.field_type().to_u16().to_le_bytes());
+            new_entry.extend_from_slice(
+                &(value.count() as u64).to_le_bytes()[..entry_size - inline_size - 4],
+            );
+            let values = if bytes.len() <= inline_size {
+                bytes.resize(inline_size, 0);
+                new_entry.extend_from_slice(&bytes);
+                None
+            } else {
+                match old_length {
+                    Some(old_length) if old_length > inline_size && bytes.len() <= old_length => {
+                        new_entry.extend_from_slice(value_field);
+                        Some((from_le_bytes(value_field), bytes))
+                    }
+                    _ => return Ok(false),
+                }
+            };
+            let position = offset + (count_size + index * entry_size) as u64;
+            updates.push((position, new_entry, values));
+        }
+
+        for (position, entry, values) in updates {
+            if let Some((values_offset, bytes)) = values {
+                file.seek(SeekFrom::Start(values_offset))?;
+                file.write_all(&bytes)?;
+            }
+            file.seek(SeekFrom::Start(position))?;
+            file.write_all(&entry)?;
+        }
+        Ok(true)
+    }
+
+    pub(super) fn get(&self, tag: Tag) -> Option<&IfdValue> {
+        self.entries.get(&tag.to_u16())
+    }
+
+    pub(super) fn insert(&mut self, tag: Tag, value: IfdValue) {
+        self.entries.insert(tag.to_u16(), value);
+    }
+
+    pub(super) fn remove(&mut self, tag: Tag) {
+        self.entries.remove(&tag.to_u16());
+    }
+
+    /// The length of the encoded IFD, including the values that do not fit into their entries.
+    pub(super) fn encoded_len(&self, variant: TiffVariant) -> u64 {
+        let (count_size, entry_size, inline_size, offset_size) = variant.sizes();
+        let values_length = self
+            .entries
+            .values()
+            .map(|value| value.to_le_bytes().len())
+            .filter(|length| *length > inline_size)
+            .map(|length| length + length % 2)
+            .sum::<usize>();
+        (count_size + self.entries.len() * entry_size + offset_size + values_length) as u64
+    }
+
+    /// Encodes the IFD to be written at `offset` of a little-endian TIFF file, followed by the
+    /// values that do not fit into their entries.
+    pub(super) fn encode(
+        &self,
+        offset: u64,
+        next_ifd_offset: u64,
+        variant: TiffVariant,
+    ) -> Vec<u8> {
+        let (count_size, entry_size, inline_size, offset_size) = variant.sizes();
+        let mut entries = Vec::with_capacity(self.encoded_len(variant) as usize);
+        let mut values = Vec::new();
+        let values_offset =
+            offset + (count_size + self.entries.len() * entry_size + offset_size) as u64;
+
+        let encode_offset = |value: u64| match variant {
+            TiffVariant::Classic => (value as u32).to_le_bytes().to_vec(),
+            TiffVariant::Big => value.to_le_bytes().to_vec(),
+        };
+        entries.extend_from_slice(&encode_offset(self.entries.len() as u64)[..count_size]);
+        for (tag, value) in &self.entries {
+            entries.extend_from_slice(&tag.to_le_bytes());
+            entries.extend_from_slice(&value.field_type().to_u16().to_le_bytes());
+            entries.extend_from_slice(&encode_offset(value.count() as u64));
+            let mut bytes = value.to_le_bytes();
+            if bytes.len() <= inline_size {
+                bytes.resize(inline_size, 0);
+                entries.extend_from_slice(&bytes);
+            } else {
+                // Values must begin on a word boundary
+                entries.extend_from_slice(&encode_offset(values_offset + values.len() as u64));
+                values.extend_from_slice(&bytes);
+                if values.len() % 2 != 0 {
+                    values.push(0);
+                }
+            }
+        }
+        entries.extend_from_slice(&encode_offset(next_ifd_offset));
+        entries.extend_from_slice(&values);
+        entries
+    }
+}
+
+/// Decodes a little-endian unsigned integer of up to 8 bytes.
+fn from_le_bytes(bytes: &[u8]) -> u64 {
+    let mut buffer = [0; 8];
+    buffer[..bytes.len()].copy_from_slice(bytes);
+    u64::from_le_bytes(buffer)
+}
+
+/// The size of a single value of the given type.
+fn type_size(field_type: Type) -> Option<usize> {
+    match field_type {
+        Type::BYTE | Type::ASCII | Type::SBYTE | Type::UNDEFINED => Some(1),
+        Type::SHORT | Type::SSHORT => Some(2),
+        Type::LONG | Type::SLONG | Type::FLOAT | Type::IFD => Some(4),
+        Type::RATIONAL
+        | Type::SRATIONAL
+        | Type::DOUBLE
+        | Type::LONG8
+        | Type::SLONG8
+        | Type::IFD8 => Some(8),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode() {
+        let mut ifd = Ifd::default();
+        ifd.insert(Tag::ImageWidth, IfdValue::Long(vec![3]));
+        ifd.insert(Tag::BitsPerSample, IfdValue::Short(vec![8, 8, 8]));
+        ifd.insert(Tag::Software, IfdValue::Ascii("abc".to_string()));
+
+        let encoded = ifd.encode(8, 0, TiffVariant::Classic);
+        assert_eq!(encoded.len() as u64, ifd.encoded_len(TiffVariant::Classic));
+        assert_eq!(
+            encoded,
+            [
+                vec![3, 0],
+                vec![0, 1, 4, 0, 1, 0, 0, 0, 3, 0, 0, 0],
+                vec![2, 1, 3, 0, 3, 0, 0, 0, 50, 0, 0, 0],
+                vec![49, 1, 2, 0, 4, 0, 0, 0, b'a', b'b', b'c', 0],
+                vec![0, 0, 0, 0],
+                vec![8, 0, 8, 0, 8, 0],
+            ]
+            .concat()
+        );
+    }
+
+    #[test]
+    fn test_encode_big_tiff() {
+        let mut ifd = Ifd::default();
+        ifd.insert(Tag::ImageWidth, IfdValue::Long(vec![3]));
+        ifd.insert(
+            Tag::StripOffsets,
+            IfdValue::offsets(vec![16, 1 << 32], TiffVariant::Big),
+        );
+
+        let encoded = ifd.encode(16, 0, TiffVariant::Big);
+        assert_eq!(encoded.len() as u64, ifd.encoded_len(TiffVariant::Big));
+        assert_eq!(
+            encoded,
+            [
+                vec![2, 0, 0, 0, 0, 0, 0, 0],
+                vec![0, 1, 4, 0, 1, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0],
+                vec![17, 1, 16, 0, 2, 0, 0, 0, 0, 0, 0, 0, 72, 0, 0, 0, 0, 0, 0, 0],
+                vec![0, 0, 0, 0, 0, 0, 0, 0],
+                vec![16, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0],
+            ]
+            .concat()
+        );
+        assert_eq!(
+            TiffVariant::Big.encode_header(64),
+            [b'I', b'I', 43, 0, 8, 0, 0, 0, 64, 0, 0, 0, 0, 0, 0, 0]
+        );
+    }
+}
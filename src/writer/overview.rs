@@ -0,0 +1,86 @@
+use tiff::tags::SampleFormat;
+
+use crate::raster_data::Sample;
+
+/// The resampling method used to compute overviews, corresponding to the `-r` option of
+/// `gdaladdo`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum Resampling {
+    /// The pixel nearest to the center of the reduced pixel
+    #[default]
+    Nearest,
+    /// The average of all pixels covered by the reduced pixel
+    Average,
+}
+
+/// Reduces the resolution of a raster by an integer factor, returning the reduced raster and its
+/// dimensions. Pixels at the right and bottom edges cover the remaining pixels if the dimensions
+/// are not divisible by the factor.
+pub(super) fn downsample<T: Sample>(
+    data: &[T],
+    [raster_width, raster_height]: [usize; 2],
+    num_samples: usize,
+    factor: usize,
+    resampling: Resampling,
+) -> (Vec<T>, [usize; 2]) {
+    let width = raster_width.div_ceil(factor);
+    let height = raster_height.div_ceil(factor);
+    let mut reduced = Vec::with_capacity(width * height * num_samples);
+    for y in 0..height {
+        for x in 0..width {
+            match resampling {
+                Resampling::Nearest => {
+                    let source_x = (x * factor + factor / 2).min(raster_width - 1);
+                    let source_y = (y * factor + factor / 2).min(raster_height - 1);
+                    let start = (source_y * raster_width + source_x) * num_samples;
+                    reduced.extend_from_slice(&data[start..start + num_samples]);
+                }
+                Resampling::Average => {
+                    let xs = x * factor..((x + 1) * factor).min(raster_width);
+                    let ys = y * factor..((y + 1) * factor).min(raster_height);
+                    let count = (xs.len() * ys.len()) as f64;
+                    for sample in 0..num_samples {
+                        let sum = ys
+                            .clone()
+                            .flat_map(|source_y| {
+                                xs.clone()
+                                    .map(move |source_x| source_y * raster_width + source_x)
+                            })
+                            .map(|index| data[index * num_samples + sample].to_f64().unwrap())
+                            .sum::<f64>();
+                        let average = match T::SAMPLE_FORMAT {
+                            SampleFormat::IEEEFP => sum / count,
+                            _ => (sum / count).round(),
+                        };
+                        reduced.push(T::from_f64(average).unwrap());
+                    }
+                }
+            }
+        }
+    }
+    (reduced, [width, height])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_downsample() {
+        let data = (0..5 * 3).map(|value| value as u8).collect::<Vec<_>>();
+
+        let (reduced, dimensions) = downsample(&data, [5, 3], 1, 2, Resampling::Nearest);
+        assert_eq!(dimensions, [3, 2]);
+        assert_eq!(reduced, [6, 8, 9, 11, 13, 14]);
+
+        // The averages of 0 1 5 6, 2 3 7 8, 4 9, 10 11, 12 13 and 14
+        let (reduced, _) = downsample(&data, [5, 3], 1, 2, Resampling::Average);
+        assert_eq!(reduced, [3, 5, 7, 11, 13, 14]);
+
+        let data = [1.0f32, 10.0, 2.0, 20.0, 4.0, 40.0, 8.0, 80.0];
+        let (reduced, dimensions) = downsample(&data, [2, 2], 2, 2, Resampling::Average);
+        assert_eq!(dimensions, [1, 1]);
+        assert_eq!(reduced, [3.75, 37.5]);
+    }
+}
@@ -0,0 +1,296 @@
+use std::io::Write;
+
+use tiff::tags::Tag;
+
+use super::ifd::{Ifd, IfdValue, TiffVariant};
+use super::{encode_samples, to_u32, Compression, EncodedImage, GeoTiffWriter, Layout};
+use crate::raster_data::Sample;
+use crate::{GeoTiffError, GeoTiffResult};
+
+/// A writer streaming a raster row by row into a sink that cannot seek, e.g. an HTTP response
+/// or a compressor, see [`GeoTiffWriter::stream`].
+///
+/// The raster is stored in strips followed by the IFD. Without compression, the size of the
+/// strips is known in advance, so that the header is written immediately and each strip as soon
+/// as its rows are complete. Compressed or sparse strips are buffered until [`finish`] is called,
+/// as the offset of the IFD in the header depends on their size.
+///
+/// [`finish`]: StreamingWriter::finish
+#[derive(Debug)]
+pub struct StreamingWriter<W: Write, T: Sample> {
+    writer: GeoTiffWriter,
+    sink: W,
+    ifd: Ifd,
+    variant: TiffVariant,
+    raster_width: usize,
+    raster_height: usize,
+    num_samples: usize,
+    rows_per_strip: usize,
+    /// The samples of the rows of the strip being filled
+    rows: Vec<T>,
+    rows_written: usize,
+    /// The encoded strips buffered until the raster is complete
+    strips: Vec<Vec<u8>>,
+    /// The byte counts of the strips already written
+    byte_counts: Vec<u64>,
+    /// Whether the strips are written as soon as they are complete
+    direct: bool,
+}
+
+impl GeoTiffWriter {
+    /// Creates a writer of a raster of `raster_width * raster_height` pixels with `num_samples`
+    /// interleaved samples each, whose rows are passed one or more at a time, see
+    /// [`StreamingWriter`].
+    ///
+    /// Fails if the writer is configured for tiles, planar bands or overviews, which require the
+    /// whole raster before the first strip or tile can be written.
+    pub fn stream<W: Write, T: Sample>(
+        &self,
+        mut sink: W,
+        raster_width: usize,
+        raster_height: usize,
+        num_samples: usize,
+    ) -> GeoTiffResult<StreamingWriter<W, T>> {
+        if let Layout::Tiles { .. } = self.layout {
+            return Err(GeoTiffError::Unsupported(
+                "Streaming tiles is not supported".to_string(),
+            ));
+        }
+        if self.planar && num_samples > 1 || !self.overviews.is_empty() || self.cloud_optimized {
+            return Err(GeoTiffError::Unsupported(
+                "Streaming planar bands or overviews is not supported".to_string(),
+            ));
+        }
+        if raster_width == 0 || raster_height == 0 || num_samples == 0 {
+            return Err(GeoTiffError::InvalidArgument(
+                "The raster must not be empty".to_string(),
+            ));
+        }
+
+        let mut ifd = self.image_ifd::<T>(raster_width, raster_height, num_samples, false)?;
+        if self.subfile_type == 0 {
+            self.insert_geo_tags(&mut ifd)?;
+        }
+        let rows_per_strip = self.rows_per_strip::<T>(raster_width * num_samples, raster_height)?;
+        ifd.insert(
+            Tag::RowsPerStrip,
+            IfdValue::Long(vec![to_u32(rows_per_strip)?]),
+        );
+
+        let direct = self.compression == Compression::None && !self.sparse;
+        let strip_count = raster_height.div_ceil(rows_per_strip);
+        let data_len =
+            (raster_width * raster_height * num_samples) as u64 * T::BITS_PER_SAMPLE as u64 / 8;
+        // Only used if the strips are uncompressed, whose size is known in advance
+        let variant = self.single_image_variant(
+            &ifd,
+            [Tag::StripOffsets, Tag::StripByteCounts],
+            strip_count,
+            data_len,
+        );
+        if direct {
+            let ifd_offset = variant.header_len() + data_len + data_len % 2;
+            if variant == TiffVariant::Classic && ifd_offset > u32::MAX as u64 {
+                return Err(GeoTiffError::Unsupported(
+                    "The GeoTIFF exceeds the size limit of 4 GiB, consider writing a BigTIFF"
+                        .to_string(),
+                ));
+            }
+            sink.write_all(&variant.encode_header(ifd_offset))?;
+        }
+
+        Ok(StreamingWriter {
+            writer: self.clone(),
+            sink,
+            ifd,
+            variant,
+            raster_width,
+            raster_height,
+            num_samples,
+            rows_per_strip,
+            rows: Vec::with_capacity(raster_width * num_samples * rows_per_strip),
+            rows_written: 0,
+            strips: Vec::with_capacity(strip_count),
+            byte_counts: Vec::with_capacity(strip_count),
+            direct,
+        })
+    }
+}
+
+impl<W: Write, T: Sample> StreamingWriter<W, T> {
+    /// Writes one or more complete rows of interleaved samples, writing or buffering the strips
+    /// they complete.
+    pub fn write_rows(&mut self, rows: &[T]) -> GeoTiffResult<()> {
+        let row_samples = self.raster_width * self.num_samples;
+        if !rows.len().is_multiple_of(row_samples) {
+            return Err(GeoTiffError::InvalidArgument(format!(
+                "Expected rows of {} samples, found {} samples",
+                row_samples,
+                rows.len()
+            )));
+        }
+        if self.rows_written + rows.len() / row_samples > self.raster_height {
+            return Err(GeoTiffError::InvalidArgument(format!(
+                "The raster has only {} rows",
+                self.raster_height
+            )));
+        }
+        for row in rows.chunks(row_samples) {
+            self.rows.extend_from_slice(row);
+            self.rows_written += 1;
+            if self.rows.len() == row_samples * self.rows_per_strip
+                || self.rows_written == self.raster_height
+            {
+                self.write_strip()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the buffered strips and the IFD after all rows have been written, returning the
+    /// sink.
+    pub fn finish(mut self) -> GeoTiffResult<W> {
+        if self.rows_written != self.raster_height {
+            return Err(GeoTiffError::InvalidArgument(format!(
+                "Only {} of {} rows have been written",
+                self.rows_written, self.raster_height
+            )));
+        }
+        let mut image = EncodedImage {
+            ifd: self.ifd,
+            chunks: self.strips,
+            offsets_tag: Tag::StripOffsets,
+            byte_counts_tag: Tag::StripByteCounts,
+        };
+        if !self.direct {
+            self.writer.write_images(&mut self.sink, vec![image])?;
+            return Ok(self.sink);
+        }
+
+        let mut offset = self.variant.header_len();
+        let mut offsets = Vec::with_capacity(self.byte_counts.len());
+        for byte_count in &self.byte_counts {
+            offsets.push(offset);
+            offset += byte_count;
+        }
+        // The IFD must begin on a word boundary
+        if !offset.is_multiple_of(2) {
+            self.sink.write_all(&[0])?;
+            offset += 1;
+        }
+        image
+            .ifd
+            .insert(Tag::StripOffsets, IfdValue::offsets(offsets, self.variant));
+        image.ifd.insert(
+            Tag::StripByteCounts,
+            IfdValue::offsets(self.byte_counts, self.variant),
+        );
+        self.sink
+            .write_all(&image.ifd.encode(offset, 0, self.variant))?;
+        self.sink.flush()?;
+        Ok(self.sink)
+    }
+
+    fn write_strip(&mut self) -> GeoTiffResult<()> {
+        let sparse =
+            self.writer.sparse && self.rows.iter().all(|sample| self.writer.is_fill(sample));
+        let strip = match sparse {
+            // Sparse strips are not stored at all
+            true => Vec::new(),
+            false => self.writer.encode_chunk::<T>(
+                encode_samples(&self.rows),
+                self.raster_width * self.num_samples,
+                self.num_samples,
+            )?,
+        };
+        self.rows.clear();
+        if self.direct {
+            self.sink.write_all(&strip)?;
+            self.byte_counts.push(strip.len() as u64);
+        } else {
+            self.strips.push(strip);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{CoordinateTransform, GeoTiff, Layout as RasterLayout};
+
+    /// A sink that cannot seek.
+    struct Pipe(Vec<u8>);
+
+    impl Write for Pipe {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_stream() {
+        let data = (0..40 * 20 * 2)
+            .map(|value| value as u16)
+            .collect::<Vec<_>>();
+        let transform =
+            CoordinateTransform::from_geotransform([1000.0, 10.0, 0.0, 2000.0, 0.0, -10.0])
+                .unwrap();
+        for writer in [
+            GeoTiffWriter::new().with_strips(3),
+            GeoTiffWriter::new().with_compression(Compression::Lzw),
+            GeoTiffWriter::new().with_big_tiff(true),
+        ] {
+            let writer = writer
+                .with_epsg(32632)
+                .unwrap()
+                .with_coordinate_transform(transform.clone());
+            let mut stream = writer.stream(Pipe(Vec::new()), 40, 20, 2).unwrap();
+            stream.write_rows(&data[..40 * 2]).unwrap();
+            // The first strip of 3 rows is written as soon as it is complete
+            let header_len = if stream.variant == TiffVariant::Big {
+                16
+            } else {
+                8
+            };
+            if stream.direct {
+                assert_eq!(stream.sink.0.len(), header_len);
+            }
+            stream.write_rows(&data[40 * 2..40 * 2 * 7]).unwrap();
+            if stream.direct && stream.rows_per_strip == 3 {
+                assert_eq!(stream.sink.0.len(), header_len + 6 * 40 * 2 * 2);
+            }
+            assert!(stream.write_rows(&data[..3]).is_err());
+            stream.write_rows(&data[40 * 2 * 7..]).unwrap();
+            assert!(stream.write_rows(&data[..40 * 2]).is_err());
+            let bytes = stream.finish().unwrap().0;
+
+            let mut buffered = Vec::new();
+            writer.write(&mut buffered, 40, 20, 2, &data).unwrap();
+            assert_eq!(bytes, buffered);
+            let geotiff = GeoTiff::read(Cursor::new(bytes)).unwrap();
+            assert_eq!(
+                geotiff.get_values::<u16>(RasterLayout::PixelInterleaved),
+                data
+            );
+            assert_eq!(geotiff.epsg_code(), Some(32632));
+        }
+
+        // Incomplete rasters are not finished
+        let mut stream = GeoTiffWriter::new()
+            .stream(Pipe(Vec::new()), 40, 20, 1)
+            .unwrap();
+        stream.write_rows(&data[..40]).unwrap();
+        assert!(stream.finish().is_err());
+        assert!(GeoTiffWriter::new()
+            .with_tiles(16, 16)
+            .stream::<_, u16>(Pipe(Vec::new()), 40, 20, 1)
+            .is_err());
+    }
+}
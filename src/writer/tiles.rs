@@ -0,0 +1,345 @@
+use std::io::{Seek, SeekFrom, Write};
+use std::marker::PhantomData;
+
+use tiff::tags::Tag;
+
+use super::ifd::{Ifd, IfdValue, TiffVariant};
+use super::{encode_samples, insert_tile_dimensions, sparse_tiles, GeoTiffWriter, Layout};
+use crate::raster_data::Sample;
+use crate::{GeoTiffError, GeoTiffResult};
+
+/// A writer receiving a tiled raster tile by tile in any order, e.g. from a computation
+/// producing one tile at a time, see [`GeoTiffWriter::tile_writer`].
+///
+/// Each tile is encoded and written as soon as it is passed, so that only a single tile is held
+/// in memory. The IFD follows the tiles and is written by [`finish`].
+///
+/// [`finish`]: TileWriter::finish
+#[derive(Debug)]
+pub struct TileWriter<W: Write + Seek, T: Sample> {
+    writer: GeoTiffWriter,
+    sink: W,
+    ifd: Ifd,
+    variant: TiffVariant,
+    raster_width: usize,
+    raster_height: usize,
+    tile_width: usize,
+    tile_height: usize,
+    /// The number of samples per pixel of a tile, which is 1 for planar bands
+    tile_samples: usize,
+    /// The offsets and byte counts of the tiles of all bands, or `None` if not yet written
+    tiles: Vec<Option<[u64; 2]>>,
+    /// The offset at which the next tile is written
+    offset: u64,
+    samples: PhantomData<T>,
+}
+
+impl GeoTiffWriter {
+    /// Creates a writer of a raster of `raster_width * raster_height` pixels with `num_samples`
+    /// samples each, whose tiles are passed one at a time, see [`TileWriter`]. The sink is written
+    /// from its start.
+    ///
+    /// Fails unless the writer is configured for tiles, see [`GeoTiffWriter::with_tiles`], or if
+    /// it is configured for overviews, which require the whole raster.
+    pub fn tile_writer<W: Write + Seek, T: Sample>(
+        &self,
+        mut sink: W,
+        raster_width: usize,
+        raster_height: usize,
+        num_samples: usize,
+    ) -> GeoTiffResult<TileWriter<W, T>> {
+        let Layout::Tiles {
+            tile_width,
+            tile_height,
+        } = self.layout
+        else {
+            return Err(GeoTiffError::InvalidArgument(
+                "Writing tile by tile requires tiles".to_string(),
+            ));
+        };
+        if !self.overviews.is_empty() || self.cloud_optimized {
+            return Err(GeoTiffError::Unsupported(
+                "Writing overviews tile by tile is not supported".to_string(),
+            ));
+        }
+        if raster_width == 0 || raster_height == 0 || num_samples == 0 {
+            return Err(GeoTiffError::InvalidArgument(
+                "The raster must not be empty".to_string(),
+            ));
+        }
+
+        let planar = self.planar && num_samples > 1;
+        let mut ifd = self.image_ifd::<T>(raster_width, raster_height, num_samples, planar)?;
+        if self.subfile_type == 0 {
+            self.insert_geo_tags(&mut ifd)?;
+        }
+        insert_tile_dimensions(&mut ifd, [tile_width, tile_height])?;
+
+        let tile_samples = if planar { 1 } else { num_samples };
+        let tile_count = raster_width.div_ceil(tile_width)
+            * raster_height.div_ceil(tile_height)
+            * (num_samples / tile_samples);
+        // The compressed size is not known yet, but rarely exceeds the uncompressed size
+        let data_len = (tile_count * tile_width * tile_height * tile_samples) as u64
+            * T::BITS_PER_SAMPLE as u64
+            / 8;
+        let variant = self.single_image_variant(
+            &ifd,
+            [Tag::TileOffsets, Tag::TileByteCounts],
+            tile_count,
+            data_len,
+        );
+        // The offset of the IFD is written by `finish`
+        sink.seek(SeekFrom::Start(0))?;
+        sink.write_all(&variant.encode_header(0))?;
+
+        Ok(TileWriter {
+            writer: self.clone(),
+            sink,
+            ifd,
+            variant,
+            raster_width,
+            raster_height,
+            tile_width,
+            tile_height,
+            tile_samples,
+            tiles: vec![None; tile_count],
+            offset: variant.header_len(),
+            samples: PhantomData,
+        })
+    }
+}
+
+impl<W: Write + Seek, T: Sample> TileWriter<W, T> {
+    /// Encodes and writes the tile in column `tile_x` and row `tile_y` of tiles, of the given band
+    /// if the bands are planar and of band 0 holding all bands otherwise.
+    ///
+    /// The tile consists of `tile_width * tile_height` pixels row by row, of which those beyond
+    /// the raster at the right and bottom edges are padding. Each tile is written once.
+    pub fn write_tile(
+        &mut self,
+        tile_x: usize,
+        tile_y: usize,
+        band: usize,
+        data: &[T],
+    ) -> GeoTiffResult<()> {
+        let tiles_across = self.raster_width.div_ceil(self.tile_width);
+        let tiles_down = self.raster_height.div_ceil(self.tile_height);
+        let bands = self.tiles.len() / (tiles_across * tiles_down);
+        if tile_x >= tiles_across || tile_y >= tiles_down || band >= bands {
+            return Err(GeoTiffError::OutOfBounds(format!(
+                "Tile ({}, {}) of band {} exceeds the {}x{} tiles of {} bands",
+                tile_x, tile_y, band, tiles_across, tiles_down, bands
+            )));
+        }
+        let tile_len = self.tile_width * self.tile_height * self.tile_samples;
+        if data.len() != tile_len {
+            return Err(GeoTiffError::InvalidArgument(format!(
+                "Expected {} samples for a tile, found {}",
+                tile_len,
+                data.len()
+            )));
+        }
+        let index = (band * tiles_down + tile_y) * tiles_across + tile_x;
+        if self.tiles[index].is_some() {
+            return Err(GeoTiffError::InvalidArgument(format!(
+                "Tile ({}, {}) of band {} has already been written",
+                tile_x, tile_y, band
+            )));
+        }
+
+        // The part of the tile within the raster, disregarding the padding
+        let width = self
+            .tile_width
+            .min(self.raster_width - tile_x * self.tile_width);
+        let height = self
+            .tile_height
+            .min(self.raster_height - tile_y * self.tile_height);
+        let sparse = self.writer.sparse
+            && sparse_tiles(
+                data,
+                [self.tile_width, self.tile_height],
+                self.tile_samples,
+                [width, height],
+                |sample| self.writer.is_fill(sample),
+            )[0];
+        if sparse {
+            // Sparse tiles are not stored at all
+            self.tiles[index] = Some([0, 0]);
+            return Ok(());
+        }
+
+        let tile = self.writer.encode_chunk::<T>(
+            encode_samples(data),
+            self.tile_width * self.tile_samples,
+            self.tile_samples,
+        )?;
+        if self.variant == TiffVariant::Classic && self.offset + tile.len() as u64 > u32::MAX as u64
+        {
+            return Err(GeoTiffError::Unsupported(
+                "The GeoTIFF exceeds the size limit of 4 GiB, consider writing a BigTIFF"
+                    .to_string(),
+            ));
+        }
+        self.sink.seek(SeekFrom::Start(self.offset))?;
+        self.sink.write_all(&tile)?;
+        self.tiles[index] = Some([self.offset, tile.len() as u64]);
+        self.offset += tile.len() as u64;
+        Ok(())
+    }
+
+    /// Writes the IFD after all tiles have been written, returning the sink.
+    ///
+    /// Tiles that have not been written are omitted if the writer writes sparse tiles, see
+    /// [`GeoTiffWriter::with_sparse`], and fail otherwise.
+    pub fn finish(mut self) -> GeoTiffResult<W> {
+        let mut offsets = Vec::with_capacity(self.tiles.len());
+        let mut byte_counts = Vec::with_capacity(self.tiles.len());
+        for (index, tile) in self.tiles.iter().enumerate() {
+            let [offset, byte_count] = match tile {
+                Some(tile) => *tile,
+                None if self.writer.sparse => [0, 0],
+                None => {
+                    return Err(GeoTiffError::InvalidArgument(format!(
+                        "Tile {} has not been written",
+                        index
+                    )))
+                }
+            };
+            offsets.push(offset);
+            byte_counts.push(byte_count);
+        }
+        self.ifd
+            .insert(Tag::TileOffsets, IfdValue::offsets(offsets, self.variant));
+        self.ifd.insert(
+            Tag::TileByteCounts,
+            IfdValue::offsets(byte_counts, self.variant),
+        );
+
+        self.sink.seek(SeekFrom::Start(self.offset))?;
+        // The IFD must begin on a word boundary
+        let mut ifd_offset = self.offset;
+        if !ifd_offset.is_multiple_of(2) {
+            self.sink.write_all(&[0])?;
+            ifd_offset += 1;
+        }
+        if self.variant == TiffVariant::Classic
+            && ifd_offset + self.ifd.encoded_len(self.variant) > u32::MAX as u64
+        {
+            return Err(GeoTiffError::Unsupported(
+                "The GeoTIFF exceeds the size limit of 4 GiB, consider writing a BigTIFF"
+                    .to_string(),
+            ));
+        }
+        self.sink
+            .write_all(&self.ifd.encode(ifd_offset, 0, self.variant))?;
+        self.sink.seek(SeekFrom::Start(0))?;
+        self.sink
+            .write_all(&self.variant.encode_header(ifd_offset))?;
+        self.sink.flush()?;
+        Ok(self.sink)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{Compression, GeoTiff, Layout as RasterLayout};
+
+    /// The tile of a raster whose samples are their index, padded with 0.
+    fn tile(
+        raster: [usize; 2],
+        num_samples: usize,
+        [tile_x, tile_y]: [usize; 2],
+        band: Option<usize>,
+    ) -> Vec<u16> {
+        let mut tile = Vec::new();
+        for y in tile_y * 16..tile_y * 16 + 16 {
+            for x in tile_x * 16..tile_x * 16 + 16 {
+                for sample in 0..num_samples {
+                    if band.is_some_and(|band| band != sample) {
+                        continue;
+                    }
+                    tile.push(match x < raster[0] && y < raster[1] {
+                        true => ((y * raster[0] + x) * num_samples + sample) as u16,
+                        false => 0,
+                    });
+                }
+            }
+        }
+        tile
+    }
+
+    #[test]
+    fn test_tile_writer() {
+        let data = (0..40 * 20 * 2)
+            .map(|value| value as u16)
+            .collect::<Vec<_>>();
+        for (writer, planar) in [
+            (GeoTiffWriter::new(), false),
+            (
+                GeoTiffWriter::new().with_compression(Compression::Lzw),
+                false,
+            ),
+            (GeoTiffWriter::new().with_planar(true), true),
+            (GeoTiffWriter::new().with_big_tiff(true), false),
+        ] {
+            let writer = writer.with_tiles(16, 16).with_epsg(32632).unwrap();
+            let mut tiles = writer
+                .tile_writer(Cursor::new(Vec::new()), 40, 20, 2)
+                .unwrap();
+            // In reverse order
+            for band in (0..if planar { 2 } else { 1 }).rev() {
+                for tile_y in (0..2).rev() {
+                    for tile_x in (0..3).rev() {
+                        let band_index = planar.then_some(band);
+                        let data = tile([40, 20], 2, [tile_x, tile_y], band_index);
+                        tiles.write_tile(tile_x, tile_y, band, &data).unwrap();
+                    }
+                }
+            }
+            let tile_len = if planar { 256 } else { 512 };
+            assert!(tiles.write_tile(0, 0, 0, &vec![0; tile_len]).is_err());
+            assert!(tiles.write_tile(3, 0, 0, &vec![0; tile_len]).is_err());
+            assert!(tiles.write_tile(0, 0, 2, &vec![0; tile_len]).is_err());
+            let bytes = tiles.finish().unwrap().into_inner();
+
+            let geotiff = GeoTiff::read(Cursor::new(bytes)).unwrap();
+            assert_eq!(
+                geotiff.get_values::<u16>(RasterLayout::PixelInterleaved),
+                data
+            );
+            assert_eq!(geotiff.epsg_code(), Some(32632));
+        }
+
+        // Sparse tiles may be omitted
+        let writer = GeoTiffWriter::new()
+            .with_tiles(16, 16)
+            .with_sparse(true)
+            .with_nodata(7.0);
+        let mut tiles = writer
+            .tile_writer(Cursor::new(Vec::new()), 40, 20, 1)
+            .unwrap();
+        tiles
+            .write_tile(1, 0, 0, &tile([40, 20], 1, [1, 0], None))
+            .unwrap();
+        tiles.write_tile(2, 1, 0, &[7; 256]).unwrap();
+        let geotiff = GeoTiff::read(Cursor::new(tiles.finish().unwrap().into_inner())).unwrap();
+        assert_eq!(geotiff.get_value_at::<u16>(17, 3, 0), 137);
+        assert_eq!(geotiff.get_value_at::<u16>(3, 3, 0), 7);
+        assert_eq!(geotiff.get_value_at::<u16>(39, 19, 0), 7);
+
+        let mut tiles = GeoTiffWriter::new()
+            .with_tiles(16, 16)
+            .tile_writer(Cursor::new(Vec::new()), 40, 20, 1)
+            .unwrap();
+        assert!(tiles.write_tile(0, 0, 0, &[0; 100]).is_err());
+        tiles.write_tile(0, 0, 0, &[0; 256]).unwrap();
+        assert!(tiles.finish().is_err());
+        assert!(GeoTiffWriter::new()
+            .tile_writer::<_, u16>(Cursor::new(Vec::new()), 40, 20, 1)
+            .is_err());
+    }
+}
@@ -0,0 +1,254 @@
+use std::io::{Read, Seek};
+
+use geo_types::MultiPolygon;
+
+use crate::geo::{bounding_rect, RasterPolygon};
+use crate::statistics::StatisticsAccumulator;
+use crate::{GeoTiffError, GeoTiffReader, GeoTiffResult, Statistics, WindowSnap};
+
+/// The fraction of a pixel covered by a polygon below which the pixel is considered outside, to
+/// ignore rounding errors at edges aligned with the pixel grid
+const MIN_COVERAGE: f64 = 1e-9;
+
+/// Which pixels count towards the statistics of a polygon, see
+/// [`GeoTiffReader::zonal_statistics`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ZonalCoverage {
+    /// The pixels whose center lies within the polygon, like rasterstats does by default
+    #[default]
+    Center,
+    /// All pixels intersecting the interior of the polygon, like rasterstats does with
+    /// `all_touched=True`
+    AllTouched,
+    /// All pixels intersecting the interior of the polygon, each weighted by the fraction of it
+    /// covered by the polygon in the mean and the standard deviation, like exactextract does
+    Fraction,
+}
+
+impl<R: Read + Seek> GeoTiffReader<R> {
+    /// Computes the statistics of the valid samples of the band within each of the polygons in
+    /// model space, e.g. the mean elevation of administrative areas, in the selected image or
+    /// overview, see [`GeoTiffReader::statistics`].
+    ///
+    /// The raster is scanned strip by strip or tile by tile, decoding only those intersecting a
+    /// polygon, each once for all polygons. The statistics of a polygon covering no valid
+    /// sample, e.g. outside the raster, are `None`. The count is the number of samples
+    /// contributing to the statistics, regardless of their weight. Fails if the GeoTIFF is not
+    /// georeferenced or the band does not exist.
+    pub fn zonal_statistics<P: Into<MultiPolygon>>(
+        &mut self,
+        polygons: impl IntoIterator<Item = P>,
+        band: usize,
+        coverage: ZonalCoverage,
+    ) -> GeoTiffResult<Vec<Option<Statistics>>> {
+        self.check_band(band)?;
+        let coordinate_transform = self
+            .metadata()
+            .coordinate_transform()
+            .cloned()
+            .ok_or_else(|| GeoTiffError::GeoKey("The GeoTIFF is not georeferenced".to_string()))?;
+        // The polygons in raster space along with the windows covering them, if any
+        let zones = polygons
+            .into_iter()
+            .map(|polygon| {
+                let polygon = polygon.into();
+                let coords = polygon
+                    .iter()
+                    .flat_map(|polygon| polygon.exterior().coords().copied());
+                let window = match bounding_rect(coords) {
+                    Some(rect) => {
+                        let (min, max) = (rect.min(), rect.max());
+                        match self.bbox_window(min.x, min.y, max.x, max.y, WindowSnap::Outward) {
+                            Ok(window) => Some(window),
+                            Err(GeoTiffError::OutOfBounds(_)) => None,
+                            Err(err) => return Err(err),
+                        }
+                    }
+                    None => None,
+                };
+                Ok((RasterPolygon::new(&coordinate_transform, &polygon), window))
+            })
+            .collect::<GeoTiffResult<Vec<_>>>()?;
+
+        let mut statistics = vec![StatisticsAccumulator::default(); zones.len()];
+        let (width, height) = (self.raster_width(), self.raster_height());
+        let [block_width, block_height] = self.block_size();
+        for block_y in (0..height).step_by(block_height) {
+            for block_x in (0..width).step_by(block_width) {
+                let block = [
+                    block_x,
+                    block_y,
+                    block_width.min(width - block_x),
+                    block_height.min(height - block_y),
+                ];
+                let intersections = zones
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(index, (_, window))| Some((index, intersect(block, (*window)?)?)))
+                    .collect::<Vec<_>>();
+                if intersections.is_empty() {
+                    continue;
+                }
+                let data = self.read_window_bands(block, &[band])?;
+                for (index, window) in intersections {
+                    let polygon = &zones[index].0;
+                    let weights = match coverage {
+                        ZonalCoverage::Center => polygon
+                            .center_mask(window)
+                            .into_iter()
+                            .map(|inside| if inside { 1.0 } else { 0.0 })
+                            .collect(),
+                        ZonalCoverage::AllTouched => polygon
+                            .coverage(window)
+                            .into_iter()
+                            .map(|fraction| if fraction > MIN_COVERAGE { 1.0 } else { 0.0 })
+                            .collect(),
+                        ZonalCoverage::Fraction => polygon.coverage(window),
+                    };
+                    let [x, y, window_width, _] = window;
+                    for (pixel, weight) in weights.into_iter().enumerate() {
+                        if weight <= MIN_COVERAGE {
+                            continue;
+                        }
+                        let column = x - block_x + pixel % window_width;
+                        let row = y - block_y + pixel / window_width;
+                        match data.get_value_at_checked::<f64>(column, row, 0) {
+                            Some(value) if !value.is_nan() => {
+                                statistics[index].add_weighted(value, weight)
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            }
+        }
+        Ok(statistics
+            .iter()
+            .map(StatisticsAccumulator::finish)
+            .collect())
+    }
+}
+
+/// The intersection of the windows `[x, y, width, height]`, or `None` if they are disjoint.
+fn intersect(a: [usize; 4], b: [usize; 4]) -> Option<[usize; 4]> {
+    let (left, top) = (a[0].max(b[0]), a[1].max(b[1]));
+    let (right, bottom) = (
+        (a[0] + a[2]).min(b[0] + b[2]),
+        (a[1] + a[3]).min(b[1] + b[3]),
+    );
+    (left < right && top < bottom).then(|| [left, top, right - left, bottom - top])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use geo_types::{LineString, Polygon, Rect};
+
+    use super::*;
+    use crate::{CoordinateTransform, GeoTiffWriter};
+
+    /// The polygon covering the columns `x0..x1` and the rows `y0..y1` of the raster.
+    fn pixel_rect(x0: f64, y0: f64, x1: f64, y1: f64) -> Polygon {
+        Rect::new(
+            (1000.0 + 10.0 * x0, 2000.0 - 10.0 * y0),
+            (1000.0 + 10.0 * x1, 2000.0 - 10.0 * y1),
+        )
+        .to_polygon()
+    }
+
+    #[test]
+    fn test_zonal_statistics() {
+        // The value of pixel (x, y) is x + 40 * y, except for the nodata value 697 at (17, 17)
+        let transform =
+            CoordinateTransform::from_geotransform([1000.0, 10.0, 0.0, 2000.0, 0.0, -10.0])
+                .unwrap();
+        let data = (0..40 * 40).map(|value| value as f32).collect::<Vec<_>>();
+        let mut bytes = Vec::new();
+        GeoTiffWriter::new()
+            .with_tiles(16, 16)
+            .with_nodata(697.0)
+            .with_coordinate_transform(transform)
+            .write(&mut bytes, 40, 40, 1, &data)
+            .unwrap();
+        let mut reader = GeoTiffReader::new(Cursor::new(bytes)).unwrap();
+
+        let polygons = vec![
+            // The pixels 14 to 17 in both directions, across four tiles
+            pixel_rect(14.0, 14.0, 18.0, 18.0),
+            // Half of pixel (14, 14), pixel (15, 14) and half of pixel (16, 14)
+            pixel_rect(14.5, 14.0, 16.5, 15.0),
+            pixel_rect(50.0, 50.0, 60.0, 60.0),
+        ];
+        let statistics = reader
+            .zonal_statistics(polygons.clone(), 0, ZonalCoverage::Center)
+            .unwrap();
+        let values = (14..18)
+            .flat_map(|y| (14..18).map(move |x| (x + 40 * y) as f64))
+            .filter(|&value| value != 697.0)
+            .collect::<Vec<_>>();
+        let mean = values.iter().sum::<f64>() / 15.0;
+        let variance = values
+            .iter()
+            .map(|value| (value - mean).powi(2))
+            .sum::<f64>()
+            / 15.0;
+        let zone = statistics[0].unwrap();
+        assert_eq!((zone.count, zone.min, zone.max), (15, 574.0, 696.0));
+        assert!((zone.mean - mean).abs() < 1e-9);
+        assert!((zone.std_dev - variance.sqrt()).abs() < 1e-9);
+        // The center of pixel (16, 14) lies on the edge
+        let zone = statistics[1].unwrap();
+        assert_eq!(
+            (zone.count, zone.min, zone.max, zone.mean),
+            (2, 574.0, 575.0, 574.5)
+        );
+        assert_eq!(statistics[2], None);
+
+        let statistics = reader
+            .zonal_statistics(polygons.clone(), 0, ZonalCoverage::AllTouched)
+            .unwrap();
+        assert_eq!(statistics[0].unwrap().count, 15);
+        let zone = statistics[1].unwrap();
+        assert_eq!((zone.count, zone.max, zone.mean), (3, 576.0, 575.0));
+
+        let statistics = reader
+            .zonal_statistics(polygons, 0, ZonalCoverage::Fraction)
+            .unwrap();
+        assert!((statistics[0].unwrap().mean - mean).abs() < 1e-9);
+        let zone = statistics[1].unwrap();
+        assert_eq!((zone.count, zone.mean), (3, 575.0));
+        assert!((zone.std_dev - 0.5f64.sqrt()).abs() < 1e-9);
+
+        // A triangle covering half of pixel (0, 0), and pixel (1, 0) except for a hole of a
+        // quarter
+        let triangle = Polygon::new(
+            LineString::from(vec![(1000.0, 2000.0), (1010.0, 2000.0), (1010.0, 1990.0)]),
+            Vec::new(),
+        );
+        let mut with_hole = pixel_rect(1.0, 0.0, 2.0, 1.0);
+        with_hole.interiors_push(pixel_rect(1.0, 0.0, 1.5, 0.5).exterior().clone());
+        let statistics = reader
+            .zonal_statistics([triangle, with_hole], 0, ZonalCoverage::Fraction)
+            .unwrap();
+        assert_eq!(statistics[0].unwrap().mean, 0.0);
+        assert_eq!(statistics[1].unwrap().mean, 1.0);
+        let zone = reader
+            .zonal_statistics(
+                [MultiPolygon::new(vec![
+                    pixel_rect(0.0, 0.0, 1.0, 0.5),
+                    pixel_rect(1.0, 0.0, 2.0, 1.0),
+                ])],
+                0,
+                ZonalCoverage::Fraction,
+            )
+            .unwrap()[0]
+            .unwrap();
+        // The values 0 and 1 weighted by 0.5 and 1
+        assert!((zone.mean - 2.0 / 3.0).abs() < 1e-9);
+
+        assert!(reader
+            .zonal_statistics([pixel_rect(0.0, 0.0, 1.0, 1.0)], 1, ZonalCoverage::Center)
+            .is_err());
+    }
+}
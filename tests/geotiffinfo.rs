@@ -0,0 +1,35 @@
+#![cfg(feature = "cli")]
+use std::process::Command;
+
+#[test]
+fn test_geotiffinfo_utm_32n() {
+    let output = Command::new(env!("CARGO_BIN_EXE_geotiffinfo"))
+        .args(["--tags", "resources/utm_32n.tif"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    for line in [
+        "Size: 32 x 16, 1 band",
+        "Data type: Byte",
+        "Compression: None",
+        "CRS: EPSG:32632 (WGS 84 / UTM zone 32N)",
+        "GeoTIFF version: 1.0",
+        "Extent: (500000, 5299520) - (500960, 5300000)",
+        "NoData: 255",
+        "  ImageWidth (256): 32",
+        "  ProjectedCSType (3072): Short([32632])",
+    ] {
+        assert!(
+            stdout.lines().any(|output_line| output_line == line),
+            "{}",
+            stdout
+        );
+    }
+
+    let output = Command::new(env!("CARGO_BIN_EXE_geotiffinfo"))
+        .arg("resources/missing.tif")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+}
@@ -1,7 +1,11 @@
 use std::fs::File;
+use std::io::Cursor;
 use std::path::Path;
 
-use geotiff::GeoTiff;
+use geotiff::{
+    CoordinateTransform, Extent, GeoTiff, GeoTiffReader, GeoTiffWriter, Layout, ModelType,
+    ModelUnits, RasterType, TiePoint,
+};
 
 fn read_geotiff<P: AsRef<Path>>(path: P) -> GeoTiff {
     GeoTiff::read(File::open(path).expect("File I/O error")).expect("File I/O error")
@@ -9,12 +13,19 @@ fn read_geotiff<P: AsRef<Path>>(path: P) -> GeoTiff {
 
 #[test]
 fn test_load_marbles() {
-    let geotiff = read_geotiff("resources/marbles.tif");
+    let mut geotiff = read_geotiff("resources/marbles.tif");
 
     println!("{geotiff:?}");
     assert_eq!(geotiff.raster_width, 1419);
     assert_eq!(geotiff.raster_height, 1001);
     assert_eq!(geotiff.num_samples, 3);
+    assert_eq!(geotiff.transform_to_model([0, 0]), None);
+    assert_eq!(geotiff.model_extent(), None);
+
+    geotiff.set_coordinate_transform(Some(
+        CoordinateTransform::from_geotransform([8.0, 0.001, 0.0, 47.0, 0.0, -0.001]).unwrap(),
+    ));
+    assert_eq!(geotiff.transform_to_model([1000, 1000]), Some([9.0, 46.0]));
     assert_eq!(geotiff.get_value_at::<u8>(761, 599, 0), 147);
     assert_eq!(geotiff.get_value_at::<u8>(761, 599, 1), 128);
     assert_eq!(geotiff.get_value_at::<u8>(761, 599, 2), 165);
@@ -22,7 +33,7 @@ fn test_load_marbles() {
 
 #[test]
 fn test_load_zh_dem_25() {
-    let geotiff = read_geotiff("resources/zh_dem_25.tif");
+    let mut geotiff = read_geotiff("resources/zh_dem_25.tif");
 
     println!("{geotiff:?}");
     assert_eq!(geotiff.raster_width, 399);
@@ -31,4 +42,408 @@ fn test_load_zh_dem_25() {
     assert_eq!(geotiff.get_value_at::<i16>(0, 0, 0), 551);
     assert_eq!(geotiff.get_value_at::<i16>(67, 45, 0), 530);
     assert_eq!(geotiff.get_value_at::<i16>(325, 142, 0), 587);
+    assert_eq!(
+        geotiff.transform_to_model([0, 0]),
+        Some([677562.5, 253012.5])
+    );
+    assert_eq!(
+        geotiff.transform_to_model([399, 366]),
+        Some([687537.5, 243862.5])
+    );
+    assert_eq!(
+        geotiff.transform_to_raster([679250.0, 250000.0]),
+        Some([67, 120])
+    );
+    assert_eq!(
+        geotiff.transform_to_raster_f64([679250.0, 250000.0]),
+        Some([67.5, 120.5])
+    );
+
+    assert_eq!(
+        geotiff.model_extent(),
+        Some(Extent::new(677562.5, 243862.5, 687537.5, 253012.5))
+    );
+    assert_eq!(geotiff.resolution(), Some([25.0, 25.0]));
+    assert_eq!(geotiff.rpc_model(), None);
+    assert_eq!(geotiff.epsg_code(), None);
+    assert_eq!(geotiff.get_value_in_meters_at(67, 45, 0), 530.0);
+    assert_eq!(geotiff.crs_wkt(), None);
+    assert_eq!(geotiff.crs_proj4(), None);
+    assert_eq!(geotiff.raster_type(), RasterType::PixelIsArea);
+    geotiff.set_raster_type(RasterType::PixelIsPoint);
+    assert_eq!(
+        geotiff.transform_to_model([0, 0]),
+        Some([677550.0, 253025.0])
+    );
+    geotiff.set_raster_type(RasterType::PixelIsArea);
+    assert_eq!(
+        geotiff.transform_to_model([0, 0]),
+        Some([677562.5, 253012.5])
+    );
+}
+
+#[test]
+fn test_load_utm_32n() {
+    let geotiff = read_geotiff("resources/utm_32n.tif");
+
+    assert_eq!(geotiff.raster_width, 32);
+    assert_eq!(geotiff.raster_height, 16);
+    assert_eq!(geotiff.get_value_at::<u8>(5, 3, 0), 11);
+
+    let geo_key_directory = geotiff.geo_key_directory();
+    assert_eq!(geo_key_directory.model_type(), Some(ModelType::Projected));
+    assert_eq!(geo_key_directory.projected_type(), Some(32632));
+    assert_eq!(geo_key_directory.citation(), Some("WGS 84 / UTM zone 32N"));
+    assert_eq!(geo_key_directory.geog_citation(), Some("WGS 84"));
+    assert_eq!(geo_key_directory.geog_angular_units(), Some(9102));
+    assert_eq!(geo_key_directory.proj_linear_units(), Some(9001));
+    assert_eq!(geotiff.citation(), Some("WGS 84 / UTM zone 32N"));
+    assert_eq!(geotiff.geog_citation(), Some("WGS 84"));
+    assert_eq!(geotiff.proj_citation(), None);
+    assert_eq!(geotiff.crs_description(), Some("WGS 84 / UTM zone 32N"));
+    assert_eq!(geotiff.epsg_code(), Some(32632));
+    assert!(!geotiff.is_user_defined_crs());
+    assert!(geotiff
+        .crs_wkt()
+        .unwrap()
+        .starts_with("PROJCRS[\"WGS 84 / UTM zone 32N\",BASEGEOGCRS[\"WGS 84\","));
+    assert!(geotiff
+        .crs_projjson()
+        .unwrap()
+        .contains("\"id\":{\"authority\":\"EPSG\",\"code\":32632}}"));
+    assert_eq!(
+        geotiff.crs_proj4().as_deref(),
+        Some(
+            "+proj=tmerc +lat_0=0 +lon_0=9 +k_0=0.9996 +x_0=500000 +y_0=0 \
+             +datum=WGS84 +units=m +no_defs +type=crs"
+        )
+    );
+    assert_eq!(geotiff.raster_type(), RasterType::PixelIsArea);
+    assert_eq!(
+        geotiff.transform_to_model([0, 0]),
+        Some([500000.0, 5300000.0])
+    );
+    assert_eq!(geotiff.resolution(), Some([30.0, 30.0]));
+}
+
+#[test]
+fn test_from_bytes_utm_32n() {
+    let bytes = std::fs::read("resources/utm_32n.tif").unwrap();
+    let geotiff = GeoTiff::from_bytes(&bytes).unwrap();
+    assert_eq!(geotiff.epsg_code(), Some(32632));
+    assert_eq!(
+        geotiff.get_values::<u8>(Layout::PixelInterleaved),
+        read_geotiff("resources/utm_32n.tif").get_values::<u8>(Layout::PixelInterleaved)
+    );
+
+    let mut reader = GeoTiffReader::from_bytes(&bytes).unwrap();
+    let window = reader.read_window(5, 3, 2, 2).unwrap();
+    assert_eq!(window.get_value_at::<u8>(0, 0, 0), 11);
+    assert!(GeoTiff::from_bytes(&bytes[..100]).is_err());
+}
+
+#[cfg(feature = "lerc")]
+#[test]
+fn test_load_lerc_deflate() {
+    // 16x16 tiles of LERC blobs compressed by Deflate, the value of pixel (x, y) being
+    // 400 + x / 2 + y / 4 except for the invalid pixel (5, 7)
+    let geotiff = read_geotiff("resources/lerc_deflate.tif");
+    assert_eq!((geotiff.raster_width, geotiff.raster_height), (20, 20));
+    assert_eq!(geotiff.epsg_code(), Some(2056));
+    assert_eq!(geotiff.get_value_at::<f32>(0, 0, 0), 400.0);
+    assert_eq!(geotiff.get_value_at::<f32>(19, 19, 0), 414.25);
+    assert!(geotiff.get_value_at::<f32>(5, 7, 0).is_nan());
+    let values = geotiff.get_values::<f32>(Layout::PixelInterleaved);
+    assert_eq!(values[7 * 20 + 6], 404.75);
+
+    let mut reader = GeoTiffReader::new(File::open("resources/lerc_deflate.tif").unwrap()).unwrap();
+    let window = reader.read_window(14, 14, 4, 4).unwrap();
+    assert_eq!(window.get_value_at::<f32>(3, 3, 0), 412.75);
+    assert_eq!(
+        geotiff.transform_to_model([0, 0]),
+        Some([2600000.0, 1200500.0])
+    );
+}
+
+#[cfg(feature = "proj")]
+#[test]
+fn test_transform_utm_32n_to_wgs_84() {
+    let geotiff = read_geotiff("resources/utm_32n.tif");
+    let transform = geotiff.transform_to_crs(4326).unwrap();
+
+    let [lon, lat] = transform.transform_to_target([0, 0]).unwrap();
+    assert!((lon - 9.0).abs() < 1e-9);
+    assert!((lat - 47.853342).abs() < 1e-6);
+    assert_eq!(transform.transform_to_raster([lon, lat]).unwrap(), [0, 0]);
+
+    let [lon, lat] = geotiff.pixel_to_lonlat(0.0, 0.0).unwrap();
+    assert!((lon - 9.0).abs() < 1e-9);
+    assert!((lat - 47.853342).abs() < 1e-6);
+    let [lon, lat] = geotiff.pixel_to_lonlat(16.5, 8.5).unwrap();
+    let [column, row] = geotiff.lonlat_to_pixel(lon, lat).unwrap();
+    assert!((column - 16.5).abs() < 1e-6 && (row - 8.5).abs() < 1e-6);
+
+    let mut reader = GeoTiffReader::new(File::open("resources/utm_32n.tif").unwrap()).unwrap();
+    let query = reader.query_point(lon, lat).unwrap();
+    assert_eq!((query.column, query.row, query.overview), (16, 8, None));
+    assert_eq!(
+        query.values,
+        vec![geotiff.get_value_at_checked::<f64>(16, 8, 0)]
+    );
+}
+
+#[test]
+fn test_load_state_plane_ftus() {
+    let mut geotiff = read_geotiff("resources/state_plane_ftus.tif");
+
+    assert_eq!(geotiff.epsg_code(), Some(2230));
+    assert_eq!(
+        geotiff.crs_description(),
+        Some("NAD83 / California zone 6 (ftUS)")
+    );
+    assert_eq!(geotiff.model_units(), ModelUnits::Native);
+    assert_eq!(
+        geotiff.model_extent(),
+        Some(Extent::new(6000000.0, 1999000.0, 6001000.0, 2000000.0))
+    );
+
+    geotiff.set_model_units(ModelUnits::Standard).unwrap();
+    let extent = geotiff.model_extent().unwrap();
+    assert!((extent.min_x - 1828803.6576).abs() < 1e-3);
+    assert!((extent.width() - 304.8006).abs() < 1e-3);
+    let [x, y] = geotiff.resolution().unwrap();
+    assert!((x - 30.48006).abs() < 1e-5 && (y - 30.48006).abs() < 1e-5);
+    assert_eq!(
+        geotiff.transform_to_raster([extent.min_x + 1.0, extent.max_y - 1.0]),
+        Some([0, 0])
+    );
+
+    geotiff.set_model_units(ModelUnits::Native).unwrap();
+    let [x, y] = geotiff.transform_to_model([0, 0]).unwrap();
+    assert!((x - 6000000.0).abs() < 1e-6 && (y - 2000000.0).abs() < 1e-6);
+}
+
+#[test]
+fn test_write_utm_32n() {
+    let geotiff = read_geotiff("resources/utm_32n.tif");
+    let data = (0..geotiff.raster_height)
+        .flat_map(|y| (0..geotiff.raster_width).map(move |x| (x, y)))
+        .map(|(x, y)| geotiff.get_value_at::<u8>(x, y, 0))
+        .collect::<Vec<_>>();
+
+    let mut bytes = Vec::new();
+    GeoTiffWriter::new()
+        .with_geo_key_directory(geotiff.geo_key_directory().clone())
+        .with_coordinate_transform(geotiff.coordinate_transform().unwrap().clone())
+        .write(
+            &mut bytes,
+            geotiff.raster_width,
+            geotiff.raster_height,
+            1,
+            &data,
+        )
+        .unwrap();
+    let written = GeoTiff::read(Cursor::new(bytes)).unwrap();
+
+    assert_eq!(written.raster_width, 32);
+    assert_eq!(written.raster_height, 16);
+    assert_eq!(written.get_value_at::<u8>(5, 3, 0), 11);
+    assert_eq!(written.geo_key_directory(), geotiff.geo_key_directory());
+    assert_eq!(written.model_extent(), geotiff.model_extent());
+    assert_eq!(written.crs_proj4(), geotiff.crs_proj4());
+}
+
+#[test]
+fn test_transform_to_raster_without_pixel_scale() {
+    // A raster rotated by 30 degrees, georeferenced by a ModelTransformationTag
+    let (sin, cos) = 30f64.to_radians().sin_cos();
+    let affine = CoordinateTransform::from_affine([
+        10.0 * cos,
+        -10.0 * sin,
+        1000.0,
+        -10.0 * sin,
+        -10.0 * cos,
+        2000.0,
+    ])
+    .unwrap();
+    // A raster georeferenced by tie points only
+    let tie_points = CoordinateTransform::from_tie_points(vec![
+        TiePoint {
+            raster_point: [0.0, 0.0],
+            model_point: [1000.0, 2000.0],
+        },
+        TiePoint {
+            raster_point: [8.0, 0.0],
+            model_point: [1080.0, 2000.0],
+        },
+        TiePoint {
+            raster_point: [0.0, 8.0],
+            model_point: [1000.0, 1920.0],
+        },
+        TiePoint {
+            raster_point: [8.0, 8.0],
+            model_point: [1080.0, 1920.0],
+        },
+    ])
+    .unwrap();
+
+    for transform in [affine, tie_points] {
+        let mut bytes = Vec::new();
+        GeoTiffWriter::new()
+            .with_coordinate_transform(transform)
+            .write(&mut bytes, 8, 8, 1, &[0u8; 64])
+            .unwrap();
+        let geotiff = GeoTiff::read(Cursor::new(bytes)).unwrap();
+
+        let model = geotiff.transform_to_model([3, 5]).unwrap();
+        let center = geotiff
+            .coordinate_transform()
+            .unwrap()
+            .transform_to_model_f64([3.5, 5.5]);
+        assert_eq!(geotiff.transform_to_raster(center), Some([3, 5]));
+        let [x, y] = geotiff.transform_to_raster_f64(model).unwrap();
+        assert!((x - 3.0).abs() < 1e-9 && (y - 5.0).abs() < 1e-9);
+    }
+}
+
+#[test]
+fn test_edit_utm_32n() {
+    let geotiff = read_geotiff("resources/utm_32n.tif");
+    let mut file = Cursor::new(std::fs::read("resources/utm_32n.tif").unwrap());
+
+    // Shift the raster by 1 km to the east, like gdal_edit.py -a_ullr
+    let transform =
+        CoordinateTransform::from_geotransform([501000.0, 30.0, 0.0, 5300000.0, 0.0, -30.0])
+            .unwrap();
+    GeoTiffWriter::new()
+        .with_geo_key_directory(geotiff.geo_key_directory().clone())
+        .with_coordinate_transform(transform)
+        .edit_georeferencing(&mut file)
+        .unwrap();
+    file.set_position(0);
+    let edited = GeoTiff::read(file).unwrap();
+
+    assert_eq!(edited.get_value_at::<u8>(5, 3, 0), 11);
+    assert_eq!(edited.geo_key_directory(), geotiff.geo_key_directory());
+    assert_eq!(
+        edited.transform_to_model([0, 0]),
+        Some([501000.0, 5300000.0])
+    );
+}
+
+#[test]
+fn test_read_window_zh_dem_25() {
+    let geotiff = read_geotiff("resources/zh_dem_25.tif");
+    let file = File::open("resources/zh_dem_25.tif").unwrap();
+    // Spanning three strips of 10 rows
+    let window = GeoTiff::read_window(file, 60, 38, 20, 15).unwrap();
+
+    assert_eq!(window.raster_width, 20);
+    assert_eq!(window.raster_height, 15);
+    for y in 0..15 {
+        for x in 0..20 {
+            assert_eq!(
+                window.get_value_at::<i16>(x, y, 0),
+                geotiff.get_value_at::<i16>(x + 60, y + 38, 0)
+            );
+        }
+    }
+    assert_eq!(
+        window.transform_to_model([0, 0]),
+        geotiff.transform_to_model([60, 38])
+    );
+    assert_eq!(
+        window.model_extent(),
+        Some(Extent::new(679062.5, 251687.5, 679562.5, 252062.5))
+    );
+
+    let file = File::open("resources/zh_dem_25.tif").unwrap();
+    assert!(GeoTiff::read_window(file, 390, 0, 10, 10).is_err());
+}
+
+#[test]
+fn test_read_window_tiles() {
+    let data = (0..40 * 20 * 3)
+        .map(|value| value as u16)
+        .collect::<Vec<_>>();
+    let mut bytes = Vec::new();
+    GeoTiffWriter::new()
+        .with_tiles(16, 16)
+        .write(&mut bytes, 40, 20, 3, &data)
+        .unwrap();
+
+    // Spanning all tiles, including the padded ones at the edges
+    let window = GeoTiff::read_window(Cursor::new(bytes), 10, 5, 30, 15).unwrap();
+    assert_eq!(window.num_samples, 3);
+    for y in 0..15 {
+        for x in 0..30 {
+            for sample in 0..3 {
+                assert_eq!(
+                    window.get_value_at::<u16>(x, y, sample) as usize,
+                    ((y + 5) * 40 + x + 10) * 3 + sample
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_read_bbox_zh_dem_25() {
+    let geotiff = read_geotiff("resources/zh_dem_25.tif");
+    let file = File::open("resources/zh_dem_25.tif").unwrap();
+    // Partially covering the pixels at the left and bottom edges
+    let window = GeoTiff::read_bbox(file, 679070.0, 251687.5, 679562.5, 252062.5).unwrap();
+
+    assert_eq!(window.raster_width, 20);
+    assert_eq!(window.raster_height, 15);
+    assert_eq!(
+        window.geotransform(),
+        Some([679062.5, 25.0, 0.0, 252062.5, 0.0, -25.0])
+    );
+    assert_eq!(
+        window.get_value_at::<i16>(7, 7, 0),
+        geotiff.get_value_at::<i16>(67, 45, 0)
+    );
+
+    // Clipped to the raster
+    let file = File::open("resources/zh_dem_25.tif").unwrap();
+    let window = GeoTiff::read_bbox(file, 600000.0, 200000.0, 677600.0, 253100.0).unwrap();
+    assert_eq!((window.raster_width, window.raster_height), (2, 366));
+
+    let file = File::open("resources/zh_dem_25.tif").unwrap();
+    assert!(GeoTiff::read_bbox(file, 0.0, 0.0, 1.0, 1.0).is_err());
+}
+
+#[test]
+fn test_load_ycbcr_jpeg() {
+    // Two JPEG-compressed tiles of uniform color, sharing their tables in the JPEGTables tag and
+    // subsampled YCbCr like `gdal_translate -co COMPRESS=JPEG -co PHOTOMETRIC=YCBCR` writes
+    let geotiff = read_geotiff("resources/ycbcr_jpeg.tif");
+    assert_eq!(geotiff.raster_width, 24);
+    assert_eq!(geotiff.raster_height, 16);
+    assert_eq!(geotiff.num_samples, 3);
+    assert_eq!(geotiff.epsg_code(), Some(4326));
+    // The samples are converted to RGB
+    let rgb = |x, y| {
+        (0..3)
+            .map(|sample| geotiff.get_value_at::<u8>(x, y, sample))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(rgb(0, 0), [201, 49, 100]);
+    assert_eq!(rgb(15, 15), [201, 49, 100]);
+    assert_eq!(rgb(23, 15), [150, 163, 83]);
+
+    let mut reader = GeoTiffReader::new(File::open("resources/ycbcr_jpeg.tif").unwrap()).unwrap();
+    assert_eq!(reader.tile_count(), Some([2, 1]));
+    let window = reader.read_window(14, 2, 4, 2).unwrap();
+    assert_eq!(window.get_value_at::<u8>(1, 0, 0), 201);
+    assert_eq!(window.get_value_at::<u8>(2, 1, 0), 150);
+    let green = reader.read_band(1).unwrap();
+    assert_eq!(green.get_value_at::<u8>(20, 8, 0), 163);
+    assert_eq!(
+        reader
+            .read_bands::<u8>([15, 0, 2, 1], &[2, 0], Layout::PixelInterleaved)
+            .unwrap(),
+        [100, 201, 83, 150]
+    );
 }